@@ -1,4 +1,5 @@
 use bech32::FromBase32;
+use gossip_lib::thread_export::ThreadFormat;
 use gossip_lib::PersonRelay;
 use gossip_lib::GLOBALS;
 use gossip_lib::{Error, ErrorKind};
@@ -29,7 +30,7 @@ impl Command {
     }
 }
 
-const COMMANDS: [Command; 28] = [
+const COMMANDS: [Command; 37] = [
     Command {
         cmd: "oneshot",
         usage_params: "{depends}",
@@ -45,6 +46,11 @@ const COMMANDS: [Command; 28] = [
         usage_params: "<listname>",
         desc: "add a new person list with the given name",
     },
+    Command {
+        cmd: "audit_relay",
+        usage_params: "<relayurl> [<days>]",
+        desc: "count the events of each kind received from the relay, by day (default 30 days)",
+    },
     Command {
         cmd: "bech32_decode",
         usage_params: "<bech32string>",
@@ -55,6 +61,11 @@ const COMMANDS: [Command; 28] = [
         usage_params: "<kind> <pubkeyhex> <d> [<relayurl>, ...]",
         desc: "encode an event address (parameterized replaceable event link).",
     },
+    Command {
+        cmd: "compact_db",
+        usage_params: "",
+        desc: "write a compacted and checked copy of the database, to be used from the next start on",
+    },
     Command {
         cmd: "decrypt",
         usage_params: "<pubkeyhex> <ciphertext>",
@@ -70,6 +81,11 @@ const COMMANDS: [Command; 28] = [
         usage_params: "<relayurl>",
         desc: "delete a relay record from storage.",
     },
+    Command {
+        cmd: "dump_event",
+        usage_params: "<idhex|note1|nevent1>",
+        desc: "print the event (pretty JSON) with the relays it was seen on and its relationships",
+    },
     Command {
         cmd: "events_of_kind",
         usage_params: "<kind>",
@@ -80,26 +96,66 @@ const COMMANDS: [Command; 28] = [
         usage_params: "<pubkeyhex> <kind>",
         desc: "print IDs of all events from <pubkeyhex> of kind=<kind>",
     },
+    Command {
+        cmd: "export_backup",
+        usage_params: "<directory>",
+        desc: "write a portable backup (events, people, settings, relays, lists and encrypted key) into a new directory",
+    },
+    Command {
+        cmd: "export_events",
+        usage_params: "[<filepath>]",
+        desc: "export every stored event as JSON, one per line (to stdout if no file is given)",
+    },
+    Command {
+        cmd: "export_thread",
+        usage_params: "<idhex> <md|html> <filepath>",
+        desc: "export the thread containing an event as a Markdown or HTML document",
+    },
     Command {
         cmd: "giftwrap_ids",
         usage_params: "",
         desc: "List the IDs of all giftwrap events you are tagged on",
     },
+    Command {
+        cmd: "export_settings",
+        usage_params: "<filepath>",
+        desc: "export settings, relays and person lists to a JSON file",
+    },
     Command {
         cmd: "help",
         usage_params: "<command>",
         desc: "show this list",
     },
+    Command {
+        cmd: "import_backup",
+        usage_params: "<directory>",
+        desc: "merge a portable backup written by export_backup into this install",
+    },
     Command {
         cmd: "import_event",
-        usage_params: "<event_json>",
-        desc: "import and process a JSON event",
+        usage_params: "<event_json|filepath>",
+        desc: "import and process a JSON event, or a file of them (one per line, or a JSON array)",
+    },
+    Command {
+        cmd: "import_settings",
+        usage_params: "<filepath>",
+        desc: "import settings, relays and person lists from a JSON file",
+    },
+    Command {
+        cmd: "list_profiles",
+        usage_params: "",
+        desc: "list the profiles, which GOSSIP_PROFILE selects between",
     },
     Command {
         cmd: "login",
         usage_params: "",
         desc: "login on the command line before starting the gossip GUI",
     },
+    Command {
+        cmd: "post_stats",
+        usage_params: "[<days>]",
+        desc: "how your posts were received, by day and best first (default 30 days)",
+    },
     Command {
         cmd: "print_event",
         usage_params: "<idhex>",
@@ -140,6 +196,11 @@ const COMMANDS: [Command; 28] = [
         usage_params: "",
         desc: "print all the relay records",
     },
+    Command {
+        cmd: "prune",
+        usage_params: "--before <YYYY-MM-DD|unixtime>",
+        desc: "delete all events created before the given date (and their related data)",
+    },
     Command {
         cmd: "rebuild_indices",
         usage_params: "",
@@ -165,6 +226,11 @@ const COMMANDS: [Command; 28] = [
         usage_params: "<idhex>",
         desc: "Verify if the given event signature is valid",
     },
+    Command {
+        cmd: "verify_db",
+        usage_params: "",
+        desc: "check that every stored event decodes and has a valid id and signature",
+    },
     Command {
         cmd: "verify_json",
         usage_params: "<event_json>",
@@ -192,20 +258,31 @@ pub fn handle_command(mut args: env::Args, runtime: &Runtime) -> Result<bool, Er
         "oneshot" => oneshot(command, args)?,
         "add_person_relay" => add_person_relay(command, args)?,
         "add_person_list" => add_person_list(command, args)?,
+        "audit_relay" => audit_relay(command, args)?,
         "bech32_decode" => bech32_decode(command, args)?,
         "bech32_encode_event_addr" => bech32_encode_event_addr(command, args)?,
+        "compact_db" => compact_db(command)?,
         "decrypt" => decrypt(command, args)?,
         "delete_spam_by_content" => delete_spam_by_content(command, args, runtime)?,
         "delete_relay" => delete_relay(command, args)?,
+        "dump_event" => dump_event(command, args)?,
         "events_of_kind" => events_of_kind(command, args)?,
         "events_of_pubkey_and_kind" => events_of_pubkey_and_kind(command, args)?,
+        "export_backup" => export_backup(command, args)?,
+        "export_events" => export_events(command, args)?,
+        "export_settings" => export_settings(command, args)?,
+        "export_thread" => export_thread(command, args)?,
         "giftwrap_ids" => giftwrap_ids(command)?,
         "help" => help(command, args)?,
+        "import_backup" => import_backup(command, args, runtime)?,
         "import_event" => import_event(command, args, runtime)?,
+        "import_settings" => import_settings(command, args)?,
+        "list_profiles" => list_profiles(command)?,
         "login" => {
             login()?;
             return Ok(false);
         }
+        "post_stats" => post_stats(command, args)?,
         "print_event" => print_event(command, args)?,
         "print_followed" => print_followed(command)?,
         "print_muted" => print_muted(command)?,
@@ -214,11 +291,13 @@ pub fn handle_command(mut args: env::Args, runtime: &Runtime) -> Result<bool, Er
         "print_person_relays" => print_person_relays(command, args)?,
         "print_relay" => print_relay(command, args)?,
         "print_relays" => print_relays(command)?,
+        "prune" => prune(command, args)?,
         "rebuild_indices" => rebuild_indices()?,
         "rename_person_list" => rename_person_list(command, args)?,
         "reprocess_recent" => reprocess_recent(command, runtime)?,
         "ungiftwrap" => ungiftwrap(command, args)?,
         "verify" => verify(command, args)?,
+        "verify_db" => verify_db(command)?,
         "verify_json" => verify_json(command, args)?,
         other => println!("Unknown command {}", other),
     }
@@ -292,6 +371,37 @@ pub fn add_person_list(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn audit_relay(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let url = match args.next() {
+        Some(url) => RelayUrl::try_from_str(&url)?,
+        None => return cmd.usage("Missing relayurl parameter".to_string()),
+    };
+    let days = match args.next() {
+        Some(days) => days.parse::<u64>()?,
+        None => 30,
+    };
+
+    let audit = gossip_lib::relay_audit::audit(&url, days)?;
+    println!(
+        "{} events: {} by people followed, {} by others, {} direct messages, {} no longer stored",
+        audit.total(),
+        audit.from_followed,
+        audit.from_others,
+        audit.dms,
+        audit.missing
+    );
+    for (kind, count) in audit.totals.iter() {
+        println!("  {:?}: {}", kind, count);
+    }
+    for (day, kinds) in audit.days.iter() {
+        println!("{}", day.0);
+        for (kind, count) in kinds.iter() {
+            println!("  {:?}: {}", kind, count);
+        }
+    }
+    Ok(())
+}
+
 pub fn bech32_decode(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let mut param = match args.next() {
         Some(s) => s,
@@ -572,10 +682,22 @@ pub fn delete_relay(cmd: Command, mut args: env::Args) -> Result<(), Error> {
 }
 
 pub fn import_event(cmd: Command, mut args: env::Args, runtime: &Runtime) -> Result<(), Error> {
-    let event = match args.next() {
-        Some(json) => {
-            let e: Event = serde_json::from_str(&json)?;
-            e
+    let events: Vec<Event> = match args.next() {
+        Some(arg) => {
+            if std::path::Path::new(&arg).is_file() {
+                let text = std::fs::read_to_string(&arg)?;
+                if text.trim_start().starts_with('[') {
+                    serde_json::from_str(&text)?
+                } else {
+                    let mut events: Vec<Event> = Vec::new();
+                    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                        events.push(serde_json::from_str(line)?);
+                    }
+                    events
+                }
+            } else {
+                vec![serde_json::from_str(&arg)?]
+            }
         }
         None => return cmd.usage("Missing event parameter".to_string()),
     };
@@ -583,10 +705,21 @@ pub fn import_event(cmd: Command, mut args: env::Args, runtime: &Runtime) -> Res
     login()?;
 
     let job = tokio::task::spawn(async move {
-        if let Err(e) =
-            gossip_lib::process::process_new_event(&event, None, None, false, true).await
-        {
-            println!("ERROR: {}", e);
+        let mut failed: usize = 0;
+        for event in &events {
+            if let Err(e) =
+                gossip_lib::process::process_new_event(event, None, None, false, true).await
+            {
+                println!("ERROR: {}: {}", event.id.as_hex_string(), e);
+                failed += 1;
+            }
+        }
+        if events.len() > 1 {
+            println!(
+                "Imported {} events ({} failed).",
+                events.len() - failed,
+                failed
+            );
         }
     });
 
@@ -596,6 +729,113 @@ pub fn import_event(cmd: Command, mut args: env::Args, runtime: &Runtime) -> Res
     Ok(())
 }
 
+pub fn dump_event(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let idstr = match args.next() {
+        Some(id) => id,
+        None => return cmd.usage("Missing id parameter".to_string()),
+    };
+
+    let id = match NostrBech32::try_from_string(&idstr) {
+        Some(NostrBech32::Id(id)) => id,
+        Some(NostrBech32::EventPointer(ep)) => ep.id,
+        Some(_) => return cmd.usage("That is not an event id".to_string()),
+        None => Id::try_from_hex_string(&idstr)?,
+    };
+
+    let event = match GLOBALS.storage.read_event(id)? {
+        Some(event) => event,
+        None => return Err(ErrorKind::EventNotFound.into()),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&event)?);
+
+    println!("SEEN ON:");
+    for (url, when) in GLOBALS.storage.get_event_seen_on_relay(id)? {
+        println!("  {} at {}", url, when.0);
+    }
+
+    println!("VIEWED: {}", GLOBALS.storage.is_event_viewed(id)?);
+
+    println!("RELATIONSHIPS:");
+    for (other, rel) in GLOBALS.storage.find_relationships_by_id(id)? {
+        println!("  {} {:?}", other.as_hex_string(), rel);
+    }
+
+    Ok(())
+}
+
+pub fn export_events(_cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    use std::io::Write;
+
+    let mut out: Box<dyn Write> = match args.next() {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let count = GLOBALS.storage.for_each_event(|event| {
+        writeln!(out, "{}", serde_json::to_string(&event)?)?;
+        Ok(())
+    })?;
+    out.flush()?;
+
+    eprintln!("Exported {} events.", count);
+    Ok(())
+}
+
+pub fn import_settings(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing filepath parameter".to_string()),
+    };
+
+    gossip_lib::settings_file::import_settings(&path)?;
+
+    println!("Imported settings from {}", path);
+    Ok(())
+}
+
+pub fn post_stats(_cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let days = match args.next() {
+        Some(days) => days.parse::<i64>()?,
+        None => 30,
+    };
+    let since = Unixtime(Unixtime::now().unwrap().0 - days * 60 * 60 * 24);
+
+    let mut stats = gossip_lib::analytics::my_post_stats(Some(since))?;
+    println!("{} posts", stats.len());
+    for period in gossip_lib::analytics::by_period(&stats, 60 * 60 * 24) {
+        println!(
+            "{}: {} posts, {} reactions, {} replies, {} reposts, {} quotes, {} zaps ({} sats), seen on {} relays",
+            period.start.0,
+            period.posts,
+            period.reactions,
+            period.replies,
+            period.reposts,
+            period.quotes,
+            period.zaps,
+            period.zap_msats / 1000,
+            period.relays
+        );
+    }
+
+    stats.sort_by(|a, b| b.engagement().cmp(&a.engagement()));
+    println!("Best received:");
+    for post in stats.iter().take(10) {
+        println!(
+            "  {}: {} reactions, {} replies, {} reposts, {} quotes, {} zaps ({} sats), seen on {} relays",
+            post.id.as_hex_string(),
+            post.reactions,
+            post.replies,
+            post.reposts,
+            post.quotes,
+            post.zaps,
+            post.zap_msats / 1000,
+            post.relays.len()
+        );
+    }
+    Ok(())
+}
+
 pub fn print_event(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let idstr = match args.next() {
         Some(id) => id,
@@ -612,6 +852,46 @@ pub fn print_event(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn prune(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    match args.next() {
+        Some(flag) if flag == "--before" => (),
+        _ => return cmd.usage("Missing --before".to_string()),
+    }
+    let (date, before) = match args.next() {
+        Some(date) => {
+            let before = parse_date(&date)?;
+            (date, before)
+        }
+        None => return cmd.usage("Missing date parameter".to_string()),
+    };
+    if before > Unixtime::now().unwrap() {
+        return Err(ErrorKind::General(format!("{} is in the future", date)).into());
+    }
+
+    println!(
+        "This deletes every event created before {}, and cannot be undone. Type \"yes\" to go on:",
+        date
+    );
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim() != "yes" {
+        println!("Nothing was pruned.");
+        return Ok(());
+    }
+
+    let count = GLOBALS.storage.prune(before)?;
+    GLOBALS.storage.sync()?;
+
+    println!("Pruned {} events.", count);
+    Ok(())
+}
+
+// A YYYY-MM-DD date (midnight UTC) or a unixtime
+fn parse_date(s: &str) -> Result<Unixtime, Error> {
+    gossip_lib::search::parse_date(s)
+        .ok_or_else(|| ErrorKind::General(format!("Not a date: {}", s)).into())
+}
+
 pub fn print_relay(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     if let Some(url) = args.next() {
         let rurl = RelayUrl::try_from_str(&url)?;
@@ -783,6 +1063,68 @@ pub fn ungiftwrap(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn export_backup(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing directory parameter".to_string()),
+    };
+
+    gossip_lib::backup::export_backup(&path)?;
+
+    println!("Exported backup to {}", path);
+    Ok(())
+}
+
+pub fn import_backup(cmd: Command, mut args: env::Args, runtime: &Runtime) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing directory parameter".to_string()),
+    };
+
+    let job = tokio::task::spawn(async move { gossip_lib::backup::import_backup(&path).await });
+    let count = runtime.block_on(job)??;
+
+    println!("Imported {} events.", count);
+    Ok(())
+}
+
+pub fn export_settings(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing filepath parameter".to_string()),
+    };
+
+    gossip_lib::settings_file::export_settings(&path)?;
+
+    println!("Exported settings to {}", path);
+    Ok(())
+}
+
+pub fn export_thread(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let idstr = match args.next() {
+        Some(id) => id,
+        None => return cmd.usage("Missing idhex parameter".to_string()),
+    };
+    let id = Id::try_from_hex_string(&idstr)?;
+
+    let format = match args.next().as_deref() {
+        Some("md") => ThreadFormat::Markdown,
+        Some("html") => ThreadFormat::Html,
+        _ => return cmd.usage("Format must be md or html".to_string()),
+    };
+
+    let path = match args.next() {
+        Some(path) => path,
+        None => return cmd.usage("Missing filepath parameter".to_string()),
+    };
+
+    let document = gossip_lib::thread_export::export_thread(id, format)?;
+    std::fs::write(&path, document)?;
+
+    println!("Exported thread to {}", path);
+    Ok(())
+}
+
 pub fn giftwrap_ids(_cmd: Command) -> Result<(), Error> {
     let ids = GLOBALS
         .storage
@@ -853,6 +1195,33 @@ pub fn verify(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn compact_db(_cmd: Command) -> Result<(), Error> {
+    let report = GLOBALS.storage.compact()?;
+    println!(
+        "Checked {} events in the compacted copy. It takes {} bytes instead of {}, and will replace the database on the next start, reclaiming {} bytes.",
+        report.events_checked,
+        report.new_size,
+        report.old_size,
+        report.reclaimed()
+    );
+    Ok(())
+}
+
+pub fn verify_db(_cmd: Command) -> Result<(), Error> {
+    let (count, problems) = GLOBALS.storage.verify_events()?;
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    println!("Checked {} events, {} problems.", count, problems.len());
+
+    if !problems.is_empty() {
+        return Err(
+            ErrorKind::General(format!("{} events failed verification", problems.len())).into(),
+        );
+    }
+    Ok(())
+}
+
 pub fn verify_json(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let json = match args.next() {
         Some(json) => json,
@@ -898,14 +1267,24 @@ pub fn rename_person_list(cmd: Command, mut args: env::Args) -> Result<(), Error
     Ok(())
 }
 
+pub fn list_profiles(_cmd: Command) -> Result<(), Error> {
+    let current = gossip_lib::Profile::current()?.name;
+    let mut profiles: Vec<Option<String>> = vec![None];
+    profiles.extend(gossip_lib::Profile::list()?.into_iter().map(Some));
+    for profile in profiles {
+        let marker = if profile == current { " *" } else { "" };
+        println!("{}{}", profile.as_deref().unwrap_or("(default)"), marker);
+    }
+    Ok(())
+}
+
 pub fn login() -> Result<(), Error> {
     if GLOBALS.identity.has_private_key() {
         let mut password = rpassword::prompt_password("Password: ").unwrap();
-        let epk = match GLOBALS.storage.read_encrypted_private_key()? {
-            Some(epk) => epk,
-            None => return Err(ErrorKind::NoPrivateKey.into()),
-        };
-        GLOBALS.identity.set_encrypted_private_key(epk)?;
+        // Without one we use a remote signer, whose key this unlocks
+        if let Some(epk) = GLOBALS.storage.read_encrypted_private_key()? {
+            GLOBALS.identity.set_encrypted_private_key(epk)?;
+        }
         GLOBALS.identity.unlock(&password)?;
         password.zeroize();
     } else {
@@ -0,0 +1,181 @@
+use crate::logging;
+use gossip_lib::{About, Error, Profile, GLOBALS};
+use nostr_types::Unixtime;
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+/// Where users can submit a crash report
+pub const ISSUES_URL: &str = "https://github.com/mikedilger/gossip/issues";
+
+/// Install a panic hook that saves a crash report (if the user opted in),
+/// then behaves as before
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if GLOBALS.storage.read_setting_crash_reports() {
+            let location = match info.location() {
+                Some(l) => format!("{}:{}", l.file(), l.line()),
+                None => "unknown".to_owned(),
+            };
+            let message = match info.payload().downcast_ref::<&str>() {
+                Some(s) => s.to_string(),
+                None => match info.payload().downcast_ref::<String>() {
+                    Some(s) => s.clone(),
+                    None => "(no message)".to_owned(),
+                },
+            };
+            let thread = std::thread::current();
+            let summary = format!(
+                "panic in thread '{}' at {}: {}",
+                thread.name().unwrap_or("unnamed"),
+                location,
+                message
+            );
+            let backtrace = Backtrace::force_capture().to_string();
+            match write_report(&summary, Some(&backtrace)) {
+                Ok(path) => eprintln!("Crash report saved to {}", path.display()),
+                Err(e) => eprintln!("Could not save crash report: {}", e),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Save a crash report for a fatal error (if the user opted in)
+pub fn fatal_error(error: &Error) {
+    if GLOBALS.storage.read_setting_crash_reports() {
+        if let Err(e) = write_report(&format!("fatal error: {}", error), None) {
+            tracing::error!("Could not save crash report: {}", e);
+        }
+    }
+}
+
+fn reports_dir() -> Result<PathBuf, Error> {
+    let mut dir = Profile::current()?.profile_dir;
+    dir.push("crash_reports");
+    Ok(dir)
+}
+
+/// Saved crash reports, newest first
+pub fn saved_reports() -> Vec<PathBuf> {
+    let dir = match reports_dir() {
+        Ok(dir) => dir,
+        Err(_) => return vec![],
+    };
+    let mut reports: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|x| x == "txt").unwrap_or(false))
+            .collect(),
+        Err(_) => vec![],
+    };
+    reports.sort();
+    reports.reverse();
+    reports
+}
+
+/// Tell the user about crash reports they have not yet dealt with
+pub fn notify_saved_reports() {
+    let count = saved_reports().len();
+    if count > 0 {
+        GLOBALS.status_queue.write().write_warning(format!(
+            "{} crash report(s) saved. See Help > Troubleshooting to review and submit them.",
+            count
+        ));
+    }
+}
+
+// Reports hold no keys and no message contents: just gossip's own diagnostics,
+// the tail of the log (redacted), and storage record counts.
+fn write_report(summary: &str, backtrace: Option<&str>) -> Result<PathBuf, Error> {
+    let about = About::new();
+    let mut report = String::new();
+
+    let _ = writeln!(report, "gossip crash report");
+    let _ = writeln!(report, "===================");
+    let _ = writeln!(report, "version: {}", about.version);
+    let _ = writeln!(
+        report,
+        "platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "time: {}", Unixtime::now().unwrap().0);
+    let _ = writeln!(report);
+    let _ = writeln!(report, "{}", redact(summary));
+
+    if let Some(backtrace) = backtrace {
+        let _ = writeln!(report);
+        let _ = writeln!(report, "backtrace:");
+        let _ = writeln!(report, "{}", backtrace);
+    }
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "storage:");
+    let storage = &GLOBALS.storage;
+    for (name, len) in [
+        ("events", storage.get_event_len()),
+        ("people", storage.get_people_len()),
+        ("person_relays", storage.get_person_relays_len()),
+        ("relays", storage.get_relays_len()),
+        ("relationships_by_id", storage.get_relationships_by_id_len()),
+        ("event_seen_on_relay", storage.get_event_seen_on_relay_len()),
+    ] {
+        match len {
+            Ok(len) => {
+                let _ = writeln!(report, "  {}: {}", name, len);
+            }
+            Err(e) => {
+                let _ = writeln!(report, "  {}: ({})", name, e);
+            }
+        }
+    }
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "recent log:");
+    for line in logging::recent_lines() {
+        let _ = writeln!(report, "{}", redact(&line));
+    }
+
+    let dir = reports_dir()?;
+    fs::create_dir_all(&dir)?;
+    let mut path = dir;
+    path.push(format!("crash-{}.txt", Unixtime::now().unwrap().0));
+    fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+// Replace anything that could be a secret key: bech32 nsec and ncryptsec strings,
+// and 64 character hex strings
+fn redact(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    let flush = |word: &mut String, output: &mut String| {
+        let secret = word.starts_with("nsec1")
+            || word.starts_with("ncryptsec1")
+            || (word.len() == 64 && word.chars().all(|c| c.is_ascii_hexdigit()));
+        if secret {
+            output.push_str("[redacted]");
+        } else {
+            output.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut output);
+            output.push(c);
+        }
+    }
+    flush(&mut word, &mut output);
+
+    output
+}
@@ -1,3 +1,4 @@
+use crate::i18n::tr_count;
 use nostr_types::Unixtime;
 
 pub fn date_ago(then: Unixtime) -> String {
@@ -8,27 +9,29 @@ pub fn date_ago(then: Unixtime) -> String {
     let days: f32 = hours / 24.0;
     let years: f32 = days / 365.0;
 
-    if seconds < 45 {
-        format!("{}s", seconds)
+    let (key, count) = if seconds < 45 {
+        ("ago-seconds", seconds.max(0) as u64)
     } else if seconds < 90 {
-        "1m".to_string()
+        ("ago-minutes", 1)
     } else if minutes < 45.0 {
-        format!("{}m", minutes as i64)
+        ("ago-minutes", minutes as u64)
     } else if minutes < 90.0 {
-        "1h".to_string()
+        ("ago-hours", 1)
     } else if hours < 24.0 {
-        format!("{}h", hours as i64)
+        ("ago-hours", hours as u64)
     } else if hours < 42.0 {
-        "1d".to_string()
+        ("ago-days", 1)
     } else if days < 14.0 {
-        format!("{}d", days as i64)
+        ("ago-days", days as u64)
     } else if days < 56.0 {
-        format!("{}w", (days / 7.0) as i64)
+        ("ago-weeks", (days / 7.0) as u64)
     } else if days < 365.0 {
-        format!("{}M", (days / 30.0) as i64)
+        ("ago-months", (days / 30.0) as u64)
     } else if years < 1.5 {
-        "1y".to_string()
+        ("ago-years", 1)
     } else {
-        format!("{}y", years as i64)
-    }
+        ("ago-years", years as u64)
+    };
+
+    tr_count(key, count, &[])
 }
@@ -0,0 +1,281 @@
+//! Translation of UI strings.
+//!
+//! Locale files live in `gossip-bin/locales/<code>.lang` and are compiled in.
+//! Each non-comment line is `key = value`. Values may contain `{name}` placeholders.
+//! Strings that depend on a count have one key per plural category, e.g.
+//! `notes.one = {count} note` and `notes.other = {count} notes`.
+//!
+//! Keys missing from a locale fall back to English, and then to the key itself.
+
+use gossip_lib::GLOBALS;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Languages we have translations for: (code, name in that language)
+pub const LANGUAGES: &[(&str, &str)] = &[("en", "English"), ("de", "Deutsch")];
+
+fn locale_source(code: &str) -> Option<&'static str> {
+    match code {
+        "en" => Some(include_str!("../../locales/en.lang")),
+        "de" => Some(include_str!("../../locales/de.lang")),
+        _ => None,
+    }
+}
+
+/// CLDR plural categories
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PluralCategory {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+struct Locale {
+    code: String,
+    strings: HashMap<String, String>,
+}
+
+impl Locale {
+    fn load(code: &str) -> Option<Locale> {
+        let source = locale_source(code)?;
+        let mut strings = HashMap::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                strings.insert(key.trim().to_owned(), value.trim().to_owned());
+            }
+        }
+        Some(Locale {
+            code: code.to_owned(),
+            strings,
+        })
+    }
+
+    // Integer plural rules for the languages we support
+    fn plural_category(&self, n: u64) -> PluralCategory {
+        match self.language() {
+            "fr" | "pt" => {
+                if n <= 1 {
+                    PluralCategory::One
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            "ru" | "uk" | "pl" => {
+                let (m10, m100) = (n % 10, n % 100);
+                if self.language() == "pl" && n == 1 {
+                    PluralCategory::One
+                } else if self.language() != "pl" && m10 == 1 && m100 != 11 {
+                    PluralCategory::One
+                } else if (2..=4).contains(&m10) && !(12..=14).contains(&m100) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+            "ja" | "zh" | "ko" => PluralCategory::Other,
+            _ => {
+                if n == 1 {
+                    PluralCategory::One
+                } else {
+                    PluralCategory::Other
+                }
+            }
+        }
+    }
+
+    fn language(&self) -> &str {
+        self.code.split(['-', '_']).next().unwrap_or("en")
+    }
+}
+
+lazy_static! {
+    static ref ENGLISH: Locale = Locale::load("en").unwrap();
+    static ref CURRENT: RwLock<Option<Locale>> = RwLock::new(None);
+}
+
+/// The language code to use for a language setting, where an empty setting means
+/// to follow the operating system
+pub fn resolve_language(setting: &str) -> String {
+    if !setting.is_empty() {
+        return setting.to_owned();
+    }
+    for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = std::env::var(var) {
+            // e.g. "de_DE.UTF-8"
+            let language = value.split(['_', '.', '@']).next().unwrap_or("");
+            if locale_source(language).is_some() {
+                return language.to_owned();
+            }
+        }
+    }
+    "en".to_owned()
+}
+
+/// Load the language from settings. Call at startup and whenever settings are saved.
+pub fn apply_settings() {
+    let code = resolve_language(&GLOBALS.storage.read_setting_language());
+    let locale = if code == "en" {
+        None
+    } else {
+        Locale::load(&code)
+    };
+    *CURRENT.write().unwrap() = locale;
+}
+
+fn lookup_opt(key: &str) -> Option<String> {
+    if let Some(locale) = CURRENT.read().unwrap().as_ref() {
+        if let Some(s) = locale.strings.get(key) {
+            return Some(s.clone());
+        }
+    }
+    ENGLISH.strings.get(key).cloned()
+}
+
+fn lookup(key: &str) -> String {
+    lookup_opt(key).unwrap_or_else(|| key.to_owned())
+}
+
+fn fill(mut s: String, args: &[(&str, String)]) -> String {
+    for (name, value) in args {
+        s = s.replace(&format!("{{{}}}", name), value);
+    }
+    s
+}
+
+/// Translate a string
+pub fn tr(key: &str) -> String {
+    lookup(key)
+}
+
+/// Translate a string with placeholders
+pub fn tr_args(key: &str, args: &[(&str, String)]) -> String {
+    fill(lookup(key), args)
+}
+
+/// Translate a sentence with a link in it. The sentence marks where the link
+/// goes with `{link}`, so that translations can put it anywhere. Returns the
+/// text before the link, the link text and the text after it.
+pub fn tr_link(key: &str, link_key: &str) -> (String, String, String) {
+    let sentence = lookup(key);
+    let (before, after) = sentence.split_once("{link}").unwrap_or((&sentence, ""));
+    (
+        before.trim_end().to_owned(),
+        lookup(link_key),
+        after.trim_start().to_owned(),
+    )
+}
+
+/// Translate a string that depends on a count. `{count}` is replaced by the
+/// count, formatted for the locale. A string that is the same for every count
+/// can be given without plural categories.
+pub fn tr_count(key: &str, count: u64, args: &[(&str, String)]) -> String {
+    let category = match CURRENT.read().unwrap().as_ref() {
+        Some(locale) => locale.plural_category(count),
+        None => ENGLISH.plural_category(count),
+    };
+    let s = lookup_opt(&format!("{}.{}", key, category.as_str()))
+        .or_else(|| lookup_opt(&format!("{}.other", key)))
+        .unwrap_or_else(|| lookup(key));
+    let s = s.replace("{count}", &format_number(count));
+    fill(s, args)
+}
+
+/// Format a number with the locale's digit grouping
+pub fn format_number(n: u64) -> String {
+    // "space" stands for a narrow no-break space, since values are trimmed
+    let separator = match lookup_opt("number-group-separator") {
+        Some(s) if s == "space" => "\u{202F}".to_owned(),
+        Some(s) => s,
+        None => ",".to_owned(),
+    };
+
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push_str(&separator);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Format the date part of a time in the locale's order
+pub fn format_date(stamp: time::OffsetDateTime) -> String {
+    tr_args(
+        "date-format",
+        &[
+            ("year", stamp.year().to_string()),
+            ("month", format!("{:02}", u8::from(stamp.month()))),
+            ("day", format!("{:02}", stamp.day())),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(code: &str) -> Locale {
+        Locale {
+            code: code.to_owned(),
+            strings: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_plural_category() {
+        let en = locale("en");
+        assert_eq!(en.plural_category(0), PluralCategory::Other);
+        assert_eq!(en.plural_category(1), PluralCategory::One);
+        assert_eq!(en.plural_category(2), PluralCategory::Other);
+
+        let fr = locale("fr_FR");
+        assert_eq!(fr.plural_category(0), PluralCategory::One);
+        assert_eq!(fr.plural_category(1), PluralCategory::One);
+        assert_eq!(fr.plural_category(2), PluralCategory::Other);
+
+        let ru = locale("ru");
+        assert_eq!(ru.plural_category(1), PluralCategory::One);
+        assert_eq!(ru.plural_category(21), PluralCategory::One);
+        assert_eq!(ru.plural_category(11), PluralCategory::Many);
+        assert_eq!(ru.plural_category(3), PluralCategory::Few);
+        assert_eq!(ru.plural_category(13), PluralCategory::Many);
+        assert_eq!(ru.plural_category(24), PluralCategory::Few);
+        assert_eq!(ru.plural_category(5), PluralCategory::Many);
+
+        let pl = locale("pl");
+        assert_eq!(pl.plural_category(1), PluralCategory::One);
+        assert_eq!(pl.plural_category(21), PluralCategory::Many);
+        assert_eq!(pl.plural_category(22), PluralCategory::Few);
+        assert_eq!(pl.plural_category(12), PluralCategory::Many);
+
+        let ja = locale("ja");
+        assert_eq!(ja.plural_category(1), PluralCategory::Other);
+    }
+
+    #[test]
+    fn test_locales_parse() {
+        for (code, _) in LANGUAGES {
+            let locale = Locale::load(code).unwrap();
+            assert!(locale.strings.contains_key("settings"), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use gossip_lib::{Error, Profile, GLOBALS};
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+/// How many rotated log files to keep besides the current one
+const LOG_FILES_KEPT: usize = 4;
+
+/// How many recent log lines are kept in memory for crash reports
+const RECENT_LINES_KEPT: usize = 200;
+
+static RUST_LOG_SET: AtomicBool = AtomicBool::new(false);
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static CURRENT_FILTER: Mutex<String> = Mutex::new(String::new());
+static LOG_FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+static RECENT_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Setup logging to the console, and to a log file once settings are available.
+/// Must be called before anything logs.
+pub fn setup() {
+    if env::var("RUST_LOG").is_err() {
+        env::set_var("RUST_LOG", "info");
+    } else {
+        RUST_LOG_SET.store(true, Ordering::Relaxed);
+    }
+    let env_filter = EnvFilter::from_default_env();
+    let max_level = match env_filter.max_level_hint() {
+        Some(l) => l,
+        None => LevelFilter::ERROR,
+    };
+    let show_debug = cfg!(debug_assertions) || max_level <= LevelFilter::DEBUG;
+
+    *CURRENT_FILTER.lock().unwrap() = env::var("RUST_LOG").unwrap_or_default();
+
+    let (filter, handle) = reload::Layer::new(env_filter);
+    let _ = FILTER_HANDLE.set(handle);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_file(show_debug)
+                .with_line_number(show_debug),
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_file(show_debug)
+                .with_line_number(show_debug)
+                .with_writer(LogFileWriter),
+        )
+        .init();
+}
+
+/// Apply the logging settings. Call after the lib is initialized, and again
+/// whenever settings are saved.
+///
+/// A RUST_LOG environment variable overrides the filter setting at startup, until
+/// the filter setting is changed.
+pub fn apply_settings(startup: bool) -> Result<(), Error> {
+    let filter = GLOBALS.storage.read_setting_log_filter();
+    let env_override = startup && RUST_LOG_SET.load(Ordering::Relaxed);
+    if !env_override {
+        set_filter(&filter)?;
+    }
+
+    let mut log_file = LOG_FILE.lock().unwrap();
+    if GLOBALS.storage.read_setting_log_to_file() {
+        let max_size = GLOBALS.storage.read_setting_log_file_max_size_kb() * 1024;
+        match log_file.as_mut() {
+            Some(file) => file.max_size = max_size,
+            None => {
+                let mut path = Profile::current()?.profile_dir;
+                path.push("gossip.log");
+                *log_file = Some(RotatingFile::open(path, max_size)?);
+            }
+        }
+    } else {
+        *log_file = None;
+    }
+
+    Ok(())
+}
+
+/// Change the tracing filter (in RUST_LOG syntax, e.g. "info,gossip_lib::overlord=debug")
+fn set_filter(filter: &str) -> Result<(), Error> {
+    let mut current = CURRENT_FILTER.lock().unwrap();
+    if *current == filter {
+        return Ok(());
+    }
+
+    let env_filter = EnvFilter::try_new(filter)
+        .map_err(|e| Error::from(format!("Invalid log filter '{}': {}", filter, e)))?;
+    if let Some(handle) = FILTER_HANDLE.get() {
+        handle
+            .reload(env_filter)
+            .map_err(|e| Error::from(format!("{}", e)))?;
+    }
+    *current = filter.to_owned();
+
+    tracing::info!("Log filter set to {}", filter);
+    Ok(())
+}
+
+/// The most recent log lines. This does not wait on the lock, so it is safe to
+/// call from a panic hook.
+pub fn recent_lines() -> Vec<String> {
+    match RECENT_LINES.try_lock() {
+        Ok(lines) => lines.iter().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner().iter().cloned().collect(),
+        Err(std::sync::TryLockError::WouldBlock) => vec![],
+    }
+}
+
+/// Check that a filter string parses, for validating settings before saving
+pub fn filter_is_valid(filter: &str) -> bool {
+    EnvFilter::try_new(filter).is_ok()
+}
+
+// A log file that rolls over to gossip.log.1, gossip.log.2, ... when it gets too big
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            max_size,
+        })
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut s = self.path.clone().into_os_string();
+        s.push(format!(".{}", n));
+        s.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let _ = fs::remove_file(self.rotated_path(LOG_FILES_KEPT));
+        for n in (1..LOG_FILES_KEPT).rev() {
+            let _ = fs::rename(self.rotated_path(n), self.rotated_path(n + 1));
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// Keeps recent lines, and writes to the log file if one is open
+struct LogFileWriter;
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut lines) = RECENT_LINES.lock() {
+            for line in String::from_utf8_lossy(buf).lines() {
+                lines.push_back(line.to_owned());
+            }
+            while lines.len() > RECENT_LINES_KEPT {
+                lines.pop_front();
+            }
+        }
+
+        match LOG_FILE.lock().unwrap().as_mut() {
+            Some(file) => file.write_all(buf).map(|_| buf.len()),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.lock().unwrap().as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFileWriter {
+    type Writer = LogFileWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogFileWriter
+    }
+}
@@ -5,7 +5,13 @@
 #![allow(clippy::uninlined_format_args)]
 
 mod commands;
+mod crash_report;
 mod date_ago;
+mod i18n;
+mod logging;
+mod notify;
+mod sound;
+mod timestamp;
 mod ui;
 mod unsaved_settings;
 
@@ -14,33 +20,40 @@ use gossip_lib::Error;
 use gossip_lib::GLOBALS;
 use std::sync::atomic::Ordering;
 use std::{env, thread};
-use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 
 pub const AVATAR_SIZE: u32 = 48; // points, not pixels
 pub const AVATAR_SIZE_F32: f32 = 48.0; // points, not pixels
 pub const AVATAR_SIZE_REPOST_F32: f32 = 27.0; // points, not pixels
 
 fn main() -> Result<(), Error> {
+    // Before any threads are started
+    timestamp::init_local_offset();
+
     // Setup logging
-    if env::var("RUST_LOG").is_err() {
-        env::set_var("RUST_LOG", "info");
+    logging::setup();
+
+    // If the OS handed us a nostr: link and gossip is already running, let
+    // the running gossip open it
+    let nostr_link = env::args()
+        .nth(1)
+        .filter(|arg| gossip_lib::deep_link::is_nostr_link(arg));
+    if let Some(link) = &nostr_link {
+        if gossip_lib::deep_link::forward(link) {
+            return Ok(());
+        }
     }
-    let env_filter = EnvFilter::from_default_env();
-    let max_level = match env_filter.max_level_hint() {
-        Some(l) => l,
-        None => LevelFilter::ERROR,
-    };
-    let show_debug = cfg!(debug_assertions) || max_level <= LevelFilter::DEBUG;
-    tracing_subscriber::fmt::fmt()
-        .with_target(false)
-        .with_file(show_debug)
-        .with_line_number(show_debug)
-        .with_env_filter(env_filter)
-        .init();
 
     // Initialize the lib
     gossip_lib::init()?;
 
+    // Now that settings are available
+    if let Err(e) = logging::apply_settings(true) {
+        tracing::error!("{}", e);
+    }
+    i18n::apply_settings();
+    crash_report::install();
+    crash_report::notify_saved_reports();
+
     // Setup async
     // We create and enter the runtime on the main thread so that
     // non-async code can have a runtime context within which to spawn
@@ -48,9 +61,16 @@ fn main() -> Result<(), Error> {
     let rt = tokio::runtime::Runtime::new()?;
     let _main_rt = rt.enter(); // <-- this allows it.
 
+    // If we were handed a nostr: link, open it once the UI is up
+    if let Some(link) = &nostr_link {
+        if let Err(e) = gossip_lib::deep_link::open(link) {
+            tracing::error!("{}", e);
+        }
+    }
+
     // If we were handed a command, execute the command and return
     let args = env::args();
-    if args.len() > 1 {
+    if args.len() > 1 && nostr_link.is_none() {
         match commands::handle_command(args, &rt) {
             Err(e) => {
                 println!("{}", e);
@@ -74,6 +94,7 @@ fn main() -> Result<(), Error> {
     // Run the UI
     if let Err(e) = ui::run() {
         tracing::error!("{}", e);
+        crash_report::fatal_error(&e);
     }
 
     // Make sure the overlord knows to shut down
@@ -93,6 +114,27 @@ fn main() -> Result<(), Error> {
 
     gossip_lib::shutdown()?;
 
+    // If another profile was chosen, start again in it
+    if let Some(profile) = gossip_lib::Profile::switch_requested() {
+        restart_in_profile(profile)?;
+    }
+
+    Ok(())
+}
+
+// Switching profiles starts a new gossip rather than tearing down and
+// rebuilding this one in place. GLOBALS is created once per process and holds
+// the open storage along with everything cached from it, and winit will not
+// create a second event loop in one process on every platform, so the UI
+// could not come back up either. The old process has shut down fully (the
+// overlord, minions and storage) by the time this is called.
+fn restart_in_profile(profile: Option<String>) -> Result<(), Error> {
+    let mut command = std::process::Command::new(env::current_exe()?);
+    match &profile {
+        Some(name) => command.env("GOSSIP_PROFILE", name),
+        None => command.env_remove("GOSSIP_PROFILE"),
+    };
+    let _ = command.spawn()?;
     Ok(())
 }
 
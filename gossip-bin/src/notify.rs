@@ -0,0 +1,68 @@
+use gossip_lib::alerts::{self, AlertCategory};
+use nostr_types::Unixtime;
+
+/// Longest body shown in a desktop notification
+const MAX_BODY_CHARS: usize = 200;
+
+/// Raise desktop notifications and play sounds for alerts that came in, as the
+/// user configured and unless muted right now
+pub fn deliver_alerts() {
+    let alerts = alerts::take_pending();
+    if alerts.is_empty() {
+        return;
+    }
+
+    let hour = crate::timestamp::local_hour(Unixtime::now().unwrap());
+    let mut sounds: Vec<AlertCategory> = Vec::new();
+    for alert in alerts {
+        match &alert.rule {
+            Some(rule) => {
+                if rule.desktop_notification && !rule.is_muted_at(hour) {
+                    show(
+                        format!(
+                            "{}: {}",
+                            rule.name,
+                            gossip_lib::names::best_name_from_pubkey_lookup(&alert.author)
+                        ),
+                        truncate(&alert.content),
+                    );
+                }
+            }
+            None => {
+                if !sounds.contains(&alert.category) {
+                    sounds.push(alert.category);
+                }
+            }
+        }
+    }
+
+    // One sound per category, however many came in at once
+    if !crate::sound::is_quiet_at(hour) {
+        for category in sounds {
+            crate::sound::play_for(category);
+        }
+    }
+}
+
+fn truncate(content: &str) -> String {
+    let mut body: String = content.chars().take(MAX_BODY_CHARS).collect();
+    if body.len() < content.len() {
+        body.push('…');
+    }
+    body
+}
+
+/// Show a desktop notification. This is done on its own thread as some
+/// platforms block until the notification server answers.
+pub fn show(summary: String, body: String) {
+    std::thread::spawn(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("gossip")
+            .summary(&summary)
+            .body(&body)
+            .show()
+        {
+            tracing::warn!("Could not show a desktop notification: {}", e);
+        }
+    });
+}
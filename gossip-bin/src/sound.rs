@@ -0,0 +1,88 @@
+use gossip_lib::alerts::AlertCategory;
+use gossip_lib::GLOBALS;
+
+/// Whether notification sounds are off at this local hour (0-23)
+pub fn is_quiet_at(hour: u8) -> bool {
+    let start = GLOBALS.storage.read_setting_sound_quiet_start_hour();
+    let end = GLOBALS.storage.read_setting_sound_quiet_end_hour();
+    if start == end {
+        false
+    } else if start < end {
+        hour >= start && hour < end
+    } else {
+        // Through midnight
+        hour >= start || hour < end
+    }
+}
+
+/// Play the sound for a category of notification, if the user turned it on
+pub fn play_for(category: AlertCategory) {
+    let storage = &GLOBALS.storage;
+    let (enabled, file) = match category {
+        AlertCategory::Mention => (
+            storage.read_setting_sound_on_mention(),
+            storage.read_setting_sound_file_mention(),
+        ),
+        AlertCategory::DirectMessage => (
+            storage.read_setting_sound_on_dm(),
+            storage.read_setting_sound_file_dm(),
+        ),
+        AlertCategory::Zap => (
+            storage.read_setting_sound_on_zap(),
+            storage.read_setting_sound_file_zap(),
+        ),
+        AlertCategory::Rule => return,
+    };
+    if enabled {
+        play(category, file);
+    }
+}
+
+// Tones used when no sound file is set
+#[cfg(feature = "sounds")]
+fn pitch(category: AlertCategory) -> f32 {
+    match category {
+        AlertCategory::Mention => 880.0,
+        AlertCategory::DirectMessage => 660.0,
+        AlertCategory::Zap => 1320.0,
+        AlertCategory::Rule => 440.0,
+    }
+}
+
+/// Play a sound file, or the built in tone for the category if `file` is empty
+#[cfg(feature = "sounds")]
+pub fn play(category: AlertCategory, file: String) {
+    // Playing blocks until the sound is done
+    std::thread::spawn(move || {
+        if let Err(e) = play_blocking(pitch(category), file.trim()) {
+            tracing::warn!("Could not play a sound: {}", e);
+        }
+    });
+}
+
+#[cfg(feature = "sounds")]
+fn play_blocking(pitch: f32, file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use rodio::Source;
+    use std::time::Duration;
+
+    let (_stream, handle) = rodio::OutputStream::try_default()?;
+    let sink = rodio::Sink::try_new(&handle)?;
+    if file.is_empty() {
+        sink.append(
+            rodio::source::SineWave::new(pitch)
+                .take_duration(Duration::from_millis(150))
+                .amplify(0.2),
+        );
+    } else {
+        let reader = std::io::BufReader::new(std::fs::File::open(file)?);
+        sink.append(rodio::Decoder::new(reader)?);
+    }
+    sink.sleep_until_end();
+    Ok(())
+}
+
+/// Play a sound file, or the built in tone for the category if `file` is empty
+#[cfg(not(feature = "sounds"))]
+pub fn play(_category: AlertCategory, _file: String) {
+    tracing::warn!("This gossip was built without the 'sounds' feature");
+}
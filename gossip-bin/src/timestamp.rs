@@ -0,0 +1,89 @@
+use crate::i18n;
+use gossip_lib::GLOBALS;
+use nostr_types::Unixtime;
+use std::sync::OnceLock;
+use time::{OffsetDateTime, UtcOffset};
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Find the local time zone offset. This must be called before any other threads
+/// are started, as the operating system cannot be asked safely after that.
+pub fn init_local_offset() {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let _ = LOCAL_OFFSET.set(offset);
+}
+
+fn offset() -> UtcOffset {
+    match GLOBALS.storage.read_setting_timezone_offset_minutes() {
+        Some(minutes) => {
+            UtcOffset::from_whole_seconds(minutes as i32 * 60).unwrap_or(UtcOffset::UTC)
+        }
+        None => *LOCAL_OFFSET.get().unwrap_or(&UtcOffset::UTC),
+    }
+}
+
+/// Display a time the way the user prefers, relative ("5m") or absolute
+pub fn display(when: Unixtime) -> String {
+    if GLOBALS.storage.read_setting_relative_timestamps() {
+        crate::date_ago::date_ago(when)
+    } else {
+        absolute(when)
+    }
+}
+
+/// Display a time as a date and time of day in the user's time zone
+pub fn absolute(when: Unixtime) -> String {
+    let stamp = match OffsetDateTime::from_unix_timestamp(when.0) {
+        Ok(stamp) => stamp.to_offset(offset()),
+        Err(_) => return when.0.to_string(),
+    };
+
+    let time_of_day = if GLOBALS.storage.read_setting_timestamps_24_hour() {
+        stamp.format(time::macros::format_description!("[hour]:[minute]"))
+    } else {
+        stamp.format(time::macros::format_description!(
+            "[hour repr:12 padding:none]:[minute] [period]"
+        ))
+    };
+
+    format!(
+        "{} {}",
+        i18n::format_date(stamp),
+        time_of_day.unwrap_or_default()
+    )
+}
+
+/// Like [absolute], followed by the time zone, for hover text
+pub fn absolute_with_zone(when: Unixtime) -> String {
+    let minutes = offset().whole_minutes();
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let zone = if minutes == 0 {
+        "UTC".to_owned()
+    } else if minutes % 60 == 0 {
+        format!("UTC{}{}", sign, minutes.abs() / 60)
+    } else {
+        format!(
+            "UTC{}{}:{:02}",
+            sign,
+            minutes.abs() / 60,
+            minutes.abs() % 60
+        )
+    };
+    format!("{} {}", absolute(when), zone)
+}
+
+/// The hour of the day (0-23) of a time, in the user's time zone
+pub fn local_hour(when: Unixtime) -> u8 {
+    match OffsetDateTime::from_unix_timestamp(when.0) {
+        Ok(stamp) => stamp.to_offset(offset()).hour(),
+        Err(_) => 0,
+    }
+}
+
+/// Display just the date of a time, in UTC
+pub fn utc_date(when: Unixtime) -> String {
+    match OffsetDateTime::from_unix_timestamp(when.0) {
+        Ok(stamp) => i18n::format_date(stamp),
+        Err(_) => when.0.to_string(),
+    }
+}
@@ -0,0 +1,372 @@
+use super::{widgets, GossipUi, Page};
+use eframe::{egui, Frame};
+use egui::{vec2, Context, RichText, TextStyle, Ui, Vec2};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip23::{self, LongFormArticle, MarkdownBlock, MarkdownSpan};
+use gossip_lib::GLOBALS;
+use nostr_types::{EventKind, Id};
+
+/// A long-form article being written, or one of ours being edited
+pub(super) struct ArticleEdit {
+    d: Option<String>,
+    title: String,
+    summary: String,
+    image: String,
+    hashtags: String,
+    content: String,
+}
+
+impl ArticleEdit {
+    fn new() -> ArticleEdit {
+        ArticleEdit {
+            d: None,
+            title: String::new(),
+            summary: String::new(),
+            image: String::new(),
+            hashtags: String::new(),
+            content: String::new(),
+        }
+    }
+
+    fn edit(article: &LongFormArticle) -> ArticleEdit {
+        ArticleEdit {
+            d: Some(article.d.clone()),
+            title: article.title.clone(),
+            summary: article.summary.clone().unwrap_or_default(),
+            image: article.image.clone().unwrap_or_default(),
+            hashtags: article.hashtags.join(" "),
+            content: article.content.clone(),
+        }
+    }
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    match app.page.clone() {
+        Page::Articles => list(app, ctx, ui),
+        Page::Article(id) => view(app, ctx, ui, id),
+        _ => (),
+    }
+
+    if let Some(mut edit) = app.article_edit.take() {
+        if render_editor(app, ui, &mut edit) {
+            app.article_edit = Some(edit);
+        }
+    }
+}
+
+fn list(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Articles");
+        ui.add_space(10.0);
+        if ui
+            .selectable_label(!app.articles_mine, "People I follow")
+            .clicked()
+        {
+            app.articles_mine = false;
+        }
+        if ui.selectable_label(app.articles_mine, "Mine").clicked() {
+            app.articles_mine = true;
+        }
+        if GLOBALS.identity.is_unlocked() && ui.button("Write an article").clicked() {
+            app.article_edit = Some(ArticleEdit::new());
+        }
+    });
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    let authors = if app.articles_mine {
+        GLOBALS.identity.public_key().into_iter().collect()
+    } else {
+        GLOBALS.people.get_subscribed_pubkeys()
+    };
+    let articles = if authors.is_empty() {
+        vec![]
+    } else {
+        nip23::articles(&authors).unwrap_or_default()
+    };
+
+    if articles.is_empty() {
+        ui.add_space(8.0);
+        ui.label("No articles yet.");
+        return;
+    }
+
+    app.vert_scroll_area().id_source("articles").show(ui, |ui| {
+        for article in articles.iter() {
+            ui.add_space(4.0);
+            ui.vertical(|ui| {
+                if ui
+                    .link(RichText::new(&article.title).text_style(TextStyle::Heading))
+                    .clicked()
+                {
+                    app.set_page(ctx, Page::Article(article.event.id));
+                }
+                byline(app, ctx, ui, article);
+                if let Some(summary) = &article.summary {
+                    ui.label(RichText::new(summary).italics());
+                }
+            });
+            ui.add_space(4.0);
+            ui.separator();
+        }
+    });
+}
+
+fn byline(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, article: &LongFormArticle) {
+    ui.horizontal_wrapped(|ui| {
+        if ui
+            .link(gossip_lib::names::best_name_from_pubkey_lookup(
+                &article.event.pubkey,
+            ))
+            .clicked()
+        {
+            app.set_page(ctx, Page::Person(article.event.pubkey));
+        }
+        ui.label(RichText::new(crate::timestamp::display(article.published())).weak());
+        if article.event.created_at != article.published() {
+            ui.label(
+                RichText::new(format!(
+                    "(edited {})",
+                    crate::timestamp::display(article.event.created_at)
+                ))
+                .weak(),
+            );
+        }
+        for hashtag in &article.hashtags {
+            ui.label(RichText::new(format!("#{}", hashtag)).weak());
+        }
+    });
+}
+
+fn view(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, id: Id) {
+    let article = match GLOBALS.storage.read_event(id) {
+        Ok(Some(event)) => {
+            // Show the latest version of it
+            let latest = event.parameter().and_then(|d| {
+                GLOBALS
+                    .storage
+                    .get_replaceable_event(EventKind::LongFormContent, event.pubkey, &d)
+                    .ok()
+                    .flatten()
+            });
+            LongFormArticle::from_event(latest.as_ref().unwrap_or(&event))
+        }
+        _ => None,
+    };
+    let article = match article {
+        Some(a) => a,
+        None => {
+            ui.add_space(10.0);
+            ui.label("That article is not available.");
+            return;
+        }
+    };
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        if ui.button("< Articles").clicked() {
+            app.set_page(ctx, Page::Articles);
+        }
+        let ours = Some(article.event.pubkey) == GLOBALS.identity.public_key();
+        if ours && GLOBALS.identity.is_unlocked() && ui.button("Edit").clicked() {
+            app.article_edit = Some(ArticleEdit::edit(&article));
+        }
+    });
+    ui.add_space(8.0);
+
+    app.vert_scroll_area().id_source("article").show(ui, |ui| {
+        ui.heading(&article.title);
+        byline(app, ctx, ui, &article);
+        if let Some(summary) = &article.summary {
+            ui.label(RichText::new(summary).italics());
+        }
+        if let Some(image) = &article.image {
+            ui.horizontal_wrapped(|ui| {
+                render_image(app, ui, image);
+            });
+        }
+        ui.add_space(8.0);
+        render_markdown(app, ui, &article.content);
+    });
+}
+
+/// Render markdown content
+pub(super) fn render_markdown(app: &mut GossipUi, ui: &mut Ui, content: &str) {
+    for block in nip23::blocks(content) {
+        match block {
+            MarkdownBlock::Heading(level, text) => {
+                ui.add_space(6.0);
+                let style = if level <= 2 {
+                    TextStyle::Heading
+                } else {
+                    TextStyle::Name("subject".into())
+                };
+                ui.horizontal_wrapped(|ui| render_spans(app, ui, text, Some(style)));
+            }
+            MarkdownBlock::Paragraph(text) => {
+                ui.horizontal_wrapped(|ui| render_spans(app, ui, &text, None));
+                ui.add_space(6.0);
+            }
+            MarkdownBlock::ListItem(number, text) => {
+                ui.horizontal_wrapped(|ui| {
+                    match number {
+                        Some(n) => ui.label(format!("{}.", n)),
+                        None => ui.label("•"),
+                    };
+                    render_spans(app, ui, text, None);
+                });
+            }
+            MarkdownBlock::Quote(text) => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(RichText::new("┃").weak());
+                    render_spans(app, ui, &text, None);
+                });
+                ui.add_space(6.0);
+            }
+            MarkdownBlock::Code(code) => {
+                ui.label(RichText::new(code).monospace());
+                ui.add_space(6.0);
+            }
+            MarkdownBlock::Image { url, .. } => {
+                ui.horizontal_wrapped(|ui| {
+                    render_image(app, ui, url);
+                });
+            }
+            MarkdownBlock::Rule => {
+                ui.separator();
+            }
+        }
+    }
+}
+
+fn render_spans(app: &mut GossipUi, ui: &mut Ui, text: &str, style: Option<TextStyle>) {
+    let styled = |rt: RichText| match &style {
+        Some(s) => rt.text_style(s.clone()),
+        None => rt,
+    };
+    for span in nip23::spans(text) {
+        match span {
+            MarkdownSpan::Text(t) => {
+                ui.label(styled(RichText::new(t)));
+            }
+            MarkdownSpan::Bold(t) => {
+                ui.label(styled(RichText::new(t).strong()));
+            }
+            MarkdownSpan::Italic(t) => {
+                ui.label(styled(RichText::new(t).italics()));
+            }
+            MarkdownSpan::Code(t) => {
+                ui.label(RichText::new(t).code());
+            }
+            MarkdownSpan::Link { label, url } => {
+                if ui
+                    .link(styled(RichText::new(label)))
+                    .on_hover_text(url)
+                    .clicked()
+                {
+                    ui.output_mut(|o| {
+                        o.open_url = Some(egui::output::OpenUrl {
+                            url: url.to_owned(),
+                            new_tab: true,
+                        });
+                    });
+                }
+            }
+            MarkdownSpan::Image { url, .. } => {
+                render_image(app, ui, url);
+            }
+        }
+    }
+}
+
+fn render_image(app: &mut GossipUi, ui: &mut Ui, url: &str) {
+    match app.try_check_url(url) {
+        Some(url) => super::feed::show_image_toggle(app, ui, url, false),
+        None => {
+            ui.label(RichText::new(url).weak());
+        }
+    }
+}
+
+// Returns whether the popup stays open
+fn render_editor(app: &mut GossipUi, ui: &mut Ui, edit: &mut ArticleEdit) -> bool {
+    const DLG_SIZE: Vec2 = vec2(640.0, 560.0);
+    let mut open = true;
+    let ret = widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading(if edit.d.is_some() {
+            "Edit article"
+        } else {
+            "Write an article"
+        });
+        ui.add_space(8.0);
+
+        egui::Grid::new("article_editor")
+            .num_columns(2)
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Title:");
+                ui.add(text_edit_line!(app, edit.title).desired_width(440.0));
+                ui.end_row();
+
+                ui.label("Summary:");
+                ui.add(text_edit_line!(app, edit.summary).desired_width(440.0));
+                ui.end_row();
+
+                ui.label("Image URL:");
+                ui.add(text_edit_line!(app, edit.image).desired_width(440.0));
+                ui.end_row();
+
+                ui.label("Hashtags:");
+                ui.add(
+                    text_edit_line!(app, edit.hashtags)
+                        .hint_text("separated by spaces")
+                        .desired_width(440.0),
+                );
+                ui.end_row();
+            });
+        ui.add_space(4.0);
+        ui.label("Content (markdown):");
+        egui::ScrollArea::vertical()
+            .max_height(DLG_SIZE.y - 240.0)
+            .show(ui, |ui| {
+                ui.add(
+                    text_edit_multiline!(app, edit.content)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(15),
+                );
+            });
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
+            app.theme.accent_button_1_style(ui.style_mut());
+            if ui
+                .add_enabled(
+                    !edit.title.trim().is_empty() && !edit.content.trim().is_empty(),
+                    egui::Button::new("Publish"),
+                )
+                .clicked()
+            {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::PublishLongFormArticle {
+                        d: edit.d.clone(),
+                        title: edit.title.clone(),
+                        summary: edit.summary.clone(),
+                        image: edit.image.clone(),
+                        hashtags: edit
+                            .hashtags
+                            .split_whitespace()
+                            .map(|s| s.to_owned())
+                            .collect(),
+                        content: edit.content.clone(),
+                    });
+                open = false;
+            }
+        });
+    });
+    if ret.inner.clicked() {
+        open = false;
+    }
+    open
+}
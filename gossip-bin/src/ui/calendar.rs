@@ -0,0 +1,203 @@
+use super::{GossipUi, Page};
+use eframe::{egui, Frame};
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip52::{self, CalendarEvent, CalendarTime, RsvpStatus};
+use gossip_lib::GLOBALS;
+use nostr_types::{Id, PublicKey, Unixtime};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// New calendar events and RSVPs come in from relays while the page is open
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+pub(in crate::ui) struct CalendarUi {
+    // cache
+    cache_next_refresh: Instant,
+    cache_events: Result<Vec<CalendarEvent>, String>,
+    cache_rsvps_next_refresh: Instant,
+    cache_rsvps: HashMap<Id, HashMap<PublicKey, RsvpStatus>>,
+}
+
+impl CalendarUi {
+    pub(in crate::ui) fn new() -> Self {
+        Self {
+            cache_next_refresh: Instant::now(),
+            cache_events: Ok(Vec::new()),
+            cache_rsvps_next_refresh: Instant::now(),
+            cache_rsvps: HashMap::new(),
+        }
+    }
+}
+
+pub(super) fn enter_page(app: &mut GossipUi) {
+    refresh_calendar_events(app);
+}
+
+fn refresh_calendar_events(app: &mut GossipUi) {
+    app.calendar.cache_events = nip52::upcoming_from_follows().map_err(|e| format!("{}", e));
+    app.calendar.cache_next_refresh = Instant::now() + REFRESH_INTERVAL;
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Calendar");
+        if ui
+            .button("Refresh")
+            .on_hover_text("Fetch the calendar events of people you follow from your relays")
+            .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchCalendarEvents);
+        }
+    });
+    ui.label("Upcoming events of people you follow (NIP-52)");
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    if app.calendar.cache_next_refresh < Instant::now() {
+        refresh_calendar_events(app);
+    }
+
+    let calendar_events = match &app.calendar.cache_events {
+        Ok(calendar_events) => calendar_events.clone(),
+        Err(e) => {
+            ui.label(e);
+            return;
+        }
+    };
+
+    if calendar_events.is_empty() {
+        ui.add_space(8.0);
+        ui.label("Nobody you follow has an upcoming event.");
+    }
+
+    app.vert_scroll_area().show(ui, |ui| {
+        for calendar_event in &calendar_events {
+            ui.add_space(8.0);
+            let author = calendar_event.event.pubkey;
+            if ui
+                .link(gossip_lib::names::best_name_from_pubkey_lookup(&author))
+                .clicked()
+            {
+                app.set_page(ctx, Page::Person(author));
+            }
+            render_calendar_event(app, ui, ctx, calendar_event);
+            ui.add_space(8.0);
+            ui.separator();
+        }
+    });
+}
+
+/// The title, times, place and people of a calendar event, and buttons to
+/// answer it
+pub(in crate::ui) fn render_calendar_event(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    ctx: &Context,
+    calendar_event: &CalendarEvent,
+) {
+    ui.vertical(|ui| {
+        ui.heading(&calendar_event.title);
+        ui.horizontal_wrapped(|ui| {
+            render_when(ui, calendar_event);
+        });
+        for location in &calendar_event.locations {
+            if location.starts_with("https://") || location.starts_with("http://") {
+                ui.hyperlink(location);
+            } else {
+                ui.label(location);
+            }
+        }
+        if let Some(summary) = &calendar_event.summary {
+            ui.label(RichText::new(summary).italics());
+        }
+
+        if !calendar_event.participants.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("With");
+                for (pubkey, role) in &calendar_event.participants {
+                    GLOBALS.people.person_of_interest(*pubkey);
+                    let name = gossip_lib::names::best_name_from_pubkey_lookup(pubkey);
+                    let response = ui.link(name);
+                    let response = match role {
+                        Some(role) => response.on_hover_text(role),
+                        None => response,
+                    };
+                    if response.clicked() {
+                        app.set_page(ctx, Page::Person(*pubkey));
+                    }
+                }
+            });
+        }
+
+        ui.add_space(4.0);
+        render_rsvps(app, ui, calendar_event);
+    });
+}
+
+fn render_when(ui: &mut Ui, calendar_event: &CalendarEvent) {
+    match (calendar_event.start, calendar_event.end) {
+        (CalendarTime::Time(start), end) => {
+            ui.label(crate::timestamp::absolute(start))
+                .on_hover_text(crate::timestamp::absolute_with_zone(start));
+            if let Some(CalendarTime::Time(end)) = end {
+                ui.label("until");
+                ui.label(crate::timestamp::absolute(end))
+                    .on_hover_text(crate::timestamp::absolute_with_zone(end));
+            }
+        }
+        (start @ CalendarTime::Date(_), end) => {
+            let start = start.unixtime();
+            ui.label(crate::timestamp::utc_date(start));
+            // The end day is not part of the event
+            if let Some(end @ CalendarTime::Date(_)) = end {
+                let last_day = Unixtime(end.unixtime().0 - 60 * 60 * 24);
+                if last_day > start {
+                    ui.label("to");
+                    ui.label(crate::timestamp::utc_date(last_day));
+                }
+            }
+        }
+    }
+}
+
+fn render_rsvps(app: &mut GossipUi, ui: &mut Ui, calendar_event: &CalendarEvent) {
+    if app.calendar.cache_rsvps_next_refresh < Instant::now() {
+        app.calendar.cache_rsvps.clear();
+        app.calendar.cache_rsvps_next_refresh = Instant::now() + REFRESH_INTERVAL;
+    }
+    let rsvps = app
+        .calendar
+        .cache_rsvps
+        .entry(calendar_event.event.id)
+        .or_insert_with(|| nip52::rsvps(calendar_event).unwrap_or_default());
+    let ours = GLOBALS
+        .identity
+        .public_key()
+        .and_then(|pk| rsvps.get(&pk).copied());
+
+    ui.horizontal_wrapped(|ui| {
+        for status in [
+            RsvpStatus::Accepted,
+            RsvpStatus::Tentative,
+            RsvpStatus::Declined,
+        ] {
+            let count = rsvps.values().filter(|s| **s == status).count();
+            let text = format!("{} ({})", status, count);
+            if GLOBALS.identity.is_unlocked() {
+                if ui.selectable_label(ours == Some(status), text).clicked() && ours != Some(status)
+                {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::Rsvp(calendar_event.event.id, status));
+                }
+            } else {
+                ui.label(RichText::new(text).weak());
+            }
+        }
+    });
+}
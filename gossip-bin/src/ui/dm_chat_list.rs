@@ -6,6 +6,9 @@ use gossip_lib::GLOBALS;
 use gossip_lib::{Error, ErrorKind};
 use std::time::{Duration, Instant};
 
+/// Shown over messages sent with the older NIP-04 encryption
+pub(crate) const LEGACY_DM_WARNING: &str = "Legacy encryption (NIP-04). Who you talk to and when is visible to relays, and the encryption itself is weaker. New messages are gift wrapped (NIP-17) once everybody in the conversation has advertised DM relays.";
+
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     // Possibly refresh DM channels (every 5 seconds)
     if app.dm_channel_next_refresh < Instant::now() {
@@ -61,25 +64,24 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                                         RichText::new(channel_name).heading().color(color),
                                     ));
 
+                                    if channeldata.legacy_message_count > 0 {
+                                        ui.label(
+                                            RichText::new("LEGACY")
+                                                .color(app.theme.notice_marker_text_color())
+                                                .small(),
+                                        )
+                                        .on_hover_text(LEGACY_DM_WARNING);
+                                    }
+
                                     ui.with_layout(
                                         egui::Layout::right_to_left(egui::Align::TOP),
                                         |ui| {
-                                            ui.label(crate::date_ago::date_ago(
+                                            ui.label(crate::timestamp::display(
                                                 channeldata.latest_message_created_at,
                                             ))
-                                            .on_hover_ui(|ui| {
-                                                if let Ok(stamp) =
-                                                    time::OffsetDateTime::from_unix_timestamp(
-                                                        channeldata.latest_message_created_at.0,
-                                                    )
-                                                {
-                                                    if let Ok(formatted) = stamp
-                                            .format(&time::format_description::well_known::Rfc2822)
-                                        {
-                                            ui.label(formatted);
-                                        }
-                                                }
-                                            });
+                                            .on_hover_text(crate::timestamp::absolute_with_zone(
+                                                channeldata.latest_message_created_at,
+                                            ));
                                             ui.label(" - ");
                                             ui.label(
                                                 RichText::new(format!(
@@ -0,0 +1,107 @@
+use super::{widgets, GossipUi};
+use eframe::egui;
+use egui::{RichText, Ui};
+use gossip_lib::inspector::EventInspection;
+use gossip_lib::GLOBALS;
+use nostr_types::Unixtime;
+
+/// Render the event being inspected (if any) in a modal
+pub(super) fn render(app: &GossipUi, ui: &mut Ui) {
+    let inspection: EventInspection = match &*GLOBALS.event_inspection.read() {
+        Some(inspection) => inspection.clone(),
+        None => return,
+    };
+
+    let size = ui.ctx().screen_rect().size() * 0.6;
+    let ret = widgets::modal_popup(ui, size, size, true, |ui| {
+        ui.heading("Event Inspector");
+        ui.add_space(10.0);
+
+        app.vert_scroll_area()
+            .id_source("event_inspector")
+            .show(ui, |ui| {
+                ui.label(format!("Id: {}", inspection.id.as_hex_string()));
+                match &inspection.verified {
+                    Ok(()) => ui.label("Signature: valid"),
+                    Err(e) => ui.label(
+                        RichText::new(format!("Signature: INVALID ({})", e))
+                            .color(app.theme.warning_marker_text_color()),
+                    ),
+                };
+                ui.label(format!("Viewed: {}", inspection.viewed));
+                ui.label(format!("Proof of work: {} bits", inspection.pow));
+                ui.label(format!("Expired: {}", inspection.expired));
+                ui.label(format!("Protected: {}", inspection.protected));
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Processing").strong());
+                let when = |at: Option<Unixtime>| match at {
+                    Some(at) => crate::timestamp::absolute_with_zone(at),
+                    None => "never".to_owned(),
+                };
+                ui.label(format!("First received: {}", when(inspection.received_at)));
+                ui.label(format!(
+                    "Last received: {}",
+                    when(inspection.last_received_at)
+                ));
+                ui.label(format!("Alerted: {}", when(inspection.alerted_at)));
+                match inspection.dismissed_until {
+                    Some(Unixtime(i64::MAX)) => ui.label("Dismissed: for ever"),
+                    Some(until) => ui.label(format!(
+                        "Dismissed until: {}",
+                        crate::timestamp::absolute_with_zone(until)
+                    )),
+                    None => ui.label("Dismissed: no"),
+                };
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Seen on").strong());
+                if inspection.seen_on.is_empty() {
+                    ui.label("(not seen on any relay)");
+                }
+                for (url, when) in &inspection.seen_on {
+                    ui.label(format!(
+                        "{} — last at {}",
+                        url,
+                        crate::timestamp::absolute_with_zone(*when)
+                    ));
+                }
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Related events").strong());
+                if inspection.related_by_id.is_empty() && inspection.related_by_addr.is_empty() {
+                    ui.label("(none)");
+                }
+                for (id, rel) in &inspection.related_by_id {
+                    ui.label(format!("{}: {:?}", id.as_hex_string(), rel));
+                }
+                for (id, rel) in &inspection.related_by_addr {
+                    ui.label(format!("{}: {:?} (by address)", id.as_hex_string(), rel));
+                }
+
+                if !inspection.versions.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(RichText::new("Versions").strong());
+                    for (id, when) in &inspection.versions {
+                        ui.label(format!(
+                            "{} at {}",
+                            id.as_hex_string(),
+                            crate::timestamp::absolute_with_zone(*when)
+                        ));
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Raw event").strong());
+                    if ui.link("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = inspection.json.clone());
+                    }
+                });
+                ui.label(RichText::new(&inspection.json).monospace());
+            });
+    });
+    if ret.inner.clicked() {
+        *GLOBALS.event_inspection.write() = None;
+    }
+}
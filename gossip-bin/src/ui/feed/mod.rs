@@ -1,12 +1,20 @@
 use super::theme::FeedProperties;
 use super::{widgets, GossipUi, Page};
+use crate::i18n::{tr, tr_link};
 use eframe::egui;
 use egui::{Context, Frame, RichText, Ui, Vec2};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::FeedKind;
+use gossip_lib::search::SearchScope;
 use gossip_lib::GLOBALS;
-use nostr_types::Id;
+use gossip_lib::{Community, FeedKind, PersonList};
+use nostr_types::{Id, PublicKey};
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// How many statuses of people in a list to show above its feed
+const MAX_LIST_STATUSES: usize = 12;
+
+mod inspector;
 
 mod notedata;
 
@@ -14,6 +22,7 @@ mod notes;
 pub use notes::Notes;
 
 mod note;
+pub(super) use note::show_image_toggle;
 pub use note::NoteRenderData;
 pub(super) mod post;
 
@@ -80,12 +89,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.add_space(10.0);
 
-                        if ui.button("Edit List").clicked() {
+                        if ui.button(tr("feed-edit-list")).clicked() {
                             app.set_page(ctx, Page::PeopleList(list));
                         }
 
                         ui.add_space(10.0);
-                        ui.label(RichText::new("Include replies").size(11.0));
+                        ui.label(RichText::new(tr("feed-include-replies")).size(11.0));
                         let size = ui.spacing().interact_size.y * egui::vec2(1.6, 0.8);
                         if widgets::switch_with_size(ui, &mut app.mainfeed_include_nonroot, size)
                             .clicked()
@@ -101,21 +110,24 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                                 );
                             });
                         }
-                        ui.label(RichText::new("Main posts").size(11.0));
+                        ui.label(RichText::new(tr("feed-main-posts")).size(11.0));
                     });
                 },
             );
+            render_list_statuses(app, ctx, ui, list);
             ui.add_space(6.0);
             render_a_feed(app, ctx, frame, ui, feed, false, &id, load_more);
         }
         FeedKind::Inbox(indirect) => {
             if read_setting!(public_key).is_none() {
                 ui.horizontal_wrapped(|ui| {
-                    ui.label("You need to ");
-                    if ui.link("setup an identity").clicked() {
+                    let (before, link, after) =
+                        tr_link("feed-no-identity", "feed-no-identity-link");
+                    ui.label(before);
+                    if ui.link(link).clicked() {
                         app.set_page(ctx, Page::YourKeys);
                     }
-                    ui.label(" to see any replies to that identity.");
+                    ui.label(after);
                 });
             }
             let feed = GLOBALS.feed.get_inbox();
@@ -126,12 +138,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                 egui::Layout::left_to_right(egui::Align::Center),
                 |ui| {
                     add_left_space(ui);
-                    ui.heading("Inbox");
+                    ui.heading(tr("feed-inbox"));
                     recompute_btn(ui);
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.add_space(10.0);
-                        ui.label(RichText::new("Everything").size(11.0));
+                        ui.label(RichText::new(tr("feed-everything")).size(11.0));
                         let size = ui.spacing().interact_size.y * egui::vec2(1.6, 0.8);
                         if widgets::switch_with_size(ui, &mut app.inbox_include_indirect, size)
                             .clicked()
@@ -147,15 +159,46 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                                 );
                             });
                         }
-                        ui.label(RichText::new("Replies & DM").size(11.0));
+                        ui.label(RichText::new(tr("feed-replies-and-dm")).size(11.0));
                     });
                 },
             );
             ui.add_space(6.0);
             render_a_feed(app, ctx, frame, ui, feed, false, id, load_more);
         }
+        FeedKind::CombinedInbox => {
+            let feed = GLOBALS.feed.get_inbox();
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(tr("feed-all-inboxes"));
+                    recompute_btn(ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(10.0);
+                        if ui.button(tr("feed-edit-identities")).clicked() {
+                            app.set_page(ctx, Page::YourKeys);
+                        }
+                    });
+                },
+            );
+            ui.add_space(6.0);
+            render_a_feed(app, ctx, frame, ui, feed, false, "all_inboxes", load_more);
+        }
         FeedKind::Thread { id, .. } => {
             if let Some(parent) = GLOBALS.feed.get_thread_parent() {
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    add_left_space(ui);
+                    if ui.link(tr("feed-search-thread")).clicked() {
+                        app.search_scope = SearchScope::Thread(parent);
+                        app.search.clear();
+                        app.set_page(ctx, Page::Search);
+                    }
+                });
                 render_a_feed(
                     app,
                     ctx,
@@ -173,7 +216,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             ui.horizontal(|ui| {
                 add_left_space(ui);
                 if Some(pubkey) == GLOBALS.identity.public_key() {
-                    ui.heading("My notes");
+                    ui.heading(tr("feed-my-notes"));
                 } else {
                     ui.heading(gossip_lib::names::best_name_from_pubkey_lookup(&pubkey));
                 }
@@ -197,11 +240,13 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             if !GLOBALS.identity.is_unlocked() {
                 ui.add_space(10.0);
                 ui.horizontal_wrapped(|ui| {
-                    ui.label("You need to ");
-                    if ui.link("setup your private-key").clicked() {
+                    let (before, link, after) =
+                        tr_link("feed-no-private-key", "feed-no-private-key-link");
+                    ui.label(before);
+                    if ui.link(link).clicked() {
                         app.set_page(ctx, Page::YourKeys);
                     }
-                    ui.label(" to see DMs.");
+                    ui.label(after);
                 });
             }
 
@@ -212,12 +257,70 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             });
             ui.add_space(10.0);
 
+            if !channel
+                .keys()
+                .iter()
+                .all(|pk| gossip_lib::nip17::can_receive(*pk))
+            {
+                ui.label(RichText::new(tr("feed-dm-legacy")).weak())
+                    .on_hover_text(crate::ui::dm_chat_list::LEGACY_DM_WARNING);
+                ui.add_space(10.0);
+            }
+
             let feed = GLOBALS.feed.get_dm_chat_feed();
             let id = channel.unique_id();
             render_a_feed(app, ctx, frame, ui, feed, false, &id, load_more);
         }
+        FeedKind::Community(addr) => {
+            let community = Community::load(&addr).ok().flatten();
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                match &community {
+                    Some(c) => ui.heading(&c.name),
+                    None => ui.heading(&addr.d),
+                };
+                recompute_btn(ui);
+            });
+            if let Some(c) = &community {
+                if let Some(description) = &c.description {
+                    ui.horizontal_wrapped(|ui| {
+                        add_left_space(ui);
+                        ui.label(description);
+                    });
+                }
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label("Moderators:");
+                    for pubkey in std::iter::once(&c.addr.author)
+                        .chain(c.moderators.iter().filter(|pk| **pk != c.addr.author))
+                    {
+                        if ui
+                            .link(gossip_lib::names::best_name_from_pubkey_lookup(pubkey))
+                            .clicked()
+                        {
+                            app.set_page(ctx, Page::Person(*pubkey));
+                        }
+                    }
+                });
+            } else {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label("Looking for the community definition...");
+                });
+            }
+            ui.add_space(6.0);
+
+            let feed = GLOBALS.feed.get_community_feed();
+            let id = format!("community_{}", addr.d);
+            render_a_feed(app, ctx, frame, ui, feed, false, &id, load_more);
+        }
     }
 
+    // Show the event being inspected, if any
+    inspector::render(app, ui);
+
     // Handle any changes due to changes in which notes are visible
     app.handle_visible_note_changes();
 }
@@ -411,6 +514,66 @@ fn add_left_space(ui: &mut Ui) {
     ui.add_space(2.0);
 }
 
+// What the people in the list say they are up to (NIP-38)
+fn render_list_statuses(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, list: PersonList) {
+    if !read_setting!(show_user_status) {
+        return;
+    }
+
+    if app.list_statuses_of != Some(list) || app.list_statuses_next_refresh < Instant::now() {
+        let pubkeys: Vec<PublicKey> = GLOBALS
+            .storage
+            .get_people_in_list(list)
+            .unwrap_or_default()
+            .drain(..)
+            .map(|(pk, _)| pk)
+            .collect();
+        app.list_statuses = gossip_lib::nip38::get_statuses_of(&pubkeys).unwrap_or_default();
+        app.list_statuses_of = Some(list);
+        app.list_statuses_next_refresh = Instant::now() + Duration::new(30, 0);
+    }
+
+    if app.list_statuses.is_empty() {
+        return;
+    }
+
+    let statuses = app.list_statuses.clone();
+    ui.add_space(4.0);
+    ui.horizontal_wrapped(|ui| {
+        add_left_space(ui);
+        for (pubkey, status) in statuses.iter().take(MAX_LIST_STATUSES) {
+            if ui
+                .link(
+                    RichText::new(gossip_lib::names::best_name_from_pubkey_lookup(pubkey)).small(),
+                )
+                .clicked()
+            {
+                app.set_page(ctx, Page::Person(*pubkey));
+            }
+            let text = RichText::new(status.display_line())
+                .italics()
+                .weak()
+                .small();
+            match &status.link {
+                Some(link) => {
+                    if ui.link(text).on_hover_text(link).clicked() {
+                        ui.output_mut(|o| {
+                            o.open_url = Some(egui::output::OpenUrl {
+                                url: link.to_owned(),
+                                new_tab: true,
+                            });
+                        });
+                    }
+                }
+                None => {
+                    ui.label(text);
+                }
+            }
+            ui.add_space(10.0);
+        }
+    });
+}
+
 fn recompute_btn(ui: &mut Ui) {
     if !read_setting!(recompute_feed_periodically) {
         if ui.link("Refresh").clicked() {
@@ -6,6 +6,7 @@ use eframe::{
 };
 use egui::{Button, Color32, Pos2, RichText, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip30::{self, EmojiSegment};
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
 use nostr_types::{ContentSegment, EventAddr, Id, IdHex, NostrBech32, PublicKey, Span, Url};
@@ -16,6 +17,55 @@ use std::{
 
 const MAX_POST_HEIGHT: f32 = 200.0;
 
+/// How big custom emoji are drawn, in points
+const EMOJI_SIZE: f32 = 20.0;
+
+/// Offer to open an event we cannot render with applications that can (NIP-89)
+fn render_handlers(app: &mut GossipUi, ui: &mut Ui, note: &Ref<NoteData>) {
+    let kind_number: u32 = note.event.kind.into();
+    if !app.handler_kinds_fetched.contains(&kind_number) {
+        app.handler_kinds_fetched.insert(kind_number);
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::FetchHandlers(note.event.kind));
+    }
+
+    let links: Vec<(String, String, usize)> = note
+        .handlers
+        .iter()
+        .filter_map(|h| {
+            h.link_for(&note.event)
+                .map(|link| (h.name.clone(), link, h.recommended_by.len()))
+        })
+        .collect();
+    if links.is_empty() {
+        return;
+    }
+
+    ui.label("Open with: ");
+    for (name, link, recommenders) in links {
+        let response = ui.link(name);
+        let response = if recommenders > 0 {
+            response.on_hover_text(format!(
+                "{}\nRecommended by {} people you follow",
+                link, recommenders
+            ))
+        } else {
+            response.on_hover_text(&link)
+        };
+        if response.clicked() {
+            ui.output_mut(|o| {
+                o.open_url = Some(egui::output::OpenUrl {
+                    url: link,
+                    new_tab: true,
+                });
+            });
+        }
+        ui.add_space(8.0);
+    }
+    ui.end_row();
+}
+
 pub(super) fn render_content(
     app: &mut GossipUi,
     ui: &mut Ui,
@@ -35,6 +85,10 @@ pub(super) fn render_content(
             // fall through in case there is also shattered content to display
         }
 
+        if note.unsupported_kind {
+            render_handlers(app, ui, &note);
+        }
+
         let content_start = ui.next_widget_position();
 
         for segment in note.shattered_content.segments.iter() {
@@ -257,8 +311,21 @@ pub(super) fn render_plain(
 
         if as_deleted {
             ui.label(RichText::new(line).strikethrough());
-        } else {
+        } else if note.emojis.is_empty() {
             ui.label(line);
+        } else {
+            // Fetching emoji in DMs could reveal us, as with other media
+            let load = !note.direct_message;
+            for piece in nip30::segments(line, &note.emojis) {
+                match piece {
+                    EmojiSegment::Text(text) => {
+                        ui.label(text);
+                    }
+                    EmojiSegment::Emoji { shortcode, url } => {
+                        render_emoji(app, ui, shortcode, url, load);
+                    }
+                }
+            }
         }
 
         first = false;
@@ -267,6 +334,34 @@ pub(super) fn render_plain(
     false
 }
 
+/// Draw a custom emoji (NIP-30), or its `:shortcode:` if the image is not
+/// (yet) available
+pub(super) fn render_emoji(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    shortcode: &str,
+    url: &str,
+    load: bool,
+) -> Response {
+    let texture = if load && read_setting!(load_media) {
+        app.try_check_url(url)
+            .and_then(|url| app.try_get_media(ui.ctx(), url))
+    } else {
+        None
+    };
+    let text = format!(":{}:", shortcode);
+    match texture {
+        Some(texture) => ui
+            .add(
+                Image::new(&texture)
+                    .max_size(Vec2::new(EMOJI_SIZE, EMOJI_SIZE))
+                    .maintain_aspect_ratio(true),
+            )
+            .on_hover_text(text),
+        None => ui.label(text),
+    }
+}
+
 pub(super) fn render_profile_link(app: &mut GossipUi, ui: &mut Ui, pubkey: &PublicKey) {
     let nam = gossip_lib::names::best_name_from_pubkey_lookup(pubkey);
     if ui.link(&nam).clicked() {
@@ -366,7 +461,12 @@ fn is_video_url(url: &url::Url) -> bool {
         || lower.ends_with(".webm")
 }
 
-fn show_image_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url, privacy_issue: bool) {
+pub(in crate::ui) fn show_image_toggle(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    url: Url,
+    privacy_issue: bool,
+) {
     let row_height = ui.cursor().height();
     let url_string = url.to_string();
     let mut show_link = true;
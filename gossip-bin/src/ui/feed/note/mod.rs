@@ -1,4 +1,5 @@
 mod content;
+pub(in crate::ui) use content::show_image_toggle;
 
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -6,20 +7,34 @@ use std::rc::Rc;
 use super::notedata::{NoteData, RepostType};
 
 use super::FeedNoteParams;
+use crate::ui::dm_chat_list::LEGACY_DM_WARNING;
 use crate::ui::widgets::{self, AvatarSize, CopyButton};
 use crate::ui::{GossipUi, Page};
 use crate::{AVATAR_SIZE_F32, AVATAR_SIZE_REPOST_F32};
 use eframe::egui::{self, Margin};
 use egui::{
-    Align, Context, Frame, Label, Layout, RichText, Sense, Separator, Stroke, TextStyle, Ui,
+    Align, Color32, Context, Frame, Label, Layout, RichText, Sense, Separator, Stroke, TextStyle,
+    Ui,
 };
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip03::{self, TimestampStatus};
+use gossip_lib::nip23::LongFormArticle;
+use gossip_lib::nip34;
+use gossip_lib::nip35::{self, Torrent};
+use gossip_lib::nip48;
+use gossip_lib::nip52::{self, CalendarEvent};
+use gossip_lib::nip53::{self, LiveActivity, LiveStatus};
+use gossip_lib::nip54::{self, WikiArticle};
+use gossip_lib::nip75::{self, ZapGoal};
+use gossip_lib::nip90::{Job, JobType};
+use gossip_lib::share;
+use gossip_lib::thread_export::ThreadFormat;
 use gossip_lib::DmChannel;
-use gossip_lib::FeedKind;
+use gossip_lib::{community_of_event, Community, FeedKind};
 use gossip_lib::{Globals, ZapState, GLOBALS};
 use nostr_types::{
-    Event, EventAddr, EventDelegation, EventKind, EventPointer, EventReference, IdHex, NostrUrl,
-    UncheckedUrl,
+    Event, EventAddr, EventDelegation, EventKind, EventPointer, EventReference, IdHex,
+    MilliSatoshi, NostrUrl, UncheckedUrl, Unixtime,
 };
 
 pub struct NoteRenderData {
@@ -79,7 +94,8 @@ pub(super) fn render_note(
             let skip = ((note_data.muted() && read_setting!(hide_mutes_entirely))
                 && !matches!(app.page, Page::Feed(FeedKind::DmChat(_)))
                 && !matches!(app.page, Page::Feed(FeedKind::Person(_))))
-                || (!note_data.deletions.is_empty() && !read_setting!(show_deleted_events));
+                || (!note_data.deletions.is_empty() && !read_setting!(show_deleted_events))
+                || gossip_lib::nip40::is_expired(&note_data.event, Unixtime::now().unwrap());
 
             if skip {
                 return;
@@ -396,8 +412,152 @@ fn render_note_inner(
                         );
                     }
 
+                    if nip34::is_git_kind(note.event.kind) {
+                        let color = app.theme.notice_marker_text_color();
+                        let marker = if nip34::is_patch(note.event.kind) {
+                            "GIT PATCH"
+                        } else if nip34::is_issue(note.event.kind) {
+                            "GIT ISSUE"
+                        } else {
+                            "GIT REPOSITORY"
+                        };
+                        ui.label(
+                            RichText::new(marker)
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                        if let Some(repo) = nip34::repo_of_event(&note.event) {
+                            ui.label(
+                                RichText::new(format!("in {}", repo.d))
+                                    .color(color)
+                                    .text_style(TextStyle::Small),
+                            );
+                        }
+                    }
+
+                    if nip35::is_torrent(note.event.kind) {
+                        let color = app.theme.notice_marker_text_color();
+                        ui.label(
+                            RichText::new("TORRENT")
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if let Some(proxy) = nip48::proxy_of(&note.event) {
+                        let text = RichText::new(format!("bridged from {}", proxy.protocol_name()))
+                            .weak()
+                            .text_style(TextStyle::Small);
+                        match proxy.url() {
+                            Some(url) => {
+                                if ui.link(text).on_hover_text(url).clicked() {
+                                    ui.output_mut(|o| {
+                                        o.open_url = Some(egui::output::OpenUrl {
+                                            url: url.to_owned(),
+                                            new_tab: true,
+                                        });
+                                    });
+                                }
+                            }
+                            None => {
+                                ui.label(text).on_hover_text(&proxy.id);
+                            }
+                        }
+                    }
+
+                    if nip75::is_zap_goal(note.event.kind) {
+                        let color = app.theme.notice_marker_text_color();
+                        ui.label(
+                            RichText::new("ZAP GOAL")
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if nip52::is_calendar_event(note.event.kind) {
+                        let color = app.theme.notice_marker_text_color();
+                        ui.label(
+                            RichText::new("CALENDAR EVENT")
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if nip53::is_live_event(note.event.kind) {
+                        let status = LiveActivity::from_event(&note.event).and_then(|a| a.status);
+                        let color = if status == Some(LiveStatus::Live) {
+                            app.theme.warning_marker_text_color()
+                        } else {
+                            app.theme.notice_marker_text_color()
+                        };
+                        ui.label(
+                            RichText::new(status.map(|s| s.as_str()).unwrap_or("LIVE ACTIVITY"))
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if nip54::is_wiki(note.event.kind) {
+                        let color = app.theme.notice_marker_text_color();
+                        ui.label(
+                            RichText::new("WIKI")
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if note.event.kind == EventKind::LongFormContent {
+                        let color = app.theme.notice_marker_text_color();
+                        ui.label(
+                            RichText::new("ARTICLE")
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if let Some(community) = community_of_event(&note.event) {
+                        if app.page != Page::Feed(FeedKind::Community(community.clone())) {
+                            let name = match Community::load(&community) {
+                                Ok(Some(c)) => c.name,
+                                _ => community.d.clone(),
+                            };
+                            ui.style_mut().override_text_style = Some(TextStyle::Small);
+                            if ui.link(format!("in {}", name)).clicked() {
+                                app.set_page(ctx, Page::Feed(FeedKind::Community(community)));
+                            }
+                            ui.reset_style();
+                        }
+                    }
+
+                    if app.page == Page::Feed(FeedKind::CombinedInbox) {
+                        // Which of the user's identities this is for
+                        let color = app.theme.notice_marker_text_color();
+                        for pubkey in
+                            gossip_lib::identities::targets(&note.event, &app.other_identities)
+                        {
+                            ui.label(
+                                RichText::new(format!(
+                                    "TO {}",
+                                    gossip_lib::names::best_name_from_pubkey_lookup(&pubkey)
+                                ))
+                                .color(color)
+                                .text_style(TextStyle::Small),
+                            );
+                        }
+                    }
+
                     if let Page::Feed(FeedKind::DmChat(_)) = app.page {
-                        // don't show ENCRYPTED DM or SECURE in the dm channel itself
+                        // don't show ENCRYPTED DM or SECURE in the dm channel itself,
+                        // but do warn about the weaker legacy encryption
+                        if note.event.kind == EventKind::EncryptedDirectMessage {
+                            let color = app.theme.notice_marker_text_color();
+                            ui.label(
+                                RichText::new("LEGACY")
+                                    .color(color)
+                                    .text_style(TextStyle::Small),
+                            )
+                            .on_hover_text(LEGACY_DM_WARNING);
+                        }
                     } else {
                         if note.event.kind.is_direct_message_related() {
                             let color = app.theme.notice_marker_text_color();
@@ -407,6 +567,13 @@ fn render_note_inner(
                                         .color(color)
                                         .text_style(TextStyle::Small),
                                 );
+                            } else if note.event.kind == EventKind::EncryptedDirectMessage {
+                                ui.label(
+                                    RichText::new("PRIVATE CHAT (LEGACY)")
+                                        .color(color)
+                                        .text_style(TextStyle::Small),
+                                )
+                                .on_hover_text(LEGACY_DM_WARNING);
                             } else {
                                 ui.label(
                                     RichText::new("PRIVATE CHAT")
@@ -449,49 +616,23 @@ fn render_note_inner(
                             }
                         }
 
-                        if note.event.kind.is_replaceable() {
-                            let param = match note.event.parameter() {
-                                Some(p) => p,
-                                None => "".to_owned(),
-                            };
-                            if ui.button("Copy naddr").clicked() {
-                                let event_addr = EventAddr {
-                                    d: param,
-                                    relays: relays.clone(),
-                                    kind: note.event.kind,
-                                    author: note.event.pubkey,
-                                };
-                                let nostr_url: NostrUrl = event_addr.into();
-                                ui.output_mut(|o| o.copied_text = format!("{}", nostr_url));
-                                *keep_open = false;
-                            }
+                        // Links carry relay hints from where we have seen the note
+                        let copy_label = if note.event.kind.is_replaceable() {
+                            "Copy naddr"
                         } else {
-                            if ui.button("Copy nevent").clicked() {
-                                let event_pointer = EventPointer {
-                                    id: note.event.id,
-                                    relays: relays.clone(),
-                                    author: None,
-                                    kind: None,
-                                };
-                                let nostr_url: NostrUrl = event_pointer.into();
-                                ui.output_mut(|o| o.copied_text = format!("{}", nostr_url));
-                                *keep_open = false;
+                            "Copy nevent"
+                        };
+                        if ui.button(copy_label).clicked() {
+                            if let Ok(bech32) = share::event_bech32(&note.event) {
+                                ui.output_mut(|o| o.copied_text = format!("nostr:{}", bech32));
                             }
+                            *keep_open = false;
                         }
                         if !note.event.kind.is_direct_message_related() {
                             if ui.button("Copy web link").clicked() {
-                                let event_pointer = EventPointer {
-                                    id: note.event.id,
-                                    relays: relays.clone(),
-                                    author: None,
-                                    kind: None,
-                                };
-                                ui.output_mut(|o| {
-                                    o.copied_text = format!(
-                                        "https://njump.me/{}",
-                                        event_pointer.as_bech32_string()
-                                    )
-                                });
+                                if let Ok(bech32) = share::event_bech32(&note.event) {
+                                    ui.output_mut(|o| o.copied_text = share::web_link(&bech32));
+                                }
                                 *keep_open = false;
                             }
                         }
@@ -510,11 +651,82 @@ fn render_note_inner(
                             });
                             *keep_open = false;
                         }
-                        if ui.button("Dismiss").clicked() {
-                            GLOBALS.dismissed.blocking_write().push(note.event.id);
-                            GLOBALS.feed.sync_recompute();
+                        if note.event.kind.is_parameterized_replaceable() {
+                            let addr = EventAddr {
+                                d: note.event.parameter().unwrap_or_default(),
+                                relays: vec![],
+                                kind: note.event.kind,
+                                author: note.event.pubkey,
+                            };
+                            let versions = GLOBALS
+                                .storage
+                                .get_replaceable_event_versions(&addr)
+                                .unwrap_or_default();
+                            for version in versions.iter().filter(|v| v.id != note.event.id) {
+                                if ui
+                                    .button(format!(
+                                        "Copy version of {}",
+                                        crate::timestamp::display(version.created_at)
+                                    ))
+                                    .clicked()
+                                {
+                                    ui.output_mut(|o| {
+                                        o.copied_text =
+                                            serde_json::to_string_pretty(version).unwrap()
+                                    });
+                                    *keep_open = false;
+                                }
+                            }
+                        }
+                        if gossip_lib::read_later::contains(note.event.id) {
+                            if ui.button("Remove from Read Later").clicked() {
+                                if let Err(e) = gossip_lib::read_later::remove(note.event.id) {
+                                    GLOBALS.status_queue.write().write_error(e.to_string());
+                                }
+                                *keep_open = false;
+                            }
+                        } else if ui.button("Read Later").clicked() {
+                            if let Err(e) = gossip_lib::read_later::add(note.event.id) {
+                                GLOBALS.status_queue.write().write_error(e.to_string());
+                            }
                             *keep_open = false;
                         }
+                        ui.menu_button("Dismiss", |ui| {
+                            for (label, days) in [
+                                ("For a day", Some(1)),
+                                ("For a week", Some(7)),
+                                ("For good", None),
+                            ] {
+                                if ui.button(label).clicked() {
+                                    let until = days.map(|d: i64| {
+                                        Unixtime(Unixtime::now().unwrap().0 + d * 60 * 60 * 24)
+                                    });
+                                    if let Err(e) =
+                                        GLOBALS.storage.dismiss_event(note.event.id, until, None)
+                                    {
+                                        GLOBALS.status_queue.write().write_error(e.to_string());
+                                    }
+                                    GLOBALS.feed.sync_recompute();
+                                    *keep_open = false;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        if let Some(addr) = community_of_event(&note.event) {
+                            if let Ok(Some(community)) = Community::load(&addr) {
+                                if community.we_moderate()
+                                    && ui.button("Approve in community").clicked()
+                                {
+                                    let _ = GLOBALS.to_overlord.send(
+                                        ToOverlordMessage::ApproveCommunityPost(
+                                            note.event.id,
+                                            addr,
+                                        ),
+                                    );
+                                    *keep_open = false;
+                                }
+                            }
+                        }
                         if let Some(our_pubkey) = GLOBALS.identity.public_key() {
                             if note.event.pubkey == our_pubkey {
                                 if note.deletions.is_empty() {
@@ -544,8 +756,93 @@ fn render_note_inner(
                                         }
                                     }
                                 }
+
+                                if ui.button("Timestamp on bitcoin").clicked() {
+                                    let _ = GLOBALS
+                                        .to_overlord
+                                        .send(ToOverlordMessage::RequestTimestamp(note.event.id));
+                                    *keep_open = false;
+                                }
+                            } else {
+                                ui.menu_button("Report", |ui| {
+                                    for report_type in gossip_lib::nip56::REPORT_TYPES {
+                                        if ui.button(report_type).clicked() {
+                                            let _ = GLOBALS.to_overlord.send(
+                                                ToOverlordMessage::Report {
+                                                    pubkey: note.event.pubkey,
+                                                    id: Some(note.event.id),
+                                                    report_type: report_type.to_owned(),
+                                                    comment: "".to_owned(),
+                                                },
+                                            );
+                                            *keep_open = false;
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            }
+                            if GLOBALS.identity.is_unlocked()
+                                && widgets::label_menu(
+                                    ui,
+                                    app,
+                                    note.event.pubkey,
+                                    Some(note.event.id),
+                                )
+                            {
+                                *keep_open = false;
+                            }
+                        }
+                        if !note.event.kind.is_direct_message_related() {
+                            for (label, format) in [
+                                ("Export thread as Markdown", ThreadFormat::Markdown),
+                                ("Export thread as HTML", ThreadFormat::Html),
+                            ] {
+                                if ui.button(label).clicked() {
+                                    let _ = GLOBALS.to_overlord.send(
+                                        ToOverlordMessage::ExportThread(note.event.id, format),
+                                    );
+                                    *keep_open = false;
+                                }
+                            }
+                            if GLOBALS.identity.is_unlocked() {
+                                ui.menu_button("Ask a DVM", |ui| {
+                                    let language =
+                                        crate::i18n::resolve_language(&read_setting!(language));
+                                    for (label, job_type) in [
+                                        ("Summarize".to_owned(), JobType::Summarization),
+                                        (
+                                            format!("Translate to {}", language),
+                                            JobType::Translation,
+                                        ),
+                                    ] {
+                                        if ui.button(label).clicked() {
+                                            let _ = GLOBALS.to_overlord.send(
+                                                ToOverlordMessage::RequestJob {
+                                                    job_type,
+                                                    input: Some(note.event.id),
+                                                    language: Some(language.clone()),
+                                                    bid_msats: None,
+                                                },
+                                            );
+                                            *keep_open = false;
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
                             }
                         }
+                        if ui.button("Broadcast to my relays").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::Broadcast(note.event.id));
+                            *keep_open = false;
+                        }
+                        if ui.button("Inspect").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::InspectEvent(note.event.id));
+                            *keep_open = false;
+                        }
                         if ui.button("Rerender").clicked() {
                             app.notes.cache_invalidate_note(&note.event.id);
                             *keep_open = false;
@@ -641,21 +938,11 @@ fn render_note_inner(
                     }
 
                     ui.label(
-                        RichText::new(crate::date_ago::date_ago(note.event.created_at))
+                        RichText::new(crate::timestamp::display(note.event.created_at))
                             .italics()
                             .weak(),
                     )
-                    .on_hover_ui(|ui| {
-                        if let Ok(stamp) =
-                            time::OffsetDateTime::from_unix_timestamp(note.event.created_at.0)
-                        {
-                            if let Ok(formatted) =
-                                stamp.format(&time::format_description::well_known::Rfc2822)
-                            {
-                                ui.label(formatted);
-                            }
-                        }
-                    });
+                    .on_hover_text(crate::timestamp::absolute_with_zone(note.event.created_at));
                 });
             });
 
@@ -739,8 +1026,9 @@ fn render_note_inner(
                                         });
                                     } else if note.event.kind == EventKind::EncryptedDirectMessage {
                                         ui.output_mut(|o| {
-                                            if let Ok(m) =
-                                                GLOBALS.identity.decrypt_event_contents(&note.event)
+                                            if let Ok(Some(m)) = GLOBALS
+                                                .identity
+                                                .try_decrypt_event_contents(&note.event)
                                             {
                                                 o.copied_text = m
                                             } else {
@@ -773,6 +1061,9 @@ fn render_note_inner(
                                             app.draft_data.repost = Some(note.event.id);
                                             app.draft_data.replying_to = None;
                                         }
+                                        if note.repost_count > 0 {
+                                            ui.label(format!("{}", note.repost_count));
+                                        }
 
                                         ui.add_space(24.0);
 
@@ -820,6 +1111,9 @@ fn render_note_inner(
                                             app.show_post_area = true;
                                             app.draft_needs_focus = true;
                                         }
+                                        if note.quote_count > 0 {
+                                            ui.label(format!("{}", note.quote_count));
+                                        }
 
                                         ui.add_space(24.0);
                                     }
@@ -864,6 +1158,12 @@ fn render_note_inner(
                                         }
                                     }
 
+                                    if note.reply_count > 0
+                                        && !note.event.kind.is_direct_message_related()
+                                    {
+                                        ui.label(format!("{}", note.reply_count));
+                                    }
+
                                     ui.add_space(24.0);
                                 }
 
@@ -1008,6 +1308,16 @@ fn render_note_inner(
                                             );
                                         }
                                     }
+                                    for (shortcode, url, count) in note.emoji_reactions.iter() {
+                                        content::render_emoji(
+                                            app,
+                                            ui,
+                                            shortcode,
+                                            url,
+                                            !note.direct_message,
+                                        );
+                                        ui.label(RichText::new(format!(" {}", count)).weak());
+                                    }
                                 }
                             });
 
@@ -1051,6 +1361,11 @@ fn render_subject(ui: &mut Ui, event: &Event) {
         subject
     } else if let Some(title) = event.title() {
         title
+    } else if nip34::is_patch(event.kind) {
+        match nip34::patch_subject(&event.content) {
+            Some(subject) => subject,
+            None => return,
+        }
     } else {
         return;
     };
@@ -1061,6 +1376,320 @@ fn render_subject(ui: &mut Ui, event: &Event) {
     ui.reset_style();
 }
 
+const MAX_PATCH_LINES: usize = 30;
+
+fn render_patch(app: &mut GossipUi, ui: &mut Ui, event: &Event) {
+    let (add_color, remove_color, hunk_color) = if app.theme.dark_mode {
+        (
+            Color32::from_rgb(0x7e, 0xe7, 0x87),
+            Color32::from_rgb(0xff, 0x7b, 0x72),
+            Color32::from_rgb(0x79, 0xc0, 0xff),
+        )
+    } else {
+        (
+            Color32::from_rgb(0x11, 0x63, 0x29),
+            Color32::from_rgb(0x82, 0x07, 0x1e),
+            Color32::from_rgb(0x05, 0x50, 0xae),
+        )
+    };
+
+    let opened = app.opened.contains(&event.id);
+
+    ui.vertical(|ui| {
+        for (i, line) in event.content.lines().enumerate() {
+            if i >= MAX_PATCH_LINES && !opened {
+                if ui.button("Show more ▼").clicked() {
+                    app.opened.insert(event.id);
+                }
+                break;
+            }
+            let text = RichText::new(line).monospace();
+            let text = if line.starts_with("+++") || line.starts_with("---") {
+                text.strong()
+            } else if line.starts_with('+') {
+                text.color(add_color)
+            } else if line.starts_with('-') {
+                text.color(remove_color)
+            } else if line.starts_with("@@") {
+                text.color(hunk_color)
+            } else {
+                text
+            };
+            ui.label(text);
+        }
+    });
+}
+
+const MAX_TORRENT_FILES: usize = 10;
+
+fn render_torrent(app: &mut GossipUi, ui: &mut Ui, event: &Event) {
+    let torrent = match Torrent::from_event(event) {
+        Some(t) => t,
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN TORRENT EVENT")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+            return;
+        }
+    };
+
+    let opened = app.opened.contains(&event.id);
+
+    ui.vertical(|ui| {
+        ui.add_space(4.0);
+        for (i, file) in torrent.files.iter().enumerate() {
+            if i >= MAX_TORRENT_FILES && !opened {
+                if ui
+                    .button(format!("Show all {} files ▼", torrent.files.len()))
+                    .clicked()
+                {
+                    app.opened.insert(event.id);
+                }
+                break;
+            }
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(&file.name).monospace());
+                if let Some(size) = file.size {
+                    ui.label(RichText::new(nip35::human_size(size)).weak());
+                }
+            });
+        }
+
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} files, {}",
+                torrent.files.len(),
+                nip35::human_size(torrent.total_size())
+            ));
+            let magnet = torrent.magnet_link();
+            if ui
+                .button("🧲 Magnet")
+                .on_hover_text("Open the magnet link with your torrent client")
+                .clicked()
+            {
+                ui.output_mut(|o| {
+                    o.open_url = Some(egui::output::OpenUrl {
+                        url: magnet.clone(),
+                        new_tab: true,
+                    });
+                });
+            }
+            if ui
+                .add(CopyButton::new())
+                .on_hover_text("Copy magnet link")
+                .clicked()
+            {
+                ui.output_mut(|o| o.copied_text = magnet);
+            }
+        });
+    });
+}
+
+fn render_wiki(app: &mut GossipUi, ui: &mut Ui, ctx: &Context, event: &Event) {
+    let article = match WikiArticle::from_event(event) {
+        Some(a) => a,
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN WIKI ARTICLE")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+            return;
+        }
+    };
+
+    ui.vertical(|ui| {
+        crate::ui::wiki::render_article(app, ui, ctx, &article);
+        ui.add_space(4.0);
+        if ui.link("Open in wiki").clicked() {
+            crate::ui::wiki::open_topic(app, ctx, &article.d, Some(event.pubkey));
+        }
+    });
+}
+
+fn render_long_form(app: &mut GossipUi, ui: &mut Ui, ctx: &Context, event: &Event) {
+    let article = match LongFormArticle::from_event(event) {
+        Some(a) => a,
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN ARTICLE")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+            return;
+        }
+    };
+
+    ui.vertical(|ui| {
+        ui.heading(&article.title);
+        if let Some(summary) = &article.summary {
+            ui.label(RichText::new(summary).italics());
+        }
+        ui.add_space(4.0);
+        if ui.link("Read article").clicked() {
+            app.set_page(ctx, Page::Article(event.id));
+        }
+    });
+}
+
+fn render_live_activity(app: &mut GossipUi, ui: &mut Ui, ctx: &Context, event: &Event) {
+    // The host keeps updating it, so show the latest version we have
+    let latest = event.parameter().and_then(|d| {
+        GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::LiveEvent, event.pubkey, &d)
+            .ok()
+            .flatten()
+    });
+    let activity = match LiveActivity::from_event(latest.as_ref().unwrap_or(event)) {
+        Some(a) => a,
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN LIVE ACTIVITY")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+            return;
+        }
+    };
+
+    ui.vertical(|ui| {
+        ui.heading(&activity.title);
+        ui.horizontal_wrapped(|ui| {
+            if let Some(starts) = activity.starts {
+                ui.label(
+                    RichText::new(format!("starts {}", crate::timestamp::display(starts))).weak(),
+                );
+            }
+            if let Some(ends) = activity.ends {
+                ui.label(RichText::new(format!("ends {}", crate::timestamp::display(ends))).weak());
+            }
+            if let Some(count) = activity.current_participants {
+                ui.label(RichText::new(format!("{} watching", count)).weak());
+            }
+        });
+        if let Some(summary) = &activity.summary {
+            ui.label(RichText::new(summary).italics());
+        }
+
+        if !activity.participants.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("With");
+                for participant in &activity.participants {
+                    GLOBALS.people.person_of_interest(participant.pubkey);
+                    let name = gossip_lib::names::best_name_from_pubkey_lookup(&participant.pubkey);
+                    let response = ui.link(name);
+                    let response = match &participant.role {
+                        Some(role) => response.on_hover_text(role),
+                        None => response,
+                    };
+                    if response.clicked() {
+                        app.set_page(ctx, Page::Person(participant.pubkey));
+                    }
+                }
+            });
+        }
+
+        if let Some(url) = activity.watch_url() {
+            ui.add_space(4.0);
+            let label = if activity.status == Some(LiveStatus::Ended) {
+                "Watch recording"
+            } else {
+                "Join"
+            };
+            if ui.button(label).on_hover_text(url).clicked() {
+                ui.output_mut(|o| {
+                    o.open_url = Some(egui::output::OpenUrl {
+                        url: url.to_owned(),
+                        new_tab: true,
+                    });
+                });
+            }
+        }
+    });
+}
+
+fn render_calendar_event(app: &mut GossipUi, ui: &mut Ui, ctx: &Context, event: &Event) {
+    // The author may have moved it since, so show the latest version we have
+    let latest = event.parameter().and_then(|d| {
+        GLOBALS
+            .storage
+            .get_replaceable_event(event.kind, event.pubkey, &d)
+            .ok()
+            .flatten()
+    });
+    match CalendarEvent::from_event(latest.as_ref().unwrap_or(event)) {
+        Some(calendar_event) => {
+            crate::ui::calendar::render_calendar_event(app, ui, ctx, &calendar_event)
+        }
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN CALENDAR EVENT")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+        }
+    }
+}
+
+fn render_zap_goal(app: &mut GossipUi, ui: &mut Ui, event: &Event) {
+    let goal = match ZapGoal::from_event(event) {
+        Some(g) => g,
+        None => {
+            let color = app.theme.warning_marker_text_color();
+            ui.label(
+                RichText::new("BROKEN ZAP GOAL")
+                    .color(color)
+                    .text_style(TextStyle::Small),
+            );
+            return;
+        }
+    };
+    let raised = goal.raised().unwrap_or(MilliSatoshi(0));
+
+    ui.vertical(|ui| {
+        ui.heading(&goal.description);
+        if let Some(summary) = &goal.summary {
+            ui.label(RichText::new(summary).italics());
+        }
+
+        let sats = raised.0 / 1000;
+        let target = goal.amount.0 / 1000;
+        let fraction = if goal.amount.0 == 0 {
+            1.0
+        } else {
+            (raised.0 as f32 / goal.amount.0 as f32).min(1.0)
+        };
+        ui.add(
+            egui::ProgressBar::new(fraction)
+                .desired_width(300.0)
+                .text(format!("{} of {} sats", sats, target)),
+        );
+
+        ui.horizontal_wrapped(|ui| {
+            if let Some(closed_at) = goal.closed_at {
+                let text = if goal.is_closed() {
+                    format!("closed {}", crate::timestamp::display(closed_at))
+                } else {
+                    format!("closes {}", crate::timestamp::display(closed_at))
+                };
+                ui.label(RichText::new(text).weak());
+            }
+            if !goal.is_closed() {
+                ui.label(RichText::new("Zap this note to give to it").weak());
+            }
+        });
+    });
+}
+
 fn render_content(
     app: &mut GossipUi,
     ui: &mut Ui,
@@ -1089,6 +1718,88 @@ fn render_content(
                 top: content_pull_top,
             })
             .show(ui, |ui| {
+                if !note.muted() {
+                    for status in &note.statuses {
+                        ui.horizontal_wrapped(|ui| {
+                            let text = RichText::new(status.display_line())
+                                .italics()
+                                .weak()
+                                .text_style(TextStyle::Small);
+                            match &status.link {
+                                Some(link) => {
+                                    if ui.link(text).on_hover_text(link).clicked() {
+                                        ui.output_mut(|o| {
+                                            o.open_url = Some(egui::output::OpenUrl {
+                                                url: link.clone(),
+                                                new_tab: true,
+                                            });
+                                        });
+                                    }
+                                }
+                                None => {
+                                    ui.label(text);
+                                }
+                            }
+                        });
+                    }
+
+                    let verified =
+                        note.attestations
+                            .iter()
+                            .find_map(|att| match nip03::status(*att) {
+                                Some(TimestampStatus::Verified { height, block_time }) => {
+                                    Some((height, block_time))
+                                }
+                                _ => None,
+                            });
+                    if note.reports > 0 {
+                        let color = app.theme.warning_marker_text_color();
+                        ui.label(
+                            RichText::new(format!(
+                                "Reported by {} {} you follow",
+                                note.reports,
+                                if note.reports == 1 {
+                                    "person"
+                                } else {
+                                    "people"
+                                }
+                            ))
+                            .color(color)
+                            .text_style(TextStyle::Small),
+                        );
+                    }
+
+                    if !note.labels.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            for label in &note.labels {
+                                ui.label(
+                                    RichText::new(format!("🏷 {}", label.value))
+                                        .weak()
+                                        .text_style(TextStyle::Small),
+                                )
+                                .on_hover_text(format!("Label in namespace {}", label.namespace));
+                            }
+                        });
+                    }
+
+                    if let Some((height, block_time)) = verified {
+                        ui.label(
+                            RichText::new("⏱ timestamped on bitcoin")
+                                .weak()
+                                .text_style(TextStyle::Small),
+                        )
+                        .on_hover_text(format!(
+                            "Existed before block {}, mined {}",
+                            height,
+                            crate::timestamp::absolute_with_zone(block_time)
+                        ));
+                    }
+
+                    for job in &note.jobs {
+                        render_job(app, ui, job);
+                    }
+                }
+
                 ui.horizontal_wrapped(|ui| {
                     if app.render_raw == Some(event.id) {
                         ui.label(serde_json::to_string_pretty(&event).unwrap());
@@ -1101,7 +1812,9 @@ fn render_content(
                         );
                     } else if app.render_qr == Some(event.id) {
                         if note.event.kind == EventKind::EncryptedDirectMessage {
-                            if let Ok(m) = GLOBALS.identity.decrypt_event_contents(&note.event) {
+                            if let Ok(Some(m)) =
+                                GLOBALS.identity.try_decrypt_event_contents(&note.event)
+                            {
                                 app.render_qr(ui, ctx, "feedqr", m.trim());
                             }
                         } else {
@@ -1191,6 +1904,34 @@ fn render_content(
                                 }
                             }
                         }
+                    } else if nip34::is_patch(event.kind) {
+                        render_subject(ui, event);
+                        render_patch(app, ui, event);
+                    } else if nip35::is_torrent(event.kind) {
+                        render_subject(ui, event);
+
+                        content::render_content(
+                            app,
+                            ui,
+                            ctx,
+                            note_ref.clone(),
+                            as_deleted,
+                            content_margin_left,
+                            bottom_of_avatar,
+                        );
+
+                        ui.end_row();
+                        render_torrent(app, ui, event);
+                    } else if nip52::is_calendar_event(event.kind) {
+                        render_calendar_event(app, ui, ctx, event);
+                    } else if nip53::is_live_event(event.kind) {
+                        render_live_activity(app, ui, ctx, event);
+                    } else if nip54::is_wiki(event.kind) {
+                        render_wiki(app, ui, ctx, event);
+                    } else if nip75::is_zap_goal(event.kind) {
+                        render_zap_goal(app, ui, event);
+                    } else if event.kind == EventKind::LongFormContent {
+                        render_long_form(app, ui, ctx, event);
                     } else {
                         // Possible subject line
                         render_subject(ui, event);
@@ -1302,3 +2043,79 @@ fn render_repost(
         ui.set_row_height(row_height);
     }
 }
+
+fn render_job(app: &mut GossipUi, ui: &mut Ui, job: &Job) {
+    let name = job.job_type.map(|t| t.name()).unwrap_or("Job");
+
+    if job.results.is_empty() {
+        // Show the latest feedback, if any, so the user knows what is going on
+        let text = match job.feedback.last() {
+            Some(feedback) => format!(
+                "🤖 {}: {} ({})",
+                name,
+                if feedback.extra_info.is_empty() {
+                    &feedback.status
+                } else {
+                    &feedback.extra_info
+                },
+                gossip_lib::names::best_name_from_pubkey_lookup(&feedback.from)
+            ),
+            None => format!("🤖 {}: waiting for a data vending machine", name),
+        };
+        ui.label(RichText::new(text).weak().text_style(TextStyle::Small));
+    }
+
+    for result in &job.results {
+        ui.label(
+            RichText::new(format!(
+                "🤖 {} by {}",
+                name,
+                gossip_lib::names::best_name_from_pubkey_lookup(&result.pubkey)
+            ))
+            .weak()
+            .text_style(TextStyle::Small),
+        );
+        ui.label(RichText::new(&result.content).italics());
+    }
+
+    // A DVM may want to be paid before (or after) doing the job
+    for feedback in job.feedback.iter().filter(|f| f.is_payment_required()) {
+        if let Some(bolt11) = &feedback.bolt11 {
+            ui.horizontal(|ui| {
+                let color = app.theme.notice_marker_text_color();
+                let amount = match feedback.amount_msats {
+                    Some(msats) => format!("{} sats", msats / 1000),
+                    None => "payment".to_owned(),
+                };
+                ui.label(
+                    RichText::new(format!(
+                        "{} asks for {}",
+                        gossip_lib::names::best_name_from_pubkey_lookup(&feedback.from),
+                        amount
+                    ))
+                    .color(color)
+                    .text_style(TextStyle::Small),
+                );
+                if ui
+                    .button("⚡ Pay")
+                    .on_hover_text("Open the invoice with your lightning wallet")
+                    .clicked()
+                {
+                    ui.output_mut(|o| {
+                        o.open_url = Some(egui::output::OpenUrl {
+                            url: format!("lightning:{}", bolt11),
+                            new_tab: true,
+                        });
+                    });
+                }
+                if ui
+                    .add(CopyButton::new())
+                    .on_hover_text("Copy invoice")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = bolt11.clone());
+                }
+            });
+        }
+    }
+}
@@ -1,3 +1,16 @@
+use gossip_lib::nip03;
+use gossip_lib::nip30;
+use gossip_lib::nip31;
+use gossip_lib::nip32::Label;
+use gossip_lib::nip34::{self, RepoAnnouncement};
+use gossip_lib::nip35;
+use gossip_lib::nip38::{self, UserStatus};
+use gossip_lib::nip52;
+use gossip_lib::nip53;
+use gossip_lib::nip54;
+use gossip_lib::nip75;
+use gossip_lib::nip89::{self, Handler};
+use gossip_lib::nip90::{self, Job};
 use gossip_lib::GLOBALS;
 use gossip_lib::{Person, PersonList};
 use std::collections::HashMap;
@@ -51,12 +64,23 @@ pub(super) struct NoteData {
     /// Known reactions to this post
     pub reactions: Vec<(char, usize)>,
 
+    /// Known reactions to this post with custom emoji, as (shortcode, url, count)
+    pub emoji_reactions: Vec<(String, String, usize)>,
+
+    /// The custom emoji of this post, by shortcode
+    pub emojis: HashMap<String, String>,
+
     /// The total amount of MilliSatoshi zapped to this note
     pub zaptotal: MilliSatoshi,
 
     /// Has the current user reacted to this post?
     pub self_already_reacted: bool,
 
+    /// Known replies, reposts and quotes of this post
+    pub reply_count: usize,
+    pub repost_count: usize,
+    pub quote_count: usize,
+
     /// The content shattered into renderable elements
     pub shattered_content: ShatteredContent,
 
@@ -68,6 +92,27 @@ pub(super) struct NoteData {
 
     /// Securely delivered via GiftWrap
     pub secure: bool,
+
+    /// Fresh user statuses of the author (NIP-38)
+    pub statuses: Vec<UserStatus>,
+
+    /// OpenTimestamps attestations of this note (NIP-03)
+    pub attestations: Vec<Id>,
+
+    /// How many people we follow have reported this note (NIP-56)
+    pub reports: usize,
+
+    /// Labels put on this note by its author or by people we follow (NIP-32)
+    pub labels: Vec<Label>,
+
+    /// We cannot render this kind of event
+    pub unsupported_kind: bool,
+
+    /// Applications that could render it instead (NIP-89)
+    pub handlers: Vec<Handler>,
+
+    /// Jobs we asked data vending machines to do on this note (NIP-90)
+    pub jobs: Vec<Job>,
 }
 
 impl NoteData {
@@ -77,15 +122,21 @@ impl NoteData {
 
         let mut secure: bool = false;
         let mut direct_message: bool = false;
+        // A remote signer may still be decrypting it
+        let mut decrypting: bool = false;
         if matches!(event.kind, EventKind::GiftWrap) {
             direct_message = true;
             secure = true;
             // Use the rumor for subsequent processing, but swap for the Giftwrap's id
             // since that is the effective event (database-accessible, deletable, etc)
-            if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(&event) {
-                let id = event.id;
-                event = rumor.into_event_with_bad_signature();
-                event.id = id; // lie, keep the giftwrap id
+            match GLOBALS.identity.try_unwrap_giftwrap(&event) {
+                Ok(Some(rumor)) => {
+                    let id = event.id;
+                    event = rumor.into_event_with_bad_signature();
+                    event.id = id; // lie, keep the giftwrap id
+                }
+                Ok(None) => decrypting = true,
+                Err(_) => (),
             }
         }
 
@@ -102,12 +153,20 @@ impl NoteData {
             .storage
             .get_reactions(event.id)
             .unwrap_or((vec![], false));
+        let emoji_reactions = nip30::emoji_reactions(event.id).unwrap_or_default();
+
+        let emojis = nip30::emoji_map(&event);
 
         let zaptotal = GLOBALS
             .storage
             .get_zap_total(event.id)
             .unwrap_or(MilliSatoshi(0));
 
+        let (reply_count, repost_count, quote_count) = GLOBALS
+            .storage
+            .get_engagement_counts(&event)
+            .unwrap_or((0, 0, 0));
+
         // build a list of all cached mentions and their index
         // only notes that are in the cache will be rendered as reposts
         let mentions = {
@@ -144,37 +203,45 @@ impl NoteData {
         };
 
         // Compute the content to our needs
+        let mut unsupported_kind = false;
         let (display_content, error_content) = match event.kind {
             EventKind::TextNote => (event.content.trim().to_string(), None),
             EventKind::Repost => ("".to_owned(), None),
             EventKind::GenericRepost => ("".to_owned(), None),
             EventKind::EncryptedDirectMessage => {
-                match GLOBALS.identity.decrypt_event_contents(&event) {
-                    Ok(m) => (m, None),
+                match GLOBALS.identity.try_decrypt_event_contents(&event) {
+                    Ok(Some(m)) => (m, None),
+                    Ok(None) => ("".to_owned(), Some("DECRYPTING...".to_owned())),
                     Err(_) => ("".to_owned(), Some("DECRYPTION FAILED".to_owned())),
                 }
             }
             EventKind::LongFormContent => (event.content.clone(), None),
             EventKind::DmChat => (event.content.clone(), None),
+            EventKind::GiftWrap if decrypting => ("".to_owned(), Some("DECRYPTING...".to_owned())),
             EventKind::GiftWrap => ("".to_owned(), Some("DECRYPTION FAILED".to_owned())),
             EventKind::ChannelMessage => (event.content.clone(), None),
             EventKind::LiveChatMessage => (event.content.clone(), None),
             EventKind::CommunityPost => (event.content.clone(), None),
             EventKind::DraftLongFormContent => (event.content.clone(), None),
+            k if nip34::is_repo_announcement(k) => match RepoAnnouncement::from_event(&event) {
+                Some(repo) => (repo.as_text(), None),
+                None => ("".to_owned(), Some("BROKEN GIT REPOSITORY".to_owned())),
+            },
+            k if nip34::is_patch(k) => (event.content.clone(), None),
+            k if nip34::is_issue(k) => (event.content.clone(), None),
+            k if nip35::is_torrent(k) => (event.content.clone(), None),
+            k if nip52::is_calendar_event(k) => (event.content.clone(), None),
+            k if nip53::is_live_event(k) => (event.content.clone(), None),
+            k if nip54::is_wiki(k) => (event.content.clone(), None),
+            k if nip75::is_zap_goal(k) => (event.content.clone(), None),
             k => {
+                unsupported_kind = true;
                 let kind_number: u32 = k.into();
-                let mut dc = format!("UNSUPPORTED EVENT KIND {}", kind_number);
-                // support the 'alt' tag of NIP-31:
-                for tag in &event.tags {
-                    if tag.tagname() == "alt" && tag.value() != "" {
-                        dc = format!(
-                            "UNSUPPORTED EVENT KIND {}, ALT: {}",
-                            kind_number,
-                            tag.value()
-                        );
-                    }
-                }
-                ("".to_owned(), Some(dc))
+                let dc = format!("UNSUPPORTED EVENT KIND {}", kind_number);
+                // Show the 'alt' description of NIP-31 as the content, so it
+                // renders like any other note
+                let alt = nip31::alt_of(&event).unwrap_or("").to_owned();
+                (alt, Some(dc))
             }
         };
 
@@ -243,6 +310,32 @@ impl NoteData {
             _ => HashMap::new(),
         };
 
+        let statuses = if GLOBALS.storage.read_setting_show_user_status() {
+            nip38::get_user_statuses(author_pubkey).unwrap_or_default()
+        } else {
+            vec![]
+        };
+
+        let reports = gossip_lib::nip56::reports_of_event(event.id)
+            .map(|r| r.len())
+            .unwrap_or(0);
+
+        let labels = gossip_lib::nip32::labels_of_event(&event).unwrap_or_default();
+
+        let attestations = if GLOBALS.storage.read_setting_verify_timestamps() {
+            nip03::attestations(event.id)
+        } else {
+            vec![]
+        };
+
+        let handlers = if unsupported_kind {
+            nip89::handlers_for_kind(event.kind).unwrap_or_default()
+        } else {
+            vec![]
+        };
+
+        let jobs = nip90::jobs_for_event(event.id).unwrap_or_default();
+
         NoteData {
             event,
             delegation,
@@ -253,12 +346,24 @@ impl NoteData {
             embedded_event,
             mentions,
             reactions,
+            emoji_reactions,
+            emojis,
             zaptotal,
             self_already_reacted,
+            reply_count,
+            repost_count,
+            quote_count,
             shattered_content,
             error_content,
             direct_message,
             secure,
+            statuses,
+            attestations,
+            reports,
+            labels,
+            unsupported_kind,
+            handlers,
+            jobs,
         }
     }
 
@@ -270,6 +375,7 @@ impl NoteData {
 
         self.reactions.clear();
         self.reactions.append(&mut reactions);
+        self.emoji_reactions = nip30::emoji_reactions(self.event.id).unwrap_or_default();
 
         self.self_already_reacted = self_already_reacted;
     }
@@ -282,4 +388,17 @@ impl NoteData {
     pub(super) fn muted(&self) -> bool {
         self.lists.contains_key(&PersonList::Muted)
     }
+
+    /// Approximate bytes held by this note. This is an estimate: the content is
+    /// counted twice (the event and its shattered form) and tags at a typical size.
+    pub(super) fn memory_usage(&self) -> usize {
+        const TAG_BYTES: usize = 128;
+        let event_bytes = |event: &Event| event.content.len() + event.tags.len() * TAG_BYTES;
+
+        std::mem::size_of::<NoteData>()
+            + event_bytes(&self.event)
+            + self.event.content.len()
+            + self.embedded_event.as_ref().map(event_bytes).unwrap_or(0)
+            + self.deletions.iter().map(|d| d.len()).sum::<usize>()
+    }
 }
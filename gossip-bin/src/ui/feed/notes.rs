@@ -38,6 +38,19 @@ impl Notes {
             .retain(|_, note| note.borrow().author.pubkey != *pubkey);
     }
 
+    /// Drop all NoteData except for the given notes
+    pub(in crate::ui) fn cache_retain(&mut self, keep: &[Id]) {
+        self.notes.retain(|id, _| keep.contains(id));
+    }
+
+    /// Approximate bytes held by the cache
+    pub(in crate::ui) fn memory_usage(&self) -> usize {
+        self.notes
+            .values()
+            .filter_map(|note| note.try_borrow().ok().map(|n| n.memory_usage()))
+            .sum()
+    }
+
     pub(super) fn try_update_and_get(&mut self, id: &Id) -> Option<Rc<RefCell<NoteData>>> {
         if self.notes.contains_key(id) {
             // get a mutable reference to update reactions, then give it back
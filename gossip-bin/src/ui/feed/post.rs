@@ -13,8 +13,16 @@ use gossip_lib::DmChannel;
 use gossip_lib::Relay;
 use gossip_lib::GLOBALS;
 use memoize::memoize;
-use nostr_types::{ContentSegment, NostrBech32, NostrUrl, ShatteredContent, Tag};
+use nostr_types::{ContentSegment, NostrBech32, NostrUrl, ShatteredContent, Tag, Unixtime};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+const POST_EXPIRATIONS: [(i64, &str); 4] = [
+    (60 * 60, "1 hour"),
+    (60 * 60 * 24, "1 day"),
+    (60 * 60 * 24 * 7, "1 week"),
+    (60 * 60 * 24 * 30, "30 days"),
+];
 
 #[memoize]
 pub fn textarea_highlighter(theme: Theme, text: String, interests: Vec<String>) -> LayoutJob {
@@ -323,6 +331,34 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
     let screen_rect = ctx.input(|i| i.screen_rect);
     let window_height = screen_rect.max.y - screen_rect.min.y;
 
+    if app.draft_data.repost.is_none() {
+        // Files dropped onto the window get uploaded for the draft
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        for path in dropped {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UploadMedia(path));
+        }
+
+        for upload in GLOBALS.uploaded_media.write().drain(..) {
+            if !app.draft_data.draft.is_empty()
+                && !app.draft_data.draft.ends_with(char::is_whitespace)
+            {
+                app.draft_data.draft.push('\n');
+            }
+            app.draft_data.draft.push_str(&upload.url);
+            app.draft_data
+                .attachments
+                .push((upload.url.clone(), upload.imeta_tag()));
+        }
+    }
+
     app.vert_scroll_area()
         .max_height(window_height * 0.7)
         .show(ui, |ui| {
@@ -388,6 +424,65 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                     ui.add_space(10.0);
                 }
 
+                if let Some(expires_in) = app.draft_data.expires_in {
+                    ui.horizontal(|ui| {
+                        ui.label("Expires after: ");
+                        let selected = POST_EXPIRATIONS
+                            .iter()
+                            .find(|(secs, _)| *secs == expires_in)
+                            .map(|(_, label)| *label)
+                            .unwrap_or("");
+                        egui::ComboBox::from_id_source("PostExpiration")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for (secs, label) in POST_EXPIRATIONS.iter() {
+                                    ui.selectable_value(
+                                        &mut app.draft_data.expires_in,
+                                        Some(*secs),
+                                        *label,
+                                    );
+                                }
+                            });
+                    })
+                    .response
+                    .on_hover_text("Relays and clients that honor expiration (NIP-40) will drop the note after this time. Others may keep it.");
+                    ui.add_space(10.0);
+                }
+
+                if app.draft_data.attaching {
+                    ui.horizontal(|ui| {
+                        ui.label("Attach file: ");
+                        ui.add(
+                            text_edit_line!(app, app.draft_data.attach_path)
+                                .hint_text("Path of the file, or drop it onto the window")
+                                .desired_width(400.0),
+                        );
+                        if ui
+                            .add_enabled(
+                                !app.draft_data.attach_path.trim().is_empty(),
+                                egui::Button::new("Upload"),
+                            )
+                            .clicked()
+                        {
+                            let path = PathBuf::from(app.draft_data.attach_path.trim());
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::UploadMedia(path));
+                            app.draft_data.attach_path = "".to_owned();
+                            app.draft_data.attaching = false;
+                        }
+                    });
+                    if read_setting!(media_server).trim().is_empty() {
+                        ui.label(RichText::new("Set a media server under Settings > Posting first.").weak());
+                    }
+                    ui.add_space(10.0);
+                }
+
+                if app.draft_data.protected {
+                    ui.label(RichText::new("Protected: relays will only accept this note from you, once you authenticate to them, and others may not republish it.").weak());
+                    ui.add_space(10.0);
+                }
+
                 // if we are tagging, we will consume arrow presses and enter key
                 let enter_key;
                 (app.draft_data.tagging_search_selected, enter_key) =
@@ -499,8 +594,8 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             // show advanced action menu
             if app.draft_data.repost.is_none() {
                 widgets::MoreMenu::bubble(ui, app)
-                    .with_max_size(vec2(180.0, 80.0))
-                    .with_min_size(vec2(180.0, 80.0))
+                    .with_max_size(vec2(180.0, 140.0))
+                    .with_min_size(vec2(180.0, 140.0))
                     .place_above(!read_setting!(posting_area_at_top))
                     .show(ui, |ui, is_open| {
                         ui.vertical_centered_justified(|ui| {
@@ -532,6 +627,37 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
 
                             ui.add_space(10.0);
 
+                            if app.draft_data.expires_in.is_some() {
+                                if ui.button("Remove Expiration").clicked() {
+                                    app.draft_data.expires_in = None;
+                                    *is_open = false;
+                                }
+                            } else if ui.button("Add Expiration").clicked() {
+                                app.draft_data.expires_in = Some(POST_EXPIRATIONS[1].0);
+                                *is_open = false;
+                            }
+
+                            ui.add_space(10.0);
+
+                            if app.draft_data.protected {
+                                if ui.button("Unmark Protected").clicked() {
+                                    app.draft_data.protected = false;
+                                    *is_open = false;
+                                }
+                            } else if ui.button("Mark Protected").clicked() {
+                                app.draft_data.protected = true;
+                                *is_open = false;
+                            }
+
+                            ui.add_space(10.0);
+
+                            if ui.button("Attach a File").clicked() {
+                                app.draft_data.attaching = true;
+                                *is_open = false;
+                            }
+
+                            ui.add_space(10.0);
+
                             ui.set_enabled(!app.draft_data.replacements.is_empty());
                             if ui.button("Show raw preview").clicked() {
                                 let raw = do_replacements(
@@ -611,6 +737,19 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
         if app.draft_data.include_subject {
             tags.push(Tag::new_subject(app.draft_data.subject.clone()));
         }
+        if let Some(secs) = app.draft_data.expires_in {
+            let expiration = Unixtime(Unixtime::now().unwrap().0 + secs);
+            tags.push(gossip_lib::nip40::expiration_tag(expiration));
+        }
+        if app.draft_data.protected {
+            tags.push(gossip_lib::nip70::protected_tag());
+        }
+        for (url, imeta) in &app.draft_data.attachments {
+            // Unless the link to it was taken out again
+            if replaced.contains(url.as_str()) {
+                tags.push(imeta.clone());
+            }
+        }
         match app.draft_data.replying_to {
             Some(replying_to_id) => {
                 let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
@@ -625,6 +764,14 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                     let _ = GLOBALS
                         .to_overlord
                         .send(ToOverlordMessage::Repost(event_id));
+                } else if let Page::Feed(FeedKind::Community(community)) = &app.page {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::PostToCommunity {
+                            content: replaced,
+                            tags,
+                            community: community.to_owned(),
+                        });
                 } else {
                     let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
                         content: replaced,
@@ -5,6 +5,7 @@ use gossip_lib::PersonList;
 
 mod about;
 mod stats;
+mod status_history;
 mod theme;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -72,6 +73,45 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 });
             });
 
+            ui.add_space(10.0);
+            ui.heading("Gossip crashed:");
+            ui.indent("crashreports", |ui| {
+                let reports = crate::crash_report::saved_reports();
+                if reports.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("No crash reports are saved. If crashes keep happening, turn on 'Save crash reports' in");
+                        if ui.link("Settings").clicked() {
+                            app.set_page(ctx, Page::Settings);
+                        }
+                        ui.label("under Network > Logging.");
+                    });
+                } else {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Review these reports. If you wish, attach them to an");
+                        ui.hyperlink_to("issue on Github", crate::crash_report::ISSUES_URL);
+                        ui.label("and then delete them.");
+                    });
+                    for report in reports {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}", report.display()));
+                            if ui.link("Open").clicked() {
+                                ui.output_mut(|o| {
+                                    o.open_url = Some(egui::output::OpenUrl {
+                                        url: format!("file://{}", report.display()),
+                                        new_tab: true,
+                                    });
+                                });
+                            }
+                            if ui.link("Delete").clicked() {
+                                if let Err(e) = std::fs::remove_file(&report) {
+                                    tracing::error!("{}", e);
+                                }
+                            }
+                        });
+                    }
+                }
+            });
+
             ui.add_space(10.0);
             ui.heading("more will be added in the future.");
 
@@ -79,6 +119,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         });
     } else if app.page == Page::HelpStats {
         stats::update(app, ctx, _frame, ui);
+    } else if app.page == Page::HelpStatusHistory {
+        status_history::update(app, ctx, _frame, ui);
     } else if app.page == Page::HelpAbout {
         about::update(app, ctx, _frame, ui);
     } else if app.page == Page::HelpTheme {
@@ -1,4 +1,5 @@
 use super::GossipUi;
+use crate::i18n::{format_number, tr, tr_args, tr_count};
 use eframe::egui;
 use egui::{Context, Ui};
 use gossip_lib::GLOBALS;
@@ -7,7 +8,7 @@ use std::sync::atomic::Ordering;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.add_space(10.0);
-    ui.heading("Statistics".to_string());
+    ui.heading(tr("stats"));
     ui.add_space(12.0);
     ui.separator();
 
@@ -16,129 +17,207 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     app.vert_scroll_area().show(ui, |ui| {
         ui.add_space(10.0);
 
-        ui.label(format!(
-            "Total Bytes Read: {}",
-            format_size(GLOBALS.bytes_read.load(Ordering::Relaxed), DECIMAL)
+        ui.label(tr_args(
+            "stats-bytes-read",
+            &[(
+                "bytes",
+                format_size(GLOBALS.bytes_read.load(Ordering::Relaxed), DECIMAL),
+            )],
         ));
 
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "HTTP Requests in flight: {}",
-            GLOBALS.fetcher.requests_in_flight()
+        ui.label(tr_args(
+            "stats-http-in-flight",
+            &[(
+                "count",
+                format_number(GLOBALS.fetcher.requests_in_flight() as u64),
+            )],
         ));
 
-        ui.label(format!(
-            "HTTP Requests queued: {}",
-            GLOBALS.fetcher.requests_queued()
+        ui.label(tr_args(
+            "stats-http-queued",
+            &[(
+                "count",
+                format_number(GLOBALS.fetcher.requests_queued() as u64),
+            )],
         ));
 
         ui.add_space(6.0);
         ui.separator();
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Number of known relays: {}",
-            match GLOBALS.storage.filter_relays(|_| true) {
-                Err(e) => {
-                    tracing::error!("{}", e);
-                    0
-                }
-                Ok(vec) => vec.len(),
+        ui.label(tr("stats-memory-heading"));
+        ui.add_space(6.0);
+        let usage = app.memory_usage;
+        for (name, bytes) in [
+            ("Events", usage.events),
+            ("Media", usage.media),
+            ("Avatars", usage.avatars),
+        ] {
+            ui.label(tr_args(
+                "stats-memory",
+                &[
+                    ("name", name.to_owned()),
+                    ("bytes", format_size(bytes, DECIMAL)),
+                ],
+            ));
+        }
+        let budget_mb = GLOBALS.storage.read_setting_memory_budget_mb();
+        if budget_mb == 0 {
+            ui.label(tr_args(
+                "stats-memory-no-budget",
+                &[("bytes", format_size(usage.total(), DECIMAL))],
+            ));
+        } else {
+            ui.label(tr_args(
+                "stats-memory-budget",
+                &[
+                    ("bytes", format_size(usage.total(), DECIMAL)),
+                    (
+                        "budget",
+                        format_size(budget_mb as u64 * 1024 * 1024, DECIMAL),
+                    ),
+                ],
+            ));
+        }
+
+        ui.add_space(6.0);
+        ui.separator();
+        ui.add_space(6.0);
+
+        let known_relays = match GLOBALS.storage.filter_relays(|_| true) {
+            Err(e) => {
+                tracing::error!("{}", e);
+                0
             }
+            Ok(vec) => vec.len(),
+        };
+        ui.label(tr_args(
+            "stats-known-relays",
+            &[("count", format_number(known_relays as u64))],
         ));
 
         ui.add_space(6.0);
         ui.separator();
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "General: {} records",
-            GLOBALS.storage.get_general_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_general_len().unwrap_or(0),
+            &[("name", "General".to_owned())],
+        ));
+        ui.add_space(6.0);
+
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_len().unwrap_or(0),
+            &[("name", "Events".to_owned())],
+        ));
+        ui.add_space(6.0);
+
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_ek_pk_index_len().unwrap_or(0),
+            &[("name", "Event Index (EK-PK)".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Events: {} records",
-            GLOBALS.storage.get_event_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_ek_c_index_len().unwrap_or(0),
+            &[("name", "Event Index (EK-C)".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Index (EK-PK): {} records",
-            GLOBALS.storage.get_event_ek_pk_index_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_tag_index_len().unwrap_or(0),
+            &[("name", "Event Index (Tags)".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Index (EK-C): {} records",
-            GLOBALS.storage.get_event_ek_c_index_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_seen_on_relay_len().unwrap_or(0),
+            &[("name", "Event Seen on Relay".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Index (Tags): {} records",
-            GLOBALS.storage.get_event_tag_index_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_viewed_len().unwrap_or(0),
+            &[("name", "Event Viewed".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Seen on Relay: {} records",
-            GLOBALS.storage.get_event_seen_on_relay_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_dismissed_len().unwrap_or(0),
+            &[("name", "Event Dismissed".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Viewed: {} records",
-            GLOBALS.storage.get_event_viewed_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_event_alerted_len().unwrap_or(0),
+            &[("name", "Event Alerted".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Hashtags: {} records",
-            GLOBALS.storage.get_hashtags_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_hashtags_len().unwrap_or(0),
+            &[("name", "Hashtags".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Relays: {} records",
-            GLOBALS.storage.get_relays_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_relays_len().unwrap_or(0),
+            &[("name", "Relays".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "People: {} records",
-            GLOBALS.storage.get_people_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_people_len().unwrap_or(0),
+            &[("name", "People".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Person-Relays: {} records",
-            GLOBALS.storage.get_person_relays_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_person_relays_len().unwrap_or(0),
+            &[("name", "Person-Relays".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Person-Lists: {} records",
-            GLOBALS.storage.get_person_lists_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_person_lists_len().unwrap_or(0),
+            &[("name", "Person-Lists".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Relationships By Id: {} records",
-            GLOBALS.storage.get_relationships_by_id_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_relationships_by_id_len().unwrap_or(0),
+            &[("name", "Event Relationships By Id".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Event Relationships By Addr: {} records",
-            GLOBALS.storage.get_relationships_by_addr_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_relationships_by_addr_len().unwrap_or(0),
+            &[("name", "Event Relationships By Addr".to_owned())],
         ));
         ui.add_space(6.0);
 
-        ui.label(format!(
-            "Nip46 Servers: {} records",
-            GLOBALS.storage.get_nip46servers_len().unwrap_or(0)
+        ui.label(tr_count(
+            "stats-records",
+            GLOBALS.storage.get_nip46servers_len().unwrap_or(0),
+            &[("name", "Nip46 Servers".to_owned())],
         ));
         ui.add_space(6.0);
     });
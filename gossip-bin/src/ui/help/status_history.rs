@@ -0,0 +1,70 @@
+use super::GossipUi;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::{StatusSeverity, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Status History".to_string());
+    ui.add_space(12.0);
+    ui.separator();
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Show:");
+        for severity in [
+            StatusSeverity::Info,
+            StatusSeverity::Warning,
+            StatusSeverity::Error,
+        ] {
+            let label = match severity {
+                StatusSeverity::Info => "Everything",
+                StatusSeverity::Warning => "Warnings and errors",
+                StatusSeverity::Error => "Errors only",
+            };
+            ui.selectable_value(&mut app.status_history_severity, severity, label);
+        }
+        ui.add_space(20.0);
+        ui.label("Containing:");
+        ui.text_edit_singleline(&mut app.status_history_filter);
+        ui.add_space(20.0);
+        if ui.button("Clear History").clicked() {
+            GLOBALS.status_queue.write().clear_history();
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let entries = GLOBALS
+        .status_queue
+        .read()
+        .history(app.status_history_severity, &app.status_history_filter);
+
+    if entries.is_empty() {
+        ui.add_space(10.0);
+        ui.label("No status messages.");
+        return;
+    }
+
+    app.vert_scroll_area().show(ui, |ui| {
+        for entry in entries {
+            ui.add_space(6.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(crate::timestamp::display(entry.time)).weak())
+                    .on_hover_text(crate::timestamp::absolute_with_zone(entry.time));
+                let severity = RichText::new(entry.severity.as_str()).small();
+                let severity = match entry.severity {
+                    StatusSeverity::Info => severity.weak(),
+                    StatusSeverity::Warning | StatusSeverity::Error => {
+                        severity.color(app.theme.warning_marker_text_color())
+                    }
+                };
+                ui.label(severity);
+                ui.label(RichText::new(entry.source).small().weak());
+            });
+            ui.label(entry.message);
+        }
+    });
+}
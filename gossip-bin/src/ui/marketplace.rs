@@ -0,0 +1,247 @@
+use super::{widgets, GossipUi, Page};
+use eframe::{egui, Frame};
+use egui::{vec2, Context, Image, RichText, Ui, Vec2};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip15::{self, Order, Product, Stall};
+use gossip_lib::{DmChannel, GLOBALS};
+
+const THUMBNAIL_SIZE: f32 = 96.0;
+
+/// An order being filled in, before it is sent to the merchant
+pub(super) struct Checkout {
+    product: Product,
+    stall: Option<Stall>,
+    quantity: u64,
+    shipping: usize,
+    name: String,
+    address: String,
+    email: String,
+    message: String,
+}
+
+impl Checkout {
+    fn new(product: Product, stall: Option<Stall>) -> Checkout {
+        Checkout {
+            product,
+            stall,
+            quantity: 1,
+            shipping: 0,
+            name: String::new(),
+            address: String::new(),
+            email: String::new(),
+            message: String::new(),
+        }
+    }
+
+    fn order(&self) -> Order {
+        let shipping_id = self
+            .stall
+            .as_ref()
+            .and_then(|s| s.shipping.get(self.shipping))
+            .map(|s| s.id.clone())
+            .unwrap_or_default();
+        let mut order = Order::new(&self.product, self.quantity, shipping_id);
+        let some = |s: &str| {
+            if s.trim().is_empty() {
+                None
+            } else {
+                Some(s.trim().to_owned())
+            }
+        };
+        order.name = some(&self.name);
+        order.address = some(&self.address);
+        order.message = some(&self.message);
+        order.contact.email = some(&self.email);
+        order
+    }
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Marketplace");
+        if ui
+            .button("Refresh")
+            .on_hover_text("Fetch the products of people you follow from your relays")
+            .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchMarketplace);
+        }
+    });
+    ui.label("Products sold by people you follow (NIP-15)");
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    let products = match nip15::followed_products() {
+        Ok(products) => products,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if products.is_empty() {
+        ui.add_space(8.0);
+        ui.label("Nobody you follow is selling anything yet.");
+    }
+
+    app.vert_scroll_area().show(ui, |ui| {
+        for (product, stall) in products {
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                render_thumbnail(app, ui, &product);
+
+                ui.vertical(|ui| {
+                    ui.label(RichText::new(&product.name).strong());
+                    ui.horizontal(|ui| {
+                        ui.label(product.display_price());
+                        if let Some(quantity) = product.quantity {
+                            ui.label(RichText::new(format!("{} available", quantity)).weak());
+                        }
+                    });
+                    if let Some(description) = &product.description {
+                        ui.label(description);
+                    }
+                    ui.horizontal(|ui| {
+                        if let Some(merchant) = product.merchant {
+                            if ui
+                                .link(gossip_lib::names::best_name_from_pubkey_lookup(&merchant))
+                                .clicked()
+                            {
+                                app.set_page(ctx, Page::Person(merchant));
+                            }
+                        }
+                        if let Some(stall) = &stall {
+                            ui.label(RichText::new(format!("at {}", stall.name)).weak());
+                        }
+                    });
+                    if GLOBALS.identity.is_unlocked()
+                        && ui
+                            .add_enabled(product.in_stock(), egui::Button::new("Buy"))
+                            .clicked()
+                    {
+                        app.marketplace_checkout = Some(Checkout::new(product.clone(), stall));
+                    }
+                });
+            });
+            ui.add_space(8.0);
+            ui.separator();
+        }
+    });
+
+    if let Some(mut checkout) = app.marketplace_checkout.take() {
+        if render_checkout(app, ui, &mut checkout) {
+            app.marketplace_checkout = Some(checkout);
+        }
+    }
+}
+
+fn render_thumbnail(app: &mut GossipUi, ui: &mut Ui, product: &Product) {
+    let size = vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let media = if read_setting!(load_media) {
+        product
+            .images
+            .first()
+            .and_then(|image| app.try_check_url(image))
+            .and_then(|url| app.try_get_media(ui.ctx(), url))
+    } else {
+        None
+    };
+    match media {
+        Some(media) => {
+            ui.add(
+                Image::new(&media)
+                    .max_size(size)
+                    .maintain_aspect_ratio(true),
+            );
+        }
+        None => {
+            ui.allocate_space(size);
+        }
+    }
+}
+
+// Returns whether the popup stays open
+fn render_checkout(app: &mut GossipUi, ui: &mut Ui, checkout: &mut Checkout) -> bool {
+    const DLG_SIZE: Vec2 = vec2(450.0, 420.0);
+    let mut open = true;
+    let ret = widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading(format!("Order {}", checkout.product.name));
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Quantity:");
+            let max = checkout.product.quantity.unwrap_or(100).max(1);
+            ui.add(egui::Slider::new(&mut checkout.quantity, 1..=max));
+            ui.label(format!(
+                "{} {}",
+                checkout.product.price * checkout.quantity as f64,
+                checkout.product.currency
+            ));
+        });
+
+        if let Some(stall) = &checkout.stall {
+            if !stall.shipping.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("Shipping:");
+                    for (index, shipping) in stall.shipping.iter().enumerate() {
+                        let label = format!(
+                            "{} (+{} {})",
+                            shipping.name.as_deref().unwrap_or(&shipping.id),
+                            shipping.cost,
+                            stall.currency
+                        );
+                        ui.radio_value(&mut checkout.shipping, index, label);
+                    }
+                });
+            }
+        }
+
+        ui.add_space(8.0);
+        for (label, value) in [
+            ("Name:", &mut checkout.name),
+            ("Address:", &mut checkout.address),
+            ("Email:", &mut checkout.email),
+            ("Message:", &mut checkout.message),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.add(text_edit_line!(app, *value).desired_width(300.0));
+            });
+        }
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new(
+                "The order is sent to the merchant in an encrypted direct message. They will answer there with a payment request.",
+            )
+            .weak(),
+        );
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
+            app.theme.accent_button_1_style(ui.style_mut());
+            if ui.button("Send order").clicked() {
+                match (checkout.product.merchant, checkout.order().to_message()) {
+                    (Some(merchant), Ok(content)) => {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
+                            content,
+                            tags: vec![],
+                            in_reply_to: None,
+                            dm_channel: Some(DmChannel::new(&[merchant])),
+                        });
+                        open = false;
+                    }
+                    (_, Err(e)) => GLOBALS.status_queue.write().write_error(e.to_string()),
+                    (None, _) => (),
+                }
+            }
+        });
+    });
+    if ret.inner.clicked() {
+        open = false;
+    }
+    open
+}
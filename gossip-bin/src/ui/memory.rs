@@ -0,0 +1,87 @@
+use super::GossipUi;
+use eframe::egui;
+use gossip_lib::GLOBALS;
+use std::time::{Duration, Instant};
+
+/// How often memory usage is measured against the budget
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Approximate memory held in caches, per subsystem, in bytes
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct MemoryUsage {
+    /// Processed events (notes) ready for rendering
+    pub events: usize,
+
+    /// Decoded images, in the UI and waiting to be handed to it
+    pub media: usize,
+
+    /// Decoded avatars, in the UI and waiting to be handed to it. Person records
+    /// are read from storage when needed, not cached, so they take no memory here.
+    pub avatars: usize,
+}
+
+impl MemoryUsage {
+    pub fn measure(app: &GossipUi) -> MemoryUsage {
+        let texture_bytes = |th: &egui::TextureHandle| {
+            let [w, h] = th.size();
+            w * h * 4
+        };
+
+        MemoryUsage {
+            events: app.notes.memory_usage(),
+            media: app.images.values().map(texture_bytes).sum::<usize>()
+                + GLOBALS.media.memory_usage(),
+            avatars: app.avatars.values().map(texture_bytes).sum::<usize>()
+                + GLOBALS.people.memory_usage(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.events + self.media + self.avatars
+    }
+}
+
+/// Measure memory usage every so often, and if it is over the budget, trim caches
+/// until it isn't. Media goes first since it is the largest and is cheap to decode
+/// again from the file cache, then notes that are not on screen, then avatars.
+/// Whatever was used since the last check is kept.
+pub(super) fn enforce_budget(app: &mut GossipUi) {
+    if Instant::now() < app.memory_next_check {
+        return;
+    }
+    app.memory_next_check = Instant::now() + CHECK_INTERVAL;
+
+    let budget = read_setting!(memory_budget_mb) as usize * 1024 * 1024;
+    app.memory_usage = MemoryUsage::measure(app);
+
+    if budget > 0 && app.memory_usage.total() > budget {
+        let before = app.memory_usage.total();
+
+        GLOBALS.media.trim_memory();
+        let used = std::mem::take(&mut app.images_used);
+        app.images.retain(|url, _| used.contains(url));
+        app.memory_usage = MemoryUsage::measure(app);
+
+        if app.memory_usage.total() > budget {
+            let visible = app.visible_note_ids.clone();
+            app.notes.cache_retain(&visible);
+            app.memory_usage = MemoryUsage::measure(app);
+        }
+
+        if app.memory_usage.total() > budget {
+            GLOBALS.people.trim_memory();
+            let used = std::mem::take(&mut app.avatars_used);
+            app.avatars.retain(|pubkey, _| used.contains(pubkey));
+            app.memory_usage = MemoryUsage::measure(app);
+        }
+
+        tracing::debug!(
+            "Memory over budget: trimmed caches from {} to {} bytes",
+            before,
+            app.memory_usage.total()
+        );
+    }
+
+    app.images_used.clear();
+    app.avatars_used.clear();
+}
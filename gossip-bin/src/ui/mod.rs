@@ -33,16 +33,22 @@ macro_rules! write_setting {
     };
 }
 
+mod articles;
+mod calendar;
 mod components;
 mod dm_chat_list;
 mod feed;
 mod help;
+mod marketplace;
+mod memory;
 mod people;
+mod read_later;
 mod relays;
 mod search;
 mod settings;
 mod theme;
 mod widgets;
+mod wiki;
 mod wizard;
 mod you;
 
@@ -61,12 +67,21 @@ use egui_winit::egui::Rect;
 use egui_winit::egui::Response;
 use egui_winit::egui::ViewportBuilder;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::follow_back::FollowBack;
+use gossip_lib::nip13;
+use gossip_lib::nip38::UserStatus;
 use gossip_lib::nip46::Approval;
+use gossip_lib::read_later::ReadLaterItem;
+use gossip_lib::search::SearchScope;
 use gossip_lib::{
-    About, DmChannel, DmChannelData, Error, FeedKind, Person, PersonList, ZapState, GLOBALS,
+    About, DmChannel, DmChannelData, Error, FeedKind, IssuedDelegation, Person, PersonList,
+    StatusSeverity, ZapState, GLOBALS,
 };
 use nostr_types::ContentSegment;
-use nostr_types::{Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl, Url};
+use nostr_types::{
+    Event, EventAddr, Id, Metadata, MilliSatoshi, NostrBech32, PublicKey, RelayUrl, Tag,
+    UncheckedUrl, Url,
+};
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 #[cfg(feature = "video-ffmpeg")]
@@ -93,6 +108,10 @@ pub fn run() -> Result<(), Error> {
     let viewport = ViewportBuilder {
         #[cfg(target_os = "linux")]
         app_id: Some("gossip".to_string()),
+        title: Some(match gossip_lib::Profile::current()?.name {
+            Some(name) => format!("gossip ({})", name),
+            None => "gossip".to_string(),
+        }),
         inner_size: Some(egui::vec2(700.0, 900.0)),
         min_inner_size: Some(egui::vec2(800.0, 600.0)),
         resizable: Some(true),
@@ -143,14 +162,22 @@ enum Page {
     YourMetadata,
     YourDelegation,
     YourNostrConnect,
+    YourBadges,
     RelaysActivityMonitor,
     RelaysCoverage,
     RelaysMine,
     RelaysKnownNetwork,
+    Articles,
+    Article(Id),
+    Marketplace,
+    Calendar,
+    ReadLater,
     Search,
     Settings,
+    Wiki(String),
     HelpHelp,
     HelpStats,
+    HelpStatusHistory,
     HelpAbout,
     HelpTheme,
     Wizard(WizardPage),
@@ -189,14 +216,22 @@ impl Page {
             Page::YourMetadata => (SubMenu::Account.as_str(), "Profile".into()),
             Page::YourDelegation => (SubMenu::Account.as_str(), "Delegation".into()),
             Page::YourNostrConnect => (SubMenu::Account.as_str(), "Nostr Connect".into()),
+            Page::YourBadges => (SubMenu::Account.as_str(), "Badges".into()),
             Page::RelaysActivityMonitor => (SubMenu::Relays.as_str(), "Active Relays".into()),
             Page::RelaysCoverage => (SubMenu::Relays.as_str(), "Coverage Report".into()),
             Page::RelaysMine => (SubMenu::Relays.as_str(), "My Relays".into()),
             Page::RelaysKnownNetwork => (SubMenu::Relays.as_str(), "Known Network".into()),
+            Page::Articles => ("Articles", "Articles".into()),
+            Page::Article(_) => ("Articles", "Article".into()),
+            Page::Marketplace => ("Marketplace", "Marketplace".into()),
+            Page::Calendar => ("Calendar", "Calendar".into()),
+            Page::ReadLater => ("Read Later", "Read Later".into()),
             Page::Search => ("Search", "Search".into()),
             Page::Settings => ("Settings", "Settings".into()),
+            Page::Wiki(d) => ("Wiki", d.clone()),
             Page::HelpHelp => (SubMenu::Help.as_str(), "Troubleshooting".into()),
             Page::HelpStats => (SubMenu::Help.as_str(), "Stats".into()),
+            Page::HelpStatusHistory => (SubMenu::Help.as_str(), "Status History".into()),
             Page::HelpAbout => (SubMenu::Help.as_str(), "About".into()),
             Page::HelpTheme => (SubMenu::Help.as_str(), "Theme Test".into()),
             Page::Wizard(wp) => ("Wizard", wp.as_str().to_string()),
@@ -228,10 +263,14 @@ impl Page {
             Page::Feed(_) => name_cat(self),
             Page::PeopleLists | Page::PeopleList(_) => cat_name(self),
             Page::Person(_) => name_cat(self),
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
-                cat_name(self)
-            }
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourBadges => cat_name(self),
             Page::Wizard(_) => name_cat(self),
+            Page::Wiki(_) => cat_name(self),
+            Page::Article(_) => name_cat(self),
             _ => name(self),
         }
     }
@@ -274,6 +313,7 @@ impl std::fmt::Display for SubMenu {
 
 #[derive(Eq, Hash, PartialEq)]
 enum SettingsTab {
+    Alerts,
     Content,
     Database,
     Id,
@@ -311,6 +351,19 @@ pub struct DraftData {
     pub include_content_warning: bool,
     pub content_warning: String,
 
+    // Seconds after posting that the note expires (NIP-40), if it does
+    pub expires_in: Option<i64>,
+
+    // Mark the note as protected (NIP-70)
+    pub protected: bool,
+
+    // Attaching a file: whether we are, and the path typed in
+    pub attaching: bool,
+    pub attach_path: String,
+
+    // Files uploaded for the draft, with their imeta tags
+    pub attachments: Vec<(String, Tag)>,
+
     // Data for normal draft
     pub repost: Option<Id>,
     pub replying_to: Option<Id>,
@@ -335,6 +388,11 @@ impl Default for DraftData {
             subject: "".to_owned(),
             include_content_warning: false,
             content_warning: "".to_owned(),
+            expires_in: None,
+            protected: false,
+            attaching: false,
+            attach_path: "".to_owned(),
+            attachments: Vec::new(),
 
             // The following are ignored for DMs
             repost: None,
@@ -360,6 +418,11 @@ impl DraftData {
         self.subject = "".to_owned();
         self.include_content_warning = false;
         self.content_warning = "".to_owned();
+        self.expires_in = None;
+        self.protected = false;
+        self.attaching = false;
+        self.attach_path = "".to_owned();
+        self.attachments.clear();
         self.repost = None;
         self.replying_to = None;
         self.tagging_search_substring = None;
@@ -401,6 +464,9 @@ struct GossipUi {
     // people::ListUi
     people_list: people::ListUi,
 
+    // calendar::CalendarUi
+    calendar: calendar::CalendarUi,
+
     // Post rendering
     render_raw: Option<Id>,
     render_qr: Option<Id>,
@@ -418,6 +484,13 @@ struct GossipUi {
     inbox_include_indirect: bool,
     submenu_ids: HashMap<SubMenu, egui::Id>,
     settings_tab: SettingsTab,
+    alert_rule_edits: Option<Vec<settings::RuleEdit>>,
+    marketplace_checkout: Option<marketplace::Checkout>,
+    wiki_topic: String,
+    wiki_author: Option<PublicKey>,
+    wiki_edit: Option<wiki::WikiEdit>,
+    articles_mine: bool,
+    article_edit: Option<articles::ArticleEdit>,
 
     // General Data
     about: About,
@@ -449,6 +522,31 @@ struct GossipUi {
     // User entry: delegatee tag (as JSON string)
     delegatee_tag_str: String,
 
+    // User entry: a delegation to issue to another key
+    delegation_delegatee: String,
+    delegation_kind: String,
+    delegation_days: String,
+
+    // The delegations we issued, loaded with the delegation page
+    issued_delegations: Result<Vec<IssuedDelegation>, String>,
+
+    // nostr: link to an address we are still fetching
+    nostr_link_addr: Option<EventAddr>,
+
+    // User entry: profile badges (accepted and ordered)
+    profile_badges: Vec<(EventAddr, Id)>,
+
+    // New followers waiting to be followed back, loaded with the lists page
+    follow_backs: Vec<FollowBack>,
+
+    // The read later list and the events in it, loaded with its page
+    read_later_items: Vec<(ReadLaterItem, Option<Event>)>,
+
+    // User entry: user status
+    status_content: String,
+    status_link: String,
+    status_expires_in: Option<i64>,
+
     // User entry: general
     add_contact: String,
     add_relay: String, // dep
@@ -459,7 +557,16 @@ struct GossipUi {
     new_metadata_fieldname: String,
     import_priv: String,
     import_pub: String,
+    bunker_uri: String,
+    wallet_uri: String,
+    other_identity: String,
+    other_identities: Vec<PublicKey>,
+    new_profile: String,
+    label_text: String,
     search: String,
+    search_scope: SearchScope,
+    status_history_severity: StatusSeverity,
+    status_history_filter: String,
     entering_search_page: bool,
     editing_petname: bool,
     petname: String,
@@ -480,6 +587,9 @@ struct GossipUi {
     // Fully opened posts
     opened: HashSet<Id>,
 
+    // Event kinds we have asked relays for application handlers for
+    handler_kinds_fetched: HashSet<u32>,
+
     // Visisble Note IDs
     // (we resubscribe to reactions/zaps/deletes when this changes)
     visible_note_ids: Vec<Id>,
@@ -499,6 +609,17 @@ struct GossipUi {
     dm_channel_cache: Vec<DmChannelData>,
     dm_channel_next_refresh: Instant,
     dm_channel_error: Option<String>,
+
+    // Cached statuses of the people in the list feed being shown
+    list_statuses_of: Option<PersonList>,
+    list_statuses: Vec<(PublicKey, UserStatus)>,
+    list_statuses_next_refresh: Instant,
+
+    // Memory budget: usage as last measured, and what was shown since
+    memory_usage: memory::MemoryUsage,
+    memory_next_check: Instant,
+    images_used: HashSet<Url>,
+    avatars_used: HashSet<PublicKey>,
 }
 
 impl Drop for GossipUi {
@@ -638,6 +759,7 @@ impl GossipUi {
             notes: Notes::new(),
             relays: relays::RelayUi::new(),
             people_list: people::ListUi::new(),
+            calendar: calendar::CalendarUi::new(),
             render_raw: None,
             render_qr: None,
             approved: HashSet::new(),
@@ -656,6 +778,13 @@ impl GossipUi {
                 .unwrap_or(false),
             submenu_ids,
             settings_tab: SettingsTab::Id,
+            alert_rule_edits: None,
+            marketplace_checkout: None,
+            wiki_topic: String::new(),
+            wiki_author: None,
+            wiki_edit: None,
+            articles_mine: false,
+            article_edit: None,
             about: About::new(),
             icon: icon_texture_handle,
             placeholder_avatar: placeholder_avatar_texture_handle,
@@ -675,6 +804,17 @@ impl GossipUi {
             editing_metadata: false,
             metadata: Metadata::new(),
             delegatee_tag_str: "".to_owned(),
+            delegation_delegatee: "".to_owned(),
+            delegation_kind: "1".to_owned(),
+            delegation_days: "30".to_owned(),
+            issued_delegations: Ok(Vec::new()),
+            nostr_link_addr: None,
+            profile_badges: Vec::new(),
+            follow_backs: Vec::new(),
+            read_later_items: Vec::new(),
+            status_content: "".to_owned(),
+            status_link: "".to_owned(),
+            status_expires_in: None,
             add_contact: "".to_owned(),
             add_relay: "".to_owned(),
             password: "".to_owned(),
@@ -684,7 +824,16 @@ impl GossipUi {
             new_metadata_fieldname: String::new(),
             import_priv: "".to_owned(),
             import_pub: "".to_owned(),
+            bunker_uri: "".to_owned(),
+            wallet_uri: "".to_owned(),
+            other_identity: "".to_owned(),
+            other_identities: gossip_lib::identities::others(),
+            new_profile: "".to_owned(),
+            label_text: "".to_owned(),
             search: "".to_owned(),
+            search_scope: SearchScope::Everywhere,
+            status_history_severity: StatusSeverity::Info,
+            status_history_filter: String::new(),
             entering_search_page: false,
             editing_petname: false,
             petname: "".to_owned(),
@@ -700,6 +849,7 @@ impl GossipUi {
             nostr_connect_relay2: "".to_owned(),
             collapsed: vec![],
             opened: HashSet::new(),
+            handler_kinds_fetched: HashSet::new(),
             visible_note_ids: vec![],
             next_visible_note_ids: vec![],
             last_visible_update: Instant::now(),
@@ -709,6 +859,13 @@ impl GossipUi {
             dm_channel_cache: vec![],
             dm_channel_next_refresh: Instant::now(),
             dm_channel_error: None,
+            list_statuses_of: None,
+            list_statuses: Vec::new(),
+            list_statuses_next_refresh: Instant::now(),
+            memory_usage: Default::default(),
+            memory_next_check: Instant::now(),
+            images_used: HashSet::new(),
+            avatars_used: HashSet::new(),
         }
     }
 
@@ -819,6 +976,13 @@ impl GossipUi {
                 GLOBALS.feed.set_feed_to_inbox(*indirect);
                 self.close_all_menus_except_feeds(ctx);
             }
+            Page::Feed(FeedKind::CombinedInbox) => {
+                GLOBALS.feed.set_feed_to_combined_inbox();
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchIdentityMentions);
+                self.close_all_menus_except_feeds(ctx);
+            }
             Page::Feed(FeedKind::Thread {
                 id,
                 referenced_by,
@@ -833,7 +997,12 @@ impl GossipUi {
                 GLOBALS.feed.set_feed_to_person(pubkey.to_owned());
                 self.close_all_menus_except_feeds(ctx);
             }
+            Page::Feed(FeedKind::Community(addr)) => {
+                GLOBALS.feed.set_feed_to_community(addr.to_owned());
+                self.close_all_menus_except_feeds(ctx);
+            }
             Page::PeopleLists => {
+                self.follow_backs = gossip_lib::follow_back::queue();
                 people::enter_page(self);
                 self.close_all_menus_except_feeds(ctx);
             }
@@ -845,7 +1014,25 @@ impl GossipUi {
                     .to_overlord
                     .send(ToOverlordMessage::UpdateMetadata(*pubkey));
             }
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
+            Page::YourBadges => {
+                self.profile_badges = GLOBALS
+                    .identity
+                    .public_key()
+                    .map(|pk| {
+                        gossip_lib::badges::get_profile_badges(pk)
+                            .unwrap_or_default()
+                            .drain(..)
+                            .map(|pb| (pb.addr, pb.award))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                self.open_menu(ctx, SubMenu::Account);
+            }
+            Page::YourDelegation => {
+                you::load_issued_delegations(self);
+                self.open_menu(ctx, SubMenu::Account);
+            }
+            Page::YourKeys | Page::YourMetadata | Page::YourNostrConnect => {
                 self.open_menu(ctx, SubMenu::Account);
             }
             Page::RelaysActivityMonitor
@@ -855,6 +1042,32 @@ impl GossipUi {
                 self.relays.enter_page();
                 self.open_menu(ctx, SubMenu::Relays);
             }
+            Page::Articles => {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchLongFormArticles);
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Article(_) => {
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Marketplace => {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchMarketplace);
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::Calendar => {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchCalendarEvents);
+                calendar::enter_page(self);
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::ReadLater => {
+                read_later::enter_page(self);
+                self.close_all_menus_except_feeds(ctx);
+            }
             Page::Search => {
                 self.entering_search_page = true;
                 self.close_all_menus_except_feeds(ctx);
@@ -862,7 +1075,18 @@ impl GossipUi {
             Page::Settings => {
                 self.close_all_menus_except_feeds(ctx);
             }
-            Page::HelpHelp | Page::HelpStats | Page::HelpAbout | Page::HelpTheme => {
+            Page::Wiki(d) => {
+                self.wiki_topic = d.clone();
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchWikiArticles(d.clone()));
+                self.close_all_menus_except_feeds(ctx);
+            }
+            Page::HelpHelp
+            | Page::HelpStats
+            | Page::HelpStatusHistory
+            | Page::HelpAbout
+            | Page::HelpTheme => {
                 self.open_menu(ctx, SubMenu::Help);
             }
             _ => {
@@ -1004,6 +1228,19 @@ impl GossipUi {
                     }
                 }
 
+                if !self.other_identities.is_empty() {
+                    if self
+                        .add_selected_label(
+                            ui,
+                            self.page == Page::Feed(FeedKind::CombinedInbox),
+                            "All inboxes",
+                        )
+                        .clicked()
+                    {
+                        self.set_page(ctx, Page::Feed(FeedKind::CombinedInbox));
+                    }
+                }
+
                 // Private chats
                 if GLOBALS.identity.is_unlocked() {
                     if self
@@ -1014,14 +1251,60 @@ impl GossipUi {
                     }
                 }
 
+                // Read later
+                if self
+                    .add_selected_label(ui, self.page == Page::ReadLater, "Read Later")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::ReadLater);
+                }
+
                 // Search
                 if self
                     .add_selected_label(ui, self.page == Page::Search, "Search")
                     .clicked()
                 {
+                    self.search_scope = SearchScope::Everywhere;
                     self.set_page(ctx, Page::Search);
                 }
 
+                // Articles
+                if self
+                    .add_selected_label(
+                        ui,
+                        matches!(self.page, Page::Articles | Page::Article(_)),
+                        "Articles",
+                    )
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Articles);
+                }
+
+                // Marketplace
+                if self
+                    .add_selected_label(ui, self.page == Page::Marketplace, "Marketplace")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Marketplace);
+                }
+
+                // Calendar
+                if self
+                    .add_selected_label(ui, self.page == Page::Calendar, "Calendar")
+                    .clicked()
+                {
+                    self.set_page(ctx, Page::Calendar);
+                }
+
+                // Wiki
+                if self
+                    .add_selected_label(ui, matches!(self.page, Page::Wiki(_)), "Wiki")
+                    .clicked()
+                {
+                    let topic = self.wiki_topic.clone();
+                    self.set_page(ctx, Page::Wiki(topic));
+                }
+
                 ui.add_space(10.0);
 
                 // ---- People Lists ----
@@ -1072,6 +1355,7 @@ impl GossipUi {
                         self.add_menu_item_page(ui, Page::YourKeys, None, true);
                         self.add_menu_item_page(ui, Page::YourDelegation, None, true);
                         self.add_menu_item_page(ui, Page::YourNostrConnect, None, true);
+                        self.add_menu_item_page(ui, Page::YourBadges, None, true);
                     });
                     self.after_openable_menu(ui, &cstate);
                 }
@@ -1091,6 +1375,7 @@ impl GossipUi {
                     cstate.show_body_indented(&header_response, ui, |ui| {
                         self.add_menu_item_page(ui, Page::HelpHelp, None, true);
                         self.add_menu_item_page(ui, Page::HelpStats, None, true);
+                        self.add_menu_item_page(ui, Page::HelpStatusHistory, None, true);
                         self.add_menu_item_page(ui, Page::HelpAbout, None, true);
                         self.add_menu_item_page(ui, Page::HelpTheme, None, true);
                     });
@@ -1218,6 +1503,12 @@ impl eframe::App for GossipUi {
             return;
         }
 
+        memory::enforce_budget(self);
+
+        self.open_nostr_links(ctx);
+
+        crate::notify::deliver_alerts();
+
         // How much scrolling has been requested by inputs during this frame?
         let compose_area_is_focused =
             ctx.memory(|mem| mem.has_focus(egui::Id::new("compose_area")));
@@ -1391,6 +1682,7 @@ impl eframe::App for GossipUi {
             ZapState::SeekingAmount(id, _, _, _) => Some(id),
             ZapState::LoadingInvoice(id, _) => Some(id),
             ZapState::ReadyToPay(id, _) => Some(id),
+            ZapState::Paying(id) => Some(id),
         };
 
         egui::CentralPanel::default()
@@ -1427,16 +1719,24 @@ impl eframe::App for GossipUi {
                     Page::YourKeys
                     | Page::YourMetadata
                     | Page::YourDelegation
-                    | Page::YourNostrConnect => you::update(self, ctx, frame, ui),
+                    | Page::YourNostrConnect
+                    | Page::YourBadges => you::update(self, ctx, frame, ui),
                     Page::RelaysActivityMonitor
                     | Page::RelaysCoverage
                     | Page::RelaysMine
                     | Page::RelaysKnownNetwork => relays::update(self, ctx, frame, ui),
+                    Page::Articles | Page::Article(_) => articles::update(self, ctx, frame, ui),
+                    Page::Marketplace => marketplace::update(self, ctx, frame, ui),
+                    Page::Calendar => calendar::update(self, ctx, frame, ui),
+                    Page::ReadLater => read_later::update(self, ctx, frame, ui),
                     Page::Search => search::update(self, ctx, frame, ui),
                     Page::Settings => settings::update(self, ctx, frame, ui),
-                    Page::HelpHelp | Page::HelpStats | Page::HelpAbout | Page::HelpTheme => {
-                        help::update(self, ctx, frame, ui)
-                    }
+                    Page::Wiki(_) => wiki::update(self, ctx, frame, ui),
+                    Page::HelpHelp
+                    | Page::HelpStats
+                    | Page::HelpStatusHistory
+                    | Page::HelpAbout
+                    | Page::HelpTheme => help::update(self, ctx, frame, ui),
                     Page::Wizard(_) => unreachable!(),
                 }
             });
@@ -1554,24 +1854,42 @@ impl GossipUi {
                     }
                 }
 
+                if !is_self && GLOBALS.identity.is_unlocked() {
+                    ui.menu_button("Report", |ui| {
+                        for report_type in gossip_lib::nip56::REPORT_TYPES {
+                            if ui.button(report_type).clicked() {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Report {
+                                    pubkey: person.pubkey,
+                                    id: None,
+                                    report_type: report_type.to_owned(),
+                                    comment: "".to_owned(),
+                                });
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+
+                if GLOBALS.identity.is_unlocked() {
+                    widgets::label_menu(ui, app, person.pubkey, None);
+                }
+
                 if ui.button("Update Metadata").clicked() {
                     let _ = GLOBALS
                         .to_overlord
                         .send(ToOverlordMessage::UpdateMetadata(person.pubkey));
                 }
 
+                if ui.button("Copy nprofile").clicked() {
+                    if let Ok(bech32) = gossip_lib::share::profile_bech32(person.pubkey) {
+                        ui.output_mut(|o| o.copied_text = format!("nostr:{}", bech32));
+                    }
+                }
+
                 if ui.button("Copy web link").clicked() {
-                    ui.output_mut(|o| {
-                        let mut profile = Profile {
-                            pubkey: person.pubkey,
-                            relays: Vec::new(),
-                        };
-                        let relays = GLOBALS.people.get_active_person_write_relays();
-                        for (relay_url, _) in relays.iter().take(3) {
-                            profile.relays.push(UncheckedUrl(format!("{}", relay_url)));
-                        }
-                        o.copied_text = format!("https://njump.me/{}", profile.as_bech32_string())
-                    });
+                    if let Ok(bech32) = gossip_lib::share::profile_bech32(person.pubkey) {
+                        ui.output_mut(|o| o.copied_text = gossip_lib::share::web_link(&bech32));
+                    }
                 }
             });
 
@@ -1585,6 +1903,31 @@ impl GossipUi {
                     .on_hover_text("followed");
             }
 
+            if profile_page {
+                if let Ok(reports) = gossip_lib::nip56::reports_of_person(person.pubkey) {
+                    if !reports.is_empty() {
+                        let mut types: Vec<&str> = reports.values().map(|t| t.as_str()).collect();
+                        types.sort();
+                        types.dedup();
+                        ui.label(
+                            RichText::new(format!("⚠ reported by {}", reports.len()))
+                                .color(app.theme.warning_marker_text_color())
+                                .small(),
+                        )
+                        .on_hover_text(format!(
+                            "Reported by people you follow for: {}",
+                            types.join(", ")
+                        ));
+                    }
+                }
+                if let Ok(labels) = gossip_lib::nip32::labels_of_person(person.pubkey) {
+                    for label in labels {
+                        ui.label(RichText::new(format!("🏷 {}", label.value)).weak().small())
+                            .on_hover_text(format!("Label in namespace {}", label.namespace));
+                    }
+                }
+            }
+
             if !profile_page {
                 if let Some(mut nip05) = person.nip05().map(|s| s.to_owned()) {
                     if nip05.starts_with("_@") {
@@ -1615,6 +1958,9 @@ impl GossipUi {
         }
 
         if let Some(th) = self.avatars.get(pubkey) {
+            if !self.avatars_used.contains(pubkey) {
+                self.avatars_used.insert(pubkey.to_owned());
+            }
             return Some(th.to_owned());
         }
 
@@ -1631,6 +1977,7 @@ impl GossipUi {
                 color_image,
                 TextureOptions::default(),
             );
+            self.avatars_used.insert(pubkey.to_owned());
             self.avatars
                 .insert(pubkey.to_owned(), texture_handle.clone());
             Some(texture_handle)
@@ -1661,7 +2008,11 @@ impl GossipUi {
 
         // see if we already have a texturehandle for this media
         if let Some(th) = self.images.get(&url) {
-            return Some(th.to_owned());
+            let th = th.to_owned();
+            if !self.images_used.contains(&url) {
+                self.images_used.insert(url);
+            }
+            return Some(th);
         }
 
         if let Some(rgba_image) = GLOBALS.media.get_image(&url) {
@@ -1673,6 +2024,7 @@ impl GossipUi {
                 color_image,
                 TextureOptions::default(),
             );
+            self.images_used.insert(url.clone());
             self.images.insert(url, texture_handle.clone());
             Some(texture_handle)
         } else {
@@ -1923,6 +2275,100 @@ impl GossipUi {
         }
     }
 
+    // Navigate to nostr: links handed to us by the OS
+    fn open_nostr_links(&mut self, ctx: &Context) {
+        // Don't interrupt the setup wizard
+        if matches!(self.page, Page::Wizard(_)) {
+            return;
+        }
+
+        for bech32 in gossip_lib::deep_link::take_pending() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            match bech32 {
+                NostrBech32::EventPointer(ep) => {
+                    let relays: Vec<RelayUrl> = ep
+                        .relays
+                        .iter()
+                        .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+                        .collect();
+                    if !relays.is_empty() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::FetchEvent(ep.id, relays));
+                    }
+                    self.set_page(
+                        ctx,
+                        Page::Feed(FeedKind::Thread {
+                            id: ep.id,
+                            referenced_by: ep.id,
+                            author: ep.author,
+                        }),
+                    );
+                }
+                NostrBech32::Id(id) => {
+                    self.set_page(
+                        ctx,
+                        Page::Feed(FeedKind::Thread {
+                            id,
+                            referenced_by: id,
+                            author: None,
+                        }),
+                    );
+                }
+                NostrBech32::EventAddr(ea) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FetchEventAddr(ea.clone()));
+                    self.nostr_link_addr = Some(ea);
+                }
+                NostrBech32::Profile(profile) => {
+                    for relay in &profile.relays {
+                        if let Ok(url) = RelayUrl::try_from_unchecked_url(relay) {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::AddPubkeyRelay(profile.pubkey, url));
+                        }
+                    }
+                    self.set_page(ctx, Page::Person(profile.pubkey));
+                }
+                NostrBech32::Pubkey(pubkey) => {
+                    self.set_page(ctx, Page::Person(pubkey));
+                }
+                NostrBech32::Relay(url) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Opened a link to relay {}", url.as_str()));
+                }
+            }
+        }
+
+        // Once the addressed event arrives, show it
+        if let Some(ea) = &self.nostr_link_addr {
+            match GLOBALS
+                .storage
+                .get_replaceable_event(ea.kind, ea.author, &ea.d)
+            {
+                Ok(Some(event)) => {
+                    self.nostr_link_addr = None;
+                    self.set_page(
+                        ctx,
+                        Page::Feed(FeedKind::Thread {
+                            id: event.id,
+                            referenced_by: event.id,
+                            author: Some(event.pubkey),
+                        }),
+                    );
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    self.nostr_link_addr = None;
+                    GLOBALS.status_queue.write().write_error(format!("{}", e));
+                }
+            }
+        }
+    }
+
     // Zap In Progress Area
     fn render_zap_area(&mut self, ui: &mut Ui, ctx: &Context) {
         let mut qr_string: Option<String> = None;
@@ -1993,6 +2439,12 @@ impl GossipUi {
                 // we have to copy it and get out of the borrow first
                 qr_string = Some(invoice.to_owned());
             }
+            ZapState::Paying(_id) => {
+                ui.label("Paying through your wallet...");
+                if ui.button("Close").clicked() {
+                    *GLOBALS.current_zap.write() = ZapState::None;
+                }
+            }
         };
 
         if let Some(qr) = qr_string {
@@ -2028,6 +2480,24 @@ impl GossipUi {
     }
 
     fn render_status_queue_area(&self, ui: &mut Ui) {
+        if let Some(progress) = nip13::progress() {
+            // About 2^target hashes are needed on average
+            let expected = 2.0_f32.powi(progress.target as i32);
+            ui.add(
+                egui::ProgressBar::new((progress.hashes as f32 / expected).min(0.99))
+                    .text(format!("PoW: {}/{} bits", progress.best, progress.target)),
+            )
+            .on_hover_text(format!(
+                "{} hashes tried since {}",
+                progress.hashes,
+                crate::timestamp::display(progress.started)
+            ));
+            if ui.button("Cancel").clicked() {
+                nip13::cancel();
+            }
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+
         let messages = GLOBALS.status_queue.read().read_all();
         if ui
             .add(Label::new(RichText::new(&messages[0])).sense(Sense::click()))
@@ -2314,7 +2784,16 @@ fn approval_dialog_inner(_app: &mut GossipUi, ui: &mut Ui) {
     // NIP-46 approvals
     for (pubkey, parsed_command) in GLOBALS.nip46_approval_requests.read().iter() {
         ui.horizontal(|ui| {
-            let text = format!("Allow {}", parsed_command.method);
+            let app_name = match GLOBALS.storage.read_nip46server(*pubkey) {
+                Ok(Some(server)) => server.metadata.map(|m| m.name),
+                _ => None,
+            }
+            .unwrap_or_else(|| pubkey.as_bech32_string());
+            let text = format!(
+                "Nostr Connect: allow {} to {}?",
+                app_name,
+                parsed_command.describe()
+            );
             ui.label(text);
             if ui.button("Approve Once").clicked() {
                 let _ = GLOBALS
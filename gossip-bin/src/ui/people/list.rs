@@ -9,6 +9,7 @@ use egui_winit::egui::text::LayoutJob;
 use egui_winit::egui::text_edit::TextEditOutput;
 use egui_winit::egui::vec2;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::follow_import;
 use gossip_lib::{FeedKind, Person, PersonList, PersonListMetadata, GLOBALS};
 use nostr_types::{Profile, PublicKey, Unixtime};
 
@@ -28,6 +29,10 @@ pub(in crate::ui) struct ListUi {
     add_contact_search_results: Vec<(String, PublicKey)>,
     add_contact_search_selected: Option<usize>,
 
+    // import follows
+    importing_follows: bool,
+    import_follows_path: String,
+
     entering_follow_someone_on_list: bool,
     clear_list_needs_confirm: bool,
 }
@@ -50,6 +55,10 @@ impl ListUi {
             add_contact_search_results: Vec::new(),
             add_contact_search_selected: None,
 
+            // import follows
+            importing_follows: false,
+            import_follows_path: String::new(),
+
             entering_follow_someone_on_list: false,
             clear_list_needs_confirm: false,
         }
@@ -86,6 +95,8 @@ pub(super) fn update(
         render_clear_list_confirm_popup(ui, app, list);
     } else if app.people_list.entering_follow_someone_on_list {
         render_add_contact_popup(ui, app, list, &metadata);
+    } else if app.people_list.importing_follows {
+        render_import_follows_popup(ui, app, list, &metadata);
     } else if let Some(list) = app.deleting_list {
         super::list::render_delete_list_dialog(ui, app, list);
     } else if app.creating_list {
@@ -115,6 +126,16 @@ pub(super) fn update(
 
             btn_h_space!(ui);
 
+            if ui
+                .button("Import follows")
+                .on_hover_text("From a Mastodon following CSV or a list of Twitter handles")
+                .clicked()
+            {
+                app.people_list.importing_follows = true;
+            }
+
+            btn_h_space!(ui);
+
             if ui.button("View the Feed").clicked() {
                 app.set_page(
                     ctx,
@@ -499,6 +520,98 @@ fn render_add_contact_popup(
     }
 }
 
+fn render_import_follows_popup(
+    ui: &mut Ui,
+    app: &mut GossipUi,
+    list: PersonList,
+    metadata: &PersonListMetadata,
+) {
+    const DLG_SIZE: Vec2 = vec2(500.0, 400.0);
+    let ret = crate::ui::widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading("Import follows");
+        ui.add_space(8.0);
+
+        ui.label("Path to a Mastodon following CSV export, or to a list of Twitter @handles (one per line):");
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.add(
+                text_edit_line!(app, app.people_list.import_follows_path)
+                    .desired_width(380.0)
+                    .hint_text("following_accounts.csv"),
+            );
+            let resolving = follow_import::is_resolving();
+            if ui
+                .add_enabled(!resolving, egui::Button::new("Look up"))
+                .clicked()
+            {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ImportFollows(
+                    app.people_list.import_follows_path.clone(),
+                ));
+            }
+            if resolving {
+                ui.spinner();
+            }
+        });
+        ui.add_space(8.0);
+
+        let candidates = follow_import::candidates();
+        let count = candidates.iter().filter(|c| c.selected).count();
+
+        egui::ScrollArea::vertical()
+            .max_height(DLG_SIZE.y - 150.0)
+            .show(ui, |ui| {
+                for (index, candidate) in candidates.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let mut selected = candidate.selected;
+                        ui.add_enabled_ui(candidate.pubkey.is_some(), |ui| {
+                            if ui.checkbox(&mut selected, &candidate.handle).changed() {
+                                follow_import::set_selected(index, selected);
+                            }
+                        });
+                        match candidate.pubkey {
+                            Some(pubkey) => {
+                                ui.label(gossip_lib::names::best_name_from_pubkey_lookup(&pubkey));
+                                ui.label(RichText::new(&candidate.via).weak());
+                            }
+                            None => {
+                                ui.label(RichText::new(&candidate.via).weak());
+                            }
+                        }
+                    });
+                }
+            });
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+            ui.horizontal(|ui| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                    app.theme.accent_button_1_style(ui.style_mut());
+                    if ui
+                        .add_enabled(
+                            count > 0,
+                            egui::Button::new(format!("Follow selected ({})", count)),
+                        )
+                        .clicked()
+                    {
+                        match follow_import::follow_selected(list, !metadata.private) {
+                            Ok(n) => GLOBALS
+                                .status_queue
+                                .write()
+                                .write(format!("Followed {} imported accounts.", n)),
+                            Err(e) => GLOBALS.status_queue.write().write_error(e.to_string()),
+                        }
+                        app.people_list.importing_follows = false;
+                        app.people_list.import_follows_path.clear();
+                        mark_refresh(app);
+                    }
+                });
+            });
+        });
+    });
+    if ret.inner.clicked() {
+        app.people_list.importing_follows = false;
+    }
+}
+
 pub(super) fn render_delete_list_dialog(ui: &mut Ui, app: &mut GossipUi, list: PersonList) {
     let metadata = GLOBALS
         .storage
@@ -864,14 +977,7 @@ fn refresh_list_data(app: &mut GossipUi, list: PersonList) {
         .unwrap_or_default()
         .unwrap_or_default();
 
-    let mut asof = "time unknown".to_owned();
-    if let Ok(stamp) = time::OffsetDateTime::from_unix_timestamp(metadata.event_created_at.0) {
-        if let Ok(formatted) = stamp.format(time::macros::format_description!(
-            "[year]-[month repr:short]-[day] ([weekday repr:short]) [hour]:[minute]"
-        )) {
-            asof = formatted;
-        }
-    }
+    let asof = crate::timestamp::absolute(metadata.event_created_at);
 
     app.people_list.cache_remote_hash = gossip_lib::hash_person_list_event(list).unwrap_or(0);
 
@@ -889,16 +995,11 @@ fn refresh_list_data(app: &mut GossipUi, list: PersonList) {
         )
     };
 
-    let mut ledit = "time unknown".to_owned();
-    if metadata.last_edit_time.0 > 0 {
-        if let Ok(stamp) = time::OffsetDateTime::from_unix_timestamp(metadata.last_edit_time.0) {
-            if let Ok(formatted) = stamp.format(time::macros::format_description!(
-                "[year]-[month repr:short]-[day] ([weekday repr:short]) [hour]:[minute]"
-            )) {
-                ledit = formatted;
-            }
-        }
-    }
+    let ledit = if metadata.last_edit_time.0 > 0 {
+        crate::timestamp::absolute(metadata.last_edit_time)
+    } else {
+        "time unknown".to_owned()
+    };
 
     let publen = app
         .people_list
@@ -5,6 +5,7 @@ use crate::ui::widgets;
 use eframe::egui;
 use egui::{Context, Ui};
 use egui_winit::egui::{Label, RichText, Sense};
+use gossip_lib::follow_back;
 use gossip_lib::{PersonList, PersonListMetadata, GLOBALS};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -41,6 +42,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
 
     let color = app.theme.accent_color();
 
+    render_follow_backs(app, ctx, ui);
+
     app.vert_scroll_area()
         .id_source("people_lists_scroll")
         .enable_scrolling(enabled)
@@ -123,3 +126,65 @@ pub(in crate::ui) fn sort_lists(
             .then(a.1.title.to_lowercase().cmp(&b.1.title.to_lowercase()))
     }
 }
+
+/// New followers who matched the follow-back rules
+fn render_follow_backs(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    let queue = app.follow_backs.clone();
+    if queue.is_empty() {
+        return;
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("New followers");
+        ui.label(format!("({})", queue.len()));
+    });
+    ui.add_space(4.0);
+    for follow_back in queue.iter() {
+        ui.horizontal(|ui| {
+            if ui
+                .link(gossip_lib::names::best_name_from_pubkey_lookup(
+                    &follow_back.pubkey,
+                ))
+                .clicked()
+            {
+                app.set_page(ctx, Page::Person(follow_back.pubkey));
+            }
+
+            let mut details: Vec<String> = vec![format!(
+                "followed you {}",
+                crate::timestamp::display(follow_back.followed_at)
+            )];
+            match follow_back.distance {
+                Some(2) => details.push("followed by people you follow".to_owned()),
+                Some(d) => details.push(format!("{} steps away", d)),
+                None => (),
+            }
+            if follow_back.nip05_valid {
+                details.push("NIP-05 verified".to_owned());
+            }
+            if let Some(oldest) = follow_back.oldest_event {
+                details.push(format!("first seen {}", crate::timestamp::display(oldest)));
+            }
+            ui.label(RichText::new(details.join(", ")).weak());
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Dismiss").clicked() {
+                    if let Err(e) = follow_back::dismiss(follow_back.pubkey) {
+                        GLOBALS.status_queue.write().write_error(e.to_string());
+                    }
+                    app.follow_backs = follow_back::queue();
+                }
+                app.theme.accent_button_1_style(ui.style_mut());
+                if ui.button("Follow back").clicked() {
+                    if let Err(e) = follow_back::approve(follow_back.pubkey) {
+                        GLOBALS.status_queue.write().write_error(e.to_string());
+                    }
+                    app.follow_backs = follow_back::queue();
+                }
+            });
+        });
+    }
+    ui.add_space(10.0);
+    ui.separator();
+}
@@ -4,12 +4,15 @@ use crate::ui::widgets::list_entry;
 use crate::ui::widgets::CopyButton;
 use crate::AVATAR_SIZE_F32;
 use eframe::egui;
-use egui::{Context, RichText, TextEdit, Ui, Vec2};
+use egui::{Context, Image, RichText, TextEdit, Ui, Vec2};
 use egui_winit::egui::vec2;
 use egui_winit::egui::InnerResponse;
 use egui_winit::egui::Response;
 use egui_winit::egui::Widget;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip39::IdentityCheck;
+use gossip_lib::relay_scope;
+use gossip_lib::search::SearchScope;
 use gossip_lib::DmChannel;
 use gossip_lib::FeedKind;
 use gossip_lib::Person;
@@ -23,6 +26,7 @@ const AVATAR_COL_WIDTH: f32 = AVATAR_SIZE_F32 * 3.0;
 const AVATAR_COL_SPACE: f32 = 20.0;
 const AVATAR_COL_WIDTH_SPACE: f32 = AVATAR_COL_WIDTH + AVATAR_COL_SPACE * 2.0;
 const MIN_ITEM_WIDTH: f32 = 200.0;
+const BADGE_ICON_SIZE: f32 = 64.0;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     let (pubkey, person) = match &app.page {
@@ -131,7 +135,7 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                                         person.petname = Some(app.petname.clone());
                                     }
                                     if let Err(e) = GLOBALS.storage.write_person(&person, None) {
-                                        GLOBALS.status_queue.write().write(format!("{}", e));
+                                        GLOBALS.status_queue.write().write_error(format!("{}", e));
                                     }
                                     app.editing_petname = false;
                                     app.notes.cache_invalidate_person(&person.pubkey);
@@ -143,7 +147,7 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                                     let mut person = person.clone();
                                     person.petname = None;
                                     if let Err(e) = GLOBALS.storage.write_person(&person, None) {
-                                        GLOBALS.status_queue.write().write(format!("{}", e));
+                                        GLOBALS.status_queue.write().write_error(format!("{}", e));
                                     }
                                     app.editing_petname = false;
                                     app.notes.cache_invalidate_person(&person.pubkey);
@@ -161,7 +165,10 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                                         person.petname = None;
                                         if let Err(e) = GLOBALS.storage.write_person(&person, None)
                                         {
-                                            GLOBALS.status_queue.write().write(format!("{}", e));
+                                            GLOBALS
+                                                .status_queue
+                                                .write()
+                                                .write_error(format!("{}", e));
                                         }
                                         app.notes.cache_invalidate_person(&person.pubkey);
                                     }
@@ -187,6 +194,133 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                 }
             }
 
+            let profile_badges = gossip_lib::badges::get_profile_badges(pubkey).unwrap_or_default();
+            if !profile_badges.is_empty() {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.heading("Badges");
+                });
+                ui.separator();
+                ui.add_space(10.0);
+
+                make_frame().show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for badge in profile_badges.iter() {
+                            let name = match &badge.definition {
+                                Some(def) => def.name.clone(),
+                                None => badge.addr.d.clone(),
+                            };
+                            let mut hover = format!(
+                                "Awarded by {}",
+                                gossip_lib::names::best_name_from_pubkey_lookup(&badge.addr.author)
+                            );
+                            if let Some(description) = badge
+                                .definition
+                                .as_ref()
+                                .and_then(|d| d.description.as_ref())
+                            {
+                                hover = format!("{}\n\n{}", description, hover);
+                            }
+                            if !badge.verified {
+                                hover.push_str("\n\n(award not verified)");
+                            }
+                            let icon = if read_setting!(load_media) {
+                                badge
+                                    .definition
+                                    .as_ref()
+                                    .and_then(|d| d.icon())
+                                    .and_then(|url| app.try_check_url(url))
+                                    .and_then(|url| app.try_get_media(ui.ctx(), url))
+                            } else {
+                                None
+                            };
+                            ui.vertical(|ui| {
+                                if let Some(icon) = icon {
+                                    let size = vec2(BADGE_ICON_SIZE, BADGE_ICON_SIZE);
+                                    let tint = if badge.verified {
+                                        egui::Color32::WHITE
+                                    } else {
+                                        egui::Color32::from_white_alpha(96)
+                                    };
+                                    ui.add(
+                                        Image::new(&icon)
+                                            .max_size(size)
+                                            .maintain_aspect_ratio(true)
+                                            .tint(tint),
+                                    )
+                                    .on_hover_text(&hover);
+                                }
+                                let mut text = RichText::new(format!("🏅 {}", name));
+                                if !badge.verified {
+                                    text = text.weak();
+                                }
+                                ui.label(text).on_hover_text(hover);
+                            });
+                            ui.add_space(10.0);
+                        }
+                    });
+                });
+            }
+
+            let identities = gossip_lib::nip39::identities(pubkey).unwrap_or_default();
+            if !identities.is_empty() {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.heading("Identities");
+                });
+                ui.separator();
+                ui.add_space(10.0);
+
+                make_frame().show(ui, |ui| {
+                    for (claim, check) in identities.iter() {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(&claim.platform).weak());
+                            match claim.profile_url() {
+                                Some(url) => {
+                                    if ui.link(&claim.identity).on_hover_text(&url).clicked() {
+                                        open_url(ui, url);
+                                    }
+                                }
+                                None => {
+                                    ui.label(&claim.identity);
+                                }
+                            }
+                            let (text, hover) = match check {
+                                IdentityCheck::Verified => {
+                                    ("✔ verified", "The proof names their public key")
+                                }
+                                IdentityCheck::Failed => (
+                                    "✘ not verified",
+                                    "The proof could not be fetched, or does not name their public key",
+                                ),
+                                IdentityCheck::Pending => ("checking...", "Fetching the proof"),
+                                IdentityCheck::Unverifiable => (
+                                    "can't be checked",
+                                    "Look at the proof yourself to see whether it names their public key",
+                                ),
+                            };
+                            let text = match check {
+                                IdentityCheck::Verified => {
+                                    RichText::new(text).color(app.theme.accent_color())
+                                }
+                                IdentityCheck::Failed => {
+                                    RichText::new(text).color(app.theme.warning_marker_text_color())
+                                }
+                                _ => RichText::new(text).weak(),
+                            };
+                            ui.label(text).on_hover_text(hover);
+                            if let Some(url) = claim.proof_url() {
+                                if ui.link("proof").on_hover_text(&url).clicked() {
+                                    open_url(ui, url);
+                                }
+                            }
+                        });
+                    }
+                });
+            }
+
             ui.add_space(10.0);
             ui.horizontal(|ui| {
                 ui.add_space(10.0);
@@ -366,6 +500,59 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                         });
                     });
 
+                    // Option to follow them only on some relays
+                    let scope = relay_scope::scope(&pubkey).unwrap_or_default();
+                    make_frame().show(ui, |ui| {
+                        ui.vertical(|ui| {
+                            item_label(ui, "Follow only on");
+                            ui.add_space(ITEM_V_SPACE);
+                            let mut changed = false;
+                            ui.horizontal_wrapped(|ui| {
+                                if scope.is_empty() {
+                                    ui.label("All the relays they write to");
+                                }
+                                for url in scope.iter() {
+                                    if ui
+                                        .button(format!("{} \u{274C}", url.host()))
+                                        .on_hover_text("Stop following them only here")
+                                        .clicked()
+                                    {
+                                        if let Err(e) = relay_scope::remove_relay(pubkey, url) {
+                                            GLOBALS.status_queue.write().write_error(e.to_string());
+                                        }
+                                        changed = true;
+                                    }
+                                }
+                            });
+                            ui.horizontal_wrapped(|ui| {
+                                for (url, _) in relays.iter().filter(|f| f.1 > 5) {
+                                    if scope.contains(url) {
+                                        continue;
+                                    }
+                                    if ui
+                                        .button(format!("+ {}", url.host()))
+                                        .on_hover_text(
+                                            "Only get their notes from this relay (and others chosen here)",
+                                        )
+                                        .clicked()
+                                    {
+                                        if let Err(e) =
+                                            relay_scope::add_relay(pubkey, url.clone())
+                                        {
+                                            GLOBALS.status_queue.write().write_error(e.to_string());
+                                        }
+                                        changed = true;
+                                    }
+                                }
+                            });
+                            if changed {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::RefreshScoresAndPickRelays);
+                            }
+                        });
+                    });
+
                     ui.add_space(10.0);
                 }
             }
@@ -413,6 +600,21 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
 
                             ui.add_space(BTN_SPACING);
 
+                            if ui
+                                .add(
+                                    egui::Button::new("Search notes")
+                                        .min_size(MIN_SIZE)
+                                        .rounding(BTN_ROUNDING),
+                                )
+                                .clicked()
+                            {
+                                app.search_scope = SearchScope::Person(person.pubkey);
+                                app.search.clear();
+                                app.set_page(ctx, Page::Search);
+                            }
+
+                            ui.add_space(BTN_SPACING);
+
                             if !is_self {
                                 if ui
                                     .add(
@@ -548,6 +750,12 @@ fn profile_item_qr(
     }
 }
 
+fn open_url(ui: &mut Ui, url: String) {
+    ui.output_mut(|o| {
+        o.open_url = Some(egui::output::OpenUrl { url, new_tab: true });
+    });
+}
+
 fn make_frame() -> egui::Frame {
     egui::Frame::none()
         .inner_margin(egui::Margin {
@@ -0,0 +1,154 @@
+use super::{GossipUi, Page};
+use eframe::{egui, Frame};
+use egui::{Context, RichText, Ui};
+use gossip_lib::read_later::{self, ReadLaterItem};
+use gossip_lib::{FeedKind, GLOBALS};
+use nostr_types::Event;
+
+/// Load the list and the latest version of each item in it
+pub(super) fn enter_page(app: &mut GossipUi) {
+    app.read_later_items = read_later::items()
+        .drain(..)
+        .map(|item| {
+            let event = read_later::latest_version(item.id).ok().flatten();
+            (item, event)
+        })
+        .collect();
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    let items = app.read_later_items.clone();
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Read Later");
+        ui.add_space(10.0);
+        if items.iter().any(|(i, _)| i.done) && ui.button("Clear done").clicked() {
+            report(app, read_later::clear_done());
+        }
+    });
+    ui.label("Notes and articles you put aside. This list is private and is never published.");
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    if items.is_empty() {
+        ui.add_space(10.0);
+        ui.label("Nothing yet. Use \"Read Later\" in the menu of a note to add it here.");
+        return;
+    }
+
+    let count = items.len();
+    app.vert_scroll_area()
+        .id_source("read_later")
+        .show(ui, |ui| {
+            for (index, (item, event)) in items.iter().enumerate() {
+                render_item(app, ctx, ui, item, event.as_ref(), index, count);
+                ui.separator();
+            }
+        });
+}
+
+fn render_item(
+    app: &mut GossipUi,
+    ctx: &Context,
+    ui: &mut Ui,
+    item: &ReadLaterItem,
+    event: Option<&Event>,
+    index: usize,
+    count: usize,
+) {
+    ui.horizontal(|ui| {
+        let mut done = item.done;
+        if ui
+            .checkbox(&mut done, "")
+            .on_hover_text("Mark as read")
+            .changed()
+        {
+            report(app, read_later::set_done(item.id, done));
+        }
+
+        if ui
+            .add_enabled(index > 0, egui::Button::new("▲"))
+            .on_hover_text("Move up")
+            .clicked()
+        {
+            report(app, read_later::move_item(item.id, true));
+        }
+        if ui
+            .add_enabled(index + 1 < count, egui::Button::new("▼"))
+            .on_hover_text("Move down")
+            .clicked()
+        {
+            report(app, read_later::move_item(item.id, false));
+        }
+
+        ui.vertical(|ui| match event {
+            Some(event) => {
+                let mut title = RichText::new(title_of(event)).strong();
+                if item.done {
+                    title = title.weak().strikethrough();
+                }
+                if ui.link(title).clicked() {
+                    app.set_page(
+                        ctx,
+                        Page::Feed(FeedKind::Thread {
+                            id: event.id,
+                            referenced_by: event.id,
+                            author: Some(event.pubkey),
+                        }),
+                    );
+                }
+                ui.label(
+                    RichText::new(format!(
+                        "by {}, added {}",
+                        gossip_lib::names::best_name_from_pubkey_lookup(&event.pubkey),
+                        crate::timestamp::display(item.added_at)
+                    ))
+                    .weak(),
+                );
+            }
+            None => {
+                ui.label(
+                    RichText::new(format!(
+                        "{} (we no longer have this event)",
+                        gossip_lib::names::hex_id_short(&item.id.into())
+                    ))
+                    .weak(),
+                );
+            }
+        });
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.button("Remove").clicked() {
+                report(app, read_later::remove(item.id));
+            }
+        });
+    });
+}
+
+// The title of an article, the subject of a note, or else how it begins
+fn title_of(event: &Event) -> String {
+    for tag in &event.tags {
+        if matches!(tag.tagname(), "title" | "subject") && !tag.value().trim().is_empty() {
+            return tag.value().trim().to_owned();
+        }
+    }
+
+    let first_line = event.content.lines().next().unwrap_or("").trim();
+    if first_line.chars().count() > 80 {
+        format!("{}…", first_line.chars().take(80).collect::<String>())
+    } else if first_line.is_empty() {
+        "(no text)".to_owned()
+    } else {
+        first_line.to_owned()
+    }
+}
+
+// Report an error, and reload the list either way
+fn report(app: &mut GossipUi, result: Result<(), gossip_lib::Error>) {
+    if let Err(e) = result {
+        GLOBALS.status_queue.write().write_error(e.to_string());
+    }
+    enter_page(app);
+}
@@ -31,6 +31,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         }
     });
 
+    super::render_payments(app, ui);
+
     let relays = if !is_editing {
         // clear edit cache if present
         if !app.relays.edit_relays.is_empty() {
@@ -28,8 +28,19 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
                 .to_overlord
                 .send(ToOverlordMessage::AdvertiseRelayList);
         }
+        btn_h_space!(ui);
+        if ui.button("Advertise DM Relays")
+            .on_hover_cursor(egui::CursorIcon::PointingHand)
+            .on_hover_text("Ask to be sent direct messages on my inbox relays. Will send 10050 kind to all relays that have 'ADVERTISE' usage enabled")
+            .clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::AdvertiseDmRelays);
+        }
     });
 
+    super::render_payments(app, ui);
+
     let relays = if !is_editing {
         // clear edit cache if present
         if !app.relays.edit_relays.is_empty() {
@@ -4,6 +4,8 @@ use super::{widgets, GossipUi, Page};
 use eframe::egui;
 use egui::{Context, Ui};
 use egui_winit::egui::{vec2, Id, Rect, RichText};
+use gossip_lib::relay_audit::{self, AuditStatus};
+use gossip_lib::relay_payment::{self, RelayPayment};
 use gossip_lib::{comms::ToOverlordMessage, Relay, GLOBALS};
 use nostr_types::RelayUrl;
 
@@ -33,6 +35,8 @@ pub(super) struct RelayUi {
     edit_done: Option<RelayUrl>,
     /// do we still need to scroll to the edit
     edit_needs_scroll: bool,
+    /// how many days back to audit the relay being edited
+    audit_days: u64,
 
     /// Add Relay dialog
     add_dialog_step: AddRelayDialogStep,
@@ -51,6 +55,7 @@ impl RelayUi {
             edit_relays: Vec::new(),
             edit_done: None,
             edit_needs_scroll: false,
+            audit_days: 30,
             add_dialog_step: AddRelayDialogStep::Inactive,
             new_relay_url: RELAY_URL_PREPOPULATE.to_string(),
         }
@@ -78,6 +83,7 @@ pub(super) enum RelaySorting {
     HighestFollowing,
     HighestSuccessRate,
     LowestSuccessRate,
+    Fastest,
 }
 
 impl RelaySorting {
@@ -91,6 +97,7 @@ impl RelaySorting {
             RelaySorting::HighestFollowing => "Following",
             RelaySorting::HighestSuccessRate => "Success Rate",
             RelaySorting::LowestSuccessRate => "Failure Rate",
+            RelaySorting::Fastest => "Fastest",
         }
     }
 }
@@ -213,6 +220,8 @@ pub(super) fn relay_scroll_list(
                     .find(|p| p.key() == &db_url)
                     .map(|f| *f.value());
 
+                let wants_payment = edit && relay_payment::wants_payment(&db_relay);
+
                 let enabled = edit || !is_editing;
                 let mut widget = super::widgets::RelayEntry::new(db_relay, app);
                 widget.set_edit(edit);
@@ -225,6 +234,7 @@ pub(super) fn relay_scroll_list(
                     widget.set_user_count(assignment.pubkeys.len());
                 }
                 let response = ui.add_enabled(enabled, widget.clone());
+                let audit_url = db_url.clone();
                 if response.clicked() {
                     if !edit {
                         app.relays.edit = Some(db_url);
@@ -246,6 +256,13 @@ pub(super) fn relay_scroll_list(
                     }
                 }
                 pos_last_entry = response.rect.left_top();
+
+                if edit {
+                    if wants_payment {
+                        render_payment(app, ui, &audit_url);
+                    }
+                    render_audit(app, ui, &audit_url);
+                }
             }
 
             if !has_edit_target && !is_entry_dialog_active(app) {
@@ -262,6 +279,192 @@ pub(super) fn relay_scroll_list(
         });
 }
 
+/// Relays that turned us away until paid. These are also shown with the relay
+/// when editing it, so not while editing.
+pub(super) fn render_payments(app: &mut GossipUi, ui: &mut Ui) {
+    if app.relays.edit.is_some() {
+        return;
+    }
+    let payments = relay_payment::payments();
+    if payments.is_empty() {
+        return;
+    }
+    for (url, _) in payments.iter() {
+        render_payment(app, ui, url);
+    }
+    ui.separator();
+}
+
+/// Offer to pay a relay that wants to be paid, and show how that is going
+fn render_payment(app: &mut GossipUi, ui: &mut Ui, url: &RelayUrl) {
+    let payment = GLOBALS.relay_payments.get(url).map(|p| p.clone());
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("{} wants to be paid", url)).strong());
+        ui.add_space(10.0);
+        if matches!(
+            payment,
+            None | Some(RelayPayment::Required) | Some(RelayPayment::Failed(_))
+        ) && ui
+            .button("Get Invoice")
+            .on_hover_text("Ask the relay for an invoice for your public key")
+            .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchRelayInvoice(url.clone()));
+        }
+        if payment.is_some() && ui.button("Dismiss").clicked() {
+            relay_payment::dismiss(url);
+        }
+    });
+
+    match payment {
+        None | Some(RelayPayment::Required) => {
+            ui.label(
+                RichText::new(
+                    "Once it is paid, gossip authenticates to it as you and posts again what it turned away.",
+                )
+                .weak(),
+            );
+        }
+        Some(RelayPayment::Fetching) => {
+            ui.label("Asking the relay for an invoice...");
+        }
+        Some(RelayPayment::ReadyToPay { invoice, msats }) => {
+            ui.label(match msats {
+                Some(msats) => format!("The invoice is for {} sats.", msats / 1000),
+                None => "The invoice does not say how much it is for.".to_owned(),
+            });
+            ui.horizontal(|ui| {
+                if gossip_lib::nip47::is_connected() && ui.button("Pay with Wallet").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::PayRelayInvoice(url.clone()));
+                }
+                if ui
+                    .add(widgets::CopyButton::new())
+                    .on_hover_text("Copy Invoice")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = invoice.clone());
+                }
+                relay_paid_button(ui, url);
+            });
+            let ctx = ui.ctx().clone();
+            app.render_qr(
+                ui,
+                &ctx,
+                &format!("relay_payment_{}", url),
+                &invoice.to_uppercase(),
+            );
+        }
+        Some(RelayPayment::Paying) => {
+            ui.label("Paying through your wallet...");
+        }
+        Some(RelayPayment::PayAtWebsite(page)) => {
+            ui.horizontal(|ui| {
+                ui.label("It takes payment on its website:");
+                ui.hyperlink(&page);
+                relay_paid_button(ui, url);
+            });
+        }
+        Some(RelayPayment::Failed(e)) => {
+            ui.label(RichText::new(e).color(app.theme.warning_marker_text_color()));
+        }
+    }
+    ui.add_space(10.0);
+}
+
+fn relay_paid_button(ui: &mut Ui, url: &RelayUrl) {
+    if ui
+        .button("I Have Paid")
+        .on_hover_text("Connect to the relay again, authenticated as you")
+        .clicked()
+    {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::RelayPaid(url.clone()));
+    }
+}
+
+/// Show how many events of each kind we got from a relay
+fn render_audit(app: &mut GossipUi, ui: &mut Ui, url: &RelayUrl) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Events received, by kind").strong());
+        ui.add_space(10.0);
+        ui.add(egui::Slider::new(&mut app.relays.audit_days, 1..=365).text("days"));
+        let running = matches!(relay_audit::status(url), Some(AuditStatus::Running));
+        if ui
+            .add_enabled(!running, egui::Button::new("Audit"))
+            .clicked()
+        {
+            relay_audit::start(url.clone(), app.relays.audit_days);
+        }
+    });
+
+    let audit = match relay_audit::status(url) {
+        None => {
+            ui.label(
+                RichText::new("Audit to see which kinds of events this relay actually gives you.")
+                    .weak(),
+            );
+            return;
+        }
+        Some(AuditStatus::Running) => {
+            ui.label("Auditing... this looks at every event we have, so it takes a while.");
+            return;
+        }
+        Some(AuditStatus::Failed(e)) => {
+            ui.label(RichText::new(e).color(app.theme.warning_marker_text_color()));
+            return;
+        }
+        Some(AuditStatus::Done(audit)) => audit,
+    };
+
+    ui.label(format!(
+        "{} events since {}: {} by people you follow, {} by others, {} direct messages",
+        audit.total(),
+        crate::timestamp::absolute(audit.since),
+        audit.from_followed,
+        audit.from_others,
+        audit.dms,
+    ));
+    if audit.missing > 0 {
+        ui.label(RichText::new(format!("{} events are no longer stored", audit.missing)).weak());
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        for (kind, count) in audit.totals.iter() {
+            ui.label(format!("{:?}: {}", kind, count));
+            ui.add_space(8.0);
+        }
+    });
+
+    egui::CollapsingHeader::new("By day")
+        .id_source(("relay_audit", url.as_str()))
+        .show(ui, |ui| {
+            egui::Grid::new(("relay_audit_days", url.as_str()))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (day, kinds) in audit.days.iter() {
+                        ui.label(crate::timestamp::utc_date(*day));
+                        ui.label(
+                            kinds
+                                .iter()
+                                .map(|(kind, count)| format!("{:?}: {}", kind, count))
+                                .collect::<Vec<String>>()
+                                .join(", "),
+                        );
+                        ui.end_row();
+                    }
+                });
+        });
+    ui.add_space(10.0);
+}
+
 pub(super) fn is_entry_dialog_active(app: &GossipUi) -> bool {
     app.relays.add_dialog_step != AddRelayDialogStep::Inactive
 }
@@ -409,7 +612,7 @@ fn entry_dialog_step1(ui: &mut Ui, ctx: &Context, app: &mut GossipUi) {
                         GLOBALS
                             .status_queue
                             .write()
-                            .write("That's not a valid relay URL.".to_owned());
+                            .write_warning("That's not a valid relay URL.".to_owned());
                     }
                 }
             });
@@ -525,6 +728,11 @@ pub(super) fn relay_sort_combo(app: &mut GossipUi, ui: &mut Ui) {
                 RelaySorting::LowestSuccessRate,
                 RelaySorting::LowestSuccessRate.get_name(),
             );
+            ui.selectable_value(
+                &mut app.relays.sort,
+                RelaySorting::Fastest,
+                RelaySorting::Fastest.get_name(),
+            );
             ui.selectable_value(
                 &mut app.relays.sort,
                 RelaySorting::WriteRelays,
@@ -612,6 +820,9 @@ pub(super) fn sort_relay(rui: &RelayUi, a: &Relay, b: &Relay) -> Ordering {
             .then(a.url.cmp(&b.url)),
         RelaySorting::LowestSuccessRate => a.success_rate().total_cmp(&b.success_rate())
             .then(a.url.cmp(&b.url)),
+        RelaySorting::Fastest => a.avg_eose_ms.unwrap_or(u64::MAX)
+                              .cmp(&b.avg_eose_ms.unwrap_or(u64::MAX))
+            .then(a.url.cmp(&b.url)),
     }
 }
 
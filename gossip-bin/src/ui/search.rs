@@ -3,8 +3,11 @@ use eframe::{egui, Frame};
 use egui::widgets::Button;
 use egui::{Context, Label, RichText, Sense, Ui};
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip90::{self, JobType};
+use gossip_lib::search::SearchScope;
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
+use nostr_types::Event;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
     ui.add_space(10.0);
@@ -12,6 +15,31 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
 
     ui.add_space(12.0);
 
+    match app.search_scope {
+        SearchScope::Everywhere => (),
+        SearchScope::Thread(_) => {
+            ui.horizontal(|ui| {
+                ui.label("Searching within the thread.");
+                if ui.link("Search everywhere").clicked() {
+                    app.search_scope = SearchScope::Everywhere;
+                }
+            });
+            ui.add_space(6.0);
+        }
+        SearchScope::Person(pubkey) => {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Searching within notes by {}.",
+                    gossip_lib::names::best_name_from_pubkey_lookup(&pubkey)
+                ));
+                if ui.link("Search everywhere").clicked() {
+                    app.search_scope = SearchScope::Everywhere;
+                }
+            });
+            ui.add_space(6.0);
+        }
+    }
+
     let mut trigger_search = false;
 
     ui.horizontal(|ui| {
@@ -19,7 +47,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
             text_edit_line!(app, app.search)
                 .hint_text("Search for People and Notes")
                 .desired_width(600.0),
-        );
+        )
+        .on_hover_text("Notes can be narrowed with from:<npub or name>, kind:<n>, since:<YYYY-MM-DD>, until:<YYYY-MM-DD>, has:media and #hashtag");
 
         if app.entering_search_page {
             response.request_focus();
@@ -32,12 +61,39 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
         if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
             trigger_search = true;
         }
+        if app.search_scope == SearchScope::Everywhere
+            && ui
+                .button("Search Relays")
+                .on_hover_text("Also ask the relays marked for search to find notes (NIP-50)")
+                .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SearchRelays(app.search.clone()));
+        }
+        if GLOBALS.identity.is_unlocked()
+            && ui
+                .button("Ask DVMs")
+                .on_hover_text("Ask data vending machines to recommend notes (NIP-90)")
+                .clicked()
+        {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::RequestJob {
+                job_type: JobType::ContentDiscovery,
+                input: None,
+                language: None,
+                bid_msats: None,
+            });
+            app.search.clear();
+            GLOBALS.people_search_results.write().clear();
+            GLOBALS.note_search_results.write().clear();
+        }
     });
 
     if trigger_search {
-        let _ = GLOBALS
-            .to_overlord
-            .send(ToOverlordMessage::Search(app.search.clone()));
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Search(
+            app.search.clone(),
+            app.search_scope.clone(),
+        ));
     }
 
     ui.add_space(12.0);
@@ -79,45 +135,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
 
         if !notes.is_empty() {
             for event in notes.iter() {
-                ui.add_space(8.0);
-                ui.separator();
-                ui.add_space(8.0);
-
-                ui.horizontal(|ui| {
-                    ui.label(
-                        RichText::new(crate::date_ago::date_ago(event.created_at))
-                            .italics()
-                            .weak(),
-                    );
-
-                    if let Ok(Some(person)) = GLOBALS.storage.read_person(&event.pubkey) {
-                        GossipUi::render_person_name_line(app, ui, &person, false);
-                    } else {
-                        ui.label(event.pubkey.as_bech32_string());
-                    }
-                });
-
-                let mut summary = event
-                    .content
-                    .get(0..event.content.len().min(100))
-                    .unwrap_or("...")
-                    .replace('\n', " ");
-
-                if summary.is_empty() {
-                    // Show something they can click on anyways
-                    summary = "[no event summary]".to_owned();
-                }
-
-                if ui.add(Label::new(summary).sense(Sense::click())).clicked() {
-                    app.set_page(
-                        ctx,
-                        Page::Feed(FeedKind::Thread {
-                            id: event.id,
-                            referenced_by: event.id,
-                            author: Some(event.pubkey),
-                        }),
-                    );
-                }
+                render_note_summary(app, ctx, ui, event);
             }
         }
 
@@ -127,6 +145,83 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
             ui.add_space(8.0);
 
             ui.label("No results found.");
+
+            render_recommendations(app, ctx, ui);
         }
     });
 }
+
+// Notes recommended by the data vending machines we last asked (NIP-90)
+fn render_recommendations(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    let job = match nip90::latest_discovery() {
+        Ok(Some(job)) => job,
+        _ => return,
+    };
+
+    ui.add_space(20.0);
+    ui.heading("Recommended by DVMs");
+
+    if job.results.is_empty() {
+        ui.add_space(8.0);
+        match job.feedback.last() {
+            Some(feedback) => ui.label(format!(
+                "{}: {}",
+                gossip_lib::names::best_name_from_pubkey_lookup(&feedback.from),
+                feedback.status
+            )),
+            None => ui.label("Waiting for a data vending machine..."),
+        };
+        return;
+    }
+
+    for result in &job.results {
+        for id in nip90::discovered_ids(result) {
+            if let Ok(Some(event)) = GLOBALS.storage.read_event(id) {
+                render_note_summary(app, ctx, ui, &event);
+            }
+        }
+    }
+}
+
+fn render_note_summary(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, event: &Event) {
+    ui.add_space(8.0);
+    ui.separator();
+    ui.add_space(8.0);
+
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(crate::timestamp::display(event.created_at))
+                .italics()
+                .weak(),
+        )
+        .on_hover_text(crate::timestamp::absolute_with_zone(event.created_at));
+
+        if let Ok(Some(person)) = GLOBALS.storage.read_person(&event.pubkey) {
+            GossipUi::render_person_name_line(app, ui, &person, false);
+        } else {
+            ui.label(event.pubkey.as_bech32_string());
+        }
+    });
+
+    let mut summary = event
+        .content
+        .get(0..event.content.len().min(100))
+        .unwrap_or("...")
+        .replace('\n', " ");
+
+    if summary.is_empty() {
+        // Show something they can click on anyways
+        summary = "[no event summary]".to_owned();
+    }
+
+    if ui.add(Label::new(summary).sense(Sense::click())).clicked() {
+        app.set_page(
+            ctx,
+            Page::Feed(FeedKind::Thread {
+                id: event.id,
+                referenced_by: event.id,
+                author: Some(event.pubkey),
+            }),
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::i18n::tr;
+use crate::ui::GossipUi;
+use eframe::egui;
+use egui::widgets::Slider;
+use egui::{Context, Ui};
+use gossip_lib::alerts::{self, AlertCategory, AlertRule};
+use gossip_lib::GLOBALS;
+use nostr_types::PublicKey;
+
+/// An alert rule being edited, with its lists as comma separated text
+#[derive(Clone)]
+pub(in crate::ui) struct RuleEdit {
+    rule: AlertRule,
+    keywords: String,
+    hashtags: String,
+    authors: String,
+    kinds: String,
+}
+
+impl RuleEdit {
+    fn new(rule: AlertRule) -> RuleEdit {
+        RuleEdit {
+            keywords: rule.keywords.join(", "),
+            hashtags: rule.hashtags.join(", "),
+            authors: rule
+                .authors
+                .iter()
+                .map(|pk| pk.as_bech32_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            kinds: rule
+                .kinds
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            rule,
+        }
+    }
+
+    fn to_rule(&self) -> AlertRule {
+        let mut rule = self.rule.clone();
+        rule.keywords = split(&self.keywords);
+        rule.hashtags = split(&self.hashtags)
+            .into_iter()
+            .map(|h| h.trim_start_matches('#').to_owned())
+            .collect();
+        rule.authors = split(&self.authors)
+            .iter()
+            .filter_map(|s| {
+                PublicKey::try_from_bech32_string(s, true)
+                    .or_else(|_| PublicKey::try_from_hex_string(s, true))
+                    .ok()
+            })
+            .collect();
+        rule.kinds = split(&self.kinds)
+            .iter()
+            .filter_map(|s| s.parse::<u32>().ok())
+            .collect();
+        rule
+    }
+}
+
+fn split(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_owned())
+        .collect()
+}
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.heading(tr("settings-alerts"));
+
+    ui.add_space(10.0);
+    ui.label("Incoming events that match one of these rules are shown in your inbox, and can also raise a desktop notification.");
+    ui.label(
+        "Rules are saved with their own buttons below, sound settings with the Save button above.",
+    );
+
+    let edits = app
+        .alert_rule_edits
+        .get_or_insert_with(|| alerts::rules().into_iter().map(RuleEdit::new).collect());
+
+    let mut remove: Option<usize> = None;
+    for (index, edit) in edits.iter_mut().enumerate() {
+        ui.add_space(10.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut edit.rule.enabled, "Enabled");
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut edit.rule.name);
+            if ui.button("Remove").clicked() {
+                remove = Some(index);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Keywords:").on_hover_text(
+                "Comma separated words or phrases to look for in the content (ignoring case)",
+            );
+            ui.text_edit_singleline(&mut edit.keywords);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Hashtags:")
+                .on_hover_text("Comma separated hashtags, with or without the '#'");
+            ui.text_edit_singleline(&mut edit.hashtags);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Authors:")
+                .on_hover_text("Comma separated npubs or hex public keys");
+            ui.text_edit_singleline(&mut edit.authors);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Kinds:").on_hover_text(
+                "Comma separated event kind numbers. If empty, the kinds shown in feeds.",
+            );
+            ui.text_edit_singleline(&mut edit.kinds);
+        });
+
+        ui.checkbox(
+            &mut edit.rule.desktop_notification,
+            "Raise a desktop notification",
+        );
+
+        ui.horizontal(|ui| {
+            ui.label("Mute desktop notifications from")
+                .on_hover_text("Local hours of the day. Set both the same to never mute.");
+            ui.add(Slider::new(&mut edit.rule.mute_start_hour, 0..=23));
+            ui.label("to");
+            ui.add(Slider::new(&mut edit.rule.mute_end_hour, 0..=23));
+        });
+    }
+
+    if let Some(index) = remove {
+        edits.remove(index);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        if ui.button("Add a rule").clicked() {
+            edits.push(RuleEdit::new(AlertRule {
+                name: "New rule".to_owned(),
+                enabled: false,
+                ..Default::default()
+            }));
+        }
+
+        let rules: Vec<AlertRule> = edits.iter().map(|e| e.to_rule()).collect();
+        if rules != alerts::rules() {
+            if ui.button("Save Rules").clicked() {
+                match alerts::save_rules(&rules) {
+                    // Show the rules as they were understood
+                    Ok(()) => *edits = rules.into_iter().map(RuleEdit::new).collect(),
+                    Err(e) => GLOBALS.status_queue.write().write_error(e.to_string()),
+                }
+            }
+            if ui.button("Revert Rules").clicked() {
+                *edits = alerts::rules().into_iter().map(RuleEdit::new).collect();
+            }
+        }
+    });
+
+    ui.add_space(20.0);
+    ui.heading("Sounds");
+    ui.add_space(10.0);
+
+    let settings = &mut app.unsaved_settings;
+    for (category, label, enabled, file) in [
+        (
+            AlertCategory::Mention,
+            "Play a sound when mentioned or replied to",
+            &mut settings.sound_on_mention,
+            &mut settings.sound_file_mention,
+        ),
+        (
+            AlertCategory::DirectMessage,
+            "Play a sound on direct messages",
+            &mut settings.sound_on_dm,
+            &mut settings.sound_file_dm,
+        ),
+        (
+            AlertCategory::Zap,
+            "Play a sound when zapped",
+            &mut settings.sound_on_zap,
+            &mut settings.sound_file_zap,
+        ),
+    ] {
+        ui.checkbox(enabled, label);
+        ui.horizontal(|ui| {
+            ui.label("Sound file:")
+                .on_hover_text("A WAV, MP3, OGG or FLAC file. If empty, a short tone is played.");
+            ui.text_edit_singleline(file);
+            if ui.button("Test").clicked() {
+                crate::sound::play(category, file.clone());
+            }
+        });
+        ui.add_space(5.0);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Quiet hours, from")
+            .on_hover_text("Local hours of the day with no sounds. Set both the same to never be quiet. Takes effect on save.");
+        ui.add(Slider::new(&mut settings.sound_quiet_start_hour, 0..=23));
+        ui.label("to");
+        ui.add(Slider::new(&mut settings.sound_quiet_end_hour, 0..=23));
+    });
+
+    ui.add_space(20.0);
+}
@@ -1,13 +1,14 @@
+use crate::i18n::tr;
 use crate::ui::GossipUi;
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("Content");
+    ui.heading(tr("settings-content"));
 
     ui.add_space(10.0);
-    ui.heading("Feed Settings");
+    ui.heading(tr("settings-feed"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
@@ -45,7 +46,7 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     });
 
     ui.add_space(10.0);
-    ui.heading("Event Selection Settings");
+    ui.heading(tr("settings-event-selection"));
     ui.add_space(10.0);
 
     ui.checkbox(
@@ -60,6 +61,18 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
 
     ui.checkbox(&mut app.unsaved_settings.reposts, "Enable reposts (show)");
 
+    ui.checkbox(
+        &mut app.unsaved_settings.show_user_status,
+        "Show user statuses (NIP-38) under author names",
+    )
+    .on_hover_text("Takes effect fully only on restart.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.verify_timestamps,
+        "Verify OpenTimestamps attestations (NIP-03) of notes",
+    )
+    .on_hover_text("Fetches attestations of notes, and checks them against bitcoin block headers from blockstream.info. Verified notes are marked as timestamped on bitcoin. Takes effect fully only on restart.");
+
     ui.checkbox(
         &mut app.unsaved_settings.direct_messages,
         "Show Direct Messages",
@@ -72,14 +85,107 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     )
     .on_hover_text("Takes effect fully only on restart.");
 
+    ui.checkbox(
+        &mut app.unsaved_settings.show_git_events,
+        "Show Git Repositories, Patches and Issues (NIP-34)",
+    )
+    .on_hover_text("Takes effect fully only on restart.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.show_torrents,
+        "Show Torrents (NIP-35)",
+    )
+    .on_hover_text("Takes effect fully only on restart.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.show_live_activities,
+        "Show Live Streams and Other Live Activities (NIP-53)",
+    )
+    .on_hover_text("Takes effect fully only on restart.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.show_calendar_events,
+        "Show Calendar Events (NIP-52)",
+    )
+    .on_hover_text("Takes effect fully only on restart.");
+
     ui.checkbox(
         &mut app.unsaved_settings.avoid_spam_on_unsafe_relays,
         "Avoid spam from unsafe relays (SpamSafe)",
     )
         .on_hover_text("Unless a relay is marked as SpamSafe, replies and mentions will only be pulled from people you follow. Takes effect fully only on restart.");
 
+    ui.horizontal(|ui| {
+        ui.label("Require Proof of Work from strangers: ")
+            .on_hover_text("Posts from people who are not in any of your lists are dropped unless they carry at least this much proof of work (NIP-13). Zero disables this.");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.min_pow_unknown_authors, 0..=32)
+                .text("leading zero bits"),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Hide posts labeled: ")
+            .on_hover_text("Comma separated labels (NIP-32) put on posts or their authors by you or people you follow, as 'namespace' or 'namespace:value'. Matching posts are hidden from your feeds.");
+        ui.text_edit_singleline(&mut app.unsaved_settings.exclude_labels);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Only show posts labeled: ")
+            .on_hover_text("Comma separated labels (NIP-32), as 'namespace' or 'namespace:value'. If any are given, only posts with a matching label are shown in your feeds. Leave empty to show everything.");
+        ui.text_edit_singleline(&mut app.unsaved_settings.include_labels);
+    });
+
+    ui.checkbox(
+        &mut app.unsaved_settings.mute_when_reporting,
+        "Mute people when reporting them or their posts",
+    )
+    .on_hover_text("Reports (NIP-56) you send also add the person to your mute list.");
+
+    ui.checkbox(
+        &mut app.unsaved_settings.hide_bridged_posts,
+        "Hide posts bridged from other networks",
+    )
+    .on_hover_text("Posts carried over from ActivityPub, Bluesky, RSS and the like (NIP-48) are hidden from your feeds.");
+
     ui.add_space(10.0);
-    ui.heading("Event Content Settings");
+    ui.checkbox(
+        &mut app.unsaved_settings.follow_back_enabled,
+        "Suggest following back new followers",
+    )
+    .on_hover_text("New followers who match the rules below are listed on the People Lists page. Takes effect fully only on restart.");
+    ui.add_enabled_ui(app.unsaved_settings.follow_back_enabled, |ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.follow_back_automatically,
+            "Follow them back without asking",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Only if within this web of trust distance: ")
+                .on_hover_text("2 is followed by somebody you follow, 3 is followed by somebody they follow. Zero allows anybody.");
+            ui.add(Slider::new(
+                &mut app.unsaved_settings.follow_back_max_wot_distance,
+                0..=3,
+            ));
+        });
+        ui.checkbox(
+            &mut app.unsaved_settings.follow_back_require_nip05,
+            "Only if they have a valid NIP-05",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Only if their account is at least this old: ")
+                .on_hover_text("Going by the oldest event of theirs that we have. Zero allows new accounts.");
+            ui.add(
+                Slider::new(
+                    &mut app.unsaved_settings.follow_back_min_account_age_days,
+                    0..=365,
+                )
+                .text("days"),
+            );
+        });
+    });
+
+    ui.add_space(10.0);
+    ui.heading(tr("settings-event-content"));
     ui.add_space(10.0);
 
     ui.checkbox(
@@ -102,6 +208,12 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     )
         .on_hover_text("If on, muted events wont be in the feed at all. If off, they will be in the feed, but the content will be replaced with the word MUTED. You will see replies to them, and you can peek at the content by viewing the note in raw form.");
 
+    ui.checkbox(
+        &mut app.unsaved_settings.mute_list_sync,
+        "Keep my mute list in sync with my other clients",
+    )
+        .on_hover_text("If on, your mute list (kind 10000) is published whenever you mute or unmute somebody, and mute lists you publish from other clients are taken in. If both changed, the two are combined.");
+
     ui.checkbox(
         &mut app.unsaved_settings.show_deleted_events,
         "Render delete events, but labeled as deleted",
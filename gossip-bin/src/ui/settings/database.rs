@@ -1,13 +1,16 @@
+use crate::i18n::tr;
 use crate::ui::GossipUi;
 use crate::unsaved_settings::UnsavedSettings;
 use eframe::egui;
 use egui::widgets::Slider;
-use egui::{Context, Ui};
+use egui::{Context, RichText, Ui};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::GLOBALS;
+use humansize::{format_size, DECIMAL};
+use std::sync::atomic::Ordering;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("Storage Settings");
+    ui.heading(tr("settings-storage"));
 
     ui.add_space(20.0);
 
@@ -17,6 +20,20 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ui.add(Slider::new(&mut app.unsaved_settings.prune_period_days, 7..=720).text("days"));
     });
 
+    ui.checkbox(
+        &mut app.unsaved_settings.prune_keep_followed,
+        "Keep events of people I follow forever",
+    )
+    .on_hover_text(
+        "Your own events, and those you bookmarked or queued to read later, are always kept.",
+    );
+
+    ui.checkbox(
+        &mut app.unsaved_settings.prune_automatically,
+        "Delete old events automatically",
+    )
+    .on_hover_text("Once a day, delete events that are older than the period above.");
+
     ui.horizontal(|ui| {
         ui.label("How long to keep downloaded files")
             .on_hover_text("Cached files older than this will be deleted");
@@ -25,19 +42,144 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         );
     });
 
+    ui.add_space(20.0);
+
+    ui.checkbox(&mut app.unsaved_settings.backup_enabled, "Back up automatically")
+        .on_hover_text("Periodically write a copy of the database into a new timestamped directory. Takes effect on save.");
+
+    ui.horizontal(|ui| {
+        ui.label("How often to back up");
+        ui.add(Slider::new(&mut app.unsaved_settings.backup_interval_hours, 1..=720).text("hours"));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("How many backups to keep")
+            .on_hover_text("Older backups beyond this many will be deleted");
+        ui.add(Slider::new(&mut app.unsaved_settings.backup_keep, 1..=100));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Backup directory")
+            .on_hover_text("If empty, backups go in the 'backups' directory of your profile");
+        ui.text_edit_singleline(&mut app.unsaved_settings.backup_directory);
+    });
+
+    ui.checkbox(&mut app.unsaved_settings.backup_include_key, "Include my encrypted private key")
+        .on_hover_text("Also write your passphrase-encrypted private key (ncryptsec) into each backup, as a separate file. The database copy contains it too.");
+
     // Only let them prune after they have saved
     let stored_settings = UnsavedSettings::load();
     if stored_settings == app.unsaved_settings {
         ui.add_space(20.0);
-        if ui.button("Delete Old Events Now").on_hover_text("This will delete events older than the period specified above. but the LMDB files will continue consuming disk space. To compact them, copy withem with `mdb_copy -c` when gossip is not running.").clicked() {
+        if ui.button("Delete Old Events Now").on_hover_text("This will delete events older than the period specified above, except those kept by the choices above. The LMDB files will continue consuming disk space until the database is compacted.").clicked() {
             let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneDatabase);
         }
 
+        ui.add_space(20.0);
+        let mut compact = GLOBALS.compact_on_quit.load(Ordering::Relaxed);
+        if ui
+            .checkbox(&mut compact, "Compact the database when gossip quits")
+            .on_hover_text("Write a compacted copy of the database as gossip quits, check it, and use it from the next start on. This gives back the space of deleted events. It needs as much free disk space as the database takes.")
+            .changed()
+        {
+            GLOBALS.compact_on_quit.store(compact, Ordering::Relaxed);
+        }
+
         ui.add_space(20.0);
         if ui.button("Delete Old Downloaded Files").on_hover_text("This will delete cache files with modification times older than the period specified above (unfortunately access times are often unavailable and/or unreliable). Note that this will eventually delete everybody's avatar, even if those are in heavy use.").clicked() {
             let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneCache);
         }
+
+        ui.add_space(20.0);
+        if ui
+            .button("Back Up Now")
+            .on_hover_text("Write a backup now, into the backup directory above")
+            .clicked()
+        {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BackupNow);
+        }
     }
 
     ui.add_space(20.0);
+    ui.separator();
+    ui.add_space(10.0);
+    show_storage_stats(ui);
+
+    ui.add_space(20.0);
+}
+
+// The largest event kinds to list
+const KINDS_SHOWN: usize = 15;
+
+fn show_storage_stats(ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("What is stored").strong());
+        if ui
+            .button("Count")
+            .on_hover_text("Read through the whole database and count what it holds. This can take a while on a large database.")
+            .clicked()
+        {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UpdateStorageStats);
+        }
+    });
+
+    let stats = match &*GLOBALS.storage_stats.read() {
+        Some(stats) => stats.clone(),
+        None => return,
+    };
+
+    ui.add_space(6.0);
+    ui.label(format!(
+        "The database file takes {}.",
+        format_size(stats.file_size, DECIMAL)
+    ));
+    if let (Some(oldest), Some(newest)) = (stats.oldest_event, stats.newest_event) {
+        ui.label(format!(
+            "Events range from {} to {}.",
+            crate::timestamp::absolute(oldest),
+            crate::timestamp::absolute(newest)
+        ));
+    }
+
+    ui.add_space(6.0);
+    egui::Grid::new("storage_stats_tables")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label(RichText::new("Table").strong());
+            ui.label(RichText::new("Records").strong());
+            ui.label(RichText::new("Size").strong());
+            ui.end_row();
+            for table in &stats.tables {
+                ui.label(table.name);
+                ui.label(format!("{}", table.records));
+                ui.label(format_size(table.bytes, DECIMAL));
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(10.0);
+    egui::Grid::new("storage_stats_kinds")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label(RichText::new("Event kind").strong());
+            ui.label(RichText::new("Events").strong());
+            ui.label(RichText::new("Size").strong());
+            ui.end_row();
+            for kind in stats.kinds.iter().take(KINDS_SHOWN) {
+                ui.label(format!("{:?} ({})", kind.kind, u32::from(kind.kind)));
+                ui.label(format!("{}", kind.events));
+                ui.label(format_size(kind.bytes, DECIMAL));
+                ui.end_row();
+            }
+        });
+    if stats.kinds.len() > KINDS_SHOWN {
+        ui.label(format!(
+            "and {} smaller kinds",
+            stats.kinds.len() - KINDS_SHOWN
+        ));
+    }
 }
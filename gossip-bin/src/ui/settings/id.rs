@@ -1,10 +1,12 @@
+use crate::i18n::tr;
 use crate::ui::{GossipUi, Page};
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
+use gossip_lib::{settings_sync, GLOBALS};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("Identity Settings");
+    ui.heading(tr("settings-identity"));
     ui.add_space(20.0);
 
     // public_key
@@ -38,5 +40,68 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     )
     .on_hover_text("If set, you will be prompted for your password before gossip starts up.");
 
+    // Settings sync
+    ui.add_space(20.0);
+    ui.checkbox(
+        &mut app.unsaved_settings.settings_sync,
+        "Sync settings between your gossip installs",
+    )
+    .on_hover_text("Settings are published encrypted to yourself as application data (NIP-78) on your write relays. Where settings differ, the most recently changed value wins. Takes effect fully only on restart.");
+
+    if app.unsaved_settings.settings_sync {
+        ui.collapsing("Settings to sync", |ui| {
+            for name in settings_sync::synced_setting_names() {
+                let mut synced = !app
+                    .unsaved_settings
+                    .settings_sync_excluded
+                    .iter()
+                    .any(|n| n == name);
+                if ui.checkbox(&mut synced, name).changed() {
+                    if synced {
+                        app.unsaved_settings
+                            .settings_sync_excluded
+                            .retain(|n| n != name);
+                    } else {
+                        app.unsaved_settings
+                            .settings_sync_excluded
+                            .push(name.to_owned());
+                    }
+                }
+            }
+        });
+    }
+
+    // Wallet
+    ui.add_space(20.0);
+    ui.label("Wallet (Nostr Wallet Connect)");
+    if gossip_lib::nip47::is_connected() {
+        ui.horizontal(|ui| {
+            ui.label("Zaps are paid through your connected wallet.");
+            if ui.button("Disconnect").clicked() {
+                if let Err(e) = gossip_lib::nip47::disconnect() {
+                    GLOBALS.status_queue.write().write_error(format!("{}", e));
+                }
+            }
+        });
+    } else {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Connection URI:");
+            ui.add(
+                text_edit_line!(app, app.wallet_uri)
+                    .password(true)
+                    .hint_text("nostr+walletconnect://...")
+                    .desired_width(400.0),
+            );
+            if ui.button("Connect").clicked() {
+                match gossip_lib::nip47::connect(&app.wallet_uri) {
+                    Ok(()) => app.wallet_uri.clear(),
+                    Err(e) => GLOBALS.status_queue.write().write_error(format!("{}", e)),
+                }
+            }
+        })
+        .response
+        .on_hover_text("Your wallet gives you this URI. Zaps are then paid through it instead of by scanning a QR code. It is stored encrypted, so your identity must be unlocked.");
+    }
+
     ui.add_space(20.0);
 }
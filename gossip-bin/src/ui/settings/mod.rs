@@ -1,8 +1,13 @@
+use crate::i18n::{self, tr};
+use crate::logging;
 use crate::ui::{GossipUi, SettingsTab};
 use crate::unsaved_settings::UnsavedSettings;
 use eframe::egui;
 use egui::{Align, Context, Layout, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
 
+mod alerts;
 mod content;
 mod database;
 mod id;
@@ -10,14 +15,16 @@ mod network;
 mod posting;
 mod ui;
 
+pub(in crate::ui) use alerts::RuleEdit;
+
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.add_space(10.0);
-    ui.heading("Settings");
+    ui.heading(tr("settings"));
 
     ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
         let stored_settings = UnsavedSettings::load();
         if stored_settings != app.unsaved_settings {
-            if ui.button("REVERT CHANGES").clicked() {
+            if ui.button(tr("settings-revert")).clicked() {
                 app.unsaved_settings = UnsavedSettings::load();
 
                 // Fully revert any DPI changes
@@ -35,7 +42,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                 ctx.set_pixels_per_point(ppt);
             }
 
-            if ui.button("SAVE CHANGES").clicked() {
+            if ui.button(tr("settings-save")).clicked() {
                 let mut dpi_changed = false;
 
                 // Apply DPI change
@@ -56,6 +63,16 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
 
                 if let Err(e) = app.unsaved_settings.save() {
                     tracing::error!("Error saving settings: {}", e);
+                } else {
+                    if let Err(e) = logging::apply_settings(false) {
+                        tracing::error!("{}", e);
+                    }
+                    i18n::apply_settings();
+                    if app.unsaved_settings.settings_sync {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::PushSettingsSync);
+                    }
                 }
 
                 if dpi_changed {
@@ -70,17 +87,47 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
 
     app.vert_scroll_area().id_source("settings").show(ui, |ui| {
         ui.horizontal_wrapped(|ui| {
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Id, "Identity");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Id,
+                tr("settings-tab-identity"),
+            );
+            ui.label("|");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Ui,
+                tr("settings-tab-ui"),
+            );
             ui.label("|");
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Ui, "Ui");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Content,
+                tr("settings-tab-content"),
+            );
             ui.label("|");
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Content, "Content");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Network,
+                tr("settings-tab-network"),
+            );
             ui.label("|");
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Network, "Network");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Posting,
+                tr("settings-tab-posting"),
+            );
             ui.label("|");
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Posting, "Posting");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Database,
+                tr("settings-tab-storage"),
+            );
             ui.label("|");
-            ui.selectable_value(&mut app.settings_tab, SettingsTab::Database, "Storage");
+            ui.selectable_value(
+                &mut app.settings_tab,
+                SettingsTab::Alerts,
+                tr("settings-tab-alerts"),
+            );
         });
 
         ui.add_space(10.0);
@@ -88,6 +135,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
         ui.add_space(10.0);
 
         match app.settings_tab {
+            SettingsTab::Alerts => alerts::update(app, ctx, frame, ui),
             SettingsTab::Content => content::update(app, ctx, frame, ui),
             SettingsTab::Database => database::update(app, ctx, frame, ui),
             SettingsTab::Id => id::update(app, ctx, frame, ui),
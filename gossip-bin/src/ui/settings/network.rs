@@ -1,22 +1,57 @@
+use crate::i18n::tr;
+use crate::logging;
 use crate::ui::{GossipUi, Page};
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("Network Settings");
+    ui.heading(tr("settings-network"));
 
     ui.add_space(10.0);
 
     ui.checkbox(&mut app.unsaved_settings.offline, "Offline Mode")
         .on_hover_text("If selected, no network requests will be issued. Takes effect on restart.");
 
+    ui.checkbox(&mut app.unsaved_settings.use_proxy, "Connect through a SOCKS5 proxy")
+        .on_hover_text("Send relay connections and web requests through a SOCKS5 proxy such as Tor. The proxy looks up host names, so .onion relays work. Takes effect on restart.");
+
+    ui.add_enabled_ui(app.unsaved_settings.use_proxy, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Proxy host");
+            ui.add(
+                egui::TextEdit::singleline(&mut app.unsaved_settings.proxy_host)
+                    .desired_width(200.0),
+            );
+            ui.label("port");
+            ui.add(Slider::new(
+                &mut app.unsaved_settings.proxy_port,
+                1..=65535,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Relays to connect to directly").on_hover_text(
+                "Comma or space separated relay URLs that bypass the proxy. .onion relays always use it.",
+            );
+            ui.text_edit_singleline(&mut app.unsaved_settings.proxy_direct_relays);
+        });
+    });
+
     ui.checkbox(&mut app.unsaved_settings.load_avatars, "Fetch Avatars").on_hover_text("If disabled, avatars will not be fetched, but cached avatars will still display. Takes effect on save.");
 
     ui.checkbox(&mut app.unsaved_settings.load_media, "Fetch Media").on_hover_text("If disabled, no new media will be fetched, but cached media will still display. Takes effect on save.");
 
+    ui.checkbox(&mut app.unsaved_settings.read_later_prefetch_media, "Fetch Media of Notes to Read Later").on_hover_text("If enabled, the images and videos linked from notes you add to read later are fetched right away, so they are there when you are offline. Takes effect on save.");
+
     ui.checkbox(&mut app.unsaved_settings.check_nip05, "Check NIP-05").on_hover_text("If disabled, NIP-05 fetches will not be performed, but existing knowledge will be preserved, and following someone by NIP-05 will override this and do the fetch. Takes effect on save.");
 
+    ui.horizontal(|ui| {
+        ui.label("Directories for importing Twitter follows: ").on_hover_text(
+            "Comma separated NIP-05 domains that list nostr users under their Twitter handles. Only add directories you trust.",
+        );
+        ui.text_edit_singleline(&mut app.unsaved_settings.follow_import_directories);
+    });
+
     ui.checkbox(&mut app.unsaved_settings.automatically_fetch_metadata, "Automatically Fetch Metadata").on_hover_text("If enabled, metadata that is entirely missing will be fetched as you scroll past people. Existing metadata won't be updated. Takes effect on save.");
 
     ui.checkbox(
@@ -29,8 +64,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         "Require user approval before AUTHenticating to a relay for the first time",
     );
 
+    ui.checkbox(
+        &mut app.unsaved_settings.relay_list_auto_advertise,
+        "Advertise my relay list when I change my inbox or outbox relays",
+    )
+    .on_hover_text("Publishes your relay list (kind 10002) shortly after you change which relays are your inbox and outbox, so others find you there. Takes effect on save.");
+
     ui.add_space(10.0);
-    ui.heading("Relay Settings");
+    ui.heading(tr("settings-relays"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
@@ -52,7 +93,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     });
 
     ui.add_space(10.0);
-    ui.heading("HTTP Fetch Settings");
+    ui.heading(tr("settings-http"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
@@ -143,9 +184,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
             .text("lines"),
         );
     });
+    ui.horizontal(|ui| {
+        ui.label("Hosts to sign in to with your key when fetching (NIP-98)")
+            .on_hover_text("Separated by spaces or commas. Some file servers and paid content need this. Only used while your key is unlocked.");
+        ui.text_edit_singleline(&mut app.unsaved_settings.fetcher_nip98_hosts);
+    });
 
     ui.add_space(10.0);
-    ui.heading("Websocket Settings");
+    ui.heading(tr("settings-websocket"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
@@ -191,7 +237,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     });
 
     ui.add_space(10.0);
-    ui.heading("Stale Time Settings");
+    ui.heading(tr("settings-stale"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
@@ -247,5 +293,62 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         );
     });
 
+    ui.add_space(20.0);
+    ui.heading(tr("settings-logging"));
+
+    ui.add_space(10.0);
+    ui.checkbox(&mut app.unsaved_settings.log_to_file, "Log to a file")
+        .on_hover_text("Writes gossip.log in your gossip profile directory. Older logs are kept as gossip.log.1 through gossip.log.4.");
+    ui.horizontal(|ui| {
+        ui.label("Rotate the log file when it reaches");
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.log_file_max_size_kb, 256..=102400).text("KiB"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Log filter:")
+            .on_hover_text("Levels per module in RUST_LOG syntax, e.g. info,gossip_lib::overlord::minion=debug. Takes effect when saved. A RUST_LOG environment variable overrides this at startup.");
+        ui.text_edit_singleline(&mut app.unsaved_settings.log_filter);
+    });
+    ui.checkbox(&mut app.unsaved_settings.crash_reports, "Save crash reports")
+        .on_hover_text("If gossip panics or fails, save a report with diagnostics and recent log lines (with anything that looks like a private key removed) that you can review and choose to submit. Reports never include your keys or messages.");
+    if !logging::filter_is_valid(&app.unsaved_settings.log_filter) {
+        ui.label(
+            egui::RichText::new("This log filter is not valid and will not be applied")
+                .color(app.theme.warning_marker_text_color()),
+        );
+    }
+
+    ui.add_space(20.0);
+    ui.heading(tr("settings-monitoring"));
+
+    ui.add_space(10.0);
+    ui.checkbox(
+        &mut app.unsaved_settings.metrics_endpoint,
+        "Serve metrics for Prometheus on localhost",
+    )
+    .on_hover_text("Counters such as connected relays, events processed, fetcher queue and storage size are served at http://127.0.0.1:<port>/metrics. Takes effect on restart.");
+    ui.horizontal(|ui| {
+        ui.label("Metrics port");
+        ui.add(Slider::new(
+            &mut app.unsaved_settings.metrics_port,
+            1024..=65535,
+        ));
+    });
+
+    ui.add_space(10.0);
+    ui.checkbox(
+        &mut app.unsaved_settings.syndication_endpoint,
+        "Serve RSS and Atom feeds on localhost",
+    )
+    .on_hover_text("Notes of people you follow, of a person, or with a hashtag, and long-form articles, are served at http://127.0.0.1:<port>/ for feed readers and other tools. Takes effect on restart.");
+    ui.horizontal(|ui| {
+        ui.label("Feeds port");
+        ui.add(Slider::new(
+            &mut app.unsaved_settings.syndication_port,
+            1024..=65535,
+        ));
+    });
+
     ui.add_space(20.0);
 }
@@ -1,15 +1,16 @@
+use crate::i18n::tr;
 use crate::ui::GossipUi;
 use eframe::egui;
 use egui::widgets::Slider;
 use egui::{Context, Ui};
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("Posting Settings");
+    ui.heading(tr("settings-posting"));
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
         ui.label("Proof of Work: ")
-            .on_hover_text("The larger the number, the longer it takes.");
+            .on_hover_text("The larger the number, the longer it takes. Posting can be cancelled while the work is computed.");
         ui.add(Slider::new(&mut app.unsaved_settings.pow, 0..=40).text("leading zero bits"));
     });
 
@@ -28,5 +29,19 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     )
     .on_hover_text("Takes effect on next relay connection.");
 
+    ui.horizontal(|ui| {
+        ui.label("Web links start with: ").on_hover_text(
+            "Copied web links are this followed by the nevent, naddr or nprofile, for sharing with people outside of nostr",
+        );
+        ui.text_edit_singleline(&mut app.unsaved_settings.web_link_base);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Media server: ").on_hover_text(
+            "Files attached to posts are uploaded here (NIP-96), for example https://nostr.build",
+        );
+        ui.text_edit_singleline(&mut app.unsaved_settings.media_server);
+    });
+
     ui.add_space(20.0);
 }
@@ -1,10 +1,68 @@
+use crate::i18n::{self, tr};
 use crate::ui::{GossipUi, ThemeVariant};
 use eframe::egui;
 use egui::widgets::{Button, Slider};
 use egui::{Context, Ui};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.heading("UI Settings");
+    ui.heading(tr("settings-ui"));
+
+    ui.add_space(20.0);
+    ui.horizontal(|ui| {
+        ui.label(tr("settings-language"));
+        let selected = match i18n::LANGUAGES
+            .iter()
+            .find(|(code, _)| *code == app.unsaved_settings.language)
+        {
+            Some((_, name)) => name.to_string(),
+            None => tr("settings-language-system"),
+        };
+        egui::ComboBox::from_id_source("Language")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut app.unsaved_settings.language,
+                    String::new(),
+                    tr("settings-language-system"),
+                );
+                for (code, name) in i18n::LANGUAGES {
+                    ui.selectable_value(
+                        &mut app.unsaved_settings.language,
+                        code.to_string(),
+                        *name,
+                    );
+                }
+            });
+    });
+
+    ui.add_space(20.0);
+    ui.checkbox(
+        &mut app.unsaved_settings.relative_timestamps,
+        "Show how long ago things happened (e.g. 5m) instead of the date and time",
+    )
+    .on_hover_text("Hover over a time to see it in full either way.");
+    ui.checkbox(
+        &mut app.unsaved_settings.timestamps_24_hour,
+        "Use the 24-hour clock",
+    );
+    ui.horizontal(|ui| {
+        let mut override_timezone = app.unsaved_settings.timezone_offset_minutes.is_some();
+        if ui
+            .checkbox(&mut override_timezone, "Override time zone")
+            .on_hover_text("Times are otherwise shown in the time zone of your computer.")
+            .changed()
+        {
+            app.unsaved_settings.timezone_offset_minutes =
+                if override_timezone { Some(0) } else { None };
+        }
+        if let Some(minutes) = app.unsaved_settings.timezone_offset_minutes.as_mut() {
+            ui.add(
+                Slider::new(minutes, -720..=840)
+                    .step_by(15.0)
+                    .text("minutes from UTC"),
+            );
+        }
+    });
 
     ui.add_space(20.0);
     ui.checkbox(
@@ -73,6 +131,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         ui.add(Slider::new(&mut app.unsaved_settings.max_fps, 2..=60).text("Frames per second"));
     });
 
+    ui.add_space(20.0);
+    ui.horizontal(|ui| {
+        ui.label("Memory budget: ").on_hover_text("When cached media, notes and avatars use more memory than this, the ones not on screen are dropped, to be loaded again when needed. Current usage is shown under Help > Stats. 0 means no limit.");
+        ui.add(Slider::new(&mut app.unsaved_settings.memory_budget_mb, 0..=4096).text("MB"));
+    });
+
     ui.add_space(20.0);
     ui.checkbox(
         &mut app.unsaved_settings.status_bar,
@@ -142,6 +142,42 @@ pub fn search_field(ui: &mut Ui, field: &mut String, width: f32) -> TextEditOutp
     output
 }
 
+/// A menu for labeling a person, or one of their events when `id` is given
+/// (NIP-32). Returns whether a label was sent.
+pub(super) fn label_menu(
+    ui: &mut Ui,
+    app: &mut GossipUi,
+    pubkey: nostr_types::PublicKey,
+    id: Option<nostr_types::Id>,
+) -> bool {
+    let mut sent = false;
+    ui.menu_button("Label", |ui| {
+        let response = ui.add(
+            text_edit_line!(app, app.label_text)
+                .hint_text("value or namespace:value")
+                .desired_width(200.0),
+        );
+        let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Apply").clicked() || entered {
+            match gossip_lib::nip32::Label::parse(&app.label_text) {
+                Some(label) => {
+                    let _ = gossip_lib::GLOBALS
+                        .to_overlord
+                        .send(gossip_lib::comms::ToOverlordMessage::Label { pubkey, id, label });
+                    app.label_text.clear();
+                    sent = true;
+                    ui.close_menu();
+                }
+                None => gossip_lib::GLOBALS
+                    .status_queue
+                    .write()
+                    .write_error("Enter a label first".to_owned()),
+            }
+        }
+    });
+    sent
+}
+
 pub(super) fn set_important_button_visuals(ui: &mut Ui, app: &GossipUi) {
     let visuals = ui.visuals_mut();
     visuals.widgets.inactive.weak_bg_fill = app.theme.accent_color();
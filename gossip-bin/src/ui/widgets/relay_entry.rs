@@ -16,9 +16,9 @@ use super::{
 /// Height of the list view (width always max. available)
 const LIST_VIEW_HEIGHT: f32 = 60.0;
 /// Height of the list view (width always max. available)
-const DETAIL_VIEW_HEIGHT: f32 = 80.0;
+const DETAIL_VIEW_HEIGHT: f32 = 105.0;
 /// Height of the edit view (width always max. available)
-const EDIT_VIEW_HEIGHT: f32 = 250.0;
+const EDIT_VIEW_HEIGHT: f32 = 280.0;
 /// Y-offset for first separator
 const HLINE_1_Y_OFFSET: f32 = LIST_VIEW_HEIGHT - 12.0;
 /// Y-offset for second separator
@@ -69,6 +69,7 @@ const WRITE_HOVER_TEXT: &str =
     "Where you actually write your events to. It is recommended to have a few.";
 const OUTBOX_HOVER_TEXT: &str = "Where you tell others you write to. You should also check Write. It is recommended to have a few.";
 const ADVERTISE_HOVER_TEXT: &str = "Your relay list will be advertised to this relay.";
+const SEARCH_HOVER_TEXT: &str = "Where you search for notes you don't have yet. Only useful if the relay supports search (NIP-50).";
 const SPAMSAFE_HOVER_TEXT: &str = "Relay is trusted to filter spam. If not set, replies and mentions from unfollowed people will not be fetched from the relay (when SpamSafe is enabled in settings).";
 
 #[derive(Clone, PartialEq)]
@@ -87,6 +88,7 @@ struct UsageBits {
     outbox: bool,
     discover: bool,
     spamsafe: bool,
+    search: bool,
 }
 
 impl UsageBits {
@@ -99,6 +101,7 @@ impl UsageBits {
             outbox: usage_bits & Relay::OUTBOX == Relay::OUTBOX,
             discover: usage_bits & Relay::DISCOVER == Relay::DISCOVER,
             spamsafe: usage_bits & Relay::SPAMSAFE == Relay::SPAMSAFE,
+            search: usage_bits & Relay::SEARCH == Relay::SEARCH,
         }
     }
 
@@ -243,7 +246,7 @@ impl RelayEntry {
         let (color, tooltip) = if self.connected {
             let mut text = "Connected".to_string();
             if let Some(at) = self.relay.last_connected_at {
-                let ago = crate::date_ago::date_ago(Unixtime(at as i64));
+                let ago = crate::timestamp::display(Unixtime(at as i64));
                 text = format!("Connected since {}", ago);
             }
             (egui::Color32::from_rgb(0x63, 0xc8, 0x56), text) // green
@@ -443,7 +446,7 @@ impl RelayEntry {
             let pos = pos + vec2(STATS_COL_3_X, 0.0);
             let mut ago = "".to_string();
             if let Some(at) = self.relay.last_general_eose_at {
-                ago += crate::date_ago::date_ago(Unixtime(at as i64)).as_str();
+                ago += crate::timestamp::display(Unixtime(at as i64)).as_str();
             } else {
                 ago += "?";
             }
@@ -461,7 +464,7 @@ impl RelayEntry {
             let pos = pos + vec2(STATS_COL_4_X, 0.0);
             let mut ago = "".to_string();
             if let Some(at) = self.relay.last_connected_at {
-                ago += crate::date_ago::date_ago(Unixtime(at as i64)).as_str();
+                ago += crate::timestamp::display(Unixtime(at as i64)).as_str();
             } else {
                 ago += "?";
             }
@@ -489,6 +492,88 @@ impl RelayEntry {
         }
     }
 
+    fn paint_health(&self, ui: &mut Ui, rect: &Rect) {
+        fn ms(value: Option<u64>) -> String {
+            match value {
+                Some(ms) => format!("{}ms", ms),
+                None => "?".to_owned(),
+            }
+        }
+
+        // ---- EOSE time ----
+        let pos = rect.min + vec2(STATS_COL_1_X, TEXT_TOP + 2.0 * STATS_Y_SPACING);
+        let text = RichText::new(format!("EOSE: {}", ms(self.relay.avg_eose_ms)));
+        let text_rect = draw_text_at(
+            ui,
+            pos,
+            text.into(),
+            Align::LEFT,
+            Some(ui.visuals().text_color()),
+            None,
+        );
+        ui.interact(text_rect, self.make_id("health_eose"), Sense::hover())
+            .on_hover_text("Average time from asking for events to getting all the stored ones");
+
+        // ---- Ping ----
+        let pos = pos + vec2(STATS_COL_2_X, 0.0);
+        let text = RichText::new(format!("Ping: {}", ms(self.relay.avg_ping_ms)));
+        draw_text_at(
+            ui,
+            pos,
+            text.into(),
+            Align::LEFT,
+            Some(ui.visuals().text_color()),
+            None,
+        );
+
+        // ---- Events received ----
+        let pos = pos + vec2(STATS_COL_3_X, 0.0);
+        let text = RichText::new(format!("Events: {}", self.relay.events_received));
+        draw_text_at(
+            ui,
+            pos,
+            text.into(),
+            Align::LEFT,
+            Some(ui.visuals().text_color()),
+            None,
+        );
+
+        // ---- Bytes received ----
+        let pos = pos + vec2(STATS_COL_4_X, 0.0);
+        let text = RichText::new(format!(
+            "Received: {}",
+            humansize::format_size(self.relay.bytes_received, humansize::DECIMAL)
+        ));
+        draw_text_at(
+            ui,
+            pos,
+            text.into(),
+            Align::LEFT,
+            Some(ui.visuals().text_color()),
+            None,
+        );
+
+        // ---- Failures and penalty ----
+        let pos = pos + vec2(STATS_COL_5_X, 0.0);
+        let strikes = gossip_lib::relay_penalty::penalty(&self.relay.url)
+            .map(|p| p.strikes)
+            .unwrap_or(0);
+        let text = RichText::new(format!(
+            "Failures: {} (strikes: {})",
+            self.relay.failure_count, strikes
+        ));
+        let text_rect = draw_text_at(
+            ui,
+            pos,
+            text.into(),
+            Align::LEFT,
+            Some(ui.visuals().text_color()),
+            None,
+        );
+        ui.interact(text_rect, self.make_id("health_failures"), Sense::hover())
+            .on_hover_text("Strikes count the times in a row it dropped us or rate-limited us, each of which keeps us away from it for longer");
+    }
+
     fn paint_reasons(&self, ui: &mut Ui, rect: &Rect) {
         const RIGHT: f32 = -17.0;
         const SPACE: f32 = 23.0;
@@ -967,6 +1052,36 @@ impl RelayEntry {
             );
         }
         let pos = pos + vec2(0.0, USAGE_SWITCH_Y_SPACING);
+        {
+            // ---- search ----
+            let id = self.make_id("search_switch");
+            let sw_rect = Rect::from_min_size(pos - vec2(0.0, USAGE_SWITCH_Y_OFFSET), switch_size);
+            let response = widgets::switch_custom_at(
+                ui,
+                true,
+                &mut self.usage.search,
+                sw_rect,
+                id,
+                knob_fill,
+                on_fill,
+                off_fill,
+            );
+            if response.changed() {
+                modify_relay(&self.relay.url, |relay| {
+                    relay.adjust_usage_bit(Relay::SEARCH, self.usage.search)
+                });
+            }
+            response.on_hover_text(SEARCH_HOVER_TEXT);
+            draw_text_at(
+                ui,
+                pos + vec2(ui.spacing().item_spacing.x + switch_size.x, 0.0),
+                "Search".into(),
+                Align::LEFT,
+                Some(ui.visuals().text_color()),
+                None,
+            );
+        }
+        let pos = pos + vec2(0.0, USAGE_SWITCH_Y_SPACING);
         {
             // ---- rank ----
             let r = self.relay.rank;
@@ -1093,6 +1208,7 @@ impl RelayEntry {
             self.paint_title(ui, &rect);
             response |= self.paint_edit_btn(ui, &rect);
             self.paint_stats(ui, &rect);
+            self.paint_health(ui, &rect);
             if self.relay.has_any_usage_bit() || self.relay.is_good_for_advertise() {
                 self.paint_usage(ui, &rect);
             }
@@ -0,0 +1,287 @@
+use super::{widgets, GossipUi, Page};
+use eframe::{egui, Frame};
+use egui::{vec2, Context, RichText, TextStyle, Ui, Vec2};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nip54::{self, WikiArticle, WikiSegment};
+use gossip_lib::GLOBALS;
+use nostr_types::{Id, PublicKey};
+
+/// A wiki article being written, or forked from someone else's
+pub(super) struct WikiEdit {
+    title: String,
+    summary: String,
+    content: String,
+    fork_of: Option<Id>,
+}
+
+impl WikiEdit {
+    fn new(title: &str) -> WikiEdit {
+        WikiEdit {
+            title: title.to_owned(),
+            summary: String::new(),
+            content: String::new(),
+            fork_of: None,
+        }
+    }
+
+    fn fork(article: &WikiArticle) -> WikiEdit {
+        // Editing our own article replaces it, rather than forking it
+        let ours = Some(article.event.pubkey) == GLOBALS.identity.public_key();
+        WikiEdit {
+            title: article.title.clone(),
+            summary: article.summary.clone().unwrap_or_default(),
+            content: article.content.clone(),
+            fork_of: if ours { None } else { Some(article.event.id) },
+        }
+    }
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    let d = match &app.page {
+        Page::Wiki(d) => d.clone(),
+        _ => return,
+    };
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Wiki");
+        ui.add_space(10.0);
+        let response = ui.add(
+            text_edit_line!(app, app.wiki_topic)
+                .hint_text("Topic")
+                .desired_width(300.0),
+        );
+        let go = ui.button("Go").clicked();
+        if go || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) {
+            let topic = app.wiki_topic.clone();
+            if !nip54::normalize(&topic).is_empty() {
+                open_topic(app, ctx, &topic, None);
+            }
+        }
+        if GLOBALS.identity.is_unlocked() && ui.button("Write an article").clicked() {
+            app.wiki_edit = Some(WikiEdit::new(&app.wiki_topic));
+        }
+    });
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    let articles = nip54::articles(&d).unwrap_or_default();
+
+    if articles.is_empty() {
+        ui.add_space(8.0);
+        ui.label(format!("No articles on \"{}\" yet.", d));
+    } else {
+        // Pick the version to show: the one by the chosen author, else ours,
+        // else one by somebody we follow, else the newest
+        let chosen = app
+            .wiki_author
+            .and_then(|pk| articles.iter().position(|a| a.event.pubkey == pk))
+            .or_else(|| {
+                let me = GLOBALS.identity.public_key();
+                articles.iter().position(|a| Some(a.event.pubkey) == me)
+            })
+            .or_else(|| {
+                let followed = GLOBALS.people.get_subscribed_pubkeys();
+                articles
+                    .iter()
+                    .position(|a| followed.contains(&a.event.pubkey))
+            })
+            .unwrap_or(0);
+
+        ui.add_space(4.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{} versions:", articles.len()));
+            for (index, article) in articles.iter().enumerate() {
+                let name = gossip_lib::names::best_name_from_pubkey_lookup(&article.event.pubkey);
+                if ui.selectable_label(index == chosen, name).clicked() {
+                    app.wiki_author = Some(article.event.pubkey);
+                }
+            }
+        });
+        ui.add_space(8.0);
+
+        let article = &articles[chosen];
+        app.vert_scroll_area().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .link(gossip_lib::names::best_name_from_pubkey_lookup(
+                        &article.event.pubkey,
+                    ))
+                    .clicked()
+                {
+                    app.set_page(ctx, Page::Person(article.event.pubkey));
+                }
+                ui.label(RichText::new(crate::timestamp::display(article.event.created_at)).weak());
+                if GLOBALS.identity.is_unlocked() {
+                    let ours = Some(article.event.pubkey) == GLOBALS.identity.public_key();
+                    if ui.button(if ours { "Edit" } else { "Fork" }).clicked() {
+                        app.wiki_edit = Some(WikiEdit::fork(article));
+                    }
+                }
+            });
+            if let Some(ea) = &article.forked_from {
+                ui.label(
+                    RichText::new(format!(
+                        "Forked from {}",
+                        gossip_lib::names::best_name_from_pubkey_lookup(&ea.author)
+                    ))
+                    .weak()
+                    .text_style(TextStyle::Small),
+                );
+            }
+            ui.add_space(8.0);
+            render_article(app, ui, ctx, article);
+        });
+    }
+
+    if let Some(mut edit) = app.wiki_edit.take() {
+        if render_editor(app, ui, &mut edit) {
+            app.wiki_edit = Some(edit);
+        }
+    }
+}
+
+/// Render an article, with its wikilinks going to the articles they name
+pub(super) fn render_article(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    ctx: &Context,
+    article: &WikiArticle,
+) {
+    ui.heading(&article.title);
+    if let Some(summary) = &article.summary {
+        ui.label(RichText::new(summary).italics());
+    }
+    ui.add_space(6.0);
+
+    let mut listing = false;
+    for line in article.content.lines() {
+        if line.trim().is_empty() && !listing {
+            ui.add_space(6.0);
+            continue;
+        }
+
+        // ---- listing blocks are shown as they are
+        if line.trim() == "----" {
+            listing = !listing;
+            continue;
+        }
+        if listing {
+            ui.label(RichText::new(line).monospace());
+            continue;
+        }
+
+        let (text, style) = if let Some(heading) = line.strip_prefix("== ") {
+            (heading, Some(TextStyle::Heading))
+        } else if let Some(heading) = line.strip_prefix("= ") {
+            (heading, Some(TextStyle::Heading))
+        } else if let Some(heading) = line.strip_prefix("=== ") {
+            (heading, Some(TextStyle::Name("subject".into())))
+        } else {
+            (line, None)
+        };
+
+        ui.horizontal_wrapped(|ui| {
+            let text = match text.strip_prefix("* ") {
+                Some(item) => {
+                    ui.label("•");
+                    item
+                }
+                None => text,
+            };
+            for segment in nip54::segments(text) {
+                match segment {
+                    WikiSegment::Text(t) => {
+                        let rt = RichText::new(t);
+                        ui.label(match &style {
+                            Some(s) => rt.text_style(s.clone()),
+                            None => rt,
+                        });
+                    }
+                    WikiSegment::Link { target, label } => {
+                        if ui.link(label).on_hover_text(&target).clicked() {
+                            open_topic(app, ctx, &target, None);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Returns whether the popup stays open
+fn render_editor(app: &mut GossipUi, ui: &mut Ui, edit: &mut WikiEdit) -> bool {
+    const DLG_SIZE: Vec2 = vec2(600.0, 500.0);
+    let mut open = true;
+    let ret = widgets::modal_popup(ui, DLG_SIZE, DLG_SIZE, true, |ui| {
+        ui.heading(if edit.fork_of.is_some() {
+            "Fork article"
+        } else {
+            "Write an article"
+        });
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Title:");
+            ui.add(text_edit_line!(app, edit.title).desired_width(400.0));
+        });
+        ui.label(
+            RichText::new(format!("Topic: {}", nip54::normalize(&edit.title)))
+                .weak()
+                .text_style(TextStyle::Small),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Summary:");
+            ui.add(text_edit_line!(app, edit.summary).desired_width(400.0));
+        });
+        ui.add_space(4.0);
+        ui.label("Content (asciidoc, link to other articles with [[Topic]]):");
+        egui::ScrollArea::vertical()
+            .max_height(DLG_SIZE.y - 200.0)
+            .show(ui, |ui| {
+                ui.add(
+                    text_edit_multiline!(app, edit.content)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(15),
+                );
+            });
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
+            app.theme.accent_button_1_style(ui.style_mut());
+            if ui
+                .add_enabled(
+                    !nip54::normalize(&edit.title).is_empty(),
+                    egui::Button::new("Publish"),
+                )
+                .clicked()
+            {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::PublishWikiArticle {
+                        title: edit.title.clone(),
+                        summary: edit.summary.clone(),
+                        content: edit.content.clone(),
+                        fork_of: edit.fork_of,
+                    });
+                app.wiki_author = GLOBALS.identity.public_key();
+                open = false;
+            }
+        });
+    });
+    if ret.inner.clicked() {
+        open = false;
+    }
+    open
+}
+
+/// Go to the wiki page of a topic, showing the version by `author` if given
+pub(super) fn open_topic(
+    app: &mut GossipUi,
+    ctx: &Context,
+    topic: &str,
+    author: Option<PublicKey>,
+) {
+    app.wiki_author = author;
+    app.set_page(ctx, Page::Wiki(nip54::normalize(topic)));
+}
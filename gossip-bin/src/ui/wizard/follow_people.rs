@@ -24,6 +24,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         app.wizard_state.contacts_sought = false;
     }
 
+    if app.wizard_state.starter_packs_sought {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::FetchStarterPacks);
+        app.wizard_state.starter_packs_sought = false;
+    }
+    app.wizard_state.load_starter_packs();
+
     ui.add_space(10.0);
     ui.heading("Followed:");
     let mut limit = 10;
@@ -148,6 +156,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     ui.label("  • Profile (nprofile1..)");
     ui.label("  • DNS ID (user@domain)");
 
+    render_starter_packs(app, ui);
+
     if app.wizard_state.has_private_key {
         ui.add_space(20.0);
         let mut label = RichText::new("  >  Publish and Finish");
@@ -179,3 +189,53 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         }
     }
 }
+
+fn render_starter_packs(app: &mut GossipUi, ui: &mut Ui) {
+    let packs = &app.wizard_state.starter_packs;
+    if packs.is_empty() {
+        return;
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+    ui.heading("Starter Packs:");
+    ui.label("Lists of people others have put together. Following a pack follows everybody in it.");
+    ui.add_space(10.0);
+
+    egui::ScrollArea::vertical()
+        .id_source("wizard_starter_packs")
+        .max_height(250.0)
+        .show(ui, |ui| {
+            for pack in packs.iter().take(20) {
+                ui.horizontal(|ui| {
+                    let new: Vec<PublicKey> = pack
+                        .pubkeys
+                        .iter()
+                        .filter(|pk| !app.wizard_state.followed.contains(pk))
+                        .copied()
+                        .collect();
+                    if ui
+                        .add_enabled(!new.is_empty(), egui::Button::new("follow all"))
+                        .clicked()
+                    {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::FollowPubkeys(
+                            new,
+                            PersonList::Followed,
+                            true,
+                        ));
+                    }
+                    ui.label(RichText::new(&pack.title).strong());
+                    ui.label(format!(
+                        "({} people, by {})",
+                        pack.pubkeys.len(),
+                        gossip_lib::names::best_name_from_pubkey_lookup(&pack.event.pubkey)
+                    ));
+                });
+                if let Some(description) = &pack.description {
+                    ui.label(RichText::new(description).weak());
+                }
+                ui.add_space(6.0);
+            }
+        });
+}
@@ -0,0 +1,110 @@
+use crate::ui::wizard::WizardPage;
+use crate::ui::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use zeroize::Zeroize;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    // If already connected, advance
+    if app.wizard_state.pubkey.is_some() {
+        app.set_page(ctx, Page::Wizard(WizardPage::ReadNostrConfig));
+    }
+
+    ui.add_space(20.0);
+    ui.label("A remote signer (a NIP-46 \"bunker\") keeps your private key somewhere else and signs for you when asked. Your private key never comes to this computer.");
+
+    ui.add_space(20.0);
+    ui.label("Your remote signer will give you a connection string starting with bunker:// that you can paste here. It may ask you to approve the connection.");
+
+    ui.add_space(20.0);
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Enter your bunker URI");
+        let response = text_edit_line!(app, app.bunker_uri)
+            .with_paste()
+            .hint_text("bunker://...")
+            .desired_width(f32::INFINITY)
+            .show(ui)
+            .response;
+        if response.changed() {
+            app.wizard_state.error = None;
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Enter a passphrase to keep the connection encrypted under");
+        let response = text_edit_line!(app, app.password)
+            .password(true)
+            .with_paste()
+            .show(ui)
+            .response;
+        if response.changed() {
+            app.wizard_state.error = None;
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Repeat passphrase to be sure");
+        let response = text_edit_line!(app, app.password2)
+            .password(true)
+            .with_paste()
+            .show(ui)
+            .response;
+        if response.changed() {
+            app.wizard_state.error = None;
+        }
+    });
+
+    let password_mismatch = app.password != app.password2;
+
+    if password_mismatch {
+        ui.add_space(10.0);
+        ui.label(
+            RichText::new("Passwords do not match.").color(app.theme.warning_marker_text_color()),
+        );
+    }
+
+    // error block
+    if let Some(err) = &app.wizard_state.error {
+        ui.add_space(10.0);
+        ui.label(RichText::new(err).color(app.theme.warning_marker_text_color()));
+    }
+
+    let ready = app.bunker_uri.trim().starts_with("bunker://") && !password_mismatch;
+
+    if ready {
+        if app.password.is_empty() {
+            ui.add_space(10.0);
+
+            ui.label(
+                RichText::new("Your password is empty!")
+                    .color(app.theme.warning_marker_text_color()),
+            );
+        }
+
+        ui.add_space(10.0);
+        if ui
+            .button(RichText::new("  >  Connect").color(app.theme.accent_color()))
+            .clicked()
+        {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ConnectBunker {
+                uri: app.bunker_uri.clone(),
+                password: app.password.clone(),
+            });
+            app.bunker_uri = "".to_owned();
+            app.password.zeroize();
+            app.password = "".to_owned();
+            app.password2.zeroize();
+            app.password2 = "".to_owned();
+        }
+    }
+
+    ui.add_space(20.0);
+    if ui.button("  <  Go Back").clicked() {
+        app.set_page(ctx, Page::Wizard(WizardPage::ImportKeys));
+    }
+}
@@ -25,6 +25,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         app.set_page(ctx, Page::Wizard(WizardPage::ImportPublicKey));
     }
 
+    ui.add_space(20.0);
+    if ui
+        .button(RichText::new("  >  Use a Remote Signer (bunker)").color(app.theme.accent_color()))
+        .clicked()
+    {
+        app.set_page(ctx, Page::Wizard(WizardPage::ImportBunker));
+    }
+
     ui.add_space(20.0);
     if ui.button("  <  Go Back").clicked() {
         app.set_page(ctx, Page::Wizard(WizardPage::WelcomeGossip));
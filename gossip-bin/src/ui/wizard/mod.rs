@@ -7,6 +7,7 @@ use gossip_lib::{FeedKind, PersonList, Relay, GLOBALS};
 use nostr_types::RelayUrl;
 
 mod follow_people;
+mod import_bunker;
 mod import_keys;
 mod import_private_key;
 mod import_public_key;
@@ -47,6 +48,7 @@ pub(super) enum WizardPage {
     WelcomeGossip,
     WelcomeNostr,
     ImportKeys,
+    ImportBunker,
     ImportPrivateKey,
     ImportPublicKey,
     ReadNostrConfig,
@@ -61,6 +63,7 @@ impl WizardPage {
             WizardPage::WelcomeGossip => "Welcome to Gossip",
             WizardPage::WelcomeNostr => "Welcome to Nostr",
             WizardPage::ImportKeys => "Import Keys",
+            WizardPage::ImportBunker => "Sign with a Remote Signer",
             WizardPage::ImportPrivateKey => "Import a Private Key",
             WizardPage::ImportPublicKey => "Import only a Public Key",
             WizardPage::ReadNostrConfig => "Read your Nostr Configuration Data",
@@ -189,6 +192,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                 WizardPage::WelcomeGossip => welcome_gossip::update(app, ctx, frame, ui),
                 WizardPage::WelcomeNostr => welcome_nostr::update(app, ctx, frame, ui),
                 WizardPage::ImportKeys => import_keys::update(app, ctx, frame, ui),
+                WizardPage::ImportBunker => import_bunker::update(app, ctx, frame, ui),
                 WizardPage::ImportPrivateKey => import_private_key::update(app, ctx, frame, ui),
                 WizardPage::ImportPublicKey => import_public_key::update(app, ctx, frame, ui),
                 WizardPage::ReadNostrConfig => read_nostr_config::update(app, ctx, frame, ui),
@@ -78,6 +78,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                         });
                     }
                     ui.label(relay.url.as_str());
+                    capabilities_label(app, ui, &relay.url);
                 });
             }
         });
@@ -107,6 +108,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                         });
                     }
                     ui.label(relay.url.as_str());
+                    capabilities_label(app, ui, &relay.url);
                 });
             }
         });
@@ -157,9 +159,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         ui.label("or");
         ui.menu_button("▼ Pick from Top Relays", |ui| {
             for (url, _relay) in relay_options.iter() {
-                if ui.add(Button::new(url.as_str()).wrap(false)).clicked() {
-                    app.wizard_state.relay_url = url.as_str().to_owned();
-                }
+                ui.horizontal(|ui| {
+                    if ui.add(Button::new(url.as_str()).wrap(false)).clicked() {
+                        app.wizard_state.relay_url = url.as_str().to_owned();
+                    }
+                    capabilities_label(app, ui, url);
+                });
             }
         });
     });
@@ -247,3 +252,33 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         };
     }
 }
+
+/// Say whether a relay is open to everybody, going by its NIP-11 document
+fn capabilities_label(app: &mut GossipUi, ui: &mut Ui, url: &RelayUrl) {
+    let caps = match app.wizard_state.relay_capabilities(url) {
+        Some(caps) => caps,
+        None => {
+            ui.label(RichText::new("checking...").weak());
+            return;
+        }
+    };
+
+    if !caps.documented {
+        ui.label(RichText::new("no relay information").weak());
+    } else if caps.payment_required {
+        ui.label(RichText::new("paid").color(app.theme.warning_marker_text_color()))
+            .on_hover_text("This relay requires payment. It is not a good inbox, as people who have not paid cannot reach you there.");
+    } else if caps.auth_required {
+        ui.label(RichText::new("sign-in required").color(app.theme.warning_marker_text_color()))
+            .on_hover_text("This relay only accepts users who authenticate, and may only accept those it knows.");
+    } else {
+        let mut text = "open".to_owned();
+        if caps.supports_auth {
+            text.push_str(", auth");
+        }
+        if caps.supports_search {
+            text.push_str(", search");
+        }
+        ui.label(RichText::new(text).color(Color32::GREEN));
+    }
+}
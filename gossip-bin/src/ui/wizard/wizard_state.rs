@@ -1,6 +1,11 @@
+use gossip_lib::onboarding::{self, RelayCapabilities, StarterPack};
 use gossip_lib::{PersonList, Relay, GLOBALS};
 use nostr_types::{Event, EventKind, PublicKey, RelayUrl};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Starter packs keep coming in from relays after we ask for them
+const STARTER_PACKS_RELOAD: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct WizardState {
@@ -23,6 +28,10 @@ pub struct WizardState {
     pub followed: Vec<PublicKey>,
     pub followed_getting_metadata: HashSet<PublicKey>,
     pub contacts_sought: bool,
+    pub starter_packs_sought: bool,
+    pub starter_packs: Vec<StarterPack>,
+    pub starter_packs_loaded_at: Option<Instant>,
+    pub relay_capabilities: HashMap<RelayUrl, RelayCapabilities>,
     pub generating: bool,
 }
 
@@ -48,6 +57,10 @@ impl Default for WizardState {
             followed: Vec::new(),
             followed_getting_metadata: HashSet::new(),
             contacts_sought: true,
+            starter_packs_sought: true,
+            starter_packs: Vec::new(),
+            starter_packs_loaded_at: None,
+            relay_capabilities: HashMap::new(),
             generating: false,
         }
     }
@@ -104,6 +117,26 @@ impl WizardState {
         }
     }
 
+    /// Reload the starter packs we have, if they have not been loaded lately
+    pub fn load_starter_packs(&mut self) {
+        if matches!(self.starter_packs_loaded_at, Some(t) if t.elapsed() < STARTER_PACKS_RELOAD) {
+            return;
+        }
+        self.starter_packs = onboarding::starter_packs().unwrap_or_default();
+        self.starter_packs_loaded_at = Some(Instant::now());
+    }
+
+    /// What we know of a relay's capabilities, only asking gossip-lib until
+    /// it knows
+    pub fn relay_capabilities(&mut self, url: &RelayUrl) -> Option<RelayCapabilities> {
+        if let Some(caps) = self.relay_capabilities.get(url) {
+            return Some(caps.clone());
+        }
+        let caps = onboarding::relay_capabilities(url)?;
+        self.relay_capabilities.insert(url.clone(), caps.clone());
+        Some(caps)
+    }
+
     #[inline]
     pub fn need_discovery_relays(&self) -> bool {
         !self
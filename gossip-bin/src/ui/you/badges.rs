@@ -0,0 +1,106 @@
+use super::GossipUi;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::badges::{self, BadgeDefinition};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use nostr_types::{EventAddr, Id};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("My Badges");
+    ui.add_space(10.0);
+    ui.label("Badges awarded to you (NIP-58) are only shown on your profile once you accept them.");
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    app.vert_scroll_area()
+        .id_source("your_badges")
+        .show(ui, |ui| {
+            ui.heading("Accepted");
+            ui.add_space(10.0);
+
+            if app.profile_badges.is_empty() {
+                ui.label("You have not accepted any badges.");
+            }
+
+            let mut move_up: Option<usize> = None;
+            let mut remove: Option<usize> = None;
+            let len = app.profile_badges.len();
+            for (i, (addr, _award)) in app.profile_badges.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(i > 0, egui::Button::new("▲")).clicked() {
+                        move_up = Some(i);
+                    }
+                    if ui
+                        .add_enabled(i + 1 < len, egui::Button::new("▼"))
+                        .clicked()
+                    {
+                        move_up = Some(i + 1);
+                    }
+                    if ui.button("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                    badge_label(ui, addr);
+                });
+            }
+            if let Some(i) = move_up {
+                app.profile_badges.swap(i - 1, i);
+            }
+            if let Some(i) = remove {
+                app.profile_badges.remove(i);
+            }
+
+            ui.add_space(10.0);
+            if ui.button("Publish").clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::PushProfileBadges(
+                        app.profile_badges.clone(),
+                    ));
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            ui.heading("Awarded");
+            ui.add_space(10.0);
+
+            let awarded: Vec<(EventAddr, Id)> = badges::get_badges_awarded_to_us()
+                .unwrap_or_default()
+                .drain(..)
+                .filter(|(addr, _)| !app.profile_badges.iter().any(|(a, _)| a == addr))
+                .collect();
+
+            if awarded.is_empty() {
+                ui.label("There are no other badges awarded to you.");
+            }
+
+            for (addr, award) in awarded {
+                ui.horizontal(|ui| {
+                    if ui.button("Accept").clicked() {
+                        app.profile_badges.push((addr.clone(), award));
+                    }
+                    badge_label(ui, &addr);
+                });
+            }
+        });
+}
+
+fn badge_label(ui: &mut Ui, addr: &EventAddr) {
+    let issuer = gossip_lib::names::best_name_from_pubkey_lookup(&addr.author);
+    match BadgeDefinition::load(addr) {
+        Ok(Some(badge)) => {
+            let response = ui.label(RichText::new(&badge.name).strong());
+            if let Some(description) = &badge.description {
+                response.on_hover_text(description);
+            }
+        }
+        _ => {
+            ui.label(RichText::new(&addr.d).strong());
+        }
+    };
+    ui.label(RichText::new(format!("from {}", issuer)).weak());
+}
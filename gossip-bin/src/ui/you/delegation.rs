@@ -3,6 +3,7 @@ use crate::ui::widgets::CopyButton;
 use eframe::egui;
 use egui::{Context, Ui};
 use gossip_lib::GLOBALS;
+use nostr_types::{EventKind, PublicKey, Unixtime};
 use tokio::task;
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -71,7 +72,7 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
                         GLOBALS
                             .status_queue
                             .write()
-                            .write(format!("Could not parse tag {e}"));
+                            .write_error(format!("Could not parse tag {e}"));
                     }
                     Ok(_) => {
                         // reset entry field
@@ -95,4 +96,162 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         }
     });
     ui.separator();
+
+    ui.add_space(24.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Delegator");
+    });
+    ui.add_space(10.0);
+    ui.label("Let another key, such as that of another device, post on my behalf (NIP-26)");
+    ui.add_space(12.0);
+
+    if !GLOBALS.identity.is_unlocked() {
+        ui.label("Unlock your key to issue delegations");
+    } else {
+        render_issue_delegation(app, ui);
+    }
+
+    ui.add_space(12.0);
+    render_issued_delegations(app, ui);
+}
+
+pub(in crate::ui) fn load_issued_delegations(app: &mut GossipUi) {
+    app.issued_delegations = gossip_lib::issued_delegations().map_err(|e| e.to_string());
+}
+
+fn render_issue_delegation(app: &mut GossipUi, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Delegatee public key:");
+        ui.add(
+            text_edit_line!(app, app.delegation_delegatee)
+                .hint_text("npub1 or hex")
+                .desired_width(400.0),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Kind:");
+        ui.add(
+            text_edit_line!(app, app.delegation_kind)
+                .hint_text("empty for any")
+                .desired_width(200.0),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Valid for days:");
+        ui.add(
+            text_edit_line!(app, app.delegation_days)
+                .hint_text("empty for no end")
+                .desired_width(60.0),
+        );
+    });
+
+    if ui.button("Issue Delegation").clicked() {
+        let input = app.delegation_delegatee.trim();
+        let delegatee = match PublicKey::try_from_bech32_string(input, true)
+            .or_else(|_| PublicKey::try_from_hex_string(input, true))
+        {
+            Ok(pk) => pk,
+            Err(_) => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write_error("That is not a public key".to_owned());
+                return;
+            }
+        };
+        // One kind only, as every condition of a delegation must hold
+        let kind = match app.delegation_kind.trim() {
+            "" => None,
+            k => match k.parse::<u32>() {
+                Ok(k) => Some(EventKind::from(k)),
+                Err(_) => {
+                    GLOBALS.status_queue.write().write_error(format!(
+                        "Not a kind number: {}. A delegation can only be for one kind.",
+                        k
+                    ));
+                    return;
+                }
+            },
+        };
+        let now = Unixtime::now().unwrap();
+        let created_before = match app.delegation_days.trim() {
+            "" => None,
+            days => match days.parse::<i64>() {
+                Ok(days) => Some(Unixtime(now.0 + days * 60 * 60 * 24)),
+                Err(_) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Not a number of days: {}", days));
+                    return;
+                }
+            },
+        };
+
+        match gossip_lib::issue_delegation(delegatee, kind, Some(now), created_before) {
+            Ok(issued) => {
+                ui.output_mut(|o| o.copied_text = issued.tag.clone());
+                app.delegation_delegatee.clear();
+                load_issued_delegations(app);
+                GLOBALS.status_queue.write().write(
+                    "Delegation issued. Its tag was copied, to set on the other device.".to_owned(),
+                );
+            }
+            Err(e) => GLOBALS.status_queue.write().write_error(e.to_string()),
+        }
+    }
+}
+
+fn render_issued_delegations(app: &mut GossipUi, ui: &mut Ui) {
+    let issued = match &app.issued_delegations {
+        Ok(issued) => issued.clone(),
+        Err(e) => {
+            ui.label(format!("Could not read issued delegations: {}", e));
+            return;
+        }
+    };
+    if issued.is_empty() {
+        ui.label("No delegations issued");
+        return;
+    }
+
+    ui.label("Issued delegations:");
+    for delegation in issued.iter().rev() {
+        ui.separator();
+        ui.horizontal_wrapped(|ui| {
+            ui.label(gossip_lib::names::best_name_from_pubkey_lookup(
+                &delegation.delegatee,
+            ));
+            ui.label(
+                egui::RichText::new(if delegation.conditions.is_empty() {
+                    "(any kind, any time)"
+                } else {
+                    delegation.conditions.as_str()
+                })
+                .monospace()
+                .small(),
+            );
+            if delegation.revoked {
+                ui.label(egui::RichText::new("revoked").weak());
+            } else {
+                if ui
+                    .add(CopyButton::new())
+                    .on_hover_text("Copy Delegation Tag")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = delegation.tag.clone());
+                }
+                if ui
+                    .button("Revoke")
+                    .on_hover_text("Stop vouching for it here. Others who already have the delegation can still honor it until its conditions run out.")
+                    .clicked()
+                {
+                    if let Err(e) = gossip_lib::revoke_delegation(&delegation.tag) {
+                        GLOBALS.status_queue.write().write_error(e.to_string());
+                    }
+                    load_issued_delegations(app);
+                }
+            }
+        });
+    }
 }
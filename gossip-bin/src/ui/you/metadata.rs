@@ -5,7 +5,7 @@ use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::Relay;
 use gossip_lib::GLOBALS;
 use lazy_static::lazy_static;
-use nostr_types::Metadata;
+use nostr_types::{Metadata, Unixtime};
 use serde_json::map::Map;
 use serde_json::value::Value;
 
@@ -49,12 +49,11 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     };
 
     if let Some(metadata_created_at) = you.metadata_created_at {
-        if let Ok(stamp) = time::OffsetDateTime::from_unix_timestamp(metadata_created_at) {
-            if let Ok(formatted) = stamp.format(&time::format_description::well_known::Rfc2822) {
-                ui.label(format!("Date Stamp of Fetched Metadata is {}", formatted));
-                ui.add_space(18.0);
-            }
-        }
+        ui.label(format!(
+            "Date Stamp of Fetched Metadata is {}",
+            crate::timestamp::absolute_with_zone(Unixtime(metadata_created_at))
+        ));
+        ui.add_space(18.0);
     }
 
     let edit_color = app.theme.input_text_color();
@@ -147,6 +146,83 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
             }
         });
     });
+
+    if GLOBALS.identity.is_unlocked() {
+        ui.add_space(18.0);
+        ui.separator();
+        ui.add_space(10.0);
+        status_composer(app, ui);
+    }
+}
+
+const STATUS_EXPIRATIONS: [(Option<i64>, &str); 4] = [
+    (Some(60 * 60), "1 hour"),
+    (Some(60 * 60 * 4), "4 hours"),
+    (Some(60 * 60 * 24), "1 day"),
+    (None, "never"),
+];
+
+fn status_composer(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("My status");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Status: ");
+        ui.add(text_edit_line!(app, app.status_content).desired_width(300.0));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Link (optional): ");
+        ui.add(text_edit_line!(app, app.status_link).desired_width(300.0));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Expires after: ");
+        let selected = STATUS_EXPIRATIONS
+            .iter()
+            .find(|(secs, _)| *secs == app.status_expires_in)
+            .map(|(_, label)| *label)
+            .unwrap_or("never");
+        egui::ComboBox::from_id_source("StatusExpiration")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (secs, label) in STATUS_EXPIRATIONS.iter() {
+                    ui.selectable_value(&mut app.status_expires_in, *secs, *label);
+                }
+            });
+    });
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        if ui.button("Set").clicked() && !app.status_content.trim().is_empty() {
+            let expiration = app
+                .status_expires_in
+                .map(|secs| Unixtime(Unixtime::now().unwrap().0 + secs));
+            let link = if app.status_link.trim().is_empty() {
+                None
+            } else {
+                Some(app.status_link.trim().to_owned())
+            };
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SetUserStatus {
+                status_type: "general".to_owned(),
+                content: app.status_content.trim().to_owned(),
+                link,
+                expiration,
+            });
+        }
+        if ui
+            .button("Clear")
+            .on_hover_text("Publishes an empty status, which clears it.")
+            .clicked()
+        {
+            app.status_content.clear();
+            app.status_link.clear();
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SetUserStatus {
+                status_type: "general".to_owned(),
+                content: "".to_owned(),
+                link: None,
+                expiration: None,
+            });
+        }
+    });
 }
 
 fn view_line(ui: &mut Ui, field: &str, data: Option<&String>) {
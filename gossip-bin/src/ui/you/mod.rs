@@ -4,14 +4,18 @@ use eframe::egui;
 use egui::style::Margin;
 use egui::{Color32, Context, Frame, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::{Globals, GLOBALS};
-use nostr_types::{KeySecurity, PublicKeyHex};
+use gossip_lib::{Globals, Profile, GLOBALS};
+use nostr_types::{KeySecurity, PublicKey, PublicKeyHex};
+use std::sync::atomic::Ordering;
 use zeroize::Zeroize;
 
+mod badges;
 mod delegation;
 mod metadata;
 mod nostr_connect;
 
+pub(in crate::ui) use delegation::load_issued_delegations;
+
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     if app.page == Page::YourKeys {
         ui.add_space(10.0);
@@ -24,7 +28,27 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         app.vert_scroll_area()
             .id_source("your_keys")
             .show(ui, |ui| {
-                if GLOBALS.identity.is_unlocked() {
+                if GLOBALS.identity.uses_bunker() {
+                    ui.heading("Ready to sign events with your remote signer");
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    show_pub_key_detail(app, ctx, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    show_bunker_detail(ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    offer_change_password(app, ui);
+                } else if GLOBALS.identity.is_unlocked() {
                     ui.heading("Ready to sign events");
 
                     ui.add_space(10.0);
@@ -97,6 +121,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(10.0);
 
+                    offer_connect_bunker(app, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     offer_delete_or_import_pub_key(app, ui);
                 } else {
                     offer_generate(app, ui);
@@ -111,8 +141,26 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(10.0);
 
+                    offer_connect_bunker(app, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     offer_delete_or_import_pub_key(app, ui);
                 }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                offer_other_identities(app, ui);
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                offer_profiles(app, ui);
             });
     } else if app.page == Page::YourMetadata {
         metadata::update(app, ctx, _frame, ui);
@@ -120,6 +168,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         delegation::update(app, ctx, _frame, ui);
     } else if app.page == Page::YourNostrConnect {
         nostr_connect::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourBadges {
+        badges::update(app, ctx, _frame, ui);
     }
 }
 
@@ -241,7 +291,7 @@ fn offer_change_password(app: &mut GossipUi, ui: &mut Ui) {
             GLOBALS
                 .status_queue
                 .write()
-                .write("Passphrases do not match.".to_owned());
+                .write_warning("Passphrases do not match.".to_owned());
             app.password2.zeroize();
             app.password2 = "".to_owned();
             app.password3.zeroize();
@@ -286,7 +336,7 @@ fn offer_export_priv_key(app: &mut GossipUi, ui: &mut Ui) {
                     "Exported key has been printed to the console standard output.".to_owned(),
                 );
             }
-            Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            Err(e) => GLOBALS.status_queue.write().write_error(format!("{}", e)),
         }
         app.password.zeroize();
         app.password = "".to_owned();
@@ -300,7 +350,7 @@ fn offer_export_priv_key(app: &mut GossipUi, ui: &mut Ui) {
                     "Exported key has been printed to the console standard output.".to_owned(),
                 );
             }
-            Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            Err(e) => GLOBALS.status_queue.write().write_error(format!("{}", e)),
         }
         app.password.zeroize();
         app.password = "".to_owned();
@@ -422,6 +472,183 @@ fn offer_delete_or_import_pub_key(app: &mut GossipUi, ui: &mut Ui) {
     }
 }
 
+fn show_bunker_detail(ui: &mut Ui) {
+    let bunker = match GLOBALS.identity.bunker() {
+        Some(b) => b,
+        None => return,
+    };
+
+    ui.heading("Remote Signer");
+    ui.add_space(10.0);
+
+    ui.label(format!(
+        "Signer key: {}",
+        bunker.bunker.remote_pubkey.as_bech32_string()
+    ));
+    for relay in bunker.bunker.relays.iter() {
+        ui.label(format!("Relay: {}", relay));
+    }
+
+    ui.add_space(10.0);
+    ui.label("Disconnecting forgets the remote signer along with your public key.");
+    if ui.button("Disconnect").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::DeletePub);
+    }
+}
+
+fn offer_connect_bunker(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Use a Remote Signer");
+    ui.add_space(10.0);
+
+    ui.label("A remote signer (a NIP-46 \"bunker\") signs for you so your private key never comes to this computer.");
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Enter your bunker URI");
+        ui.add(
+            text_edit_line!(app, app.bunker_uri)
+                .hint_text("bunker://...")
+                .desired_width(f32::INFINITY),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Enter a passphrase to keep the connection encrypted under");
+        ui.add(text_edit_line!(app, app.password).password(true));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Repeat passphrase to be sure");
+        ui.add(text_edit_line!(app, app.password2).password(true));
+    });
+
+    if ui.button("Connect").clicked() {
+        if app.password != app.password2 {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("Passphrases do not match.".to_owned());
+        } else {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ConnectBunker {
+                uri: app.bunker_uri.clone(),
+                password: app.password.clone(),
+            });
+            app.bunker_uri = "".to_owned();
+        }
+        app.password.zeroize();
+        app.password = "".to_owned();
+        app.password2.zeroize();
+        app.password2 = "".to_owned();
+    }
+}
+
+fn offer_other_identities(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Other Identities");
+    ui.add_space(10.0);
+
+    ui.label("If you have other nostr identities, add their public keys here to see what is addressed to them under \"All inboxes\". This does not let you post as them.");
+    ui.add_space(10.0);
+
+    for pubkey in app.other_identities.clone() {
+        ui.horizontal(|ui| {
+            ui.label(gossip_lib::names::best_name_from_pubkey_lookup(&pubkey));
+            ui.label(pubkey.as_bech32_string());
+            if ui.button("Remove").clicked() {
+                if let Err(e) = gossip_lib::identities::remove(&pubkey) {
+                    GLOBALS.status_queue.write().write_error(format!("{}", e));
+                }
+                app.other_identities = gossip_lib::identities::others();
+            }
+        });
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Add an identity");
+        ui.add(
+            text_edit_line!(app, app.other_identity)
+                .hint_text("npub1 or hex")
+                .desired_width(400.0),
+        );
+        if ui.button("Add").clicked() {
+            let input = app.other_identity.trim();
+            match PublicKey::try_from_bech32_string(input, true)
+                .or_else(|_| PublicKey::try_from_hex_string(input, true))
+            {
+                Ok(pubkey) => {
+                    if let Err(e) = gossip_lib::identities::add(pubkey) {
+                        GLOBALS.status_queue.write().write_error(format!("{}", e));
+                    }
+                    app.other_identities = gossip_lib::identities::others();
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::UpdateMetadata(pubkey));
+                    app.other_identity.clear();
+                }
+                Err(_) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error("That is not a public key.".to_owned());
+                }
+            }
+        }
+    });
+}
+
+fn offer_profiles(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Profiles");
+    ui.add_space(10.0);
+
+    ui.label("Each profile has its own storage, settings and keys, such as one for work and one for personal use. Switching restarts gossip in the other profile.");
+    ui.add_space(10.0);
+
+    let current = match Profile::current() {
+        Ok(profile) => profile.name,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+    let mut profiles: Vec<Option<String>> = vec![None];
+    match Profile::list() {
+        Ok(list) => profiles.extend(list.into_iter().map(Some)),
+        Err(e) => {
+            ui.label(format!("{}", e));
+        }
+    }
+
+    for profile in profiles {
+        ui.horizontal(|ui| {
+            ui.label(profile.as_deref().unwrap_or("(default)"));
+            if profile == current {
+                ui.label("(in use)");
+            } else if ui.button("Switch").clicked() {
+                switch_profile(profile.as_deref());
+            }
+        });
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("New profile");
+        ui.add(
+            text_edit_line!(app, app.new_profile)
+                .hint_text("name")
+                .desired_width(200.0),
+        );
+        if ui.button("Create and Switch").clicked() {
+            let name = app.new_profile.trim().to_owned();
+            app.new_profile.clear();
+            switch_profile(Some(&name));
+        }
+    });
+}
+
+fn switch_profile(name: Option<&str>) {
+    match Profile::switch_to(name) {
+        Ok(()) => GLOBALS.shutting_down.store(true, Ordering::Relaxed),
+        Err(e) => GLOBALS.status_queue.write().write_error(format!("{}", e)),
+    }
+}
+
 pub(super) fn offer_delete(app: &mut GossipUi, ui: &mut Ui) {
     ui.heading("DELETE This Identity");
 
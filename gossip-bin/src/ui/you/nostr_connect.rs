@@ -89,7 +89,7 @@ fn setup_unconnected_service(app: &mut GossipUi, ui: &mut Ui) {
         // GINA - server needs to send 'connect' to the client
     },
         Err(e) => {
-        GLOBALS.status_queue.write().write(format!("{}", e));
+        GLOBALS.status_queue.write().write_error(format!("{}", e));
     }
     }
         app.nostr_connect_string = "".to_owned();
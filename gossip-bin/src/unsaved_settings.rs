@@ -37,9 +37,15 @@ pub struct UnsavedSettings {
     pub public_key: Option<PublicKey>,
     pub log_n: u8,
     pub login_at_startup: bool,
+    pub settings_sync: bool,
+    pub settings_sync_excluded: Vec<String>,
 
     // Network settings
     pub offline: bool,
+    pub use_proxy: bool,
+    pub proxy_host: String,
+    pub proxy_port: u16,
+    pub proxy_direct_relays: String,
     pub load_avatars: bool,
     pub load_media: bool,
     pub check_nip05: bool,
@@ -60,27 +66,50 @@ pub struct UnsavedSettings {
     // Event Selection
     pub reposts: bool,
     pub show_long_form: bool,
+    pub show_git_events: bool,
+    pub show_torrents: bool,
+    pub show_live_activities: bool,
+    pub show_calendar_events: bool,
     pub show_mentions: bool,
     pub direct_messages: bool,
     pub future_allowance_secs: u64,
 
     // Event Content Settings
     pub hide_mutes_entirely: bool,
+    pub mute_list_sync: bool,
     pub reactions: bool,
     pub enable_zap_receipts: bool,
+    pub show_user_status: bool,
+    pub verify_timestamps: bool,
     pub show_media: bool,
     pub approve_content_warning: bool,
     pub show_deleted_events: bool,
     pub avoid_spam_on_unsafe_relays: bool,
+    pub min_pow_unknown_authors: u8,
+    pub exclude_labels: String,
+    pub include_labels: String,
+    pub mute_when_reporting: bool,
+    pub hide_bridged_posts: bool,
+    pub follow_back_enabled: bool,
+    pub follow_back_automatically: bool,
+    pub follow_back_max_wot_distance: u8,
+    pub follow_back_require_nip05: bool,
+    pub follow_back_min_account_age_days: u64,
+    pub read_later_prefetch_media: bool,
 
     // Posting Settings
     pub pow: u8,
     pub set_client_tag: bool,
     pub set_user_agent: bool,
+    pub relay_list_auto_advertise: bool,
+    pub web_link_base: String,
+    pub media_server: String,
+    pub follow_import_directories: String,
     pub delegatee_tag: String,
 
     // UI settings
     pub max_fps: u32,
+    pub memory_budget_mb: u32,
     pub recompute_feed_periodically: bool,
     pub feed_recompute_interval_ms: u32,
     pub theme_variant: String,
@@ -93,6 +122,18 @@ pub struct UnsavedSettings {
     pub image_resize_algorithm: String,
     pub inertial_scrolling: bool,
     pub mouse_acceleration: f32,
+    pub language: String,
+    pub relative_timestamps: bool,
+    pub timestamps_24_hour: bool,
+    pub timezone_offset_minutes: Option<i16>,
+    pub sound_on_mention: bool,
+    pub sound_on_dm: bool,
+    pub sound_on_zap: bool,
+    pub sound_file_mention: String,
+    pub sound_file_dm: String,
+    pub sound_file_zap: String,
+    pub sound_quiet_start_hour: u8,
+    pub sound_quiet_end_hour: u8,
 
     // Staletime settings
     pub relay_list_becomes_stale_hours: u64,
@@ -119,10 +160,26 @@ pub struct UnsavedSettings {
     pub fetcher_host_exclusion_on_med_error_secs: u64,
     pub fetcher_host_exclusion_on_high_error_secs: u64,
     pub nip11_lines_to_output_on_error: usize,
+    pub fetcher_nip98_hosts: String,
+    pub log_to_file: bool,
+    pub log_filter: String,
+    pub log_file_max_size_kb: u64,
+    pub crash_reports: bool,
+    pub metrics_endpoint: bool,
+    pub metrics_port: u16,
+    pub syndication_endpoint: bool,
+    pub syndication_port: u16,
 
     // Database settings
     pub prune_period_days: u64,
     pub cache_prune_period_days: u64,
+    pub prune_keep_followed: bool,
+    pub prune_automatically: bool,
+    pub backup_enabled: bool,
+    pub backup_interval_hours: u64,
+    pub backup_keep: u32,
+    pub backup_directory: String,
+    pub backup_include_key: bool,
 }
 
 impl Default for UnsavedSettings {
@@ -131,7 +188,13 @@ impl Default for UnsavedSettings {
             public_key: default_setting!(public_key),
             log_n: default_setting!(log_n),
             login_at_startup: default_setting!(login_at_startup),
+            settings_sync: default_setting!(settings_sync),
+            settings_sync_excluded: default_setting!(settings_sync_excluded),
             offline: default_setting!(offline),
+            use_proxy: default_setting!(use_proxy),
+            proxy_host: default_setting!(proxy_host),
+            proxy_port: default_setting!(proxy_port),
+            proxy_direct_relays: default_setting!(proxy_direct_relays),
             load_avatars: default_setting!(load_avatars),
             load_media: default_setting!(load_media),
             check_nip05: default_setting!(check_nip05),
@@ -148,21 +211,44 @@ impl Default for UnsavedSettings {
             overlap: default_setting!(overlap),
             reposts: default_setting!(reposts),
             show_long_form: default_setting!(show_long_form),
+            show_git_events: default_setting!(show_git_events),
+            show_torrents: default_setting!(show_torrents),
+            show_live_activities: default_setting!(show_live_activities),
+            show_calendar_events: default_setting!(show_calendar_events),
             show_mentions: default_setting!(show_mentions),
             direct_messages: default_setting!(direct_messages),
             future_allowance_secs: default_setting!(future_allowance_secs),
             hide_mutes_entirely: default_setting!(hide_mutes_entirely),
+            mute_list_sync: default_setting!(mute_list_sync),
             reactions: default_setting!(reactions),
             enable_zap_receipts: default_setting!(enable_zap_receipts),
+            show_user_status: default_setting!(show_user_status),
+            verify_timestamps: default_setting!(verify_timestamps),
             show_media: default_setting!(show_media),
             approve_content_warning: default_setting!(approve_content_warning),
             show_deleted_events: default_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: default_setting!(avoid_spam_on_unsafe_relays),
+            min_pow_unknown_authors: default_setting!(min_pow_unknown_authors),
+            exclude_labels: default_setting!(exclude_labels),
+            include_labels: default_setting!(include_labels),
+            mute_when_reporting: default_setting!(mute_when_reporting),
+            hide_bridged_posts: default_setting!(hide_bridged_posts),
+            follow_back_enabled: default_setting!(follow_back_enabled),
+            follow_back_automatically: default_setting!(follow_back_automatically),
+            follow_back_max_wot_distance: default_setting!(follow_back_max_wot_distance),
+            follow_back_require_nip05: default_setting!(follow_back_require_nip05),
+            follow_back_min_account_age_days: default_setting!(follow_back_min_account_age_days),
+            read_later_prefetch_media: default_setting!(read_later_prefetch_media),
             pow: default_setting!(pow),
             set_client_tag: default_setting!(set_client_tag),
             set_user_agent: default_setting!(set_user_agent),
+            relay_list_auto_advertise: default_setting!(relay_list_auto_advertise),
+            web_link_base: default_setting!(web_link_base),
+            media_server: default_setting!(media_server),
+            follow_import_directories: default_setting!(follow_import_directories),
             delegatee_tag: default_setting!(delegatee_tag),
             max_fps: default_setting!(max_fps),
+            memory_budget_mb: default_setting!(memory_budget_mb),
             recompute_feed_periodically: default_setting!(recompute_feed_periodically),
             feed_recompute_interval_ms: default_setting!(feed_recompute_interval_ms),
             theme_variant: default_setting!(theme_variant),
@@ -175,6 +261,18 @@ impl Default for UnsavedSettings {
             image_resize_algorithm: default_setting!(image_resize_algorithm),
             inertial_scrolling: default_setting!(inertial_scrolling),
             mouse_acceleration: default_setting!(mouse_acceleration),
+            language: default_setting!(language),
+            relative_timestamps: default_setting!(relative_timestamps),
+            timestamps_24_hour: default_setting!(timestamps_24_hour),
+            timezone_offset_minutes: default_setting!(timezone_offset_minutes),
+            sound_on_mention: default_setting!(sound_on_mention),
+            sound_on_dm: default_setting!(sound_on_dm),
+            sound_on_zap: default_setting!(sound_on_zap),
+            sound_file_mention: default_setting!(sound_file_mention),
+            sound_file_dm: default_setting!(sound_file_dm),
+            sound_file_zap: default_setting!(sound_file_zap),
+            sound_quiet_start_hour: default_setting!(sound_quiet_start_hour),
+            sound_quiet_end_hour: default_setting!(sound_quiet_end_hour),
             relay_list_becomes_stale_hours: default_setting!(relay_list_becomes_stale_hours),
             metadata_becomes_stale_hours: default_setting!(metadata_becomes_stale_hours),
             nip05_becomes_stale_if_valid_hours: default_setting!(
@@ -205,8 +303,24 @@ impl Default for UnsavedSettings {
                 fetcher_host_exclusion_on_high_error_secs
             ),
             nip11_lines_to_output_on_error: default_setting!(nip11_lines_to_output_on_error),
+            fetcher_nip98_hosts: default_setting!(fetcher_nip98_hosts),
+            log_to_file: default_setting!(log_to_file),
+            log_filter: default_setting!(log_filter),
+            log_file_max_size_kb: default_setting!(log_file_max_size_kb),
+            crash_reports: default_setting!(crash_reports),
+            metrics_endpoint: default_setting!(metrics_endpoint),
+            metrics_port: default_setting!(metrics_port),
+            syndication_endpoint: default_setting!(syndication_endpoint),
+            syndication_port: default_setting!(syndication_port),
             prune_period_days: default_setting!(prune_period_days),
             cache_prune_period_days: default_setting!(prune_period_days),
+            prune_keep_followed: default_setting!(prune_keep_followed),
+            prune_automatically: default_setting!(prune_automatically),
+            backup_enabled: default_setting!(backup_enabled),
+            backup_interval_hours: default_setting!(backup_interval_hours),
+            backup_keep: default_setting!(backup_keep),
+            backup_directory: default_setting!(backup_directory),
+            backup_include_key: default_setting!(backup_include_key),
         }
     }
 }
@@ -217,7 +331,13 @@ impl UnsavedSettings {
             public_key: load_setting!(public_key),
             log_n: load_setting!(log_n),
             login_at_startup: load_setting!(login_at_startup),
+            settings_sync: load_setting!(settings_sync),
+            settings_sync_excluded: load_setting!(settings_sync_excluded),
             offline: load_setting!(offline),
+            use_proxy: load_setting!(use_proxy),
+            proxy_host: load_setting!(proxy_host),
+            proxy_port: load_setting!(proxy_port),
+            proxy_direct_relays: load_setting!(proxy_direct_relays),
             load_avatars: load_setting!(load_avatars),
             load_media: load_setting!(load_media),
             check_nip05: load_setting!(check_nip05),
@@ -232,21 +352,44 @@ impl UnsavedSettings {
             overlap: load_setting!(overlap),
             reposts: load_setting!(reposts),
             show_long_form: load_setting!(show_long_form),
+            show_git_events: load_setting!(show_git_events),
+            show_torrents: load_setting!(show_torrents),
+            show_live_activities: load_setting!(show_live_activities),
+            show_calendar_events: load_setting!(show_calendar_events),
             show_mentions: load_setting!(show_mentions),
             direct_messages: load_setting!(direct_messages),
             future_allowance_secs: load_setting!(future_allowance_secs),
             hide_mutes_entirely: load_setting!(hide_mutes_entirely),
+            mute_list_sync: load_setting!(mute_list_sync),
             reactions: load_setting!(reactions),
             enable_zap_receipts: load_setting!(enable_zap_receipts),
+            show_user_status: load_setting!(show_user_status),
+            verify_timestamps: load_setting!(verify_timestamps),
             show_media: load_setting!(show_media),
             approve_content_warning: load_setting!(approve_content_warning),
             show_deleted_events: load_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: load_setting!(avoid_spam_on_unsafe_relays),
+            min_pow_unknown_authors: load_setting!(min_pow_unknown_authors),
+            exclude_labels: load_setting!(exclude_labels),
+            include_labels: load_setting!(include_labels),
+            mute_when_reporting: load_setting!(mute_when_reporting),
+            hide_bridged_posts: load_setting!(hide_bridged_posts),
+            follow_back_enabled: load_setting!(follow_back_enabled),
+            follow_back_automatically: load_setting!(follow_back_automatically),
+            follow_back_max_wot_distance: load_setting!(follow_back_max_wot_distance),
+            follow_back_require_nip05: load_setting!(follow_back_require_nip05),
+            follow_back_min_account_age_days: load_setting!(follow_back_min_account_age_days),
+            read_later_prefetch_media: load_setting!(read_later_prefetch_media),
             pow: load_setting!(pow),
             set_client_tag: load_setting!(set_client_tag),
             set_user_agent: load_setting!(set_user_agent),
+            relay_list_auto_advertise: load_setting!(relay_list_auto_advertise),
+            web_link_base: load_setting!(web_link_base),
+            media_server: load_setting!(media_server),
+            follow_import_directories: load_setting!(follow_import_directories),
             delegatee_tag: load_setting!(delegatee_tag),
             max_fps: load_setting!(max_fps),
+            memory_budget_mb: load_setting!(memory_budget_mb),
             recompute_feed_periodically: load_setting!(recompute_feed_periodically),
             feed_recompute_interval_ms: load_setting!(feed_recompute_interval_ms),
             theme_variant: load_setting!(theme_variant),
@@ -259,6 +402,18 @@ impl UnsavedSettings {
             image_resize_algorithm: load_setting!(image_resize_algorithm),
             inertial_scrolling: load_setting!(inertial_scrolling),
             mouse_acceleration: load_setting!(mouse_acceleration),
+            language: load_setting!(language),
+            relative_timestamps: load_setting!(relative_timestamps),
+            timestamps_24_hour: load_setting!(timestamps_24_hour),
+            timezone_offset_minutes: load_setting!(timezone_offset_minutes),
+            sound_on_mention: load_setting!(sound_on_mention),
+            sound_on_dm: load_setting!(sound_on_dm),
+            sound_on_zap: load_setting!(sound_on_zap),
+            sound_file_mention: load_setting!(sound_file_mention),
+            sound_file_dm: load_setting!(sound_file_dm),
+            sound_file_zap: load_setting!(sound_file_zap),
+            sound_quiet_start_hour: load_setting!(sound_quiet_start_hour),
+            sound_quiet_end_hour: load_setting!(sound_quiet_end_hour),
             relay_list_becomes_stale_hours: load_setting!(relay_list_becomes_stale_hours),
             metadata_becomes_stale_hours: load_setting!(metadata_becomes_stale_hours),
             nip05_becomes_stale_if_valid_hours: load_setting!(nip05_becomes_stale_if_valid_hours),
@@ -287,8 +442,24 @@ impl UnsavedSettings {
                 fetcher_host_exclusion_on_high_error_secs
             ),
             nip11_lines_to_output_on_error: load_setting!(nip11_lines_to_output_on_error),
+            fetcher_nip98_hosts: load_setting!(fetcher_nip98_hosts),
+            log_to_file: load_setting!(log_to_file),
+            log_filter: load_setting!(log_filter),
+            log_file_max_size_kb: load_setting!(log_file_max_size_kb),
+            crash_reports: load_setting!(crash_reports),
+            metrics_endpoint: load_setting!(metrics_endpoint),
+            metrics_port: load_setting!(metrics_port),
+            syndication_endpoint: load_setting!(syndication_endpoint),
+            syndication_port: load_setting!(syndication_port),
             prune_period_days: load_setting!(prune_period_days),
             cache_prune_period_days: load_setting!(cache_prune_period_days),
+            prune_keep_followed: load_setting!(prune_keep_followed),
+            prune_automatically: load_setting!(prune_automatically),
+            backup_enabled: load_setting!(backup_enabled),
+            backup_interval_hours: load_setting!(backup_interval_hours),
+            backup_keep: load_setting!(backup_keep),
+            backup_directory: load_setting!(backup_directory),
+            backup_include_key: load_setting!(backup_include_key),
         }
     }
 
@@ -297,7 +468,13 @@ impl UnsavedSettings {
         save_setting!(public_key, self, txn);
         save_setting!(log_n, self, txn);
         save_setting!(login_at_startup, self, txn);
+        save_setting!(settings_sync, self, txn);
+        save_setting!(settings_sync_excluded, self, txn);
         save_setting!(offline, self, txn);
+        save_setting!(use_proxy, self, txn);
+        save_setting!(proxy_host, self, txn);
+        save_setting!(proxy_port, self, txn);
+        save_setting!(proxy_direct_relays, self, txn);
         save_setting!(load_avatars, self, txn);
         save_setting!(load_media, self, txn);
         save_setting!(check_nip05, self, txn);
@@ -312,21 +489,44 @@ impl UnsavedSettings {
         save_setting!(overlap, self, txn);
         save_setting!(reposts, self, txn);
         save_setting!(show_long_form, self, txn);
+        save_setting!(show_git_events, self, txn);
+        save_setting!(show_torrents, self, txn);
+        save_setting!(show_live_activities, self, txn);
+        save_setting!(show_calendar_events, self, txn);
         save_setting!(show_mentions, self, txn);
         save_setting!(direct_messages, self, txn);
         save_setting!(future_allowance_secs, self, txn);
         save_setting!(hide_mutes_entirely, self, txn);
+        save_setting!(mute_list_sync, self, txn);
         save_setting!(reactions, self, txn);
         save_setting!(enable_zap_receipts, self, txn);
+        save_setting!(show_user_status, self, txn);
+        save_setting!(verify_timestamps, self, txn);
         save_setting!(show_media, self, txn);
         save_setting!(approve_content_warning, self, txn);
         save_setting!(show_deleted_events, self, txn);
         save_setting!(avoid_spam_on_unsafe_relays, self, txn);
+        save_setting!(min_pow_unknown_authors, self, txn);
+        save_setting!(exclude_labels, self, txn);
+        save_setting!(include_labels, self, txn);
+        save_setting!(mute_when_reporting, self, txn);
+        save_setting!(hide_bridged_posts, self, txn);
+        save_setting!(follow_back_enabled, self, txn);
+        save_setting!(follow_back_automatically, self, txn);
+        save_setting!(follow_back_max_wot_distance, self, txn);
+        save_setting!(follow_back_require_nip05, self, txn);
+        save_setting!(follow_back_min_account_age_days, self, txn);
+        save_setting!(read_later_prefetch_media, self, txn);
         save_setting!(pow, self, txn);
         save_setting!(set_client_tag, self, txn);
         save_setting!(set_user_agent, self, txn);
+        save_setting!(relay_list_auto_advertise, self, txn);
+        save_setting!(web_link_base, self, txn);
+        save_setting!(media_server, self, txn);
+        save_setting!(follow_import_directories, self, txn);
         save_setting!(delegatee_tag, self, txn);
         save_setting!(max_fps, self, txn);
+        save_setting!(memory_budget_mb, self, txn);
         save_setting!(recompute_feed_periodically, self, txn);
         save_setting!(feed_recompute_interval_ms, self, txn);
         save_setting!(theme_variant, self, txn);
@@ -339,6 +539,18 @@ impl UnsavedSettings {
         save_setting!(image_resize_algorithm, self, txn);
         save_setting!(inertial_scrolling, self, txn);
         save_setting!(mouse_acceleration, self, txn);
+        save_setting!(language, self, txn);
+        save_setting!(relative_timestamps, self, txn);
+        save_setting!(timestamps_24_hour, self, txn);
+        save_setting!(timezone_offset_minutes, self, txn);
+        save_setting!(sound_on_mention, self, txn);
+        save_setting!(sound_on_dm, self, txn);
+        save_setting!(sound_on_zap, self, txn);
+        save_setting!(sound_file_mention, self, txn);
+        save_setting!(sound_file_dm, self, txn);
+        save_setting!(sound_file_zap, self, txn);
+        save_setting!(sound_quiet_start_hour, self, txn);
+        save_setting!(sound_quiet_end_hour, self, txn);
         save_setting!(relay_list_becomes_stale_hours, self, txn);
         save_setting!(metadata_becomes_stale_hours, self, txn);
         save_setting!(nip05_becomes_stale_if_valid_hours, self, txn);
@@ -359,8 +571,24 @@ impl UnsavedSettings {
         save_setting!(fetcher_host_exclusion_on_med_error_secs, self, txn);
         save_setting!(fetcher_host_exclusion_on_high_error_secs, self, txn);
         save_setting!(nip11_lines_to_output_on_error, self, txn);
+        save_setting!(fetcher_nip98_hosts, self, txn);
+        save_setting!(log_to_file, self, txn);
+        save_setting!(log_filter, self, txn);
+        save_setting!(log_file_max_size_kb, self, txn);
+        save_setting!(crash_reports, self, txn);
+        save_setting!(metrics_endpoint, self, txn);
+        save_setting!(metrics_port, self, txn);
+        save_setting!(syndication_endpoint, self, txn);
+        save_setting!(syndication_port, self, txn);
         save_setting!(prune_period_days, self, txn);
         save_setting!(cache_prune_period_days, self, txn);
+        save_setting!(prune_keep_followed, self, txn);
+        save_setting!(prune_automatically, self, txn);
+        save_setting!(backup_enabled, self, txn);
+        save_setting!(backup_interval_hours, self, txn);
+        save_setting!(backup_keep, self, txn);
+        save_setting!(backup_directory, self, txn);
+        save_setting!(backup_include_key, self, txn);
         txn.commit()?;
         Ok(())
     }
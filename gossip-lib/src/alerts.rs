@@ -0,0 +1,410 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::storage::json_key;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, EventReference, Id, PublicKey, Unixtime};
+use parking_lot::RwLock as PRwLock;
+use serde::{Deserialize, Serialize};
+
+/// How many alerted events we remember
+const MAX_ALERTED: usize = 1000;
+
+/// Events older than this (when they arrive) don't raise notifications, so we
+/// don't go off for everything we missed while offline
+const RECENT_SECS: i64 = 600;
+
+/// A rule that raises an alert when an incoming event matches it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRule {
+    pub name: String,
+
+    pub enabled: bool,
+
+    /// Words or phrases to look for in the content (ignoring case)
+    pub keywords: Vec<String>,
+
+    /// Hashtags to look for (without the '#', ignoring case)
+    pub hashtags: Vec<String>,
+
+    /// Authors to alert on
+    pub authors: Vec<PublicKey>,
+
+    /// Event kinds this rule applies to. If empty, it applies to feed
+    /// displayable kinds.
+    pub kinds: Vec<u32>,
+
+    /// Whether to also raise a desktop notification
+    pub desktop_notification: bool,
+
+    /// Local hour of the day (0-23) at which desktop notifications for this rule
+    /// are muted. The rule still records matches in the inbox while muted.
+    pub mute_start_hour: u8,
+
+    /// Local hour of the day (0-23) at which they are unmuted again. If this is
+    /// the same as `mute_start_hour`, the rule is never muted.
+    pub mute_end_hour: u8,
+}
+
+impl AlertRule {
+    /// Whether an event matches this rule. An event matches if it is of one of
+    /// the rule's kinds, and any of its keywords, hashtags or authors match. A
+    /// rule with no keywords, hashtags or authors matches every event of its
+    /// kinds.
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let kind_ok = if self.kinds.is_empty() {
+            event.kind.is_feed_displayable()
+        } else {
+            self.kinds.contains(&u32::from(event.kind))
+        };
+        if !kind_ok {
+            return false;
+        }
+
+        if self.keywords.is_empty() && self.hashtags.is_empty() && self.authors.is_empty() {
+            return true;
+        }
+
+        if self.authors.contains(&event.pubkey) {
+            return true;
+        }
+
+        if !self.keywords.is_empty() {
+            let content = event.content.to_lowercase();
+            if self
+                .keywords
+                .iter()
+                .filter(|k| !k.trim().is_empty())
+                .any(|k| content.contains(&k.trim().to_lowercase()))
+            {
+                return true;
+            }
+        }
+
+        if !self.hashtags.is_empty() {
+            let hashtags = event.hashtags();
+            if self.hashtags.iter().any(|wanted| {
+                let wanted = wanted.trim().trim_start_matches('#');
+                hashtags.iter().any(|h| h.eq_ignore_ascii_case(wanted))
+            }) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Whether this rule has no keywords, hashtags, authors or kinds
+    pub fn is_blank(&self) -> bool {
+        self.keywords.iter().all(|k| k.trim().is_empty())
+            && self.hashtags.iter().all(|h| h.trim().is_empty())
+            && self.authors.is_empty()
+            && self.kinds.is_empty()
+    }
+
+    /// Whether desktop notifications for this rule are muted at this local hour
+    pub fn is_muted_at(&self, hour: u8) -> bool {
+        let (start, end) = (self.mute_start_hour, self.mute_end_hour);
+        if start == end {
+            false
+        } else if start < end {
+            hour >= start && hour < end
+        } else {
+            // Through midnight
+            hour >= start || hour < end
+        }
+    }
+}
+
+/// What an alert is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertCategory {
+    /// The event matched one of the user's alert rules
+    Rule,
+
+    /// The event mentions or replies to the user
+    Mention,
+
+    /// A direct message to the user
+    DirectMessage,
+
+    /// A zap receipt for a zap to the user
+    Zap,
+}
+
+/// An incoming event the user may want to be told about
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub id: Id,
+    pub author: PublicKey,
+    pub content: String,
+    pub category: AlertCategory,
+
+    /// The rule it matched, if the category is `Rule`
+    pub rule: Option<AlertRule>,
+}
+
+lazy_static! {
+    // Rules are checked against every incoming event, so we keep them decoded
+    static ref RULES: PRwLock<Option<Vec<AlertRule>>> = PRwLock::new(None);
+    static ref PENDING: PRwLock<Vec<Alert>> = PRwLock::new(Vec::new());
+}
+
+/// The user's alert rules
+pub fn rules() -> Vec<AlertRule> {
+    if let Some(rules) = &*RULES.read() {
+        return rules.clone();
+    }
+
+    let rules: Vec<AlertRule> = match GLOBALS.storage.read_json(json_key::ALERT_RULES) {
+        Ok(rules) => rules.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    };
+    *RULES.write() = Some(rules.clone());
+    rules
+}
+
+/// Replace the user's alert rules. Enabled rules have to say what to look
+/// for, so that a rule left blank does not alert on everything.
+pub fn save_rules(rules: &[AlertRule]) -> Result<(), Error> {
+    if let Some(rule) = rules.iter().find(|r| r.enabled && r.is_blank()) {
+        return Err(format!(
+            "Alert rule \"{}\" needs keywords, hashtags, authors or kinds before it can be enabled",
+            rule.name
+        )
+        .into());
+    }
+
+    GLOBALS
+        .storage
+        .write_json(json_key::ALERT_RULES, rules, None)?;
+    *RULES.write() = Some(rules.to_vec());
+    Ok(())
+}
+
+/// The events that matched alert rules, most recent match last. These are
+/// shown in the inbox.
+pub fn alerted_events() -> Vec<Id> {
+    match GLOBALS.storage.read_alerted_events() {
+        Ok(alerted) => alerted,
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    }
+}
+
+/// Take the alerts that came in since last asked. These are for the front end
+/// to raise desktop notifications or play sounds for, as the user configured.
+pub fn take_pending() -> Vec<Alert> {
+    std::mem::take(&mut *PENDING.write())
+}
+
+/// Check a new incoming event against the alert rules, and for whether it is a
+/// mention, direct message or zap for the user
+pub(crate) fn check(event: &Event) -> Result<(), Error> {
+    let my_pubkey = GLOBALS.identity.public_key();
+    if Some(event.pubkey) == my_pubkey
+        || GLOBALS
+            .people
+            .is_person_in_list(&event.pubkey, PersonList::Muted)
+    {
+        return Ok(());
+    }
+
+    let matched: Vec<AlertRule> = if event.kind == EventKind::EncryptedDirectMessage {
+        vec![]
+    } else {
+        rules().into_iter().filter(|r| r.matches(event)).collect()
+    };
+
+    if !matched.is_empty() {
+        GLOBALS
+            .storage
+            .mark_event_alerted(event.id, MAX_ALERTED, None)?;
+    }
+
+    let now = Unixtime::now().unwrap();
+    if event.created_at.0 < now.0 - RECENT_SECS {
+        return Ok(());
+    }
+
+    let alert = |category: AlertCategory, rule: Option<AlertRule>| Alert {
+        id: event.id,
+        author: event.pubkey,
+        content: event.content.clone(),
+        category,
+        rule,
+    };
+
+    let mut pending = PENDING.write();
+    for rule in matched {
+        pending.push(alert(AlertCategory::Rule, Some(rule)));
+    }
+
+    if let Some(my_pubkey) = my_pubkey {
+        let tags_me = event.people().iter().any(|(p, _, _)| *p == my_pubkey);
+        if event.kind == EventKind::EncryptedDirectMessage || event.kind == EventKind::DmChat {
+            if tags_me {
+                pending.push(alert(AlertCategory::DirectMessage, None));
+            }
+        } else if event.kind == EventKind::Zap {
+            if tags_me {
+                pending.push(alert(AlertCategory::Zap, None));
+            }
+        } else if event.kind.is_feed_displayable()
+            && (event.people_referenced_in_content().contains(&my_pubkey)
+                || (tags_me && replies_to_me(event, my_pubkey)?))
+        {
+            pending.push(alert(AlertCategory::Mention, None));
+        }
+    }
+
+    Ok(())
+}
+
+// Whether an event is a direct reply to one of our events
+fn replies_to_me(event: &Event, my_pubkey: PublicKey) -> Result<bool, Error> {
+    Ok(match event.replies_to() {
+        Some(EventReference::Id(id, _, _)) => match GLOBALS.storage.read_event(id)? {
+            Some(parent) => parent.pubkey == my_pubkey,
+            None => false,
+        },
+        Some(EventReference::Addr(ea)) => ea.author == my_pubkey,
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr_types::{Signature, Tag};
+
+    fn pubkey() -> PublicKey {
+        PublicKey::try_from_hex_string(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            true,
+        )
+        .unwrap()
+    }
+
+    fn event(kind: EventKind, content: &str, tags: Vec<Tag>) -> Event {
+        Event {
+            id: Id([0; 32]),
+            pubkey: pubkey(),
+            created_at: Unixtime(1700000000),
+            kind,
+            sig: Signature::zeroes(),
+            content: content.to_owned(),
+            tags,
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let note = event(
+            EventKind::TextNote,
+            "Gossip now has Alerts",
+            vec![Tag::new(&["t", "Nostr"])],
+        );
+
+        let rule = AlertRule {
+            enabled: true,
+            keywords: vec![" alerts ".to_owned()],
+            ..Default::default()
+        };
+        assert!(rule.matches(&note));
+
+        // Disabled rules match nothing
+        let disabled = AlertRule {
+            enabled: false,
+            ..rule.clone()
+        };
+        assert!(!disabled.matches(&note));
+
+        let rule = AlertRule {
+            enabled: true,
+            keywords: vec!["bitcoin".to_owned()],
+            ..Default::default()
+        };
+        assert!(!rule.matches(&note));
+
+        let rule = AlertRule {
+            enabled: true,
+            hashtags: vec!["#nostr".to_owned()],
+            ..Default::default()
+        };
+        assert!(rule.matches(&note));
+
+        let rule = AlertRule {
+            enabled: true,
+            authors: vec![pubkey()],
+            ..Default::default()
+        };
+        assert!(rule.matches(&note));
+
+        // Kinds limit what the other criteria apply to
+        let rule = AlertRule {
+            enabled: true,
+            keywords: vec!["alerts".to_owned()],
+            kinds: vec![u32::from(EventKind::LongFormContent)],
+            ..Default::default()
+        };
+        assert!(!rule.matches(&note));
+        let article = event(EventKind::LongFormContent, "More about alerts", vec![]);
+        assert!(rule.matches(&article));
+
+        // Without kinds, only feed displayable events match
+        let rule = AlertRule {
+            enabled: true,
+            authors: vec![pubkey()],
+            ..Default::default()
+        };
+        assert!(!rule.matches(&event(EventKind::Metadata, "{}", vec![])));
+    }
+
+    #[test]
+    fn test_is_blank() {
+        assert!(AlertRule::default().is_blank());
+        assert!(AlertRule {
+            keywords: vec![" ".to_owned()],
+            ..Default::default()
+        }
+        .is_blank());
+        assert!(!AlertRule {
+            kinds: vec![1],
+            ..Default::default()
+        }
+        .is_blank());
+    }
+
+    #[test]
+    fn test_is_muted_at() {
+        let mut rule = AlertRule::default();
+        assert!(!rule.is_muted_at(0));
+
+        rule.mute_start_hour = 9;
+        rule.mute_end_hour = 17;
+        assert!(!rule.is_muted_at(8));
+        assert!(rule.is_muted_at(9));
+        assert!(rule.is_muted_at(16));
+        assert!(!rule.is_muted_at(17));
+
+        // Through midnight
+        rule.mute_start_hour = 22;
+        rule.mute_end_hour = 7;
+        assert!(rule.is_muted_at(23));
+        assert!(rule.is_muted_at(0));
+        assert!(rule.is_muted_at(6));
+        assert!(!rule.is_muted_at(7));
+        assert!(!rule.is_muted_at(21));
+    }
+}
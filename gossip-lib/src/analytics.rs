@@ -0,0 +1,171 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use nostr_types::{Event, EventAddr, EventKind, Id, PublicKey, RelayUrl, Unixtime};
+use std::collections::{BTreeMap, HashSet};
+
+// Everything here is computed from what we have stored: the relationships
+// recorded as reactions, replies, reposts and zap receipts come in, and the
+// relays each post was seen on. Engagement we never fetched is not counted.
+
+/// How one of the user's posts was received
+#[derive(Clone, Debug)]
+pub struct PostStats {
+    pub id: Id,
+    pub kind: EventKind,
+    pub created_at: Unixtime,
+
+    /// Reactions, one per person
+    pub reactions: usize,
+    pub replies: usize,
+    pub reposts: usize,
+    pub quotes: usize,
+    pub zaps: usize,
+    pub zap_msats: u64,
+
+    /// The relays the post was seen on
+    pub relays: Vec<RelayUrl>,
+}
+
+impl PostStats {
+    /// Reactions, replies, reposts, quotes and zaps together
+    pub fn engagement(&self) -> usize {
+        self.reactions + self.replies + self.reposts + self.quotes + self.zaps
+    }
+}
+
+/// How the user's posts made in one period were received
+#[derive(Clone, Debug)]
+pub struct PeriodStats {
+    /// When the period starts
+    pub start: Unixtime,
+    pub posts: usize,
+    pub reactions: usize,
+    pub replies: usize,
+    pub reposts: usize,
+    pub quotes: usize,
+    pub zaps: usize,
+    pub zap_msats: u64,
+
+    /// Distinct relays any of the posts was seen on
+    pub relays: usize,
+}
+
+/// How a post was received
+pub fn post_stats(event: &Event) -> Result<PostStats, Error> {
+    let mut stats = PostStats {
+        id: event.id,
+        kind: event.kind,
+        created_at: event.created_at,
+        reactions: 0,
+        replies: 0,
+        reposts: 0,
+        quotes: 0,
+        zaps: 0,
+        zap_msats: 0,
+        relays: vec![],
+    };
+
+    let mut reactors: HashSet<PublicKey> = HashSet::new();
+    for (_, rel) in GLOBALS.storage.find_relationships_by_id(event.id)? {
+        match rel {
+            RelationshipById::Reaction { by, .. } => {
+                reactors.insert(by);
+            }
+            RelationshipById::Reply => stats.replies += 1,
+            RelationshipById::Repost => stats.reposts += 1,
+            RelationshipById::Quote => stats.quotes += 1,
+            RelationshipById::ZapReceipt { amount, .. } => {
+                stats.zaps += 1;
+                stats.zap_msats += amount.0;
+            }
+            _ => (),
+        }
+    }
+    stats.reactions = reactors.len();
+
+    // Replies to articles and other replaceable events refer to them by address
+    if event.kind.is_replaceable() {
+        stats.replies += GLOBALS
+            .storage
+            .get_replaceable_replies(&EventAddr {
+                d: event.parameter().unwrap_or_default(),
+                relays: vec![],
+                kind: event.kind,
+                author: event.pubkey,
+            })?
+            .len();
+    }
+
+    stats.relays = GLOBALS
+        .storage
+        .get_event_seen_on_relay(event.id)?
+        .drain(..)
+        .map(|(url, _)| url)
+        .collect();
+
+    Ok(stats)
+}
+
+/// How each of the user's posts (since `since`, if given) was received, newest
+/// first. Reposts by the user are not their posts, and are left out.
+pub fn my_post_stats(since: Option<Unixtime>) -> Result<Vec<PostStats>, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(vec![]),
+    };
+
+    let kinds: Vec<EventKind> = crate::feed::feed_displayable_event_kinds(false)
+        .drain(..)
+        .filter(|k| *k != EventKind::Repost && *k != EventKind::GenericRepost)
+        .collect();
+
+    GLOBALS
+        .storage
+        .find_events(&kinds, &[pubkey], since, |_| true, true)?
+        .iter()
+        .map(post_stats)
+        .collect()
+}
+
+/// Post stats added up by period (e.g. 60 * 60 * 24 for days, in UTC), newest
+/// first
+pub fn by_period(stats: &[PostStats], period_secs: i64) -> Vec<PeriodStats> {
+    let mut periods: BTreeMap<i64, (PeriodStats, HashSet<RelayUrl>)> = BTreeMap::new();
+    for post in stats {
+        let start = post.created_at.0 - post.created_at.0.rem_euclid(period_secs);
+        let (period, relays) = periods.entry(start).or_insert_with(|| {
+            (
+                PeriodStats {
+                    start: Unixtime(start),
+                    posts: 0,
+                    reactions: 0,
+                    replies: 0,
+                    reposts: 0,
+                    quotes: 0,
+                    zaps: 0,
+                    zap_msats: 0,
+                    relays: 0,
+                },
+                HashSet::new(),
+            )
+        });
+        period.posts += 1;
+        period.reactions += post.reactions;
+        period.replies += post.replies;
+        period.reposts += post.reposts;
+        period.quotes += post.quotes;
+        period.zaps += post.zaps;
+        period.zap_msats += post.zap_msats;
+        relays.extend(post.relays.iter().cloned());
+    }
+
+    periods
+        .into_values()
+        .rev()
+        .map(|(mut period, relays)| {
+            period.relays = relays.len();
+            period
+        })
+        .collect()
+}
@@ -0,0 +1,418 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::profile::Profile;
+use crate::storage::json_key;
+use nostr_types::Unixtime;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Backups are directories named this followed by when they were taken
+const PREFIX: &str = "gossip-backup-";
+
+/// A backup is written here first, and renamed once it is complete
+const PARTIAL: &str = ".partial";
+
+/// How often we check whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Periodically back up storage, if the `backup_enabled` setting is on
+pub(crate) fn start() {
+    tokio::task::spawn(async {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                break;
+            }
+
+            if !GLOBALS.storage.read_setting_backup_enabled() {
+                continue;
+            }
+
+            match is_due() {
+                Ok(false) => continue,
+                Ok(true) => (),
+                Err(e) => {
+                    tracing::error!("Backup: {}", e);
+                    continue;
+                }
+            }
+
+            if let Err(e) = tokio::task::spawn_blocking(backup_and_report).await {
+                tracing::error!("Backup: {}", e);
+            }
+        }
+    });
+}
+
+/// Back up now, reporting the result on the status queue
+pub fn backup_and_report() {
+    match backup_now() {
+        Ok(path) => GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Backup written to {}", path.display())),
+        Err(e) => GLOBALS
+            .status_queue
+            .write()
+            .write_error(format!("Backup failed: {}", e)),
+    }
+}
+
+/// Write a backup of storage (and, if the `backup_include_key` setting is on,
+/// the encrypted private key) into a new timestamped directory, then remove
+/// the oldest backups beyond `backup_keep`. Returns the new directory.
+pub fn backup_now() -> Result<PathBuf, Error> {
+    if RUNNING.swap(true, Ordering::Relaxed) {
+        return Err("A backup is already running".into());
+    }
+    let result = backup_inner();
+    RUNNING.store(false, Ordering::Relaxed);
+    result
+}
+
+fn backup_inner() -> Result<PathBuf, Error> {
+    let dir = backup_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let name = format!(
+        "{}{}",
+        PREFIX,
+        crate::syndication::rfc3339(Unixtime::now().unwrap()).replace(':', "")
+    );
+    let partial = dir.join(format!("{}{}", name, PARTIAL));
+    let complete = dir.join(&name);
+
+    std::fs::create_dir_all(&partial)?;
+    if let Err(e) = write_backup(&partial) {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &complete)?;
+
+    rotate(&dir)?;
+
+    Ok(complete)
+}
+
+fn write_backup(path: &Path) -> Result<(), Error> {
+    GLOBALS.storage.copy_to_file(path.join("data.mdb"))?;
+
+    if GLOBALS.storage.read_setting_backup_include_key() {
+        if let Some(epk) = GLOBALS.storage.read_encrypted_private_key()? {
+            std::fs::write(path.join("key.ncryptsec"), &epk.0)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where backups go: the `backup_directory` setting, or `backups` in the
+/// profile directory if that is empty
+pub fn backup_dir() -> Result<PathBuf, Error> {
+    let setting = GLOBALS.storage.read_setting_backup_directory();
+    if setting.trim().is_empty() {
+        let mut path = Profile::current()?.profile_dir;
+        path.push("backups");
+        Ok(path)
+    } else {
+        Ok(PathBuf::from(setting.trim()))
+    }
+}
+
+/// The completed backups, oldest first
+pub fn list_backups() -> Result<Vec<PathBuf>, Error> {
+    let dir = backup_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups: Vec<PathBuf> = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(PREFIX) && !name.ends_with(PARTIAL) && entry.path().is_dir() {
+            backups.push(entry.path());
+        }
+    }
+    // The names sort by time
+    backups.sort();
+    Ok(backups)
+}
+
+fn is_due() -> Result<bool, Error> {
+    let interval =
+        Duration::from_secs(GLOBALS.storage.read_setting_backup_interval_hours() * 60 * 60);
+    let latest = match list_backups()?.pop() {
+        Some(latest) => latest,
+        None => return Ok(true),
+    };
+    let modified = std::fs::metadata(latest)?.modified()?;
+    Ok(SystemTime::now()
+        .duration_since(modified)
+        .map(|age| age >= interval)
+        .unwrap_or(false))
+}
+
+fn rotate(dir: &Path) -> Result<(), Error> {
+    // Leftovers from backups that did not finish
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(PREFIX) && name.ends_with(PARTIAL) {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+
+    let keep = GLOBALS.storage.read_setting_backup_keep().max(1) as usize;
+    let backups = list_backups()?;
+    if backups.len() > keep {
+        for old in &backups[..backups.len() - keep] {
+            tracing::info!("Removing old backup {}", old.display());
+            std::fs::remove_dir_all(old)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Version of the portable backup format
+pub const PORTABLE_BACKUP_VERSION: u32 = 1;
+
+/// What a portable backup holds, written as `manifest.json`
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    version: u32,
+    exported_at: i64,
+    public_key: Option<String>,
+    events: usize,
+    people: usize,
+}
+
+/// Write a portable backup into a new directory `path`: every event, person
+/// and person relay as JSON lines, the settings, relays and person lists (see
+/// [export_settings](crate::settings_file::export_settings)), our alert rules,
+/// relay scopes, read later queue, other identities, issued delegations and
+/// wallet connection, and our encrypted private key. Unlike the database
+/// copies of scheduled backups, this does not depend on the machine or the
+/// version of gossip that reads it.
+pub fn export_backup<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    std::fs::create_dir_all(path)?;
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path.join("events.jsonl"))?);
+    let events = GLOBALS.storage.for_each_event(|event| {
+        writeln!(out, "{}", serde_json::to_string(&event)?)?;
+        Ok(())
+    })?;
+    out.flush()?;
+
+    let people = GLOBALS.storage.filter_people(|_| true)?;
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path.join("people.jsonl"))?);
+    for person in &people {
+        writeln!(out, "{}", serde_json::to_string(person)?)?;
+    }
+    out.flush()?;
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path.join("person_relays.jsonl"))?);
+    for person in &people {
+        for person_relay in GLOBALS.storage.get_person_relays(person.pubkey)? {
+            writeln!(out, "{}", serde_json::to_string(&person_relay)?)?;
+        }
+    }
+    out.flush()?;
+
+    crate::settings_file::export_settings(path.join("settings.json"))?;
+
+    let mut documents: BTreeMap<String, serde_json::Value> = BTreeMap::new();
+    for key in json_key::BACKED_UP {
+        if let Some(value) = GLOBALS.storage.read_json(key)? {
+            documents.insert(String::from_utf8_lossy(key).into_owned(), value);
+        }
+    }
+    std::fs::write(
+        path.join("general.json"),
+        serde_json::to_string_pretty(&documents)?,
+    )?;
+
+    // It is encrypted with the passphrase
+    if let Some(epk) = GLOBALS.storage.read_encrypted_private_key()? {
+        std::fs::write(path.join("key.ncryptsec"), &epk.0)?;
+    }
+
+    let manifest = Manifest {
+        version: PORTABLE_BACKUP_VERSION,
+        exported_at: Unixtime::now().unwrap().0,
+        public_key: GLOBALS
+            .storage
+            .read_setting_public_key()
+            .map(|pk| pk.as_hex_string()),
+        events,
+        people: people.len(),
+    };
+    std::fs::write(
+        path.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    tracing::info!(
+        "Exported {} events and {} people to {}",
+        events,
+        people.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Read a portable backup written by [export_backup] into storage. Settings,
+/// relays, lists and people are merged with what we have, and the events are
+/// processed as if they had just arrived. Our keys, and things like alert
+/// rules and the read later queue, are only taken from the backup if we have
+/// none yet. Returns the number of events imported.
+pub async fn import_backup<P: AsRef<Path>>(path: P) -> Result<usize, Error> {
+    let path = path.as_ref();
+
+    let manifest: Manifest =
+        serde_json::from_str(&std::fs::read_to_string(path.join("manifest.json"))?)?;
+    if manifest.version > PORTABLE_BACKUP_VERSION {
+        return Err(format!(
+            "Backup version {} is newer than this gossip supports ({})",
+            manifest.version, PORTABLE_BACKUP_VERSION
+        )
+        .into());
+    }
+
+    crate::settings_file::import_settings(path.join("settings.json"))?;
+
+    if GLOBALS.storage.read_setting_public_key().is_none() {
+        if let Some(hex) = &manifest.public_key {
+            let public_key = nostr_types::PublicKey::try_from_hex_string(hex, true)?;
+            GLOBALS
+                .storage
+                .write_setting_public_key(&Some(public_key), None)?;
+            let key_path = path.join("key.ncryptsec");
+            if key_path.exists() {
+                let epk = nostr_types::EncryptedPrivateKey(std::fs::read_to_string(key_path)?);
+                GLOBALS
+                    .storage
+                    .write_encrypted_private_key(Some(&epk), None)?;
+            }
+        }
+    }
+
+    // Before the events, as those are checked against alert rules
+    let general = path.join("general.json");
+    if general.exists() {
+        let documents: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(general)?)?;
+        for key in json_key::BACKED_UP {
+            let value = match documents.get(&*String::from_utf8_lossy(key)) {
+                Some(value) => value,
+                None => continue,
+            };
+            if GLOBALS
+                .storage
+                .read_json::<serde_json::Value>(key)?
+                .is_none()
+            {
+                GLOBALS.storage.write_json(key, value, None)?;
+            }
+        }
+    }
+
+    for person in read_lines::<crate::people::Person>(&path.join("people.jsonl"))? {
+        let person = person?;
+        if GLOBALS.storage.read_person(&person.pubkey)?.is_none() {
+            GLOBALS.storage.write_person(&person, None)?;
+        }
+    }
+
+    for person_relay in
+        read_lines::<crate::person_relay::PersonRelay>(&path.join("person_relays.jsonl"))?
+    {
+        let person_relay = person_relay?;
+        if GLOBALS
+            .storage
+            .read_person_relay(person_relay.pubkey, &person_relay.url)?
+            .is_none()
+        {
+            GLOBALS.storage.write_person_relay(&person_relay, None)?;
+        }
+    }
+
+    let mut imported: usize = 0;
+    // There can be millions of these, so they are read one at a time
+    for event in read_lines::<nostr_types::Event>(&path.join("events.jsonl"))? {
+        let event = event?;
+        match crate::process::process_new_event(&event, None, None, false, false).await {
+            Ok(()) => imported += 1,
+            Err(e) => tracing::warn!("Skipping event {}: {}", event.id.as_hex_string(), e),
+        }
+    }
+
+    tracing::info!("Imported {} events from {}", imported, path.display());
+
+    Ok(imported)
+}
+
+// Read a file of JSON lines, one value at a time, skipping blank lines
+fn read_lines<T: DeserializeOwned>(
+    path: &Path,
+) -> Result<impl Iterator<Item = Result<T, Error>>, Error> {
+    let file = std::fs::File::open(path)?;
+    Ok(parse_lines(std::io::BufReader::new(file)))
+}
+
+fn parse_lines<T: DeserializeOwned, R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<T, Error>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(serde_json::from_str(&line).map_err(Into::into)),
+        Err(e) => Some(Err(e.into())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr_types::{Id, RelayUrl};
+
+    #[test]
+    fn test_parse_lines() {
+        let urls = vec![
+            RelayUrl::try_from_str("wss://relay.example.com/").unwrap(),
+            RelayUrl::try_from_str("wss://other.example.net/").unwrap(),
+        ];
+        let mut jsonl = String::new();
+        for url in &urls {
+            jsonl.push_str(&serde_json::to_string(url).unwrap());
+            jsonl.push_str("\n\n");
+        }
+        let read: Vec<RelayUrl> = parse_lines(jsonl.as_bytes())
+            .collect::<Result<_, Error>>()
+            .unwrap();
+        assert_eq!(read, urls);
+
+        // One bad line is an error for that line only
+        let jsonl = format!(
+            "\"nope\"\n{}\n",
+            serde_json::to_string(&Id([7; 32])).unwrap()
+        );
+        let read: Vec<Result<Id, Error>> = parse_lines(jsonl.as_bytes()).collect();
+        assert_eq!(read.len(), 2);
+        assert!(read[0].is_err());
+        assert_eq!(read[1].as_ref().unwrap(), &Id([7; 32]));
+    }
+}
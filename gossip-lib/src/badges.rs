@@ -0,0 +1,185 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventAddr, EventKind, Id, PublicKey};
+
+/// The 'd' tag of the profile badges event (kind 30008)
+pub const PROFILE_BADGES_D: &str = "profile_badges";
+
+/// A badge definition (NIP-58, kind 30009)
+#[derive(Debug, Clone)]
+pub struct BadgeDefinition {
+    pub addr: EventAddr,
+    pub name: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub thumb: Option<String>,
+}
+
+impl BadgeDefinition {
+    pub fn from_event(event: &Event) -> Option<BadgeDefinition> {
+        if event.kind != EventKind::BadgeDefinition {
+            return None;
+        }
+
+        let mut d = String::new();
+        let mut name: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut image: Option<String> = None;
+        let mut thumb: Option<String> = None;
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "name" => name = Some(tag.value().to_owned()),
+                "description" => description = Some(tag.value().to_owned()),
+                "image" => image = Some(tag.value().to_owned()),
+                // Take the first thumbnail, which should be the largest
+                "thumb" if thumb.is_none() => thumb = Some(tag.value().to_owned()),
+                _ => (),
+            }
+        }
+
+        let name = name.unwrap_or_else(|| d.clone());
+
+        Some(BadgeDefinition {
+            addr: EventAddr {
+                d,
+                relays: vec![],
+                kind: EventKind::BadgeDefinition,
+                author: event.pubkey,
+            },
+            name,
+            description,
+            image,
+            thumb,
+        })
+    }
+
+    /// The picture to show for the badge where there is little room: the
+    /// thumbnail, or else the full image
+    pub fn icon(&self) -> Option<&str> {
+        self.thumb.as_deref().or(self.image.as_deref())
+    }
+
+    /// Load a badge definition from local storage
+    pub fn load(addr: &EventAddr) -> Result<Option<BadgeDefinition>, Error> {
+        Ok(GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::BadgeDefinition, addr.author, &addr.d)?
+            .and_then(|event| BadgeDefinition::from_event(&event)))
+    }
+}
+
+/// A badge as listed in somebody's profile badges event
+#[derive(Debug, Clone)]
+pub struct ProfileBadge {
+    /// The badge definition address
+    pub addr: EventAddr,
+
+    /// The badge award event
+    pub award: Id,
+
+    /// The badge definition, if we have it
+    pub definition: Option<BadgeDefinition>,
+
+    /// True if we have the award event, and it was issued by the badge author
+    /// to the profile owner
+    pub verified: bool,
+}
+
+/// Get the profile badges event of a person, if we have it
+pub fn get_profile_badges_event(pubkey: PublicKey) -> Result<Option<Event>, Error> {
+    GLOBALS
+        .storage
+        .get_replaceable_event(EventKind::ProfileBadges, pubkey, PROFILE_BADGES_D)
+}
+
+/// Get the (ordered) list of badges a person has accepted onto their profile
+pub fn get_profile_badges(pubkey: PublicKey) -> Result<Vec<ProfileBadge>, Error> {
+    let event = match get_profile_badges_event(pubkey)? {
+        Some(e) => e,
+        None => return Ok(vec![]),
+    };
+
+    let mut output: Vec<ProfileBadge> = Vec::new();
+    for (addr, award) in badge_pairs(&event) {
+        let verified = match GLOBALS.storage.read_event(award)? {
+            Some(award_event) => {
+                award_event.kind == EventKind::BadgeAward
+                    && award_event.pubkey == addr.author
+                    && award_event
+                        .tags
+                        .iter()
+                        .any(|t| matches!(t.parse_pubkey(), Ok((pk, _, _)) if pk == pubkey))
+            }
+            None => false,
+        };
+        let definition = BadgeDefinition::load(&addr)?;
+        output.push(ProfileBadge {
+            addr,
+            award,
+            definition,
+            verified,
+        });
+    }
+
+    Ok(output)
+}
+
+/// Get the badges that have been awarded to the user, as (definition, award) pairs,
+/// most recent award first
+pub fn get_badges_awarded_to_us() -> Result<Vec<(EventAddr, Id)>, Error> {
+    match GLOBALS.identity.public_key() {
+        Some(pk) => get_badges_awarded_to(pk),
+        None => Ok(vec![]),
+    }
+}
+
+/// Get the badges that have been awarded to a person, whether or not they
+/// accepted them, as (definition, award) pairs, most recent award first.
+///
+/// For anybody but the user this goes through every badge award we have, as
+/// only the user's 'p' tags are indexed.
+pub fn get_badges_awarded_to(pubkey: PublicKey) -> Result<Vec<(EventAddr, Id)>, Error> {
+    let awards = GLOBALS.storage.find_events_tagging(
+        &[EventKind::BadgeAward],
+        &[],
+        None,
+        &[pubkey],
+        |_| true,
+        true,
+    )?;
+
+    let mut output: Vec<(EventAddr, Id)> = Vec::new();
+    for award in &awards {
+        for tag in &award.tags {
+            if let Ok((ea, _)) = tag.parse_address() {
+                // Only the badge author can award the badge
+                if ea.kind == EventKind::BadgeDefinition && ea.author == award.pubkey {
+                    output.push((ea, award.id));
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// The (definition, award) pairs of a profile badges event, in order
+pub(crate) fn badge_pairs(event: &Event) -> Vec<(EventAddr, Id)> {
+    let mut output: Vec<(EventAddr, Id)> = Vec::new();
+    let mut pending: Option<EventAddr> = None;
+    for tag in &event.tags {
+        if let Ok((ea, _)) = tag.parse_address() {
+            if ea.kind == EventKind::BadgeDefinition {
+                pending = Some(ea);
+            }
+        } else if let Ok((id, _, _)) = tag.parse_event() {
+            // An 'e' tag must follow its 'a' tag
+            if let Some(ea) = pending.take() {
+                output.push((ea, id));
+            }
+        }
+    }
+    output
+}
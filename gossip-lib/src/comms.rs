@@ -1,12 +1,18 @@
 use crate::dm_channel::DmChannel;
+use crate::nip32::Label;
 use crate::nip46::{Approval, ParsedCommand};
+use crate::nip52::RsvpStatus;
+use crate::nip90::JobType;
 use crate::people::PersonList;
 use crate::relay::Relay;
+use crate::search::SearchScope;
+use crate::thread_export::ThreadFormat;
 use nostr_types::{
-    Event, EventAddr, Id, IdHex, Metadata, MilliSatoshi, Profile, PublicKey, RelayUrl, Tag,
-    UncheckedUrl, Unixtime,
+    Event, EventAddr, EventKind, Id, IdHex, Metadata, MilliSatoshi, Profile, PublicKey, RelayUrl,
+    Tag, UncheckedUrl, Unixtime,
 };
 use std::fmt;
+use std::path::PathBuf;
 
 /// This is a message sent to the Overlord. Tasks which take any amount of time,
 /// especially involving relays, are handled by the Overlord in this way. There is
@@ -21,18 +27,30 @@ pub enum ToOverlordMessage {
     /// Calls [add_relay](crate::Overlord::add_relay)
     AddRelay(RelayUrl),
 
+    /// Calls [advertise_dm_relays](crate::Overlord::advertise_dm_relays)
+    AdvertiseDmRelays,
+
     /// Calls [advertise_relay_list](crate::Overlord::advertise_relay_list)
     AdvertiseRelayList,
 
     /// internal
     AdvertiseRelayListNextChunk(Box<Event>, Vec<RelayUrl>),
 
+    /// Calls [approve_community_post](crate::Overlord::approve_community_post)
+    ApproveCommunityPost(Id, EventAddr),
+
     /// Calls [auth_approved](crate::Overlord::auth_approved)
     AuthApproved(RelayUrl),
 
     /// Calls [auth_approved](crate::Overlord::auth_declined)
     AuthDeclined(RelayUrl),
 
+    /// Calls [backup_now](crate::Overlord::backup_now)
+    BackupNow,
+
+    /// Calls [broadcast](crate::Overlord::broadcast)
+    Broadcast(Id),
+
     /// Calls [change_passphrase](crate::Overlord::change_passphrase)
     ChangePassphrase { old: String, new: String },
 
@@ -42,6 +60,9 @@ pub enum ToOverlordMessage {
     /// Calls [auth_approved](crate::Overlord::connect_approved)
     ConnectApproved(RelayUrl),
 
+    /// Calls [connect_bunker](crate::Overlord::connect_bunker)
+    ConnectBunker { uri: String, password: String },
+
     /// Calls [auth_approved](crate::Overlord::connect_declined)
     ConnectDeclined(RelayUrl),
 
@@ -63,15 +84,45 @@ pub enum ToOverlordMessage {
     /// Calls [drop_relay](crate::Overlord::drop_relay)
     DropRelay(RelayUrl),
 
+    /// Calls [export_thread](crate::Overlord::export_thread)
+    ExportThread(Id, ThreadFormat),
+
+    /// Calls [fetch_calendar_events](crate::Overlord::fetch_calendar_events)
+    FetchCalendarEvents,
+
     /// Calls [fetch_event](crate::Overlord::fetch_event)
     FetchEvent(Id, Vec<RelayUrl>),
 
     /// Calls [fetch_event_addr](crate::Overlord::fetch_event_addr)
     FetchEventAddr(EventAddr),
 
+    /// Calls [fetch_handlers](crate::Overlord::fetch_handlers)
+    FetchHandlers(EventKind),
+
+    /// Calls [fetch_identity_mentions](crate::Overlord::fetch_identity_mentions)
+    FetchIdentityMentions,
+
+    /// Calls [fetch_long_form_articles](crate::Overlord::fetch_long_form_articles)
+    FetchLongFormArticles,
+
+    /// Calls [fetch_marketplace](crate::Overlord::fetch_marketplace)
+    FetchMarketplace,
+
+    /// Calls [fetch_relay_invoice](crate::Overlord::fetch_relay_invoice)
+    FetchRelayInvoice(RelayUrl),
+
+    /// Calls [fetch_starter_packs](crate::Overlord::fetch_starter_packs)
+    FetchStarterPacks,
+
+    /// Calls [fetch_wiki_articles](crate::Overlord::fetch_wiki_articles)
+    FetchWikiArticles(String),
+
     /// Calls [follow_pubkey](crate::Overlord::follow_pubkey)
     FollowPubkey(PublicKey, PersonList, bool),
 
+    /// Calls [follow_pubkeys](crate::Overlord::follow_pubkeys)
+    FollowPubkeys(Vec<PublicKey>, PersonList, bool),
+
     /// Calls [follow_nip05](crate::Overlord::follow_nip05)
     FollowNip05(String, PersonList, bool),
 
@@ -84,6 +135,9 @@ pub enum ToOverlordMessage {
     /// Calls [hide_or_show_relay](crate::Overlord::hide_or_show_relay)
     HideOrShowRelay(RelayUrl, bool),
 
+    /// Calls [import_follows](crate::Overlord::import_follows)
+    ImportFollows(String),
+
     /// Calls [import_priv](crate::Overlord::import_priv)
     ImportPriv {
         // nsec, hex, or ncryptsec
@@ -94,6 +148,16 @@ pub enum ToOverlordMessage {
     /// Calls [import_pub](crate::Overlord::import_pub)
     ImportPub(String),
 
+    /// Calls [inspect_event](crate::Overlord::inspect_event)
+    InspectEvent(Id),
+
+    /// Calls [label](crate::Overlord::label)
+    Label {
+        pubkey: PublicKey,
+        id: Option<Id>,
+        label: Label,
+    },
+
     /// Calls [like](crate::Overlord::like)
     Like(Id, PublicKey),
 
@@ -109,6 +173,9 @@ pub enum ToOverlordMessage {
     /// Calls [nip46_server_op_approval_response](crate::Overlord::nip46_server_op_approval_response)
     Nip46ServerOpApprovalResponse(PublicKey, ParsedCommand, Approval),
 
+    /// Calls [pay_relay_invoice](crate::Overlord::pay_relay_invoice)
+    PayRelayInvoice(RelayUrl),
+
     /// Calls [post](crate::Overlord::post)
     Post {
         content: String,
@@ -123,39 +190,102 @@ pub enum ToOverlordMessage {
     /// Calls [post_nip46_event](crate::Overlord::post_nip46_event)
     PostNip46Event(Event, Vec<RelayUrl>),
 
+    /// Calls [post_to_community](crate::Overlord::post_to_community)
+    PostToCommunity {
+        content: String,
+        tags: Vec<Tag>,
+        community: EventAddr,
+    },
+
     /// Calls [prune_cache](crate::Overlord::prune_cache)
     PruneCache,
 
     /// Calls [prune_database](crate::Overlord::prune_database)
     PruneDatabase,
 
+    /// Calls [publish_long_form_article](crate::Overlord::publish_long_form_article)
+    PublishLongFormArticle {
+        /// The article it replaces, if it is an edit of one of ours
+        d: Option<String>,
+        title: String,
+        summary: String,
+        image: String,
+        hashtags: Vec<String>,
+        content: String,
+    },
+
+    /// Calls [publish_wiki_article](crate::Overlord::publish_wiki_article)
+    PublishWikiArticle {
+        title: String,
+        summary: String,
+        content: String,
+        fork_of: Option<Id>,
+    },
+
     /// Calls [push_person_list](crate::Overlord::push_person_list)
     PushPersonList(PersonList),
 
     /// Calls [push_metadata](crate::Overlord::push_metadata)
     PushMetadata(Metadata),
 
+    /// Calls [push_profile_badges](crate::Overlord::push_profile_badges)
+    PushProfileBadges(Vec<(EventAddr, Id)>),
+
+    /// Calls [push_settings_sync](crate::Overlord::push_settings_sync)
+    PushSettingsSync,
+
     /// Calls [rank_relay](crate::Overlord::rank_relay)
     RankRelay(RelayUrl, u8),
 
     /// internal (the overlord sends messages to itself sometimes!)
     ReengageMinion(RelayUrl, Vec<RelayJob>),
 
+    /// Calls [relay_paid](crate::Overlord::relay_paid)
+    RelayPaid(RelayUrl),
+
     /// Calls [refresh_scores_and_pick_relays](crate::Overlord::refresh_scores_and_pick_relays)
     RefreshScoresAndPickRelays,
 
     /// Calls [reresh_subscribed_metadata](crate::Overlord::refresh_subscribed_metadata)
     RefreshSubscribedMetadata,
 
+    /// Calls [report](crate::Overlord::report)
+    Report {
+        pubkey: PublicKey,
+        id: Option<Id>,
+        report_type: String,
+        comment: String,
+    },
+
     /// Calls [repost](crate::Overlord::repost)
     Repost(Id),
 
+    /// Calls [request_job](crate::Overlord::request_job)
+    RequestJob {
+        job_type: JobType,
+        input: Option<Id>,
+        language: Option<String>,
+        bid_msats: Option<u64>,
+    },
+
+    /// Calls [request_timestamp](crate::Overlord::request_timestamp)
+    RequestTimestamp(Id),
+
+    /// Calls [rsvp](crate::Overlord::rsvp)
+    Rsvp(Id, RsvpStatus),
+
     /// Calls [search](crate::Overlord::search)
-    Search(String),
+    Search(String, SearchScope),
+
+    /// Calls [search_relays](crate::Overlord::search_relays)
+    SearchRelays(String),
 
     /// Calls [set_active_person](crate::Overlord::set_active_person)
     SetActivePerson(PublicKey),
 
+    /// internal
+    SetCommunityFeed(EventAddr),
+
     /// internal
     SetDmChannel(DmChannel),
 
@@ -169,6 +299,14 @@ pub enum ToOverlordMessage {
         author: Option<PublicKey>,
     },
 
+    /// Calls [set_user_status](crate::Overlord::set_user_status)
+    SetUserStatus {
+        status_type: String,
+        content: String,
+        link: Option<String>,
+        expiration: Option<Unixtime>,
+    },
+
     /// Calls [start_long_lived_subscriptions](crate::Overlord::start_long_lived_subscriptions)
     StartLongLivedSubscriptions,
 
@@ -205,6 +343,15 @@ pub enum ToOverlordMessage {
     /// Calls [update_relay](crate::Overlord::update_relay)
     UpdateRelay(Relay, Relay),
 
+    /// Calls [update_storage_stats](crate::Overlord::update_storage_stats)
+    UpdateStorageStats,
+
+    /// Calls [upgrade_timestamps](crate::Overlord::upgrade_timestamps)
+    UpgradeTimestamps,
+
+    /// Calls [upload_media](crate::Overlord::upload_media)
+    UploadMedia(PathBuf),
+
     /// Calls [visible_notes_changed](crate::Overlord::visible_notes_changed)
     VisibleNotesChanged(Vec<Id>),
 
@@ -239,11 +386,20 @@ pub(crate) enum ToMinionPayloadDetail {
     AdvertiseRelayList(Box<Event>),
     AuthApproved,
     AuthDeclined,
+    FetchCalendarEvents,
     FetchEvent(Id),
     FetchEventAddr(EventAddr),
+    FetchHandlers(EventKind),
+    FetchIdentityMentions(Vec<PublicKey>, Unixtime),
+    FetchLongFormArticles,
+    FetchMarketplace,
+    FetchStarterPacks,
+    FetchWikiArticles(String),
     PostEvents(Vec<Event>),
+    SearchNotes(String),
     Shutdown,
     SubscribeAugments(Vec<IdHex>),
+    SubscribeCommunityFeed(EventAddr),
     SubscribeOutbox,
     SubscribeDiscover(Vec<PublicKey>),
     SubscribeGeneralFeed(Vec<PublicKey>),
@@ -262,6 +418,7 @@ pub(crate) enum ToMinionPayloadDetail {
     },
     TempSubscribeInboxFeedChunk(Unixtime),
     TempSubscribeMetadata(Vec<PublicKey>),
+    UnsubscribeCommunityFeed,
     UnsubscribePersonFeed,
     UnsubscribeThreadFeed,
 }
@@ -285,7 +442,9 @@ pub enum RelayConnectionReason {
     PostMetadata,
     PostMuteList,
     PostNostrConnect,
+    ReadCommunity,
     ReadThread,
+    SearchNotes,
     SubscribePerson,
 }
 
@@ -317,7 +476,9 @@ impl RelayConnectionReason {
             PostMuteList => "Posting our mute list",
             PostMetadata => "Posting our metadata",
             PostNostrConnect => "Posting nostrconnect",
+            ReadCommunity => "Reading the approved posts of a community",
             ReadThread => "Reading ancestors to build a thread",
+            SearchNotes => "Searching for notes on a search relay",
             SubscribePerson => "Subscribe to the events of a person",
         }
     }
@@ -342,7 +503,9 @@ impl RelayConnectionReason {
             PostMuteList => false,
             PostMetadata => false,
             PostNostrConnect => false,
+            ReadCommunity => true,
             ReadThread => true,
+            SearchNotes => false,
             SubscribePerson => false,
         }
     }
@@ -0,0 +1,155 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use gossip_relay_picker::Direction;
+use nostr_types::{Event, EventAddr, EventKind, Id, PublicKey, RelayUrl};
+
+/// A moderated community (NIP-72), as defined by a kind 34550 event
+#[derive(Debug, Clone)]
+pub struct Community {
+    pub addr: EventAddr,
+    pub name: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub moderators: Vec<PublicKey>,
+    pub relays: Vec<RelayUrl>,
+}
+
+impl Community {
+    /// Parse a community from its definition event
+    pub fn from_event(event: &Event) -> Option<Community> {
+        if event.kind != EventKind::CommunityDefinition {
+            return None;
+        }
+
+        let mut d = String::new();
+        let mut name: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut image: Option<String> = None;
+        let mut moderators: Vec<PublicKey> = Vec::new();
+        let mut relays: Vec<RelayUrl> = Vec::new();
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "name" => name = Some(tag.value().to_owned()),
+                "description" => description = Some(tag.value().to_owned()),
+                "image" => image = Some(tag.value().to_owned()),
+                "relay" => {
+                    if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                        relays.push(url);
+                    }
+                }
+                "p" => {
+                    if let Ok((pubkey, _, Some(role))) = tag.parse_pubkey() {
+                        if role == "moderator" && !moderators.contains(&pubkey) {
+                            moderators.push(pubkey);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let name = name.unwrap_or_else(|| d.clone());
+
+        Some(Community {
+            addr: EventAddr {
+                d,
+                relays: relays.iter().map(|r| r.to_unchecked_url()).collect(),
+                kind: EventKind::CommunityDefinition,
+                author: event.pubkey,
+            },
+            name,
+            description,
+            image,
+            moderators,
+            relays,
+        })
+    }
+
+    /// Load a community definition from local storage
+    pub fn load(addr: &EventAddr) -> Result<Option<Community>, Error> {
+        Ok(GLOBALS
+            .storage
+            .get_replaceable_event(EventKind::CommunityDefinition, addr.author, &addr.d)?
+            .and_then(|event| Community::from_event(&event)))
+    }
+
+    /// The community owner is always considered a moderator
+    pub fn is_moderator(&self, pubkey: PublicKey) -> bool {
+        self.addr.author == pubkey || self.moderators.contains(&pubkey)
+    }
+
+    /// The value used in 'a' tags that reference this community
+    pub fn a_tag_value(&self) -> String {
+        a_tag_value(&self.addr)
+    }
+
+    /// Are we a moderator of this community?
+    pub fn we_moderate(&self) -> bool {
+        match GLOBALS.identity.public_key() {
+            Some(pk) => self.is_moderator(pk),
+            None => false,
+        }
+    }
+
+    /// Get the Ids of posts approved by a moderator of this community, newest
+    /// approval first. Approvals from non-moderators are ignored.
+    pub fn approved_posts(&self) -> Result<Vec<Id>, Error> {
+        let value = self.a_tag_value();
+        let approvals = GLOBALS.storage.find_tagged_events(
+            "a",
+            Some(&value),
+            |e| e.kind == EventKind::CommunityPostApproval && self.is_moderator(e.pubkey),
+            true,
+        )?;
+
+        let mut ids: Vec<Id> = Vec::new();
+        for approval in &approvals {
+            for tag in &approval.tags {
+                if let Ok((id, _, _)) = tag.parse_event() {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// The community relays, or if none are listed, the owner's write relays
+    pub fn relay_urls(&self) -> Result<Vec<RelayUrl>, Error> {
+        if !self.relays.is_empty() {
+            return Ok(self.relays.clone());
+        }
+        Ok(GLOBALS
+            .storage
+            .get_best_relays(self.addr.author, Direction::Write)?
+            .drain(..)
+            .map(|(url, _)| url)
+            .collect())
+    }
+}
+
+/// Format a community address the way it appears in 'a' tags
+pub(crate) fn a_tag_value(addr: &EventAddr) -> String {
+    format!(
+        "{}:{}:{}",
+        u32::from(addr.kind),
+        addr.author.as_hex_string(),
+        addr.d
+    )
+}
+
+/// If the event was posted to a community, get the community address
+pub fn community_of_event(event: &Event) -> Option<EventAddr> {
+    for tag in &event.tags {
+        if let Ok((ea, _)) = tag.parse_address() {
+            if ea.kind == EventKind::CommunityDefinition {
+                return Some(ea);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::profile::Profile;
+use lazy_static::lazy_static;
+use nostr_types::{NostrBech32, NostrUrl};
+use parking_lot::RwLock as PRwLock;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// What a running gossip answers when it takes a link, so we don't mistake some
+/// other program that took over a stale port for it
+const ACK: &str = "gossip ok";
+
+/// Links are short. Anything longer is not for us.
+const MAX_LINK_LEN: usize = 4096;
+
+lazy_static! {
+    static ref PENDING: PRwLock<Vec<NostrBech32>> = PRwLock::new(Vec::new());
+}
+
+/// Whether a command line argument is a nostr: link (as handed to us by the OS)
+pub fn is_nostr_link(arg: &str) -> bool {
+    arg.len() > 6
+        && arg
+            .get(..6)
+            .map(|s| s.eq_ignore_ascii_case("nostr:"))
+            .unwrap_or(false)
+}
+
+/// Parse a nostr: link (NIP-21)
+pub fn parse(link: &str) -> Option<NostrBech32> {
+    // Some browsers hand over nostr://..., which NIP-21 does not allow
+    let link = link.trim().replacen("nostr://", "nostr:", 1);
+    NostrUrl::try_from_string(&link).map(|url| url.0)
+}
+
+/// Queue a link to be opened by the UI
+pub fn open(link: &str) -> Result<(), Error> {
+    match parse(link) {
+        Some(bech32) => {
+            PENDING.write().push(bech32);
+            Ok(())
+        }
+        None => Err(format!("Not a nostr link: {}", link).into()),
+    }
+}
+
+/// Take the links waiting to be opened
+pub fn take_pending() -> Vec<NostrBech32> {
+    std::mem::take(&mut *PENDING.write())
+}
+
+/// Hand a link to gossip if it is already running for this profile. Returns
+/// true if it took it, in which case we need not start.
+pub fn forward(link: &str) -> bool {
+    match forward_inner(link) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::debug!("No running gossip took the link: {}", e);
+            false
+        }
+    }
+}
+
+fn forward_inner(link: &str) -> Result<(), Error> {
+    let port: u16 = std::fs::read_to_string(port_file()?)?
+        .trim()
+        .parse()
+        .map_err(|_| Error::from("Bad port file"))?;
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(1))?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    stream.write_all(format!("{}\n", link.trim()).as_bytes())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    if answer.trim() == ACK {
+        Ok(())
+    } else {
+        Err("Unexpected answer".into())
+    }
+}
+
+/// Listen on localhost for links handed over by later launches of gossip
+pub(crate) fn start() {
+    tokio::task::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", 0)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Could not listen for nostr: links: {}", e);
+                return;
+            }
+        };
+
+        let port_file = match port_file() {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return;
+            }
+        };
+        let port = match listener.local_addr() {
+            Ok(addr) => addr.port(),
+            Err(e) => {
+                tracing::error!("{}", e);
+                return;
+            }
+        };
+        if let Err(e) = std::fs::write(&port_file, format!("{}", port)) {
+            tracing::error!("Could not write {}: {}", port_file.display(), e);
+            return;
+        }
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("nostr: link listener: {}", e);
+                        continue;
+                    }
+                },
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {
+                    if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                        let _ = std::fs::remove_file(&port_file);
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            tokio::task::spawn(async move {
+                if let Err(e) = serve(stream).await {
+                    tracing::debug!("nostr: link listener: {}", e);
+                }
+            });
+        }
+    });
+}
+
+async fn serve(mut stream: TcpStream) -> Result<(), Error> {
+    let (reader, mut writer) = stream.split();
+    let mut line = String::new();
+    tokio::time::timeout(
+        Duration::from_secs(2),
+        tokio::io::BufReader::new(reader.take(MAX_LINK_LEN as u64)).read_line(&mut line),
+    )
+    .await
+    .map_err(|_| Error::from("Timed out"))??;
+
+    open(line.trim())?;
+
+    writer.write_all(format!("{}\n", ACK).as_bytes()).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+fn port_file() -> Result<PathBuf, Error> {
+    let mut path = Profile::current()?.profile_dir;
+    path.push("link.port");
+    Ok(path)
+}
@@ -1,7 +1,9 @@
-use crate::error::Error;
+use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
-use nostr_types::{PublicKey, Tag};
+use crate::storage::json_key;
+use nostr_types::{DelegationConditions, EventKind, PublicKey, Tag, Unixtime};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 
 /// A delegation tag to use when posting events on another's behalf
 #[derive(Default)]
@@ -66,3 +68,104 @@ impl Delegation {
         Ok(())
     }
 }
+
+/// A delegation we issued, letting another key (such as that of another
+/// device) post on our behalf (NIP-26)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedDelegation {
+    pub delegatee: PublicKey,
+
+    /// The conditions query string, e.g. "kind=1&created_at<1700000000"
+    pub conditions: String,
+
+    /// The delegation tag as JSON, for entering on the other device
+    pub tag: String,
+
+    pub issued_at: Unixtime,
+
+    /// Whether we stopped vouching for it. Delegations cannot be taken back
+    /// on the network, so this only records that we no longer want it used.
+    pub revoked: bool,
+}
+
+/// Issue a delegation to another key, for the given kind within the given
+/// time window. No kind means any kind. This needs our identity unlocked.
+///
+/// NIP-26 conditions must all hold, so one delegation cannot cover a few
+/// kinds: "kind=1&kind=7" would allow no event at all.
+pub fn issue_delegation(
+    delegatee: PublicKey,
+    kind: Option<EventKind>,
+    created_after: Option<Unixtime>,
+    created_before: Option<Unixtime>,
+) -> Result<IssuedDelegation, Error> {
+    let delegator = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+    if delegatee == delegator {
+        return Err(ErrorKind::General("A key cannot delegate to itself".to_owned()).into());
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(kind) = kind {
+        parts.push(format!("kind={}", u32::from(kind)));
+    }
+    if let Some(after) = created_after {
+        parts.push(format!("created_at>{}", after.0));
+    }
+    if let Some(before) = created_before {
+        parts.push(format!("created_at<{}", before.0));
+    }
+    let conditions = parts.join("&");
+
+    let delegation_conditions = DelegationConditions::try_from_str(&conditions)?;
+    let signature = GLOBALS
+        .identity
+        .generate_delegation_signature(delegatee, &delegation_conditions)?;
+    let tag = Tag::new(&[
+        "delegation",
+        &delegator.as_hex_string(),
+        &conditions,
+        &signature.as_hex_string(),
+    ]);
+
+    let issued = IssuedDelegation {
+        delegatee,
+        conditions,
+        tag: serde_json::to_string(&tag)?,
+        issued_at: Unixtime::now().unwrap(),
+        revoked: false,
+    };
+
+    let mut all = issued_delegations()?;
+    all.push(issued.clone());
+    save_issued_delegations(&all)?;
+
+    Ok(issued)
+}
+
+/// The delegations we issued, oldest first
+pub fn issued_delegations() -> Result<Vec<IssuedDelegation>, Error> {
+    Ok(GLOBALS
+        .storage
+        .read_json(json_key::ISSUED_DELEGATIONS)?
+        .unwrap_or_default())
+}
+
+/// Mark an issued delegation, by its tag, as no longer vouched for
+pub fn revoke_delegation(tag: &str) -> Result<(), Error> {
+    let mut all = issued_delegations()?;
+    for issued in all.iter_mut() {
+        if issued.tag == tag {
+            issued.revoked = true;
+        }
+    }
+    save_issued_delegations(&all)
+}
+
+fn save_issued_delegations(all: &[IssuedDelegation]) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .write_json(json_key::ISSUED_DELEGATIONS, all, None)
+}
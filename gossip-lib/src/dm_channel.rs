@@ -105,4 +105,7 @@ pub struct DmChannelData {
     pub latest_message_content: Option<String>,
     pub message_count: usize,
     pub unread_message_count: usize,
+
+    /// How many of the messages use the older NIP-04 encryption
+    pub legacy_message_count: usize,
 }
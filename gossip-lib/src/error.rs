@@ -26,6 +26,7 @@ pub enum ErrorKind {
     Nip46NeedApproval,
     Nip46ParsingError(String, String),
     Nip46RelayNeeded,
+    Nip46Remote(String),
     Nostr(nostr_types::Error),
     NoPublicKey,
     NoPrivateKey,
@@ -55,6 +56,7 @@ pub enum ErrorKind {
     ReqwestHttpError(reqwest::Error),
     SerdeJson(serde_json::Error),
     SliceError(std::array::TryFromSliceError),
+    Socks(tokio_socks::Error),
     Speedy(speedy::Error),
     Svg(usvg::Error),
     TagNotIndexed(String),
@@ -114,6 +116,7 @@ impl std::fmt::Display for Error {
             Nip46NeedApproval => write!(f, "NIP-46 approval needed"),
             Nip46ParsingError(_id, e) => write!(f, "NIP-46 parse error: {e}"),
             Nip46RelayNeeded => write!(f, "NIP-46 relay needed to respond."),
+            Nip46Remote(s) => write!(f, "NIP-46 remote signer: {s}"),
             Nostr(e) => write!(f, "Nostr: {e}"),
             NoPublicKey => write!(f, "No public key identity available."),
             NoPrivateKey => write!(f, "No private key available."),
@@ -145,6 +148,7 @@ impl std::fmt::Display for Error {
             ReqwestHttpError(e) => write!(f, "HTTP (reqwest) error: {e}"),
             SerdeJson(e) => write!(f, "SerdeJson Error: {e}"),
             SliceError(e) => write!(f, "Slice: {e}"),
+            Socks(e) => write!(f, "SOCKS proxy: {e}"),
             Speedy(e) => write!(f, "Speedy: {e}"),
             Svg(e) => write!(f, "SVG: {e}"),
             TagNotIndexed(s) => write!(f, "Tag not indexed: {s}"),
@@ -325,6 +329,12 @@ impl From<std::str::Utf8Error> for ErrorKind {
     }
 }
 
+impl From<tokio_socks::Error> for ErrorKind {
+    fn from(e: tokio_socks::Error) -> ErrorKind {
+        ErrorKind::Socks(e)
+    }
+}
+
 impl From<tungstenite::Error> for ErrorKind {
     fn from(e: tungstenite::Error) -> ErrorKind {
         ErrorKind::Websocket(e)
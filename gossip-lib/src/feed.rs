@@ -1,9 +1,19 @@
+use crate::alerts;
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::community::Community;
 use crate::dm_channel::DmChannel;
 use crate::error::Error;
 use crate::globals::GLOBALS;
+use crate::identities;
+use crate::nip13;
+use crate::nip32;
+use crate::nip40;
+use crate::nip48;
 use crate::people::PersonList;
-use nostr_types::{Event, EventKind, EventReference, Id, PublicKey, PublicKeyHex, Unixtime};
+use crate::storage::Snapshot;
+use nostr_types::{
+    Event, EventAddr, EventKind, EventReference, Id, PublicKey, PublicKeyHex, Unixtime,
+};
 use parking_lot::RwLock;
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -15,6 +25,7 @@ use tokio::task;
 pub enum FeedKind {
     List(PersonList, bool), // with replies
     Inbox(bool),            // indirect
+    CombinedInbox,          // every identity of the user
     Thread {
         id: Id, // FIXME, should be an EventReference
         referenced_by: Id,
@@ -22,6 +33,7 @@ pub enum FeedKind {
     },
     Person(PublicKey),
     DmChat(DmChannel),
+    Community(EventAddr),
 }
 
 impl std::fmt::Display for FeedKind {
@@ -33,12 +45,17 @@ impl std::fmt::Display for FeedKind {
                 _ => write!(f, "UNKNOWN"),
             },
             FeedKind::Inbox(_) => write!(f, "Inbox"),
+            FeedKind::CombinedInbox => write!(f, "All Inboxes"),
             FeedKind::Thread {
                 id,
                 referenced_by: _,
                 author: _,
             } => write!(f, "Thread {}", crate::names::hex_id_short(&(*id).into())),
             FeedKind::Person(pk) => write!(f, "{}", crate::names::best_name_from_pubkey_lookup(pk)),
+            FeedKind::Community(ea) => match Community::load(ea) {
+                Ok(Some(community)) => write!(f, "{}", community.name),
+                _ => write!(f, "{}", ea.d),
+            },
         }
     }
 }
@@ -48,9 +65,11 @@ impl FeedKind {
         match self {
             Self::List(_, _) => true,
             Self::Inbox(_) => true,
+            Self::CombinedInbox => true,
             Self::Thread { .. } => false, // always full
             Self::Person(_) => true,
-            Self::DmChat(_) => false, // always full
+            Self::DmChat(_) => false,    // always full
+            Self::Community(_) => false, // always full
         }
     }
 }
@@ -67,6 +86,7 @@ pub struct Feed {
     inbox_feed: RwLock<Vec<Id>>,
     person_feed: RwLock<Vec<Id>>,
     dm_chat_feed: RwLock<Vec<Id>>,
+    community_feed: RwLock<Vec<Id>>,
 
     // When feeds start
     general_feed_start: RwLock<Unixtime>,
@@ -95,6 +115,7 @@ impl Feed {
             inbox_feed: RwLock::new(Vec::new()),
             person_feed: RwLock::new(Vec::new()),
             dm_chat_feed: RwLock::new(Vec::new()),
+            community_feed: RwLock::new(Vec::new()),
             general_feed_start: RwLock::new(Unixtime::now().unwrap()),
             person_feed_start: RwLock::new(Unixtime::now().unwrap()),
             inbox_feed_start: RwLock::new(Unixtime::now().unwrap()),
@@ -140,6 +161,111 @@ impl Feed {
         start
     }
 
+    // Events in the inbox of the identity in use
+    fn my_inbox_events(
+        &self,
+        my_pubkey: PublicKey,
+        indirect: bool,
+        kinds_with_dms: &[EventKind],
+        dismissed: &HashSet<Id>,
+        now: Unixtime,
+        snapshot: &Snapshot,
+        labels: &nip32::LabelFilter,
+    ) -> Result<Vec<Event>, Error> {
+        // Unfortunately it is expensive to find all events referencing
+        // any of my events, and we don't have such an index.
+        //
+        // so for now we rely on the fact that replies are supposed to
+        // 'p' tag the authors of people up the chain (see last paragraph
+        // of NIP-10)
+
+        let my_event_ids: HashSet<Id> = GLOBALS.storage.find_event_ids(
+            kinds_with_dms,
+            &[my_pubkey], // pubkeys
+            None,         // since
+        )?;
+
+        let since: Unixtime = *self.inbox_feed_start.read();
+
+        let my_pubkeyhex: PublicKeyHex = my_pubkey.into();
+
+        let mut inbox_events: Vec<Event> = GLOBALS.storage.find_tagged_events(
+            "p",
+            Some(my_pubkeyhex.as_str()),
+            |e| {
+                if e.created_at < since || e.created_at > now {
+                    return false;
+                }
+                if !kinds_with_dms.contains(&e.kind) {
+                    return false;
+                }
+                if dismissed.contains(&e.id) {
+                    return false;
+                }
+                if nip40::is_expired(e, now) {
+                    return false;
+                }
+                if !nip13::passes_filter(e) {
+                    return false;
+                }
+                if !labels.passes(e) {
+                    return false;
+                }
+                if !nip48::passes_filter(e) {
+                    return false;
+                }
+                if e.kind == EventKind::GiftWrap || e.kind == EventKind::EncryptedDirectMessage {
+                    return true;
+                }
+
+                // Include if it directly replies to one of my events
+                match e.replies_to() {
+                    Some(EventReference::Id(id, _url, _marker)) => {
+                        if my_event_ids.contains(&id) {
+                            return true;
+                        }
+                    }
+                    Some(EventReference::Addr(ea)) => {
+                        if ea.author == my_pubkey {
+                            return true;
+                        }
+                    }
+                    None => (),
+                }
+
+                if indirect {
+                    // Include if it tags me
+                    e.people().iter().any(|(p, _, _)| *p == my_pubkey)
+                } else {
+                    // Include if it directly references me in the content
+                    e.people_referenced_in_content()
+                        .iter()
+                        .any(|p| *p == my_pubkey)
+                }
+            },
+            true,
+        )?;
+
+        // Events that matched the user's alert rules
+        for id in alerts::alerted_events() {
+            if dismissed.contains(&id) || inbox_events.iter().any(|e| e.id == id) {
+                continue;
+            }
+            if let Some(e) = snapshot.read_event(id)? {
+                if e.created_at >= since && e.created_at <= now && !nip40::is_expired(&e, now) {
+                    inbox_events.push(e);
+                }
+            }
+        }
+        inbox_events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(inbox_events)
+    }
+
+    pub(crate) fn inbox_feed_start(&self) -> Unixtime {
+        *self.inbox_feed_start.read()
+    }
+
     fn unlisten(&self) {
         let feed_kind = self.current_feed_kind.read().to_owned();
 
@@ -166,6 +292,18 @@ impl Feed {
                 },
             });
         }
+
+        // If not in the Community feed
+        if !matches!(feed_kind, FeedKind::Community(_)) {
+            // Stop listening to Community events
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::UnsubscribeCommunityFeed,
+                },
+            });
+        }
     }
 
     /// Change the feed to the main feed
@@ -193,6 +331,17 @@ impl Feed {
         self.unlisten();
     }
 
+    /// Change the feed to the inboxes of all of the user's identities
+    pub fn set_feed_to_combined_inbox(&self) {
+        *self.current_feed_kind.write() = FeedKind::CombinedInbox;
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+    }
+
     /// Change the feed to a thread
     pub fn set_feed_to_thread(&self, id: Id, referenced_by: Id, author: Option<PublicKey>) {
         *self.current_feed_kind.write() = FeedKind::Thread {
@@ -250,6 +399,22 @@ impl Feed {
             .send(ToOverlordMessage::SetDmChannel(channel));
     }
 
+    /// Change the feed to a moderated community's approved posts
+    pub fn set_feed_to_community(&self, addr: EventAddr) {
+        *self.current_feed_kind.write() = FeedKind::Community(addr.clone());
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        self.unlisten();
+
+        // Listen for Community events
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SetCommunityFeed(addr));
+    }
+
     /// Get the kind of the current feed
     pub fn get_feed_kind(&self) -> FeedKind {
         self.current_feed_kind.read().to_owned()
@@ -279,6 +444,12 @@ impl Feed {
         self.dm_chat_feed.read().clone()
     }
 
+    /// Read the Community feed
+    pub fn get_community_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.community_feed.read().clone()
+    }
+
     /// Get the parent of the current thread feed.
     /// The children should be recursively found via `GLOBALS.storage.get_replies(id)`
     pub fn get_thread_parent(&self) -> Option<Id> {
@@ -345,9 +516,19 @@ impl Feed {
         *self.interval_ms.write() = feed_recompute_interval_ms;
 
         // Filter further for the general feed
-        let dismissed = GLOBALS.dismissed.read().await.clone();
+        let dismissed: HashSet<Id> = GLOBALS
+            .storage
+            .read_dismissed_events()?
+            .into_iter()
+            .collect();
         let now = Unixtime::now().unwrap();
 
+        // One read transaction for the reads of this recompute
+        let snapshot = GLOBALS.storage.snapshot()?;
+
+        // Who labelled whom, gathered once rather than for every event
+        let labels = nip32::LabelFilter::new()?;
+
         let current_feed_kind = self.current_feed_kind.read().to_owned();
         match current_feed_kind {
             FeedKind::List(list, with_replies) => {
@@ -366,105 +547,76 @@ impl Feed {
                 let events: Vec<Id> = if pubkeys.is_empty() {
                     Default::default()
                 } else {
-                    GLOBALS
-                        .storage
-                        .find_events(
-                            &kinds_without_dms,
-                            &pubkeys, // pubkeys
-                            Some(since),
-                            |e| {
-                                e.created_at <= now // no future events
+                    let events = snapshot.find_events(
+                        &kinds_without_dms,
+                        &pubkeys, // pubkeys
+                        Some(since),
+                        |e| {
+                            e.created_at <= now // no future events
                                     && e.kind != EventKind::EncryptedDirectMessage // no DMs
                                     && e.kind != EventKind::DmChat // no DMs
                                     && !dismissed.contains(&e.id) // not dismissed
+                                    && !nip40::is_expired(e, now) // not expired
+                                    && labels.passes(e) // not excluded by label
+                                    && nip48::passes_filter(e) // not hidden as bridged
                                     && if !with_replies {
                                         e.replies_to().is_none() // is not a reply
                                     } else {
                                         true
                                     }
-                            },
-                            true,
-                        )?
-                        .iter()
-                        .map(|e| e.id)
-                        .collect()
+                        },
+                    )?;
+
+                    // The user's feed script (if any) gets the final say
+                    crate::filter::apply_feed_script(events)
                 };
 
                 *self.followed_feed.write() = events;
             }
             FeedKind::Inbox(indirect) => {
                 if let Some(my_pubkey) = GLOBALS.identity.public_key() {
-                    // Unfortunately it is expensive to find all events referencing
-                    // any of my events, and we don't have such an index.
-                    //
-                    // so for now we rely on the fact that replies are supposed to
-                    // 'p' tag the authors of people up the chain (see last paragraph
-                    // of NIP-10)
-
-                    let my_event_ids: HashSet<Id> = GLOBALS.storage.find_event_ids(
+                    let inbox_events = self.my_inbox_events(
+                        my_pubkey,
+                        indirect,
                         &kinds_with_dms,
-                        &[my_pubkey], // pubkeys
-                        None,         // since
+                        &dismissed,
+                        now,
+                        &snapshot,
+                        &labels,
                     )?;
+                    *self.inbox_feed.write() = inbox_events.iter().map(|e| e.id).collect();
+                }
+            }
+            FeedKind::CombinedInbox => {
+                let mut inbox_events: Vec<Event> = match GLOBALS.identity.public_key() {
+                    Some(my_pubkey) => self.my_inbox_events(
+                        my_pubkey,
+                        true,
+                        &kinds_with_dms,
+                        &dismissed,
+                        now,
+                        &snapshot,
+                        &labels,
+                    )?,
+                    None => vec![],
+                };
 
-                    let since: Unixtime = *self.inbox_feed_start.read();
-
-                    let my_pubkeyhex: PublicKeyHex = my_pubkey.into();
-
-                    let inbox_events: Vec<Id> = GLOBALS
-                        .storage
-                        .find_tagged_events(
-                            "p",
-                            Some(my_pubkeyhex.as_str()),
-                            |e| {
-                                if e.created_at < since || e.created_at > now {
-                                    return false;
-                                }
-                                if !kinds_with_dms.contains(&e.kind) {
-                                    return false;
-                                }
-                                if dismissed.contains(&e.id) {
-                                    return false;
-                                }
-                                if e.kind == EventKind::GiftWrap
-                                    || e.kind == EventKind::EncryptedDirectMessage
-                                {
-                                    return true;
-                                }
-
-                                // Include if it directly replies to one of my events
-                                match e.replies_to() {
-                                    Some(EventReference::Id(id, _url, _marker)) => {
-                                        if my_event_ids.contains(&id) {
-                                            return true;
-                                        }
-                                    }
-                                    Some(EventReference::Addr(ea)) => {
-                                        if ea.author == my_pubkey {
-                                            return true;
-                                        }
-                                    }
-                                    None => (),
-                                }
-
-                                if indirect {
-                                    // Include if it tags me
-                                    e.people().iter().any(|(p, _, _)| *p == my_pubkey)
-                                } else {
-                                    // Include if it directly references me in the content
-                                    e.people_referenced_in_content()
-                                        .iter()
-                                        .any(|p| *p == my_pubkey)
-                                }
-                            },
-                            true,
-                        )?
-                        .iter()
-                        .map(|e| e.id)
-                        .collect();
-
-                    *self.inbox_feed.write() = inbox_events;
+                let since: Unixtime = *self.inbox_feed_start.read();
+                for event in identities::mentions_of_others(&kinds_with_dms, since, |e| {
+                    e.created_at <= now
+                        && !dismissed.contains(&e.id)
+                        && !nip40::is_expired(e, now)
+                        && nip13::passes_filter(e)
+                        && labels.passes(e)
+                        && nip48::passes_filter(e)
+                })? {
+                    if !inbox_events.iter().any(|e| e.id == event.id) {
+                        inbox_events.push(event);
+                    }
                 }
+                inbox_events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+                *self.inbox_feed.write() = inbox_events.iter().map(|e| e.id).collect();
             }
             FeedKind::Thread { .. } => {
                 // Potentially update thread parent to a higher parent
@@ -486,6 +638,9 @@ impl Feed {
                     if dismissed.contains(&e.id) {
                         return false;
                     }
+                    if nip40::is_expired(e, now) {
+                        return false;
+                    }
                     if !kinds_without_dms.contains(&e.kind) {
                         return false;
                     }
@@ -521,6 +676,34 @@ impl Feed {
                 let ids = GLOBALS.storage.dm_events(&channel)?;
                 *self.dm_chat_feed.write() = ids;
             }
+            FeedKind::Community(addr) => {
+                // Only posts approved by a moderator are shown
+                let ids: Vec<Id> = match Community::load(&addr)? {
+                    Some(community) => community.approved_posts()?,
+                    None => Vec::new(),
+                };
+
+                let mut events: Vec<Event> = Vec::new();
+                for id in ids {
+                    if dismissed.contains(&id) {
+                        continue;
+                    }
+                    if let Some(event) = snapshot.read_event(id)? {
+                        if event.created_at <= now
+                            && event.kind.is_feed_displayable()
+                            && !nip40::is_expired(&event, now)
+                        {
+                            events.push(event);
+                        }
+                    }
+                }
+
+                events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+
+                let events: Vec<Id> = events.iter().map(|e| e.id).collect();
+
+                *self.community_feed.write() = events;
+            }
         }
 
         self.recompute_lock.store(false, Ordering::Relaxed);
@@ -535,8 +718,13 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
     let show_long_form = GLOBALS.storage.read_setting_show_long_form();
     let direct_messages = GLOBALS.storage.read_setting_direct_messages();
     let enable_zap_receipts = GLOBALS.storage.read_setting_enable_zap_receipts();
+    let show_git_events = GLOBALS.storage.read_setting_show_git_events();
+    let show_torrents = GLOBALS.storage.read_setting_show_torrents();
+    let show_live_activities = GLOBALS.storage.read_setting_show_live_activities();
+    let show_calendar_events = GLOBALS.storage.read_setting_show_calendar_events();
+    let verify_timestamps = GLOBALS.storage.read_setting_verify_timestamps();
 
-    EventKind::iter()
+    let mut kinds: Vec<EventKind> = EventKind::iter()
         .filter(|k| {
             *k == EventKind::Metadata
                 || *k == EventKind::TextNote
@@ -560,17 +748,17 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
             //|| *k == EventKind::PublicChatReserved47
             //|| *k == EventKind::PublicChatReserved48
             //|| *k == EventKind::PublicChatReserved49
-            // || *k == EventKind::Timestamp
+                || ((*k == EventKind::Timestamp) && verify_timestamps)
                 || ((*k == EventKind::GiftWrap) && direct_messages)
             // || *k == EventKind::FileMetadata
             // || *k == EventKind::LiveChatMessage
             // || *k == EventKind::ProblemTracker
-            // || *k == EventKind::Reporting
-            // || *k == EventKind::Label
+                || *k == EventKind::Reporting
+                || *k == EventKind::Label
             // || *k == EventKind::CommunityPost
             // || *k == EventKind::CommunityPostApproval
             // || *k == EventKind::JobFeedback
-            // || *k == EventKind::ZapGoal
+                || ((*k == EventKind::ZapGoal) && enable_zap_receipts)
                 || *k == EventKind::ZapRequest
                 || ((*k == EventKind::Zap) && enable_zap_receipts)
             // || *k == EventKind::Highlights
@@ -604,26 +792,49 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
             // || *k == EventKind::DraftLongFormContent
             // || *k == EventKind::EmojiSets
             // || *k == EventKind::AppSpecificData
-            // || *k == EventKind::LiveEvent
+                || ((*k == EventKind::LiveEvent) && show_live_activities)
             // || *k == EventKind::UserStatus
             // || *k == EventKind::ClassifiedListing
             // || *k == EventKind::DraftClassifiedListing
-            // || *k == EventKind::DateBasedCalendarEvent
-            // || *k == EventKind::TimeBasedCalendarEvent
+                || ((*k == EventKind::DateBasedCalendarEvent) && show_calendar_events)
+                || ((*k == EventKind::TimeBasedCalendarEvent) && show_calendar_events)
             // || *k == EventKind::Calendar
-            // || *k == EventKind::CalendarEventRsvp
+                || ((*k == EventKind::CalendarEventRsvp) && show_calendar_events)
             // || *k == EventKind::HandlerRecommendation
             // || *k == EventKind::HandlerInformation
             // || *k == EventKind::CommunityDefinition
         })
-        .collect()
+        .collect();
+
+    // Kinds not (yet) known to nostr-types
+    if show_git_events {
+        for k in crate::nip34::git_event_kinds() {
+            if !kinds.contains(&k) {
+                kinds.push(k);
+            }
+        }
+    }
+    if show_torrents {
+        kinds.push(crate::nip35::torrent_kind());
+    }
+    if show_long_form && !kinds.contains(&crate::nip54::wiki_kind()) {
+        kinds.push(crate::nip54::wiki_kind());
+    }
+
+    kinds
 }
 
 pub fn feed_related_event_kinds(dms: bool) -> Vec<EventKind> {
     enabled_event_kinds()
         .drain(..)
         .filter(|k| {
-            k.is_feed_related()
+            (k.is_feed_related()
+                || crate::nip34::is_git_kind(*k)
+                || crate::nip35::is_torrent(*k)
+                || crate::nip52::is_calendar_event(*k)
+                || crate::nip53::is_live_event(*k)
+                || crate::nip54::is_wiki(*k)
+                || crate::nip75::is_zap_goal(*k))
                 && (dms
                     || (*k != EventKind::EncryptedDirectMessage
                         && *k != EventKind::DmChat
@@ -636,7 +847,13 @@ pub fn feed_displayable_event_kinds(dms: bool) -> Vec<EventKind> {
     enabled_event_kinds()
         .drain(..)
         .filter(|k| {
-            k.is_feed_displayable()
+            (k.is_feed_displayable()
+                || crate::nip34::is_git_kind(*k)
+                || crate::nip35::is_torrent(*k)
+                || crate::nip52::is_calendar_event(*k)
+                || crate::nip53::is_live_event(*k)
+                || crate::nip54::is_wiki(*k)
+                || crate::nip75::is_zap_goal(*k))
                 && (dms
                     || (*k != EventKind::EncryptedDirectMessage
                         && *k != EventKind::DmChat
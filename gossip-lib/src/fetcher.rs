@@ -59,7 +59,7 @@ impl Fetcher {
         let timeout =
             std::time::Duration::new(GLOBALS.storage.read_setting_fetcher_timeout_sec(), 0);
         *GLOBALS.fetcher.client.write().unwrap() = Some(
-            Client::builder()
+            crate::proxy::client_builder()?
                 .gzip(true)
                 .brotli(true)
                 .deflate(true)
@@ -298,6 +298,12 @@ impl Fetcher {
         if GLOBALS.storage.read_setting_set_user_agent() {
             req = req.header("User-Agent", USER_AGENT);
         };
+        if GLOBALS.identity.is_unlocked() && crate::nip98::host_wants_auth(&host) {
+            match crate::nip98::authorization(url.as_str(), "GET", None) {
+                Ok(authorization) => req = req.header("Authorization", authorization),
+                Err(e) => tracing::warn!("FETCH {url}: Could not sign HTTP auth: {e}"),
+            }
+        }
 
         enum FailOutcome {
             Fail,
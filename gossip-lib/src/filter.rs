@@ -1,10 +1,20 @@
 use crate::globals::GLOBALS;
 use crate::people::Person;
 use crate::profile::Profile;
-use nostr_types::{Event, EventKind, Id, Rumor};
-use rhai::{Engine, Scope, AST};
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, Id, PublicKey, Rumor, Unixtime};
+use parking_lot::RwLock as PRwLock;
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 
+/// How long web of trust scores are kept before they are counted again
+const WOT_SCORE_SECS: i64 = 60 * 10;
+
+/// How many operations a script may take for one event before it is stopped,
+/// so that a script that loops cannot hang feed building or event processing
+pub(crate) const MAX_SCRIPT_OPERATIONS: u64 = 50_000;
+
 #[derive(Clone, Copy, Debug)]
 pub enum EventFilterAction {
     Deny,
@@ -13,6 +23,15 @@ pub enum EventFilterAction {
 }
 
 pub fn load_script(engine: &Engine) -> Option<AST> {
+    load(engine, "filter.rhai", "spam filter")
+}
+
+/// Load the user's feed script (feed.rhai), if they have one
+pub fn load_feed_script(engine: &Engine) -> Option<AST> {
+    load(engine, "feed.rhai", "feed script")
+}
+
+fn load(engine: &Engine, filename: &str, what: &str) -> Option<AST> {
     let profile = match Profile::current() {
         Ok(profile) => profile,
         Err(e) => {
@@ -22,12 +41,12 @@ pub fn load_script(engine: &Engine) -> Option<AST> {
     };
 
     let mut path = profile.profile_dir.clone();
-    path.push("filter.rhai");
+    path.push(filename);
 
     let script = match fs::read_to_string(&path) {
         Ok(script) => script,
         Err(e) => {
-            tracing::info!("No spam filter: {}", e);
+            tracing::info!("No {}: {}", what, e);
             return None;
         }
     };
@@ -35,12 +54,12 @@ pub fn load_script(engine: &Engine) -> Option<AST> {
     let ast = match engine.compile(script) {
         Ok(ast) => ast,
         Err(e) => {
-            tracing::error!("Failed to compile spam filter: {}", e);
+            tracing::error!("Failed to compile {}: {}", what, e);
             return None;
         }
     };
 
-    tracing::info!("Spam filter loaded.");
+    tracing::info!("Loaded {}.", what);
 
     Some(ast)
 }
@@ -64,6 +83,20 @@ pub fn filter_rumor(rumor: Rumor, author: Option<Person>, id: Id) -> EventFilter
             None => false,
         },
     );
+    scope.push(
+        "reports",
+        crate::nip56::reports_of_person(rumor.pubkey)
+            .map(|r| r.len())
+            .unwrap_or(0) as i64,
+    );
+    scope.push(
+        "labels",
+        crate::nip32::labels_of_person(rumor.pubkey)
+            .unwrap_or_default()
+            .iter()
+            .map(|l| Dynamic::from(l.to_string()))
+            .collect::<Array>(),
+    );
 
     filter(scope, id)
 }
@@ -87,6 +120,14 @@ pub fn filter_event(event: Event, author: Option<Person>) -> EventFilterAction {
             None => false,
         },
     );
+    scope.push("reports", crate::nip56::report_count(&event) as i64);
+    scope.push(
+        "labels",
+        crate::nip32::all_labels(&event)
+            .iter()
+            .map(|l| Dynamic::from(l.to_string()))
+            .collect::<Array>(),
+    );
 
     filter(scope, event.id)
 }
@@ -117,4 +158,99 @@ fn filter(mut scope: Scope, id: Id) -> EventFilterAction {
     }
 }
 
+/// Run the user's feed script over the candidate events of a feed, which are
+/// newest first. Events it scores 0 are dropped, and the rest are put in order
+/// of their score, newest first among equal scores.
+pub(crate) fn apply_feed_script(events: Vec<Event>) -> Vec<Id> {
+    let ast = match &GLOBALS.feed_script {
+        Some(ast) => ast,
+        None => return events.iter().map(|e| e.id).collect(),
+    };
+
+    let followed: Vec<PublicKey> = GLOBALS.people.get_subscribed_pubkeys();
+    let wot = wot_scores(&followed);
+    let followed: HashSet<PublicKey> = followed.into_iter().collect();
+
+    let mut scored: Vec<(i64, Id)> = Vec::new();
+    for event in events {
+        let mut scope = Scope::new();
+        scope.push("id", event.id.as_hex_string());
+        scope.push("pubkey", event.pubkey.as_hex_string());
+        scope.push("kind", <EventKind as Into<u32>>::into(event.kind));
+        scope.push("created_at", event.created_at.0);
+        scope.push("content", event.content.clone());
+        scope.push(
+            "tags",
+            event
+                .tags
+                .iter()
+                .map(|t| {
+                    Dynamic::from(
+                        (0..)
+                            .map(|i| t.get_index(i))
+                            .take_while(|v| !v.is_empty())
+                            .map(|v| Dynamic::from(v.to_owned()))
+                            .collect::<Array>(),
+                    )
+                })
+                .collect::<Array>(),
+        );
+        scope.push("followed", followed.contains(&event.pubkey));
+        scope.push(
+            "nip05valid",
+            match GLOBALS.storage.read_person(&event.pubkey) {
+                Ok(Some(person)) => person.nip05_valid,
+                _ => false,
+            },
+        );
+        scope.push("wot", wot.get(&event.pubkey).copied().unwrap_or(0) as i64);
+        scope.push("is_reply", event.replies_to().is_some());
+
+        let score = match GLOBALS
+            .filter_engine
+            .call_fn::<i64>(&mut scope, ast, "feed", ())
+        {
+            Ok(score) => score,
+            Err(e) => {
+                tracing::error!("{}", e);
+                1
+            }
+        };
+        if score > 0 {
+            scored.push((score, event.id));
+        }
+    }
+
+    // This sort is stable, so equal scores stay newest first
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, id)| id).collect()
+}
+
+lazy_static! {
+    static ref WOT_SCORES: PRwLock<(HashMap<PublicKey, usize>, Unixtime)> =
+        PRwLock::new((HashMap::new(), Unixtime(0)));
+}
+
+// How many of the people we follow have each person in their contact list.
+// Feeds are recomputed often, so these are kept for a while.
+fn wot_scores(followed: &[PublicKey]) -> HashMap<PublicKey, usize> {
+    let now = Unixtime::now().unwrap();
+    {
+        let scores = WOT_SCORES.read();
+        if now.0 - scores.1 .0 < WOT_SCORE_SECS {
+            return scores.0.clone();
+        }
+    }
+
+    let scores = match crate::follow_back::follows_of(followed) {
+        Ok(scores) => scores,
+        Err(e) => {
+            tracing::error!("{}", e);
+            HashMap::new()
+        }
+    };
+    *WOT_SCORES.write() = (scores.clone(), now);
+    scores
+}
+
 // Only call the filter if the author isn't followed
@@ -0,0 +1,274 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::storage::json_key;
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// How long we give a new follower's metadata (and NIP-05) to come in before
+/// checking them against the rules
+const CHECK_DELAY: Duration = Duration::from_secs(30);
+
+/// A new follower who matched the follow-back rules, waiting for approval
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FollowBack {
+    pub pubkey: PublicKey,
+
+    /// When their contact list with us in it was made
+    pub followed_at: Unixtime,
+
+    /// How far they are from us in our web of trust, if known. 2 is being
+    /// followed by somebody we follow.
+    pub distance: Option<u8>,
+
+    pub nip05_valid: bool,
+
+    /// Their oldest event that we have
+    pub oldest_event: Option<Unixtime>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct FollowBackState {
+    queue: Vec<FollowBack>,
+
+    /// Followers that were followed back or dismissed, so they are not queued
+    /// again when their contact list comes in again
+    handled: Vec<PublicKey>,
+}
+
+lazy_static! {
+    // Followers being checked right now
+    static ref CHECKING: DashSet<PublicKey> = DashSet::new();
+}
+
+fn load() -> FollowBackState {
+    match GLOBALS.storage.read_json(json_key::FOLLOW_BACK_STATE) {
+        Ok(state) => state.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            FollowBackState::default()
+        }
+    }
+}
+
+fn save(state: &FollowBackState) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .write_json(json_key::FOLLOW_BACK_STATE, state, None)
+}
+
+/// New followers waiting for the user to follow them back, newest first
+pub fn queue() -> Vec<FollowBack> {
+    let mut queue = load().queue;
+    queue.sort_by(|a, b| b.followed_at.cmp(&a.followed_at));
+    queue
+}
+
+/// Follow back a queued follower
+pub fn approve(pubkey: PublicKey) -> Result<(), Error> {
+    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::FollowPubkey(
+        pubkey,
+        PersonList::Followed,
+        true,
+    ));
+    handled(pubkey)
+}
+
+/// Do not follow back a queued follower
+pub fn dismiss(pubkey: PublicKey) -> Result<(), Error> {
+    handled(pubkey)
+}
+
+fn handled(pubkey: PublicKey) -> Result<(), Error> {
+    let mut state = load();
+    state.queue.retain(|f| f.pubkey != pubkey);
+    if !state.handled.contains(&pubkey) {
+        state.handled.push(pubkey);
+    }
+    save(&state)
+}
+
+/// See whether somebody else's contact list makes them a new follower of
+/// ours, and if so check them against the follow-back rules
+pub(crate) fn check_contact_list(event: &Event) -> Result<(), Error> {
+    if event.kind != EventKind::ContactList || !GLOBALS.storage.read_setting_follow_back_enabled() {
+        return Ok(());
+    }
+
+    let me = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+
+    let pubkey = event.pubkey;
+    if pubkey == me
+        || !event.is_tagged(&me)
+        || GLOBALS
+            .people
+            .is_person_in_list(&pubkey, PersonList::Followed)
+        || GLOBALS.people.is_person_in_list(&pubkey, PersonList::Muted)
+    {
+        return Ok(());
+    }
+
+    let state = load();
+    if state.handled.contains(&pubkey) || state.queue.iter().any(|f| f.pubkey == pubkey) {
+        return Ok(());
+    }
+
+    if !CHECKING.insert(pubkey) {
+        return Ok(());
+    }
+
+    // Get their metadata, so their NIP-05 gets checked
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::UpdateMetadata(pubkey));
+
+    let followed_at = event.created_at;
+    tokio::task::spawn(async move {
+        tokio::time::sleep(CHECK_DELAY).await;
+        if let Err(e) = check_follower(pubkey, followed_at) {
+            tracing::error!("{}", e);
+        }
+        CHECKING.remove(&pubkey);
+    });
+
+    Ok(())
+}
+
+fn check_follower(pubkey: PublicKey, followed_at: Unixtime) -> Result<(), Error> {
+    let max_distance = GLOBALS.storage.read_setting_follow_back_max_wot_distance();
+    let require_nip05 = GLOBALS.storage.read_setting_follow_back_require_nip05();
+    let min_age_days = GLOBALS
+        .storage
+        .read_setting_follow_back_min_account_age_days();
+
+    let distance = wot_distance(pubkey)?;
+    if max_distance > 0 && !matches!(distance, Some(d) if d <= max_distance) {
+        return Ok(());
+    }
+
+    let nip05_valid = match GLOBALS.storage.read_person(&pubkey)? {
+        Some(person) => person.nip05_valid,
+        None => false,
+    };
+    if require_nip05 && !nip05_valid {
+        return Ok(());
+    }
+
+    let oldest_event = oldest_event(pubkey)?;
+    if min_age_days > 0 {
+        let now = Unixtime::now().unwrap();
+        match oldest_event {
+            Some(oldest) if now.0 - oldest.0 >= min_age_days as i64 * 60 * 60 * 24 => (),
+            _ => return Ok(()),
+        }
+    }
+
+    let follow_back = FollowBack {
+        pubkey,
+        followed_at,
+        distance,
+        nip05_valid,
+        oldest_event,
+    };
+
+    if GLOBALS.storage.read_setting_follow_back_automatically() {
+        approve(pubkey)?;
+        GLOBALS.status_queue.write().write(format!(
+            "Followed back {}",
+            crate::names::best_name_from_pubkey_lookup(&pubkey)
+        ));
+    } else {
+        let mut state = load();
+        if !state.queue.iter().any(|f| f.pubkey == pubkey) {
+            state.queue.push(follow_back);
+            save(&state)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// How far somebody is from us in our web of trust, as far as the contact
+/// lists we have tell: 1 if we follow them, 2 if somebody we follow does, 3
+/// if somebody followed by somebody we follow does.
+pub fn wot_distance(pubkey: PublicKey) -> Result<Option<u8>, Error> {
+    let followed: Vec<PublicKey> = GLOBALS.people.get_subscribed_pubkeys();
+    if followed.contains(&pubkey) {
+        return Ok(Some(1));
+    }
+
+    if followed_by_any(&followed, pubkey)? {
+        return Ok(Some(2));
+    }
+
+    let second: Vec<PublicKey> = follows_of(&followed)?.into_keys().collect();
+    if followed_by_any(&second, pubkey)? {
+        return Ok(Some(3));
+    }
+
+    Ok(None)
+}
+
+// Whether the contact list of any of these people has this person in it
+fn followed_by_any(pubkeys: &[PublicKey], pubkey: PublicKey) -> Result<bool, Error> {
+    if pubkeys.is_empty() {
+        return Ok(false);
+    }
+    Ok(!GLOBALS
+        .storage
+        .find_events_tagging(
+            &[EventKind::ContactList],
+            pubkeys,
+            None,
+            &[pubkey],
+            |_| true,
+            false,
+        )?
+        .is_empty())
+}
+
+/// Everybody in the contact lists we have of these people, with how many of
+/// them follow each
+pub(crate) fn follows_of(pubkeys: &[PublicKey]) -> Result<HashMap<PublicKey, usize>, Error> {
+    let mut follows: HashMap<PublicKey, usize> = HashMap::new();
+    if pubkeys.is_empty() {
+        return Ok(follows);
+    }
+
+    for event in GLOBALS
+        .storage
+        .find_events(&[EventKind::ContactList], pubkeys, None, |_| true, false)?
+        .iter()
+    {
+        let mut seen: HashSet<PublicKey> = HashSet::new();
+        for tag in &event.tags {
+            if let Ok((pk, _, _)) = tag.parse_pubkey() {
+                if seen.insert(pk) {
+                    *follows.entry(pk).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    Ok(follows)
+}
+
+fn oldest_event(pubkey: PublicKey) -> Result<Option<Unixtime>, Error> {
+    let mut kinds = crate::feed::feed_related_event_kinds(true);
+    kinds.push(EventKind::Metadata);
+    kinds.push(EventKind::ContactList);
+    Ok(GLOBALS
+        .storage
+        .find_events(&kinds, &[pubkey], None, |_| true, false)?
+        .iter()
+        .map(|e| e.created_at)
+        .min())
+}
@@ -0,0 +1,263 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
+use nostr_types::{EventKind, PublicKey, RelayUrl};
+use parking_lot::RwLock as PRwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Bridges that give Mastodon accounts a NIP-05 of `user_at_instance@bridge`
+const MASTODON_BRIDGES: [&str; 1] = ["mostr.pub"];
+
+/// How many handles are looked up at once
+const CONCURRENCY: usize = 8;
+
+/// An account from another network, and who it is on nostr (if we found out)
+#[derive(Clone, Debug)]
+pub struct ImportCandidate {
+    /// The account, as `user@instance` (Mastodon), `@handle` or `twitter:<id>` (Twitter)
+    pub handle: String,
+
+    /// Their nostr pubkey, if resolved
+    pub pubkey: Option<PublicKey>,
+
+    /// How they were resolved, or why not
+    pub via: String,
+
+    /// The relays their NIP-05 lists for them. These are only recorded if they
+    /// are followed.
+    pub relays: Vec<RelayUrl>,
+
+    /// Whether the user wants to follow them
+    pub selected: bool,
+}
+
+lazy_static! {
+    static ref CANDIDATES: PRwLock<Vec<ImportCandidate>> = PRwLock::new(Vec::new());
+}
+
+static RESOLVING: AtomicBool = AtomicBool::new(false);
+
+/// The accounts of the current import, for review
+pub fn candidates() -> Vec<ImportCandidate> {
+    CANDIDATES.read().clone()
+}
+
+/// Whether handles are still being looked up
+pub fn is_resolving() -> bool {
+    RESOLVING.load(Ordering::Relaxed)
+}
+
+/// Select or deselect an account for following
+pub fn set_selected(index: usize, selected: bool) {
+    if let Some(candidate) = CANDIDATES.write().get_mut(index) {
+        candidate.selected = selected && candidate.pubkey.is_some();
+    }
+}
+
+/// Forget the current import
+pub fn clear() {
+    CANDIDATES.write().clear();
+}
+
+/// The accounts in a Mastodon following CSV export, or in the `following.js`
+/// of a Twitter archive (or a plain list of @handles). Twitter archives only
+/// have account ids, which cannot be looked up, so a list of @handles is the
+/// way to import Twitter follows.
+pub fn parse_handles(text: &str) -> Vec<String> {
+    let mut handles: Vec<String> = Vec::new();
+
+    let trimmed = text.trim_start();
+    if trimmed.starts_with("window.YTD") || trimmed.starts_with('[') {
+        // Twitter archive: window.YTD.following.part0 = [ { "following": { ... } }, ... ]
+        let json = match trimmed.find('[') {
+            Some(start) => &trimmed[start..],
+            None => return handles,
+        };
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json).unwrap_or_default();
+        for entry in entries {
+            let following = &entry["following"];
+            let handle = ["userName", "screenName"]
+                .iter()
+                .find_map(|k| following[k].as_str())
+                .map(|name| format!("@{}", name.trim_start_matches('@')))
+                .or_else(|| {
+                    following["accountId"]
+                        .as_str()
+                        .map(|id| format!("twitter:{}", id))
+                });
+            if let Some(handle) = handle {
+                handles.push(handle);
+            }
+        }
+    } else {
+        // CSV, the account is the first column
+        for line in text.lines() {
+            let field = line.split(',').next().unwrap_or("").trim();
+            if field.is_empty() || field.eq_ignore_ascii_case("Account address") {
+                continue;
+            }
+            let handle = match field.trim_start_matches('@').contains('@') {
+                // user@instance
+                true => field.trim_start_matches('@').to_owned(),
+                // @handle
+                false => format!("@{}", field.trim_start_matches('@')),
+            };
+            handles.push(handle);
+        }
+    }
+
+    // Keep the first of each, in the order given
+    let mut seen: HashSet<String> = HashSet::new();
+    handles.retain(|h| seen.insert(h.to_lowercase()));
+    handles
+}
+
+/// Look up the nostr pubkeys of accounts, for review
+pub(crate) async fn resolve_all(handles: Vec<String>) {
+    RESOLVING.store(true, Ordering::Relaxed);
+    CANDIDATES.write().clear();
+
+    let twitter_claims = if handles.iter().any(|h| h.starts_with('@')) {
+        twitter_claims().unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+    let twitter_claims = &twitter_claims;
+
+    let candidates: Vec<ImportCandidate> = stream::iter(handles)
+        .map(|handle| async move {
+            let (pubkey, via, relays) = match resolve(&handle, twitter_claims).await {
+                Resolved::Found(pubkey, via, relays) => (Some(pubkey), via, relays),
+                Resolved::NotFound(why) => (None, why, vec![]),
+            };
+            ImportCandidate {
+                handle,
+                pubkey,
+                via,
+                relays,
+                selected: pubkey.is_some(),
+            }
+        })
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+
+    let found = candidates.iter().filter(|c| c.pubkey.is_some()).count();
+    GLOBALS.status_queue.write().write(format!(
+        "Found {} of {} accounts on nostr. Review them before following.",
+        found,
+        candidates.len()
+    ));
+
+    *CANDIDATES.write() = candidates;
+    RESOLVING.store(false, Ordering::Relaxed);
+}
+
+enum Resolved {
+    Found(PublicKey, String, Vec<RelayUrl>),
+    NotFound(String),
+}
+
+async fn resolve(handle: &str, twitter_claims: &HashMap<String, PublicKey>) -> Resolved {
+    if handle.starts_with("twitter:") {
+        return Resolved::NotFound("the archive does not have their handle".to_owned());
+    }
+
+    if let Some(name) = handle.strip_prefix('@') {
+        // Twitter
+        if let Some(pk) = twitter_claims.get(&name.to_lowercase()) {
+            return Resolved::Found(*pk, "claimed in their profile".to_owned(), vec![]);
+        }
+        for directory in directories() {
+            if let Ok(Some((pk, relays))) =
+                crate::nip05::lookup_nip05(&format!("{}@{}", name, directory)).await
+            {
+                return Resolved::Found(pk, format!("listed by {}", directory), relays);
+            }
+        }
+        return Resolved::NotFound("not claimed by anybody we know of".to_owned());
+    }
+
+    // Mastodon: maybe their domain serves NIP-05 too
+    if let Ok(Some((pk, relays))) = crate::nip05::lookup_nip05(handle).await {
+        return Resolved::Found(pk, "NIP-05".to_owned(), relays);
+    }
+
+    if let Ok((user, instance)) = crate::nip05::parse_nip05(handle) {
+        for bridge in MASTODON_BRIDGES {
+            let bridged = format!("{}_at_{}@{}", user, instance, bridge);
+            if let Ok(Some((pk, relays))) = crate::nip05::lookup_nip05(&bridged).await {
+                return Resolved::Found(pk, format!("bridged by {}", bridge), relays);
+            }
+        }
+    }
+
+    Resolved::NotFound("not found".to_owned())
+}
+
+// Twitter handles (lowercased) that people claim in their metadata (NIP-39),
+// from the metadata we have. The user reviews the matches, as the proofs are
+// not checked.
+fn twitter_claims() -> Result<HashMap<String, PublicKey>, Error> {
+    let mut claims: HashMap<String, PublicKey> = HashMap::new();
+    for event in GLOBALS.storage.find_events(
+        &[EventKind::Metadata],
+        &[],
+        None,
+        |e| e.tags.iter().any(|t| t.tagname() == "i"),
+        false,
+    )? {
+        for tag in &event.tags {
+            if tag.tagname() != "i" {
+                continue;
+            }
+            if let Some(name) = tag.value().strip_prefix("twitter:") {
+                claims
+                    .entry(name.trim_start_matches('@').to_lowercase())
+                    .or_insert(event.pubkey);
+            }
+        }
+    }
+    Ok(claims)
+}
+
+// NIP-05 domains that list people under their Twitter handles, from the
+// `follow_import_directories` setting
+fn directories() -> Vec<String> {
+    GLOBALS
+        .storage
+        .read_setting_follow_import_directories()
+        .split(',')
+        .map(|d| d.trim().to_owned())
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// Follow the selected accounts on a list, publishing the list once. Returns how
+/// many were followed.
+pub fn follow_selected(list: PersonList, public: bool) -> Result<usize, Error> {
+    let mut pubkeys: Vec<PublicKey> = Vec::new();
+    for candidate in CANDIDATES.read().iter().filter(|c| c.selected) {
+        if let Some(pubkey) = candidate.pubkey {
+            crate::nip05::record_nip05_relays(pubkey, &candidate.relays)?;
+            if !pubkeys.contains(&pubkey) {
+                pubkeys.push(pubkey);
+            }
+        }
+    }
+
+    GLOBALS.people.follow_all(&pubkeys, list, public, true)?;
+
+    if !pubkeys.is_empty() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::PushPersonList(list));
+    }
+
+    clear();
+    Ok(pubkeys.len())
+}
@@ -4,13 +4,16 @@ use crate::error::Error;
 use crate::feed::Feed;
 use crate::fetcher::Fetcher;
 use crate::gossip_identity::GossipIdentity;
+use crate::inspector::EventInspection;
 use crate::media::Media;
 use crate::nip46::ParsedCommand;
 use crate::people::{People, Person};
 use crate::relay::Relay;
+use crate::relay_payment::RelayPayment;
+use crate::relay_penalty::Penalty;
 use crate::relay_picker_hooks::Hooks;
 use crate::status::StatusQueue;
-use crate::storage::Storage;
+use crate::storage::{Storage, StorageStats};
 use dashmap::{DashMap, DashSet};
 use gossip_relay_picker::{Direction, RelayPicker};
 use nostr_types::{Event, Id, PayRequestData, Profile, PublicKey, RelayUrl, UncheckedUrl};
@@ -29,6 +32,7 @@ pub enum ZapState {
     SeekingAmount(Id, PublicKey, PayRequestData, UncheckedUrl),
     LoadingInvoice(Id, PublicKey),
     ReadyToPay(Id, String), // String is the Zap Invoice as a string, to be shown as a QR code
+    Paying(Id),             // Paying the invoice through the connected wallet (NIP-47)
 }
 
 /// Global data shared between threads. Access via the static ref `GLOBALS`.
@@ -52,6 +56,15 @@ pub struct Globals {
     /// The relays currently connected to
     pub connected_relays: DashMap<RelayUrl, Vec<RelayJob>>,
 
+    /// Relays that rate-limited us or dropped us, and how they are held back
+    pub relay_penalties: DashMap<RelayUrl, Penalty>,
+
+    /// Relays that want to be paid, and where paying them stands
+    pub relay_payments: DashMap<RelayUrl, RelayPayment>,
+
+    /// Events relays would not take until paid, to post again once they are
+    pub relay_held_events: DashMap<RelayUrl, Vec<Event>>,
+
     /// The relay picker, used to pick the next relay
     pub relay_picker: RelayPicker<Hooks>,
 
@@ -62,9 +75,6 @@ pub struct Globals {
     /// Wrapped Identity wrapping a Signer
     pub identity: GossipIdentity,
 
-    /// Dismissed Events
-    pub dismissed: RwLock<Vec<Id>>,
-
     /// Feed
     pub feed: Feed,
 
@@ -87,6 +97,10 @@ pub struct Globals {
     /// How many subscriptions are open and not yet at EOSE
     pub open_subscriptions: AtomicUsize,
 
+    /// Bumped on each change to our inbox or outbox relays, so that a burst of
+    /// changes is advertised once
+    pub(crate) relay_list_changes: AtomicUsize,
+
     /// Delegation handling
     pub delegation: Delegation,
 
@@ -99,6 +113,12 @@ pub struct Globals {
     pub people_search_results: PRwLock<Vec<Person>>,
     pub note_search_results: PRwLock<Vec<Event>>,
 
+    /// The event being inspected, for debugging
+    pub event_inspection: PRwLock<Option<EventInspection>>,
+
+    /// What the database holds, counted on request
+    pub storage_stats: PRwLock<Option<StorageStats>>,
+
     /// UI note cache invalidation per note
     // when we update an augment (deletion/reaction/zap) the UI must recompute
     pub ui_notes_to_invalidate: PRwLock<Vec<Id>>,
@@ -113,6 +133,9 @@ pub struct Globals {
     /// Current zap data, for UI
     pub current_zap: PRwLock<ZapState>,
 
+    /// Files we uploaded that the UI has yet to put into the draft
+    pub uploaded_media: PRwLock<Vec<crate::nip96::Upload>>,
+
     /// Hashtag regex
     pub hashtag_regex: Regex,
 
@@ -128,6 +151,7 @@ pub struct Globals {
     /// Filter
     pub(crate) filter_engine: Engine,
     pub(crate) filter: Option<AST>,
+    pub(crate) feed_script: Option<AST>,
 
     // Wait for login
     pub wait_for_login: AtomicBool,
@@ -136,6 +160,9 @@ pub struct Globals {
     // Wait for data migration
     pub wait_for_data_migration: AtomicBool,
 
+    /// Compact the database once everything else has shut down
+    pub compact_on_quit: AtomicBool,
+
     // Active advertise jobs
     pub active_advertise_jobs: DashSet<u64>,
 
@@ -164,8 +191,10 @@ lazy_static! {
             Err(e) => panic!("{e}")
         };
 
-        let filter_engine = Engine::new();
+        let mut filter_engine = Engine::new();
+        filter_engine.set_max_operations(crate::filter::MAX_SCRIPT_OPERATIONS);
         let filter = crate::filter::load_script(&filter_engine);
+        let feed_script = crate::filter::load_feed_script(&filter_engine);
 
         Globals {
             to_minions,
@@ -173,10 +202,12 @@ lazy_static! {
             tmp_overlord_receiver: Mutex::new(Some(tmp_overlord_receiver)),
             people: People::new(),
             connected_relays: DashMap::new(),
+            relay_penalties: DashMap::new(),
+            relay_payments: DashMap::new(),
+            relay_held_events: DashMap::new(),
             relay_picker: Default::default(),
             shutting_down: AtomicBool::new(false),
             identity: GossipIdentity::default(),
-            dismissed: RwLock::new(Vec::new()),
             feed: Feed::new(),
             fetcher: Fetcher::new(),
             failed_avatars: RwLock::new(HashSet::new()),
@@ -186,25 +217,31 @@ lazy_static! {
             )),
             bytes_read: AtomicUsize::new(0),
             open_subscriptions: AtomicUsize::new(0),
+            relay_list_changes: AtomicUsize::new(0),
             delegation: Delegation::default(),
             media: Media::new(),
             events_being_searched_for: PRwLock::new(Vec::new()),
             //event_addrs_being_searched_for: PRwLock::new(Vec::new()),
             people_search_results: PRwLock::new(Vec::new()),
             note_search_results: PRwLock::new(Vec::new()),
+            event_inspection: PRwLock::new(None),
+            storage_stats: PRwLock::new(None),
             ui_notes_to_invalidate: PRwLock::new(Vec::new()),
             ui_people_to_invalidate: PRwLock::new(Vec::new()),
             ui_invalidate_all: AtomicBool::new(false),
             current_zap: PRwLock::new(ZapState::None),
+            uploaded_media: PRwLock::new(Vec::new()),
             hashtag_regex: Regex::new(r"(?:^|\W)(#[\w\p{Extended_Pictographic}]+)(?:$|\W)").unwrap(),
             tagging_regex: Regex::new(r"(?:^|\s+)@([\w\p{Extended_Pictographic}]+)(?:$|\W)").unwrap(),
             storage,
             events_processed: AtomicU32::new(0),
             filter_engine,
             filter,
+            feed_script,
             wait_for_login: AtomicBool::new(false),
             wait_for_login_notify: Notify::new(),
             wait_for_data_migration: AtomicBool::new(false),
+            compact_on_quit: AtomicBool::new(false),
             active_advertise_jobs: DashSet::new(),
             connect_requests: PRwLock::new(Vec::new()),
             auth_requests: PRwLock::new(Vec::new()),
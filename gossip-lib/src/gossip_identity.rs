@@ -1,22 +1,42 @@
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
+use crate::nip46::{self, Nip46Bunker, Nip46BunkerClient};
+use dashmap::DashMap;
+use futures::task::noop_waker_ref;
 use nostr_types::{
     ContentEncryptionAlgorithm, DelegationConditions, EncryptedPrivateKey, Event, EventKind,
     EventV1, EventV2, Id, Identity, KeySecurity, Metadata, PreEvent, PrivateKey, PublicKey, Rumor,
-    RumorV1, RumorV2, Signature,
+    RumorV1, RumorV2, Signature, Tag, Unixtime,
 };
 use parking_lot::RwLock;
-use std::sync::mpsc::Sender;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 use tokio::task;
 
 pub struct GossipIdentity {
     pub inner: RwLock<Identity>,
+
+    // A remote signer (NIP-46) that signs for our public key, if we use one.
+    // The inner identity is then only the public key.
+    bunker: RwLock<Option<Arc<Nip46BunkerClient>>>,
+
+    // The remote signer we were paired with, until the passphrase unlocks our
+    // key for it
+    locked_bunker: RwLock<Option<Nip46Bunker>>,
+
+    // Events the remote signer is still decrypting for the UI, or the error
+    // it gave
+    signer_work: DashMap<Id, Option<String>>,
 }
 
 impl Default for GossipIdentity {
     fn default() -> GossipIdentity {
         GossipIdentity {
             inner: RwLock::new(Identity::default()),
+            bunker: RwLock::new(None),
+            locked_bunker: RwLock::new(None),
+            signer_work: DashMap::new(),
         }
     }
 }
@@ -30,6 +50,10 @@ impl GossipIdentity {
             (Some(pk), None) => *self.inner.write() = Identity::Public(pk),
             (None, _) => *self.inner.write() = Identity::None,
         }
+
+        if let (Some(_), Some(bunker)) = (pk, GLOBALS.storage.read_nip46_bunker()?) {
+            *self.locked_bunker.write() = Some(bunker);
+        }
         Ok(())
     }
 
@@ -92,19 +116,58 @@ impl GossipIdentity {
         Ok(())
     }
 
+    // Any function that replaces the key should run this, since a remote
+    // signer only signs for the key it was paired with
+    fn forget_bunker(&self) -> Result<(), Error> {
+        let had_bunker = self.bunker.write().take().is_some();
+        let had_locked_bunker = self.locked_bunker.write().take().is_some();
+        if had_bunker || had_locked_bunker {
+            GLOBALS.storage.delete_nip46_bunker(None)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn set_public_key(&self, public_key: PublicKey) -> Result<(), Error> {
+        self.forget_bunker()?;
         *self.inner.write() = Identity::Public(public_key);
         self.on_keychange()?;
         Ok(())
     }
 
     pub(crate) fn clear_public_key(&self) -> Result<(), Error> {
+        self.forget_bunker()?;
         *self.inner.write() = Identity::None;
         self.on_keychange()?;
         Ok(())
     }
 
+    /// Sign with a remote signer for this public key from now on
+    pub(crate) fn set_bunker(
+        &self,
+        client: Nip46BunkerClient,
+        public_key: PublicKey,
+    ) -> Result<(), Error> {
+        GLOBALS.storage.write_nip46_bunker(&client.bunker, None)?;
+        *self.inner.write() = Identity::Public(public_key);
+        *self.locked_bunker.write() = None;
+        *self.bunker.write() = Some(Arc::new(client));
+        self.on_keychange()?;
+        self.on_unlock()?;
+        Ok(())
+    }
+
+    /// Whether we sign with a remote signer
+    pub fn uses_bunker(&self) -> bool {
+        self.bunker.read().is_some()
+    }
+
+    /// The remote signer we sign with, if any
+    pub fn bunker(&self) -> Option<Arc<Nip46BunkerClient>> {
+        self.bunker.read().clone()
+    }
+
     pub fn set_encrypted_private_key(&self, epk: EncryptedPrivateKey) -> Result<(), Error> {
+        self.forget_bunker()?;
         let public_key = match *self.inner.read() {
             Identity::None => return Err(ErrorKind::NoPublicKey.into()),
             Identity::Public(public_key) => public_key,
@@ -116,6 +179,15 @@ impl GossipIdentity {
     }
 
     pub(crate) async fn change_passphrase(&self, old: &str, new: &str) -> Result<(), Error> {
+        if let Some(mut bunker) = GLOBALS.storage.read_nip46_bunker()? {
+            bunker.change_passphrase(old, new)?;
+            GLOBALS.storage.write_nip46_bunker(&bunker, None)?;
+            if let Some(locked) = self.locked_bunker.write().as_mut() {
+                *locked = bunker;
+            }
+            return Ok(());
+        }
+
         let log_n = GLOBALS.storage.read_setting_log_n();
         self.inner.write().change_passphrase(old, new, log_n)?;
         Ok(())
@@ -124,12 +196,22 @@ impl GossipIdentity {
     pub(crate) fn set_private_key(&self, pk: PrivateKey, pass: &str) -> Result<(), Error> {
         let log_n = GLOBALS.storage.read_setting_log_n();
         let identity = Identity::from_private_key(pk, pass, log_n)?;
+        self.forget_bunker()?;
         *self.inner.write() = identity;
         self.on_keychange()?;
         Ok(())
     }
 
     pub fn unlock(&self, pass: &str) -> Result<(), Error> {
+        let locked_bunker = self.locked_bunker.read().clone();
+        if let Some(bunker) = locked_bunker {
+            let client = Nip46BunkerClient::load(bunker, pass)?;
+            *self.bunker.write() = Some(Arc::new(client));
+            *self.locked_bunker.write() = None;
+            self.on_unlock()?;
+            return Ok(());
+        }
+
         self.inner.write().unlock(pass)?;
 
         // If older version, re-encrypt with new version at default 2^18 rounds
@@ -148,23 +230,28 @@ impl GossipIdentity {
 
     pub(crate) fn generate_private_key(&self, pass: &str) -> Result<(), Error> {
         let log_n = GLOBALS.storage.read_setting_log_n();
-        *self.inner.write() = Identity::generate(pass, log_n)?;
+        let identity = Identity::generate(pass, log_n)?;
+        self.forget_bunker()?;
+        *self.inner.write() = identity;
         self.on_keychange()?;
         Ok(())
     }
 
     pub(crate) fn delete_identity(&self) -> Result<(), Error> {
+        self.forget_bunker()?;
         *self.inner.write() = Identity::None;
         self.on_keychange()?;
         Ok(())
     }
 
+    /// Whether we have a key that the passphrase unlocks: the user's private
+    /// key, or our key for their remote signer
     pub fn has_private_key(&self) -> bool {
-        self.inner.read().has_private_key()
+        self.inner.read().has_private_key() || self.locked_bunker.read().is_some()
     }
 
     pub fn is_unlocked(&self) -> bool {
-        self.inner.read().is_unlocked()
+        self.uses_bunker() || self.inner.read().is_unlocked()
     }
 
     pub fn public_key(&self) -> Option<PublicKey> {
@@ -180,21 +267,12 @@ impl GossipIdentity {
     }
 
     pub fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(bunker.sign_event(input));
+        }
         Ok(self.inner.read().sign_event(input)?)
     }
 
-    pub fn sign_event_with_pow(
-        &self,
-        input: PreEvent,
-        zero_bits: u8,
-        work_sender: Option<Sender<u8>>,
-    ) -> Result<Event, Error> {
-        Ok(self
-            .inner
-            .read()
-            .sign_event_with_pow(input, zero_bits, work_sender)?)
-    }
-
     pub fn export_private_key_bech32(&self, pass: &str) -> Result<(String, bool), Error> {
         let log_n = GLOBALS.storage.read_setting_log_n();
         Ok(self
@@ -209,9 +287,100 @@ impl GossipIdentity {
     }
 
     pub fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(Self::bunker_unwrap_giftwrap(bunker, event.clone()));
+        }
         Ok(self.inner.read().unwrap_giftwrap(event)?)
     }
 
+    /// Like unwrap_giftwrap, but never waits on a remote signer, so that it may
+    /// be called while drawing. Until the signer has answered this gives
+    /// Ok(None), and the note is redrawn once it has.
+    pub fn try_unwrap_giftwrap(&self, event: &Event) -> Result<Option<Rumor>, Error> {
+        match self.bunker() {
+            Some(bunker) => self.without_waiting(
+                event.id,
+                Self::bunker_unwrap_giftwrap(bunker, event.clone()),
+            ),
+            None => self.unwrap_giftwrap(event).map(Some),
+        }
+    }
+
+    async fn bunker_unwrap_giftwrap(
+        bunker: Arc<Nip46BunkerClient>,
+        event: Event,
+    ) -> Result<Rumor, Error> {
+        if event.kind != EventKind::GiftWrap {
+            return Err(ErrorKind::WrongEventKind.into());
+        }
+        let seal: Event = serde_json::from_str(
+            &bunker
+                .decrypt(
+                    &event.pubkey,
+                    &event.content,
+                    ContentEncryptionAlgorithm::Nip44v2,
+                )
+                .await?,
+        )?;
+        seal.verify(None)?;
+        let rumor: Rumor = serde_json::from_str(
+            &bunker
+                .decrypt(
+                    &seal.pubkey,
+                    &seal.content,
+                    ContentEncryptionAlgorithm::Nip44v2,
+                )
+                .await?,
+        )?;
+        // Nobody may put words in the mouth of the seal's signer
+        if rumor.pubkey != seal.pubkey {
+            return Err(ErrorKind::Nip46Remote("rumor and seal authors differ".to_owned()).into());
+        }
+        Ok(rumor)
+    }
+
+    // Run a remote signer request for an event as far as it goes without
+    // waiting. If it would have to wait, finish it in the background and have
+    // the UI redraw the event then; the signer's answers are kept, so the next
+    // try gets it at once.
+    fn without_waiting<T, F>(&self, id: Id, future: F) -> Result<Option<T>, Error>
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        if let Some(work) = self.signer_work.get(&id) {
+            return match &*work {
+                Some(error) => Err(error.as_str().into()),
+                None => Ok(None),
+            };
+        }
+
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(result) => result.map(Some),
+            Poll::Pending => {
+                self.signer_work.insert(id, None);
+                std::mem::drop(tokio::spawn(async move {
+                    match future.await {
+                        Ok(_) => {
+                            GLOBALS.identity.signer_work.remove(&id);
+                        }
+                        Err(e) => {
+                            tracing::warn!("The remote signer could not decrypt {}: {}", id, e);
+                            GLOBALS
+                                .identity
+                                .signer_work
+                                .insert(id, Some(format!("{e}")));
+                        }
+                    }
+                    GLOBALS.ui_notes_to_invalidate.write().push(id);
+                }));
+                Ok(None)
+            }
+        }
+    }
+
     /// @deprecated for migrations only
     pub fn unwrap_giftwrap1(&self, event: &EventV1) -> Result<RumorV1, Error> {
         Ok(self.inner.read().unwrap_giftwrap1(event)?)
@@ -223,18 +392,81 @@ impl GossipIdentity {
     }
 
     pub fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(Self::bunker_decrypt_event_contents(
+                bunker,
+                event.clone(),
+                self.public_key(),
+            ));
+        }
         Ok(self.inner.read().decrypt_event_contents(event)?)
     }
 
+    /// Like decrypt_event_contents, but never waits on a remote signer, so
+    /// that it may be called while drawing. Until the signer has answered this
+    /// gives Ok(None), and the note is redrawn once it has.
+    pub fn try_decrypt_event_contents(&self, event: &Event) -> Result<Option<String>, Error> {
+        match self.bunker() {
+            Some(bunker) => self.without_waiting(
+                event.id,
+                Self::bunker_decrypt_event_contents(bunker, event.clone(), self.public_key()),
+            ),
+            None => self.decrypt_event_contents(event).map(Some),
+        }
+    }
+
+    async fn bunker_decrypt_event_contents(
+        bunker: Arc<Nip46BunkerClient>,
+        event: Event,
+        us: Option<PublicKey>,
+    ) -> Result<String, Error> {
+        if event.kind != EventKind::EncryptedDirectMessage {
+            return Err(ErrorKind::WrongEventKind.into());
+        }
+        // The other party is the author, unless we are
+        let other = if Some(event.pubkey) == us {
+            match event.tags.iter().find_map(|t| t.parse_pubkey().ok()) {
+                Some((pk, _, _)) => pk,
+                None => return Err(ErrorKind::NoPublicKey.into()),
+            }
+        } else {
+            event.pubkey
+        };
+        let algo = if event.content.contains("?iv=") {
+            ContentEncryptionAlgorithm::Nip04
+        } else {
+            ContentEncryptionAlgorithm::Nip44v2
+        };
+        bunker.decrypt(&other, &event.content, algo).await
+    }
+
     pub fn decrypt_nip04(&self, other: &PublicKey, ciphertext: &str) -> Result<Vec<u8>, Error> {
+        if let Some(bunker) = self.bunker() {
+            return Ok(nip46::wait(bunker.decrypt(
+                other,
+                ciphertext,
+                ContentEncryptionAlgorithm::Nip04,
+            ))?
+            .into_bytes());
+        }
         Ok(self.inner.read().decrypt_nip04(other, ciphertext)?)
     }
 
     pub fn decrypt_nip44(&self, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(bunker.decrypt(
+                other,
+                ciphertext,
+                ContentEncryptionAlgorithm::Nip44v2,
+            ));
+        }
         Ok(self.inner.read().decrypt_nip44(other, ciphertext)?)
     }
 
     pub fn nip44_conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(bunker.nip44_conversation_key(other));
+        }
         Ok(self.inner.read().nip44_conversation_key(other)?)
     }
 
@@ -244,6 +476,9 @@ impl GossipIdentity {
         plaintext: &str,
         algo: ContentEncryptionAlgorithm,
     ) -> Result<String, Error> {
+        if let Some(bunker) = self.bunker() {
+            return nip46::wait(bunker.encrypt(other, plaintext, algo));
+        }
         Ok(self.inner.read().encrypt(other, plaintext, algo)?)
     }
 
@@ -252,6 +487,11 @@ impl GossipIdentity {
         input: PreEvent,
         metadata: Metadata,
     ) -> Result<Event, Error> {
+        if let Some(bunker) = self.bunker() {
+            let mut input = input;
+            input.content = serde_json::to_string(&metadata)?;
+            return nip46::wait(bunker.sign_event(input));
+        }
         Ok(self.inner.read().create_metadata_event(input, metadata)?)
     }
 
@@ -263,6 +503,29 @@ impl GossipIdentity {
         relays: Vec<String>,
         content: String,
     ) -> Result<Event, Error> {
+        if let Some(bunker) = self.bunker() {
+            let pubkey = match self.public_key() {
+                Some(pk) => pk,
+                None => return Err(ErrorKind::NoPublicKey.into()),
+            };
+            let mut relays_tag: Vec<&str> = vec!["relays"];
+            relays_tag.extend(relays.iter().map(|r| r.as_str()));
+            let mut tags = vec![
+                Tag::new_pubkey(recipient_pubkey, None, None),
+                Tag::new(&relays_tag),
+                Tag::new(&["amount", &millisatoshis.to_string()]),
+            ];
+            if let Some(id) = zapped_event {
+                tags.push(Tag::new_event(id, None, None));
+            }
+            return nip46::wait(bunker.sign_event(PreEvent {
+                pubkey,
+                created_at: Unixtime::now().unwrap(),
+                kind: EventKind::ZapRequest,
+                tags,
+                content,
+            }));
+        }
         Ok(self.inner.read().create_zap_request_event(
             recipient_pubkey,
             zapped_event,
@@ -0,0 +1,97 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::json_key;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+
+// Gossip signs as one identity at a time. The user's other identities are kept
+// by their public key only, so that what is addressed to them can be shown
+// alongside the inbox.
+
+/// The user's other identities
+pub fn others() -> Vec<PublicKey> {
+    match GLOBALS.storage.read_json(json_key::OTHER_IDENTITIES) {
+        Ok(others) => others.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    }
+}
+
+fn save(others: &[PublicKey]) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .write_json(json_key::OTHER_IDENTITIES, others, None)
+}
+
+/// Add one of the user's other identities
+pub fn add(pubkey: PublicKey) -> Result<(), Error> {
+    if Some(pubkey) == GLOBALS.identity.public_key() {
+        return Ok(());
+    }
+    let mut others = others();
+    if !others.contains(&pubkey) {
+        others.push(pubkey);
+        save(&others)?;
+    }
+    Ok(())
+}
+
+/// Remove one of the user's other identities
+pub fn remove(pubkey: &PublicKey) -> Result<(), Error> {
+    let mut others = others();
+    others.retain(|pk| pk != pubkey);
+    save(&others)
+}
+
+/// All of the user's identities, the one in use first
+pub fn all() -> Vec<PublicKey> {
+    let mut all: Vec<PublicKey> = Vec::new();
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        all.push(pk);
+    }
+    for pk in others() {
+        if !all.contains(&pk) {
+            all.push(pk);
+        }
+    }
+    all
+}
+
+/// Which of the user's identities an event is addressed to, given their other
+/// identities as [others] returned them
+pub fn targets(event: &Event, others: &[PublicKey]) -> Vec<PublicKey> {
+    GLOBALS
+        .identity
+        .public_key()
+        .iter()
+        .chain(others.iter())
+        .filter(|pk| event.pubkey != **pk && event.is_tagged(pk))
+        .copied()
+        .collect()
+}
+
+/// Events since `since` that are addressed to one of the user's other
+/// identities: mentions, replies and direct messages.
+pub(crate) fn mentions_of_others<F>(
+    kinds: &[EventKind],
+    since: Unixtime,
+    f: F,
+) -> Result<Vec<Event>, Error>
+where
+    F: Fn(&Event) -> bool,
+{
+    let others = others();
+    if kinds.is_empty() {
+        return Ok(vec![]);
+    }
+
+    GLOBALS.storage.find_events_tagging(
+        kinds,
+        &[],
+        Some(since),
+        &others,
+        |e| others.iter().any(|pk| e.pubkey != *pk && e.is_tagged(pk)) && f(e),
+        true,
+    )
+}
@@ -0,0 +1,107 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::{RelationshipByAddr, RelationshipById};
+use nostr_types::{EventAddr, Id, RelayUrl, Unixtime};
+
+/// The internals of an event, for debugging
+#[derive(Clone, Debug)]
+pub struct EventInspection {
+    /// The event id
+    pub id: Id,
+
+    /// The event, as JSON
+    pub json: String,
+
+    /// Whether the event id and signature verify, or why not
+    pub verified: Result<(), String>,
+
+    /// Relays we have seen it on, and when we last saw it there
+    pub seen_on: Vec<(RelayUrl, Unixtime)>,
+
+    /// When we first received it from a relay, which is when we processed it.
+    /// None if it never came from a relay (such as our own before posting).
+    pub received_at: Option<Unixtime>,
+
+    /// When we last received it from a relay
+    pub last_received_at: Option<Unixtime>,
+
+    /// When it matched one of the user's alert rules
+    pub alerted_at: Option<Unixtime>,
+
+    /// Until when the user dismissed it
+    pub dismissed_until: Option<Unixtime>,
+
+    /// Whether the user has viewed it
+    pub viewed: bool,
+
+    /// Events related to this one (replies, reactions, deletions, ...), and how
+    pub related_by_id: Vec<(Id, RelationshipById)>,
+
+    /// Events related to this one by its address (for replaceable events)
+    pub related_by_addr: Vec<(Id, RelationshipByAddr)>,
+
+    /// Versions we have seen (for parameterized replaceable events)
+    pub versions: Vec<(Id, Unixtime)>,
+
+    /// Its proof-of-work difficulty (NIP-13)
+    pub pow: u8,
+
+    /// Whether it has expired (NIP-40)
+    pub expired: bool,
+
+    /// Whether it is protected (NIP-70)
+    pub protected: bool,
+}
+
+/// Inspect an event we have. Returns None if we don't have it.
+pub fn inspect(id: Id) -> Result<Option<EventInspection>, Error> {
+    let event = match GLOBALS.storage.read_event(id)? {
+        Some(event) => event,
+        None => return Ok(None),
+    };
+
+    let mut seen_on = GLOBALS.storage.get_event_seen_on_relay(id)?;
+    seen_on.sort_by(|a, b| b.1.cmp(&a.1));
+    let received_at = seen_on.iter().map(|(_, when)| *when).min();
+    let last_received_at = seen_on.first().map(|(_, when)| *when);
+
+    let (related_by_addr, versions) = if event.kind.is_replaceable() {
+        let addr = EventAddr {
+            d: event.parameter().unwrap_or_default(),
+            relays: vec![],
+            kind: event.kind,
+            author: event.pubkey,
+        };
+        let versions = if event.kind.is_parameterized_replaceable() {
+            GLOBALS
+                .storage
+                .get_replaceable_event_versions(&addr)?
+                .iter()
+                .map(|e| (e.id, e.created_at))
+                .collect()
+        } else {
+            vec![]
+        };
+        (GLOBALS.storage.find_relationships_by_addr(&addr)?, versions)
+    } else {
+        (vec![], vec![])
+    };
+
+    Ok(Some(EventInspection {
+        id,
+        json: serde_json::to_string_pretty(&event)?,
+        verified: event.verify(None).map_err(|e| format!("{}", e)),
+        seen_on,
+        received_at,
+        last_received_at,
+        alerted_at: GLOBALS.storage.read_event_alerted(id)?,
+        dismissed_until: GLOBALS.storage.read_event_dismissed_until(id)?,
+        viewed: GLOBALS.storage.is_event_viewed(id)?,
+        related_by_id: GLOBALS.storage.find_relationships_by_id(id)?,
+        related_by_addr,
+        versions,
+        pow: crate::nip13::difficulty(&event),
+        expired: crate::nip40::is_expired(&event, Unixtime::now().unwrap()),
+        protected: crate::nip70::is_protected(&event),
+    }))
+}
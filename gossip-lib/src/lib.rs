@@ -66,11 +66,31 @@
 mod about;
 pub use about::About;
 
+/// Alert rules for incoming events
+pub mod alerts;
+
+/// Analytics of how the user's own posts were received
+pub mod analytics;
+
+/// Backups of storage, scheduled or portable
+pub mod backup;
+
+/// Badges (NIP-58)
+pub mod badges;
+
 /// Defines messages sent to the overlord
 pub mod comms;
 
+mod community;
+pub use community::{community_of_event, Community};
+
+/// Opening nostr: links handed to us by the OS
+pub mod deep_link;
+
 mod delegation;
-pub use delegation::Delegation;
+pub use delegation::{
+    issue_delegation, issued_delegations, revoke_delegation, Delegation, IssuedDelegation,
+};
 
 mod dm_channel;
 pub use dm_channel::{DmChannel, DmChannelData};
@@ -89,25 +109,118 @@ pub use fetcher::Fetcher;
 
 mod filter;
 
+/// Following back new followers
+pub mod follow_back;
+
+/// Importing follows from other networks
+pub mod follow_import;
+
 mod globals;
 pub use globals::{Globals, ZapState, GLOBALS};
 
 mod gossip_identity;
 pub use gossip_identity::GossipIdentity;
 
+/// The user's other identities
+pub mod identities;
+
+/// Inspecting the internals of events
+pub mod inspector;
+
 mod media;
 pub use media::Media;
 
+/// Metrics for monitoring a long-running gossip
+pub mod metrics;
+
 /// Rendering various names of users
 pub mod names;
 
+/// OpenTimestamps attestations (NIP-03)
+pub mod nip03;
+
 /// nip05 handling
 pub mod nip05;
 
+/// Proof of work (NIP-13)
+pub mod nip13;
+
+/// Marketplace stalls and products (NIP-15)
+pub mod nip15;
+
+/// Private direct messages (NIP-17)
+pub mod nip17;
+
+/// Long-form articles (NIP-23)
+pub mod nip23;
+
+/// Custom emoji (NIP-30)
+pub mod nip30;
+
+/// Descriptions of unknown event kinds (NIP-31)
+pub mod nip31;
+
+/// Labeling (NIP-32)
+pub mod nip32;
+
+/// Git collaboration events (NIP-34)
+pub mod nip34;
+
+/// Torrents (NIP-35)
+pub mod nip35;
+
+/// User statuses (NIP-38)
+pub mod nip38;
+
+/// External identities (NIP-39)
+pub mod nip39;
+
+/// Expiration timestamps (NIP-40)
+pub mod nip40;
+
 #[allow(dead_code)]
 pub mod nip46;
 pub use nip46::{Nip46Server, Nip46UnconnectedServer};
 
+/// Paying through a wallet (NIP-47, Nostr Wallet Connect)
+pub mod nip47;
+
+/// Bridged content (NIP-48)
+pub mod nip48;
+
+/// Calendar events (NIP-52)
+pub mod nip52;
+
+/// Live activities (NIP-53)
+pub mod nip53;
+
+/// Wiki articles (NIP-54)
+pub mod nip54;
+
+/// Reporting (NIP-56)
+pub mod nip56;
+
+/// Protected events (NIP-70)
+pub mod nip70;
+
+/// Zap goals (NIP-75)
+pub mod nip75;
+
+/// Application handlers (NIP-89)
+pub mod nip89;
+
+/// Data vending machines (NIP-90)
+pub mod nip90;
+
+/// Uploading files to media servers (NIP-96)
+pub mod nip96;
+
+/// Signing in to web servers with nostr events (NIP-98)
+pub mod nip98;
+
+/// Helping new users get started
+pub mod onboarding;
+
 mod overlord;
 pub use overlord::Overlord;
 
@@ -121,24 +234,60 @@ pub use person_relay::PersonRelay;
 pub mod process;
 
 mod profile;
+pub use profile::Profile;
+
+mod proxy;
+
+/// A private queue of notes and articles to read later
+pub mod read_later;
 
 mod relationship;
 
 mod relay;
 pub use relay::Relay;
 
+/// Auditing the events received from relays
+pub mod relay_audit;
+
+/// Backing off from relays that rate-limit us or drop us
+pub mod relay_penalty;
+
+/// Paying relays that want to be paid
+pub mod relay_payment;
+
 mod relay_picker_hooks;
 pub use relay_picker_hooks::Hooks;
 
+/// Following people only on specific relays
+pub mod relay_scope;
+
+/// Local search
+pub mod search;
+
+/// Settings export and import
+pub mod settings_file;
+
+/// Settings sync between gossip installs (NIP-78)
+pub mod settings_sync;
+
+/// Links for sharing notes and profiles
+pub mod share;
+
 mod status;
-pub use status::StatusQueue;
+pub use status::{StatusEntry, StatusQueue, StatusSeverity};
 
 mod storage;
 pub use storage::types::*;
-pub use storage::Storage;
+pub use storage::{CompactionReport, KindStats, Snapshot, Storage, StorageStats, TableStats};
+
+/// RSS and Atom feeds of nostr content
+pub mod syndication;
 
 mod tags;
 
+/// Exporting threads as documents
+pub mod thread_export;
+
 #[macro_use]
 extern crate lazy_static;
 
@@ -153,6 +302,9 @@ pub fn init() -> Result<(), Error> {
     // Initialize storage
     GLOBALS.storage.init()?;
 
+    // Forget dismissals that have run out
+    GLOBALS.storage.remove_expired_dismissals(None)?;
+
     // Load signer from settings
     GLOBALS.identity.load()?;
 
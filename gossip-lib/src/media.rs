@@ -63,15 +63,35 @@ impl Media {
         self.failed_media.blocking_write().remove(unchecked_url);
     }
 
+    /// Bytes held by media that has been processed but not yet handed to the UI
+    pub fn memory_usage(&self) -> usize {
+        let images: usize = self.image_temp.iter().map(|i| i.value().len()).sum();
+        let data: usize = self.data_temp.iter().map(|d| d.value().len()).sum();
+        images + data
+    }
+
+    /// Drop media that has been processed but not yet handed to the UI.
+    /// It will be processed again (from the cache) when next asked for.
+    pub fn trim_memory(&self) {
+        let urls: Vec<Url> = self.image_temp.iter().map(|i| i.key().clone()).collect();
+        for url in &urls {
+            self.image_temp.remove(url);
+            self.media_pending_processing.remove(url);
+        }
+        self.data_temp.clear();
+    }
+
     /// Get an image by Url
     ///
     /// This returns immediately, usually with None if never called on that Url before.
-    /// Call it again later to try to pick up the result.
+    /// Call it again later to try to pick up the result. Once handed over, the image
+    /// is processed again if asked for again.
     ///
     /// FIXME: this API doesn't serve async clients well.
     pub fn get_image(&self, url: &Url) -> Option<RgbaImage> {
         // If we have it, hand it over (we won't need a copy anymore)
         if let Some(th) = self.image_temp.remove(url) {
+            self.media_pending_processing.remove(url);
             return Some(th.1);
         }
 
@@ -82,6 +102,8 @@ impl Media {
 
         match self.get_data(url) {
             Some(bytes) => {
+                self.media_pending_processing.insert(url.clone());
+
                 // Finish this later (spawn)
                 let aurl = url.to_owned();
                 tokio::spawn(async move {
@@ -110,7 +132,6 @@ impl Media {
                         }
                     }
                 });
-                self.media_pending_processing.insert(url.clone());
                 None
             }
             None => None,
@@ -308,3 +329,29 @@ fn round_image(image: &mut RgbaImage) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trimmed_image_is_processed_again() {
+        let media = Media::new();
+        let url =
+            Url::try_from_unchecked_url(&UncheckedUrl("https://example.com/a.png".to_owned()))
+                .unwrap();
+
+        // Processed, but trimmed before the UI picked it up
+        media.media_pending_processing.insert(url.clone());
+        media.image_temp.insert(url.clone(), RgbaImage::new(2, 2));
+        media.trim_memory();
+        assert_eq!(media.memory_usage(), 0);
+        assert!(!media.media_pending_processing.contains(&url));
+
+        // Processed again, and handed over
+        media.media_pending_processing.insert(url.clone());
+        media.image_temp.insert(url.clone(), RgbaImage::new(2, 2));
+        assert!(media.get_image(&url).is_some());
+        assert!(!media.media_pending_processing.contains(&url));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::profile::Profile;
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Start serving metrics in the Prometheus text format on localhost, if enabled
+pub(crate) fn start() {
+    if !GLOBALS.storage.read_setting_metrics_endpoint() {
+        return;
+    }
+
+    let port = GLOBALS.storage.read_setting_metrics_port();
+    tokio::task::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Could not start metrics endpoint on port {}: {}", port, e);
+                return;
+            }
+        };
+        tracing::info!("Serving metrics at http://127.0.0.1:{}/metrics", port);
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Metrics endpoint: {}", e);
+                        continue;
+                    }
+                },
+                _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {
+                    if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                        tracing::info!("Metrics endpoint shutting down.");
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            tokio::task::spawn(async move {
+                if let Err(e) = serve(stream).await {
+                    tracing::debug!("Metrics endpoint: {}", e);
+                }
+            });
+        }
+    });
+}
+
+async fn serve(mut stream: TcpStream) -> Result<(), Error> {
+    // We only need the request line
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let response = if path == "/metrics" {
+        let body = render()?;
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned()
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Render the current metrics in the Prometheus text exposition format
+pub fn render() -> Result<String, Error> {
+    let mut out = String::new();
+
+    let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    };
+
+    metric(
+        "gossip_connected_relays",
+        "gauge",
+        "Relays we currently have a connection to",
+        GLOBALS.connected_relays.len() as u64,
+    );
+    metric(
+        "gossip_open_subscriptions",
+        "gauge",
+        "Subscriptions open across all relays",
+        GLOBALS.open_subscriptions.load(Ordering::Relaxed) as u64,
+    );
+    metric(
+        "gossip_events_processed_total",
+        "counter",
+        "Events processed since startup",
+        GLOBALS.events_processed.load(Ordering::Relaxed) as u64,
+    );
+    metric(
+        "gossip_bytes_read_total",
+        "counter",
+        "Bytes read from relays and HTTP servers since startup",
+        GLOBALS.bytes_read.load(Ordering::Relaxed) as u64,
+    );
+    metric(
+        "gossip_pending_connect_requests",
+        "gauge",
+        "Relay connections waiting for approval",
+        GLOBALS.connect_requests.read().len() as u64,
+    );
+    metric(
+        "gossip_pending_auth_requests",
+        "gauge",
+        "Relay authentications waiting for approval",
+        GLOBALS.auth_requests.read().len() as u64,
+    );
+    metric(
+        "gossip_fetcher_requests_queued",
+        "gauge",
+        "HTTP requests queued for fetching",
+        GLOBALS.fetcher.requests_queued() as u64,
+    );
+    metric(
+        "gossip_fetcher_requests_in_flight",
+        "gauge",
+        "HTTP requests currently being fetched",
+        GLOBALS.fetcher.requests_in_flight() as u64,
+    );
+    metric(
+        "gossip_storage_events",
+        "gauge",
+        "Events in storage",
+        GLOBALS.storage.get_event_len()?,
+    );
+    metric(
+        "gossip_storage_people",
+        "gauge",
+        "People in storage",
+        GLOBALS.storage.get_people_len()?,
+    );
+    metric(
+        "gossip_storage_relays",
+        "gauge",
+        "Relays in storage",
+        GLOBALS.storage.get_relays_len()?,
+    );
+
+    let mut data_file = Profile::current()?.lmdb_dir;
+    data_file.push("data.mdb");
+    if let Ok(md) = std::fs::metadata(&data_file) {
+        metric(
+            "gossip_storage_size_bytes",
+            "gauge",
+            "Size of the LMDB data file",
+            md.len(),
+        );
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,609 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use base64::Engine;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, Id, Unixtime};
+use sha2::Digest;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// Calendars we ask to timestamp our events
+const CALENDARS: [&str; 2] = [
+    "https://alice.btc.calendar.opentimestamps.org",
+    "https://bob.btc.calendar.opentimestamps.org",
+];
+
+/// Where we look up bitcoin block headers
+const ESPLORA_API: &str = "https://blockstream.info/api";
+
+/// How often we ask calendars whether pending timestamps have made it into bitcoin
+const UPGRADE_INTERVAL: Duration = Duration::from_secs(60 * 30);
+
+/// How long we wait before checking an attestation again that we could not check
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+const HEADER_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+const MAJOR_VERSION: u8 = 1;
+
+const OP_SHA256: u8 = 0x08;
+const OP_APPEND: u8 = 0xf0;
+const OP_PREPEND: u8 = 0xf1;
+const FORK: u8 = 0xff;
+const ATTESTATION: u8 = 0x00;
+
+const BITCOIN_ATTESTATION: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const PENDING_ATTESTATION: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+
+// Deeper proofs than this are not real
+const MAX_DEPTH: usize = 256;
+
+/// The result of checking an attestation (kind 1040) against bitcoin
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimestampStatus {
+    /// We are checking it
+    Checking,
+
+    /// The event existed before the bitcoin block at this height, mined at this time
+    Verified { height: u64, block_time: Unixtime },
+
+    /// The attestation does not check out
+    Failed(String),
+
+    /// We could not check it just now, and will try again later
+    Unavailable(String),
+}
+
+lazy_static! {
+    // With when each was checked, so that attestations that were unavailable
+    // get checked again
+    static ref STATUSES: DashMap<Id, (TimestampStatus, Instant)> = DashMap::new();
+}
+
+/// Start the periodic task that publishes our attestations once they are in bitcoin
+pub(crate) fn start() {
+    tokio::task::spawn(async {
+        loop {
+            tokio::time::sleep(UPGRADE_INTERVAL).await;
+
+            if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                break;
+            }
+
+            match GLOBALS.storage.read_pending_timestamps() {
+                Ok(pending) if !pending.is_empty() => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(crate::comms::ToOverlordMessage::UpgradeTimestamps);
+                }
+                Ok(_) => (),
+                Err(e) => tracing::error!("{}", e),
+            }
+        }
+    });
+}
+
+/// Attestation events (kind 1040) we have for an event
+pub fn attestations(id: Id) -> Vec<Id> {
+    match GLOBALS.storage.find_relationships_by_id(id) {
+        Ok(rels) => rels
+            .iter()
+            .filter(|(_, rel)| *rel == RelationshipById::Timestamp)
+            .map(|(attestation_id, _)| *attestation_id)
+            .collect(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    }
+}
+
+/// How an attestation checks out. This returns None the first time, and starts
+/// checking it in the background.
+pub fn status(attestation_id: Id) -> Option<TimestampStatus> {
+    if let Some(entry) = STATUSES.get(&attestation_id) {
+        let (status, checked_at) = &*entry;
+        if !matches!(status, TimestampStatus::Unavailable(_))
+            || checked_at.elapsed() < RECHECK_INTERVAL
+        {
+            return Some(status.clone());
+        }
+    }
+
+    STATUSES.insert(attestation_id, (TimestampStatus::Checking, Instant::now()));
+    tokio::task::spawn(async move {
+        let status = match GLOBALS.storage.read_event(attestation_id) {
+            Ok(Some(event)) => match verify(&event).await {
+                Ok(status) => status,
+                Err(e) => TimestampStatus::Unavailable(format!("{}", e)),
+            },
+            Ok(None) => TimestampStatus::Failed("attestation event is missing".to_owned()),
+            Err(e) => TimestampStatus::Unavailable(format!("{}", e)),
+        };
+        STATUSES.insert(attestation_id, (status, Instant::now()));
+    });
+
+    None
+}
+
+/// Check an attestation event (kind 1040) against bitcoin. This errors only if
+/// bitcoin could not be asked, a bad proof gives `TimestampStatus::Failed`.
+pub async fn verify(attestation: &Event) -> Result<TimestampStatus, Error> {
+    if attestation.kind != EventKind::Timestamp {
+        return Ok(TimestampStatus::Failed("not an attestation".to_owned()));
+    }
+
+    let target = match attestation.tags.iter().find_map(|t| t.parse_event().ok()) {
+        Some((id, _, _)) => id,
+        None => return Ok(TimestampStatus::Failed("no event referenced".to_owned())),
+    };
+
+    let bytes = match base64::engine::general_purpose::STANDARD.decode(attestation.content.trim()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Ok(TimestampStatus::Failed(format!(
+                "OpenTimestamps proof: {}",
+                e
+            )))
+        }
+    };
+
+    let (digest, found) = match parse_file(&bytes) {
+        Ok(parsed) => parsed,
+        Err(e) => return Ok(TimestampStatus::Failed(format!("{}", e))),
+    };
+    if digest != target.0 {
+        return Ok(TimestampStatus::Failed(
+            "proof is not for the referenced event".to_owned(),
+        ));
+    }
+
+    // Check every bitcoin attestation, lowest block first. The proof holds if
+    // any of them does.
+    let mut heights: Vec<(u64, &Leaf)> = found
+        .iter()
+        .filter_map(|leaf| match leaf.attestation {
+            Attestation::Bitcoin { height } => Some((height, leaf)),
+            _ => None,
+        })
+        .collect();
+    if heights.is_empty() {
+        return Ok(TimestampStatus::Failed(
+            "proof has no bitcoin attestation".to_owned(),
+        ));
+    }
+    heights.sort_by_key(|(height, _)| *height);
+
+    let mut mismatched: Vec<u64> = Vec::new();
+    for (height, leaf) in heights {
+        let (merkle_root, block_time) = block_header(height).await?;
+        // Block explorers show the merkle root byte-reversed
+        let mut commitment = leaf.commitment.clone();
+        commitment.reverse();
+        if commitment == merkle_root {
+            return Ok(TimestampStatus::Verified { height, block_time });
+        }
+        mismatched.push(height);
+    }
+
+    Ok(TimestampStatus::Failed(format!(
+        "proof does not match bitcoin block {}",
+        mismatched
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    )))
+}
+
+/// Ask the calendars to timestamp one of our events. This gives a pending proof,
+/// which has to be upgraded once the calendars have put it into bitcoin.
+pub(crate) async fn request(id: Id) -> Result<Vec<u8>, Error> {
+    let client = client()?;
+
+    let mut responses: Vec<Vec<u8>> = Vec::new();
+    for calendar in CALENDARS.iter() {
+        let response = client
+            .post(format!("{}/digest", calendar))
+            .header("Accept", "application/vnd.opentimestamps.v1")
+            .body(id.0.to_vec())
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => {
+                let bytes = response.bytes().await?;
+                GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+                responses.push(bytes.to_vec());
+            }
+            Ok(response) => {
+                tracing::warn!("{} would not timestamp: {}", calendar, response.status())
+            }
+            Err(e) => tracing::warn!("{} would not timestamp: {}", calendar, e),
+        }
+    }
+
+    if responses.is_empty() {
+        return Err(ErrorKind::General("No calendar would timestamp the event".to_owned()).into());
+    }
+
+    let mut file = file_header(&id);
+    let last = responses.len() - 1;
+    for (i, response) in responses.iter().enumerate() {
+        if i < last {
+            file.push(FORK);
+        }
+        file.extend_from_slice(response);
+    }
+
+    Ok(file)
+}
+
+/// Try to upgrade a pending proof. If a calendar has put it into bitcoin, this returns
+/// a proof with just that path and its bitcoin attestation.
+pub(crate) async fn upgrade(id: Id, file: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let client = client()?;
+
+    let (_, found) = parse_file(file)?;
+    for leaf in found.iter() {
+        if let Attestation::Pending { uri } = &leaf.attestation {
+            let response = client
+                .get(format!(
+                    "{}/timestamp/{}",
+                    uri.trim_end_matches('/'),
+                    hex::encode(&leaf.commitment)
+                ))
+                .header("Accept", "application/vnd.opentimestamps.v1")
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                continue; // not there yet
+            }
+            let bytes = response.bytes().await?;
+            GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+
+            // Check the upgrade reaches bitcoin
+            let mut upgraded_leaves = Vec::new();
+            let mut reader = Reader::new(&bytes);
+            parse_timestamp(
+                &mut reader,
+                leaf.commitment.clone(),
+                vec![],
+                &mut upgraded_leaves,
+                0,
+            )?;
+            if !upgraded_leaves
+                .iter()
+                .any(|l| matches!(l.attestation, Attestation::Bitcoin { .. }))
+            {
+                continue;
+            }
+
+            let mut upgraded = file_header(&id);
+            upgraded.extend_from_slice(&leaf.path);
+            upgraded.extend_from_slice(&bytes);
+            return Ok(Some(upgraded));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Encode a proof for the content of an attestation event
+pub(crate) fn encode(file: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(file)
+}
+
+fn client() -> Result<reqwest::Client, Error> {
+    Ok(crate::proxy::client_builder()?
+        .timeout(std::time::Duration::new(60, 0))
+        .build()?)
+}
+
+// The merkle root (as shown by block explorers) and time of a bitcoin block
+async fn block_header(height: u64) -> Result<(Vec<u8>, Unixtime), Error> {
+    #[derive(serde::Deserialize)]
+    struct Block {
+        merkle_root: String,
+        timestamp: i64,
+    }
+
+    let client = client()?;
+    let hash = client
+        .get(format!("{}/block-height/{}", ESPLORA_API, height))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let block: Block = client
+        .get(format!("{}/block/{}", ESPLORA_API, hash.trim()))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let merkle_root = hex::decode(&block.merkle_root)
+        .map_err(|e| ErrorKind::General(format!("Bad merkle root: {}", e)))?;
+    Ok((merkle_root, Unixtime(block.timestamp)))
+}
+
+fn file_header(id: &Id) -> Vec<u8> {
+    let mut file = HEADER_MAGIC.to_vec();
+    file.push(MAJOR_VERSION);
+    file.push(OP_SHA256);
+    file.extend_from_slice(&id.0);
+    file
+}
+
+#[derive(Clone, Debug)]
+enum Attestation {
+    Bitcoin { height: u64 },
+    Pending { uri: String },
+    Unknown,
+}
+
+// An attestation in a proof, with the commitment it attests to and the
+// serialized operations that lead to it from the digest
+struct Leaf {
+    commitment: Vec<u8>,
+    path: Vec<u8>,
+    attestation: Attestation,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.pos + len > self.bytes.len() {
+            return Err(ErrorKind::General("OpenTimestamps proof is truncated".to_owned()).into());
+        }
+        let out = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn varuint(&mut self) -> Result<u64, Error> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift > 63 {
+                return Err(
+                    ErrorKind::General("OpenTimestamps varuint is too long".to_owned()).into(),
+                );
+            }
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn varbytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.varuint()? as usize;
+        self.bytes(len)
+    }
+}
+
+fn write_varuint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(b);
+            return;
+        }
+        out.push(b | 0x80);
+    }
+}
+
+// Parse an .ots file, returning the digest it is for and its attestations
+fn parse_file(bytes: &[u8]) -> Result<([u8; 32], Vec<Leaf>), Error> {
+    let mut reader = Reader::new(bytes);
+    if reader.bytes(HEADER_MAGIC.len())? != HEADER_MAGIC {
+        return Err(ErrorKind::General("Not an OpenTimestamps proof".to_owned()).into());
+    }
+    if reader.varuint()? != MAJOR_VERSION as u64 {
+        return Err(
+            ErrorKind::General("Unsupported OpenTimestamps proof version".to_owned()).into(),
+        );
+    }
+    if reader.byte()? != OP_SHA256 {
+        return Err(ErrorKind::General(
+            "OpenTimestamps proof is not for a SHA-256 digest".to_owned(),
+        )
+        .into());
+    }
+    let digest: [u8; 32] = reader.bytes(32)?.try_into()?;
+
+    let mut leaves = Vec::new();
+    parse_timestamp(&mut reader, digest.to_vec(), vec![], &mut leaves, 0)?;
+    Ok((digest, leaves))
+}
+
+fn parse_timestamp(
+    reader: &mut Reader,
+    msg: Vec<u8>,
+    path: Vec<u8>,
+    leaves: &mut Vec<Leaf>,
+    depth: usize,
+) -> Result<(), Error> {
+    if depth > MAX_DEPTH {
+        return Err(ErrorKind::General("OpenTimestamps proof is too deep".to_owned()).into());
+    }
+
+    // Every item but the last is preceded by a fork
+    loop {
+        let tag = reader.byte()?;
+        if tag == FORK {
+            let tag = reader.byte()?;
+            parse_item(reader, tag, msg.clone(), path.clone(), leaves, depth)?;
+        } else {
+            return parse_item(reader, tag, msg, path, leaves, depth);
+        }
+    }
+}
+
+fn parse_item(
+    reader: &mut Reader,
+    tag: u8,
+    msg: Vec<u8>,
+    mut path: Vec<u8>,
+    leaves: &mut Vec<Leaf>,
+    depth: usize,
+) -> Result<(), Error> {
+    if tag == ATTESTATION {
+        let kind: [u8; 8] = reader.bytes(8)?.try_into()?;
+        let payload = reader.varbytes()?;
+        let mut payload_reader = Reader::new(payload);
+        let attestation = match kind {
+            BITCOIN_ATTESTATION => Attestation::Bitcoin {
+                height: payload_reader.varuint()?,
+            },
+            PENDING_ATTESTATION => Attestation::Pending {
+                uri: String::from_utf8(payload_reader.varbytes()?.to_vec())?,
+            },
+            _ => Attestation::Unknown,
+        };
+        leaves.push(Leaf {
+            commitment: msg,
+            path,
+            attestation,
+        });
+        return Ok(());
+    }
+
+    path.push(tag);
+    let result = match tag {
+        OP_SHA256 => sha2::Sha256::digest(&msg).to_vec(),
+        OP_APPEND | OP_PREPEND => {
+            let arg = reader.varbytes()?;
+            write_varuint(&mut path, arg.len() as u64);
+            path.extend_from_slice(arg);
+            if tag == OP_APPEND {
+                [msg.as_slice(), arg].concat()
+            } else {
+                [arg, msg.as_slice()].concat()
+            }
+        }
+        _ => {
+            return Err(ErrorKind::General(format!(
+                "Unsupported OpenTimestamps operation {:#04x}",
+                tag
+            ))
+            .into())
+        }
+    };
+
+    parse_timestamp(reader, result, path, leaves, depth + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attestation(out: &mut Vec<u8>, kind: [u8; 8], payload: &[u8]) {
+        out.push(ATTESTATION);
+        out.extend_from_slice(&kind);
+        write_varuint(out, payload.len() as u64);
+        out.extend_from_slice(payload);
+    }
+
+    #[test]
+    fn test_varuint() {
+        for value in [0, 1, 127, 128, 300, 840_000, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varuint(&mut bytes, value);
+            assert_eq!(Reader::new(&bytes).varuint().unwrap(), value);
+        }
+        assert!(Reader::new(&[0x80]).varuint().is_err());
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let id = Id([7; 32]);
+        let mut file = file_header(&id);
+
+        // A fork: append then hash to a bitcoin attestation...
+        file.push(FORK);
+        file.push(OP_APPEND);
+        write_varuint(&mut file, 2);
+        file.extend_from_slice(&[1, 2]);
+        file.push(OP_SHA256);
+        let mut height = Vec::new();
+        write_varuint(&mut height, 840_000);
+        attestation(&mut file, BITCOIN_ATTESTATION, &height);
+
+        // ...and prepend to a pending one
+        file.push(OP_PREPEND);
+        write_varuint(&mut file, 1);
+        file.push(9);
+        let uri = b"https://alice.btc.calendar.opentimestamps.org";
+        let mut payload = Vec::new();
+        write_varuint(&mut payload, uri.len() as u64);
+        payload.extend_from_slice(uri);
+        attestation(&mut file, PENDING_ATTESTATION, &payload);
+
+        let (digest, leaves) = parse_file(&file).unwrap();
+        assert_eq!(digest, id.0);
+        assert_eq!(leaves.len(), 2);
+
+        let appended = [id.0.as_slice(), &[1, 2]].concat();
+        assert_eq!(
+            leaves[0].commitment,
+            sha2::Sha256::digest(&appended).to_vec()
+        );
+        assert_eq!(leaves[0].path, vec![OP_APPEND, 2, 1, 2, OP_SHA256]);
+        assert!(matches!(
+            leaves[0].attestation,
+            Attestation::Bitcoin { height: 840_000 }
+        ));
+
+        assert_eq!(leaves[1].commitment, [&[9u8][..], &id.0[..]].concat());
+        assert_eq!(leaves[1].path, vec![OP_PREPEND, 1, 9]);
+        assert!(matches!(&leaves[1].attestation,
+            Attestation::Pending { uri } if uri == "https://alice.btc.calendar.opentimestamps.org"));
+
+        // Replaying a leaf's path from the digest gives its commitment
+        let mut replay = file_header(&id);
+        replay.extend_from_slice(&leaves[0].path);
+        attestation(&mut replay, BITCOIN_ATTESTATION, &height);
+        let (_, replayed) = parse_file(&replay).unwrap();
+        assert_eq!(replayed[0].commitment, leaves[0].commitment);
+    }
+
+    #[test]
+    fn test_parse_file_errors() {
+        let id = Id([7; 32]);
+        assert!(parse_file(b"not a proof").is_err());
+
+        // Truncated
+        let file = file_header(&id);
+        assert!(parse_file(&file).is_err());
+
+        // Unknown operation
+        let mut file = file_header(&id);
+        file.push(0x42);
+        assert!(parse_file(&file).is_err());
+
+        // Too deep
+        let mut file = file_header(&id);
+        file.extend(std::iter::repeat(OP_SHA256).take(MAX_DEPTH + 2));
+        attestation(&mut file, BITCOIN_ATTESTATION, &[1]);
+        assert!(parse_file(&file).is_err());
+    }
+}
@@ -129,32 +129,58 @@ pub async fn get_and_follow_nip05(
     Ok(())
 }
 
+/// Look up the pubkey of a DNS ID and the relays it lists for them, without
+/// recording anything. See [record_nip05_relays] for keeping the relays.
+pub async fn lookup_nip05(nip05: &str) -> Result<Option<(PublicKey, Vec<RelayUrl>)>, Error> {
+    let (user, domain) = parse_nip05(nip05)?;
+    let nip05file = fetch_nip05(&user, &domain).await?;
+    let pubkey = match nip05file.names.get(&user) {
+        Some(pk) => PublicKey::try_from_hex_string(pk, true)?,
+        None => return Ok(None),
+    };
+    let relays = match nip05file.relays.get(&pubkey.into()) {
+        Some(relays) => relays
+            .iter()
+            .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+            .collect(),
+        None => vec![],
+    };
+    Ok(Some((pubkey, relays)))
+}
+
 async fn update_relays(nip05: &str, nip05file: Nip05, pubkey: &PublicKey) -> Result<(), Error> {
     // Set their relays
-    let relays = match nip05file.relays.get(&(*pubkey).into()) {
-        Some(relays) => relays,
+    let relays: Vec<RelayUrl> = match nip05file.relays.get(&(*pubkey).into()) {
+        Some(relays) => relays
+            .iter()
+            .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+            .collect(),
         None => return Ok(()),
     };
-    for relay in relays.iter() {
-        // Save relay
-        if let Ok(relay_url) = RelayUrl::try_from_unchecked_url(relay) {
-            GLOBALS.storage.write_relay_if_missing(&relay_url, None)?;
-
-            // Save person_relay
-            let mut pr = match GLOBALS.storage.read_person_relay(*pubkey, &relay_url)? {
-                Some(pr) => pr,
-                None => PersonRelay::new(*pubkey, relay_url.clone()),
-            };
-            pr.last_suggested_nip05 = Some(Unixtime::now().unwrap().0 as u64);
-            GLOBALS.storage.write_person_relay(&pr, None)?;
-        }
-    }
+    record_nip05_relays(*pubkey, &relays)?;
 
     tracing::info!("Setup {} relays for {}", relays.len(), nip05);
 
     Ok(())
 }
 
+/// Record relays that a NIP-05 file suggests for somebody
+pub fn record_nip05_relays(pubkey: PublicKey, relays: &[RelayUrl]) -> Result<(), Error> {
+    for relay_url in relays {
+        // Save relay
+        GLOBALS.storage.write_relay_if_missing(relay_url, None)?;
+
+        // Save person_relay
+        let mut pr = match GLOBALS.storage.read_person_relay(pubkey, relay_url)? {
+            Some(pr) => pr,
+            None => PersonRelay::new(pubkey, relay_url.clone()),
+        };
+        pr.last_suggested_nip05 = Some(Unixtime::now().unwrap().0 as u64);
+        GLOBALS.storage.write_person_relay(&pr, None)?;
+    }
+    Ok(())
+}
+
 // returns user and domain
 pub fn parse_nip05(nip05: &str) -> Result<(String, String), Error> {
     let mut parts: Vec<&str> = nip05.split('@').collect();
@@ -181,7 +207,7 @@ pub fn parse_nip05(nip05: &str) -> Result<(String, String), Error> {
 async fn fetch_nip05(user: &str, domain: &str) -> Result<Nip05, Error> {
     // FIXME add user-agent if configured
 
-    let nip05_future = reqwest::Client::builder()
+    let nip05_future = crate::proxy::client_builder()?
         .timeout(std::time::Duration::new(60, 0))
         .redirect(reqwest::redirect::Policy::none()) // see NIP-05
         .gzip(true)
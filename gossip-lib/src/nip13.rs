@@ -0,0 +1,253 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, PreEvent, Tag, Unixtime};
+use parking_lot::RwLock as PRwLock;
+use sha2::Digest;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How many hashes each thread does between checks for a result or cancellation
+const BATCH: u64 = 4096;
+
+/// Progress of the proof-of-work being computed for an outgoing event
+#[derive(Clone, Debug)]
+pub struct PowProgress {
+    /// The difficulty (leading zero bits) we are mining for
+    pub target: u8,
+
+    /// The most leading zero bits found so far
+    pub best: u8,
+
+    /// How many hashes have been tried
+    pub hashes: u64,
+
+    /// When mining started
+    pub started: Unixtime,
+}
+
+struct Job {
+    target: u8,
+    best: AtomicU8,
+    hashes: AtomicU64,
+    started: Unixtime,
+    cancelled: AtomicBool,
+}
+
+lazy_static! {
+    static ref CURRENT: PRwLock<Option<Arc<Job>>> = PRwLock::new(None);
+}
+
+/// Progress of the proof-of-work currently being computed, if any
+pub fn progress() -> Option<PowProgress> {
+    CURRENT.read().as_ref().map(|job| PowProgress {
+        target: job.target,
+        best: job.best.load(Ordering::Relaxed),
+        hashes: job.hashes.load(Ordering::Relaxed),
+        started: job.started,
+    })
+}
+
+/// Stop computing proof-of-work. The event being mined is not sent.
+pub fn cancel() {
+    if let Some(job) = CURRENT.read().as_ref() {
+        job.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sign an event, first adding the proof-of-work from the `pow` setting (if any)
+pub(crate) async fn sign_event(pre_event: PreEvent) -> Result<Event, Error> {
+    let target = GLOBALS.storage.read_setting_pow();
+    if target == 0 {
+        return GLOBALS.identity.sign_event(pre_event);
+    }
+
+    let pre_event = match mine(pre_event, target).await? {
+        Some(pre_event) => pre_event,
+        None => return Err(ErrorKind::General("Proof of work was cancelled".to_owned()).into()),
+    };
+    let event = GLOBALS.identity.sign_event(pre_event)?;
+
+    // The nonce was found by hashing our own serialization of the event, so
+    // make sure the real id has the work
+    if leading_zero_bits(&event.id.0) < target {
+        return Err(ErrorKind::General(
+            "Proof of work was computed for a different event id".to_owned(),
+        )
+        .into());
+    }
+
+    GLOBALS
+        .status_queue
+        .write()
+        .write(format!("Message sent with {target} bits of work computed."));
+    Ok(event)
+}
+
+/// Add a nonce tag to an event so that its id has at least `target` leading zero
+/// bits, using all CPU cores. Returns None if cancelled.
+pub(crate) async fn mine(pre_event: PreEvent, target: u8) -> Result<Option<PreEvent>, Error> {
+    let job = Arc::new(Job {
+        target,
+        best: AtomicU8::new(0),
+        hashes: AtomicU64::new(0),
+        started: Unixtime::now().unwrap(),
+        cancelled: AtomicBool::new(false),
+    });
+    if let Some(previous) = CURRENT.write().replace(job.clone()) {
+        // Only one event is mined at a time
+        previous.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    let worker_job = job.clone();
+    let result = tokio::task::spawn_blocking(move || mine_blocking(pre_event, &worker_job)).await;
+
+    {
+        let mut current = CURRENT.write();
+        if current.as_ref().map(|j| Arc::ptr_eq(j, &job)) == Some(true) {
+            *current = None;
+        }
+    }
+
+    result.map_err(|e| ErrorKind::General(format!("Proof of work failed: {}", e)))?
+}
+
+/// The difficulty of an event's proof-of-work: its leading zero bits, but no more
+/// than the difficulty committed to in its nonce tag. Work without a committed
+/// difficulty does not count, since it could have been found by chance.
+pub fn difficulty(event: &Event) -> u8 {
+    let committed = event.tags.iter().find_map(|tag| {
+        if tag.tagname() == "nonce" {
+            tag.get_index(2).parse::<u8>().ok()
+        } else {
+            None
+        }
+    });
+    match committed {
+        Some(committed) => event.pow().min(committed),
+        None => 0,
+    }
+}
+
+/// Whether an event has the proof-of-work that the `min_pow_unknown_authors` setting
+/// requires. Only feed events are checked, and events from us or from people in
+/// any of our lists (other than muted) always pass.
+pub fn passes_filter(event: &Event) -> bool {
+    let min = GLOBALS.storage.read_setting_min_pow_unknown_authors();
+    if min == 0 || !event.kind.is_feed_displayable() || event.kind == EventKind::GiftWrap {
+        return true;
+    }
+
+    if Some(event.pubkey) == GLOBALS.identity.public_key() {
+        return true;
+    }
+
+    if let Ok(lists) = GLOBALS.storage.read_person_lists(&event.pubkey) {
+        if lists.keys().any(|list| *list != PersonList::Muted) {
+            return true;
+        }
+    }
+
+    difficulty(event) >= min
+}
+
+fn mine_blocking(mut pre_event: PreEvent, job: &Job) -> Result<Option<PreEvent>, Error> {
+    // Drop any nonce we had, the work is committed to the new target
+    pre_event.tags.retain(|t| t.tagname() != "nonce");
+
+    // The serialized event (NIP-01) around the nonce value
+    let tags = serde_json::to_string(&pre_event.tags)?;
+    let tags = tags.trim_end_matches(']');
+    let prefix = format!(
+        "[0,\"{}\",{},{},{}{}[\"nonce\",\"",
+        pre_event.pubkey.as_hex_string(),
+        pre_event.created_at.0,
+        u32::from(pre_event.kind),
+        tags,
+        if pre_event.tags.is_empty() { "" } else { "," },
+    );
+    let suffix = format!(
+        "\",\"{}\"]],{}]",
+        job.target,
+        serde_json::to_string(&pre_event.content)?
+    );
+
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get() as u64)
+        .unwrap_or(1);
+    let found: Mutex<Option<u64>> = Mutex::new(None);
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        for start in 0..threads {
+            let (prefix, suffix, found, done) = (&prefix, &suffix, &found, &done);
+            scope.spawn(move || {
+                let mut nonce = start;
+                loop {
+                    for _ in 0..BATCH {
+                        let mut hasher = sha2::Sha256::new();
+                        hasher.update(prefix.as_bytes());
+                        hasher.update(nonce.to_string().as_bytes());
+                        hasher.update(suffix.as_bytes());
+                        let zeroes = leading_zero_bits(&hasher.finalize());
+                        if zeroes > job.best.load(Ordering::Relaxed) {
+                            job.best.fetch_max(zeroes, Ordering::Relaxed);
+                        }
+                        if zeroes >= job.target {
+                            *found.lock().unwrap() = Some(nonce);
+                            done.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                        nonce += threads;
+                    }
+                    job.hashes.fetch_add(BATCH, Ordering::Relaxed);
+                    if done.load(Ordering::Relaxed) || job.cancelled.load(Ordering::Relaxed) {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    let nonce = match *found.lock().unwrap() {
+        Some(nonce) => nonce,
+        None => return Ok(None),
+    };
+
+    pre_event.tags.push(Tag::new(&[
+        "nonce",
+        &format!("{}", nonce),
+        &format!("{}", job.target),
+    ]));
+    Ok(Some(pre_event))
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u8 {
+    let mut zeroes: u32 = 0;
+    for byte in hash {
+        if *byte == 0 {
+            zeroes += 8;
+        } else {
+            zeroes += byte.leading_zeros();
+            break;
+        }
+    }
+    zeroes.min(u8::MAX as u32) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xff, 0x00]), 0);
+        assert_eq!(leading_zero_bits(&[0x7f]), 1);
+        assert_eq!(leading_zero_bits(&[0x01, 0xff]), 7);
+        assert_eq!(leading_zero_bits(&[0x00, 0x0f, 0xff]), 12);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00, 0x80]), 16);
+        assert_eq!(leading_zero_bits(&[0u8; 32]), 255);
+        assert_eq!(leading_zero_bits(&[]), 0);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Id, PublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The kind of stall events
+pub const STALL_KIND: u32 = 30017;
+
+/// The kind of product events
+pub const PRODUCT_KIND: u32 = 30018;
+
+pub fn marketplace_kinds() -> Vec<EventKind> {
+    vec![EventKind::from(STALL_KIND), EventKind::from(PRODUCT_KIND)]
+}
+
+/// A way a stall ships, and what it costs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Shipping {
+    pub id: String,
+    pub name: Option<String>,
+    pub cost: f64,
+    pub regions: Vec<String>,
+}
+
+/// A merchant's stall, where their products are sold
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stall {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub shipping: Vec<Shipping>,
+
+    #[serde(skip)]
+    pub merchant: Option<PublicKey>,
+}
+
+impl Stall {
+    pub fn from_event(event: &Event) -> Option<Stall> {
+        if u32::from(event.kind) != STALL_KIND {
+            return None;
+        }
+        let mut stall: Stall = serde_json::from_str(&event.content).ok()?;
+        stall.merchant = Some(event.pubkey);
+        Some(stall)
+    }
+}
+
+/// A product for sale
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Product {
+    pub id: String,
+    pub stall_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub images: Vec<String>,
+    pub currency: String,
+    pub price: f64,
+
+    /// How many are available, if limited
+    pub quantity: Option<u64>,
+
+    /// Pairs of attribute name and value, e.g. ["size", "M"]
+    pub specs: Vec<Vec<String>>,
+
+    #[serde(skip)]
+    pub merchant: Option<PublicKey>,
+
+    #[serde(skip)]
+    pub event_id: Option<Id>,
+}
+
+impl Product {
+    pub fn from_event(event: &Event) -> Option<Product> {
+        if u32::from(event.kind) != PRODUCT_KIND {
+            return None;
+        }
+        let mut product: Product = serde_json::from_str(&event.content).ok()?;
+        product.merchant = Some(event.pubkey);
+        product.event_id = Some(event.id);
+        Some(product)
+    }
+
+    pub fn display_price(&self) -> String {
+        format!("{} {}", self.price, self.currency)
+    }
+
+    pub fn in_stock(&self) -> bool {
+        self.quantity != Some(0)
+    }
+}
+
+/// Products of the people we follow, with their stalls, newest first
+pub fn followed_products() -> Result<Vec<(Product, Option<Stall>)>, Error> {
+    let pubkeys = GLOBALS.people.get_subscribed_pubkeys();
+    if pubkeys.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut stalls: HashMap<(PublicKey, String), Stall> = HashMap::new();
+    for event in GLOBALS.storage.find_events(
+        &[EventKind::from(STALL_KIND)],
+        &pubkeys,
+        None,
+        |_| true,
+        false,
+    )? {
+        if let Some(stall) = Stall::from_event(&event) {
+            stalls.insert((event.pubkey, stall.id.clone()), stall);
+        }
+    }
+
+    let mut products: Vec<(Product, Option<Stall>)> = Vec::new();
+    for event in GLOBALS.storage.find_events(
+        &[EventKind::from(PRODUCT_KIND)],
+        &pubkeys,
+        None,
+        |_| true,
+        true,
+    )? {
+        if let Some(product) = Product::from_event(&event) {
+            let stall = stalls
+                .get(&(event.pubkey, product.stall_id.clone()))
+                .cloned();
+            products.push((product, stall));
+        }
+    }
+
+    Ok(products)
+}
+
+/// A product and how many are wanted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderItem {
+    pub product_id: String,
+    pub quantity: u64,
+}
+
+/// How the merchant can reach the customer
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderContact {
+    pub nostr: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+/// An order, sent to the merchant in an encrypted direct message. The merchant
+/// answers with a payment request, also by direct message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Order {
+    pub id: String,
+
+    /// 0 for a new order
+    #[serde(rename = "type")]
+    pub order_type: u8,
+
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub message: Option<String>,
+    pub contact: OrderContact,
+    pub items: Vec<OrderItem>,
+    pub shipping_id: String,
+}
+
+impl Order {
+    pub fn new(product: &Product, quantity: u64, shipping_id: String) -> Order {
+        Order {
+            id: format!("{:016x}", rand::random::<u64>()),
+            order_type: 0,
+            name: None,
+            address: None,
+            message: None,
+            contact: OrderContact {
+                nostr: GLOBALS
+                    .identity
+                    .public_key()
+                    .map(|pk| pk.as_bech32_string()),
+                ..Default::default()
+            },
+            items: vec![OrderItem {
+                product_id: product.id.clone(),
+                quantity,
+            }],
+            shipping_id,
+        }
+    }
+
+    pub fn to_message(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use nostr_types::{Event, EventKind, PreEvent, PublicKey, RelayUrl, Tag, Unixtime};
+
+/// The list of relays a person wants their direct messages sent to (NIP-17)
+pub const DM_RELAY_LIST_KIND: u32 = 10050;
+
+pub fn dm_relay_list_kind() -> EventKind {
+    EventKind::from(DM_RELAY_LIST_KIND)
+}
+
+/// The relays a person has asked to be sent direct messages on, from the
+/// latest DM relay list of theirs that we have
+pub fn dm_relays(pubkey: PublicKey) -> Result<Vec<RelayUrl>, Error> {
+    let event = match GLOBALS
+        .storage
+        .get_replaceable_event(dm_relay_list_kind(), pubkey, "")?
+    {
+        Some(event) => event,
+        None => return Ok(vec![]),
+    };
+
+    let mut relays: Vec<RelayUrl> = Vec::new();
+    for tag in &event.tags {
+        if tag.tagname() == "relay" {
+            if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                if !relays.contains(&url) {
+                    relays.push(url);
+                }
+            }
+        }
+    }
+    Ok(relays)
+}
+
+/// Whether a person can be sent NIP-17 direct messages, that is whether we
+/// know where they want them
+pub fn can_receive(pubkey: PublicKey) -> bool {
+    matches!(dm_relays(pubkey), Ok(relays) if !relays.is_empty())
+}
+
+/// The relays our own direct messages should arrive on. These are the ones
+/// in our DM relay list, or our inbox relays if we have not published one.
+pub fn my_dm_relays() -> Result<Vec<RelayUrl>, Error> {
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        let relays = dm_relays(pk)?;
+        if !relays.is_empty() {
+            return Ok(relays);
+        }
+    }
+
+    Ok(GLOBALS
+        .storage
+        .filter_relays(|r| r.has_usage_bits(Relay::INBOX) && r.rank != 0)?
+        .iter()
+        .map(|relay| relay.url.clone())
+        .collect())
+}
+
+/// A DM relay list naming our inbox relays, ready to be signed
+pub fn dm_relay_list(pubkey: PublicKey) -> Result<PreEvent, Error> {
+    let tags: Vec<Tag> = GLOBALS
+        .storage
+        .filter_relays(|r| r.has_usage_bits(Relay::INBOX) && r.rank != 0)?
+        .iter()
+        .map(|relay| Tag::new(&["relay", relay.url.as_str()]))
+        .collect();
+
+    Ok(PreEvent {
+        pubkey,
+        created_at: Unixtime::now().unwrap(),
+        kind: dm_relay_list_kind(),
+        tags,
+        content: "".to_owned(),
+    })
+}
+
+/// Seal and gift wrap a chat message for each of the recipients, and once more
+/// for ourself so we can read what we sent. The unsigned message (the rumor)
+/// names every recipient, which is how the conversation is threaded.
+///
+/// Returns each recipient with the gift wrap addressed to them, our own copy
+/// last.
+pub fn wrap_message(
+    me: PublicKey,
+    recipients: &[PublicKey],
+    content: &str,
+) -> Result<Vec<(PublicKey, Event)>, Error> {
+    let rumor = PreEvent {
+        pubkey: me,
+        created_at: Unixtime::now().unwrap(),
+        kind: EventKind::DmChat,
+        tags: recipients
+            .iter()
+            .map(|pk| Tag::new_pubkey(*pk, None, None))
+            .collect(),
+        content: content.to_owned(),
+    };
+
+    let mut wraps: Vec<(PublicKey, Event)> = Vec::new();
+    for pubkey in recipients.iter().filter(|pk| **pk != me) {
+        wraps.push((*pubkey, GLOBALS.identity.giftwrap(rumor.clone(), *pubkey)?));
+    }
+    wraps.push((me, GLOBALS.identity.giftwrap(rumor, me)?));
+    Ok(wraps)
+}
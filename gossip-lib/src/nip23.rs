@@ -0,0 +1,461 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, Tag, Unixtime};
+
+/// A long-form article (kind 30023)
+#[derive(Debug, Clone)]
+pub struct LongFormArticle {
+    /// Identifies the article among its author's, so new versions replace it
+    pub d: String,
+    pub title: String,
+    pub summary: Option<String>,
+
+    /// The URL of a header image
+    pub image: Option<String>,
+
+    /// When the article was first published. Later edits keep this.
+    pub published_at: Option<Unixtime>,
+
+    pub hashtags: Vec<String>,
+
+    /// Markdown content
+    pub content: String,
+
+    pub event: Event,
+}
+
+impl LongFormArticle {
+    pub fn from_event(event: &Event) -> Option<LongFormArticle> {
+        if event.kind != EventKind::LongFormContent {
+            return None;
+        }
+
+        let d = event.parameter()?;
+        let mut title = String::new();
+        let mut summary = None;
+        let mut image = None;
+        let mut published_at = None;
+        let mut hashtags: Vec<String> = Vec::new();
+        for tag in &event.tags {
+            match tag.tagname() {
+                "title" => title = tag.value().to_owned(),
+                "summary" if !tag.value().trim().is_empty() => {
+                    summary = Some(tag.value().to_owned())
+                }
+                "image" if !tag.value().trim().is_empty() => image = Some(tag.value().to_owned()),
+                "published_at" => {
+                    if let Ok(secs) = tag.value().parse::<i64>() {
+                        published_at = Some(Unixtime(secs));
+                    }
+                }
+                "t" => hashtags.push(tag.value().to_owned()),
+                _ => (),
+            }
+        }
+        if title.trim().is_empty() {
+            title = d.clone();
+        }
+
+        Some(LongFormArticle {
+            d,
+            title,
+            summary,
+            image,
+            published_at,
+            hashtags,
+            content: event.content.clone(),
+            event: event.clone(),
+        })
+    }
+
+    /// When it was first published, or else when this version was made
+    pub fn published(&self) -> Unixtime {
+        self.published_at.unwrap_or(self.event.created_at)
+    }
+}
+
+/// The latest version of each article by these people (by anybody if none are
+/// given), most recently published first
+pub fn articles(authors: &[PublicKey]) -> Result<Vec<LongFormArticle>, Error> {
+    let mut articles: Vec<LongFormArticle> = GLOBALS
+        .storage
+        .find_events(
+            &[EventKind::LongFormContent],
+            authors,
+            None,
+            |_| true,
+            false,
+        )?
+        .iter()
+        .filter_map(LongFormArticle::from_event)
+        .collect();
+
+    // Keep only the newest version of each
+    articles.sort_by(|a, b| b.event.created_at.cmp(&a.event.created_at));
+    let mut seen: Vec<(PublicKey, String)> = Vec::new();
+    articles.retain(|a| {
+        let key = (a.event.pubkey, a.d.clone());
+        if seen.contains(&key) {
+            false
+        } else {
+            seen.push(key);
+            true
+        }
+    });
+
+    articles.sort_by(|a, b| b.published().cmp(&a.published()));
+    Ok(articles)
+}
+
+/// A `d` tag for a new article with this title: lowercase, words joined with
+/// '-', plus the time so that articles with the same title do not replace each
+/// other
+pub fn new_d(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    format!("{}-{}", words.join("-"), Unixtime::now().unwrap().0)
+}
+
+/// The tags of an article
+pub fn article_tags(
+    d: &str,
+    title: &str,
+    summary: &str,
+    image: &str,
+    published_at: Unixtime,
+    hashtags: &[String],
+) -> Vec<Tag> {
+    let mut tags = vec![
+        Tag::new(&["d", d]),
+        Tag::new(&["title", title.trim()]),
+        Tag::new(&["published_at", &published_at.0.to_string()]),
+    ];
+    if !summary.trim().is_empty() {
+        tags.push(Tag::new(&["summary", summary.trim()]));
+    }
+    if !image.trim().is_empty() {
+        tags.push(Tag::new(&["image", image.trim()]));
+    }
+    for hashtag in hashtags {
+        let hashtag = hashtag.trim().trim_start_matches('#');
+        if !hashtag.is_empty() {
+            tags.push(Tag::new_hashtag(hashtag.to_lowercase()));
+        }
+    }
+    tags
+}
+
+/// A block of markdown content
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkdownBlock<'a> {
+    /// A heading, level 1 to 6
+    Heading(usize, &'a str),
+
+    /// A paragraph, its lines joined
+    Paragraph(String),
+
+    /// A list item, numbered if it is in an ordered list
+    ListItem(Option<&'a str>, &'a str),
+
+    Quote(String),
+
+    /// Code, shown as it is
+    Code(String),
+
+    /// An image on a line of its own
+    Image {
+        alt: &'a str,
+        url: &'a str,
+    },
+
+    Rule,
+}
+
+/// Split markdown content into blocks. This handles the parts of markdown
+/// articles commonly use, not all of CommonMark.
+pub fn blocks(content: &str) -> Vec<MarkdownBlock<'_>> {
+    let mut blocks: Vec<MarkdownBlock<'_>> = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut quote: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    fn flush<'a>(
+        blocks: &mut Vec<MarkdownBlock<'a>>,
+        paragraph: &mut Vec<&'a str>,
+        quote: &mut Vec<&'a str>,
+    ) {
+        if !paragraph.is_empty() {
+            blocks.push(MarkdownBlock::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if !quote.is_empty() {
+            blocks.push(MarkdownBlock::Quote(quote.join(" ")));
+            quote.clear();
+        }
+    }
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if let Some(lines) = &mut code {
+            if trimmed.starts_with("```") {
+                blocks.push(MarkdownBlock::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            code = Some(Vec::new());
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            continue;
+        }
+
+        if let Some(q) = trimmed.strip_prefix('>') {
+            if !paragraph.is_empty() {
+                flush(&mut blocks, &mut paragraph, &mut quote);
+            }
+            quote.push(q.trim());
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(MarkdownBlock::Heading(hashes, trimmed[hashes..].trim()));
+            continue;
+        }
+
+        if trimmed.len() >= 3
+            && (trimmed.chars().all(|c| c == '-')
+                || trimmed.chars().all(|c| c == '*')
+                || trimmed.chars().all(|c| c == '_'))
+        {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(MarkdownBlock::Rule);
+            continue;
+        }
+
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .or_else(|| trimmed.strip_prefix("+ "))
+        {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(MarkdownBlock::ListItem(None, item));
+            continue;
+        }
+
+        if let Some((number, item)) = trimmed.split_once(". ") {
+            if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                flush(&mut blocks, &mut paragraph, &mut quote);
+                blocks.push(MarkdownBlock::ListItem(Some(number), item));
+                continue;
+            }
+        }
+
+        if let Some(MarkdownSpan::Image { alt, url }) = single_span(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(MarkdownBlock::Image { alt, url });
+            continue;
+        }
+
+        if !quote.is_empty() {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+        }
+        paragraph.push(trimmed);
+    }
+
+    // An unclosed code block runs to the end
+    if let Some(lines) = code {
+        blocks.push(MarkdownBlock::Code(lines.join("\n")));
+    }
+    flush(&mut blocks, &mut paragraph, &mut quote);
+
+    blocks
+}
+
+/// A piece of a line of markdown
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkdownSpan<'a> {
+    Text(&'a str),
+    Bold(&'a str),
+    Italic(&'a str),
+    Code(&'a str),
+    Link { label: &'a str, url: &'a str },
+    Image { alt: &'a str, url: &'a str },
+}
+
+fn single_span(line: &str) -> Option<MarkdownSpan<'_>> {
+    let spans = spans(line);
+    if spans.len() == 1 {
+        spans.into_iter().next()
+    } else {
+        None
+    }
+}
+
+/// Split a line of markdown into text, emphasis, code and links
+pub fn spans(line: &str) -> Vec<MarkdownSpan<'_>> {
+    let mut spans: Vec<MarkdownSpan<'_>> = Vec::new();
+    let mut rest = line;
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    while pos < rest.len() {
+        let here = &rest[pos..];
+        let found: Option<(MarkdownSpan<'_>, usize)> = if let Some(after) = here.strip_prefix("**")
+        {
+            after
+                .find("**")
+                .filter(|end| *end > 0)
+                .map(|end| (MarkdownSpan::Bold(&after[..end]), end + 4))
+        } else if here.starts_with('*')
+            // so that snake_case words stay as they are
+            || (here.starts_with('_')
+                && !rest[..pos].ends_with(|c: char| c.is_alphanumeric()))
+        {
+            let marker = &here[..1];
+            let after = &here[1..];
+            after
+                .find(marker)
+                .filter(|end| *end > 0 && !after.starts_with(' '))
+                .map(|end| (MarkdownSpan::Italic(&after[..end]), end + 2))
+        } else if let Some(after) = here.strip_prefix('`') {
+            after
+                .find('`')
+                .map(|end| (MarkdownSpan::Code(&after[..end]), end + 2))
+        } else if let Some(after) = here.strip_prefix("![") {
+            link_parts(after).map(|(alt, url, len)| (MarkdownSpan::Image { alt, url }, len + 2))
+        } else if let Some(after) = here.strip_prefix('[') {
+            link_parts(after).map(|(label, url, len)| (MarkdownSpan::Link { label, url }, len + 1))
+        } else {
+            None
+        };
+
+        match found {
+            Some((span, len)) => {
+                if pos > text_start {
+                    spans.push(MarkdownSpan::Text(&rest[text_start..pos]));
+                }
+                spans.push(span);
+                rest = &rest[pos + len..];
+                pos = 0;
+                text_start = 0;
+            }
+            None => {
+                pos += here.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+            }
+        }
+    }
+    if text_start < rest.len() {
+        spans.push(MarkdownSpan::Text(&rest[text_start..]));
+    }
+    spans
+}
+
+// Given what follows the '[' of a link, its label, url, and the length of
+// "label](url)"
+fn link_parts(after: &str) -> Option<(&str, &str, usize)> {
+    let close = after.find("](")?;
+    let url_part = &after[close + 2..];
+    let end = url_part.find(')')?;
+    Some((&after[..close], &url_part[..end], close + 2 + end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let content = "# Title\n\nFirst line\nsecond line\n\n- one\n* two\n1. three\n\n\
+                       > quoted\n> more\n\n```\ncode  here\n```\n---\n![alt](http://x/y.png)\n\
+                       ####### not a heading\n#nospace";
+        assert_eq!(
+            blocks(content),
+            vec![
+                MarkdownBlock::Heading(1, "Title"),
+                MarkdownBlock::Paragraph("First line second line".to_owned()),
+                MarkdownBlock::ListItem(None, "one"),
+                MarkdownBlock::ListItem(None, "two"),
+                MarkdownBlock::ListItem(Some("1"), "three"),
+                MarkdownBlock::Quote("quoted more".to_owned()),
+                MarkdownBlock::Code("code  here".to_owned()),
+                MarkdownBlock::Rule,
+                MarkdownBlock::Image {
+                    alt: "alt",
+                    url: "http://x/y.png",
+                },
+                MarkdownBlock::Paragraph("####### not a heading #nospace".to_owned()),
+            ]
+        );
+
+        // a quote ends where a paragraph starts, and the other way around
+        assert_eq!(
+            blocks("text\n> q\ntext"),
+            vec![
+                MarkdownBlock::Paragraph("text".to_owned()),
+                MarkdownBlock::Quote("q".to_owned()),
+                MarkdownBlock::Paragraph("text".to_owned()),
+            ]
+        );
+
+        // an unclosed code block runs to the end
+        assert_eq!(
+            blocks("```rust\n  a\n\nb"),
+            vec![MarkdownBlock::Code("  a\n\nb".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        assert_eq!(spans("plain"), vec![MarkdownSpan::Text("plain")]);
+        assert_eq!(
+            spans("a **b** *c* _d_ `e` [f](g) ![h](i)"),
+            vec![
+                MarkdownSpan::Text("a "),
+                MarkdownSpan::Bold("b"),
+                MarkdownSpan::Text(" "),
+                MarkdownSpan::Italic("c"),
+                MarkdownSpan::Text(" "),
+                MarkdownSpan::Italic("d"),
+                MarkdownSpan::Text(" "),
+                MarkdownSpan::Code("e"),
+                MarkdownSpan::Text(" "),
+                MarkdownSpan::Link {
+                    label: "f",
+                    url: "g",
+                },
+                MarkdownSpan::Text(" "),
+                MarkdownSpan::Image { alt: "h", url: "i" },
+            ]
+        );
+
+        // markers that do not open a span are left as text
+        for line in [
+            "snake_case_word",
+            "2 * 3 * 4",
+            "[unclosed](link",
+            "****",
+            "`",
+        ] {
+            assert_eq!(spans(line), vec![MarkdownSpan::Text(line)]);
+        }
+
+        assert_eq!(
+            spans("ünïcode **bøld**"),
+            vec![MarkdownSpan::Text("ünïcode "), MarkdownSpan::Bold("bøld")]
+        );
+    }
+}
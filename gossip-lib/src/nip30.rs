@@ -0,0 +1,229 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use nostr_types::{Event, Id, PublicKey, Unixtime};
+use std::collections::HashMap;
+
+/// A piece of text that may hold custom emoji (NIP-30)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmojiSegment<'a> {
+    Text(&'a str),
+
+    /// A `:shortcode:` the event has an image for
+    Emoji {
+        shortcode: &'a str,
+        url: &'a str,
+    },
+}
+
+/// The custom emoji of an event, from its `emoji` tags, by shortcode
+pub fn emoji_map(event: &Event) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+    for tag in &event.tags {
+        if tag.tagname() == "emoji" {
+            let shortcode = tag.value();
+            let url = tag.get_index(2);
+            if is_shortcode(shortcode) && !url.is_empty() {
+                map.insert(shortcode.to_owned(), url.to_owned());
+            }
+        }
+    }
+    map
+}
+
+/// Split text at the `:shortcode:` occurrences that are in the emoji map.
+/// Other colons are left in the text.
+pub fn segments<'a>(text: &'a str, emojis: &'a HashMap<String, String>) -> Vec<EmojiSegment<'a>> {
+    let mut output: Vec<EmojiSegment<'a>> = Vec::new();
+    if emojis.is_empty() {
+        output.push(EmojiSegment::Text(text));
+        return output;
+    }
+
+    let mut text_start = 0;
+    let mut pos = 0;
+    while let Some(open) = text[pos..].find(':').map(|i| pos + i) {
+        let close = match text[open + 1..].find(':').map(|i| open + 1 + i) {
+            Some(close) => close,
+            None => break,
+        };
+        let shortcode = &text[open + 1..close];
+        match emojis.get_key_value(shortcode) {
+            Some((shortcode, url)) => {
+                if open > text_start {
+                    output.push(EmojiSegment::Text(&text[text_start..open]));
+                }
+                output.push(EmojiSegment::Emoji {
+                    shortcode,
+                    url: url.as_str(),
+                });
+                text_start = close + 1;
+                pos = close + 1;
+            }
+            // The closing colon may open the next shortcode
+            None => pos = close,
+        }
+    }
+    if text_start < text.len() {
+        output.push(EmojiSegment::Text(&text[text_start..]));
+    }
+    output
+}
+
+/// Reactions to an event with a custom emoji, as (shortcode, url, count),
+/// counting only the latest reaction of each person other than the author
+pub fn emoji_reactions(id: Id) -> Result<Vec<(String, String, usize)>, Error> {
+    let author = GLOBALS.storage.read_event(id)?.map(|e| e.pubkey);
+    let mut latest: HashMap<PublicKey, (Unixtime, Option<(String, String)>)> = HashMap::new();
+    for (reacting_id, rel) in GLOBALS.storage.find_relationships_by_id(id)? {
+        if let RelationshipById::Reaction { by, .. } = rel {
+            if Some(by) == author {
+                continue;
+            }
+            let event = match GLOBALS.storage.read_event(reacting_id)? {
+                Some(event) => event,
+                None => continue,
+            };
+            if let Some((prior_time, _)) = latest.get(&by) {
+                if event.created_at <= *prior_time {
+                    continue;
+                }
+            }
+            // A later ordinary reaction takes the place of an emoji one
+            let emoji = reaction_emoji(&event);
+            latest.insert(by, (event.created_at, emoji));
+        }
+    }
+
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    for (_, (_, emoji)) in latest.drain() {
+        if let Some(emoji) = emoji {
+            *counts.entry(emoji).or_insert(0) += 1;
+        }
+    }
+
+    let mut output: Vec<(String, String, usize)> = counts
+        .drain()
+        .map(|((shortcode, url), count)| (shortcode, url, count))
+        .collect();
+    output.sort();
+    Ok(output)
+}
+
+// The custom emoji a reaction is made of, if it is one
+fn reaction_emoji(event: &Event) -> Option<(String, String)> {
+    let shortcode = event.content.trim().strip_prefix(':')?.strip_suffix(':')?;
+    let url = emoji_map(event).remove(shortcode)?;
+    Some((shortcode.to_owned(), url))
+}
+
+// Shortcodes are made of letters, digits, underscores and hyphens
+fn is_shortcode(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr_types::{EventKind, Signature, Tag};
+
+    const SOAPBOX: &str = "https://example.com/soapbox.png";
+    const GLEASONATOR: &str = "https://example.com/gleasonator.png";
+
+    fn emojis() -> HashMap<String, String> {
+        let mut emojis = HashMap::new();
+        emojis.insert("soapbox".to_owned(), SOAPBOX.to_owned());
+        emojis.insert("gleasonator".to_owned(), GLEASONATOR.to_owned());
+        emojis
+    }
+
+    fn event(content: &str, tags: Vec<Tag>) -> Event {
+        Event {
+            id: Id([0; 32]),
+            pubkey: PublicKey::try_from_hex_string(
+                "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                true,
+            )
+            .unwrap(),
+            created_at: Unixtime(1700000000),
+            kind: EventKind::Reaction,
+            sig: Signature::zeroes(),
+            content: content.to_owned(),
+            tags,
+        }
+    }
+
+    #[test]
+    fn test_segments() {
+        let emojis = emojis();
+        let soapbox = EmojiSegment::Emoji {
+            shortcode: "soapbox",
+            url: SOAPBOX,
+        };
+        let gleasonator = EmojiSegment::Emoji {
+            shortcode: "gleasonator",
+            url: GLEASONATOR,
+        };
+
+        assert_eq!(
+            segments("hi :soapbox:!", &emojis),
+            vec![
+                EmojiSegment::Text("hi "),
+                soapbox.clone(),
+                EmojiSegment::Text("!"),
+            ]
+        );
+        assert_eq!(
+            segments(":soapbox::gleasonator:", &emojis),
+            vec![soapbox.clone(), gleasonator]
+        );
+
+        // a colon that opens no shortcode can close one
+        assert_eq!(
+            segments("at 12:30 :soapbox:", &emojis),
+            vec![EmojiSegment::Text("at 12:30 "), soapbox]
+        );
+
+        for text in [":unknown: and :", "no colons"] {
+            assert_eq!(segments(text, &emojis), vec![EmojiSegment::Text(text)]);
+        }
+        assert_eq!(segments("", &emojis), vec![]);
+        assert_eq!(
+            segments(":soapbox:", &HashMap::new()),
+            vec![EmojiSegment::Text(":soapbox:")]
+        );
+    }
+
+    #[test]
+    fn test_is_shortcode() {
+        assert!(is_shortcode("soapbox"));
+        assert!(is_shortcode("blob_cat-2"));
+        assert!(!is_shortcode(""));
+        assert!(!is_shortcode("two words"));
+        assert!(!is_shortcode("colon:"));
+    }
+
+    #[test]
+    fn test_emoji_map() {
+        let reaction = event(
+            ":soapbox:",
+            vec![
+                Tag::new(&["emoji", "soapbox", SOAPBOX]),
+                Tag::new(&["emoji", "no url"]),
+                Tag::new(&["emoji", "bad code", GLEASONATOR]),
+                Tag::new(&["t", "soapbox"]),
+            ],
+        );
+        let map = emoji_map(&reaction);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("soapbox").map(|s| s.as_str()), Some(SOAPBOX));
+
+        assert_eq!(
+            reaction_emoji(&reaction),
+            Some(("soapbox".to_owned(), SOAPBOX.to_owned()))
+        );
+        assert_eq!(reaction_emoji(&event("+", vec![])), None);
+    }
+}
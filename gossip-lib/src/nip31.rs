@@ -0,0 +1,11 @@
+use nostr_types::Event;
+
+/// What an event is about, in words, from its `alt` tag (NIP-31). Clients
+/// show this for kinds they do not know how to render.
+pub fn alt_of(event: &Event) -> Option<&str> {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.tagname() == "alt" && !tag.value().trim().is_empty())
+        .map(|tag| tag.value())
+}
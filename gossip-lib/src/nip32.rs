@@ -0,0 +1,243 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use nostr_types::{Event, EventKind, Id, PublicKey, Tag};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The namespace of labels that do not declare one
+pub const DEFAULT_NAMESPACE: &str = "ugc";
+
+/// A label (NIP-32)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub namespace: String,
+    pub value: String,
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.value)
+    }
+}
+
+impl Label {
+    /// A label from `namespace:value`, or from just a value in the default
+    /// namespace
+    pub fn parse(text: &str) -> Option<Label> {
+        let text = text.trim();
+        let (namespace, value) = match text.split_once(':') {
+            Some((namespace, value)) => (namespace.trim(), value.trim()),
+            None => (DEFAULT_NAMESPACE, text),
+        };
+        if namespace.is_empty() || value.is_empty() {
+            return None;
+        }
+        Some(Label {
+            namespace: namespace.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+
+    /// Whether this label matches a `namespace` or `namespace:value` pattern
+    pub fn matches(&self, pattern: &str) -> bool {
+        match pattern.split_once(':') {
+            Some((namespace, value)) => self.namespace == namespace && self.value == value,
+            None => self.namespace == pattern,
+        }
+    }
+}
+
+/// The tags of a label on a person, or on one of their events
+pub fn label_tags(pubkey: PublicKey, id: Option<Id>, label: &Label) -> Vec<Tag> {
+    let mut tags = vec![
+        Tag::new(&["L", &label.namespace]),
+        Tag::new(&["l", &label.value, &label.namespace]),
+    ];
+    if let Some(id) = id {
+        tags.push(Tag::new(&["e", &id.as_hex_string()]));
+    }
+    tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+    tags
+}
+
+/// The labels in the 'l' tags of an event
+pub fn labels_in(event: &Event) -> Vec<Label> {
+    let mut labels: Vec<Label> = Vec::new();
+    for tag in &event.tags {
+        if tag.tagname() == "l" && !tag.value().is_empty() {
+            let namespace = match tag.get_index(2) {
+                "" => DEFAULT_NAMESPACE,
+                ns => ns,
+            };
+            let label = Label {
+                namespace: namespace.to_owned(),
+                value: tag.value().to_owned(),
+            };
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+    labels
+}
+
+/// Labels applied to an event by trusted labelers, including labels the author
+/// put on it themselves
+pub fn labels_of_event(event: &Event) -> Result<Vec<Label>, Error> {
+    labels_of_event_by(event, |pk| GLOBALS.people.is_trusted(pk))
+}
+
+fn labels_of_event_by<F>(event: &Event, labeler: F) -> Result<Vec<Label>, Error>
+where
+    F: Fn(&PublicKey) -> bool,
+{
+    let mut labels: Vec<Label> = if event.kind == EventKind::Label {
+        vec![]
+    } else {
+        labels_in(event)
+    };
+    for (label_id, rel) in GLOBALS.storage.find_relationships_by_id(event.id)? {
+        if !matches!(rel, RelationshipById::Labels { .. }) {
+            continue;
+        }
+        if let Some(label_event) = GLOBALS.storage.read_event(label_id)? {
+            if labeler(&label_event.pubkey) {
+                for label in labels_in(&label_event) {
+                    if !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+            }
+        }
+    }
+    Ok(labels)
+}
+
+/// Labels applied to a person by trusted labelers
+pub fn labels_of_person(pubkey: PublicKey) -> Result<Vec<Label>, Error> {
+    let mut labels: Vec<Label> = Vec::new();
+    for label_event in GLOBALS.storage.find_events_tagging(
+        &[EventKind::Label],
+        &GLOBALS.people.get_trusted_pubkeys()?,
+        None,
+        &[pubkey],
+        |_| true,
+        false,
+    )? {
+        for label in labels_in(&label_event) {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+    Ok(labels)
+}
+
+/// Labels of an event and of its author
+pub fn all_labels(event: &Event) -> Vec<Label> {
+    let mut labels = labels_of_event(event).unwrap_or_default();
+    for label in labels_of_person(event.pubkey).unwrap_or_default() {
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    labels
+}
+
+/// The `exclude_labels` and `include_labels` settings, with what trusted
+/// labelers said about people, gathered once for checking many events (as in a
+/// feed recompute). These are comma separated lists of `namespace` or
+/// `namespace:value` patterns. Events with a label matching an excluded pattern
+/// are dropped. If any patterns are included, only events with a label matching
+/// one of them are kept.
+#[derive(Debug, Default)]
+pub struct LabelFilter {
+    exclude: Vec<String>,
+    include: Vec<String>,
+
+    /// Us and the people we follow
+    labelers: HashSet<PublicKey>,
+
+    /// The labels that labelers put on each person
+    person_labels: HashMap<PublicKey, Vec<Label>>,
+}
+
+impl LabelFilter {
+    /// Read the settings, and if they filter anything, the labels that trusted
+    /// labelers put on people
+    pub fn new() -> Result<LabelFilter, Error> {
+        let mut filter = LabelFilter {
+            exclude: patterns(&GLOBALS.storage.read_setting_exclude_labels()),
+            include: patterns(&GLOBALS.storage.read_setting_include_labels()),
+            ..Default::default()
+        };
+        if filter.is_empty() {
+            return Ok(filter);
+        }
+
+        let labelers = GLOBALS.people.get_trusted_pubkeys()?;
+        for label_event in
+            GLOBALS
+                .storage
+                .find_events(&[EventKind::Label], &labelers, None, |_| true, false)?
+        {
+            let labels = labels_in(&label_event);
+            for tag in &label_event.tags {
+                if let Ok((pk, _, _)) = tag.parse_pubkey() {
+                    let person_labels = filter.person_labels.entry(pk).or_default();
+                    for label in &labels {
+                        if !person_labels.contains(label) {
+                            person_labels.push(label.clone());
+                        }
+                    }
+                }
+            }
+        }
+        filter.labelers = labelers.into_iter().collect();
+
+        Ok(filter)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.exclude.is_empty() && self.include.is_empty()
+    }
+
+    /// Whether an event passes the filter
+    pub fn passes(&self, event: &Event) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let mut labels =
+            labels_of_event_by(event, |pk| self.labelers.contains(pk)).unwrap_or_default();
+        if let Some(person_labels) = self.person_labels.get(&event.pubkey) {
+            for label in person_labels {
+                if !labels.contains(label) {
+                    labels.push(label.clone());
+                }
+            }
+        }
+
+        if labels
+            .iter()
+            .any(|l| self.exclude.iter().any(|p| l.matches(p)))
+        {
+            return false;
+        }
+
+        self.include.is_empty()
+            || labels
+                .iter()
+                .any(|l| self.include.iter().any(|p| l.matches(p)))
+    }
+}
+
+fn patterns(setting: &str) -> Vec<String> {
+    setting
+        .split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_owned())
+        .collect()
+}
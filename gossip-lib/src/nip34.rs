@@ -0,0 +1,151 @@
+use nostr_types::{Event, EventAddr, EventKind, PublicKey, Tag};
+
+/// Git repository announcement
+pub const KIND_REPO_ANNOUNCEMENT: u32 = 30617;
+
+/// Git patch
+pub const KIND_PATCH: u32 = 1617;
+
+/// Git issue
+pub const KIND_ISSUE: u32 = 1621;
+
+/// All the git collaboration event kinds
+pub fn git_event_kinds() -> Vec<EventKind> {
+    vec![
+        EventKind::from(KIND_REPO_ANNOUNCEMENT),
+        EventKind::from(KIND_PATCH),
+        EventKind::from(KIND_ISSUE),
+    ]
+}
+
+pub fn is_git_kind(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_REPO_ANNOUNCEMENT || k == KIND_PATCH || k == KIND_ISSUE
+}
+
+pub fn is_repo_announcement(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_REPO_ANNOUNCEMENT
+}
+
+pub fn is_patch(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_PATCH
+}
+
+pub fn is_issue(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_ISSUE
+}
+
+/// A git repository announcement (kind 30617)
+#[derive(Debug, Clone)]
+pub struct RepoAnnouncement {
+    pub addr: EventAddr,
+    pub name: String,
+    pub description: Option<String>,
+    pub web: Vec<String>,
+    pub clone: Vec<String>,
+    pub maintainers: Vec<PublicKey>,
+}
+
+impl RepoAnnouncement {
+    pub fn from_event(event: &Event) -> Option<RepoAnnouncement> {
+        if !is_repo_announcement(event.kind) {
+            return None;
+        }
+
+        let mut d = String::new();
+        let mut name: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut web: Vec<String> = Vec::new();
+        let mut clone: Vec<String> = Vec::new();
+        let mut maintainers: Vec<PublicKey> = Vec::new();
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "name" => name = Some(tag.value().to_owned()),
+                "description" => description = Some(tag.value().to_owned()),
+                // These tags may list several values
+                "web" => web.extend(tag_values(tag)),
+                "clone" => clone.extend(tag_values(tag)),
+                "maintainers" => {
+                    for pkstr in tag_values(tag).iter() {
+                        if let Ok(pk) = PublicKey::try_from_hex_string(pkstr, true) {
+                            maintainers.push(pk);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let name = name.unwrap_or_else(|| d.clone());
+
+        Some(RepoAnnouncement {
+            addr: EventAddr {
+                d,
+                relays: vec![],
+                kind: event.kind,
+                author: event.pubkey,
+            },
+            name,
+            description,
+            web,
+            clone,
+            maintainers,
+        })
+    }
+
+    /// Render the announcement as plain text, for display
+    pub fn as_text(&self) -> String {
+        let mut output = format!("Git repository: {}", self.name);
+        if let Some(description) = &self.description {
+            output.push_str("\n\n");
+            output.push_str(description);
+        }
+        for url in &self.web {
+            output.push_str(&format!("\n\nweb: {}", url));
+        }
+        for url in &self.clone {
+            output.push_str(&format!("\nclone: {}", url));
+        }
+        output
+    }
+}
+
+// All the values of a tag after the tagname
+fn tag_values(tag: &Tag) -> Vec<String> {
+    (1..)
+        .map(|i| tag.get_index(i))
+        .take_while(|v| !v.is_empty())
+        .map(|v| v.to_owned())
+        .collect()
+}
+
+/// If the event is a patch or issue against a repository, get the repository address
+pub fn repo_of_event(event: &Event) -> Option<EventAddr> {
+    for tag in &event.tags {
+        if let Ok((ea, _)) = tag.parse_address() {
+            if is_repo_announcement(ea.kind) {
+                return Some(ea);
+            }
+        }
+    }
+    None
+}
+
+/// Get the subject of a patch made with `git format-patch`
+pub fn patch_subject(content: &str) -> Option<String> {
+    for line in content.lines() {
+        if let Some(subject) = line.strip_prefix("Subject: ") {
+            return Some(subject.trim().to_owned());
+        }
+        // headers are over at the first blank line
+        if line.is_empty() {
+            break;
+        }
+    }
+    None
+}
@@ -0,0 +1,105 @@
+use nostr_types::{Event, EventKind};
+
+/// Torrent announcement
+pub const KIND_TORRENT: u32 = 2003;
+
+pub fn torrent_kind() -> EventKind {
+    EventKind::from(KIND_TORRENT)
+}
+
+pub fn is_torrent(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_TORRENT
+}
+
+/// A file within a torrent
+#[derive(Debug, Clone)]
+pub struct TorrentFile {
+    pub name: String,
+    pub size: Option<u64>,
+}
+
+/// A torrent announcement (kind 2003)
+#[derive(Debug, Clone)]
+pub struct Torrent {
+    pub title: String,
+    /// The BitTorrent info hash ('x' tag)
+    pub info_hash: String,
+    pub files: Vec<TorrentFile>,
+    pub trackers: Vec<String>,
+}
+
+impl Torrent {
+    pub fn from_event(event: &Event) -> Option<Torrent> {
+        if !is_torrent(event.kind) {
+            return None;
+        }
+
+        let mut title = String::new();
+        let mut info_hash = String::new();
+        let mut files: Vec<TorrentFile> = Vec::new();
+        let mut trackers: Vec<String> = Vec::new();
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "title" => title = tag.value().to_owned(),
+                "x" => info_hash = tag.value().to_owned(),
+                "file" => files.push(TorrentFile {
+                    name: tag.value().to_owned(),
+                    size: tag.get_index(2).parse::<u64>().ok(),
+                }),
+                "tracker" => trackers.push(tag.value().to_owned()),
+                _ => (),
+            }
+        }
+
+        // Without an info hash there is nothing to download
+        if info_hash.is_empty() {
+            return None;
+        }
+
+        Some(Torrent {
+            title,
+            info_hash,
+            files,
+            trackers,
+        })
+    }
+
+    /// The sum of the sizes of all files that list a size
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().filter_map(|f| f.size).sum()
+    }
+
+    /// A magnet link for this torrent
+    pub fn magnet_link(&self) -> String {
+        let mut link = format!("magnet:?xt=urn:btih:{}", self.info_hash);
+        if !self.title.is_empty() {
+            link.push_str(&format!("&dn={}", encode(&self.title)));
+        }
+        for tracker in &self.trackers {
+            link.push_str(&format!("&tr={}", encode(tracker)));
+        }
+        link
+    }
+}
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+/// Format a byte count for display
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
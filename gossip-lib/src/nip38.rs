@@ -0,0 +1,116 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+
+/// The kinds of user statuses we render, in display order
+pub const STATUS_TYPES: [&str; 2] = ["general", "music"];
+
+/// Statuses without an expiration are considered stale after this long
+const MAX_AGE_WITHOUT_EXPIRATION_SECS: i64 = 60 * 60 * 24 * 7;
+
+/// A user status (NIP-38, kind 30315)
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserStatus {
+    /// The type of status ('d' tag), e.g. "general" or "music"
+    pub status_type: String,
+
+    pub content: String,
+
+    /// An optional link ('r' tag)
+    pub link: Option<String>,
+
+    pub created_at: Unixtime,
+
+    pub expiration: Option<Unixtime>,
+}
+
+impl UserStatus {
+    pub fn from_event(event: &Event) -> Option<UserStatus> {
+        if event.kind != EventKind::UserStatus {
+            return None;
+        }
+
+        let mut status_type = String::new();
+        let mut link: Option<String> = None;
+        let mut expiration: Option<Unixtime> = None;
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => status_type = tag.value().to_owned(),
+                "r" => link = Some(tag.value().to_owned()),
+                "expiration" => {
+                    if let Ok(secs) = tag.value().parse::<i64>() {
+                        expiration = Some(Unixtime(secs));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Some(UserStatus {
+            status_type,
+            content: event.content.trim().to_owned(),
+            link,
+            created_at: event.created_at,
+            expiration,
+        })
+    }
+
+    /// A status is fresh if it is not empty (cleared) and not expired
+    pub fn is_fresh(&self) -> bool {
+        if self.content.is_empty() {
+            return false;
+        }
+        let now = Unixtime::now().unwrap();
+        match self.expiration {
+            Some(expiration) => expiration > now,
+            None => self.created_at.0 + MAX_AGE_WITHOUT_EXPIRATION_SECS > now.0,
+        }
+    }
+
+    /// A short line for display
+    pub fn display_line(&self) -> String {
+        match self.status_type.as_str() {
+            "music" => format!("♫ {}", self.content),
+            _ => self.content.clone(),
+        }
+    }
+}
+
+/// Get the fresh statuses of these people, most recently set first
+pub fn get_statuses_of(pubkeys: &[PublicKey]) -> Result<Vec<(PublicKey, UserStatus)>, Error> {
+    if pubkeys.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut output: Vec<(PublicKey, UserStatus)> = GLOBALS
+        .storage
+        .find_events(&[EventKind::UserStatus], pubkeys, None, |_| true, false)?
+        .iter()
+        .filter_map(|event| {
+            UserStatus::from_event(event)
+                .filter(|s| s.is_fresh())
+                .map(|s| (event.pubkey, s))
+        })
+        .collect();
+    output.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at));
+    Ok(output)
+}
+
+/// Get the fresh statuses of a person, in display order
+pub fn get_user_statuses(pubkey: PublicKey) -> Result<Vec<UserStatus>, Error> {
+    let mut output: Vec<UserStatus> = Vec::new();
+    for status_type in STATUS_TYPES.iter() {
+        if let Some(event) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(EventKind::UserStatus, pubkey, status_type)?
+        {
+            if let Some(status) = UserStatus::from_event(&event) {
+                if status.is_fresh() {
+                    output.push(status);
+                }
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,229 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, UncheckedUrl, Url};
+use std::time::Duration;
+
+/// How long a proof we fetched stays good before we check it again
+const PROOF_BECOMES_STALE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// An account elsewhere that somebody says is theirs (NIP-39), from an `i` tag
+/// of their metadata
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdentityClaim {
+    /// Such as "github", "twitter", "mastodon" or "telegram"
+    pub platform: String,
+
+    /// Their name on that platform. For mastodon this includes the instance,
+    /// e.g. "bitcoinhackers.org/@semisol"
+    pub identity: String,
+
+    /// Where on the platform they posted their npub, in the platform's terms
+    pub proof: String,
+}
+
+/// How checking an identity claim went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityCheck {
+    /// We are still fetching the proof
+    Pending,
+
+    /// The proof names their npub
+    Verified,
+
+    /// The proof could not be fetched, or does not name their npub
+    Failed,
+
+    /// We have no way to check claims on this platform
+    Unverifiable,
+}
+
+impl IdentityClaim {
+    fn from_tag_value(value: &str, proof: &str) -> Option<IdentityClaim> {
+        let (platform, identity) = value.split_once(':')?;
+        if platform.is_empty() || identity.is_empty() {
+            return None;
+        }
+        Some(IdentityClaim {
+            platform: platform.to_lowercase(),
+            identity: identity.to_owned(),
+            proof: proof.to_owned(),
+        })
+    }
+
+    /// Their profile on the platform
+    pub fn profile_url(&self) -> Option<String> {
+        match self.platform.as_str() {
+            "github" => Some(format!("https://github.com/{}", self.identity)),
+            "twitter" => Some(format!("https://twitter.com/{}", self.identity)),
+            "mastodon" => {
+                let (instance, user) = self.identity.split_once('/')?;
+                Some(format!("https://{}/{}", instance, user))
+            }
+            "telegram" => Some(format!("https://t.me/{}", self.identity)),
+            _ => None,
+        }
+    }
+
+    /// The post with their npub in it, for people to look at
+    pub fn proof_url(&self) -> Option<String> {
+        match self.platform.as_str() {
+            "github" => Some(format!(
+                "https://gist.github.com/{}/{}",
+                self.identity, self.proof
+            )),
+            "twitter" => Some(format!(
+                "https://twitter.com/{}/status/{}",
+                self.identity, self.proof
+            )),
+            "mastodon" => {
+                let (instance, user) = self.identity.split_once('/')?;
+                Some(format!("https://{}/{}/{}", instance, user, self.proof))
+            }
+            "telegram" => Some(format!("https://t.me/{}", self.proof)),
+            _ => None,
+        }
+    }
+
+    // Where to fetch the proof from to check it. Only an API that says who
+    // posted the proof will do, since anybody can post somebody else's npub.
+    // Twitter only shows posts to browsers running its javascript, and a
+    // telegram post's page does not reliably say whose channel it is in, so
+    // those can't be checked.
+    fn check_url(&self) -> Option<String> {
+        match self.platform.as_str() {
+            "github" => Some(format!("https://api.github.com/gists/{}", self.proof)),
+            "mastodon" => {
+                let (instance, _) = self.identity.split_once('/')?;
+                Some(format!(
+                    "https://{}/api/v1/statuses/{}",
+                    instance, self.proof
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    // The cache key of the outcome of checking this claim
+    fn key(&self) -> String {
+        format!("{}:{}:{}", self.platform, self.identity, self.proof)
+    }
+}
+
+/// The identity claims in a metadata event
+pub fn claims_of_event(event: &Event) -> Vec<IdentityClaim> {
+    if event.kind != EventKind::Metadata {
+        return vec![];
+    }
+    event
+        .tags
+        .iter()
+        .filter(|tag| tag.tagname() == "i")
+        .filter_map(|tag| IdentityClaim::from_tag_value(tag.value(), tag.get_index(2)))
+        .collect()
+}
+
+/// The identity claims of a person, from the latest metadata of theirs we
+/// have, each with how checking it went so far. This starts checking any
+/// that are not yet checked, so call it again later for the outcome.
+pub fn identities(pubkey: PublicKey) -> Result<Vec<(IdentityClaim, IdentityCheck)>, Error> {
+    let event = match GLOBALS
+        .storage
+        .get_replaceable_event(EventKind::Metadata, pubkey, "")?
+    {
+        Some(event) => event,
+        None => return Ok(vec![]),
+    };
+
+    Ok(claims_of_event(&event)
+        .drain(..)
+        .map(|claim| {
+            let check = check(pubkey, &claim);
+            (claim, check)
+        })
+        .collect())
+}
+
+fn check(pubkey: PublicKey, claim: &IdentityClaim) -> IdentityCheck {
+    let key = (pubkey, claim.key());
+    if let Some(check) = GLOBALS.people.identity_checks.get(&key) {
+        return *check;
+    }
+
+    let url = match claim
+        .check_url()
+        .and_then(|u| Url::try_from_unchecked_url(&UncheckedUrl(u)).ok())
+    {
+        Some(url) => url,
+        None => {
+            GLOBALS
+                .people
+                .identity_checks
+                .insert(key, IdentityCheck::Unverifiable);
+            return IdentityCheck::Unverifiable;
+        }
+    };
+
+    let check = match GLOBALS.fetcher.try_get(&url, PROOF_BECOMES_STALE) {
+        Ok(None) => return IdentityCheck::Pending,
+        Ok(Some(bytes)) => {
+            if proof_is_good(pubkey, claim, &bytes) {
+                IdentityCheck::Verified
+            } else {
+                IdentityCheck::Failed
+            }
+        }
+        Err(_) => IdentityCheck::Failed,
+    };
+    GLOBALS.people.identity_checks.insert(key, check);
+    check
+}
+
+// Whether a fetched proof names their npub, and is by the account they claim
+fn proof_is_good(pubkey: PublicKey, claim: &IdentityClaim, bytes: &[u8]) -> bool {
+    let npub = pubkey.as_bech32_string();
+    if claim.platform == "github" {
+        #[derive(serde::Deserialize)]
+        struct Owner {
+            login: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct File {
+            #[serde(default)]
+            content: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct Gist {
+            owner: Owner,
+            files: std::collections::HashMap<String, File>,
+        }
+        return match serde_json::from_slice::<Gist>(bytes) {
+            Ok(gist) => {
+                gist.owner.login.eq_ignore_ascii_case(&claim.identity)
+                    && gist.files.values().any(|f| f.content.contains(&npub))
+            }
+            Err(_) => false,
+        };
+    }
+    if claim.platform == "mastodon" {
+        #[derive(serde::Deserialize)]
+        struct Account {
+            url: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct Status {
+            content: String,
+            account: Account,
+        }
+        return match serde_json::from_slice::<Status>(bytes) {
+            Ok(status) => {
+                status.content.contains(&npub)
+                    && claim
+                        .profile_url()
+                        .map(|u| status.account.url.eq_ignore_ascii_case(&u))
+                        .unwrap_or(false)
+            }
+            Err(_) => false,
+        };
+    }
+    false
+}
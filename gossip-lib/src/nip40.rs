@@ -0,0 +1,54 @@
+use crate::globals::GLOBALS;
+use nostr_types::{Event, Tag, Unixtime};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// How often expired events are removed from storage. This looks through every
+/// event, so it is not done often.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The time an event expires at, if it has an 'expiration' tag (NIP-40)
+pub fn expiration(event: &Event) -> Option<Unixtime> {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.tagname() == "expiration")
+        .and_then(|tag| tag.value().parse::<i64>().ok())
+        .map(Unixtime)
+}
+
+/// Whether an event has expired as of `now`. Expired events should not be shown,
+/// kept, or sent to relays.
+pub fn is_expired(event: &Event, now: Unixtime) -> bool {
+    match expiration(event) {
+        Some(expiration) => expiration <= now,
+        None => false,
+    }
+}
+
+/// An 'expiration' tag for an event that expires at the given time
+pub fn expiration_tag(expiration: Unixtime) -> Tag {
+    Tag::new(&["expiration", &format!("{}", expiration.0)])
+}
+
+/// Start the periodic task that removes expired events from storage
+pub(crate) fn start() {
+    tokio::task::spawn(async {
+        loop {
+            tokio::time::sleep(PURGE_INTERVAL).await;
+
+            if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let purge = tokio::task::spawn_blocking(|| {
+                GLOBALS.storage.prune_expired(Unixtime::now().unwrap())
+            });
+            match purge.await {
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => tracing::error!("Purging expired events: {}", e),
+                Err(e) => tracing::error!("Purging expired events: {}", e),
+            }
+        }
+    });
+}
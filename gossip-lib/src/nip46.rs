@@ -1,11 +1,24 @@
 use crate::comms::ToOverlordMessage;
 use crate::globals::GLOBALS;
 use crate::{Error, ErrorKind};
+use dashmap::DashMap;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
 use nostr_types::{
-    ContentEncryptionAlgorithm, Event, EventKind, PreEvent, PublicKey, RelayUrl, Tag, Unixtime,
+    ClientMessage, ContentEncryptionAlgorithm, EncryptedPrivateKey, Event, EventKind, Filter,
+    Identity, PreEvent, PublicKey, PublicKeyHex, RelayMessage, RelayUrl, SubscriptionId, Tag,
+    Unixtime,
 };
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use speedy::{Readable, Writable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tungstenite::protocol::Message as WsMessage;
 
 #[derive(Debug, Clone, Readable, Writable, Serialize, Deserialize)]
 pub struct Nip46ClientMetadata {
@@ -169,6 +182,7 @@ impl Nip46Server {
             ref id,
             ref method,
             ref params,
+            nip44,
         } = cmd;
 
         let result: Result<String, Error> = match method.as_str() {
@@ -228,19 +242,35 @@ impl Nip46Server {
                 "".to_owned(),
                 self.peer_pubkey,
                 self.relays.clone(),
+                *nip44,
             )?,
+            // This gets answered once the user decides
+            Err(e) if matches!(e.kind, ErrorKind::Nip46NeedApproval) => return Err(e),
             Err(e) => send_response(
                 id.to_owned(),
                 "".to_owned(),
                 format!("{}", e),
                 self.peer_pubkey,
                 self.relays.clone(),
+                *nip44,
             )?,
         }
 
         Ok(())
     }
 
+    /// Answer a command that the user declined
+    pub fn decline(&self, cmd: &ParsedCommand) -> Result<(), Error> {
+        send_response(
+            cmd.id.clone(),
+            "".to_owned(),
+            "The user declined this request".to_owned(),
+            self.peer_pubkey,
+            self.relays.clone(),
+            cmd.nip44,
+        )
+    }
+
     fn get_public_key(&self) -> Result<String, Error> {
         if let Some(pk) = GLOBALS.identity.public_key() {
             Ok(pk.as_hex_string())
@@ -377,10 +407,51 @@ pub struct ParsedCommand {
     pub id: String,
     pub method: String,
     pub params: Vec<String>,
+
+    /// Whether the command came NIP-44 encrypted (else NIP-04), so that we
+    /// answer in kind
+    pub nip44: bool,
+}
+
+impl ParsedCommand {
+    /// What the command asks for, for the user to approve
+    pub fn describe(&self) -> String {
+        match self.method.as_str() {
+            "sign_event" => {
+                match self
+                    .params
+                    .first()
+                    .and_then(|p| serde_json::from_str::<Nip46PreEvent>(p).ok())
+                {
+                    Some(pre_event) => {
+                        format!("sign an event of kind {}", u32::from(pre_event.kind))
+                    }
+                    None => "sign an event".to_owned(),
+                }
+            }
+            "nip04_encrypt" | "nip44_encrypt" => "encrypt a message".to_owned(),
+            "nip04_decrypt" | "nip44_decrypt" => "decrypt a message".to_owned(),
+            "nip44_get_key" => "get a conversation key (to encrypt and decrypt)".to_owned(),
+            other => other.to_owned(),
+        }
+    }
+}
+
+// Commands and answers are NIP-44 encrypted, or by older clients NIP-04
+fn is_nip44(contents: &str) -> bool {
+    !contents.contains("?iv=")
 }
 
 fn parse_command(peer_pubkey: PublicKey, contents: &str) -> Result<ParsedCommand, Error> {
-    let bytes = GLOBALS.identity.decrypt_nip04(&peer_pubkey, contents)?;
+    let nip44 = is_nip44(contents);
+    let bytes = if nip44 {
+        GLOBALS
+            .identity
+            .decrypt_nip44(&peer_pubkey, contents)?
+            .into_bytes()
+    } else {
+        GLOBALS.identity.decrypt_nip04(&peer_pubkey, contents)?
+    };
 
     let json: serde_json::Value = serde_json::from_slice(&bytes)?;
 
@@ -429,7 +500,12 @@ fn parse_command(peer_pubkey: PublicKey, contents: &str) -> Result<ParsedCommand
                         }
                     }
                 }
-                Ok(ParsedCommand { id, method, params })
+                Ok(ParsedCommand {
+                    id,
+                    method,
+                    params,
+                    nip44,
+                })
             }
             None => Err(ErrorKind::Nip46ParsingError(id, "params not an array".to_owned()).into()),
         },
@@ -443,6 +519,7 @@ fn send_response(
     error: String,
     peer_pubkey: PublicKey,
     relays: Vec<RelayUrl>,
+    nip44: bool,
 ) -> Result<(), Error> {
     use serde_json::json;
 
@@ -458,9 +535,12 @@ fn send_response(
     });
     let s = output.to_string();
 
-    let e = GLOBALS
-        .identity
-        .encrypt(&peer_pubkey, &s, ContentEncryptionAlgorithm::Nip04)?;
+    let algo = if nip44 {
+        ContentEncryptionAlgorithm::Nip44v2
+    } else {
+        ContentEncryptionAlgorithm::Nip04
+    };
+    let e = GLOBALS.identity.encrypt(&peer_pubkey, &s, algo)?;
 
     let pre_event = PreEvent {
         pubkey: public_key,
@@ -480,6 +560,8 @@ fn send_response(
 }
 
 pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Error> {
+    let nip44 = is_nip44(&event.content);
+
     // If we have a server for that pubkey
     if let Some(mut server) = GLOBALS.storage.read_nip46server(event.pubkey)? {
         // Parse the command
@@ -494,6 +576,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
                         msg.clone(),
                         event.pubkey,
                         server.relays.clone(),
+                        nip44,
                     )?;
                 }
 
@@ -537,6 +620,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
                     msg.clone(),
                     event.pubkey,
                     vec![seen_on_relay],
+                    nip44,
                 )?;
             }
 
@@ -545,7 +629,9 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
         }
     };
 
-    let ParsedCommand { id, method, params } = parsed_command;
+    let ParsedCommand {
+        id, method, params, ..
+    } = parsed_command;
 
     // Do we have a waiiting unconnected server?
     let userver = match GLOBALS.storage.read_nip46_unconnected_server()? {
@@ -558,6 +644,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
                 "Gossip is not configured to receive a connection".to_string(),
                 event.pubkey,
                 vec![seen_on_relay],
+                nip44,
             )?;
             return Ok(()); // no need to pass back error
         }
@@ -576,17 +663,21 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
             "Your pubkey is not configured for nostr connect here.".to_string(),
             event.pubkey,
             reply_relays,
+            nip44,
         )?;
         return Ok(()); // no need to pass back error
     }
 
-    if params.len() != 2 {
+    // The third, optional, parameter is the permissions the client would
+    // like. We ask the user as each kind of request comes in instead.
+    if params.len() != 2 && params.len() != 3 {
         send_response(
             id.clone(),
             "".to_owned(),
             "connect requires two parameters".to_string(),
             event.pubkey,
             reply_relays,
+            nip44,
         )?;
         return Ok(()); // no need to pass back error
     }
@@ -600,6 +691,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
                 "connect requires two parameters".to_string(),
                 event.pubkey,
                 reply_relays,
+                nip44,
             )?;
             return Err(ErrorKind::NoPublicKey.into());
         }
@@ -613,6 +705,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
             "Gossip is not configured to sign with the requested public key".to_string(),
             event.pubkey,
             reply_relays,
+            nip44,
         )?;
         return Ok(()); // no need to pass back error
     }
@@ -624,6 +717,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
             "Incorrect secret.".to_string(),
             event.pubkey,
             reply_relays,
+            nip44,
         )?;
         return Ok(()); // no need to pass back error
     }
@@ -653,7 +747,559 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
         "".to_owned(),
         event.pubkey,
         reply_relays,
+        nip44,
     )?;
 
     Ok(())
 }
+
+/// How long we wait for the remote signer to answer a request
+const BUNKER_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long we wait once the remote signer has asked the user to approve a
+/// request on its auth_url
+const BUNKER_AUTH_URL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A remote signer (bunker) that we sign with, so that the user's private key
+/// never lives on this machine
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct Nip46Bunker {
+    /// The key the bunker talks to us with. This is not necessarily the user's.
+    pub remote_pubkey: PublicKey,
+
+    /// The relays the bunker listens on, in the order we try them
+    pub relays: Vec<RelayUrl>,
+
+    // Our own key for talking to the bunker, and its ncryptsec. This is kept
+    // under the user's passphrase and unlocked with it at login, as their
+    // private key would be. It cannot be encrypted to the user's
+    // key, since with a bunker that key is not here. What the key gets
+    // somebody is what a running gossip gets: they can ask the bunker to
+    // sign, encrypt and decrypt, under whatever approval policy the user set
+    // there. The user revokes it by disconnecting the client in the bunker.
+    client_pubkey: PublicKey,
+    client_key: String,
+}
+
+impl Nip46Bunker {
+    /// Keep our key for the bunker under a new passphrase
+    pub(crate) fn change_passphrase(&mut self, old: &str, new: &str) -> Result<(), Error> {
+        let mut client = Identity::from_locked_parts(
+            self.client_pubkey,
+            EncryptedPrivateKey(self.client_key.clone()),
+        );
+        client.unlock(old)?;
+        let log_n = GLOBALS.storage.read_setting_log_n();
+        client.change_passphrase(old, new, log_n)?;
+        match client.encrypted_private_key() {
+            Some(epk) => self.client_key = epk.0.clone(),
+            None => return Err(ErrorKind::NoPrivateKey.into()),
+        }
+        Ok(())
+    }
+}
+
+/// A connection to a remote signer, ready to make requests of it
+pub struct Nip46BunkerClient {
+    pub bunker: Nip46Bunker,
+    link: Arc<SignerLink>,
+
+    // The way to the task that keeps our connection to the bunker. It is
+    // started with the first request, and stops when we are dropped.
+    requests: Mutex<Option<mpsc::UnboundedSender<SignerRequest>>>,
+
+    // Decryptions we have already had the bunker do, by ciphertext
+    decrypted: DashMap<String, String>,
+}
+
+// A request for the connection task, and where its answer goes
+struct SignerRequest {
+    method: &'static str,
+    params: Vec<String>,
+    reply: oneshot::Sender<Result<String, Error>>,
+}
+
+// A request sent to the bunker that it has not answered yet
+struct PendingRequest {
+    event: Event,
+    deadline: Instant,
+    reply: oneshot::Sender<Result<String, Error>>,
+}
+
+// What the connection task needs: where the bunker is, and our key for it
+struct SignerLink {
+    bunker: Nip46Bunker,
+    client: Identity,
+}
+
+impl Nip46BunkerClient {
+    /// Pair with a remote signer given its bunker URI
+    /// (bunker://<remote-pubkey-hex>?relay=wss://...&secret=...)
+    ///
+    /// Our key for the bunker is kept under the passphrase `pass`.
+    ///
+    /// Returns the client and the user's public key
+    pub(crate) async fn pair(
+        uri: &str,
+        pass: &str,
+    ) -> Result<(Nip46BunkerClient, PublicKey), Error> {
+        let url = url::Url::parse(uri.trim()).map_err(|_| ErrorKind::BadNostrConnectString)?;
+        if url.scheme() != "bunker" {
+            return Err(ErrorKind::BadNostrConnectString.into());
+        }
+        let remote_pubkey = match url.host_str() {
+            Some(hex) => PublicKey::try_from_hex_string(hex, true)?,
+            None => return Err(ErrorKind::BadNostrConnectString.into()),
+        };
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret = String::new();
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => {
+                    if let Ok(relay) = RelayUrl::try_from_str(&value) {
+                        if !relays.contains(&relay) {
+                            relays.push(relay);
+                        }
+                    }
+                }
+                "secret" => secret = value.into_owned(),
+                _ => (),
+            }
+        }
+        if relays.is_empty() {
+            return Err(ErrorKind::BadNostrConnectString.into());
+        }
+
+        let log_n = GLOBALS.storage.read_setting_log_n();
+        let client = Identity::generate(pass, log_n)?;
+        let client_key = match client.encrypted_private_key() {
+            Some(epk) => epk.0.clone(),
+            None => return Err(ErrorKind::NoPrivateKey.into()),
+        };
+        let client_pubkey = match client.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+
+        let bunker_client = Self::new(
+            Nip46Bunker {
+                remote_pubkey,
+                relays,
+                client_pubkey,
+                client_key,
+            },
+            client,
+        );
+
+        let answer = bunker_client
+            .request(
+                "connect",
+                vec![remote_pubkey.as_hex_string(), secret.clone()],
+            )
+            .await?;
+        // Bunkers answer "ack", or lately the secret we gave them
+        if answer != "ack" && answer != secret {
+            return Err(
+                ErrorKind::Nip46Remote(format!("unexpected answer to connect: {answer}")).into(),
+            );
+        }
+
+        let answer = bunker_client.request("get_public_key", vec![]).await?;
+        let pubkey = PublicKey::try_from_hex_string(&answer, true)?;
+
+        Ok((bunker_client, pubkey))
+    }
+
+    /// Get back a client for a bunker we paired with before, unlocking our key
+    /// for it with the passphrase `pass`
+    pub(crate) fn load(bunker: Nip46Bunker, pass: &str) -> Result<Nip46BunkerClient, Error> {
+        let mut client = Identity::from_locked_parts(
+            bunker.client_pubkey,
+            EncryptedPrivateKey(bunker.client_key.clone()),
+        );
+        client.unlock(pass)?;
+
+        Ok(Self::new(bunker, client))
+    }
+
+    fn new(bunker: Nip46Bunker, client: Identity) -> Nip46BunkerClient {
+        Nip46BunkerClient {
+            link: Arc::new(SignerLink {
+                bunker: bunker.clone(),
+                client,
+            }),
+            bunker,
+            requests: Mutex::new(None),
+            decrypted: DashMap::new(),
+        }
+    }
+
+    /// Have the bunker sign an event
+    pub async fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+        let pre_event = serde_json::json!({
+            "kind": input.kind,
+            "content": input.content,
+            "tags": input.tags,
+            "created_at": input.created_at,
+        });
+        let answer = self
+            .request("sign_event", vec![pre_event.to_string()])
+            .await?;
+        let event: Event = serde_json::from_str(&answer)?;
+        if event.pubkey != input.pubkey {
+            return Err(ErrorKind::Nip46Remote("signed with a different key".to_owned()).into());
+        }
+        event.verify(None)?;
+        Ok(event)
+    }
+
+    /// Have the bunker encrypt something to somebody
+    pub async fn encrypt(
+        &self,
+        other: &PublicKey,
+        plaintext: &str,
+        algo: ContentEncryptionAlgorithm,
+    ) -> Result<String, Error> {
+        let method = match algo {
+            ContentEncryptionAlgorithm::Nip04 => "nip04_encrypt",
+            _ => "nip44_encrypt",
+        };
+        self.request(method, vec![other.as_hex_string(), plaintext.to_owned()])
+            .await
+    }
+
+    /// Have the bunker decrypt something from somebody
+    pub async fn decrypt(
+        &self,
+        other: &PublicKey,
+        ciphertext: &str,
+        algo: ContentEncryptionAlgorithm,
+    ) -> Result<String, Error> {
+        if let Some(plaintext) = self.decrypted.get(ciphertext) {
+            return Ok(plaintext.clone());
+        }
+        let method = match algo {
+            ContentEncryptionAlgorithm::Nip04 => "nip04_decrypt",
+            _ => "nip44_decrypt",
+        };
+        let plaintext = self
+            .request(method, vec![other.as_hex_string(), ciphertext.to_owned()])
+            .await?;
+        self.decrypted
+            .insert(ciphertext.to_owned(), plaintext.clone());
+        Ok(plaintext)
+    }
+
+    /// Ask the bunker for the NIP-44 conversation key with somebody. Not every
+    /// bunker will do this.
+    pub async fn nip44_conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
+        let answer = self
+            .request("nip44_get_key", vec![other.as_hex_string()])
+            .await?;
+        let bytes = hex::decode(answer).map_err(|e| ErrorKind::Nip46Remote(format!("{e}")))?;
+        Ok(bytes.as_slice().try_into()?)
+    }
+
+    // Hand the request to the connection task, starting it if need be, and
+    // wait for the answer
+    async fn request(&self, method: &'static str, params: Vec<String>) -> Result<String, Error> {
+        let (reply, answer) = oneshot::channel();
+        let request = SignerRequest {
+            method,
+            params,
+            reply,
+        };
+        {
+            let mut requests = self.requests.lock();
+            let sender = requests.get_or_insert_with(|| {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                std::mem::drop(tokio::spawn(self.link.clone().serve(receiver)));
+                sender
+            });
+            if sender.send(request).is_err() {
+                // The task is gone, so start another next time
+                *requests = None;
+                return Err("The connection to the remote signer has stopped".into());
+            }
+        }
+        answer
+            .await
+            .map_err(|_| Error::from("The connection to the remote signer has stopped"))?
+    }
+}
+
+/// Wait on a request to the remote signer from synchronous code. The request
+/// itself is carried by the signer's connection task; on a runtime worker the
+/// worker's other tasks are handed off while we wait. Never call this while
+/// drawing the UI.
+pub(crate) fn wait<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+impl SignerLink {
+    // Carry requests to the bunker and its answers back, over one connection
+    // kept open on the first relay that works, until nobody can make requests
+    // anymore
+    async fn serve(self: Arc<Self>, mut requests: mpsc::UnboundedReceiver<SignerRequest>) {
+        let relays = &self.bunker.relays;
+        let mut pending: HashMap<String, PendingRequest> = HashMap::new();
+        let mut relay_index: usize = 0;
+        let mut failures: usize = 0;
+
+        loop {
+            // Only connect once there is something to ask
+            if pending.is_empty() {
+                match requests.recv().await {
+                    Some(request) => {
+                        self.prepare(request, &mut pending);
+                    }
+                    None => return,
+                }
+                continue;
+            }
+
+            if relays.is_empty() {
+                for (_, waiting) in pending.drain() {
+                    let _ = waiting.reply.send(Err(ErrorKind::NoRelay.into()));
+                }
+                continue;
+            }
+
+            let relay = &relays[relay_index % relays.len()];
+            match self
+                .converse(relay, &mut pending, &mut requests, &mut failures)
+                .await
+            {
+                Ok(()) => return,
+                Err(e) if pending.is_empty() => {
+                    // Nothing was lost, so connect again when next asked
+                    tracing::debug!("NIP-46 connection to {} ended: {}", relay, e);
+                }
+                Err(e) => {
+                    tracing::warn!("NIP-46 connection to {} failed: {}", relay, e);
+                    relay_index += 1;
+                    failures += 1;
+                    if failures >= relays.len() {
+                        // Every relay failed us, so give up on what is waiting
+                        for (_, waiting) in pending.drain() {
+                            let _ = waiting
+                                .reply
+                                .send(
+                                    Err(format!("Could not reach the remote signer: {e}").into()),
+                                );
+                        }
+                        failures = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    // Talk to the bunker over the relay until it fails us (an error), or until
+    // nobody can make requests anymore
+    async fn converse(
+        &self,
+        relay: &RelayUrl,
+        pending: &mut HashMap<String, PendingRequest>,
+        requests: &mut mpsc::UnboundedReceiver<SignerRequest>,
+        failures: &mut usize,
+    ) -> Result<(), Error> {
+        let connect_timeout =
+            Duration::from_secs(GLOBALS.storage.read_setting_websocket_connect_timeout_sec());
+        let (mut stream, _) = tokio::time::timeout(
+            connect_timeout,
+            crate::proxy::connect_websocket(relay.as_str(), None),
+        )
+        .await??;
+
+        let filter = {
+            let mut filter = Filter {
+                authors: vec![self.bunker.remote_pubkey.into()],
+                kinds: vec![EventKind::NostrConnect],
+                since: Some(Unixtime(Unixtime::now().unwrap().0 - 10)),
+                ..Default::default()
+            };
+            let pkh: PublicKeyHex = self.bunker.client_pubkey.into();
+            filter.set_tag_values('p', vec![pkh.to_string()]);
+            filter
+        };
+        let req = ClientMessage::Req(SubscriptionId("nip46".to_owned()), vec![filter]);
+        stream
+            .send(WsMessage::Text(serde_json::to_string(&req)?))
+            .await?;
+
+        // Send again whatever the last connection left unanswered
+        for waiting in pending.values() {
+            let msg = ClientMessage::Event(Box::new(waiting.event.clone()));
+            stream
+                .send(WsMessage::Text(serde_json::to_string(&msg)?))
+                .await?;
+        }
+
+        loop {
+            let deadline = pending.values().map(|p| p.deadline).min();
+            tokio::select! {
+                request = requests.recv() => {
+                    let request = match request {
+                        Some(request) => request,
+                        None => {
+                            let _ = stream.close(None).await;
+                            return Ok(());
+                        }
+                    };
+                    if let Some(event) = self.prepare(request, pending) {
+                        let msg = ClientMessage::Event(Box::new(event));
+                        stream
+                            .send(WsMessage::Text(serde_json::to_string(&msg)?))
+                            .await?;
+                    }
+                }
+                message = stream.next() => {
+                    match message {
+                        Some(Ok(WsMessage::Text(text))) => {
+                            self.handle_message(&text, pending, failures)?
+                        }
+                        Some(Ok(_)) => (),
+                        Some(Err(e)) => return Err(e.into()),
+                        None => return Err("The relay closed the connection".into()),
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                    if deadline.is_some() =>
+                {
+                    let now = Instant::now();
+                    let expired: Vec<String> = pending
+                        .iter()
+                        .filter(|(_, p)| p.deadline <= now)
+                        .map(|(id, _)| id.clone())
+                        .collect();
+                    for id in expired {
+                        if let Some(waiting) = pending.remove(&id) {
+                            let _ = waiting
+                                .reply
+                                .send(Err("The remote signer did not answer in time".into()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Make the request into an event for the bunker and note it as waiting.
+    // Returns the event to send, or None if it could not be made (the
+    // requester has been told).
+    fn prepare(
+        &self,
+        request: SignerRequest,
+        pending: &mut HashMap<String, PendingRequest>,
+    ) -> Option<Event> {
+        let id = textnonce::TextNonce::sized_urlsafe(16)
+            .unwrap()
+            .into_string();
+        let command = serde_json::json!({
+            "id": id,
+            "method": request.method,
+            "params": request.params,
+        });
+        let event = self
+            .client
+            .encrypt(
+                &self.bunker.remote_pubkey,
+                &command.to_string(),
+                ContentEncryptionAlgorithm::Nip44v2,
+            )
+            .and_then(|content| {
+                self.client.sign_event(PreEvent {
+                    pubkey: self.bunker.client_pubkey,
+                    created_at: Unixtime::now().unwrap(),
+                    kind: EventKind::NostrConnect,
+                    tags: vec![Tag::new_pubkey(self.bunker.remote_pubkey, None, None)],
+                    content,
+                })
+            });
+        match event {
+            Ok(event) => {
+                pending.insert(
+                    id,
+                    PendingRequest {
+                        event: event.clone(),
+                        deadline: Instant::now() + BUNKER_REQUEST_TIMEOUT,
+                        reply: request.reply,
+                    },
+                );
+                Some(event)
+            }
+            Err(e) => {
+                let _ = request.reply.send(Err(e.into()));
+                None
+            }
+        }
+    }
+
+    // Pass an answer from the bunker back to whoever asked
+    fn handle_message(
+        &self,
+        text: &str,
+        pending: &mut HashMap<String, PendingRequest>,
+        failures: &mut usize,
+    ) -> Result<(), Error> {
+        let event = match serde_json::from_str::<RelayMessage>(text) {
+            Ok(RelayMessage::Event(_, event)) => event,
+            Ok(RelayMessage::Ok(id, false, message))
+                if pending.values().any(|p| p.event.id == id) =>
+            {
+                return Err(format!("The relay refused our request: {message}").into())
+            }
+            _ => return Ok(()),
+        };
+
+        let response: serde_json::Value = match self
+            .decrypt_response(&event)
+            .ok()
+            .and_then(|plaintext| serde_json::from_str(&plaintext).ok())
+        {
+            Some(response) => response,
+            None => return Ok(()),
+        };
+        let id = match response["id"].as_str() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let result = response["result"].as_str().unwrap_or("");
+        let error = response["error"].as_str().unwrap_or("");
+        if result == "auth_url" {
+            // The bunker wants the user to approve this on a web page
+            if let Some(waiting) = pending.get_mut(id) {
+                GLOBALS.status_queue.write().write(format!(
+                    "Your remote signer asks you to approve this at {error}"
+                ));
+                waiting.deadline = Instant::now() + BUNKER_AUTH_URL_TIMEOUT;
+            }
+            return Ok(());
+        }
+        if let Some(waiting) = pending.remove(id) {
+            *failures = 0;
+            let answer = if !error.is_empty() {
+                Err(ErrorKind::Nip46Remote(error.to_owned()).into())
+            } else {
+                Ok(result.to_owned())
+            };
+            let _ = waiting.reply.send(answer);
+        }
+        Ok(())
+    }
+
+    // Bunkers answer in whichever encryption they like
+    fn decrypt_response(&self, event: &Event) -> Result<String, Error> {
+        if event.content.contains("?iv=") {
+            let bytes = self
+                .client
+                .decrypt_nip04(&self.bunker.remote_pubkey, &event.content)?;
+            Ok(String::from_utf8(bytes)?)
+        } else {
+            Ok(self
+                .client
+                .decrypt_nip44(&self.bunker.remote_pubkey, &event.content)?)
+        }
+    }
+}
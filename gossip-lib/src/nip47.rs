@@ -0,0 +1,224 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::storage::json_key;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use nostr_types::{
+    ClientMessage, ContentEncryptionAlgorithm, Event, EventKind, Filter, IdHex, Identity, PreEvent,
+    PrivateKey, PublicKey, RelayMessage, RelayUrl, SubscriptionId, Tag, Unixtime,
+};
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+/// How long we wait for the wallet to answer
+const WALLET_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A wallet we pay through (NIP-47), from its connection URI
+/// (nostr+walletconnect://<wallet-pubkey>?relay=wss://...&secret=<hex>)
+pub struct WalletConnection {
+    pub wallet_pubkey: PublicKey,
+    pub relay: RelayUrl,
+
+    // Signs and encrypts our requests. The wallet only knows us by this key.
+    client: Identity,
+}
+
+impl WalletConnection {
+    pub fn from_uri(uri: &str) -> Result<WalletConnection, Error> {
+        let url = url::Url::parse(uri.trim())
+            .map_err(|_| ErrorKind::General("Not a wallet connection URI".to_owned()))?;
+        if url.scheme() != "nostr+walletconnect" && url.scheme() != "nostrwalletconnect" {
+            return Err(ErrorKind::General("Not a wallet connection URI".to_owned()).into());
+        }
+        let wallet_pubkey = match url.host_str() {
+            Some(hex) => PublicKey::try_from_hex_string(hex, true)?,
+            None => return Err(ErrorKind::General("The URI has no wallet key".to_owned()).into()),
+        };
+
+        let mut relay: Option<RelayUrl> = None;
+        let mut secret: Option<PrivateKey> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" if relay.is_none() => relay = RelayUrl::try_from_str(&value).ok(),
+                "secret" => secret = PrivateKey::try_from_hex_string(&value).ok(),
+                _ => (),
+            }
+        }
+        let relay = match relay {
+            Some(r) => r,
+            None => return Err(ErrorKind::General("The URI has no relay".to_owned()).into()),
+        };
+        let secret = match secret {
+            Some(s) => s,
+            None => return Err(ErrorKind::General("The URI has no secret".to_owned()).into()),
+        };
+
+        // The secret is kept encrypted in storage along with the rest of the
+        // URI, so the copy of it in here needs no passphrase of its own
+        let client = Identity::from_private_key(secret, "", 8)?;
+
+        Ok(WalletConnection {
+            wallet_pubkey,
+            relay,
+            client,
+        })
+    }
+
+    /// Pay a lightning invoice, returning the preimage
+    pub async fn pay_invoice(&self, invoice: &str) -> Result<String, Error> {
+        let result = self
+            .request(serde_json::json!({
+                "method": "pay_invoice",
+                "params": { "invoice": invoice },
+            }))
+            .await?;
+        Ok(result["preimage"].as_str().unwrap_or("").to_owned())
+    }
+
+    /// The wallet's balance in millisatoshis
+    pub async fn get_balance(&self) -> Result<u64, Error> {
+        let result = self
+            .request(serde_json::json!({
+                "method": "get_balance",
+                "params": {},
+            }))
+            .await?;
+        match result["balance"].as_u64() {
+            Some(msats) => Ok(msats),
+            None => Err(ErrorKind::General("The wallet did not give a balance".to_owned()).into()),
+        }
+    }
+
+    async fn request(&self, command: serde_json::Value) -> Result<serde_json::Value, Error> {
+        let client_pubkey = match self.client.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+        let content = self.client.encrypt(
+            &self.wallet_pubkey,
+            &command.to_string(),
+            ContentEncryptionAlgorithm::Nip04,
+        )?;
+        let request = self.client.sign_event(PreEvent {
+            pubkey: client_pubkey,
+            created_at: Unixtime::now().unwrap(),
+            kind: EventKind::WalletRequest,
+            tags: vec![Tag::new_pubkey(self.wallet_pubkey, None, None)],
+            content,
+        })?;
+
+        let connect_timeout =
+            Duration::from_secs(GLOBALS.storage.read_setting_websocket_connect_timeout_sec());
+        let (mut stream, _) = tokio::time::timeout(
+            connect_timeout,
+            crate::proxy::connect_websocket(self.relay.as_str(), None),
+        )
+        .await??;
+
+        // The response refers to the request by its id
+        let filter = {
+            let mut filter = Filter {
+                authors: vec![self.wallet_pubkey.into()],
+                kinds: vec![EventKind::WalletResponse],
+                ..Default::default()
+            };
+            let idhex: IdHex = request.id.into();
+            filter.set_tag_values('e', vec![idhex.to_string()]);
+            filter
+        };
+        let req = ClientMessage::Req(SubscriptionId("nwc".to_owned()), vec![filter]);
+        stream
+            .send(WsMessage::Text(serde_json::to_string(&req)?))
+            .await?;
+        let msg = ClientMessage::Event(Box::new(request));
+        stream
+            .send(WsMessage::Text(serde_json::to_string(&msg)?))
+            .await?;
+
+        let deadline = tokio::time::Instant::now() + WALLET_TIMEOUT;
+        let response = loop {
+            let text = match tokio::time::timeout_at(deadline, stream.next()).await? {
+                Some(Ok(WsMessage::Text(text))) => text,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err("The wallet relay closed the connection".into()),
+            };
+            match serde_json::from_str::<RelayMessage>(&text) {
+                Ok(RelayMessage::Event(_, event)) => break self.decrypt_response(&event)?,
+                Ok(RelayMessage::Ok(_, false, message)) => {
+                    return Err(format!("The wallet relay refused our request: {message}").into())
+                }
+                _ => continue,
+            }
+        };
+
+        let _ = stream.close(None).await;
+
+        if let Some(error) = response["error"].as_object() {
+            return Err(ErrorKind::General(format!(
+                "Wallet error {}: {}",
+                error.get("code").and_then(|c| c.as_str()).unwrap_or(""),
+                error.get("message").and_then(|m| m.as_str()).unwrap_or("")
+            ))
+            .into());
+        }
+        Ok(response["result"].clone())
+    }
+
+    fn decrypt_response(&self, event: &Event) -> Result<serde_json::Value, Error> {
+        let bytes = self
+            .client
+            .decrypt_nip04(&self.wallet_pubkey, &event.content)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Pay through this wallet from now on. The connection URI is kept encrypted
+/// to ourself, so this needs the identity unlocked.
+pub fn connect(uri: &str) -> Result<(), Error> {
+    // Make sure it is usable first
+    let _ = WalletConnection::from_uri(uri)?;
+
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+    let encrypted =
+        GLOBALS
+            .identity
+            .encrypt(&pubkey, uri.trim(), ContentEncryptionAlgorithm::Nip44v2)?;
+    GLOBALS
+        .storage
+        .write_json(json_key::WALLET_CONNECTION, &encrypted, None)
+}
+
+/// Stop paying through the wallet
+pub fn disconnect() -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .delete_json(json_key::WALLET_CONNECTION, None)
+}
+
+/// Whether we have a wallet to pay through
+pub fn is_connected() -> bool {
+    matches!(
+        GLOBALS
+            .storage
+            .read_json::<String>(json_key::WALLET_CONNECTION),
+        Ok(Some(_))
+    )
+}
+
+/// The wallet we pay through, if there is one and we can decrypt it
+pub fn wallet() -> Result<Option<WalletConnection>, Error> {
+    let encrypted: String = match GLOBALS.storage.read_json(json_key::WALLET_CONNECTION)? {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+    let uri = GLOBALS.identity.decrypt_nip44(&pubkey, &encrypted)?;
+    Ok(Some(WalletConnection::from_uri(&uri)?))
+}
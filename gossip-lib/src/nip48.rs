@@ -0,0 +1,51 @@
+use crate::globals::GLOBALS;
+use nostr_types::Event;
+
+/// Where a bridged event came from, from its `proxy` tag (NIP-48)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proxy {
+    /// The id of the original in its own protocol, usually a URL
+    pub id: String,
+
+    /// Such as "activitypub", "atproto", "rss" or "web"
+    pub protocol: String,
+}
+
+impl Proxy {
+    /// The protocol as people know it
+    pub fn protocol_name(&self) -> &str {
+        match self.protocol.as_str() {
+            "activitypub" => "ActivityPub",
+            "atproto" => "Bluesky",
+            "rss" => "RSS",
+            "web" => "the web",
+            other => other,
+        }
+    }
+
+    /// Where the original can be looked at, if its id is a web address
+    pub fn url(&self) -> Option<&str> {
+        if self.id.starts_with("https://") || self.id.starts_with("http://") {
+            Some(&self.id)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where an event was bridged from, if it was
+pub fn proxy_of(event: &Event) -> Option<Proxy> {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.tagname() == "proxy" && !tag.value().is_empty())
+        .map(|tag| Proxy {
+            id: tag.value().to_owned(),
+            protocol: tag.get_index(2).to_owned(),
+        })
+}
+
+/// Whether an event passes the `hide_bridged_posts` setting
+pub fn passes_filter(event: &Event) -> bool {
+    !GLOBALS.storage.read_setting_hide_bridged_posts() || proxy_of(event).is_none()
+}
@@ -0,0 +1,318 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKey, Tag, Unixtime};
+use std::collections::HashMap;
+use time::macros::format_description;
+use time::Date;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+pub fn is_calendar_event(kind: EventKind) -> bool {
+    kind == EventKind::DateBasedCalendarEvent || kind == EventKind::TimeBasedCalendarEvent
+}
+
+pub fn calendar_event_kinds() -> Vec<EventKind> {
+    vec![
+        EventKind::DateBasedCalendarEvent,
+        EventKind::TimeBasedCalendarEvent,
+    ]
+}
+
+/// When a calendar event starts or ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarTime {
+    /// A day, the same everywhere, for date-based events (kind 31922)
+    Date(Date),
+
+    /// A moment, for time-based events (kind 31923)
+    Time(Unixtime),
+}
+
+impl CalendarTime {
+    // Dates are written as YYYY-MM-DD
+    fn parse_date(value: &str) -> Option<CalendarTime> {
+        Date::parse(
+            value.trim(),
+            format_description!("[year]-[month padding:none]-[day padding:none]"),
+        )
+        .ok()
+        .map(CalendarTime::Date)
+    }
+
+    fn parse_time(value: &str) -> Option<CalendarTime> {
+        value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(|t| CalendarTime::Time(Unixtime(t)))
+    }
+
+    /// The moment this is at. Dates are taken as midnight UTC.
+    pub fn unixtime(&self) -> Unixtime {
+        match *self {
+            CalendarTime::Date(date) => Unixtime(date.midnight().assume_utc().unix_timestamp()),
+            CalendarTime::Time(t) => t,
+        }
+    }
+}
+
+/// A date-based or time-based calendar event (NIP-52)
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    /// Identifies the event among its author's
+    pub d: String,
+    pub title: String,
+    pub summary: Option<String>,
+
+    /// The URL of an image
+    pub image: Option<String>,
+
+    /// Addresses, links or other places, in the order given
+    pub locations: Vec<String>,
+
+    pub start: CalendarTime,
+
+    /// For date-based events this is the first day after the event
+    pub end: Option<CalendarTime>,
+
+    /// People taking part, and their role if given
+    pub participants: Vec<(PublicKey, Option<String>)>,
+
+    pub event: Event,
+}
+
+impl CalendarEvent {
+    pub fn from_event(event: &Event) -> Option<CalendarEvent> {
+        let parse: fn(&str) -> Option<CalendarTime> = match event.kind {
+            EventKind::DateBasedCalendarEvent => CalendarTime::parse_date,
+            EventKind::TimeBasedCalendarEvent => CalendarTime::parse_time,
+            _ => return None,
+        };
+
+        let d = event.parameter()?;
+        let mut title = String::new();
+        let mut summary = None;
+        let mut image = None;
+        let mut locations: Vec<String> = Vec::new();
+        let mut start = None;
+        let mut end = None;
+        let mut participants: Vec<(PublicKey, Option<String>)> = Vec::new();
+        for tag in &event.tags {
+            let value = tag.value();
+            match tag.tagname() {
+                // "name" was used before "title"
+                "title" => title = value.to_owned(),
+                "name" if title.is_empty() => title = value.to_owned(),
+                "summary" if !value.trim().is_empty() => summary = Some(value.to_owned()),
+                "image" if !value.trim().is_empty() => image = Some(value.to_owned()),
+                "location" if !value.trim().is_empty() => locations.push(value.to_owned()),
+                "start" => start = parse(value),
+                "end" => end = parse(value),
+                "p" => {
+                    if let Ok((pubkey, _, _)) = tag.parse_pubkey() {
+                        if !participants.iter().any(|(p, _)| *p == pubkey) {
+                            let role = tag.get_index(3);
+                            let role = if role.is_empty() {
+                                None
+                            } else {
+                                Some(role.to_owned())
+                            };
+                            participants.push((pubkey, role));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if title.trim().is_empty() {
+            title = d.clone();
+        }
+
+        Some(CalendarEvent {
+            d,
+            title,
+            summary,
+            image,
+            locations,
+            start: start?,
+            end,
+            participants,
+            event: event.clone(),
+        })
+    }
+
+    /// The value used in 'a' tags that reference this calendar event
+    pub fn a_tag_value(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            u32::from(self.event.kind),
+            self.event.pubkey.as_hex_string(),
+            self.d
+        )
+    }
+
+    /// Whether it is over. Events without an end are over when they start,
+    /// or for date-based events, at the end of their day.
+    pub fn has_ended(&self, now: Unixtime) -> bool {
+        let end = match (self.end, self.start) {
+            (Some(end), _) => end.unixtime(),
+            (None, start @ CalendarTime::Date(_)) => Unixtime(start.unixtime().0 + SECONDS_PER_DAY),
+            (None, start) => start.unixtime(),
+        };
+        end < now
+    }
+}
+
+/// How somebody answered an invitation to a calendar event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RsvpStatus {
+    Accepted,
+    Declined,
+    Tentative,
+}
+
+impl RsvpStatus {
+    fn from_tag_value(value: &str) -> Option<RsvpStatus> {
+        match value {
+            "accepted" => Some(RsvpStatus::Accepted),
+            "declined" => Some(RsvpStatus::Declined),
+            "tentative" => Some(RsvpStatus::Tentative),
+            _ => None,
+        }
+    }
+
+    /// The value of the `status` tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RsvpStatus::Accepted => "accepted",
+            RsvpStatus::Declined => "declined",
+            RsvpStatus::Tentative => "tentative",
+        }
+    }
+}
+
+impl std::fmt::Display for RsvpStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RsvpStatus::Accepted => write!(f, "Going"),
+            RsvpStatus::Declined => write!(f, "Not going"),
+            RsvpStatus::Tentative => write!(f, "Maybe"),
+        }
+    }
+}
+
+/// The tags of an RSVP (kind 31925) to a calendar event. The `d` tag is the
+/// event's address, so that a later RSVP of ours replaces the earlier one.
+pub fn rsvp_tags(calendar_event: &CalendarEvent, status: RsvpStatus) -> Vec<Tag> {
+    let a = calendar_event.a_tag_value();
+    vec![
+        Tag::new(&["a", &a]),
+        Tag::new(&["e", &calendar_event.event.id.as_hex_string()]),
+        Tag::new(&["d", &a]),
+        Tag::new(&["status", status.as_str()]),
+        Tag::new(&["p", &calendar_event.event.pubkey.as_hex_string()]),
+    ]
+}
+
+/// The answers people gave to a calendar event, taking each person's latest
+/// RSVP only
+pub fn rsvps(calendar_event: &CalendarEvent) -> Result<HashMap<PublicKey, RsvpStatus>, Error> {
+    let value = calendar_event.a_tag_value();
+    // The tag index is searched by prefix, so the coordinate must be checked
+    // in full or RSVPs to "...:meetup-2" would count for "...:meetup"
+    let events = GLOBALS.storage.find_tagged_events(
+        "a",
+        Some(&value),
+        |e| {
+            e.kind == EventKind::CalendarEventRsvp
+                && e.tags
+                    .iter()
+                    .any(|t| t.tagname() == "a" && t.value() == value)
+        },
+        true,
+    )?;
+
+    // Sorted newest first, so the first RSVP of each person is their latest
+    let mut output: HashMap<PublicKey, RsvpStatus> = HashMap::new();
+    for event in &events {
+        if output.contains_key(&event.pubkey) {
+            continue;
+        }
+        if let Some(status) = event
+            .tags
+            .iter()
+            .find(|t| t.tagname() == "status")
+            .and_then(|t| RsvpStatus::from_tag_value(t.value()))
+        {
+            output.insert(event.pubkey, status);
+        }
+    }
+    Ok(output)
+}
+
+/// Calendar events by people we follow, and by us, that are not over yet,
+/// soonest first. Only the latest version of each is kept.
+pub fn upcoming_from_follows() -> Result<Vec<CalendarEvent>, Error> {
+    let mut pubkeys = GLOBALS.people.get_subscribed_pubkeys();
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        pubkeys.push(pk);
+    }
+
+    let now = Unixtime::now().unwrap();
+    let mut latest: HashMap<(PublicKey, String), CalendarEvent> = HashMap::new();
+    for event in
+        GLOBALS
+            .storage
+            .find_events(&calendar_event_kinds(), &pubkeys, None, |_| true, false)?
+    {
+        if let Some(calendar_event) = CalendarEvent::from_event(&event) {
+            let key = (event.pubkey, calendar_event.d.clone());
+            match latest.get(&key) {
+                Some(prior) if prior.event.created_at >= event.created_at => (),
+                _ => {
+                    latest.insert(key, calendar_event);
+                }
+            }
+        }
+    }
+
+    let mut output: Vec<CalendarEvent> =
+        latest.into_values().filter(|c| !c.has_ended(now)).collect();
+    output.sort_by_key(|c| c.start.unixtime());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let date = CalendarTime::parse_date("2024-03-01").unwrap();
+        assert_eq!(date.unixtime(), Unixtime(1709251200));
+        assert_eq!(
+            CalendarTime::parse_date(" 2024-3-1 ").map(|d| d.unixtime()),
+            Some(Unixtime(1709251200))
+        );
+        assert_eq!(
+            CalendarTime::parse_date("1970-01-01").map(|d| d.unixtime()),
+            Some(Unixtime(0))
+        );
+        assert_eq!(
+            CalendarTime::parse_date("1969-12-31").map(|d| d.unixtime()),
+            Some(Unixtime(-86400))
+        );
+        assert_eq!(CalendarTime::parse_date("2023-02-29"), None);
+        assert_eq!(CalendarTime::parse_date("2024-00-10"), None);
+        assert_eq!(CalendarTime::parse_date("1709251200"), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            CalendarTime::parse_time("1709251200"),
+            Some(CalendarTime::Time(Unixtime(1709251200)))
+        );
+        assert_eq!(CalendarTime::parse_time("2024-03-01"), None);
+    }
+}
@@ -0,0 +1,147 @@
+use nostr_types::{Event, EventKind, PublicKey, Unixtime};
+
+pub fn is_live_event(kind: EventKind) -> bool {
+    kind == EventKind::LiveEvent
+}
+
+/// Where a live activity is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveStatus {
+    Planned,
+    Live,
+    Ended,
+}
+
+impl LiveStatus {
+    fn from_tag_value(value: &str) -> Option<LiveStatus> {
+        match value {
+            "planned" => Some(LiveStatus::Planned),
+            "live" => Some(LiveStatus::Live),
+            "ended" => Some(LiveStatus::Ended),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiveStatus::Planned => "PLANNED",
+            LiveStatus::Live => "LIVE",
+            LiveStatus::Ended => "ENDED",
+        }
+    }
+}
+
+/// Somebody taking part in a live activity
+#[derive(Debug, Clone)]
+pub struct LiveParticipant {
+    pub pubkey: PublicKey,
+
+    /// Such as "Host" or "Speaker"
+    pub role: Option<String>,
+}
+
+/// A live activity such as a stream (kind 30311). Its author updates it as it
+/// goes, so only the latest version is of interest.
+#[derive(Debug, Clone)]
+pub struct LiveActivity {
+    /// Identifies the activity among its author's
+    pub d: String,
+    pub title: String,
+    pub summary: Option<String>,
+
+    /// The URL of a preview image
+    pub image: Option<String>,
+
+    pub status: Option<LiveStatus>,
+
+    /// Where to watch it while it is live
+    pub streaming: Option<String>,
+
+    /// Where to watch it after it has ended
+    pub recording: Option<String>,
+
+    pub starts: Option<Unixtime>,
+    pub ends: Option<Unixtime>,
+
+    pub current_participants: Option<u64>,
+    pub participants: Vec<LiveParticipant>,
+
+    pub event: Event,
+}
+
+impl LiveActivity {
+    pub fn from_event(event: &Event) -> Option<LiveActivity> {
+        if !is_live_event(event.kind) {
+            return None;
+        }
+
+        let d = event.parameter()?;
+        let mut title = String::new();
+        let mut summary = None;
+        let mut image = None;
+        let mut status = None;
+        let mut streaming = None;
+        let mut recording = None;
+        let mut starts = None;
+        let mut ends = None;
+        let mut current_participants = None;
+        let mut participants: Vec<LiveParticipant> = Vec::new();
+        for tag in &event.tags {
+            let value = tag.value();
+            match tag.tagname() {
+                "title" => title = value.to_owned(),
+                "summary" if !value.trim().is_empty() => summary = Some(value.to_owned()),
+                "image" if !value.trim().is_empty() => image = Some(value.to_owned()),
+                "status" => status = LiveStatus::from_tag_value(value),
+                "streaming" if !value.trim().is_empty() => streaming = Some(value.to_owned()),
+                "recording" if !value.trim().is_empty() => recording = Some(value.to_owned()),
+                "starts" => starts = value.parse::<i64>().ok().map(Unixtime),
+                "ends" => ends = value.parse::<i64>().ok().map(Unixtime),
+                "current_participants" => current_participants = value.parse::<u64>().ok(),
+                "p" => {
+                    if let Ok((pubkey, _, _)) = tag.parse_pubkey() {
+                        if !participants.iter().any(|p| p.pubkey == pubkey) {
+                            let role = tag.get_index(3);
+                            participants.push(LiveParticipant {
+                                pubkey,
+                                role: if role.is_empty() {
+                                    None
+                                } else {
+                                    Some(role.to_owned())
+                                },
+                            });
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if title.trim().is_empty() {
+            title = d.clone();
+        }
+
+        Some(LiveActivity {
+            d,
+            title,
+            summary,
+            image,
+            status,
+            streaming,
+            recording,
+            starts,
+            ends,
+            current_participants,
+            participants,
+            event: event.clone(),
+        })
+    }
+
+    /// Where to watch it: the stream while it is on, the recording once it
+    /// has ended
+    pub fn watch_url(&self) -> Option<&str> {
+        match self.status {
+            Some(LiveStatus::Ended) => self.recording.as_deref().or(self.streaming.as_deref()),
+            _ => self.streaming.as_deref(),
+        }
+    }
+}
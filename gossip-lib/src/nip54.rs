@@ -0,0 +1,228 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventAddr, EventKind, Tag};
+
+/// Wiki article
+pub const KIND_WIKI: u32 = 30818;
+
+pub fn wiki_kind() -> EventKind {
+    EventKind::from(KIND_WIKI)
+}
+
+pub fn is_wiki(kind: EventKind) -> bool {
+    let k: u32 = kind.into();
+    k == KIND_WIKI
+}
+
+/// The `d` tag of an article with this title: lowercase, with anything that
+/// is not a letter or a digit turned into a '-'
+pub fn normalize(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().collect::<String>()
+            } else {
+                "-".to_owned()
+            }
+        })
+        .collect()
+}
+
+/// A wiki article (kind 30818)
+#[derive(Debug, Clone)]
+pub struct WikiArticle {
+    /// The normalized topic, which articles by different authors share
+    pub d: String,
+    pub title: String,
+    pub summary: Option<String>,
+
+    /// Asciidoc content, with [[wikilinks]]
+    pub content: String,
+
+    /// The article this one was forked from
+    pub forked_from: Option<EventAddr>,
+
+    pub event: Event,
+}
+
+impl WikiArticle {
+    pub fn from_event(event: &Event) -> Option<WikiArticle> {
+        if !is_wiki(event.kind) {
+            return None;
+        }
+
+        let d = event.parameter()?;
+        let mut title = String::new();
+        let mut summary = None;
+        let mut forked_from = None;
+        for tag in &event.tags {
+            match tag.tagname() {
+                "title" => title = tag.value().to_owned(),
+                "summary" => summary = Some(tag.value().to_owned()),
+                "a" if tag.get_index(3) == "fork" => {
+                    if let Ok((ea, _)) = tag.parse_address() {
+                        forked_from = Some(ea);
+                    }
+                }
+                _ => (),
+            }
+        }
+        if title.is_empty() {
+            title = d.clone();
+        }
+
+        Some(WikiArticle {
+            d,
+            title,
+            summary,
+            content: event.content.clone(),
+            forked_from,
+            event: event.clone(),
+        })
+    }
+}
+
+/// The articles on a topic that we have, by any author, newest first
+pub fn articles(d: &str) -> Result<Vec<WikiArticle>, Error> {
+    let d = normalize(d);
+    // The tag index is searched by prefix, so "bitcoin" would also find
+    // "bitcoin-mining"
+    Ok(GLOBALS
+        .storage
+        .find_tagged_events(
+            "d",
+            Some(&d),
+            |e| is_wiki(e.kind) && e.parameter().as_deref() == Some(d.as_str()),
+            true,
+        )?
+        .iter()
+        .filter_map(WikiArticle::from_event)
+        .collect())
+}
+
+/// A piece of article content
+#[derive(Debug, Clone, PartialEq)]
+pub enum WikiSegment<'a> {
+    Text(&'a str),
+
+    /// A [[wikilink]], or [[target|label]]. The target is normalized.
+    Link {
+        target: String,
+        label: &'a str,
+    },
+}
+
+/// Split a line of content into text and wikilinks
+pub fn segments(line: &str) -> Vec<WikiSegment<'_>> {
+    let mut segments: Vec<WikiSegment<'_>> = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let end = match after.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        if start > 0 {
+            segments.push(WikiSegment::Text(&rest[..start]));
+        }
+        let inner = &after[..end];
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target, label),
+            None => (inner, inner),
+        };
+        segments.push(WikiSegment::Link {
+            target: normalize(target),
+            label,
+        });
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(WikiSegment::Text(rest));
+    }
+    segments
+}
+
+/// The tags of an article. A fork refers to the article it was forked from
+/// by address only, so it is not taken as a reply to it.
+pub fn article_tags(title: &str, summary: &str, fork_of: Option<&Event>) -> Vec<Tag> {
+    let mut tags = vec![
+        Tag::new(&["d", &normalize(title)]),
+        Tag::new(&["title", title.trim()]),
+    ];
+    if !summary.trim().is_empty() {
+        tags.push(Tag::new(&["summary", summary.trim()]));
+    }
+    if let Some(source) = fork_of {
+        if let Some(d) = source.parameter() {
+            tags.push(Tag::new(&[
+                "a",
+                &format!("{}:{}:{}", KIND_WIKI, source.pubkey.as_hex_string(), d),
+                "",
+                "fork",
+            ]));
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Nostr"), "nostr");
+        assert_eq!(normalize("  Bitcoin Core "), "bitcoin-core");
+        assert_eq!(normalize("C++"), "c--");
+        assert_eq!(normalize("Ärger"), "ärger");
+    }
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(segments(""), vec![]);
+        assert_eq!(segments("plain"), vec![WikiSegment::Text("plain")]);
+        assert_eq!(
+            segments("see [[Bitcoin Core]] and [[nostr|the protocol]]."),
+            vec![
+                WikiSegment::Text("see "),
+                WikiSegment::Link {
+                    target: "bitcoin-core".to_owned(),
+                    label: "Bitcoin Core",
+                },
+                WikiSegment::Text(" and "),
+                WikiSegment::Link {
+                    target: "nostr".to_owned(),
+                    label: "the protocol",
+                },
+                WikiSegment::Text("."),
+            ]
+        );
+        assert_eq!(
+            segments("[[a]][[b]]"),
+            vec![
+                WikiSegment::Link {
+                    target: "a".to_owned(),
+                    label: "a",
+                },
+                WikiSegment::Link {
+                    target: "b".to_owned(),
+                    label: "b",
+                },
+            ]
+        );
+
+        // an unclosed link is left as text
+        assert_eq!(
+            segments("[[a]] then [[b"),
+            vec![
+                WikiSegment::Link {
+                    target: "a".to_owned(),
+                    label: "a",
+                },
+                WikiSegment::Text(" then [[b"),
+            ]
+        );
+    }
+}
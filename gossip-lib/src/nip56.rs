@@ -0,0 +1,112 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use crate::relationship::RelationshipById;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, Id, PublicKey, Tag};
+use std::collections::{HashMap, HashSet};
+
+/// The kinds of report (NIP-56)
+pub const REPORT_TYPES: [&str; 7] = [
+    "spam",
+    "impersonation",
+    "illegal",
+    "nudity",
+    "malware",
+    "profanity",
+    "other",
+];
+
+/// The tags of a report against a person, or against one of their events
+pub fn report_tags(pubkey: PublicKey, id: Option<Id>, report_type: &str) -> Vec<Tag> {
+    match id {
+        Some(id) => vec![
+            Tag::new(&["e", &id.as_hex_string(), report_type]),
+            Tag::new(&["p", &pubkey.as_hex_string()]),
+        ],
+        None => vec![Tag::new(&["p", &pubkey.as_hex_string(), report_type])],
+    }
+}
+
+/// Reports against an event by people we follow (or by us), one per reporter
+pub fn reports_of_event(id: Id) -> Result<HashMap<PublicKey, String>, Error> {
+    let mut reports: HashMap<PublicKey, String> = HashMap::new();
+    for (report_id, rel) in GLOBALS.storage.find_relationships_by_id(id)? {
+        if let RelationshipById::Reports(report_type) = rel {
+            if let Some(report) = GLOBALS.storage.read_event(report_id)? {
+                if GLOBALS.people.is_trusted(&report.pubkey) {
+                    reports.insert(report.pubkey, report_type);
+                }
+            }
+        }
+    }
+    Ok(reports)
+}
+
+lazy_static! {
+    // Reports of a person are looked for whenever one of their events is
+    // shown or filtered, and finding them goes through every report by the
+    // people we follow. So they are kept, with the hash of our follow list
+    // they were found under, until a report of them comes in.
+    static ref PERSON_REPORTS: DashMap<PublicKey, (u64, HashMap<PublicKey, String>)> =
+        DashMap::new();
+}
+
+/// Reports against a person by people we follow (or by us), one per reporter
+pub fn reports_of_person(pubkey: PublicKey) -> Result<HashMap<PublicKey, String>, Error> {
+    let follows = GLOBALS.storage.hash_person_list(PersonList::Followed)?;
+    if let Some(entry) = PERSON_REPORTS.get(&pubkey) {
+        if entry.0 == follows {
+            return Ok(entry.1.clone());
+        }
+    }
+
+    let reports = find_reports_of_person(pubkey)?;
+    PERSON_REPORTS.insert(pubkey, (follows, reports.clone()));
+    Ok(reports)
+}
+
+/// Forget what we found of the reports of the people a new report is about
+pub(crate) fn forget_reports_of(report: &Event) {
+    for tag in &report.tags {
+        if let Ok((pk, _, _)) = tag.parse_pubkey() {
+            PERSON_REPORTS.remove(&pk);
+        }
+    }
+}
+
+fn find_reports_of_person(pubkey: PublicKey) -> Result<HashMap<PublicKey, String>, Error> {
+    let mut reports: HashMap<PublicKey, String> = HashMap::new();
+    for report in GLOBALS.storage.find_events_tagging(
+        &[EventKind::Reporting],
+        &GLOBALS.people.get_trusted_pubkeys()?,
+        None,
+        &[pubkey],
+        |_| true,
+        false,
+    )? {
+        // Reports against one of their events also tag them, without a type
+        for tag in &report.tags {
+            if let Ok((pk, _, _)) = tag.parse_pubkey() {
+                let report_type = tag.get_index(2);
+                if pk == pubkey && !report_type.is_empty() {
+                    reports.insert(report.pubkey, report_type.to_owned());
+                }
+            }
+        }
+    }
+    Ok(reports)
+}
+
+/// How many people we follow have reported this event or its author
+pub fn report_count(event: &Event) -> usize {
+    let mut reporters: HashSet<PublicKey> = HashSet::new();
+    if let Ok(reports) = reports_of_event(event.id) {
+        reporters.extend(reports.keys());
+    }
+    if let Ok(reports) = reports_of_person(event.pubkey) {
+        reporters.extend(reports.keys());
+    }
+    reporters.len()
+}
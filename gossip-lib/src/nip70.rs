@@ -0,0 +1,12 @@
+use nostr_types::{Event, Tag};
+
+/// Whether an event is protected (NIP-70): relays should only accept it from
+/// its author, once they have authenticated, and nobody else should republish it
+pub fn is_protected(event: &Event) -> bool {
+    event.tags.iter().any(|tag| tag.tagname() == "-")
+}
+
+/// The tag that marks an event as protected
+pub fn protected_tag() -> Tag {
+    Tag::new(&["-"])
+}
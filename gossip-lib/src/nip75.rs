@@ -0,0 +1,86 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use nostr_types::{Event, EventKind, MilliSatoshi, Unixtime};
+
+pub fn is_zap_goal(kind: EventKind) -> bool {
+    kind == EventKind::ZapGoal
+}
+
+/// A fundraising goal that people reach by zapping it (kind 9041)
+#[derive(Debug, Clone)]
+pub struct ZapGoal {
+    /// What it is for, from the content
+    pub description: String,
+
+    /// How much is sought
+    pub amount: MilliSatoshi,
+
+    /// Zaps after this time no longer count
+    pub closed_at: Option<Unixtime>,
+
+    pub summary: Option<String>,
+
+    /// The URL of an image
+    pub image: Option<String>,
+
+    pub event: Event,
+}
+
+impl ZapGoal {
+    pub fn from_event(event: &Event) -> Option<ZapGoal> {
+        if !is_zap_goal(event.kind) {
+            return None;
+        }
+
+        let mut amount = None;
+        let mut closed_at = None;
+        let mut summary = None;
+        let mut image = None;
+        for tag in &event.tags {
+            let value = tag.value();
+            match tag.tagname() {
+                "amount" => amount = value.parse::<u64>().ok().map(MilliSatoshi),
+                "closed_at" => closed_at = value.parse::<i64>().ok().map(Unixtime),
+                "summary" if !value.trim().is_empty() => summary = Some(value.to_owned()),
+                "image" if !value.trim().is_empty() => image = Some(value.to_owned()),
+                _ => (),
+            }
+        }
+
+        Some(ZapGoal {
+            description: event.content.clone(),
+            amount: amount?,
+            closed_at,
+            summary,
+            image,
+            event: event.clone(),
+        })
+    }
+
+    /// How much has been zapped to the goal so far, going by the zap receipts
+    /// we have. Zaps after it closed are left out.
+    pub fn raised(&self) -> Result<MilliSatoshi, Error> {
+        let mut total = MilliSatoshi(0);
+        for (receipt_id, rel) in GLOBALS.storage.find_relationships_by_id(self.event.id)? {
+            if let RelationshipById::ZapReceipt { amount, .. } = rel {
+                if let Some(closed_at) = self.closed_at {
+                    match GLOBALS.storage.read_event(receipt_id)? {
+                        Some(receipt) if receipt.created_at <= closed_at => (),
+                        _ => continue,
+                    }
+                }
+                total = total + amount;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Whether the goal no longer takes zaps
+    pub fn is_closed(&self) -> bool {
+        match self.closed_at {
+            Some(closed_at) => closed_at < Unixtime::now().unwrap(),
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventAddr, EventKind, EventPointer, PublicKey};
+
+/// An application that announced it can handle some event kinds (NIP-89, kind 31990)
+#[derive(Debug, Clone)]
+pub struct Handler {
+    pub addr: EventAddr,
+    pub name: String,
+    pub picture: Option<String>,
+
+    /// Web URL templates along with the optional NIP-19 entity type they expect
+    pub web: Vec<(String, Option<String>)>,
+
+    /// The event kinds this handler supports
+    pub kinds: Vec<EventKind>,
+
+    /// People we follow who recommend this handler
+    pub recommended_by: Vec<PublicKey>,
+}
+
+impl Handler {
+    pub fn from_event(event: &Event) -> Option<Handler> {
+        if event.kind != EventKind::HandlerInformation {
+            return None;
+        }
+
+        let mut d = String::new();
+        let mut web: Vec<(String, Option<String>)> = Vec::new();
+        let mut kinds: Vec<EventKind> = Vec::new();
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "web" => {
+                    let entity = tag.get_index(2);
+                    let entity = if entity.is_empty() {
+                        None
+                    } else {
+                        Some(entity.to_owned())
+                    };
+                    web.push((tag.value().to_owned(), entity));
+                }
+                "k" => {
+                    if let Ok(k) = tag.value().parse::<u32>() {
+                        kinds.push(EventKind::from(k));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // We can only offer handlers that we can link to
+        if web.is_empty() {
+            return None;
+        }
+
+        // The content is optionally kind-0 style metadata
+        let mut name: Option<String> = None;
+        let mut picture: Option<String> = None;
+        if let Ok(serde_json::Value::Object(map)) =
+            serde_json::from_str::<serde_json::Value>(&event.content)
+        {
+            for field in ["display_name", "name"] {
+                if name.is_none() {
+                    if let Some(serde_json::Value::String(s)) = map.get(field) {
+                        if !s.is_empty() {
+                            name = Some(s.to_owned());
+                        }
+                    }
+                }
+            }
+            if let Some(serde_json::Value::String(s)) = map.get("picture") {
+                picture = Some(s.to_owned());
+            }
+        }
+
+        let name = name.unwrap_or_else(|| d.clone());
+
+        Some(Handler {
+            addr: EventAddr {
+                d,
+                relays: vec![],
+                kind: event.kind,
+                author: event.pubkey,
+            },
+            name,
+            picture,
+            web,
+            kinds,
+            recommended_by: vec![],
+        })
+    }
+
+    /// Build a link that opens the event in this handler
+    pub fn link_for(&self, event: &Event) -> Option<String> {
+        let (entity, bech32) = if event.kind.is_parameterized_replaceable() {
+            let mut d = "".to_owned();
+            for tag in &event.tags {
+                if tag.tagname() == "d" {
+                    d = tag.value().to_owned();
+                }
+            }
+            let ea = EventAddr {
+                d,
+                relays: vec![],
+                kind: event.kind,
+                author: event.pubkey,
+            };
+            ("naddr", ea.as_bech32_string())
+        } else {
+            let ep = EventPointer {
+                id: event.id,
+                relays: vec![],
+                author: Some(event.pubkey),
+                kind: Some(event.kind),
+            };
+            ("nevent", ep.as_bech32_string())
+        };
+
+        // Prefer a template for our entity type, then one that doesn't specify
+        let template = self
+            .web
+            .iter()
+            .find(|(_, e)| e.as_deref() == Some(entity))
+            .or_else(|| self.web.iter().find(|(_, e)| e.is_none()))?;
+
+        Some(template.0.replace("<bech32>", &bech32))
+    }
+}
+
+/// Get the handlers for an event kind that come from our web of trust: those announced
+/// by people we follow and those recommended (kind 31989) by people we follow. Handlers
+/// recommended by more people come first.
+pub fn handlers_for_kind(kind: EventKind) -> Result<Vec<Handler>, Error> {
+    let mut wot = GLOBALS.people.get_subscribed_pubkeys();
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        wot.push(pk);
+    }
+    if wot.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let kind_number: u32 = kind.into();
+    let kind_str = format!("{}", kind_number);
+
+    // Recommendations by people we follow
+    let recommendations = GLOBALS.storage.find_events(
+        &[EventKind::HandlerRecommendation],
+        &wot,
+        None,
+        |e| {
+            e.tags
+                .iter()
+                .any(|t| t.tagname() == "d" && t.value() == kind_str)
+        },
+        false,
+    )?;
+    let mut recommended: Vec<(EventAddr, PublicKey)> = Vec::new();
+    for event in &recommendations {
+        for tag in &event.tags {
+            if let Ok((ea, _)) = tag.parse_address() {
+                if ea.kind == EventKind::HandlerInformation {
+                    recommended.push((ea, event.pubkey));
+                }
+            }
+        }
+    }
+
+    let announcements = GLOBALS.storage.find_events(
+        &[EventKind::HandlerInformation],
+        &[],
+        None,
+        |e| {
+            e.tags
+                .iter()
+                .any(|t| t.tagname() == "k" && t.value() == kind_str)
+        },
+        true,
+    )?;
+
+    let mut handlers: Vec<Handler> = Vec::new();
+    for event in &announcements {
+        let mut handler = match Handler::from_event(event) {
+            Some(h) => h,
+            None => continue,
+        };
+
+        // Newest first, so skip older versions of the same handler
+        if handlers.iter().any(|h| h.addr == handler.addr) {
+            continue;
+        }
+
+        handler.recommended_by = recommended
+            .iter()
+            .filter(|(ea, _)| ea.author == handler.addr.author && ea.d == handler.addr.d)
+            .map(|(_, pk)| *pk)
+            .collect();
+
+        if handler.recommended_by.is_empty() && !wot.contains(&handler.addr.author) {
+            continue;
+        }
+
+        handlers.push(handler);
+    }
+
+    handlers.sort_by(|a, b| b.recommended_by.len().cmp(&a.recommended_by.len()));
+
+    Ok(handlers)
+}
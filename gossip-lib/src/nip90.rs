@@ -0,0 +1,260 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use crate::relay::Relay;
+use nostr_types::{Event, EventKind, Id, PublicKey, RelayUrl, Tag};
+
+/// The kind of job feedback events
+pub const FEEDBACK_KIND: u32 = 7000;
+
+/// The jobs we know how to ask data vending machines for (NIP-90)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobType {
+    Summarization,
+    Translation,
+    ContentDiscovery,
+}
+
+impl JobType {
+    pub const ALL: [JobType; 3] = [
+        JobType::Summarization,
+        JobType::Translation,
+        JobType::ContentDiscovery,
+    ];
+
+    /// The kind of a request for this job
+    pub fn request_kind(&self) -> EventKind {
+        EventKind::from(match self {
+            JobType::Summarization => 5001,
+            JobType::Translation => 5002,
+            JobType::ContentDiscovery => 5300,
+        })
+    }
+
+    /// The kind of a result of this job
+    pub fn result_kind(&self) -> EventKind {
+        EventKind::from(u32::from(self.request_kind()) + 1000)
+    }
+
+    pub fn from_kind(kind: EventKind) -> Option<JobType> {
+        JobType::ALL
+            .into_iter()
+            .find(|t| t.request_kind() == kind || t.result_kind() == kind)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobType::Summarization => "Summary",
+            JobType::Translation => "Translation",
+            JobType::ContentDiscovery => "Recommended notes",
+        }
+    }
+}
+
+/// The kinds of the responses to our job requests
+pub fn response_kinds() -> Vec<EventKind> {
+    let mut kinds: Vec<EventKind> = JobType::ALL.iter().map(|t| t.result_kind()).collect();
+    kinds.push(EventKind::from(FEEDBACK_KIND));
+    kinds
+}
+
+pub fn is_job_request(kind: EventKind) -> bool {
+    (5000..6000).contains(&u32::from(kind))
+}
+
+pub fn is_job_feedback(kind: EventKind) -> bool {
+    u32::from(kind) == FEEDBACK_KIND
+}
+
+/// The tags of a job request. `input` is the note the job is about (not used
+/// for content discovery). `language` is the target of a translation. `bid_msats`
+/// is the most we are willing to pay.
+pub fn job_request_tags(
+    job_type: JobType,
+    input: Option<Id>,
+    language: Option<String>,
+    bid_msats: Option<u64>,
+) -> Result<Vec<Tag>, Error> {
+    let mut tags: Vec<Tag> = Vec::new();
+
+    if let Some(id) = input {
+        let mut tag = Tag::new(&["i", &id.as_hex_string(), "event"]);
+        if let Some((url, _)) = GLOBALS.storage.get_event_seen_on_relay(id)?.first() {
+            tag.push_values(vec![url.as_str().to_owned()]);
+        }
+        tags.push(tag);
+    }
+
+    if job_type == JobType::Translation {
+        let language = match language {
+            Some(l) if !l.trim().is_empty() => l.trim().to_owned(),
+            _ => "en".to_owned(),
+        };
+        tags.push(Tag::new(&["param", "language", &language]));
+    }
+
+    if job_type != JobType::ContentDiscovery {
+        tags.push(Tag::new(&["output", "text/plain"]));
+    }
+
+    // Where to send the results: our inbox relays, which we listen to
+    let relays: Vec<String> = read_relays()?
+        .iter()
+        .map(|url| url.as_str().to_owned())
+        .collect();
+    if !relays.is_empty() {
+        let mut relays_tag = Tag::new(&["relays"]);
+        relays_tag.push_values(relays);
+        tags.push(relays_tag);
+    }
+
+    if let Some(bid) = bid_msats {
+        tags.push(Tag::new(&["bid", &bid.to_string()]));
+    }
+
+    Ok(tags)
+}
+
+fn read_relays() -> Result<Vec<RelayUrl>, Error> {
+    Ok(GLOBALS
+        .storage
+        .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+        .drain(..)
+        .map(|r| r.url)
+        .collect())
+}
+
+/// The events a job request of ours is about
+pub(crate) fn inputs_of(request_id: Id) -> Result<Vec<Id>, Error> {
+    let request = match GLOBALS.storage.read_event(request_id)? {
+        Some(event) if Some(event.pubkey) == GLOBALS.identity.public_key() => event,
+        _ => return Ok(vec![]),
+    };
+    Ok(request
+        .tags
+        .iter()
+        .filter(|tag| tag.tagname() == "i" && tag.get_index(2) == "event")
+        .filter_map(|tag| Id::try_from_hex_string(tag.value()).ok())
+        .collect())
+}
+
+/// Feedback from a data vending machine about a job
+#[derive(Clone, Debug)]
+pub struct JobFeedback {
+    pub from: PublicKey,
+    /// "payment-required", "processing", "error", "success" or "partial"
+    pub status: String,
+    pub extra_info: String,
+    pub amount_msats: Option<u64>,
+    pub bolt11: Option<String>,
+    pub content: String,
+}
+
+impl JobFeedback {
+    pub fn from_event(event: &Event) -> JobFeedback {
+        let mut feedback = JobFeedback {
+            from: event.pubkey,
+            status: String::new(),
+            extra_info: String::new(),
+            amount_msats: None,
+            bolt11: None,
+            content: event.content.clone(),
+        };
+        for tag in &event.tags {
+            match tag.tagname() {
+                "status" => {
+                    feedback.status = tag.value().to_owned();
+                    feedback.extra_info = tag.get_index(2).to_owned();
+                }
+                "amount" => {
+                    feedback.amount_msats = tag.value().parse::<u64>().ok();
+                    if !tag.get_index(2).is_empty() {
+                        feedback.bolt11 = Some(tag.get_index(2).to_owned());
+                    }
+                }
+                _ => (),
+            }
+        }
+        feedback
+    }
+
+    pub fn is_payment_required(&self) -> bool {
+        self.status == "payment-required"
+    }
+}
+
+/// One of our job requests, with the responses it got
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub request: Event,
+    pub job_type: Option<JobType>,
+    pub results: Vec<Event>,
+    pub feedback: Vec<JobFeedback>,
+}
+
+/// Our job requests about an event, with their responses, oldest first
+pub fn jobs_for_event(id: Id) -> Result<Vec<Job>, Error> {
+    let mut jobs: Vec<Job> = Vec::new();
+    for (request_id, rel) in GLOBALS.storage.find_relationships_by_id(id)? {
+        if rel != RelationshipById::JobRequest {
+            continue;
+        }
+        if let Some(request) = GLOBALS.storage.read_event(request_id)? {
+            jobs.push(job(request)?);
+        }
+    }
+    jobs.sort_by(|a, b| a.request.created_at.cmp(&b.request.created_at));
+    Ok(jobs)
+}
+
+/// Our latest content discovery job, if any
+pub fn latest_discovery() -> Result<Option<Job>, Error> {
+    let my_pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+    let kind = JobType::ContentDiscovery.request_kind();
+    let mut requests = GLOBALS
+        .storage
+        .find_events(&[kind], &[my_pubkey], None, |_| true, true)?;
+    match requests.drain(..).next() {
+        Some(request) => Ok(Some(job(request)?)),
+        None => Ok(None),
+    }
+}
+
+fn job(request: Event) -> Result<Job, Error> {
+    let mut results: Vec<Event> = Vec::new();
+    let mut feedback: Vec<JobFeedback> = Vec::new();
+    for (response_id, rel) in GLOBALS.storage.find_relationships_by_id(request.id)? {
+        match rel {
+            RelationshipById::JobResult => {
+                if let Some(event) = GLOBALS.storage.read_event(response_id)? {
+                    results.push(event);
+                }
+            }
+            RelationshipById::JobFeedback => {
+                if let Some(event) = GLOBALS.storage.read_event(response_id)? {
+                    feedback.push(JobFeedback::from_event(&event));
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(Job {
+        job_type: JobType::from_kind(request.kind),
+        request,
+        results,
+        feedback,
+    })
+}
+
+/// The events recommended by a content discovery result. Its content is a
+/// JSON array of tags.
+pub fn discovered_ids(result: &Event) -> Vec<Id> {
+    let tags: Vec<Vec<String>> = serde_json::from_str(&result.content).unwrap_or_default();
+    tags.iter()
+        .filter(|t| t.len() >= 2 && t[0] == "e")
+        .filter_map(|t| Id::try_from_hex_string(&t[1]).ok())
+        .collect()
+}
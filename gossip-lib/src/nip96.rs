@@ -0,0 +1,232 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::Tag;
+use sha2::Digest;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// How long we give the server to take the file and finish with it
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often we ask a server that is still processing the file
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A file we uploaded to a media server (NIP-96)
+#[derive(Debug, Clone)]
+pub struct Upload {
+    /// Where the file can be had from
+    pub url: String,
+
+    /// The file metadata the server gave back, as NIP-94 tags
+    pub tags: Vec<Tag>,
+}
+
+impl Upload {
+    /// An `imeta` tag (NIP-92) describing the file, for a note that links it
+    pub fn imeta_tag(&self) -> Tag {
+        let mut fields: Vec<String> = vec!["imeta".to_owned(), format!("url {}", self.url)];
+        for tag in &self.tags {
+            let name = tag.tagname();
+            if name == "url" || name.is_empty() || tag.value().is_empty() {
+                continue;
+            }
+            fields.push(format!("{} {}", name, tag.value()));
+        }
+        let fields: Vec<&str> = fields.iter().map(|s| s.as_str()).collect();
+        Tag::new(&fields)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ServerInfo {
+    api_url: String,
+
+    /// Another server whose API this one uses
+    #[serde(default)]
+    delegated_to_url: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct UploadResponse {
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    processing_url: Option<String>,
+    #[serde(default)]
+    nip94_event: Option<Nip94Event>,
+}
+
+#[derive(serde::Deserialize)]
+struct Nip94Event {
+    tags: Vec<Vec<String>>,
+}
+
+/// Upload a file to the media server in the `media_server` setting,
+/// authenticating as ourself (NIP-98)
+pub async fn upload_file(path: &Path) -> Result<Upload, Error> {
+    let server = GLOBALS.storage.read_setting_media_server();
+    let server = server.trim().trim_end_matches('/');
+    if server.is_empty() {
+        return Err(ErrorKind::General("No media server is set up".to_owned()).into());
+    }
+
+    let data = tokio::fs::read(path).await?;
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "upload".to_owned());
+    let media_type = media_type(path);
+
+    let client = crate::proxy::client_builder()?
+        .timeout(UPLOAD_TIMEOUT)
+        .build()?;
+    let api_url = api_url(&client, server).await?;
+
+    let boundary = format!("gossip-{:016x}", rand::random::<u64>());
+    let body = multipart_body(&boundary, &filename, media_type, &data);
+    let authorization = crate::nip98::authorization(&api_url, "POST", Some(&body))?;
+
+    let response = client
+        .post(&api_url)
+        .header("Authorization", authorization)
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .body(body)
+        .send()
+        .await?;
+    let mut response = read_response(response).await?;
+
+    // The server may take the file and finish with it later
+    let deadline = tokio::time::Instant::now() + UPLOAD_TIMEOUT;
+    while response.nip94_event.is_none() && response.status == "processing" {
+        let processing_url = match &response.processing_url {
+            Some(url) => url.clone(),
+            None => break,
+        };
+        if tokio::time::Instant::now() >= deadline {
+            return Err(
+                ErrorKind::General("The media server is taking too long".to_owned()).into(),
+            );
+        }
+        tokio::time::sleep(PROCESSING_POLL_INTERVAL).await;
+        response = read_response(client.get(&processing_url).send().await?).await?;
+    }
+
+    if response.status == "error" {
+        return Err(ErrorKind::General(format!(
+            "The media server refused the file: {}",
+            response.message.unwrap_or_default()
+        ))
+        .into());
+    }
+
+    let mut tags: Vec<Tag> = match response.nip94_event {
+        Some(event) => event
+            .tags
+            .iter()
+            .map(|t| Tag::new(&t.iter().map(|s| s.as_str()).collect::<Vec<&str>>()))
+            .collect(),
+        None => vec![],
+    };
+    let url = match tags.iter().find(|t| t.tagname() == "url") {
+        Some(t) => t.value().to_owned(),
+        None => {
+            return Err(ErrorKind::General(
+                "The media server did not say where the file is".to_owned(),
+            )
+            .into())
+        }
+    };
+
+    if !tags.iter().any(|t| t.tagname() == "ox") {
+        tags.push(Tag::new(&["ox", &file_hash(&data)]));
+    }
+
+    Ok(Upload { url, tags })
+}
+
+// Where to send files, from the server's /.well-known/nostr/nip96.json
+async fn api_url(client: &reqwest::Client, server: &str) -> Result<String, Error> {
+    let mut server = server.to_owned();
+
+    // Follow at most one delegation, as servers may not delegate further
+    for _ in 0..2 {
+        let bytes = client
+            .get(format!("{}/.well-known/nostr/nip96.json", server))
+            .send()
+            .await?
+            .bytes()
+            .await?;
+        GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+        let info: ServerInfo = serde_json::from_slice(&bytes)?;
+        match info.delegated_to_url {
+            Some(url) if info.api_url.is_empty() => {
+                server = url.trim_end_matches('/').to_owned();
+            }
+            _ => return Ok(info.api_url),
+        }
+    }
+
+    Err(ErrorKind::General("The media server has no upload API".to_owned()).into())
+}
+
+async fn read_response(response: reqwest::Response) -> Result<UploadResponse, Error> {
+    let status = response.status();
+    let bytes = response.bytes().await?;
+    GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+    match serde_json::from_slice::<UploadResponse>(&bytes) {
+        Ok(r) => Ok(r),
+        Err(_) => Err(ErrorKind::General(format!("The media server answered {}", status)).into()),
+    }
+}
+
+// The body of a multipart/form-data request with the file as its only field
+fn multipart_body(boundary: &str, filename: &str, media_type: &str, data: &[u8]) -> Vec<u8> {
+    let mut body: Vec<u8> = Vec::with_capacity(data.len() + 512);
+    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+    body.extend_from_slice(
+        format!(
+            "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+            filename.replace('"', "")
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", media_type).as_bytes());
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+// The media type of a file, going by its extension
+fn media_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+// The SHA-256 of a file's contents, in hex, as NIP-94 identifies files
+fn file_hash(data: &[u8]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
@@ -0,0 +1,47 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use base64::Engine;
+use nostr_types::{EventKind, PreEvent, Tag, Unixtime};
+use sha2::Digest;
+
+/// Whether we sign in to this host when fetching from it, as the
+/// `fetcher_nip98_hosts` setting lists
+pub fn host_wants_auth(host: &str) -> bool {
+    GLOBALS
+        .storage
+        .read_setting_fetcher_nip98_hosts()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .any(|h| !h.is_empty() && h.eq_ignore_ascii_case(host))
+}
+
+/// The value of an Authorization header proving to a web server that a
+/// request comes from us (NIP-98). It signs the URL, the method, and when
+/// there is a body, its hash.
+pub fn authorization(url: &str, method: &str, body: Option<&[u8]>) -> Result<String, Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(crate::error::ErrorKind::NoPublicKey.into()),
+    };
+
+    let mut tags = vec![Tag::new(&["u", url]), Tag::new(&["method", method])];
+    if let Some(body) = body {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(body);
+        let payload = hex::encode(hasher.finalize());
+        tags.push(Tag::new(&["payload", &payload]));
+    }
+
+    let event = GLOBALS.identity.sign_event(PreEvent {
+        pubkey: public_key,
+        created_at: Unixtime::now().unwrap(),
+        kind: EventKind::HttpAuth,
+        tags,
+        content: "".to_owned(),
+    })?;
+
+    let json = serde_json::to_string(&event)?;
+    Ok(format!(
+        "Nostr {}",
+        base64::engine::general_purpose::STANDARD.encode(json)
+    ))
+}
@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::USER_AGENT;
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use nostr_types::{Event, EventKind, PublicKey, RelayInformationDocument, RelayUrl, Unixtime};
+use std::time::Duration;
+
+/// Starter packs (NIP-51), lists of people to follow put together for newcomers
+pub const STARTER_PACK_KIND: u32 = 39089;
+
+pub fn starter_pack_kinds() -> Vec<EventKind> {
+    vec![EventKind::from(STARTER_PACK_KIND), EventKind::FollowSets]
+}
+
+/// What a relay's NIP-11 document says about whether it suits a newcomer
+#[derive(Clone, Debug, Default)]
+pub struct RelayCapabilities {
+    /// The relay answered with a NIP-11 document
+    pub documented: bool,
+    pub auth_required: bool,
+    pub payment_required: bool,
+
+    /// It does NIP-42 authentication, which private direct messages want
+    pub supports_auth: bool,
+
+    /// It does NIP-50 search
+    pub supports_search: bool,
+}
+
+impl RelayCapabilities {
+    pub fn from_document(doc: &RelayInformationDocument) -> RelayCapabilities {
+        let limitation = doc.limitation.as_ref();
+        RelayCapabilities {
+            documented: true,
+            auth_required: limitation.and_then(|l| l.auth_required).unwrap_or(false),
+            payment_required: limitation.and_then(|l| l.payment_required).unwrap_or(false),
+            supports_auth: doc.supports_nip(42),
+            supports_search: doc.supports_nip(50),
+        }
+    }
+
+    /// Anybody can read from it and write to it without paying or signing up
+    pub fn is_open(&self) -> bool {
+        !self.auth_required && !self.payment_required
+    }
+}
+
+lazy_static! {
+    static ref CHECKING: DashSet<RelayUrl> = DashSet::new();
+}
+
+/// What we know of a relay's capabilities. This returns None until it has been
+/// checked, and starts checking it in the background. Callers that ask often
+/// should keep the answer rather than asking again.
+pub fn relay_capabilities(url: &RelayUrl) -> Option<RelayCapabilities> {
+    if CHECKING.contains(url) {
+        return None;
+    }
+
+    if let Some(relay) = GLOBALS.storage.read_relay(url).ok()? {
+        if let Some(doc) = &relay.nip11 {
+            return Some(RelayCapabilities::from_document(doc));
+        }
+        if relay.last_attempt_nip11.is_some() {
+            // It was asked and had nothing to say
+            return Some(RelayCapabilities::default());
+        }
+    }
+
+    if CHECKING.insert(url.clone()) {
+        let url = url.clone();
+        tokio::task::spawn(async move {
+            if let Err(e) = fetch_nip11(&url).await {
+                tracing::warn!("{}: Unable to check NIP-11: {}", &url, e);
+            }
+            CHECKING.remove(&url);
+        });
+    }
+
+    None
+}
+
+// Fetch a relay's NIP-11 document without connecting to it, and keep it
+async fn fetch_nip11(url: &RelayUrl) -> Result<(), Error> {
+    let http_url = url
+        .as_str()
+        .replacen("wss://", "https://", 1)
+        .replacen("ws://", "http://", 1);
+
+    let mut req = crate::proxy::client_builder_for_relay(url.as_str())?
+        .timeout(Duration::from_secs(
+            GLOBALS.storage.read_setting_fetcher_timeout_sec(),
+        ))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?
+        .get(http_url)
+        .header("Accept", "application/nostr+json");
+    if GLOBALS.storage.read_setting_set_user_agent() {
+        req = req.header("User-Agent", USER_AGENT);
+    }
+    let result = match req.send().await {
+        Ok(response) => response.json::<RelayInformationDocument>().await.ok(),
+        Err(_) => None,
+    };
+
+    let mut relay = GLOBALS.storage.read_or_create_relay(url, None)?;
+    relay.last_attempt_nip11 = Some(Unixtime::now().unwrap().0 as u64);
+    if result.is_some() {
+        relay.nip11 = result;
+    }
+    GLOBALS.storage.write_relay(&relay, None)?;
+    Ok(())
+}
+
+/// A published list of people to follow
+#[derive(Clone, Debug)]
+pub struct StarterPack {
+    pub title: String,
+    pub description: Option<String>,
+    pub pubkeys: Vec<PublicKey>,
+    pub event: Event,
+}
+
+impl StarterPack {
+    pub fn from_event(event: &Event) -> Option<StarterPack> {
+        if !starter_pack_kinds().contains(&event.kind) {
+            return None;
+        }
+
+        let mut title = None;
+        let mut description = None;
+        let mut pubkeys: Vec<PublicKey> = Vec::new();
+        for tag in &event.tags {
+            match tag.tagname() {
+                "title" => title = Some(tag.value().to_owned()),
+                "description" => description = Some(tag.value().to_owned()),
+                "p" => {
+                    if let Ok((pk, _, _)) = tag.parse_pubkey() {
+                        if !pubkeys.contains(&pk) {
+                            pubkeys.push(pk);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Some(StarterPack {
+            // Follow sets without a title are usually private or for the
+            // author's own use
+            title: title.filter(|t| !t.trim().is_empty())?,
+            description: description.filter(|d| !d.trim().is_empty()),
+            pubkeys,
+            event: event.clone(),
+        })
+    }
+}
+
+/// Starter packs and titled follow sets we have, with enough people in them to
+/// be worth following, biggest first
+pub fn starter_packs() -> Result<Vec<StarterPack>, Error> {
+    let mut packs: Vec<StarterPack> = GLOBALS
+        .storage
+        .find_events(&starter_pack_kinds(), &[], None, |_| true, false)?
+        .iter()
+        .filter_map(StarterPack::from_event)
+        .filter(|p| p.pubkeys.len() >= 3)
+        .collect();
+    packs.sort_by(|a, b| b.pubkeys.len().cmp(&a.pubkeys.len()));
+    Ok(packs)
+}
@@ -24,6 +24,8 @@ impl Minion {
 
         match relay_message {
             RelayMessage::Event(subid, event) => {
+                self.dbrelay.events_received += 1;
+
                 let handle = self
                     .subscription_map
                     .get_handle_by_id(&subid.0)
@@ -106,6 +108,12 @@ impl Minion {
                 // If this is a temporary subscription, we should close it after an EOSE
                 let close: bool = handle.starts_with("temp_");
 
+                // Time from the REQ to its EOSE
+                if let Some(sent) = self.req_sent_at.remove(&handle) {
+                    self.dbrelay
+                        .record_eose_ms(sent.elapsed().as_millis() as u64);
+                }
+
                 // Update the matching subscription
                 match self.subscription_map.get_mut_by_id(&subid.0) {
                     Some(sub) => {
@@ -162,10 +170,49 @@ impl Minion {
                             self.auth_state = AuthState::Authenticated;
                             self.try_subscribe_waiting().await?;
                         }
+                        self.post_waiting_for_auth().await?;
+                    }
+                }
+
+                // A relay that wants us authenticated first says so. Post it
+                // again once we are, unless we already were.
+                if !ok
+                    && ok_message.starts_with("auth-required")
+                    && self.dbrelay.allow_auth != Some(false)
+                    && GLOBALS.identity.is_unlocked()
+                    && matches!(self.auth_state, AuthState::None | AuthState::Waiting(_))
+                {
+                    if let Some(event) = self.postings.remove(&id) {
+                        tracing::info!("{}: will post {} again after AUTH", &self.url, idhex);
+                        // Its job completed when it was posted
+                        self.postings_waiting_for_auth.push((0, event));
+                        if self.auth_state == AuthState::None {
+                            self.maybe_authenticate().await?;
+                        }
+                        return Ok(());
                     }
                 }
 
-                if self.postings.contains(&id) {
+                // Post it again later, and more slowly
+                if !ok && ok_message.starts_with("rate-limited") {
+                    if let Some(event) = self.postings.remove(&id) {
+                        crate::relay_penalty::rate_limited(&self.url);
+                        self.postings_rate_limited.push(event);
+                        tracing::warn!("{relay_response}");
+                        return Ok(());
+                    }
+                }
+
+                // Keep it to post again once the relay is paid
+                if !ok && crate::relay_payment::is_payment_message(&ok_message) {
+                    if let Some(event) = self.postings.remove(&id) {
+                        crate::relay_payment::payment_required(&self.url, Some(event));
+                        tracing::warn!("{relay_response}");
+                        return Ok(());
+                    }
+                }
+
+                if self.postings.contains_key(&id) {
                     if ok {
                         // Save seen_on data
                         // (it was already processed by the overlord before the minion got it,
@@ -190,15 +237,7 @@ impl Minion {
             }
             RelayMessage::Auth(challenge) => {
                 self.auth_challenge = challenge.to_owned();
-                if GLOBALS.storage.read_setting_relay_auth_requires_approval() {
-                    match self.dbrelay.allow_auth {
-                        Some(true) => self.authenticate().await?,
-                        Some(false) => (),
-                        None => GLOBALS.auth_requests.write().push(self.url.clone()),
-                    }
-                } else {
-                    self.authenticate().await?
-                }
+                self.maybe_authenticate().await?;
             }
             RelayMessage::Closed(subid, message) => {
                 let handle = self
@@ -210,6 +249,10 @@ impl Minion {
                     tracing::info!("{}: Closed: {}: {}", &self.url, handle, message);
                 }
 
+                if crate::relay_payment::is_payment_message(&message) {
+                    crate::relay_payment::payment_required(&self.url, None);
+                }
+
                 // Check the machine-readable prefix
                 if let Some(prefix) = message.split(':').next() {
                     match prefix {
@@ -229,7 +272,8 @@ impl Minion {
                             );
                         }
                         "rate-limited" => {
-                            // Wait to retry later
+                            // Wait to retry later, and more slowly
+                            crate::relay_penalty::rate_limited(&self.url);
                             self.subscriptions_rate_limited.push(handle);
 
                             // return now, don't remove sub from map
@@ -259,8 +303,8 @@ impl Minion {
                             } else {
                                 match self.auth_state {
                                     AuthState::None => {
-                                        // authenticate
-                                        self.authenticate().await?;
+                                        // authenticate (or ask whether we may)
+                                        self.maybe_authenticate().await?;
 
                                         // cork and retry once auth completes
                                         self.subscriptions_waiting_for_auth
@@ -290,6 +334,14 @@ impl Minion {
                                 }
                             }
                         }
+                        "payment-required" => {
+                            tracing::warn!(
+                                "{} won't serve our {} sub until paid",
+                                &self.url,
+                                &handle
+                            );
+                            self.failed_subs.insert(handle.clone());
+                        }
                         "restricted" => {
                             tracing::warn!(
                                 "{} won't serve our {} sub (says restricted)",
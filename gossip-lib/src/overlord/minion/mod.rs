@@ -16,14 +16,14 @@ use http::uri::{Parts, Scheme};
 use http::Uri;
 use mime::Mime;
 use nostr_types::{
-    ClientMessage, EventAddr, EventKind, Filter, Id, IdHex, PreEvent, PublicKey, PublicKeyHex,
-    RelayInformationDocument, RelayUrl, Tag, Unixtime,
+    ClientMessage, Event, EventAddr, EventKind, Filter, Id, IdHex, PreEvent, PublicKey,
+    PublicKeyHex, RelayInformationDocument, RelayUrl, Tag, Unixtime,
 };
 use reqwest::Response;
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subscription_map::SubscriptionMap;
 use tokio::net::TcpStream;
 use tokio::select;
@@ -32,6 +32,9 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use tungstenite::protocol::{Message as WsMessage, WebSocketConfig};
 
+// How often a connected minion saves the relay's latency and traffic
+const HEALTH_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuthState {
     None,
@@ -73,9 +76,16 @@ pub struct Minion {
     stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
     subscription_map: SubscriptionMap,
     next_events_subscription_id: u32,
-    postings: HashSet<Id>,
+    postings: HashMap<Id, Event>,
+    postings_waiting_for_auth: Vec<(u64, Event)>, // with the id of their job
+    postings_rate_limited: Vec<Event>,
     sought_events: HashMap<Id, EventSeekState>,
     last_message_sent: String,
+    last_throttled_send: Option<Instant>,
+    send_queue: VecDeque<(String, Option<String>)>, // with the handle of a REQ
+    req_sent_at: HashMap<String, Instant>,
+    ping_sent_at: Option<Instant>,
+    health_saved_at: Instant,
     auth_challenge: String,
     subscriptions_waiting_for_auth: Vec<(String, Unixtime)>,
     subscriptions_waiting_for_metadata: Vec<(u64, Vec<PublicKey>)>,
@@ -103,9 +113,16 @@ impl Minion {
             stream: None,
             subscription_map: SubscriptionMap::new(),
             next_events_subscription_id: 0,
-            postings: HashSet::new(),
+            postings: HashMap::new(),
+            postings_waiting_for_auth: Vec::new(),
+            postings_rate_limited: Vec::new(),
             sought_events: HashMap::new(),
             last_message_sent: String::new(),
+            last_throttled_send: None,
+            send_queue: VecDeque::new(),
+            req_sent_at: HashMap::new(),
+            ping_sent_at: None,
+            health_saved_at: Instant::now(),
             auth_challenge: "".to_string(),
             subscriptions_waiting_for_auth: Vec::new(),
             subscriptions_waiting_for_metadata: Vec::new(),
@@ -159,7 +176,7 @@ impl Minion {
             let uri = http::Uri::from_parts(parts)?;
 
             // Fetch NIP-11 data
-            let request_nip11_future = reqwest::Client::builder()
+            let request_nip11_future = crate::proxy::client_builder_for_relay(self.url.as_str())?
                 .timeout(fetcher_timeout)
                 .redirect(reqwest::redirect::Policy::none())
                 .gzip(true)
@@ -274,7 +291,7 @@ impl Minion {
 
             let (websocket_stream, response) = tokio::time::timeout(
                 std::time::Duration::new(connect_timeout_secs, 0),
-                tokio_tungstenite::connect_async_with_config(req, Some(config), false),
+                crate::proxy::connect_websocket(req, Some(config)),
             )
             .await??;
 
@@ -309,6 +326,8 @@ impl Minion {
                     tracing::warn!("{}", e);
 
                     if let ErrorKind::Websocket(_) = e.kind {
+                        self.save_health();
+                        self.report_unposted();
                         return Err(e);
                     }
 
@@ -317,6 +336,9 @@ impl Minion {
             }
         }
 
+        self.save_health();
+        self.report_unposted();
+
         // Close the connection
         let ws_stream = self.stream.as_mut().unwrap();
         if !ws_stream.is_terminated() {
@@ -335,6 +357,9 @@ impl Minion {
     }
 
     async fn loop_handler(&mut self) -> Result<(), Error> {
+        // Messages held back for a relay that rate-limited us
+        let next_send_at = self.next_send_at();
+
         let ws_stream = self.stream.as_mut().unwrap();
 
         // Ping timer
@@ -354,6 +379,10 @@ impl Minion {
             biased;
             _ = ping_timer.tick() => {
                 ws_stream.send(WsMessage::Ping(vec![0x1])).await?;
+                self.ping_sent_at = Some(Instant::now());
+            },
+            _ = tokio::time::sleep_until(next_send_at.unwrap_or_else(Instant::now).into()), if next_send_at.is_some() => {
+                self.send_queued().await?;
             },
             _ = task_timer.tick()  => {
                 // Update subscription for sought events
@@ -361,6 +390,10 @@ impl Minion {
 
                 // Try to subscribe to subscriptions waiting for something
                 self.try_subscribe_waiting().await?;
+
+                if self.health_saved_at.elapsed() > HEALTH_SAVE_INTERVAL {
+                    self.save_health();
+                }
             },
             to_minion_message = self.from_overlord.recv() => {
                 let to_minion_message = match to_minion_message {
@@ -389,6 +422,7 @@ impl Minion {
                 }?;
 
                 GLOBALS.bytes_read.fetch_add(ws_message.len(), Ordering::Relaxed);
+                self.dbrelay.bytes_received += ws_message.len() as u64;
 
                 tracing::trace!("{}: Handling message", &self.url);
                 match ws_message {
@@ -401,7 +435,12 @@ impl Minion {
                     },
                     WsMessage::Binary(_) => tracing::warn!("{}, Unexpected binary message", &self.url),
                     WsMessage::Ping(_) => { }, // tungstenite automatically pongs.
-                    WsMessage::Pong(_) => { }, // Verify it is 0x1? Nah. It's just for keep-alive.
+                    WsMessage::Pong(_) => {
+                        // Verify it is 0x1? Nah. It's just for keep-alive, and timing.
+                        if let Some(sent) = self.ping_sent_at.take() {
+                            self.dbrelay.record_ping_ms(sent.elapsed().as_millis() as u64);
+                        }
+                    },
                     WsMessage::Close(_) => {
                         self.exiting = Some(MinionExitReason::GotWSClose);
                     }
@@ -410,8 +449,12 @@ impl Minion {
             },
         }
 
-        // Don't continue if we have no more subscriptions
-        if self.subscription_map.is_empty() {
+        // Don't continue if we have no more subscriptions, nor events to post
+        // again
+        if self.subscription_map.is_empty()
+            && self.postings_rate_limited.is_empty()
+            && self.send_queue.is_empty()
+        {
             self.exiting = Some(MinionExitReason::SubscriptionsHaveCompleted);
         }
 
@@ -424,13 +467,10 @@ impl Minion {
     ) -> Result<(), Error> {
         match message.detail {
             ToMinionPayloadDetail::AdvertiseRelayList(event) => {
-                let id = event.id;
-                self.postings.insert(id);
+                self.postings.insert(event.id, (*event).clone());
                 let msg = ClientMessage::Event(event);
                 let wire = serde_json::to_string(&msg)?;
-                let ws_stream = self.stream.as_mut().unwrap();
-                self.last_message_sent = wire.clone();
-                ws_stream.send(WsMessage::Text(wire)).await?;
+                self.send_spaced(wire, None).await?;
                 tracing::info!("Advertised relay list to {}", &self.url);
                 self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
                     self.url.clone(),
@@ -445,6 +485,10 @@ impl Minion {
             ToMinionPayloadDetail::AuthDeclined => {
                 self.dbrelay.allow_auth = Some(false); // save in our memory copy of the relay
                 GLOBALS.auth_requests.write().retain(|url| *url != self.url);
+                self.post_waiting_for_auth().await?;
+            }
+            ToMinionPayloadDetail::FetchCalendarEvents => {
+                self.get_calendar_events(message.job_id).await?;
             }
             ToMinionPayloadDetail::FetchEvent(id) => {
                 self.sought_events
@@ -461,21 +505,49 @@ impl Minion {
                 // anyways we would have to create a parallel thing.
                 self.get_event_addr(message.job_id, ea).await?;
             }
+            ToMinionPayloadDetail::FetchHandlers(kind) => {
+                self.get_handlers(message.job_id, kind).await?;
+            }
+            ToMinionPayloadDetail::FetchIdentityMentions(pubkeys, since) => {
+                self.get_identity_mentions(message.job_id, pubkeys, since)
+                    .await?;
+            }
+            ToMinionPayloadDetail::FetchLongFormArticles => {
+                self.get_long_form_articles(message.job_id).await?;
+            }
+            ToMinionPayloadDetail::FetchMarketplace => {
+                self.get_marketplace(message.job_id).await?;
+            }
+            ToMinionPayloadDetail::FetchStarterPacks => {
+                self.get_starter_packs(message.job_id).await?;
+            }
+            ToMinionPayloadDetail::FetchWikiArticles(d) => {
+                self.get_wiki_articles(message.job_id, d).await?;
+            }
             ToMinionPayloadDetail::PostEvents(mut events) => {
+                let mut held = false;
                 for event in events.drain(..) {
-                    let id = event.id;
-                    self.postings.insert(id);
-                    let msg = ClientMessage::Event(Box::new(event));
-                    let wire = serde_json::to_string(&msg)?;
-                    let ws_stream = self.stream.as_mut().unwrap();
-                    self.last_message_sent = wire.clone();
-                    ws_stream.send(WsMessage::Text(wire)).await?;
-                    tracing::info!("Posted event to {}", &self.url);
+                    if self.needs_auth_before_posting(&event) {
+                        self.postings_waiting_for_auth.push((message.job_id, event));
+                        held = true;
+                        if self.auth_state == AuthState::None {
+                            self.maybe_authenticate().await?;
+                        }
+                    } else {
+                        self.post_event(event).await?;
+                    }
                 }
-                self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
-                    self.url.clone(),
-                    message.job_id,
-                ))?;
+                // If some are held, the job completes once they are posted,
+                // so that the overlord does not disconnect us before then
+                if !held {
+                    self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                        self.url.clone(),
+                        message.job_id,
+                    ))?;
+                }
+            }
+            ToMinionPayloadDetail::SearchNotes(text) => {
+                self.search_notes(message.job_id, text).await?;
             }
             ToMinionPayloadDetail::Shutdown => {
                 tracing::debug!("{}: Websocket listener shutting down", &self.url);
@@ -484,6 +556,10 @@ impl Minion {
             ToMinionPayloadDetail::SubscribeAugments(ids) => {
                 self.subscribe_augments(message.job_id, ids).await?;
             }
+            ToMinionPayloadDetail::SubscribeCommunityFeed(community) => {
+                self.subscribe_community_feed(message.job_id, community)
+                    .await?;
+            }
             ToMinionPayloadDetail::SubscribeGeneralFeed(pubkeys) => {
                 self.subscribe_general_feed(message.job_id, pubkeys).await?;
             }
@@ -525,6 +601,9 @@ impl Minion {
                 self.temp_subscribe_metadata(message.job_id, pubkeys)
                     .await?;
             }
+            ToMinionPayloadDetail::UnsubscribeCommunityFeed => {
+                self.unsubscribe("community_feed").await?;
+            }
             ToMinionPayloadDetail::UnsubscribePersonFeed => {
                 self.unsubscribe("person_feed").await?;
             }
@@ -539,6 +618,19 @@ impl Minion {
     async fn subscribe_augments(&mut self, job_id: u64, ids: Vec<IdHex>) -> Result<(), Error> {
         let mut event_kinds = crate::feed::feed_related_event_kinds(false);
         event_kinds.retain(|f| f.augments_feed_related());
+        if GLOBALS.storage.read_setting_verify_timestamps()
+            && !event_kinds.contains(&EventKind::Timestamp)
+        {
+            event_kinds.push(EventKind::Timestamp);
+        }
+        // Reports (NIP-56) by people we follow count against spam
+        if !event_kinds.contains(&EventKind::Reporting) {
+            event_kinds.push(EventKind::Reporting);
+        }
+        // and so do labels (NIP-32)
+        if !event_kinds.contains(&EventKind::Label) {
+            event_kinds.push(EventKind::Label);
+        }
 
         let filter = {
             let mut filter = Filter {
@@ -601,12 +693,22 @@ impl Minion {
 
             // feed related by people followed
             filters.push(Filter {
-                authors: pkp,
+                authors: pkp.clone(),
                 kinds: event_kinds.clone(),
                 since: Some(since),
                 ..Default::default()
             });
 
+            // user statuses of people followed
+            if GLOBALS.storage.read_setting_show_user_status() {
+                filters.push(Filter {
+                    authors: pkp,
+                    kinds: vec![EventKind::UserStatus],
+                    since: Some(since),
+                    ..Default::default()
+                });
+            }
+
             // Try to find where people post.
             // Subscribe to kind-10002 `RelayList`s to see where people post.
             // Subscribe to ContactLists so we can look at the contents and
@@ -717,6 +819,46 @@ impl Minion {
             };
             filters.push(filter);
 
+            // Responses to our DVM job requests
+            let filter = {
+                let mut filter = Filter {
+                    kinds: crate::nip90::response_kinds(),
+                    since: Some(replies_since),
+                    ..Default::default()
+                };
+                let values = vec![pkh.to_string()];
+                filter.set_tag_values('p', values);
+                filter
+            };
+            filters.push(filter);
+
+            // Contact lists with me in them, to find new followers to follow back
+            if GLOBALS.storage.read_setting_follow_back_enabled() {
+                let filter = {
+                    let mut filter = Filter {
+                        kinds: vec![EventKind::ContactList],
+                        since: Some(replies_since),
+                        ..Default::default()
+                    };
+                    let values = vec![pkh.to_string()];
+                    filter.set_tag_values('p', values);
+                    filter
+                };
+                filters.push(filter);
+            }
+
+            // Badges awarded to me. These are rare, so we look back all the way.
+            let filter = {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::BadgeAward],
+                    ..Default::default()
+                };
+                let values = vec![pkh.to_string()];
+                filter.set_tag_values('p', values);
+                filter
+            };
+            filters.push(filter);
+
             // Giftwrap specially looks back further
             // Giftwraps cannot be filtered by author so we have to take them regardless
             // of the spamsafe designation of the relay.
@@ -764,7 +906,7 @@ impl Minion {
 
             // Read back in things that we wrote out to our write relays
             // that we need
-            let filters: Vec<Filter> = vec![
+            let mut filters: Vec<Filter> = vec![
                 // Actual config stuff
                 Filter {
                     authors: vec![pkh.clone()],
@@ -775,6 +917,8 @@ impl Minion {
                         EventKind::MuteList,
                         EventKind::FollowSets,
                         EventKind::RelayList,
+                        EventKind::ProfileBadges,
+                        crate::nip17::dm_relay_list_kind(),
                     ],
                     // these are all replaceable, no since required
                     ..Default::default()
@@ -789,13 +933,24 @@ impl Minion {
                 // Events I posted recently, including feed_displayable and
                 //  augments (deletions, reactions, timestamp, label,reporting, and zap)
                 Filter {
-                    authors: vec![pkh],
+                    authors: vec![pkh.clone()],
                     kinds: crate::feed::feed_related_event_kinds(false), // not DMs
                     since: Some(since),
                     ..Default::default()
                 },
             ];
 
+            // Our settings, synced between gossip installs (NIP-78)
+            if GLOBALS.storage.read_setting_settings_sync() {
+                let mut filter = Filter {
+                    authors: vec![pkh],
+                    kinds: vec![EventKind::AppSpecificData],
+                    ..Default::default()
+                };
+                filter.set_tag_values('d', vec![crate::settings_sync::SETTINGS_SYNC_D.to_owned()]);
+                filters.push(filter);
+            }
+
             self.subscribe(filters, "config_feed", job_id).await?;
         }
 
@@ -813,7 +968,7 @@ impl Minion {
 
             let filters: Vec<Filter> = vec![Filter {
                 authors: pkp,
-                kinds: vec![EventKind::RelayList],
+                kinds: vec![EventKind::RelayList, crate::nip17::dm_relay_list_kind()],
                 // these are all replaceable, no since required
                 ..Default::default()
             }];
@@ -825,6 +980,49 @@ impl Minion {
         Ok(())
     }
 
+    // Subscribe to a moderated community (NIP-72): its definition, the
+    // approvals of its moderators, and the posts made to it
+    async fn subscribe_community_feed(
+        &mut self,
+        job_id: u64,
+        community: EventAddr,
+    ) -> Result<(), Error> {
+        let a_value = crate::community::a_tag_value(&community);
+
+        let mut filters: Vec<Filter> = Vec::new();
+
+        // The community definition (for the moderator list)
+        {
+            let pkh: PublicKeyHex = community.author.into();
+            let mut filter = Filter {
+                authors: vec![pkh],
+                kinds: vec![EventKind::CommunityDefinition],
+                ..Default::default()
+            };
+            filter.set_tag_values('d', vec![community.d.clone()]);
+            filters.push(filter);
+        }
+
+        // Approvals, and posts made to the community
+        {
+            let mut filter = Filter {
+                kinds: vec![
+                    EventKind::CommunityPostApproval,
+                    EventKind::TextNote,
+                    EventKind::CommunityPost,
+                ],
+                limit: Some(500),
+                ..Default::default()
+            };
+            filter.set_tag_values('a', vec![a_value]);
+            filters.push(filter);
+        }
+
+        self.subscribe(filters, "community_feed", job_id).await?;
+
+        Ok(())
+    }
+
     // Subscribe to the posts a person generates on the relays they write to
     async fn subscribe_person_feed(&mut self, job_id: u64, pubkey: PublicKey) -> Result<(), Error> {
         // NOTE we do not unsubscribe to the general feed
@@ -1203,6 +1401,16 @@ impl Minion {
             }
         }
 
+        // Post rate-limited events again
+        for event in std::mem::take(&mut self.postings_rate_limited) {
+            tracing::info!(
+                "Posting previously rate-limited event {} to {}",
+                event.id.as_hex_string(),
+                &self.url
+            );
+            self.post_event(event).await?;
+        }
+
         Ok(())
     }
 
@@ -1221,6 +1429,229 @@ impl Minion {
         self.subscribe(vec![filter], &handle, job_id).await
     }
 
+    async fn get_handlers(&mut self, job_id: u64, kind: EventKind) -> Result<(), Error> {
+        // create a handle for ourselves
+        let handle = format!("temp_handlers_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        let kind_number: u32 = kind.into();
+        let kind_str = format!("{}", kind_number);
+
+        let mut filters: Vec<Filter> = Vec::new();
+
+        // Recommendations by people we follow
+        let pkp: Vec<PublicKeyHex> = GLOBALS
+            .people
+            .get_subscribed_pubkeys()
+            .drain(..)
+            .map(|pk| pk.into())
+            .collect();
+        if !pkp.is_empty() {
+            let mut filter = Filter {
+                authors: pkp,
+                kinds: vec![EventKind::HandlerRecommendation],
+                ..Default::default()
+            };
+            filter.set_tag_values('d', vec![kind_str.clone()]);
+            filters.push(filter);
+        }
+
+        // Handlers announcing support for this kind
+        let mut filter = Filter {
+            kinds: vec![EventKind::HandlerInformation],
+            limit: Some(50),
+            ..Default::default()
+        };
+        filter.set_tag_values('k', vec![kind_str]);
+        filters.push(filter);
+
+        self.subscribe(filters, &handle, job_id).await
+    }
+
+    async fn get_long_form_articles(&mut self, job_id: u64) -> Result<(), Error> {
+        let mut pubkeys = GLOBALS.people.get_subscribed_pubkeys();
+        if let Some(pk) = GLOBALS.identity.public_key() {
+            pubkeys.push(pk);
+        }
+        let pkp: Vec<PublicKeyHex> = pubkeys.drain(..).map(|pk| pk.into()).collect();
+
+        // create a handle for ourselves
+        let handle = format!("temp_long_form_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // Recent articles by people we follow, and our own
+        let filter = Filter {
+            authors: pkp,
+            kinds: vec![EventKind::LongFormContent],
+            limit: Some(200),
+            ..Default::default()
+        };
+
+        self.subscribe(vec![filter], &handle, job_id).await
+    }
+
+    async fn get_calendar_events(&mut self, job_id: u64) -> Result<(), Error> {
+        let mut pkp: Vec<PublicKeyHex> = GLOBALS
+            .people
+            .get_subscribed_pubkeys()
+            .drain(..)
+            .map(|pk| pk.into())
+            .collect();
+        if let Some(pk) = GLOBALS.identity.public_key() {
+            pkp.push(pk.into());
+        }
+        if pkp.is_empty() {
+            return Ok(());
+        }
+
+        // create a handle for ourselves
+        let handle = format!("temp_calendar_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // Calendar events of people we follow, and RSVPs to them
+        let filter = Filter {
+            authors: pkp,
+            kinds: crate::nip52::calendar_event_kinds(),
+            ..Default::default()
+        };
+        let mut filters = vec![filter];
+
+        let addresses: Vec<String> = crate::nip52::upcoming_from_follows()?
+            .iter()
+            .map(|c| c.a_tag_value())
+            .collect();
+        if !addresses.is_empty() {
+            let mut filter = Filter {
+                kinds: vec![EventKind::CalendarEventRsvp],
+                ..Default::default()
+            };
+            filter.set_tag_values('a', addresses);
+            filters.push(filter);
+        }
+
+        self.subscribe(filters, &handle, job_id).await
+    }
+
+    async fn get_marketplace(&mut self, job_id: u64) -> Result<(), Error> {
+        let pkp: Vec<PublicKeyHex> = GLOBALS
+            .people
+            .get_subscribed_pubkeys()
+            .drain(..)
+            .map(|pk| pk.into())
+            .collect();
+        if pkp.is_empty() {
+            return Ok(());
+        }
+
+        // create a handle for ourselves
+        let handle = format!("temp_marketplace_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // Stalls and products of people we follow
+        let filter = Filter {
+            authors: pkp,
+            kinds: crate::nip15::marketplace_kinds(),
+            ..Default::default()
+        };
+
+        self.subscribe(vec![filter], &handle, job_id).await
+    }
+
+    async fn get_identity_mentions(
+        &mut self,
+        job_id: u64,
+        pubkeys: Vec<PublicKey>,
+        since: Unixtime,
+    ) -> Result<(), Error> {
+        // create a handle for ourselves
+        let handle = format!(
+            "temp_identity_mentions_{}",
+            self.next_events_subscription_id
+        );
+        self.next_events_subscription_id += 1;
+
+        let values: Vec<String> = pubkeys.iter().map(|pk| pk.as_hex_string()).collect();
+
+        // Anything addressed to them, like the mentions feed of our own user
+        let mut event_kinds = crate::feed::feed_related_event_kinds(true);
+        event_kinds.retain(|f| *f != EventKind::GiftWrap);
+        let mut filter = Filter {
+            kinds: event_kinds,
+            since: Some(since),
+            ..Default::default()
+        };
+        filter.set_tag_values('p', values.clone());
+
+        // GiftWraps are backdated, so look a week further back for them
+        let mut giftwrap_filter = Filter {
+            kinds: vec![EventKind::GiftWrap],
+            since: Some(Unixtime(since.0 - 60 * 60 * 24 * 7)),
+            ..Default::default()
+        };
+        giftwrap_filter.set_tag_values('p', values);
+
+        self.subscribe(vec![filter, giftwrap_filter], &handle, job_id)
+            .await
+    }
+
+    async fn get_starter_packs(&mut self, job_id: u64) -> Result<(), Error> {
+        // create a handle for ourselves
+        let handle = format!("temp_starter_packs_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // The latest starter packs and follow sets, by anybody
+        let filter = Filter {
+            kinds: crate::onboarding::starter_pack_kinds(),
+            limit: Some(100),
+            ..Default::default()
+        };
+
+        self.subscribe(vec![filter], &handle, job_id).await
+    }
+
+    async fn get_wiki_articles(&mut self, job_id: u64, d: String) -> Result<(), Error> {
+        // create a handle for ourselves
+        let handle = format!("temp_wiki_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // Articles on this topic by anybody
+        let mut filter = Filter {
+            kinds: vec![crate::nip54::wiki_kind()],
+            limit: Some(50),
+            ..Default::default()
+        };
+        filter.set_tag_values('d', vec![d]);
+
+        self.subscribe(vec![filter], &handle, job_id).await
+    }
+
+    async fn search_notes(&mut self, job_id: u64, text: String) -> Result<(), Error> {
+        // create a handle for ourselves
+        let handle = format!("temp_search_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        // Notes by anybody that the relay finds for this text (NIP-50)
+        let filter = Filter {
+            kinds: crate::feed::feed_displayable_event_kinds(false),
+            limit: Some(100),
+            ..Default::default()
+        };
+
+        self.subscription_map.add(&handle, job_id, vec![filter]);
+        if let Some(sub) = self.subscription_map.get_mut(&handle) {
+            sub.set_search(text);
+        }
+
+        if matches!(self.auth_state, AuthState::Waiting(_)) {
+            // Save this, subscribe after AUTH completes
+            self.subscriptions_waiting_for_auth
+                .push((handle.to_owned(), Unixtime::now().unwrap()));
+            return Ok(());
+        }
+
+        self.send_subscription(&handle).await
+    }
+
     // Load more, one more chunk back
     async fn temp_subscribe_general_feed_chunk(
         &mut self,
@@ -1297,7 +1728,12 @@ impl Minion {
         let handle = "temp_subscribe_metadata".to_string();
         let filter = Filter {
             authors: pkhp,
-            kinds: vec![EventKind::Metadata, EventKind::RelayList],
+            kinds: vec![
+                EventKind::Metadata,
+                EventKind::RelayList,
+                EventKind::ProfileBadges,
+                EventKind::UserStatus,
+            ],
             // FIXME: we could probably get a since-last-fetched-their-metadata here.
             //        but relays should just return the lastest of these.
             ..Default::default()
@@ -1374,15 +1810,61 @@ impl Minion {
     }
 
     async fn send_subscription(&mut self, handle: &str) -> Result<(), Error> {
-        let req_message = match self.subscription_map.get(handle) {
-            Some(sub) => sub.req_message(),
+        let wire = match self.subscription_map.get(handle) {
+            Some(sub) => sub.req_wire()?,
             None => return Ok(()), // Not much we can do. It is not there.
         };
-        let wire = serde_json::to_string(&req_message)?;
-        let websocket_stream = self.stream.as_mut().unwrap();
-        tracing::trace!("{}: Sending {}", &self.url, &wire);
-        self.last_message_sent = wire.clone();
-        websocket_stream.send(WsMessage::Text(wire.clone())).await?;
+        self.send_spaced(wire, Some(handle)).await
+    }
+
+    // Send a REQ or EVENT, spacing these out for a relay that has rate-limited
+    // us. What has to wait is queued and sent from the loop as its time comes,
+    // so that we keep reading from the relay meanwhile.
+    async fn send_spaced(&mut self, wire: String, handle: Option<&str>) -> Result<(), Error> {
+        // A newer REQ for a subscription replaces one still waiting
+        if let Some(handle) = handle {
+            if let Some(queued) = self
+                .send_queue
+                .iter_mut()
+                .find(|(_, h)| h.as_deref() == Some(handle))
+            {
+                queued.0 = wire;
+                return Ok(());
+            }
+        }
+        self.send_queue
+            .push_back((wire, handle.map(|h| h.to_owned())));
+        self.send_queued().await
+    }
+
+    // When the next queued message may be sent, if any are queued
+    fn next_send_at(&self) -> Option<Instant> {
+        if self.send_queue.is_empty() {
+            return None;
+        }
+        let interval = crate::relay_penalty::send_interval(&self.url);
+        Some(match self.last_throttled_send {
+            Some(last) => last + interval,
+            None => Instant::now(),
+        })
+    }
+
+    // Send the queued messages whose time has come
+    async fn send_queued(&mut self) -> Result<(), Error> {
+        while let Some(at) = self.next_send_at() {
+            if at > Instant::now() {
+                break;
+            }
+            let (wire, handle) = self.send_queue.pop_front().unwrap();
+            let websocket_stream = self.stream.as_mut().unwrap();
+            tracing::trace!("{}: Sending {}", &self.url, &wire);
+            self.last_message_sent = wire.clone();
+            websocket_stream.send(WsMessage::Text(wire)).await?;
+            if let Some(handle) = handle {
+                self.req_sent_at.insert(handle, Instant::now());
+            }
+            self.last_throttled_send = Some(Instant::now());
+        }
         Ok(())
     }
 
@@ -1390,6 +1872,9 @@ impl Minion {
         if !self.subscription_map.has(handle) {
             return Ok(());
         }
+        // Its REQ need not go out at all now
+        self.send_queue
+            .retain(|(_, h)| h.as_deref() != Some(handle));
         let subscription = self.subscription_map.get(handle).unwrap();
         let wire = serde_json::to_string(&subscription.close_message())?;
         let websocket_stream = self.stream.as_mut().unwrap();
@@ -1418,6 +1903,86 @@ impl Minion {
         Ok(())
     }
 
+    async fn post_event(&mut self, event: Event) -> Result<(), Error> {
+        self.postings.insert(event.id, event.clone());
+        let msg = ClientMessage::Event(Box::new(event));
+        let wire = serde_json::to_string(&msg)?;
+        self.send_spaced(wire, None).await?;
+        tracing::info!("Posted event to {}", &self.url);
+        Ok(())
+    }
+
+    // Relays only accept protected events (NIP-70) from their author once
+    // authenticated, so if the relay has challenged us we hold these back until
+    // we have answered it.
+    fn needs_auth_before_posting(&self, event: &Event) -> bool {
+        crate::nip70::is_protected(event)
+            && !self.auth_challenge.is_empty()
+            && self.dbrelay.allow_auth != Some(false)
+            && matches!(self.auth_state, AuthState::None | AuthState::Waiting(_))
+    }
+
+    // Post the events that were waiting for authentication. If it failed or was
+    // declined, the relay will most likely refuse them, and say so.
+    async fn post_waiting_for_auth(&mut self) -> Result<(), Error> {
+        let waiting = std::mem::take(&mut self.postings_waiting_for_auth);
+        let mut job_ids: Vec<u64> = Vec::new();
+        for (job_id, event) in waiting {
+            self.post_event(event).await?;
+            if !job_ids.contains(&job_id) {
+                job_ids.push(job_id);
+            }
+        }
+        for job_id in job_ids {
+            self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                self.url.clone(),
+                job_id,
+            ))?;
+        }
+        Ok(())
+    }
+
+    // Events still waiting for AUTH when we disconnect will not be posted here.
+    // Their jobs may have completed already (the relay only asked for AUTH
+    // after we posted), so say so rather than lose them quietly.
+    fn report_unposted(&mut self) {
+        let waiting = std::mem::take(&mut self.postings_waiting_for_auth);
+        if waiting.is_empty() {
+            return;
+        }
+        for (_, event) in &waiting {
+            tracing::warn!(
+                "{}: disconnected before posting {}, which waited for AUTH",
+                &self.url,
+                event.id.as_hex_string()
+            );
+        }
+        GLOBALS.status_queue.write().write_warning(format!(
+            "{} event(s) were not posted to {}, which wanted authentication first. You can post them again from the note menu.",
+            waiting.len(),
+            &self.url
+        ));
+    }
+
+    // Answer the relay's AUTH challenge, unless the user wants to approve
+    // authenticating to this relay first, in which case ask them
+    async fn maybe_authenticate(&mut self) -> Result<(), Error> {
+        if !GLOBALS.storage.read_setting_relay_auth_requires_approval() {
+            return self.authenticate().await;
+        }
+
+        match self.dbrelay.allow_auth {
+            Some(true) => self.authenticate().await,
+            Some(false) => Ok(()),
+            None => {
+                if !GLOBALS.auth_requests.read().contains(&self.url) {
+                    GLOBALS.auth_requests.write().push(self.url.clone());
+                }
+                Ok(())
+            }
+        }
+    }
+
     async fn authenticate(&mut self) -> Result<(), Error> {
         match self.auth_state {
             AuthState::Authenticated => return Ok(()),
@@ -1487,6 +2052,30 @@ impl Minion {
         }
     }
 
+    // Save the latency and traffic measured so far. Only these fields are
+    // written, so changes made elsewhere to the relay record are kept.
+    fn save_health(&mut self) {
+        self.health_saved_at = Instant::now();
+        let (avg_eose_ms, avg_ping_ms, events_received, bytes_received) = (
+            self.dbrelay.avg_eose_ms,
+            self.dbrelay.avg_ping_ms,
+            self.dbrelay.events_received,
+            self.dbrelay.bytes_received,
+        );
+        if let Err(e) = GLOBALS.storage.modify_relay(
+            &self.url,
+            |relay| {
+                relay.avg_eose_ms = avg_eose_ms;
+                relay.avg_ping_ms = avg_ping_ms;
+                relay.events_received = events_received;
+                relay.bytes_received = bytes_received;
+            },
+            None,
+        ) {
+            tracing::error!("{}: ERROR saving relay health: {}", &self.url, e);
+        }
+    }
+
     async fn bump_failure_count(&mut self) {
         // Update in self
         self.dbrelay.failure_count += 1;
@@ -7,6 +7,10 @@ pub struct Subscription {
     id: String,
     job_id: u64,
     filters: Vec<Filter>,
+
+    // Full text to search for (NIP-50), which our filters have no field for
+    search: Option<String>,
+
     eose: bool,
     clone: bool,
 }
@@ -18,6 +22,7 @@ impl Subscription {
             id: id.to_owned(),
             job_id,
             filters: vec![],
+            search: None,
             eose: false,
             clone: false,
         }
@@ -31,6 +36,10 @@ impl Subscription {
         &self.filters
     }
 
+    pub fn set_search(&mut self, search: String) {
+        self.search = Some(search);
+    }
+
     pub fn get_id(&self) -> String {
         self.id.clone()
     }
@@ -60,6 +69,27 @@ impl Subscription {
         ClientMessage::Req(SubscriptionId(self.get_id()), self.filters.clone())
     }
 
+    /// The REQ as it goes on the wire, with the search text added to each
+    /// filter if there is one
+    pub fn req_wire(&self) -> Result<String, serde_json::Error> {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return serde_json::to_string(&self.req_message()),
+        };
+        let mut value = serde_json::to_value(self.req_message())?;
+        if let Some(array) = value.as_array_mut() {
+            for filter in array.iter_mut().skip(2) {
+                if let Some(object) = filter.as_object_mut() {
+                    object.insert(
+                        "search".to_owned(),
+                        serde_json::Value::String(search.clone()),
+                    );
+                }
+            }
+        }
+        serde_json::to_string(&value)
+    }
+
     pub fn close_message(&self) -> ClientMessage {
         ClientMessage::Close(SubscriptionId(self.get_id()))
     }
@@ -71,6 +101,7 @@ impl Clone for Subscription {
             id: self.id.clone(),
             job_id: self.job_id,
             filters: self.filters.clone(),
+            search: self.search.clone(),
             eose: self.eose,
             clone: true,
         }
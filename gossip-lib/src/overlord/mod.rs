@@ -4,17 +4,25 @@ use crate::comms::{
     RelayConnectionReason, RelayJob, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail,
     ToOverlordMessage,
 };
+use crate::community::Community;
 use crate::dm_channel::DmChannel;
 use crate::error::{Error, ErrorKind};
 use crate::feed::FeedKind;
 use crate::globals::{Globals, ZapState, GLOBALS};
-use crate::nip46::{Approval, ParsedCommand};
+use crate::nip32::Label;
+use crate::nip46::{Approval, Nip46BunkerClient, ParsedCommand};
+use crate::nip52::{CalendarEvent, RsvpStatus};
+use crate::nip90::JobType;
 use crate::people::{Person, PersonList};
 use crate::person_relay::PersonRelay;
 use crate::relay::Relay;
+use crate::relay_payment::RelayPayment;
+use crate::search::{SearchQuery, SearchScope};
 use crate::tags::{
-    add_addr_to_tags, add_event_to_tags, add_pubkey_to_tags, add_subject_to_tags_if_missing,
+    add_addr_to_tags, add_event_to_tags, add_pubkey_to_tags, add_quote_to_tags,
+    add_subject_to_tags_if_missing,
 };
+use crate::thread_export::ThreadFormat;
 use gossip_relay_picker::{Direction, RelayAssignment};
 use heed::RwTxn;
 use http::StatusCode;
@@ -25,8 +33,8 @@ use nostr_types::{
     PublicKey, RelayUrl, Tag, UncheckedUrl, Unixtime,
 };
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::atomic::Ordering;
-use std::sync::mpsc;
 use std::time::Duration;
 use tokio::sync::broadcast::Sender;
 use tokio::sync::mpsc::UnboundedReceiver;
@@ -52,6 +60,10 @@ pub struct Overlord {
 
     // Map from minion task::Id to Url
     minions_task_url: HashMap<task::Id, RelayUrl>,
+
+    // Jobs to start on a relay once its minion has exited, along with its
+    // persistent jobs
+    jobs_after_exit: HashMap<RelayUrl, Vec<RelayJob>>,
 }
 
 impl Overlord {
@@ -99,6 +111,7 @@ impl Overlord {
             inbox,
             minions: task::JoinSet::new(),
             minions_task_url: HashMap::new(),
+            jobs_after_exit: HashMap::new(),
         }
     }
 
@@ -151,6 +164,18 @@ impl Overlord {
         }
 
         tracing::info!("Overlord confirms all minions have shutdown");
+
+        // Nothing writes to storage anymore, so the compacted copy misses nothing
+        if GLOBALS.compact_on_quit.load(Ordering::Relaxed) {
+            match GLOBALS.storage.compact() {
+                Ok(report) => tracing::info!(
+                    "Compacted the database, checking {} events. {} bytes will be reclaimed on the next start.",
+                    report.events_checked,
+                    report.reclaimed()
+                ),
+                Err(e) => tracing::error!("Compacting the database: {}", e),
+            }
+        }
     }
 
     async fn run_inner(&mut self) -> Result<(), Error> {
@@ -190,6 +215,27 @@ impl Overlord {
         // Start periodic tasks in people manager (after signer)
         crate::people::People::start();
 
+        // Start the metrics endpoint, if enabled
+        crate::metrics::start();
+
+        // Start the RSS and Atom feeds endpoint, if enabled
+        crate::syndication::start();
+
+        // Start publishing our timestamp attestations once they are in bitcoin
+        crate::nip03::start();
+
+        // Take nostr: links from later launches of gossip
+        crate::deep_link::start();
+
+        // Start scheduled backups
+        crate::backup::start();
+
+        // Start removing events as they expire
+        crate::nip40::start();
+
+        // Start pruning old events, if the user wants that
+        Self::start_scheduled_pruning();
+
         // Initialize the relay picker
         GLOBALS.relay_picker.init().await?;
 
@@ -423,6 +469,9 @@ impl Overlord {
         let mut exclusion: u64;
         let mut completed: bool = false;
 
+        // Whether the relay dropped us, which puts it in the penalty box
+        let mut dropped: bool = false;
+
         match join_result {
             Err(join_error) => {
                 tracing::error!("Minion {} completed with join error: {}", &url, join_error);
@@ -433,6 +482,7 @@ impl Overlord {
                 Ok(exitreason) => {
                     if exitreason.benign() {
                         tracing::debug!("Minion {} completed: {:?}", &url, exitreason);
+                        crate::relay_penalty::forgive(&url);
                     } else {
                         tracing::info!("Minion {} completed: {:?}", &url, exitreason);
                     }
@@ -448,6 +498,11 @@ impl Overlord {
                     if matches!(exitreason, MinionExitReason::SubscriptionsHaveCompleted) {
                         completed = true;
                     }
+
+                    dropped = matches!(
+                        exitreason,
+                        MinionExitReason::GotDisconnected | MinionExitReason::GotWSClose
+                    );
                 }
                 Err(e) => {
                     Self::bump_failure_count(&url);
@@ -461,7 +516,10 @@ impl Overlord {
                                 StatusCode::MOVED_PERMANENTLY => 60 * 60 * 24,
                                 StatusCode::PERMANENT_REDIRECT => 60 * 60 * 24,
                                 StatusCode::UNAUTHORIZED => 60 * 60 * 24,
-                                StatusCode::PAYMENT_REQUIRED => 60 * 60 * 24,
+                                StatusCode::PAYMENT_REQUIRED => {
+                                    crate::relay_payment::payment_required(&url, None);
+                                    60 * 60 * 24
+                                }
                                 StatusCode::FORBIDDEN => 60 * 60 * 24,
                                 StatusCode::NOT_FOUND => 60 * 60 * 24,
                                 StatusCode::PROXY_AUTHENTICATION_REQUIRED => 60 * 60 * 24,
@@ -474,10 +532,12 @@ impl Overlord {
                         } else if let tungstenite::error::Error::ConnectionClosed = wserror {
                             tracing::debug!("Minion {} completed", &url);
                             exclusion = 30; // was not actually an error, but needs a pause
+                            dropped = true;
                         } else if let tungstenite::error::Error::Protocol(protocol_error) = wserror
                         {
                             exclusion = match protocol_error {
                                 tungstenite::error::ProtocolError::ResetWithoutClosingHandshake => {
+                                    dropped = true;
                                     60
                                 }
                                 _ => 120,
@@ -488,6 +548,11 @@ impl Overlord {
             },
         };
 
+        // Stay away longer from relays that keep dropping us
+        if dropped {
+            exclusion = exclusion.max(crate::relay_penalty::penalize(&url));
+        }
+
         // Let the relay picker know it disconnected
         GLOBALS
             .relay_picker
@@ -513,37 +578,35 @@ impl Overlord {
         }
         self.pick_relays().await;
 
+        // Jobs that were waiting for this minion to exit
+        let mut restart_jobs: Vec<RelayJob> = self.jobs_after_exit.remove(&url).unwrap_or_default();
+
         if let Some(mut jobs) = jobs {
             // Remove any advertise jobs from the active set
             for job in &jobs {
                 GLOBALS.active_advertise_jobs.remove(&job.payload.job_id);
             }
 
-            if completed {
-                return;
+            // Take up any persistent jobs again
+            if !completed {
+                restart_jobs.extend(jobs.drain(..).filter(|job| job.reason.persistent()));
             }
+        }
 
-            // If we have any persistent jobs, restart after a delay
-            let persistent_jobs: Vec<RelayJob> = jobs
-                .drain(..)
-                .filter(|job| job.reason.persistent())
-                .collect();
-
-            if !persistent_jobs.is_empty() {
-                // Do it after a delay
-                std::mem::drop(tokio::spawn(async move {
-                    // Delay for exclusion first
-                    tracing::info!(
-                        "Minion {} will restart in {} seconds to continue persistent jobs",
-                        &url,
-                        exclusion
-                    );
-                    tokio::time::sleep(Duration::new(exclusion, 0)).await;
-                    let _ = GLOBALS
-                        .to_overlord
-                        .send(ToOverlordMessage::ReengageMinion(url, persistent_jobs));
-                }));
-            }
+        if !restart_jobs.is_empty() {
+            // Do it after a delay
+            std::mem::drop(tokio::spawn(async move {
+                // Delay for exclusion first
+                tracing::info!(
+                    "Minion {} will restart in {} seconds to continue its jobs",
+                    &url,
+                    exclusion
+                );
+                tokio::time::sleep(Duration::new(exclusion, 0)).await;
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::ReengageMinion(url, restart_jobs));
+            }));
         }
     }
 
@@ -562,18 +625,30 @@ impl Overlord {
             ToOverlordMessage::AddRelay(relay_url) => {
                 self.add_relay(relay_url).await?;
             }
+            ToOverlordMessage::AdvertiseDmRelays => {
+                self.advertise_dm_relays().await?;
+            }
             ToOverlordMessage::AdvertiseRelayList => {
                 self.advertise_relay_list().await?;
             }
             ToOverlordMessage::AdvertiseRelayListNextChunk(event, relays) => {
                 self.advertise_relay_list_next_chunk(event, relays).await?;
             }
+            ToOverlordMessage::ApproveCommunityPost(id, community) => {
+                self.approve_community_post(id, community).await?;
+            }
             ToOverlordMessage::AuthApproved(relay_url) => {
                 self.auth_approved(relay_url)?;
             }
             ToOverlordMessage::AuthDeclined(relay_url) => {
                 self.auth_declined(relay_url)?;
             }
+            ToOverlordMessage::BackupNow => {
+                Self::backup_now();
+            }
+            ToOverlordMessage::Broadcast(id) => {
+                self.broadcast(id).await?;
+            }
             ToOverlordMessage::ChangePassphrase { old, new } => {
                 Self::change_passphrase(old, new).await?;
             }
@@ -583,6 +658,9 @@ impl Overlord {
             ToOverlordMessage::ConnectApproved(relay_url) => {
                 self.connect_approved(relay_url).await?;
             }
+            ToOverlordMessage::ConnectBunker { uri, password } => {
+                Self::connect_bunker(uri, password).await?;
+            }
             ToOverlordMessage::ConnectDeclined(relay_url) => {
                 self.connect_declined(relay_url).await?;
             }
@@ -604,15 +682,45 @@ impl Overlord {
             ToOverlordMessage::DropRelay(relay_url) => {
                 self.drop_relay(relay_url)?;
             }
+            ToOverlordMessage::ExportThread(id, format) => {
+                Self::export_thread(id, format);
+            }
+            ToOverlordMessage::FetchCalendarEvents => {
+                self.fetch_calendar_events().await?;
+            }
             ToOverlordMessage::FetchEvent(id, relay_urls) => {
                 self.fetch_event(id, relay_urls).await?;
             }
             ToOverlordMessage::FetchEventAddr(ea) => {
                 self.fetch_event_addr(ea).await?;
             }
+            ToOverlordMessage::FetchHandlers(kind) => {
+                self.fetch_handlers(kind).await?;
+            }
+            ToOverlordMessage::FetchIdentityMentions => {
+                self.fetch_identity_mentions().await?;
+            }
+            ToOverlordMessage::FetchLongFormArticles => {
+                self.fetch_long_form_articles().await?;
+            }
+            ToOverlordMessage::FetchMarketplace => {
+                self.fetch_marketplace().await?;
+            }
+            ToOverlordMessage::FetchRelayInvoice(relay_url) => {
+                Self::fetch_relay_invoice(relay_url);
+            }
+            ToOverlordMessage::FetchStarterPacks => {
+                self.fetch_starter_packs().await?;
+            }
+            ToOverlordMessage::FetchWikiArticles(d) => {
+                self.fetch_wiki_articles(d).await?;
+            }
             ToOverlordMessage::FollowPubkey(pubkey, list, public) => {
                 self.follow_pubkey(pubkey, list, public).await?;
             }
+            ToOverlordMessage::FollowPubkeys(pubkeys, list, public) => {
+                self.follow_pubkeys(pubkeys, list, public).await?;
+            }
             ToOverlordMessage::FollowNip05(nip05, list, public) => {
                 Self::follow_nip05(nip05, list, public).await?;
             }
@@ -625,12 +733,21 @@ impl Overlord {
             ToOverlordMessage::HideOrShowRelay(relay_url, hidden) => {
                 Self::hide_or_show_relay(relay_url, hidden)?;
             }
+            ToOverlordMessage::ImportFollows(path) => {
+                Self::import_follows(path)?;
+            }
             ToOverlordMessage::ImportPriv { privkey, password } => {
                 Self::import_priv(privkey, password).await?;
             }
             ToOverlordMessage::ImportPub(pubstr) => {
                 Self::import_pub(pubstr).await?;
             }
+            ToOverlordMessage::InspectEvent(id) => {
+                self.inspect_event(id).await?;
+            }
+            ToOverlordMessage::Label { pubkey, id, label } => {
+                self.label(pubkey, id, label).await?;
+            }
             ToOverlordMessage::Like(id, pubkey) => {
                 self.like(id, pubkey).await?;
             }
@@ -638,9 +755,14 @@ impl Overlord {
                 match GLOBALS.feed.get_feed_kind() {
                     FeedKind::List(_, _) => self.load_more_general_feed().await?,
                     FeedKind::Inbox(_) => self.load_more_inbox_feed().await?,
+                    FeedKind::CombinedInbox => {
+                        self.load_more_inbox_feed().await?;
+                        self.fetch_identity_mentions().await?;
+                    }
                     FeedKind::Person(pubkey) => self.load_more_person_feed(pubkey).await?,
                     FeedKind::DmChat(_) => (), // DmChat is complete, not chunked
                     FeedKind::Thread { .. } => (), // Thread is complete, not chunked
+                    FeedKind::Community(_) => (), // Community is complete, not chunked
                 }
             }
             ToOverlordMessage::MinionJobComplete(url, job_id) => {
@@ -671,6 +793,9 @@ impl Overlord {
             ToOverlordMessage::RefreshScoresAndPickRelays => {
                 self.refresh_scores_and_pick_relays().await?;
             }
+            ToOverlordMessage::PayRelayInvoice(relay_url) => {
+                Self::pay_relay_invoice(relay_url);
+            }
             ToOverlordMessage::Post {
                 content,
                 tags,
@@ -685,36 +810,101 @@ impl Overlord {
             ToOverlordMessage::PostNip46Event(event, relays) => {
                 self.post_nip46_event(event, relays).await?;
             }
+            ToOverlordMessage::PostToCommunity {
+                content,
+                tags,
+                community,
+            } => {
+                self.post_to_community(content, tags, community).await?;
+            }
             ToOverlordMessage::PruneCache => {
                 Self::prune_cache().await?;
             }
             ToOverlordMessage::PruneDatabase => {
                 Self::prune_database()?;
             }
+            ToOverlordMessage::PublishLongFormArticle {
+                d,
+                title,
+                summary,
+                image,
+                hashtags,
+                content,
+            } => {
+                self.publish_long_form_article(d, title, summary, image, hashtags, content)
+                    .await?;
+            }
+            ToOverlordMessage::PublishWikiArticle {
+                title,
+                summary,
+                content,
+                fork_of,
+            } => {
+                self.publish_wiki_article(title, summary, content, fork_of)
+                    .await?;
+            }
             ToOverlordMessage::PushPersonList(person_list) => {
                 self.push_person_list(person_list).await?;
             }
             ToOverlordMessage::PushMetadata(metadata) => {
                 self.push_metadata(metadata).await?;
             }
+            ToOverlordMessage::PushProfileBadges(badges) => {
+                self.push_profile_badges(badges).await?;
+            }
+            ToOverlordMessage::PushSettingsSync => {
+                self.push_settings_sync().await?;
+            }
             ToOverlordMessage::RankRelay(relay_url, rank) => {
                 Self::rank_relay(relay_url, rank)?;
             }
             ToOverlordMessage::ReengageMinion(url, persistent_jobs) => {
                 self.engage_minion(url, persistent_jobs).await?;
             }
+            ToOverlordMessage::RelayPaid(relay_url) => {
+                self.relay_paid(relay_url).await?;
+            }
             ToOverlordMessage::RefreshSubscribedMetadata => {
                 self.refresh_subscribed_metadata().await?;
             }
+            ToOverlordMessage::Report {
+                pubkey,
+                id,
+                report_type,
+                comment,
+            } => {
+                self.report(pubkey, id, report_type, comment).await?;
+            }
             ToOverlordMessage::Repost(id) => {
                 self.repost(id).await?;
             }
-            ToOverlordMessage::Search(text) => {
-                Self::search(text).await?;
+            ToOverlordMessage::RequestJob {
+                job_type,
+                input,
+                language,
+                bid_msats,
+            } => {
+                self.request_job(job_type, input, language, bid_msats)
+                    .await?;
+            }
+            ToOverlordMessage::RequestTimestamp(id) => {
+                self.request_timestamp(id).await?;
+            }
+            ToOverlordMessage::Rsvp(id, status) => {
+                self.rsvp(id, status).await?;
+            }
+            ToOverlordMessage::Search(text, scope) => {
+                Self::search(text, scope).await?;
+            }
+            ToOverlordMessage::SearchRelays(text) => {
+                self.search_relays(text).await?;
             }
             ToOverlordMessage::SetActivePerson(pubkey) => {
                 Self::set_active_person(pubkey).await?;
             }
+            ToOverlordMessage::SetCommunityFeed(community) => {
+                self.set_community_feed(community).await?;
+            }
             ToOverlordMessage::SetDmChannel(dmchannel) => {
                 self.set_dm_channel(dmchannel).await?;
             }
@@ -728,6 +918,15 @@ impl Overlord {
             } => {
                 self.set_thread_feed(id, referenced_by, author).await?;
             }
+            ToOverlordMessage::SetUserStatus {
+                status_type,
+                content,
+                link,
+                expiration,
+            } => {
+                self.set_user_status(status_type, content, link, expiration)
+                    .await?;
+            }
             ToOverlordMessage::StartLongLivedSubscriptions => {
                 self.start_long_lived_subscriptions().await?;
             }
@@ -761,6 +960,15 @@ impl Overlord {
             ToOverlordMessage::UpdateRelay(old, new) => {
                 self.update_relay(old, new).await?;
             }
+            ToOverlordMessage::UpdateStorageStats => {
+                Self::update_storage_stats().await?;
+            }
+            ToOverlordMessage::UpgradeTimestamps => {
+                self.upgrade_timestamps().await?;
+            }
+            ToOverlordMessage::UploadMedia(path) => {
+                Self::upload_media(path);
+            }
             ToOverlordMessage::VisibleNotesChanged(visible) => {
                 self.visible_notes_changed(visible).await?;
             }
@@ -819,6 +1027,52 @@ impl Overlord {
         Ok(())
     }
 
+    /// Advertise the user's inbox relays as the ones to send them direct
+    /// messages on (NIP-17)
+    pub async fn advertise_dm_relays(&mut self) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let pre_event = crate::nip17::dm_relay_list(public_key)?;
+        if pre_event.tags.is_empty() {
+            GLOBALS.status_queue.write().write_warning(
+                "You have no inbox relays to receive direct messages on.".to_owned(),
+            );
+            return Ok(());
+        }
+
+        let event = GLOBALS.identity.sign_event(pre_event)?;
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        let relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.is_good_for_advertise() && r.rank != 0)?
+            .iter()
+            .map(|relay| relay.url.clone())
+            .collect();
+
+        for url in relays {
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::Advertising,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
     /// Advertise the user's current relay list
     pub async fn advertise_relay_list(&mut self) -> Result<(), Error> {
         let public_key = match GLOBALS.identity.public_key() {
@@ -878,6 +1132,25 @@ impl Overlord {
         Ok(())
     }
 
+    // Advertise the relay list a little while after it stops changing
+    fn relay_list_changed() {
+        if !GLOBALS.storage.read_setting_relay_list_auto_advertise()
+            || !GLOBALS.identity.is_unlocked()
+        {
+            return;
+        }
+
+        let change = GLOBALS.relay_list_changes.fetch_add(1, Ordering::Relaxed) + 1;
+        std::mem::drop(tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            if GLOBALS.relay_list_changes.load(Ordering::Relaxed) == change {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::AdvertiseRelayList);
+            }
+        }));
+    }
+
     /// Advertise the user's current relay list in chunks
     pub async fn advertise_relay_list_next_chunk(
         &mut self,
@@ -933,6 +1206,74 @@ impl Overlord {
         Ok(())
     }
 
+    /// Approve a post into a moderated community (NIP-72). Only moderators of the
+    /// community can do this.
+    pub async fn approve_community_post(
+        &mut self,
+        id: Id,
+        community_addr: EventAddr,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let community = match Community::load(&community_addr)? {
+            Some(c) => c,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write_warning("Cannot approve - community definition not found.".to_owned());
+                return Ok(());
+            }
+        };
+
+        if !community.is_moderator(public_key) {
+            GLOBALS.status_queue.write().write_warning(
+                "Cannot approve - you are not a moderator of this community.".to_owned(),
+            );
+            return Ok(());
+        }
+
+        let post = match GLOBALS.storage.read_event(id)? {
+            Some(event) => event,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write_warning("Cannot approve - cannot find event.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let relay_url = GLOBALS
+            .storage
+            .get_event_seen_on_relay(post.id)?
+            .first()
+            .map(|(rurl, _)| rurl.to_unchecked_url());
+
+        let tags: Vec<Tag> = vec![
+            Tag::new_address(&community.addr, None),
+            Tag::new_event(post.id, relay_url, None),
+            Tag::new_pubkey(post.pubkey, None, None),
+            Tag::new_kind(post.kind),
+        ];
+
+        self.sign_and_broadcast(
+            EventKind::CommunityPostApproval,
+            tags,
+            serde_json::to_string(&post)?,
+            community.relay_urls()?,
+        )
+        .await?;
+
+        Ok(())
+    }
+
     /// User has approved authentication on this relay. Save this result for later
     /// and inform the minion.
     pub fn auth_approved(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
@@ -997,6 +1338,84 @@ impl Overlord {
         Ok(())
     }
 
+    /// Back up storage now, in the background
+    pub fn backup_now() {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Backing up storage...".to_owned());
+        std::mem::drop(tokio::task::spawn_blocking(
+            crate::backup::backup_and_report,
+        ));
+    }
+
+    /// Republish a stored event (ours or someone else's) to all of our write relays,
+    /// to help it propagate or to restore it on relays that lost it.
+    pub async fn broadcast(&mut self, id: Id) -> Result<(), Error> {
+        let event = match GLOBALS.storage.read_event(id)? {
+            Some(event) => event,
+            None => {
+                GLOBALS.status_queue.write().write_warning(
+                    "We do not have that event, so it was not broadcast.".to_owned(),
+                );
+                return Ok(());
+            }
+        };
+
+        if crate::nip40::is_expired(&event, Unixtime::now().unwrap()) {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("That event has expired, so it was not broadcast.".to_owned());
+            return Ok(());
+        }
+
+        if crate::nip70::is_protected(&event) && Some(event.pubkey) != GLOBALS.identity.public_key()
+        {
+            GLOBALS.status_queue.write().write_warning(
+                "That event is protected, so only its author may broadcast it.".to_owned(),
+            );
+            return Ok(());
+        }
+
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+
+        if relays.is_empty() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("You have no write relays to broadcast to.".to_owned());
+            return Ok(());
+        }
+
+        let count = relays.len();
+        for relay in relays {
+            // Send it the event to post
+            tracing::debug!("Asking {} to broadcast", &relay.url);
+
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Broadcasting to {} relays.", count));
+
+        Ok(())
+    }
+
     /// Change the user's passphrase.
     pub async fn change_passphrase(mut old: String, mut new: String) -> Result<(), Error> {
         GLOBALS.identity.change_passphrase(&old, &new).await?;
@@ -1064,6 +1483,38 @@ impl Overlord {
         Ok(())
     }
 
+    /// Pair with a NIP-46 remote signer (bunker) and sign with it from now on,
+    /// as the user it signs for. Our key for the bunker is kept under the
+    /// passphrase.
+    pub async fn connect_bunker(uri: String, mut password: String) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(async move {
+            let paired = Nip46BunkerClient::pair(&uri, &password).await;
+            password.zeroize();
+            match paired {
+                Ok((client, pubkey)) => {
+                    if let Err(e) = GLOBALS.identity.set_bunker(client, pubkey) {
+                        tracing::error!("{}", e);
+                        return;
+                    }
+                    if let Err(e) = Self::delegation_reset().await {
+                        tracing::error!("{}", e);
+                    }
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write("Connected to your remote signer.".to_owned());
+                }
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Could not connect to the remote signer: {}", e));
+                }
+            }
+        }));
+        Ok(())
+    }
+
     /// Remove any key delegation setup
     pub async fn delegation_reset() -> Result<(), Error> {
         if GLOBALS.delegation.reset() {
@@ -1322,13 +1773,33 @@ impl Overlord {
         Ok(())
     }
 
-    /// Fetch an event from a specific relay by event `Id`
-    pub async fn fetch_event(&mut self, id: Id, relay_urls: Vec<RelayUrl>) -> Result<(), Error> {
-        // Don't do this if we already have the event
-        if !GLOBALS.storage.has_event(id)? {
-            // Note: minions will remember if they get the same id multiple times
-            //       not to fetch it multiple times.
-
+    /// Export the thread containing this event to a file, in the background
+    pub fn export_thread(id: Id, format: ThreadFormat) {
+        std::mem::drop(tokio::task::spawn_blocking(move || {
+            match crate::thread_export::export_thread_to_file(id, format) {
+                Ok(path) => GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Exported thread to {}", path.display())),
+                Err(e) => GLOBALS.status_queue.write().write_error(format!("{}", e)),
+            }
+        }));
+    }
+
+    /// Fetch calendar events (NIP-52) of people we follow, and the RSVPs to the
+    /// upcoming ones, from our read relays
+    pub async fn fetch_calendar_events(&mut self) -> Result<(), Error> {
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchCalendarEvents, false)
+            .await
+    }
+
+    /// Fetch an event from a specific relay by event `Id`
+    pub async fn fetch_event(&mut self, id: Id, relay_urls: Vec<RelayUrl>) -> Result<(), Error> {
+        // Don't do this if we already have the event
+        if !GLOBALS.storage.has_event(id)? {
+            // Note: minions will remember if they get the same id multiple times
+            //       not to fetch it multiple times.
+
             for url in relay_urls.iter() {
                 self.engage_minion(
                     url.to_owned(),
@@ -1368,6 +1839,128 @@ impl Overlord {
         Ok(())
     }
 
+    // Fetch with a one-off subscription from each of our read relays, and with
+    // `discover` from our discovery relays too
+    async fn fetch_from_read_relays(
+        &mut self,
+        detail: ToMinionPayloadDetail,
+        discover: bool,
+    ) -> Result<(), Error> {
+        let relays: Vec<Relay> = GLOBALS.storage.filter_relays(|r| {
+            (r.has_usage_bits(Relay::READ) || (discover && r.has_usage_bits(Relay::DISCOVER)))
+                && r.rank != 0
+        })?;
+
+        for relay in relays {
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::FetchEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: detail.clone(),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch application handlers (NIP-89) for an event kind, and their
+    /// recommendations by people we follow, from our read relays
+    pub async fn fetch_handlers(&mut self, kind: EventKind) -> Result<(), Error> {
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchHandlers(kind), false)
+            .await
+    }
+
+    /// Fetch the long-form articles (NIP-23) of people we follow, from our read relays
+    pub async fn fetch_long_form_articles(&mut self) -> Result<(), Error> {
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchLongFormArticles, false)
+            .await
+    }
+
+    /// Fetch the stalls and products (NIP-15) of people we follow, from our read relays
+    pub async fn fetch_marketplace(&mut self) -> Result<(), Error> {
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchMarketplace, false)
+            .await
+    }
+
+    /// Ask a relay that wants to be paid for an invoice for our public key.
+    /// Where that stands is kept in `GLOBALS.relay_payments`.
+    pub fn fetch_relay_invoice(relay_url: RelayUrl) {
+        GLOBALS
+            .relay_payments
+            .insert(relay_url.clone(), RelayPayment::Fetching);
+        std::mem::drop(tokio::spawn(async move {
+            let payment = match crate::relay_payment::fetch_invoice(&relay_url).await {
+                Ok(payment) => payment,
+                Err(e) => {
+                    tracing::warn!("{}: could not get an invoice: {}", relay_url, e);
+                    RelayPayment::Failed(format!("{}", e))
+                }
+            };
+            GLOBALS.relay_payments.insert(relay_url, payment);
+        }));
+    }
+
+    /// Fetch starter packs and follow sets (NIP-51) for new users to pick people
+    /// to follow from, from our read and discovery relays
+    pub async fn fetch_starter_packs(&mut self) -> Result<(), Error> {
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchStarterPacks, true)
+            .await
+    }
+
+    /// Fetch what is addressed to the user's other identities, from the relays
+    /// they read from, back to where the inbox feed starts
+    pub async fn fetch_identity_mentions(&mut self) -> Result<(), Error> {
+        let others = crate::identities::others();
+        if others.is_empty() {
+            return Ok(());
+        }
+
+        // Make sure we know where they read from
+        self.subscribe_discover(others.clone(), None).await?;
+
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+        let mut relay_pubkeys: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for pubkey in others {
+            for (url, _) in GLOBALS
+                .storage
+                .get_best_relays(pubkey, Direction::Read)?
+                .drain(..)
+                .take(num_relays_per_person as usize + 1)
+            {
+                relay_pubkeys.entry(url).or_default().push(pubkey);
+            }
+        }
+
+        let since = GLOBALS.feed.inbox_feed_start();
+        for (url, pubkeys) in relay_pubkeys.drain() {
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::FetchMentions,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::FetchIdentityMentions(pubkeys, since),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the wiki articles (NIP-54) on a topic, by anyone, from our read relays
+    pub async fn fetch_wiki_articles(&mut self, d: String) -> Result<(), Error> {
+        let d = crate::nip54::normalize(&d);
+        self.fetch_from_read_relays(ToMinionPayloadDetail::FetchWikiArticles(d), false)
+            .await
+    }
+
     /// Follow a person by `PublicKey`
     pub async fn follow_pubkey(
         &mut self,
@@ -1380,6 +1973,18 @@ impl Overlord {
         Ok(())
     }
 
+    /// Follow many people by `PublicKey` at once
+    pub async fn follow_pubkeys(
+        &mut self,
+        pubkeys: Vec<PublicKey>,
+        list: PersonList,
+        public: bool,
+    ) -> Result<(), Error> {
+        GLOBALS.people.follow_all(&pubkeys, list, public, true)?;
+        tracing::debug!("Followed {} people", pubkeys.len());
+        Ok(())
+    }
+
     /// Follow a person by a nip-05 address
     pub async fn follow_nip05(nip05: String, list: PersonList, public: bool) -> Result<(), Error> {
         std::mem::drop(tokio::spawn(async move {
@@ -1447,6 +2052,33 @@ impl Overlord {
         Ok(())
     }
 
+    /// Read a Mastodon following CSV or a Twitter archive `following.js`, and
+    /// look up who those accounts are on nostr. The results are left in
+    /// [follow_import](crate::follow_import) for the user to review.
+    pub fn import_follows(path: String) -> Result<(), Error> {
+        if crate::follow_import::is_resolving() {
+            return Err("Already importing follows".into());
+        }
+        let text = std::fs::read_to_string(path.trim())?;
+        let handles = crate::follow_import::parse_handles(&text);
+        if handles.is_empty() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("No accounts found in that file.".to_owned());
+            return Ok(());
+        }
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Looking up {} accounts on nostr...", handles.len()));
+        std::mem::drop(tokio::spawn(async move {
+            crate::follow_import::resolve_all(handles).await;
+        }));
+        Ok(())
+    }
+
     /// Import a private key
     pub async fn import_priv(mut privkey: String, mut password: String) -> Result<(), Error> {
         if privkey.starts_with("ncryptsec") {
@@ -1457,7 +2089,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write(format!("Private key failed to decrypt: {}", e));
+                    .write_error(format!("Private key failed to decrypt: {}", e));
             } else {
                 password.zeroize();
             }
@@ -1470,7 +2102,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write("Private key not recognized.".to_owned());
+                    .write_error("Private key not recognized.".to_owned());
             } else {
                 let privkey = maybe_pk1.unwrap_or_else(|_| maybe_pk2.unwrap());
                 GLOBALS.identity.set_private_key(privkey, &password)?;
@@ -1489,7 +2121,7 @@ impl Overlord {
             GLOBALS
                 .status_queue
                 .write()
-                .write("Public key not recognized.".to_owned());
+                .write_error("Public key not recognized.".to_owned());
         } else {
             let pubkey = maybe_pk1.unwrap_or_else(|_| maybe_pk2.unwrap());
             GLOBALS.identity.set_public_key(pubkey)?;
@@ -1498,6 +2130,20 @@ impl Overlord {
         Ok(())
     }
 
+    /// Inspect the internals of an event we have, for debugging.
+    /// The inspection arrives in `GLOBALS.event_inspection`
+    pub async fn inspect_event(&mut self, id: Id) -> Result<(), Error> {
+        let inspection = crate::inspector::inspect(id)?;
+        if inspection.is_none() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("We do not have that event to inspect.".to_owned());
+        }
+        *GLOBALS.event_inspection.write() = inspection;
+        Ok(())
+    }
+
     fn maybe_disconnect_relay(&mut self, url: &RelayUrl) -> Result<(), Error> {
         if let Some(refmut) = GLOBALS.connected_relays.get_mut(url) {
             // If no job remains, disconnect the relay
@@ -1524,6 +2170,95 @@ impl Overlord {
         Ok(())
     }
 
+    /// Label a person, or one of their events when `id` is given (NIP-32)
+    pub async fn label(
+        &mut self,
+        pubkey: PublicKey,
+        id: Option<Id>,
+        label: Label,
+    ) -> Result<(), Error> {
+        let tags = crate::nip32::label_tags(pubkey, id, &label);
+        if self
+            .sign_and_broadcast(EventKind::Label, tags, "".to_owned(), vec![])
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Labeled {}.", label));
+
+        Ok(())
+    }
+
+    // Sign an event of ours with these tags (with proof of work if the settings
+    // ask for it), send it to our write relays and the `also_to` relays, and
+    // process it for ourself so it shows right away. This returns None if we
+    // have no public key.
+    async fn sign_and_broadcast(
+        &mut self,
+        kind: EventKind,
+        mut tags: Vec<Tag>,
+        content: String,
+        also_to: Vec<RelayUrl>,
+    ) -> Result<Option<Event>, Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(None);
+            }
+        };
+
+        if GLOBALS.storage.read_setting_set_client_tag() {
+            tags.push(Tag::new(&["client", "gossip"]));
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind,
+            tags,
+            content,
+        };
+
+        let event = crate::nip13::sign_event(pre_event).await?;
+
+        let mut relay_urls: Vec<RelayUrl> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?
+            .drain(..)
+            .map(|relay| relay.url)
+            .collect();
+        relay_urls.extend(also_to);
+        relay_urls.sort();
+        relay_urls.dedup();
+
+        for url in relay_urls {
+            // Send it the event to post
+            tracing::debug!("Asking {} to post", &url);
+
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        Ok(Some(event))
+    }
+
     /// Like a post. The backend doesn't read the event, so you have to supply the
     /// pubkey author too.
     pub async fn like(&mut self, id: Id, pubkey: PublicKey) -> Result<(), Error> {
@@ -1559,18 +2294,7 @@ impl Overlord {
                 content: "+".to_owned(),
             };
 
-            let powint = GLOBALS.storage.read_setting_pow();
-            if powint > 0 {
-                let (work_sender, work_receiver) = mpsc::channel();
-                std::thread::spawn(move || {
-                    work_logger(work_receiver, powint);
-                });
-                GLOBALS
-                    .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
-            } else {
-                GLOBALS.identity.sign_event(pre_event)?
-            }
+            crate::nip13::sign_event(pre_event).await?
         };
 
         let relays: Vec<Relay> = GLOBALS
@@ -1705,10 +2429,11 @@ impl Overlord {
 
         // Handle the request
         if let Some(mut server) = GLOBALS.storage.read_nip46server(pubkey)? {
-            // Temporarily set the approval (we don't save this)
-            // NOTE: for now we set the server approval setting in memory but don't save it back.
-            //       So the approval only applies to this one time. FIXME: we should use the options
-            //       to approve always (saved) and Until a set time.
+            if matches!(approval, Approval::None) {
+                server.decline(&parsed_command)?;
+                return Ok(());
+            }
+
             match parsed_command.method.as_str() {
                 "sign_event" => server.sign_approval = approval,
                 "nip04_encrypt" | "nip44_encrypt" => server.encrypt_approval = approval,
@@ -1720,12 +2445,53 @@ impl Overlord {
                 _ => {}
             }
 
+            // Lasting approvals are remembered for this app
+            if matches!(approval, Approval::Always | Approval::Until(_)) {
+                GLOBALS.storage.write_nip46server(&server, None)?;
+            }
+
             server.handle(&parsed_command)?;
         }
 
         Ok(())
     }
 
+    /// Pay the invoice fetched from a relay through the connected wallet, and
+    /// connect to it again once paid
+    pub fn pay_relay_invoice(relay_url: RelayUrl) {
+        let (invoice, msats) = match GLOBALS.relay_payments.get(&relay_url).as_deref() {
+            Some(RelayPayment::ReadyToPay { invoice, msats }) => (invoice.clone(), *msats),
+            _ => return,
+        };
+
+        GLOBALS
+            .relay_payments
+            .insert(relay_url.clone(), RelayPayment::Paying);
+        std::mem::drop(tokio::spawn(async move {
+            let result = match crate::nip47::wallet() {
+                Ok(Some(wallet)) => wallet.pay_invoice(&invoice).await.map(|_preimage| ()),
+                Ok(None) => Err("No wallet is connected".into()),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::RelayPaid(relay_url));
+                }
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Your wallet could not pay {}: {}", relay_url, e));
+                    GLOBALS
+                        .relay_payments
+                        .insert(relay_url, RelayPayment::ReadyToPay { invoice, msats });
+                }
+            }
+        }));
+    }
+
     /// Trigger the relay picker to find relays for people not fully covered
     pub async fn refresh_scores_and_pick_relays(&mut self) -> Result<(), Error> {
         // When manually doing this, we refresh person_relay scores first which
@@ -1789,6 +2555,14 @@ impl Overlord {
 
         let mut maybe_parent: Option<Event> = None;
 
+        // Send NIP-17 direct messages whenever everybody in the conversation
+        // has said where they want them. Group conversations require this.
+        if let Some(dmc) = &dm_channel {
+            if dmc.keys().iter().all(|pk| crate::nip17::can_receive(*pk)) {
+                return self.post_dm(public_key, dmc, content).await;
+            }
+        }
+
         let pre_event = match dm_channel {
             Some(dmc) => {
                 if dmc.keys().len() > 1 {
@@ -1839,10 +2613,23 @@ impl Overlord {
                         NostrBech32::EventPointer(ep) => {
                             // NIP-10: "Those marked with "mention" denote a quoted or reposted event id."
                             add_event_to_tags(&mut tags, ep.id, None, "mention").await;
+                            if dm_channel.is_none() {
+                                let relay = ep.relays.first().cloned();
+                                add_quote_to_tags(&mut tags, ep.id, relay).await;
+                                if let Some(author) = ep.author {
+                                    add_pubkey_to_tags(&mut tags, author).await;
+                                }
+                            }
                         }
                         NostrBech32::Id(id) => {
                             // NIP-10: "Those marked with "mention" denote a quoted or reposted event id."
                             add_event_to_tags(&mut tags, *id, None, "mention").await;
+                            if dm_channel.is_none() {
+                                add_quote_to_tags(&mut tags, *id, None).await;
+                                if let Some(quoted) = GLOBALS.storage.read_event(*id)? {
+                                    add_pubkey_to_tags(&mut tags, quoted.pubkey).await;
+                                }
+                            }
                         }
                         NostrBech32::Profile(prof) => {
                             if dm_channel.is_none() {
@@ -1985,20 +2772,7 @@ impl Overlord {
             })
             .collect();
 
-        let event = {
-            let powint = GLOBALS.storage.read_setting_pow();
-            if powint > 0 {
-                let (work_sender, work_receiver) = mpsc::channel();
-                std::thread::spawn(move || {
-                    work_logger(work_receiver, powint);
-                });
-                GLOBALS
-                    .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
-            } else {
-                GLOBALS.identity.sign_event(pre_event)?
-            }
-        };
+        let event = crate::nip13::sign_event(pre_event).await?;
 
         // Process this event locally
         crate::process::process_new_event(&event, None, None, false, false).await?;
@@ -2059,7 +2833,62 @@ impl Overlord {
         Ok(())
     }
 
+    /// Send a NIP-17 direct message: seal and gift wrap it for each person in
+    /// the conversation and publish each wrap to that person's DM relays
+    async fn post_dm(
+        &mut self,
+        public_key: PublicKey,
+        dm_channel: &DmChannel,
+        content: String,
+    ) -> Result<(), Error> {
+        let wraps = crate::nip17::wrap_message(public_key, dm_channel.keys(), &content)?;
+
+        for (pubkey, wrap) in wraps {
+            let relay_urls = if pubkey == public_key {
+                // Keep our own copy, so the conversation shows what we said
+                crate::process::process_new_event(&wrap, None, None, false, false).await?;
+                crate::nip17::my_dm_relays()?
+            } else {
+                crate::nip17::dm_relays(pubkey)?
+            };
+
+            for url in relay_urls {
+                tracing::debug!("Asking {} to post a direct message", &url);
+
+                self.engage_minion(
+                    url,
+                    vec![RelayJob {
+                        reason: RelayConnectionReason::PostEvent,
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::PostEvents(vec![wrap.clone()]),
+                        },
+                    }],
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn post_again(&mut self, event: Event) -> Result<(), Error> {
+        if crate::nip40::is_expired(&event, Unixtime::now().unwrap()) {
+            GLOBALS
+                .status_queue
+                .write()
+                .write_warning("That event has expired, so it was not posted again.".to_owned());
+            return Ok(());
+        }
+
+        if crate::nip70::is_protected(&event) && Some(event.pubkey) != GLOBALS.identity.public_key()
+        {
+            GLOBALS.status_queue.write().write_warning(
+                "That event is protected, so only its author may post it again.".to_owned(),
+            );
+            return Ok(());
+        }
+
         let relay_urls = Globals::relays_for_event(&event)?;
 
         for url in relay_urls {
@@ -2107,6 +2936,41 @@ impl Overlord {
         Ok(())
     }
 
+    /// Post a TextNote into a moderated community (NIP-72). It will only show in the
+    /// community feed once a moderator approves it.
+    pub async fn post_to_community(
+        &mut self,
+        content: String,
+        mut tags: Vec<Tag>,
+        community_addr: EventAddr,
+    ) -> Result<(), Error> {
+        // Tag the community
+        tags.push(Tag::new_address(&community_addr, None));
+
+        // Tag the community owner, so they notice
+        add_pubkey_to_tags(&mut tags, community_addr.author).await;
+
+        // Find and tag all hashtags
+        for capture in GLOBALS.hashtag_regex.captures_iter(&content) {
+            tags.push(Tag::new_hashtag(capture[1][1..].to_string()));
+        }
+
+        // Post to the community relays as well as our own write relays
+        let community_relays: Vec<RelayUrl> = match Community::load(&community_addr)? {
+            Some(community) => community.relay_urls()?,
+            None => community_addr
+                .relays
+                .iter()
+                .filter_map(|u| RelayUrl::try_from_unchecked_url(u).ok())
+                .collect(),
+        };
+
+        self.sign_and_broadcast(EventKind::TextNote, tags, content, community_relays)
+            .await?;
+
+        Ok(())
+    }
+
     /// Prune the cache (downloaded files)
     pub async fn prune_cache() -> Result<(), Error> {
         GLOBALS
@@ -2142,7 +3006,20 @@ impl Overlord {
                 GLOBALS.storage.read_setting_prune_period_days() * 60 * 60 * 24,
                 0,
             );
-        let count = GLOBALS.storage.prune(then)?;
+        let ids = GLOBALS.storage.prunable_events(then)?;
+        GLOBALS.status_queue.write().write(format!(
+            "Pruning database, removing {} old events..",
+            ids.len()
+        ));
+        let count = GLOBALS.storage.delete_events_and_indexes(&ids)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Pruning database, removing expired events..".to_owned());
+        let count = count + GLOBALS.storage.prune_expired(now)?;
+
+        GLOBALS.storage.write_last_prune(now, None)?;
 
         GLOBALS.status_queue.write().write(format!(
             "Database has been pruned. {} events removed.",
@@ -2152,6 +3029,132 @@ impl Overlord {
         Ok(())
     }
 
+    /// Count what the database holds. The counts arrive in
+    /// `GLOBALS.storage_stats`
+    pub async fn update_storage_stats() -> Result<(), Error> {
+        let stats = tokio::task::spawn_blocking(move || GLOBALS.storage.get_stats()).await??;
+        *GLOBALS.storage_stats.write() = Some(stats);
+        Ok(())
+    }
+
+    // Prune the database once a day, if the `prune_automatically` setting is on
+    fn start_scheduled_pruning() {
+        tokio::task::spawn(async {
+            loop {
+                tokio::time::sleep(Duration::from_secs(60 * 10)).await;
+
+                if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                if !GLOBALS.storage.read_setting_prune_automatically() {
+                    continue;
+                }
+
+                let now = Unixtime::now().unwrap();
+                match GLOBALS.storage.read_last_prune() {
+                    Ok(Some(last)) if now.0 - last.0 < 60 * 60 * 24 => continue,
+                    Ok(_) => (),
+                    Err(e) => {
+                        tracing::error!("Scheduled prune: {}", e);
+                        continue;
+                    }
+                }
+
+                match tokio::task::spawn_blocking(Self::prune_database).await {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => tracing::error!("Scheduled prune: {}", e),
+                    Err(e) => tracing::error!("Scheduled prune: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Publish a long-form article (NIP-23). Giving the `d` of one of our
+    /// articles replaces it, keeping when it was first published.
+    pub async fn publish_long_form_article(
+        &mut self,
+        d: Option<String>,
+        title: String,
+        summary: String,
+        image: String,
+        hashtags: Vec<String>,
+        content: String,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        if title.trim().is_empty() {
+            return Err("An article needs a title".into());
+        }
+
+        let (d, published_at) = match d {
+            Some(d) => {
+                let published_at = GLOBALS
+                    .storage
+                    .get_replaceable_event(EventKind::LongFormContent, public_key, &d)?
+                    .as_ref()
+                    .and_then(crate::nip23::LongFormArticle::from_event)
+                    .map(|a| a.published());
+                (d, published_at)
+            }
+            None => (crate::nip23::new_d(&title), None),
+        };
+        let tags = crate::nip23::article_tags(
+            &d,
+            &title,
+            &summary,
+            &image,
+            published_at.unwrap_or(Unixtime::now().unwrap()),
+            &hashtags,
+        );
+
+        self.sign_and_broadcast(EventKind::LongFormContent, tags, content, vec![])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Publish a wiki article (NIP-54). Its topic comes from the title. If it is
+    /// a fork of someone else's article, it says so.
+    pub async fn publish_wiki_article(
+        &mut self,
+        title: String,
+        summary: String,
+        content: String,
+        fork_of: Option<Id>,
+    ) -> Result<(), Error> {
+        if crate::nip54::normalize(&title).is_empty() {
+            return Err("A wiki article needs a title".into());
+        }
+
+        let source = match fork_of {
+            Some(id) => GLOBALS.storage.read_event(id)?,
+            None => None,
+        };
+
+        let tags = crate::nip54::article_tags(&title, &summary, source.as_ref());
+        if self
+            .sign_and_broadcast(crate::nip54::wiki_kind(), tags, content, vec![])
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Published wiki article \"{}\".", title.trim()));
+
+        Ok(())
+    }
+
     /// Publish the user's specified PersonList
     pub async fn push_person_list(&mut self, list: PersonList) -> Result<(), Error> {
         let metadata = match GLOBALS.storage.get_person_list_metadata(list)? {
@@ -2231,6 +3234,50 @@ impl Overlord {
         Ok(())
     }
 
+    /// Publish our settings (encrypted to ourselves) as NIP-78 application data, so
+    /// that they roam to our other gossip installs. Does nothing unless settings sync
+    /// is enabled.
+    pub async fn push_settings_sync(&mut self) -> Result<(), Error> {
+        if !GLOBALS.storage.read_setting_settings_sync() {
+            return Ok(());
+        }
+
+        let content = match crate::settings_sync::build_settings_content()? {
+            Some(content) => content,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+
+        let tags = vec![Tag::new_identifier(
+            crate::settings_sync::SETTINGS_SYNC_D.to_owned(),
+        )];
+        self.sign_and_broadcast(EventKind::AppSpecificData, tags, content, vec![])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Publish our profile badges (NIP-58, kind 30008), which accepts the given badge
+    /// awards and sets their display order.
+    pub async fn push_profile_badges(&mut self, badges: Vec<(EventAddr, Id)>) -> Result<(), Error> {
+        let mut tags: Vec<Tag> = vec![Tag::new_identifier(
+            crate::badges::PROFILE_BADGES_D.to_string(),
+        )];
+        for (addr, award) in badges.iter() {
+            tags.push(Tag::new_address(addr, None));
+            tags.push(Tag::new_event(*award, None, None));
+        }
+
+        if self
+            .sign_and_broadcast(EventKind::ProfileBadges, tags, "".to_owned(), vec![])
+            .await?
+            .is_none()
+        {
+            return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()); // not even a public key
+        }
+
+        Ok(())
+    }
+
     /// Rank a relay from 0 to 9.  The default rank is 3.  A rank of 0 means the relay will not be used.
     /// This represent a user's judgement, and is factored into how suitable a relay is for various
     /// purposes.
@@ -2242,6 +3289,47 @@ impl Overlord {
         Ok(())
     }
 
+    /// A relay that wanted to be paid is paid. Authenticate to it from now on,
+    /// connect to it again, and post the events it turned away.
+    pub async fn relay_paid(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
+        GLOBALS.relay_payments.remove(&relay_url);
+        let events = crate::relay_payment::take_held_events(&relay_url);
+        crate::relay_penalty::forgive(&relay_url);
+        self.auth_approved(relay_url.clone())?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("{} is paid. Connecting to it again.", relay_url));
+
+        let mut jobs: Vec<RelayJob> = Vec::new();
+        if !events.is_empty() {
+            jobs.push(RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(events),
+                },
+            });
+        }
+
+        if GLOBALS.connected_relays.contains_key(&relay_url) {
+            // The new minion takes these up along with the persistent jobs of
+            // the old one, once it has exited
+            if !jobs.is_empty() {
+                self.jobs_after_exit
+                    .entry(relay_url.clone())
+                    .or_default()
+                    .extend(jobs);
+            }
+            self.drop_relay(relay_url)?;
+        } else {
+            self.engage_minion(relay_url, jobs).await?;
+        }
+
+        Ok(())
+    }
+
     /// Refresh metadata for everybody who is followed
     /// This gets it whether we had it or not. Because it might have changed.
     pub async fn refresh_subscribed_metadata(&mut self) -> Result<(), Error> {
@@ -2287,6 +3375,35 @@ impl Overlord {
         Ok(())
     }
 
+    /// Report a person, or one of their events (NIP-56)
+    pub async fn report(
+        &mut self,
+        pubkey: PublicKey,
+        id: Option<Id>,
+        report_type: String,
+        comment: String,
+    ) -> Result<(), Error> {
+        let tags = crate::nip56::report_tags(pubkey, id, &report_type);
+        if self
+            .sign_and_broadcast(EventKind::Reporting, tags, comment, vec![])
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        if GLOBALS.storage.read_setting_mute_when_reporting() {
+            GLOBALS.people.mute(&pubkey, true, true)?;
+        }
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Report sent.".to_owned());
+
+        Ok(())
+    }
+
     /// Repost a post by `Id`
     pub async fn repost(&mut self, id: Id) -> Result<(), Error> {
         let reposted_event = match GLOBALS.storage.read_event(id)? {
@@ -2295,7 +3412,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write("Cannot repost - cannot find event.".to_owned());
+                    .write_warning("Cannot repost - cannot find event.".to_owned());
                 return Ok(());
             }
         };
@@ -2361,18 +3478,7 @@ impl Overlord {
                 content: serde_json::to_string(&reposted_event)?,
             };
 
-            let powint = GLOBALS.storage.read_setting_pow();
-            if powint > 0 {
-                let (work_sender, work_receiver) = mpsc::channel();
-                std::thread::spawn(move || {
-                    work_logger(work_receiver, powint);
-                });
-                GLOBALS
-                    .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
-            } else {
-                GLOBALS.identity.sign_event(pre_event)?
-            }
+            crate::nip13::sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -2393,29 +3499,120 @@ impl Overlord {
             relay_urls.dedup();
         }
 
-        for url in relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to (re)post", &url);
+        for url in relay_urls {
+            // Send it the event to post
+            tracing::debug!("Asking {} to (re)post", &url);
+
+            self.engage_minion(
+                url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Ask data vending machines to do a job (NIP-90). Their feedback and results
+    /// are shown on the input note, or for content discovery, on the search page.
+    pub async fn request_job(
+        &mut self,
+        job_type: JobType,
+        input: Option<Id>,
+        language: Option<String>,
+        bid_msats: Option<u64>,
+    ) -> Result<(), Error> {
+        let tags = crate::nip90::job_request_tags(job_type, input, language, bid_msats)?;
+        if self
+            .sign_and_broadcast(job_type.request_kind(), tags, "".to_owned(), vec![])
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        if let Some(id) = input {
+            GLOBALS.ui_notes_to_invalidate.write().push(id);
+        }
+
+        GLOBALS.status_queue.write().write(format!(
+            "Asked data vending machines for a {}.",
+            job_type.name().to_lowercase()
+        ));
+
+        Ok(())
+    }
+
+    /// Ask OpenTimestamps calendars to timestamp one of our events (NIP-03). The
+    /// attestation is published once the calendars have put it into bitcoin, see
+    /// [upgrade_timestamps](crate::Overlord::upgrade_timestamps).
+    pub async fn request_timestamp(&mut self, id: Id) -> Result<(), Error> {
+        let file = crate::nip03::request(id).await?;
+
+        let mut pending = GLOBALS.storage.read_pending_timestamps()?;
+        pending.retain(|(pending_id, _)| *pending_id != id);
+        pending.push((id, file));
+        GLOBALS.storage.write_pending_timestamps(&pending, None)?;
+
+        GLOBALS.status_queue.write().write(
+            "Submitted for timestamping. The attestation will be published once it is in bitcoin, usually within a few hours.".to_owned(),
+        );
+
+        Ok(())
+    }
 
-            self.engage_minion(
-                url.clone(),
-                vec![RelayJob {
-                    reason: RelayConnectionReason::PostEvent,
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                    },
-                }],
+    /// Answer an invitation to a calendar event (NIP-52), replacing any earlier
+    /// answer of ours
+    pub async fn rsvp(&mut self, id: Id, status: RsvpStatus) -> Result<(), Error> {
+        let calendar_event = match GLOBALS.storage.read_event(id)? {
+            Some(event) => match CalendarEvent::from_event(&event) {
+                Some(calendar_event) => calendar_event,
+                None => return Err(ErrorKind::General("Not a calendar event".to_owned()).into()),
+            },
+            None => return Err(ErrorKind::EventNotFound.into()),
+        };
+
+        // Tell the author's inbox relays too, so they hear about it
+        let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+        let inbox_relays: Vec<RelayUrl> = GLOBALS
+            .storage
+            .get_best_relays(calendar_event.event.pubkey, Direction::Read)?
+            .drain(..)
+            .take(num_relays_per_person as usize + 1)
+            .map(|(relay_url, _)| relay_url)
+            .collect();
+
+        let tags = crate::nip52::rsvp_tags(&calendar_event, status);
+        if self
+            .sign_and_broadcast(
+                EventKind::CalendarEventRsvp,
+                tags,
+                "".to_owned(),
+                inbox_relays,
             )
-            .await?;
+            .await?
+            .is_none()
+        {
+            return Ok(());
         }
 
+        GLOBALS.status_queue.write().write(format!(
+            "Answered \"{}\" to {}.",
+            status, calendar_event.title
+        ));
+
         Ok(())
     }
 
-    /// Search people and notes in the local database.
+    /// Search people and notes in the local database, within the given scope.
     /// Search results eventually arrive in `GLOBALS.people_search_results` and `GLOBALS.note_search_results`
-    pub async fn search(mut text: String) -> Result<(), Error> {
+    pub async fn search(mut text: String, scope: SearchScope) -> Result<(), Error> {
         if text.len() < 2 {
             GLOBALS
                 .status_queue
@@ -2513,32 +3710,88 @@ impl Overlord {
             }
         }
 
-        people_search_results.extend(GLOBALS.storage.filter_people(|p| {
-            if let Some(metadata) = &p.metadata {
-                if let Ok(s) = serde_json::to_string(&metadata) {
-                    if s.to_lowercase().contains(&text) {
-                        return true;
-                    }
-                }
-            }
+        let query = SearchQuery::parse(&text, scope);
+        if !query.unresolved.is_empty() {
+            GLOBALS.status_queue.write().write(format!(
+                "Search did not understand: {}",
+                query.unresolved.join(" ")
+            ));
+            Self::set_search_results(people_search_results, note_search_results);
+            return Ok(());
+        }
 
-            if let Some(petname) = &p.petname {
-                if petname.to_lowercase().contains(&text) {
-                    return true;
-                }
-            }
+        if query.has_operators() {
+            note_search_results.extend(GLOBALS.storage.search_events_by_query(&query)?);
+
+            Self::set_search_results(people_search_results, note_search_results);
 
-            false
-        })?);
+            return Ok(());
+        }
+
+        people_search_results.extend(crate::search::search_people(&text)?);
 
         note_search_results.extend(GLOBALS.storage.search_events(&text)?);
 
-        *GLOBALS.people_search_results.write() = people_search_results;
-        *GLOBALS.note_search_results.write() = note_search_results;
+        Self::set_search_results(people_search_results, note_search_results);
+
+        Ok(())
+    }
+
+    /// Search the local database as [search](crate::Overlord::search) does, and also
+    /// ask the relays marked for search to find notes (NIP-50). Notes they find are
+    /// stored and added to `GLOBALS.note_search_results` as they come in.
+    pub async fn search_relays(&mut self, text: String) -> Result<(), Error> {
+        Self::search(text.clone(), SearchScope::Everywhere).await?;
+
+        let text = text.trim().to_owned();
+        if text.len() < 2 {
+            return Ok(());
+        }
+
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::SEARCH) && r.rank != 0)?;
+        if relays.is_empty() {
+            GLOBALS.status_queue.write().write(
+                "No relays are marked for search. Turn on Search for some on the relays page."
+                    .to_owned(),
+            );
+            return Ok(());
+        }
+
+        for relay in relays {
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::SearchNotes,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SearchNotes(text.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
 
         Ok(())
     }
 
+    fn set_search_results(people: Vec<Person>, mut notes: Vec<Event>) {
+        // Deleted notes are left out unless the user wants to see them
+        if !GLOBALS.storage.read_setting_show_deleted_events() {
+            notes.retain(|event| {
+                GLOBALS
+                    .storage
+                    .get_deletions(event)
+                    .map(|reasons| reasons.is_empty())
+                    .unwrap_or(true)
+            });
+        }
+
+        *GLOBALS.people_search_results.write() = people;
+        *GLOBALS.note_search_results.write() = notes;
+    }
+
     /// Set a particular person as active in the `People` structure. This affects the results of
     /// some functions of that structure
     pub async fn set_active_person(pubkey: PublicKey) -> Result<(), Error> {
@@ -2546,6 +3799,51 @@ impl Overlord {
         Ok(())
     }
 
+    async fn set_community_feed(&mut self, community_addr: EventAddr) -> Result<(), Error> {
+        // subscribe on the community relays (if we know them), the relay hints
+        // in the address, and the owner's outbox relays
+        let mut relays: Vec<RelayUrl> = community_addr
+            .relays
+            .iter()
+            .filter_map(|u| RelayUrl::try_from_unchecked_url(u).ok())
+            .collect();
+        match Community::load(&community_addr)? {
+            Some(community) => relays.extend(community.relay_urls()?),
+            None => {
+                let num_relays_per_person = GLOBALS.storage.read_setting_num_relays_per_person();
+                relays.extend(
+                    GLOBALS
+                        .storage
+                        .get_best_relays(community_addr.author, Direction::Write)?
+                        .drain(..)
+                        .take(num_relays_per_person as usize + 1)
+                        .map(|(relay, _rank)| relay),
+                );
+            }
+        }
+        relays.sort();
+        relays.dedup();
+
+        for relay in relays.iter() {
+            // Subscribe
+            self.engage_minion(
+                relay.to_owned(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::ReadCommunity,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeCommunityFeed(
+                            community_addr.clone(),
+                        ),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
     async fn set_dm_channel(&mut self, dmchannel: DmChannel) -> Result<(), Error> {
         // subscribe to channel on outbox and inbox relays
         //   outbox: you may have written them there. Other clients may have too.
@@ -2720,7 +4018,7 @@ impl Overlord {
             GLOBALS
                 .status_queue
                 .write()
-                .write("Could not find any relays for that event".to_owned());
+                .write_warning("Could not find any relays for that event".to_owned());
             return Ok(());
         } else {
             // Clean up relays
@@ -2758,6 +4056,30 @@ impl Overlord {
         Ok(())
     }
 
+    /// Set (or with empty content, clear) one of our user statuses (NIP-38)
+    pub async fn set_user_status(
+        &mut self,
+        status_type: String,
+        content: String,
+        link: Option<String>,
+        expiration: Option<Unixtime>,
+    ) -> Result<(), Error> {
+        let mut tags: Vec<Tag> = vec![Tag::new_identifier(status_type)];
+        if let Some(link) = link {
+            if !link.is_empty() {
+                tags.push(Tag::new(&["r", &link]));
+            }
+        }
+        if let Some(expiration) = expiration {
+            tags.push(crate::nip40::expiration_tag(expiration));
+        }
+
+        self.sign_and_broadcast(EventKind::UserStatus, tags, content, vec![])
+            .await?;
+
+        Ok(())
+    }
+
     /// This is done at startup and after the wizard.
     pub async fn start_long_lived_subscriptions(&mut self) -> Result<(), Error> {
         // Pick Relays and start Minions
@@ -2857,12 +4179,22 @@ impl Overlord {
     pub async fn subscribe_mentions(&mut self, relays: Option<Vec<RelayUrl>>) -> Result<(), Error> {
         let mention_relays: Vec<RelayUrl> = match relays {
             Some(r) => r,
-            None => GLOBALS
-                .storage
-                .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
-                .iter()
-                .map(|relay| relay.url.clone())
-                .collect(),
+            None => {
+                let mut urls: Vec<RelayUrl> = GLOBALS
+                    .storage
+                    .filter_relays(|r| r.has_usage_bits(Relay::READ) && r.rank != 0)?
+                    .iter()
+                    .map(|relay| relay.url.clone())
+                    .collect();
+
+                // Direct messages come in on our DM relays
+                for url in crate::nip17::my_dm_relays()? {
+                    if !urls.contains(&url) {
+                        urls.push(url);
+                    }
+                }
+                urls
+            }
         };
         for relay_url in mention_relays.iter() {
             self.engage_minion(
@@ -2915,7 +4247,7 @@ impl Overlord {
             GLOBALS
                 .status_queue
                 .write()
-                .write("The passphrase is wrong, try again".to_owned());
+                .write_error("The passphrase is wrong, try again".to_owned());
         };
         password.zeroize();
 
@@ -3003,10 +4335,9 @@ impl Overlord {
         let my_pubkey = match GLOBALS.storage.read_setting_public_key() {
             Some(pk) => pk,
             None => {
-                GLOBALS
-                    .status_queue
-                    .write()
-                    .write("You cannot update person lists without an identity".to_string());
+                GLOBALS.status_queue.write().write_warning(
+                    "You cannot update person lists without an identity".to_string(),
+                );
                 return Ok(());
             }
         };
@@ -3029,7 +4360,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write("Could not find a person-list event to update from".to_string());
+                    .write_warning("Could not find a person-list event to update from".to_string());
                 return Ok(()); // we have no event to update from, so we are done
             }
         };
@@ -3200,6 +4531,11 @@ impl Overlord {
             return Ok(());
         }
 
+        let list_bits = Relay::INBOX | Relay::OUTBOX;
+        if old.get_usage_bits() & list_bits != new.get_usage_bits() & list_bits {
+            Self::relay_list_changed();
+        }
+
         // If rank went to zero
         if old.rank != 0 && new.rank == 0 {
             // Close minion for this relay
@@ -3298,6 +4634,132 @@ impl Overlord {
         Ok(())
     }
 
+    /// Publish attestations (NIP-03) for our events whose timestamps have made it
+    /// into bitcoin, and keep waiting on the rest
+    pub async fn upgrade_timestamps(&mut self) -> Result<(), Error> {
+        // We can't sign them yet, so leave them pending
+        if !GLOBALS.identity.is_unlocked() {
+            return Ok(());
+        }
+
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let pending = GLOBALS.storage.read_pending_timestamps()?;
+        let mut still_pending: Vec<(Id, Vec<u8>)> = Vec::new();
+        let mut events: Vec<Event> = Vec::new();
+
+        for (id, file) in pending {
+            let upgraded = match crate::nip03::upgrade(id, &file).await {
+                Ok(Some(upgraded)) => upgraded,
+                Ok(None) => {
+                    still_pending.push((id, file));
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Could not upgrade timestamp of {}: {}",
+                        id.as_hex_string(),
+                        e
+                    );
+                    still_pending.push((id, file));
+                    continue;
+                }
+            };
+
+            let mut tags: Vec<Tag> = vec![Tag::new_event(id, None, None)];
+            if let Some(event) = GLOBALS.storage.read_event(id)? {
+                tags.push(Tag::new_kind(event.kind));
+            }
+
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now().unwrap(),
+                kind: EventKind::Timestamp,
+                tags,
+                content: crate::nip03::encode(&upgraded),
+            };
+
+            let event = GLOBALS.identity.sign_event(pre_event)?;
+
+            // Process this event locally
+            crate::process::process_new_event(&event, None, None, false, false).await?;
+
+            events.push(event);
+        }
+
+        GLOBALS
+            .storage
+            .write_pending_timestamps(&still_pending, None)?;
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let relays: Vec<Relay> = GLOBALS
+            .storage
+            .filter_relays(|r| r.has_usage_bits(Relay::WRITE) && r.rank != 0)?;
+
+        for relay in relays {
+            tracing::debug!("Posting timestamp attestations to {}", &relay.url);
+
+            self.engage_minion(
+                relay.url.clone(),
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(events.clone()),
+                    },
+                }],
+            )
+            .await?;
+        }
+
+        GLOBALS.status_queue.write().write(format!(
+            "Published {} timestamp attestation(s).",
+            events.len()
+        ));
+
+        Ok(())
+    }
+
+    /// Upload a file to our media server (NIP-96). When it is done the upload lands in
+    /// `GLOBALS.uploaded_media` for the UI to put into the draft.
+    pub fn upload_media(path: PathBuf) {
+        let name = path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Uploading {}...", name));
+
+        std::mem::drop(tokio::spawn(async move {
+            match crate::nip96::upload_file(&path).await {
+                Ok(upload) => {
+                    GLOBALS.uploaded_media.write().push(upload);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Uploaded {}.", name));
+                }
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Could not upload {}: {}", name, e));
+                }
+            }
+        }));
+    }
+
     /// Set which notes are currently visible to the user. This is used to modify subscriptions
     /// that query for likes, zaps, and deletions. Such subscriptions only query for that data
     /// for events currently in view, to keep them small.
@@ -3352,14 +4814,14 @@ impl Overlord {
             GLOBALS
                 .status_queue
                 .write()
-                .write("You need to setup your private-key to zap.".to_string());
+                .write_warning("You need to setup your private-key to zap.".to_string());
             *GLOBALS.current_zap.write() = ZapState::None;
             return Ok(());
         }
 
         *GLOBALS.current_zap.write() = ZapState::CheckingLnurl(id, target_pubkey, lnurl.clone());
 
-        let client = reqwest::Client::builder()
+        let client = crate::proxy::client_builder()?
             .timeout(std::time::Duration::new(15, 0))
             .gzip(true)
             .brotli(true)
@@ -3379,7 +4841,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write(format!("Zap pay request data invalid: {}, {}", text, e));
+                    .write_error(format!("Zap pay request data invalid: {}, {}", text, e));
                 *GLOBALS.current_zap.write() = ZapState::None;
                 return Ok(());
             }
@@ -3416,7 +4878,7 @@ impl Overlord {
                 GLOBALS
                     .status_queue
                     .write()
-                    .write("You need to setup your private-key to zap.".to_string());
+                    .write_warning("You need to setup your private-key to zap.".to_string());
                 *GLOBALS.current_zap.write() = ZapState::None;
                 return Ok(());
             }
@@ -3449,7 +4911,7 @@ impl Overlord {
                     GLOBALS
                         .status_queue
                         .write()
-                        .write("Zap amount is too low.".to_string());
+                        .write_warning("Zap amount is too low.".to_string());
                     // leave zap state as is.
                     return Ok(());
                 }
@@ -3462,7 +4924,7 @@ impl Overlord {
                     GLOBALS
                         .status_queue
                         .write()
-                        .write("Zap amount is too high.".to_string());
+                        .write_warning("Zap amount is too high.".to_string());
                     // leave zap state as is.
                     return Ok(());
                 }
@@ -3539,7 +5001,7 @@ impl Overlord {
         let event = GLOBALS.identity.sign_event(pre_event)?;
         let serialized_event = serde_json::to_string(&event)?;
 
-        let client = reqwest::Client::builder()
+        let client = crate::proxy::client_builder()?
             .timeout(std::time::Duration::new(15, 0))
             .gzip(true)
             .brotli(true)
@@ -3567,7 +5029,33 @@ impl Overlord {
         if let Value::Object(map) = value {
             if let Some(Value::String(s)) = map.get("pr") {
                 tracing::debug!("Zap Invoice = {}", s);
-                *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, s.to_owned());
+                let invoice = s.to_owned();
+                if !crate::nip47::is_connected() {
+                    *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, invoice);
+                    return Ok(());
+                }
+
+                // The wallet pays without asking, so only pay the invoice we asked for
+                if let Err(e) =
+                    crate::relay_payment::check_zap_invoice(&invoice, msats.0, &serialized_event)
+                {
+                    tracing::warn!(
+                        "Not paying zap invoice {} through the wallet: {}",
+                        invoice,
+                        e
+                    );
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Not paying the zap through your wallet: {}", e));
+                    *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, invoice);
+                    return Ok(());
+                }
+
+                *GLOBALS.current_zap.write() = ZapState::Paying(id);
+                std::mem::drop(tokio::spawn(async move {
+                    Self::pay_zap_through_wallet(id, invoice).await;
+                }));
                 return Ok(());
             }
         }
@@ -3577,26 +5065,52 @@ impl Overlord {
         GLOBALS
             .status_queue
             .write()
-            .write("Zap invoice data not recognized.".to_string());
+            .write_error("Zap invoice data not recognized.".to_string());
 
         Ok(())
     }
-}
 
-fn work_logger(work_receiver: mpsc::Receiver<u8>, powint: u8) {
-    while let Ok(work) = work_receiver.recv() {
-        if work >= powint {
-            // Even if work > powint, it doesn't count since we declared our target.
-            GLOBALS
-                .status_queue
-                .write()
-                .write(format!("Message sent with {powint} bits of work computed."));
-            break;
-        } else {
-            GLOBALS
-                .status_queue
-                .write()
-                .write(format!("PoW: {work}/{powint}"));
+    // Pay a zap invoice through the connected wallet. If that does not work
+    // the invoice is shown to be paid some other way.
+    async fn pay_zap_through_wallet(id: Id, invoice: String) {
+        let paid = match crate::nip47::wallet() {
+            Ok(Some(wallet)) => match wallet.pay_invoice(&invoice).await {
+                Ok(_preimage) => {
+                    let message = match wallet.get_balance().await {
+                        Ok(msats) => {
+                            format!("Zap paid. Your wallet has {} sats left.", msats / 1000)
+                        }
+                        Err(_) => "Zap paid.".to_owned(),
+                    };
+                    GLOBALS.status_queue.write().write(message);
+                    true
+                }
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write_error(format!("Your wallet could not pay the zap: {}", e));
+                    false
+                }
+            },
+            Ok(None) => false,
+            Err(e) => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write_error(format!("Could not use your wallet: {}", e));
+                false
+            }
+        };
+
+        // Unless the user gave up on it meanwhile
+        let mut current_zap = GLOBALS.current_zap.write();
+        if matches!(*current_zap, ZapState::Paying(zap_id) if zap_id == id) {
+            *current_zap = if paid {
+                ZapState::None
+            } else {
+                ZapState::ReadyToPay(id, invoice)
+            };
         }
     }
 }
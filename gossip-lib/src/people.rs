@@ -26,6 +26,9 @@ pub type PersonListMetadata = crate::storage::types::PersonListMetadata3;
 
 /// Handles people and remembers what needs to be done for each, such as fetching
 /// metadata or avatars.
+///
+/// Person records are not held here. They are read from storage when needed,
+/// so nothing about the people we know is loaded at startup.
 pub struct People {
     // active person's relays (pull from db as needed)
     active_person: RwLock<Option<PublicKey>>,
@@ -51,6 +54,10 @@ pub struct People {
     // This only relates to the Metadata event, not subsequent avatar or nip05
     // loads.
     fetching_metadata: DashMap<PublicKey, Unixtime>,
+
+    // How checking people's identity claims (NIP-39) went, by person and
+    // claim. Claims still being checked are not in here.
+    pub(crate) identity_checks: DashMap<(PublicKey, String), crate::nip39::IdentityCheck>,
 }
 
 impl Default for People {
@@ -69,6 +76,7 @@ impl People {
             recheck_nip05: DashSet::new(),
             people_of_interest: DashSet::new(),
             fetching_metadata: DashMap::new(),
+            identity_checks: DashMap::new(),
         }
     }
 
@@ -115,6 +123,27 @@ impl People {
             .unwrap_or(false)
     }
 
+    /// Us and the people we follow, whose reports and labels we go by
+    pub fn get_trusted_pubkeys(&self) -> Result<Vec<PublicKey>, Error> {
+        let mut pubkeys: Vec<PublicKey> = GLOBALS
+            .storage
+            .get_people_in_list(PersonList::Followed)?
+            .drain(..)
+            .map(|(pk, _)| pk)
+            .collect();
+        if let Some(pk) = GLOBALS.identity.public_key() {
+            pubkeys.push(pk);
+        }
+        Ok(pubkeys)
+    }
+
+    /// Whether this is us or somebody we follow, whose reports and labels we
+    /// go by
+    pub fn is_trusted(&self, pubkey: &PublicKey) -> bool {
+        Some(*pubkey) == GLOBALS.identity.public_key()
+            || self.is_person_in_list(pubkey, PersonList::Followed)
+    }
+
     /// Get all the pubkeys that need relay lists (from the given set)
     pub fn get_subscribed_pubkeys_needing_relay_lists(
         &self,
@@ -126,15 +155,13 @@ impl People {
                     .storage
                     .read_setting_relay_list_becomes_stale_hours() as i64;
 
-        if let Ok(vec) = GLOBALS.storage.filter_people(|p| {
-            p.is_subscribed_to()
-                && p.relay_list_last_received < stale
-                && among_these.contains(&p.pubkey)
-        }) {
-            vec.iter().map(|p| p.pubkey).collect()
-        } else {
-            vec![]
-        }
+        // Read just these people, rather than scanning everybody we know about
+        among_these
+            .iter()
+            .filter_map(|pubkey| GLOBALS.storage.read_person(pubkey).ok().flatten())
+            .filter(|p| p.relay_list_last_received < stale && p.is_subscribed_to())
+            .map(|p| p.pubkey)
+            .collect()
     }
 
     /// Create person record for this pubkey, if missing
@@ -350,11 +377,26 @@ impl People {
         Ok(())
     }
 
+    /// Bytes held by avatars that have been processed but not yet handed to the UI
+    pub fn memory_usage(&self) -> usize {
+        self.avatars_temp.iter().map(|a| a.value().len()).sum()
+    }
+
+    /// Drop avatars that have been processed but not yet handed to the UI.
+    /// They will be processed again (from the cache) when next asked for.
+    pub fn trim_memory(&self) {
+        let pubkeys: Vec<PublicKey> = self.avatars_temp.iter().map(|a| *a.key()).collect();
+        for pubkey in &pubkeys {
+            self.avatars_temp.remove(pubkey);
+            self.avatars_pending_processing.remove(pubkey);
+        }
+    }
+
     /// Get the avatar `RgbaImage` for the person.
     ///
     /// This usually returns None when first called, and eventually returns the image.
-    /// Once the image is returned, it will return None ever after, because the image is
-    /// moved, not copied.
+    /// The image is moved, not copied, so once it is returned it is processed again if
+    /// asked for again.
     ///
     /// FIXME this API is not good for async front ends.
     pub fn get_avatar(
@@ -365,6 +407,7 @@ impl People {
     ) -> Option<RgbaImage> {
         // If we have it, hand it over (we won't need a copy anymore)
         if let Some(th) = self.avatars_temp.remove(pubkey) {
+            self.avatars_pending_processing.remove(pubkey);
             return Some(th.1);
         }
 
@@ -418,6 +461,8 @@ impl People {
             // cache expires in 3 days
             Ok(None) => None,
             Ok(Some(bytes)) => {
+                self.avatars_pending_processing.insert(pubkey.to_owned());
+
                 // Finish this later (spawn)
                 let apubkey = *pubkey;
                 tokio::spawn(async move {
@@ -442,7 +487,6 @@ impl People {
                         }
                     }
                 });
-                self.avatars_pending_processing.insert(pubkey.to_owned());
                 None
             }
             Err(e) => {
@@ -748,6 +792,49 @@ impl People {
         Ok(())
     }
 
+    /// Follow many people at once, in one transaction and with one relay
+    /// picker refresh
+    pub fn follow_all(
+        &self,
+        pubkeys: &[PublicKey],
+        list: PersonList,
+        public: bool,
+        discover: bool, // if you also want to subscribe to their relay lists
+    ) -> Result<(), Error> {
+        if pubkeys.is_empty() {
+            return Ok(());
+        }
+
+        let mut txn = GLOBALS.storage.get_write_txn()?;
+        for pubkey in pubkeys {
+            GLOBALS
+                .storage
+                .add_person_to_list(pubkey, list, public, Some(&mut txn))?;
+        }
+        txn.commit()?;
+
+        for pubkey in pubkeys {
+            GLOBALS.relay_picker.add_someone(*pubkey)?;
+        }
+
+        GLOBALS
+            .ui_people_to_invalidate
+            .write()
+            .extend(pubkeys.iter().copied());
+
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::RefreshScoresAndPickRelays);
+
+        if discover {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SubscribeDiscover(pubkeys.to_vec(), None));
+        }
+
+        Ok(())
+    }
+
     /// Clear a person list
     pub(crate) fn clear_person_list(&self, list: PersonList) -> Result<(), Error> {
         GLOBALS.storage.clear_person_list(list, None)?;
@@ -794,6 +881,48 @@ impl People {
 
         GLOBALS.ui_people_to_invalidate.write().push(*pubkey);
 
+        if GLOBALS.storage.read_setting_mute_list_sync() && GLOBALS.identity.is_unlocked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::PushPersonList(PersonList::Muted));
+        }
+
+        Ok(())
+    }
+
+    /// Take in a mute list of ours that came from elsewhere, given what we
+    /// knew of the list before it came.
+    ///
+    /// If nothing was changed here since the list we last had from a relay, the
+    /// new one replaces ours. If it was, the two are combined and published
+    /// again, so that neither side loses a mute.
+    pub(crate) fn sync_mute_list(
+        &self,
+        event: &Event,
+        previous: &PersonListMetadata,
+    ) -> Result<(), Error> {
+        if !GLOBALS.storage.read_setting_mute_list_sync()
+            || event.created_at <= previous.event_created_at
+        {
+            return Ok(());
+        }
+
+        // Edits made here since our last list came are kept however old they
+        // are, since the new list was made without them
+        let unpublished = previous.last_edit_time > previous.event_created_at;
+
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::UpdatePersonList {
+                person_list: PersonList::Muted,
+                merge: unpublished,
+            });
+        if unpublished && GLOBALS.identity.is_unlocked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::PushPersonList(PersonList::Muted));
+        }
+
         Ok(())
     }
 
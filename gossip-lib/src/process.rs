@@ -46,6 +46,44 @@ pub async fn process_new_event(
         }
     }
 
+    // Do not keep events that have already expired (NIP-40)
+    if !duplicate && crate::nip40::is_expired(event, now) {
+        tracing::trace!(
+            "{}: Expired Event: {} {:?} @{}",
+            seen_on.as_ref().map(|r| r.as_str()).unwrap_or("_"),
+            subscription.as_ref().unwrap_or(&"_".to_string()),
+            event.kind,
+            event.created_at
+        );
+        return Ok(());
+    }
+
+    // Do not keep events from strangers without enough proof of work (NIP-13)
+    if !duplicate && !crate::nip13::passes_filter(event) {
+        tracing::trace!(
+            "{}: Insufficient PoW: {} {:?} @{}",
+            seen_on.as_ref().map(|r| r.as_str()).unwrap_or("_"),
+            subscription.as_ref().unwrap_or(&"_".to_string()),
+            event.kind,
+            event.created_at
+        );
+        return Ok(());
+    }
+
+    // Do not take feed events of people followed only on specific relays
+    // from other relays
+    if let Some(url) = &seen_on {
+        if event.kind.is_feed_displayable() && !crate::relay_scope::accepts(&event.pubkey, url) {
+            tracing::trace!(
+                "{}: Not a relay {} is followed on: {:?}",
+                url.as_str(),
+                event.pubkey.as_hex_string(),
+                event.kind,
+            );
+            return Ok(());
+        }
+    }
+
     if let Some(url) = &seen_on {
         // Save seen-on-relay information
         GLOBALS
@@ -132,17 +170,22 @@ pub async fn process_new_event(
         }
     }
 
-    // Ignore if the event is already deleted (by address)
-    if let Some(parameter) = event.parameter() {
+    // Ignore if the event is already deleted (by address). Such a deletion only
+    // covers versions up to its own time; later versions are not deleted.
+    if event.kind.is_replaceable() {
         let ea = EventAddr {
-            d: parameter.to_owned(),
+            d: event.parameter().unwrap_or_default(),
             relays: vec![],
             kind: event.kind,
             author: event.pubkey,
         };
-        for (_id, relbyaddr) in GLOBALS.storage.find_relationships_by_addr(&ea)? {
+        for (deleting_id, relbyaddr) in GLOBALS.storage.find_relationships_by_addr(&ea)? {
             if let RelationshipByAddr::Deletion { by, reason: _ } = relbyaddr {
-                if by == event.pubkey {
+                let covers_this_version = match GLOBALS.storage.read_event(deleting_id)? {
+                    Some(deleting_event) => deleting_event.created_at >= event.created_at,
+                    None => false,
+                };
+                if by == event.pubkey && covers_this_version {
                     tracing::trace!(
                         "{}: Deleted Event: {} {:?} @{}",
                         seen_on.as_ref().map(|r| r.as_str()).unwrap_or("_"),
@@ -194,6 +237,18 @@ pub async fn process_new_event(
     }
     // FIXME do same for event addr
 
+    // Notes a search relay found for us are search results too
+    if subscription
+        .as_ref()
+        .map(|s| s.starts_with("temp_search_"))
+        .unwrap_or(false)
+    {
+        let mut results = GLOBALS.note_search_results.write();
+        if !results.iter().any(|e| e.id == event.id) {
+            results.push(event.clone());
+        }
+    }
+
     // If it is a GiftWrap, from here on out operate on the Rumor with the giftwrap's id
     let mut event: &Event = event; // take ownership of this reference
     let mut rumor_event: Event;
@@ -204,6 +259,11 @@ pub async fn process_new_event(
         event = &rumor_event;
     }
 
+    // Raise alerts for incoming events matching the user's rules
+    if seen_on.is_some() {
+        crate::alerts::check(event)?;
+    }
+
     if seen_on.is_some() {
         for tag in event.tags.iter() {
             if let Ok((_, Some(uurl), _optmarker)) = tag.parse_event() {
@@ -239,6 +299,19 @@ pub async fn process_new_event(
     // Invalidate UI events indicated by those relationships
     GLOBALS.ui_notes_to_invalidate.write().extend(&invalid_ids);
 
+    // If a DVM recommended notes to us, fetch the ones we don't have
+    if event.kind == crate::nip90::JobType::ContentDiscovery.result_kind() {
+        if let Some(url) = &seen_on {
+            for id in crate::nip90::discovered_ids(event) {
+                if !GLOBALS.storage.has_event(id)? {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FetchEvent(id, vec![url.to_owned()]));
+                }
+            }
+        }
+    }
+
     // If metadata, update person
     if event.kind == EventKind::Metadata {
         let metadata: Metadata = serde_json::from_str(&event.content)?;
@@ -265,34 +338,144 @@ pub async fn process_new_event(
         // Only our own
         if let Some(pubkey) = GLOBALS.identity.public_key() {
             if event.pubkey == pubkey {
+                let previous = if event.kind == EventKind::MuteList {
+                    GLOBALS
+                        .storage
+                        .get_person_list_metadata(PersonList::Muted)?
+                } else {
+                    None
+                };
+
                 // Updates stamps and counts, does NOT change membership
                 let (_personlist, _metadata) =
                     update_or_allocate_person_list_from_event(event, pubkey)?;
+
+                if let Some(previous) = previous {
+                    GLOBALS.people.sync_mute_list(event, &previous)?;
+                }
             }
         }
+    } else if event.kind == EventKind::AppSpecificData {
+        crate::settings_sync::apply_settings_event(event)?;
+    } else if event.kind == EventKind::HandlerInformation
+        || event.kind == EventKind::HandlerRecommendation
+    {
+        // Notes of unsupported kinds may now have somewhere to be opened
+        GLOBALS.ui_invalidate_all.store(true, Ordering::Relaxed);
+    } else if event.kind == EventKind::UserStatus {
+        // Rerender notes by this person, so the new status shows
+        GLOBALS.ui_people_to_invalidate.write().push(event.pubkey);
     } else if event.kind == EventKind::RelayList {
         GLOBALS.storage.process_relay_list(event)?;
 
+        // Make sure the relay picker covers somebody we follow on their new relays
+        if GLOBALS
+            .people
+            .get_subscribed_pubkeys()
+            .contains(&event.pubkey)
+        {
+            GLOBALS.relay_picker.add_someone(event.pubkey)?;
+        }
+
         // the following also refreshes scores before it picks relays
         let _ = GLOBALS
             .to_overlord
             .send(ToOverlordMessage::RefreshScoresAndPickRelays);
-    } else if event.kind == EventKind::Repost {
-        // If the content is a repost, seek the event it reposts
+    } else if event.kind == EventKind::Repost || event.kind == EventKind::GenericRepost {
+        // If the content is a repost, seek the event it reposts. Where the
+        // repost gives no relay, the relay we got it from likely has it too.
         for eref in event.mentions().iter() {
             match eref {
                 EventReference::Id(id, optrelay, _marker) => {
-                    if let Some(rurl) = optrelay {
+                    if GLOBALS.storage.has_event(*id)? {
+                        continue;
+                    }
+                    let relays: Vec<RelayUrl> = match optrelay {
+                        Some(rurl) => vec![rurl.to_owned()],
+                        None => seen_on.iter().cloned().collect(),
+                    };
+                    if !relays.is_empty() {
                         let _ = GLOBALS
                             .to_overlord
-                            .send(ToOverlordMessage::FetchEvent(*id, vec![rurl.to_owned()]));
+                            .send(ToOverlordMessage::FetchEvent(*id, relays));
                     }
                 }
                 EventReference::Addr(ea) => {
+                    let mut ea = ea.clone();
+                    if ea.relays.is_empty() {
+                        if let Some(relay_url) = seen_on.as_ref() {
+                            ea.relays.push(relay_url.to_unchecked_url());
+                        }
+                    }
                     if !ea.relays.is_empty() {
                         let _ = GLOBALS
                             .to_overlord
-                            .send(ToOverlordMessage::FetchEventAddr(ea.clone()));
+                            .send(ToOverlordMessage::FetchEventAddr(ea));
+                    }
+                }
+            }
+        }
+    } else if event.kind == EventKind::ProfileBadges || event.kind == EventKind::BadgeAward {
+        // Seek the badge definitions (and for profile badges, the award events)
+        for tag in &event.tags {
+            if let Ok((mut ea, _marker)) = tag.parse_address() {
+                if ea.kind != EventKind::BadgeDefinition {
+                    continue;
+                }
+                if GLOBALS
+                    .storage
+                    .get_replaceable_event(ea.kind, ea.author, &ea.d)?
+                    .is_none()
+                {
+                    if ea.relays.is_empty() {
+                        if let Some(relay_url) = seen_on.as_ref() {
+                            ea.relays.push(relay_url.to_unchecked_url());
+                        }
+                    }
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FetchEventAddr(ea));
+                }
+            } else if event.kind == EventKind::ProfileBadges {
+                if let Ok((id, optrelay, _marker)) = tag.parse_event() {
+                    if GLOBALS.storage.read_event(id)?.is_none() {
+                        let mut relays: Vec<RelayUrl> = Vec::new();
+                        if let Some(rurl) =
+                            optrelay.and_then(|u| RelayUrl::try_from_unchecked_url(&u).ok())
+                        {
+                            relays.push(rurl);
+                        }
+                        if let Some(relay_url) = seen_on.as_ref() {
+                            relays.push(relay_url.to_owned());
+                        }
+                        if !relays.is_empty() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::FetchEvent(id, relays));
+                        }
+                    }
+                }
+            }
+        }
+    } else if event.kind == EventKind::CommunityPostApproval {
+        // Approvals embed the approved post; otherwise seek it
+        match serde_json::from_str::<Event>(&event.content) {
+            Ok(post) if post.verify(None).is_ok() => {
+                if !GLOBALS.storage.has_event(post.id)? {
+                    process_new_event(&post, seen_on.clone(), None, false, false).await?;
+                }
+            }
+            _ => {
+                for tag in &event.tags {
+                    if let Ok((id, _, _)) = tag.parse_event() {
+                        if GLOBALS.storage.read_event(id)?.is_none() {
+                            if let Some(relay_url) = seen_on.as_ref() {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::FetchEvent(
+                                    id,
+                                    vec![relay_url.to_owned()],
+                                ));
+                            }
+                        }
                     }
                 }
             }
@@ -396,6 +579,9 @@ pub async fn process_new_event(
 }
 
 async fn process_somebody_elses_contact_list(event: &Event) -> Result<(), Error> {
+    // They may have just followed us
+    crate::follow_back::check_contact_list(event)?;
+
     // We don't keep their contacts or show to the user yet.
     // We only process the contents for (non-standard) relay list information.
 
@@ -447,18 +633,20 @@ pub(crate) fn process_relationships_of_event<'a>(
 
     let mut f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
         // replies to
-        match event.replies_to() {
+        let replies_to = event.replies_to();
+        match &replies_to {
             Some(EventReference::Id(id, _, _)) => {
                 GLOBALS.storage.write_relationship_by_id(
-                    id,
+                    *id,
                     event.id,
                     RelationshipById::Reply,
                     Some(txn),
                 )?;
+                invalidate.push(*id);
             }
             Some(EventReference::Addr(ea)) => {
                 GLOBALS.storage.write_relationship_by_addr(
-                    ea,
+                    ea.clone(),
                     event.id,
                     RelationshipByAddr::Reply,
                     Some(txn),
@@ -467,6 +655,59 @@ pub(crate) fn process_relationships_of_event<'a>(
             None => (),
         }
 
+        // reposts (this replaces a reply relationship to the same event)
+        if event.kind == EventKind::Repost || event.kind == EventKind::GenericRepost {
+            for tag in &event.tags {
+                if let Ok((id, _, _)) = tag.parse_event() {
+                    GLOBALS.storage.write_relationship_by_id(
+                        id,
+                        event.id,
+                        RelationshipById::Repost,
+                        Some(txn),
+                    )?;
+                    invalidate.push(id);
+                }
+            }
+        }
+
+        // quotes, by 'q' tag or by reference in the content (but not of what it
+        // replies to)
+        if event.kind.is_feed_displayable()
+            && event.kind != EventKind::Repost
+            && event.kind != EventKind::GenericRepost
+        {
+            let mut quoted: Vec<Id> = Vec::new();
+            for tag in &event.tags {
+                if tag.tagname() == "q" {
+                    if let Ok(id) = Id::try_from_hex_string(tag.value()) {
+                        quoted.push(id);
+                    }
+                }
+            }
+            for bech32 in NostrBech32::find_all_in_string(&event.content) {
+                match bech32 {
+                    NostrBech32::Id(id) => quoted.push(id),
+                    NostrBech32::EventPointer(ep) => quoted.push(ep.id),
+                    _ => (),
+                }
+            }
+            quoted.sort();
+            quoted.dedup();
+            for id in quoted {
+                if matches!(&replies_to, Some(EventReference::Id(reply_id, _, _)) if *reply_id == id)
+                {
+                    continue;
+                }
+                GLOBALS.storage.write_relationship_by_id(
+                    id,
+                    event.id,
+                    RelationshipById::Quote,
+                    Some(txn),
+                )?;
+                invalidate.push(id);
+            }
+        }
+
         // timestamps
         if event.kind == EventKind::Timestamp {
             for tag in &event.tags {
@@ -514,22 +755,40 @@ pub(crate) fn process_relationships_of_event<'a>(
                         )?;
                     }
                     EventReference::Addr(ea) => {
-                        // If we have the event,
-                        // Actually delete at this point in some cases
-                        if let Some(deleted_event) = GLOBALS
-                            .storage
-                            .get_replaceable_event(ea.kind, ea.author, &ea.d)?
+                        // This deletes every version we have up to the time
+                        // of the deletion
+                        let deleted_events = GLOBALS.storage.find_events(
+                            &[ea.kind],
+                            &[ea.author],
+                            None,
+                            |e| {
+                                e.created_at <= event.created_at
+                                    && (!ea.kind.is_parameterized_replaceable()
+                                        || e.parameter().as_deref() == Some(ea.d.as_str()))
+                            },
+                            false,
+                        )?;
+                        if deleted_events
+                            .iter()
+                            .any(|e| !e.delete_author_allowed(event.pubkey))
                         {
-                            if !deleted_event.delete_author_allowed(event.pubkey) {
-                                // No further processing if not a valid delete
-                                continue;
-                            }
+                            // No further processing if not a valid delete
+                            continue;
+                        }
+                        for deleted_event in deleted_events.iter() {
                             invalidate.push(deleted_event.id);
                             if !deleted_event.kind.is_feed_displayable() {
                                 // Otherwise actually delete (PITA to do otherwise)
                                 GLOBALS.storage.delete_event(deleted_event.id, Some(txn))?;
                             }
                         }
+                        if ea.kind.is_parameterized_replaceable() {
+                            GLOBALS.storage.delete_replaceable_event_versions(
+                                ea,
+                                event.created_at,
+                                Some(txn),
+                            )?;
+                        }
 
                         // Store the delete (we either don't have the target to verify,
                         // or we just verified above. In the former case, it is okay because
@@ -565,33 +824,24 @@ pub(crate) fn process_relationships_of_event<'a>(
 
         // labels
         if event.kind == EventKind::Label {
-            // Get the label from the "l" tag
-            let mut label = "";
-            let mut namespace = "";
-            for t in &event.tags {
-                if t.tagname() == "l" {
-                    if t.value() != "" {
-                        label = t.value();
-                        if t.get_index(2) != "" {
-                            namespace = t.get_index(2);
-                        }
+            // Only the first label is recorded in the relationship. All of them
+            // are read back from the label event (see nip32::labels_of_event)
+            if let Some(first) = crate::nip32::labels_in(event).first() {
+                for tag in &event.tags {
+                    if let Ok((id, _, _)) = tag.parse_event() {
+                        GLOBALS.storage.write_relationship_by_id(
+                            id,
+                            event.id,
+                            RelationshipById::Labels {
+                                label: first.value.clone(),
+                                namespace: first.namespace.clone(),
+                            },
+                            Some(txn),
+                        )?;
+                        invalidate.push(id);
                     }
                 }
             }
-
-            for tag in &event.tags {
-                if let Ok((id, _, _)) = tag.parse_event() {
-                    GLOBALS.storage.write_relationship_by_id(
-                        id,
-                        event.id,
-                        RelationshipById::Labels {
-                            label: label.to_owned(),
-                            namespace: namespace.to_owned(),
-                        },
-                        Some(txn),
-                    )?;
-                }
-            }
         }
 
         // ListMutesThread
@@ -730,6 +980,7 @@ pub(crate) fn process_relationships_of_event<'a>(
         }
 
         if event.kind == EventKind::Reporting {
+            crate::nip56::forget_reports_of(event);
             for tag in &event.tags {
                 if let Ok((id, Some(rurl), _)) = tag.parse_event() {
                     let report = &rurl.0;
@@ -772,6 +1023,40 @@ pub(crate) fn process_relationships_of_event<'a>(
                         RelationshipById::JobResult,
                         Some(txn),
                     )?;
+                    invalidate.extend(crate::nip90::inputs_of(id)?);
+                }
+            }
+        }
+
+        // JobFeedback
+        if crate::nip90::is_job_feedback(event.kind) {
+            for tag in &event.tags {
+                if let Ok((id, _, _)) = tag.parse_event() {
+                    GLOBALS.storage.write_relationship_by_id(
+                        id,
+                        event.id,
+                        RelationshipById::JobFeedback,
+                        Some(txn),
+                    )?;
+                    invalidate.extend(crate::nip90::inputs_of(id)?);
+                }
+            }
+        }
+
+        // JobRequest (only ours, so we can show the results on the input note)
+        if crate::nip90::is_job_request(event.kind)
+            && Some(event.pubkey) == GLOBALS.identity.public_key()
+        {
+            for tag in &event.tags {
+                if tag.tagname() == "i" && tag.get_index(2) == "event" {
+                    if let Ok(id) = Id::try_from_hex_string(tag.value()) {
+                        GLOBALS.storage.write_relationship_by_id(
+                            id,
+                            event.id,
+                            RelationshipById::JobRequest,
+                            Some(txn),
+                        )?;
+                    }
                 }
             }
         }
@@ -788,6 +1073,8 @@ pub(crate) fn process_relationships_of_event<'a>(
         }
     };
 
+    GLOBALS.storage.forget_engagement_counts(&invalidate);
+
     Ok(invalidate)
 }
 
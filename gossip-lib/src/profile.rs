@@ -10,8 +10,15 @@ use normpath::PathExt;
 
 lazy_static! {
     static ref CURRENT: RwLock<Option<Profile>> = RwLock::new(None);
+
+    // The profile to start again in once gossip has shut down. The inner None
+    // is the default profile.
+    static ref SWITCH_TO: RwLock<Option<Option<String>>> = RwLock::new(None);
 }
 
+// Directories within the base directory that are not profiles
+const NOT_PROFILES: [&str; 2] = ["cache", "lmdb"];
+
 /// Storage paths
 #[derive(Clone, Debug, PartialEq)]
 pub struct Profile {
@@ -26,32 +33,14 @@ pub struct Profile {
 
     /// The LMDB directory (within the profile directory)
     pub lmdb_dir: PathBuf,
+
+    /// The name of the profile, or None for the default profile
+    pub name: Option<String>,
 }
 
 impl Profile {
     fn new() -> Result<Profile, Error> {
-        // Get system standard directory for user data
-        let data_dir = dirs::data_dir()
-            .ok_or::<Error>("Cannot find a directory to store application data.".into())?;
-
-        // Canonicalize (follow symlinks, resolve ".." paths)
-        let data_dir = normalize(data_dir)?;
-
-        // Push "gossip" to data_dir, or override with GOSSIP_DIR
-        let base_dir = match env::var("GOSSIP_DIR") {
-            Ok(dir) => {
-                tracing::info!("Using GOSSIP_DIR: {}", dir);
-                // Note, this must pre-exist
-                normalize(dir)?
-            }
-            Err(_) => {
-                let mut base_dir = data_dir;
-                base_dir.push("gossip");
-                // We canonicalize here because gossip might be a link, but if it
-                // doesn't exist yet we have to just go with basedir
-                normalize(base_dir.as_path()).unwrap_or(base_dir)
-            }
-        };
+        let base_dir = base_dir()?;
 
         let cache_dir = {
             let mut cache_dir = base_dir.clone();
@@ -60,32 +49,10 @@ impl Profile {
         };
 
         // optional profile name, if specified the the user data is stored in a subdirectory
-        let profile_dir = match env::var("GOSSIP_PROFILE") {
-            Ok(profile) => {
-                if "cache".eq_ignore_ascii_case(profile.as_str()) {
-                    return Err(Error::from("Profile name 'cache' is reserved."));
-                }
-
-                // Check that it doesn't corrupt the expected path
-                let mut dir = base_dir.clone();
-                dir.push(&profile);
-                match dir.file_name() {
-                    Some(filename) => {
-                        if filename != OsStr::new(&profile) {
-                            return Err(Error::from(format!(
-                                "Profile is not a simple filename: {}",
-                                profile
-                            )));
-                        }
-                    }
-                    None => {
-                        return Err(Error::from(format!("Profile is invalid: {}", profile)));
-                    }
-                };
-
-                dir
-            }
-            Err(_) => base_dir.clone(),
+        let name = env::var("GOSSIP_PROFILE").ok();
+        let profile_dir = match &name {
+            Some(profile) => profile_dir(&base_dir, profile)?,
+            None => base_dir.clone(),
         };
 
         let lmdb_dir = {
@@ -111,6 +78,7 @@ impl Profile {
             profile_dir,
             cache_dir,
             lmdb_dir,
+            name,
         })
     }
 
@@ -127,6 +95,101 @@ impl Profile {
         *w = Some(created.clone());
         Ok(created)
     }
+
+    /// The names of the profiles that have storage, not counting the default
+    /// profile, sorted
+    pub fn list() -> Result<Vec<String>, Error> {
+        let base_dir = Profile::current()?.base_dir;
+        let mut output: Vec<String> = Vec::new();
+        for entry in fs::read_dir(base_dir)? {
+            let entry = entry?;
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if NOT_PROFILES.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            if entry.path().join("lmdb").is_dir() {
+                output.push(name);
+            }
+        }
+        output.sort();
+        Ok(output)
+    }
+
+    /// Ask for gossip to start again in another profile (None for the default
+    /// profile) once it has shut down. A new profile gets its own storage,
+    /// settings and keys when it first starts. Storage stays open for as long
+    /// as the process runs, so the caller still needs to shut gossip down.
+    pub fn switch_to(name: Option<&str>) -> Result<(), Error> {
+        if let Some(name) = name {
+            let _ = profile_dir(&Profile::current()?.base_dir, name)?;
+        }
+        *SWITCH_TO.write().unwrap() = Some(name.map(|n| n.to_owned()));
+        Ok(())
+    }
+
+    /// The profile asked for with [switch_to](Profile::switch_to), if any
+    pub fn switch_requested() -> Option<Option<String>> {
+        SWITCH_TO.read().unwrap().clone()
+    }
+}
+
+fn base_dir() -> Result<PathBuf, Error> {
+    // Get system standard directory for user data
+    let data_dir = dirs::data_dir()
+        .ok_or::<Error>("Cannot find a directory to store application data.".into())?;
+
+    // Canonicalize (follow symlinks, resolve ".." paths)
+    let data_dir = normalize(data_dir)?;
+
+    // Push "gossip" to data_dir, or override with GOSSIP_DIR
+    let base_dir = match env::var("GOSSIP_DIR") {
+        Ok(dir) => {
+            tracing::info!("Using GOSSIP_DIR: {}", dir);
+            // Note, this must pre-exist
+            normalize(dir)?
+        }
+        Err(_) => {
+            let mut base_dir = data_dir;
+            base_dir.push("gossip");
+            // We canonicalize here because gossip might be a link, but if it
+            // doesn't exist yet we have to just go with basedir
+            normalize(base_dir.as_path()).unwrap_or(base_dir)
+        }
+    };
+
+    Ok(base_dir)
+}
+
+// The directory of a named profile, checking that the name is usable
+fn profile_dir(base_dir: &Path, profile: &str) -> Result<PathBuf, Error> {
+    if NOT_PROFILES.iter().any(|n| n.eq_ignore_ascii_case(profile)) {
+        return Err(Error::from(format!(
+            "Profile name '{}' is reserved.",
+            profile
+        )));
+    }
+
+    // Check that it doesn't corrupt the expected path
+    let mut dir = base_dir.to_path_buf();
+    dir.push(profile);
+    match dir.file_name() {
+        Some(filename) => {
+            if filename != OsStr::new(profile) {
+                return Err(Error::from(format!(
+                    "Profile is not a simple filename: {}",
+                    profile
+                )));
+            }
+        }
+        None => {
+            return Err(Error::from(format!("Profile is invalid: {}", profile)));
+        }
+    };
+
+    Ok(dir)
 }
 
 #[cfg(not(windows))]
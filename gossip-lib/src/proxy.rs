@@ -0,0 +1,197 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use reqwest::ClientBuilder;
+use std::net::{IpAddr, SocketAddr};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::client::Response;
+use tungstenite::protocol::WebSocketConfig;
+
+/// The SOCKS5 proxy from the settings, as host and port, if it is in use.
+/// With the proxy turned on but no host set this is an error, so that we
+/// never quietly connect directly.
+fn proxy() -> Result<Option<(String, u16)>, Error> {
+    if !GLOBALS.storage.read_setting_use_proxy() {
+        return Ok(None);
+    }
+    let host = GLOBALS.storage.read_setting_proxy_host();
+    let host = host.trim();
+    if host.is_empty() {
+        return Err("The proxy is turned on, but no proxy host is set".into());
+    }
+    Ok(Some((
+        host.to_owned(),
+        GLOBALS.storage.read_setting_proxy_port(),
+    )))
+}
+
+fn is_onion(host: &str) -> bool {
+    host.trim_end_matches('.').ends_with(".onion")
+}
+
+/// Whether a relay is reached through the proxy. Relays listed in the
+/// `proxy_direct_relays` setting are not, except for .onion relays, which
+/// can only be reached through it.
+fn relay_uses_proxy(url: &str, host: &str) -> bool {
+    if is_onion(host) {
+        return true;
+    }
+    let url = url.trim_end_matches('/');
+    !GLOBALS
+        .storage
+        .read_setting_proxy_direct_relays()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .any(|direct| !direct.is_empty() && direct.trim_end_matches('/') == url)
+}
+
+/// A reqwest client builder that goes through the proxy if one is set.
+/// The proxy resolves names, so DNS does not leak and .onion hosts work.
+pub(crate) fn client_builder() -> Result<ClientBuilder, Error> {
+    let builder = reqwest::Client::builder();
+    match proxy()? {
+        Some((host, port)) => {
+            let proxy = reqwest::Proxy::all(format!("socks5h://{}:{}", host, port))?;
+            Ok(builder.proxy(proxy))
+        }
+        None => Ok(builder),
+    }
+}
+
+/// A reqwest client builder for talking HTTP to a relay (such as for its
+/// NIP-11 document), which honors `proxy_direct_relays`
+pub(crate) fn client_builder_for_relay(url: &str) -> Result<ClientBuilder, Error> {
+    let host = url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_owned()))
+        .unwrap_or_default();
+    if relay_uses_proxy(url, &host) {
+        client_builder()
+    } else {
+        Ok(reqwest::Client::builder())
+    }
+}
+
+/// Open a websocket to a relay, through the proxy if one is set and the
+/// relay is not to be reached directly
+pub(crate) async fn connect_websocket<R>(
+    request: R,
+    config: Option<WebSocketConfig>,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response), Error>
+where
+    R: IntoClientRequest + Unpin,
+{
+    let request = request.into_client_request()?;
+    let uri = request.uri().clone();
+    let host = uri.host().unwrap_or_default().to_owned();
+
+    let proxy = match proxy()? {
+        Some(proxy) if relay_uses_proxy(&uri.to_string(), &host) => proxy,
+        _ => {
+            if is_onion(&host) {
+                return Err(format!("{} can only be reached through a proxy", uri).into());
+            }
+            return Ok(tokio_tungstenite::connect_async_with_config(request, config, false).await?);
+        }
+    };
+
+    let port = match (uri.port_u16(), uri.scheme_str()) {
+        (Some(port), _) => port,
+        (None, Some("ws")) => 80,
+        (None, _) => 443,
+    };
+    let stream = socks5_connect(&proxy, &host, port).await?;
+    Ok(tokio_tungstenite::client_async_tls_with_config(request, stream, config, None).await?)
+}
+
+// Ask the SOCKS5 proxy for a connection to host:port. A host name is passed
+// as a name so that the proxy does the lookup.
+async fn socks5_connect(proxy: &(String, u16), host: &str, port: u16) -> Result<TcpStream, Error> {
+    let proxy = (proxy.0.as_str(), proxy.1);
+    // IPv6 addresses come in brackets in URLs
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let stream = match host.parse::<IpAddr>() {
+        Ok(ip) => Socks5Stream::connect(proxy, SocketAddr::new(ip, port)).await?,
+        Err(_) => Socks5Stream::connect(proxy, (host, port)).await?,
+    };
+    Ok(stream.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Play a SOCKS5 proxy for one connection, answering with the given reply
+    // code, and hand back the address type and address it was asked for
+    async fn fake_proxy(reply: u8) -> ((String, u16), tokio::task::JoinHandle<(u8, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            // Greeting: version, and the methods offered
+            let mut greeting = [0u8; 2];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting[0], 5);
+            let mut methods = vec![0u8; greeting[1] as usize];
+            stream.read_exact(&mut methods).await.unwrap();
+            assert!(methods.contains(&0));
+            stream.write_all(&[5, 0]).await.unwrap();
+
+            // Request: version, CONNECT, reserved, address type
+            let mut request = [0u8; 4];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..3], &[5, 1, 0]);
+            let len = match request[3] {
+                1 => 4,
+                4 => 16,
+                3 => stream.read_u8().await.unwrap() as usize,
+                other => panic!("bad address type {other}"),
+            };
+            let mut address = vec![0u8; len + 2];
+            stream.read_exact(&mut address).await.unwrap();
+
+            stream
+                .write_all(&[5, reply, 0, 1, 127, 0, 0, 1, 0, 80])
+                .await
+                .unwrap();
+            (request[3], address)
+        });
+        (("127.0.0.1".to_owned(), port), handle)
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_by_name() {
+        let (proxy, handle) = fake_proxy(0).await;
+        socks5_connect(&proxy, "relay.example.onion", 443)
+            .await
+            .unwrap();
+        let (address_type, address) = handle.await.unwrap();
+        assert_eq!(address_type, 3);
+        assert_eq!(&address[..address.len() - 2], b"relay.example.onion");
+        assert_eq!(&address[address.len() - 2..], &443u16.to_be_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_ipv6() {
+        let (proxy, handle) = fake_proxy(0).await;
+        socks5_connect(&proxy, "[::1]", 80).await.unwrap();
+        let (address_type, address) = handle.await.unwrap();
+        assert_eq!(address_type, 4);
+        assert_eq!(address[15], 1);
+        assert_eq!(&address[16..], &80u16.to_be_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_refused() {
+        // 5 is "connection refused"
+        let (proxy, handle) = fake_proxy(5).await;
+        assert!(socks5_connect(&proxy, "relay.example.com", 443)
+            .await
+            .is_err());
+        handle.await.unwrap();
+    }
+}
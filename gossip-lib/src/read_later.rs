@@ -0,0 +1,164 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::json_key;
+use linkify::{LinkFinder, LinkKind};
+use nostr_types::{Event, Id, UncheckedUrl, Unixtime, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// The queue is private. It is kept in local storage only, and unlike bookmarks
+// it is never published.
+
+const MEDIA_EXTENSIONS: [&str; 9] = [
+    ".jpg", ".jpeg", ".png", ".gif", ".webp", ".svg", ".mp4", ".mov", ".webm",
+];
+
+/// A note or article queued to read later
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReadLaterItem {
+    pub id: Id,
+    pub added_at: Unixtime,
+    pub done: bool,
+}
+
+fn load() -> Vec<ReadLaterItem> {
+    match GLOBALS.storage.read_json(json_key::READ_LATER) {
+        Ok(items) => items.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            vec![]
+        }
+    }
+}
+
+fn save(items: &[ReadLaterItem]) -> Result<(), Error> {
+    GLOBALS
+        .storage
+        .write_json(json_key::READ_LATER, items, None)
+}
+
+/// The queue, in the user's order
+pub fn items() -> Vec<ReadLaterItem> {
+    load()
+}
+
+/// Whether an event is in the queue
+pub fn contains(id: Id) -> bool {
+    load().iter().any(|i| i.id == id)
+}
+
+/// Add an event to the end of the queue. If the user wants, the media it links
+/// to is fetched now so that it can be read offline.
+pub fn add(id: Id) -> Result<(), Error> {
+    let mut items = load();
+    if items.iter().any(|i| i.id == id) {
+        return Ok(());
+    }
+    items.push(ReadLaterItem {
+        id,
+        added_at: Unixtime::now().unwrap(),
+        done: false,
+    });
+    save(&items)?;
+
+    if GLOBALS.storage.read_setting_read_later_prefetch_media() {
+        if let Some(event) = GLOBALS.storage.read_event(id)? {
+            prefetch_media(&event);
+        }
+    }
+
+    Ok(())
+}
+
+/// Take an event out of the queue
+pub fn remove(id: Id) -> Result<(), Error> {
+    let mut items = load();
+    items.retain(|i| i.id != id);
+    save(&items)
+}
+
+/// Mark an event in the queue as read, or as not read yet
+pub fn set_done(id: Id, done: bool) -> Result<(), Error> {
+    let mut items = load();
+    for item in items.iter_mut() {
+        if item.id == id {
+            item.done = done;
+        }
+    }
+    save(&items)
+}
+
+/// Move an event one place up (towards the front) or down the queue
+pub fn move_item(id: Id, up: bool) -> Result<(), Error> {
+    let mut items = load();
+    if let Some(index) = items.iter().position(|i| i.id == id) {
+        if up && index > 0 {
+            items.swap(index, index - 1);
+        } else if !up && index + 1 < items.len() {
+            items.swap(index, index + 1);
+        }
+    }
+    save(&items)
+}
+
+/// Take everything marked done out of the queue
+pub fn clear_done() -> Result<(), Error> {
+    let mut items = load();
+    items.retain(|i| !i.done);
+    save(&items)
+}
+
+/// The event queued under this id, or its latest version if it is replaceable
+/// (as long-form articles are) and has been edited since
+pub fn latest_version(id: Id) -> Result<Option<Event>, Error> {
+    let event = match GLOBALS.storage.read_event(id)? {
+        Some(event) => event,
+        None => return Ok(None),
+    };
+
+    if event.kind.is_replaceable() {
+        let parameter = event.parameter().unwrap_or_default();
+        if let Some(latest) =
+            GLOBALS
+                .storage
+                .get_replaceable_event(event.kind, event.pubkey, &parameter)?
+        {
+            return Ok(Some(latest));
+        }
+    }
+
+    Ok(Some(event))
+}
+
+// Ask the fetcher for the images and videos this event links to. They end up
+// in the file cache, where the UI finds them later.
+fn prefetch_media(event: &Event) {
+    let mut urls: Vec<String> = LinkFinder::new()
+        .kinds(&[LinkKind::Url])
+        .links(&event.content)
+        .map(|l| l.as_str().to_owned())
+        .filter(|u| is_media(u))
+        .collect();
+
+    // Article header images
+    for tag in &event.tags {
+        if tag.tagname() == "image" && !tag.value().is_empty() {
+            urls.push(tag.value().to_owned());
+        }
+    }
+
+    let max_age =
+        Duration::from_secs(60 * 60 * GLOBALS.storage.read_setting_media_becomes_stale_hours());
+    for url in urls {
+        if let Ok(url) = Url::try_from_unchecked_url(&UncheckedUrl(url)) {
+            if let Err(e) = GLOBALS.fetcher.try_get(&url, max_age) {
+                tracing::warn!("{}", e);
+            }
+        }
+    }
+}
+
+fn is_media(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or("").to_lowercase();
+    MEDIA_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
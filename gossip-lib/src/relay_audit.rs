@@ -0,0 +1,157 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use nostr_types::{EventKind, RelayUrl, Unixtime};
+use std::collections::{BTreeMap, HashSet};
+
+const DAY: i64 = 60 * 60 * 24;
+
+/// How many events of each kind we received from a relay, by day
+#[derive(Clone, Debug)]
+pub struct RelayAudit {
+    /// When the audit was made
+    pub made_at: Unixtime,
+
+    /// How far back the audit looked
+    pub since: Unixtime,
+
+    /// Each UTC day (by the unixtime it starts at), newest first, with the count
+    /// of each kind received that day, most received first
+    pub days: Vec<(Unixtime, Vec<(EventKind, usize)>)>,
+
+    /// The count of each kind over the whole audit, most received first
+    pub totals: Vec<(EventKind, usize)>,
+
+    /// Direct messages to or from us
+    pub dms: usize,
+
+    /// Events by people we follow
+    pub from_followed: usize,
+
+    /// Events by anybody else
+    pub from_others: usize,
+
+    /// Events we saw on the relay but no longer have
+    pub missing: usize,
+}
+
+impl RelayAudit {
+    pub fn total(&self) -> usize {
+        self.from_followed + self.from_others
+    }
+}
+
+/// The state of auditing a relay
+#[derive(Clone, Debug)]
+pub enum AuditStatus {
+    Running,
+    Done(RelayAudit),
+    Failed(String),
+}
+
+lazy_static! {
+    static ref AUDITS: DashMap<RelayUrl, AuditStatus> = DashMap::new();
+}
+
+/// The latest audit of a relay, if one was started
+pub fn status(url: &RelayUrl) -> Option<AuditStatus> {
+    AUDITS.get(url).map(|s| s.clone())
+}
+
+/// Audit the events received from a relay over the past `days` days, in the
+/// background. This has to look at every event we have seen anywhere, so it
+/// takes a while.
+pub fn start(url: RelayUrl, days: u64) {
+    if matches!(status(&url), Some(AuditStatus::Running)) {
+        return;
+    }
+
+    AUDITS.insert(url.clone(), AuditStatus::Running);
+    tokio::task::spawn_blocking(move || {
+        let status = match audit(&url, days) {
+            Ok(audit) => AuditStatus::Done(audit),
+            Err(e) => AuditStatus::Failed(format!("{}", e)),
+        };
+        AUDITS.insert(url, status);
+    });
+}
+
+/// Audit the events received from a relay over the past `days` days
+pub fn audit(url: &RelayUrl, days: u64) -> Result<RelayAudit, Error> {
+    let now = Unixtime::now().unwrap();
+    let since = Unixtime(now.0 - days as i64 * DAY);
+    let seen = GLOBALS.storage.get_events_seen_on_relay(url, since)?;
+
+    let followed: HashSet<_> = GLOBALS
+        .people
+        .get_subscribed_pubkeys()
+        .into_iter()
+        .collect();
+    let me = GLOBALS.identity.public_key();
+
+    let mut audit = RelayAudit {
+        made_at: now,
+        since,
+        days: vec![],
+        totals: vec![],
+        dms: 0,
+        from_followed: 0,
+        from_others: 0,
+        missing: 0,
+    };
+    let mut by_day: BTreeMap<i64, BTreeMap<u32, usize>> = BTreeMap::new();
+    let mut totals: BTreeMap<u32, usize> = BTreeMap::new();
+
+    for (id, when) in seen {
+        let event = match GLOBALS.storage.read_event(id)? {
+            Some(event) => event,
+            None => {
+                audit.missing += 1;
+                continue;
+            }
+        };
+
+        let day = when.0 - when.0.rem_euclid(DAY);
+        *by_day
+            .entry(day)
+            .or_default()
+            .entry(u32::from(event.kind))
+            .or_default() += 1;
+        *totals.entry(u32::from(event.kind)).or_default() += 1;
+
+        if followed.contains(&event.pubkey) {
+            audit.from_followed += 1;
+        } else {
+            audit.from_others += 1;
+        }
+
+        if let Some(me) = me {
+            if matches!(
+                event.kind,
+                EventKind::EncryptedDirectMessage | EventKind::GiftWrap
+            ) && (event.pubkey == me || event.is_tagged(&me))
+            {
+                audit.dms += 1;
+            }
+        }
+    }
+
+    audit.days = by_day
+        .into_iter()
+        .rev()
+        .map(|(day, kinds)| (Unixtime(day), most_first(kinds)))
+        .collect();
+    audit.totals = most_first(totals);
+
+    Ok(audit)
+}
+
+fn most_first(counts: BTreeMap<u32, usize>) -> Vec<(EventKind, usize)> {
+    let mut counts: Vec<(EventKind, usize)> = counts
+        .into_iter()
+        .map(|(kind, count)| (EventKind::from(kind), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    counts
+}
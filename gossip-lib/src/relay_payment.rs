@@ -0,0 +1,275 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use crate::USER_AGENT;
+use lightning_invoice::{Invoice, InvoiceDescription};
+use nostr_types::{Event, RelayUrl};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Where we are with paying a relay that wants to be paid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayPayment {
+    /// It turned us away for not having paid
+    Required,
+
+    /// Asking it for an invoice
+    Fetching,
+
+    /// The invoice, and its amount in millisatoshis if it says
+    ReadyToPay {
+        invoice: String,
+        msats: Option<u64>,
+    },
+
+    /// Paying the invoice through the connected wallet
+    Paying,
+
+    /// It gave no invoice, so it has to be paid on this web page
+    PayAtWebsite(String),
+
+    Failed(String),
+}
+
+// Words that, in a "restricted:" or "blocked:" message, mean the relay wants
+// to be paid
+const PAYMENT_WORDS: [&str; 6] = [
+    "pay",
+    "paid",
+    "payment",
+    "admission",
+    "subscribe",
+    "subscription",
+];
+
+/// Whether a relay's OK or CLOSED message means it wants to be paid. Besides
+/// "payment-required:", relays use "restricted:" or "blocked:" for this, with
+/// some word about paying.
+pub(crate) fn is_payment_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    if message.starts_with("payment-required:") {
+        return true;
+    }
+    if !message.starts_with("restricted:") && !message.starts_with("blocked:") {
+        return false;
+    }
+    message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| PAYMENT_WORDS.contains(&word))
+}
+
+/// Note that a relay turned us away until paid, keeping the event it would
+/// not take, if any
+pub(crate) fn payment_required(url: &RelayUrl, event: Option<Event>) {
+    if let Some(event) = event {
+        GLOBALS
+            .relay_held_events
+            .entry(url.clone())
+            .or_default()
+            .push(event);
+    }
+    if GLOBALS.relay_payments.contains_key(url) {
+        return;
+    }
+    GLOBALS
+        .relay_payments
+        .insert(url.clone(), RelayPayment::Required);
+    GLOBALS.status_queue.write().write_warning(format!(
+        "{} wants to be paid. You can pay it from the relay pages.",
+        url
+    ));
+}
+
+/// Whether a relay wants to be paid, because it turned us away or because its
+/// NIP-11 document says so
+pub fn wants_payment(relay: &Relay) -> bool {
+    GLOBALS.relay_payments.contains_key(&relay.url)
+        || relay
+            .nip11
+            .as_ref()
+            .and_then(|doc| doc.limitation.as_ref())
+            .and_then(|l| l.payment_required)
+            .unwrap_or(false)
+}
+
+/// Relays we are paying or were asked to pay, and where that stands
+pub fn payments() -> Vec<(RelayUrl, RelayPayment)> {
+    let mut output: Vec<(RelayUrl, RelayPayment)> = GLOBALS
+        .relay_payments
+        .iter()
+        .map(|r| (r.key().clone(), r.value().clone()))
+        .collect();
+    output.sort_by(|a, b| a.0.cmp(&b.0));
+    output
+}
+
+/// Stop asking to pay a relay, and drop the events it would not take
+pub fn dismiss(url: &RelayUrl) {
+    GLOBALS.relay_payments.remove(url);
+    GLOBALS.relay_held_events.remove(url);
+}
+
+pub(crate) fn take_held_events(url: &RelayUrl) -> Vec<Event> {
+    GLOBALS
+        .relay_held_events
+        .remove(url)
+        .map(|(_, v)| v)
+        .unwrap_or_default()
+}
+
+/// Look for a lightning invoice for our public key where the relay's NIP-11
+/// document says to pay it (its `payments_url`)
+pub(crate) async fn fetch_invoice(url: &RelayUrl) -> Result<RelayPayment, Error> {
+    let payments_url = match GLOBALS
+        .storage
+        .read_relay(url)?
+        .and_then(|relay| relay.nip11)
+        .and_then(|doc| doc.payments_url)
+    {
+        Some(u) if !u.trim().is_empty() => u.trim().to_owned(),
+        _ => return Err(format!("{} does not say where to pay it", url).into()),
+    };
+
+    let client = crate::proxy::client_builder_for_relay(url.as_str())?
+        .timeout(Duration::from_secs(
+            GLOBALS.storage.read_setting_fetcher_timeout_sec(),
+        ))
+        .build()?;
+    let user_agent = GLOBALS.storage.read_setting_set_user_agent();
+
+    let mut request_url = url::Url::parse(&payments_url)?;
+    if let Some(pubkey) = GLOBALS.identity.public_key() {
+        request_url
+            .query_pairs_mut()
+            .append_pair("pubkey", &pubkey.as_hex_string());
+    }
+    let mut req = client.get(request_url).header("Accept", "application/json");
+    if user_agent {
+        req = req.header("User-Agent", USER_AGENT);
+    }
+    let text = req.send().await?.text().await?;
+
+    Ok(match find_invoice(&text) {
+        Some(invoice) => RelayPayment::ReadyToPay {
+            msats: invoice_msats(&invoice),
+            invoice,
+        },
+        None => RelayPayment::PayAtWebsite(payments_url),
+    })
+}
+
+// The bolt11 invoice in a JSON reply, under one of the usual keys. Web pages
+// are not searched for one, as the first invoice on a page need not be ours
+// (it may be an example, or for donations), so those are paid on the page.
+fn find_invoice(text: &str) -> Option<String> {
+    let map = match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(map)) => map,
+        _ => return None,
+    };
+    ["pr", "invoice", "payment_request", "bolt11"]
+        .iter()
+        .filter_map(|key| map.get(*key).and_then(|v| v.as_str()))
+        .map(|s| s.trim().to_lowercase())
+        .find(|s| s.parse::<Invoice>().is_ok())
+}
+
+/// The amount of a bolt11 invoice in millisatoshis, if it is a good invoice
+/// and asks for an amount
+pub fn invoice_msats(invoice: &str) -> Option<u64> {
+    invoice.parse::<Invoice>().ok()?.amount_milli_satoshis()
+}
+
+/// Check that the invoice an LNURL server gave us for a zap is the one we asked
+/// for (NIP-57): it must be for exactly `msats`, and commit to our zap request
+/// by the hash of its description. Otherwise it must not be paid unseen.
+pub fn check_zap_invoice(invoice: &str, msats: u64, zap_request: &str) -> Result<(), Error> {
+    match invoice_msats(invoice) {
+        Some(amount) if amount == msats => {}
+        Some(amount) => {
+            return Err(format!(
+                "The zap invoice asks for {} sats instead of {}",
+                amount / 1000,
+                msats / 1000
+            )
+            .into())
+        }
+        None => return Err("The zap invoice does not ask for an amount".into()),
+    }
+
+    let invoice = invoice.parse::<Invoice>().map_err(|e| format!("{}", e))?;
+    match invoice.description() {
+        InvoiceDescription::Hash(hash)
+            if hash.0.as_ref() == &Sha256::digest(zap_request.as_bytes())[..] =>
+        {
+            Ok(())
+        }
+        _ => Err("The zap invoice is not for our zap request".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the BOLT 11 test vectors
+    const INVOICE_2500U: &str = "lnbc2500u1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu9qrsgquk0rl77nj30yxdy8j9vdx85fkpmdla2087ne0xh8nhedh8w27kyke0lp53ut353s06fv3qfegext0eh0ymjpf39tuven09sam30g4vgpfna3rh";
+    const INVOICE_NO_AMOUNT: &str = "lnbc1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq9qrsgq357wnc5r2ueh7ck6q93dj32dlqnls087fxdwk8qakdyafkq3yap9us6v52vjjsrvywa6rt52cm9r9zqt8r2t7mlcwspyetp5h2tztugp9lfyql";
+    const INVOICE_PICO: &str = "lnbc9678785340p1pwmna7lpp5gc3xfm08u9qy06djf8dfflhugl6p7lgza6dsjxq454gxhj9t7a0sd8dgfkx7cmtwd68yetpd5s9xar0wfjn5gpc8qhrsdfq24f5ggrxdaezqsnvda3kkum5wfjkzmfqf3jkgem9wgsyuctwdus9xgrcyqcjcgpzgfskx6eqf9hzqnteypzxz7fzypfhg6trddjhygrcyqezcgpzfysywmm5ypxxjemgw3hxjmn8yptk7untd9hxwg3q2d6xjcmtv4ezq7pqxgsxzmnyyqcjqmt0wfjjq6t5v4khxsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygsxqyjw5qcqp2rzjq0gxwkzc8w6323m55m4jyxcjwmy7stt9hwkwe2qxmy8zpsgg7jcuwz87fcqqeuqqqyqqqqlgqqqqn3qq9q9qrsgqrvgkpnmps664wgkp43l22qsgdw4ve24aca4nymnxddlnp8vh9v2sdxlu5ywdxefsfvm0fq3sesf08uf6q9a2ke0hc9j6z6wlxg5z5kqpu2v9wz";
+    const INVOICE_TESTNET: &str = "lntb20m1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygshp58yjmdan79s6qqdhdzgynm4zwqd5d7xmw5fk98klysy043l2ahrqspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqfpp3x9et2e20v6pu37c5d9vax37wxq72un989qrsgqdj545axuxtnfemtpwkc45hx9d2ft7x04mt8q7y6t0k2dge9e7h8kpy9p34ytyslj3yu569aalz2xdk8xkd7ltxqld94u8h2esmsmacgpghe9k8";
+
+    #[test]
+    fn test_invoice_msats() {
+        assert_eq!(invoice_msats(INVOICE_2500U), Some(250_000_000));
+        assert_eq!(invoice_msats(INVOICE_PICO), Some(967_878_534));
+        assert_eq!(invoice_msats(INVOICE_TESTNET), Some(2_000_000_000));
+        assert_eq!(invoice_msats(INVOICE_NO_AMOUNT), None);
+        assert_eq!(invoice_msats("lnbc2500u1garbage"), None);
+    }
+
+    #[test]
+    fn test_check_zap_invoice() {
+        // The description hash of the testnet invoice
+        const CAKE: &str = "One piece of chocolate cake, one icecream cone, one pickle, one slice of swiss cheese, one slice of salami, one lollypop, one piece of cherry pie, one sausage, one cupcake, and one slice of watermelon";
+
+        assert!(check_zap_invoice(INVOICE_TESTNET, 2_000_000_000, CAKE).is_ok());
+
+        // another amount
+        assert!(check_zap_invoice(INVOICE_TESTNET, 1_000_000_000, CAKE).is_err());
+
+        // another zap request
+        assert!(check_zap_invoice(INVOICE_TESTNET, 2_000_000_000, "{}").is_err());
+
+        // no amount, a plain description, or no invoice at all
+        assert!(check_zap_invoice(INVOICE_NO_AMOUNT, 0, CAKE).is_err());
+        assert!(check_zap_invoice(INVOICE_2500U, 250_000_000, "1 cup coffee").is_err());
+        assert!(check_zap_invoice("lnbc2500u1garbage", 250_000_000, CAKE).is_err());
+    }
+
+    #[test]
+    fn test_find_invoice() {
+        let reply = format!(r#"{{"pr":"{}","routes":[]}}"#, INVOICE_2500U);
+        assert_eq!(find_invoice(&reply), Some(INVOICE_2500U.to_owned()));
+
+        let reply = format!(r#"{{"invoice":"{}"}}"#, INVOICE_TESTNET.to_uppercase());
+        assert_eq!(find_invoice(&reply), Some(INVOICE_TESTNET.to_owned()));
+
+        // Something that is not an invoice is passed over
+        let reply = format!(r#"{{"pr":"lnbc1nope","bolt11":"{}"}}"#, INVOICE_PICO);
+        assert_eq!(find_invoice(&reply), Some(INVOICE_PICO.to_owned()));
+
+        // Web pages are not searched
+        let page = format!("<html><p>Donate: {}</p></html>", INVOICE_2500U);
+        assert_eq!(find_invoice(&page), None);
+    }
+
+    #[test]
+    fn test_is_payment_message() {
+        assert!(is_payment_message("payment-required: pay at https://x"));
+        assert!(is_payment_message("restricted: you must pay to post"));
+        assert!(is_payment_message("blocked: admission fee not paid"));
+        assert!(!is_payment_message("blocked: payload too large"));
+        assert!(!is_payment_message("restricted: not on the whitelist"));
+        assert!(!is_payment_message("error: please pay"));
+    }
+}
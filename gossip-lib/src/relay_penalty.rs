@@ -0,0 +1,102 @@
+use crate::globals::GLOBALS;
+use nostr_types::RelayUrl;
+use std::time::{Duration, Instant};
+
+// Cool-down after the first strike, doubling with each strike after it
+const BASE_COOLDOWN_SECS: u64 = 30;
+const MAX_COOLDOWN_SECS: u64 = 60 * 60 * 6;
+
+// Spacing of the messages we send to a relay that has rate-limited us
+const MIN_SEND_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_SEND_INTERVAL: Duration = Duration::from_secs(10);
+
+// How long a relay has to go without rate-limiting us before we speed up again
+const RELAX_AFTER: Duration = Duration::from_secs(60);
+
+/// How a relay is being held back, after it rate-limited us or dropped the
+/// connection
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Penalty {
+    /// How many times in a row it did so
+    pub strikes: u32,
+
+    /// The least time between the messages we send it. Zero if it never
+    /// rate-limited us.
+    pub send_interval: Duration,
+
+    last_rate_limited: Option<Instant>,
+}
+
+/// Count a strike against a relay that dropped our connection, and return how
+/// many seconds to stay away from it
+pub(crate) fn penalize(url: &RelayUrl) -> u64 {
+    let mut penalty = GLOBALS.relay_penalties.entry(url.clone()).or_default();
+    penalty.strikes += 1;
+    cooldown_secs(penalty.strikes)
+}
+
+/// Note that a relay said "rate-limited", so we send to it less often and stay
+/// away longer if it drops us
+pub(crate) fn rate_limited(url: &RelayUrl) {
+    let mut penalty = GLOBALS.relay_penalties.entry(url.clone()).or_default();
+    penalty.strikes += 1;
+    penalty.send_interval = (penalty.send_interval * 2).clamp(MIN_SEND_INTERVAL, MAX_SEND_INTERVAL);
+    penalty.last_rate_limited = Some(Instant::now());
+    tracing::info!(
+        "{}: rate-limited us, sending no more than every {}ms",
+        url,
+        penalty.send_interval.as_millis()
+    );
+}
+
+/// The least time to leave between the messages we send to a relay. This
+/// halves for every minute that it has not rate-limited us.
+pub(crate) fn send_interval(url: &RelayUrl) -> Duration {
+    let mut penalty = match GLOBALS.relay_penalties.get_mut(url) {
+        Some(penalty) => penalty,
+        None => return Duration::ZERO,
+    };
+    if let Some(last) = penalty.last_rate_limited {
+        if !penalty.send_interval.is_zero() && last.elapsed() > RELAX_AFTER {
+            penalty.send_interval /= 2;
+            if penalty.send_interval < MIN_SEND_INTERVAL {
+                penalty.send_interval = Duration::ZERO;
+            }
+            penalty.last_rate_limited = Some(Instant::now());
+        }
+    }
+    penalty.send_interval
+}
+
+/// Clear the strikes of a relay that served us without trouble
+pub(crate) fn forgive(url: &RelayUrl) {
+    if let Some(mut penalty) = GLOBALS.relay_penalties.get_mut(url) {
+        penalty.strikes = 0;
+    }
+}
+
+/// The penalty a relay is under, if any
+pub fn penalty(url: &RelayUrl) -> Option<Penalty> {
+    GLOBALS.relay_penalties.get(url).map(|p| *p)
+}
+
+fn cooldown_secs(strikes: u32) -> u64 {
+    let doublings = strikes.saturating_sub(1).min(16);
+    (BASE_COOLDOWN_SECS << doublings).min(MAX_COOLDOWN_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_secs() {
+        assert_eq!(cooldown_secs(0), BASE_COOLDOWN_SECS);
+        assert_eq!(cooldown_secs(1), BASE_COOLDOWN_SECS);
+        assert_eq!(cooldown_secs(2), BASE_COOLDOWN_SECS * 2);
+        assert_eq!(cooldown_secs(5), BASE_COOLDOWN_SECS * 16);
+        assert_eq!(cooldown_secs(10), BASE_COOLDOWN_SECS * 512);
+        assert_eq!(cooldown_secs(11), MAX_COOLDOWN_SECS);
+        assert_eq!(cooldown_secs(u32::MAX), MAX_COOLDOWN_SECS);
+    }
+}
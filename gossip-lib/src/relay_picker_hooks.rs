@@ -26,6 +26,12 @@ impl RelayPickerHooks for Hooks {
         pubkey: PublicKey,
         direction: Direction,
     ) -> Result<Vec<(RelayUrl, u64)>, Error> {
+        // People followed only on specific relays are read from just those
+        if matches!(direction, Direction::Write) {
+            if let Some(relays) = crate::relay_scope::scope(&pubkey) {
+                return Ok(relays.into_iter().map(|url| (url, 20)).collect());
+            }
+        }
         GLOBALS.storage.get_best_relays(pubkey, direction)
     }
 
@@ -58,7 +64,7 @@ impl RelayPickerHooks for Hooks {
             Err(_) => 0,
             Ok(Some(relay)) => {
                 let success_rate = relay.success_rate();
-                let rank = (relay.rank as f32 * (1.3 * success_rate)) as u64;
+                let rank = (relay.rank as f32 * (1.3 * success_rate) * relay.speed_factor()) as u64;
                 score * rank
             }
             Ok(None) => score,
@@ -0,0 +1,98 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::json_key;
+use lazy_static::lazy_static;
+use nostr_types::{PublicKey, RelayUrl};
+use parking_lot::RwLock as PRwLock;
+use std::collections::HashMap;
+
+lazy_static! {
+    // Scopes are checked against every incoming event, so we keep them decoded
+    static ref SCOPES: PRwLock<Option<HashMap<PublicKey, Vec<RelayUrl>>>> = PRwLock::new(None);
+}
+
+fn load() -> HashMap<PublicKey, Vec<RelayUrl>> {
+    if let Some(scopes) = &*SCOPES.read() {
+        return scopes.clone();
+    }
+
+    // Stored as a list of pairs, as JSON object keys have to be strings
+    let pairs: Vec<(PublicKey, Vec<RelayUrl>)> =
+        match GLOBALS.storage.read_json(json_key::RELAY_SCOPES) {
+            Ok(pairs) => pairs.unwrap_or_default(),
+            Err(e) => {
+                tracing::error!("{}", e);
+                vec![]
+            }
+        };
+    let scopes: HashMap<PublicKey, Vec<RelayUrl>> = pairs.into_iter().collect();
+    *SCOPES.write() = Some(scopes.clone());
+    scopes
+}
+
+/// The relays a person is followed only on, if they are followed that way
+pub fn scope(pubkey: &PublicKey) -> Option<Vec<RelayUrl>> {
+    load().get(pubkey).cloned()
+}
+
+/// Everybody followed only on specific relays, and those relays
+pub fn all() -> Vec<(PublicKey, Vec<RelayUrl>)> {
+    load().into_iter().collect()
+}
+
+/// Follow a person only on these relays. Their events are then only
+/// subscribed to and accepted from these relays. An empty list follows them
+/// wherever they write again.
+///
+/// The relay picker has to pick again for this to take effect, see
+/// [ToOverlordMessage::RefreshScoresAndPickRelays](crate::comms::ToOverlordMessage::RefreshScoresAndPickRelays)
+pub fn set_scope(pubkey: PublicKey, relays: Vec<RelayUrl>) -> Result<(), Error> {
+    let mut scopes = load();
+    if relays.is_empty() {
+        scopes.remove(&pubkey);
+    } else {
+        // The relay picker only picks relays we know about
+        for url in relays.iter() {
+            GLOBALS.storage.read_or_create_relay(url, None)?;
+        }
+        scopes.insert(pubkey, relays);
+    }
+
+    let pairs: Vec<(&PublicKey, &Vec<RelayUrl>)> = scopes.iter().collect();
+    GLOBALS
+        .storage
+        .write_json(json_key::RELAY_SCOPES, &pairs, None)?;
+    *SCOPES.write() = Some(scopes);
+    Ok(())
+}
+
+/// Add a relay to the ones a person is followed only on
+pub fn add_relay(pubkey: PublicKey, url: RelayUrl) -> Result<(), Error> {
+    let mut relays = scope(&pubkey).unwrap_or_default();
+    if !relays.contains(&url) {
+        relays.push(url);
+    }
+    set_scope(pubkey, relays)
+}
+
+/// Remove a relay from the ones a person is followed only on. Removing the
+/// last one follows them wherever they write again.
+pub fn remove_relay(pubkey: PublicKey, url: &RelayUrl) -> Result<(), Error> {
+    let mut relays = scope(&pubkey).unwrap_or_default();
+    relays.retain(|r| r != url);
+    set_scope(pubkey, relays)
+}
+
+/// Whether an event by this author is accepted from this relay
+pub(crate) fn accepts(pubkey: &PublicKey, url: &RelayUrl) -> bool {
+    if SCOPES.read().is_none() {
+        load();
+    }
+    match &*SCOPES.read() {
+        Some(scopes) => match scopes.get(pubkey) {
+            Some(relays) => relays.contains(url),
+            None => true,
+        },
+        None => true,
+    }
+}
@@ -0,0 +1,500 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::Person;
+use nostr_types::{Event, EventKind, Id, PublicKey, Tag, Unixtime};
+use regex::Regex;
+use std::collections::HashMap;
+use time::macros::format_description;
+use time::Date;
+
+/// What part of the local archive a search looks at
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SearchScope {
+    #[default]
+    Everywhere,
+
+    /// The thread starting at this event, and all replies below it
+    Thread(Id),
+
+    /// Notes by this person
+    Person(PublicKey),
+}
+
+/// A parsed local search query.
+///
+/// Besides plain text, these operators are understood:
+///
+/// * `from:<npub, hex or name>` - notes by that person
+/// * `kind:<n>` - events of that kind
+/// * `since:<YYYY-MM-DD>` and `until:<YYYY-MM-DD>` - by date (a unix time also works)
+/// * `has:media` - notes linking an image or a video
+/// * `#hashtag` - notes with that hashtag
+///
+/// Anything else is searched for as text.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    /// Plain text to match against content and tags
+    pub text: String,
+
+    pub authors: Vec<PublicKey>,
+    pub kinds: Vec<EventKind>,
+    pub since: Option<Unixtime>,
+    pub until: Option<Unixtime>,
+    pub has_media: bool,
+
+    /// Lowercased, without the leading '#'
+    pub hashtags: Vec<String>,
+
+    /// Operator values we could not make sense of
+    pub unresolved: Vec<String>,
+
+    pub scope: SearchScope,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str, scope: SearchScope) -> SearchQuery {
+        let mut query = SearchQuery {
+            scope,
+            ..Default::default()
+        };
+        let mut words: Vec<&str> = Vec::new();
+
+        for word in input.split_whitespace() {
+            if let Some(who) = word.strip_prefix("from:") {
+                let found = find_authors(who);
+                if found.is_empty() {
+                    query.unresolved.push(word.to_owned());
+                }
+                query.authors.extend(found);
+            } else if let Some(k) = word.strip_prefix("kind:") {
+                match k.parse::<u32>() {
+                    Ok(k) => query.kinds.push(EventKind::from(k)),
+                    Err(_) => query.unresolved.push(word.to_owned()),
+                }
+            } else if let Some(date) = word.strip_prefix("since:") {
+                match parse_date(date) {
+                    Some(when) => query.since = Some(when),
+                    None => query.unresolved.push(word.to_owned()),
+                }
+            } else if let Some(date) = word.strip_prefix("until:") {
+                match parse_date(date) {
+                    // include the whole day
+                    Some(when) if !date.contains('-') => query.until = Some(when),
+                    Some(when) => query.until = Some(Unixtime(when.0 + 86400)),
+                    None => query.unresolved.push(word.to_owned()),
+                }
+            } else if word == "has:media" {
+                query.has_media = true;
+            } else if word.len() > 1 && word.starts_with('#') {
+                query.hashtags.push(word[1..].to_lowercase());
+            } else {
+                words.push(word);
+            }
+        }
+
+        query.text = words.join(" ");
+        query
+    }
+
+    /// Whether any operators or a scope were given (other than plain text)
+    pub fn has_operators(&self) -> bool {
+        self.scope != SearchScope::Everywhere
+            || !self.authors.is_empty()
+            || !self.kinds.is_empty()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.has_media
+            || !self.hashtags.is_empty()
+    }
+
+    /// Check an event against everything except the text, the kinds and the authors,
+    /// which are better handled by the caller via indexes
+    pub fn matches_operators(&self, event: &Event) -> bool {
+        if let Some(since) = self.since {
+            if event.created_at < since {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if event.created_at >= until {
+                return false;
+            }
+        }
+        if self.has_media && !has_media(event) {
+            return false;
+        }
+        for hashtag in &self.hashtags {
+            if !event
+                .tags
+                .iter()
+                .any(|t| t.tagname() == "t" && t.value().to_lowercase() == *hashtag)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check an event against the entire query. `re` must be the regex for the text,
+    /// if there is any text.
+    pub fn matches(&self, event: &Event, re: Option<&Regex>) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+        if !self.authors.is_empty() && !self.authors.contains(&event.pubkey) {
+            return false;
+        }
+        if !self.matches_operators(event) {
+            return false;
+        }
+        if let Some(re) = re {
+            if !re.is_match(&event.content) && !event.tags.iter().any(|t| tag_matches(t, re)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn tag_matches(tag: &Tag, re: &Regex) -> bool {
+    (0..)
+        .map(|i| tag.get_index(i))
+        .take_while(|v| !v.is_empty())
+        .any(|v| re.is_match(v))
+}
+
+fn find_authors(who: &str) -> Vec<PublicKey> {
+    if let Ok(pk) = PublicKey::try_from_bech32_string(who, true) {
+        return vec![pk];
+    }
+    if let Ok(pk) = PublicKey::try_from_hex_string(who, true) {
+        return vec![pk];
+    }
+
+    let who = who.to_lowercase();
+    GLOBALS
+        .storage
+        .filter_people(|p| {
+            if let Some(petname) = &p.petname {
+                if petname.to_lowercase() == who {
+                    return true;
+                }
+            }
+            if let Some(name) = p.name() {
+                if name.to_lowercase() == who {
+                    return true;
+                }
+            }
+            if let Some(name) = p.display_name() {
+                if name.to_lowercase() == who {
+                    return true;
+                }
+            }
+            false
+        })
+        .unwrap_or_default()
+        .drain(..)
+        .map(|p| p.pubkey)
+        .collect()
+}
+
+fn has_media(event: &Event) -> bool {
+    if event.tags.iter().any(|t| t.tagname() == "imeta") {
+        return true;
+    }
+    for word in event.content.split_whitespace() {
+        if !word.starts_with("http://") && !word.starts_with("https://") {
+            continue;
+        }
+        let path = word.split(['?', '#']).next().unwrap_or("").to_lowercase();
+        if MEDIA_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+            return true;
+        }
+    }
+    false
+}
+
+const MEDIA_EXTENSIONS: [&str; 11] = [
+    ".jpg", ".jpeg", ".png", ".gif", ".webp", ".svg", ".mp4", ".mov", ".webm", ".mkv", ".avi",
+];
+
+/// Parse a YYYY-MM-DD date (UTC midnight) or a unix time
+pub fn parse_date(s: &str) -> Option<Unixtime> {
+    if let Ok(secs) = s.parse::<i64>() {
+        return Some(Unixtime(secs));
+    }
+
+    let date = Date::parse(
+        s,
+        format_description!("[year]-[month padding:none]-[day padding:none]"),
+    )
+    .ok()?;
+    Some(Unixtime(date.midnight().assume_utc().unix_timestamp()))
+}
+
+const MAX_PEOPLE_RESULTS: usize = 100;
+
+/// Search people by petname, name, display_name and nip05, tolerating typos and
+/// diacritics. People we follow and people we interact with more rank higher.
+pub fn search_people(text: &str) -> Result<Vec<Person>, Error> {
+    let needle = fold(text.trim().trim_start_matches('@'));
+    if needle.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let interactions = our_interactions()?;
+
+    let mut scored: Vec<(u32, Person)> = GLOBALS
+        .storage
+        .filter_people(|_| true)?
+        .drain(..)
+        .filter_map(|person| {
+            let fields = [
+                person.petname.as_deref(),
+                person.name(),
+                person.display_name(),
+                person.nip05(),
+            ];
+            let best = fields
+                .iter()
+                .flatten()
+                .map(|field| field_score(&needle, field))
+                .max()
+                .unwrap_or(0);
+            if best == 0 {
+                return None;
+            }
+
+            let mut score = best;
+            if person.is_subscribed_to() {
+                score += 300;
+            }
+            let count = interactions.get(&person.pubkey).copied().unwrap_or(0);
+            score += count.min(50) as u32 * 4;
+
+            Some((score, person))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.truncate(MAX_PEOPLE_RESULTS);
+
+    Ok(scored.drain(..).map(|(_, person)| person).collect())
+}
+
+// How often we have tagged each person (replied, mentioned, reacted, messaged)
+fn our_interactions() -> Result<HashMap<PublicKey, usize>, Error> {
+    let mut counts: HashMap<PublicKey, usize> = HashMap::new();
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(counts),
+    };
+
+    let events = GLOBALS.storage.find_events(
+        &[
+            EventKind::TextNote,
+            EventKind::Repost,
+            EventKind::Reaction,
+            EventKind::EncryptedDirectMessage,
+        ],
+        &[pubkey],
+        None,
+        |_| true,
+        false,
+    )?;
+
+    for event in &events {
+        for tag in &event.tags {
+            if let Ok((pk, _, _)) = tag.parse_pubkey() {
+                *counts.entry(pk).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+// Score how well a (folded) needle matches a field, 0 if it does not
+fn field_score(needle: &str, field: &str) -> u32 {
+    let hay = fold(field);
+    if hay == needle {
+        return 1000;
+    }
+    if hay.starts_with(needle) {
+        return 800;
+    }
+    let words = hay
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty());
+    if words.clone().any(|w| w.starts_with(needle)) {
+        return 700;
+    }
+    if hay.contains(needle) {
+        return 600;
+    }
+
+    // Tolerate typos, more of them in longer needles
+    let needle_len = needle.chars().count();
+    let max_typos = match needle_len {
+        0..=2 => return 0,
+        3..=5 => 1,
+        _ => 2,
+    };
+    let typos = words
+        .chain(std::iter::once(hay.as_str()))
+        .map(|w| {
+            // compare against the whole word and against its start
+            let prefix: String = w.chars().take(needle_len).collect();
+            edit_distance(needle, w).min(edit_distance(needle, &prefix))
+        })
+        .min()
+        .unwrap_or(usize::MAX);
+    match typos {
+        1 => 400,
+        2 if max_typos >= 2 => 250,
+        _ => 0,
+    }
+}
+
+// Optimal string alignment distance (Levenshtein plus adjacent transpositions)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Lowercase and transliterate common diacritics, so that "José" matches "jose"
+pub fn fold(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    for c in s.chars().flat_map(|c| c.to_lowercase()) {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => output.push('a'),
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => output.push('c'),
+            'ď' | 'đ' | 'ð' => output.push('d'),
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => output.push('e'),
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => output.push('g'),
+            'ĥ' | 'ħ' => output.push('h'),
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => output.push('i'),
+            'ĵ' => output.push('j'),
+            'ķ' => output.push('k'),
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => output.push('l'),
+            'ñ' | 'ń' | 'ņ' | 'ň' => output.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => output.push('o'),
+            'ŕ' | 'ŗ' | 'ř' => output.push('r'),
+            'ś' | 'ŝ' | 'ş' | 'š' => output.push('s'),
+            'ţ' | 'ť' | 'ŧ' => output.push('t'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => output.push('u'),
+            'ŵ' => output.push('w'),
+            'ý' | 'ÿ' | 'ŷ' => output.push('y'),
+            'ź' | 'ż' | 'ž' => output.push('z'),
+            'ß' => output.push_str("ss"),
+            'æ' => output.push_str("ae"),
+            'œ' => output.push_str("oe"),
+            'þ' => output.push_str("th"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1700000000"), Some(Unixtime(1700000000)));
+        assert_eq!(parse_date("1970-01-01"), Some(Unixtime(0)));
+        assert_eq!(parse_date("2024-01-05"), Some(Unixtime(1704412800)));
+        assert_eq!(parse_date("2024-1-5"), Some(Unixtime(1704412800)));
+        assert_eq!(parse_date("1969-12-31"), Some(Unixtime(-86400)));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse(
+            "hello kind:30023 since:2024-01-05 until:2024-01-05 has:media #Nostr world",
+            SearchScope::Everywhere,
+        );
+        assert_eq!(query.text, "hello world");
+        assert_eq!(query.kinds, vec![EventKind::LongFormContent]);
+        assert_eq!(query.since, Some(Unixtime(1704412800)));
+        assert_eq!(query.until, Some(Unixtime(1704412800 + 86400)));
+        assert!(query.has_media);
+        assert_eq!(query.hashtags, vec!["nostr".to_owned()]);
+        assert!(query.unresolved.is_empty());
+        assert!(query.has_operators());
+
+        let query = SearchQuery::parse(
+            "until:1700000000 kind:x since:soon",
+            SearchScope::Everywhere,
+        );
+        assert_eq!(query.until, Some(Unixtime(1700000000)));
+        assert_eq!(
+            query.unresolved,
+            vec!["kind:x".to_owned(), "since:soon".to_owned()]
+        );
+        assert!(query.text.is_empty());
+
+        let query = SearchQuery::parse("just text", SearchScope::Everywhere);
+        assert!(!query.has_operators());
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("José"), "jose");
+        assert_eq!(fold("ÅNGSTRÖM"), "angstrom");
+        assert_eq!(fold("Straße"), "strasse");
+        assert_eq!(fold("Œuvre Þing"), "oeuvre thing");
+        assert_eq!(fold("Łódź"), "lodz");
+        assert_eq!(fold("already plain 123"), "already plain 123");
+        assert_eq!(fold("日本"), "日本");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("nostr", "nostr"), 0);
+        assert_eq!(edit_distance("nostr", "nostrr"), 1);
+        assert_eq!(edit_distance("nostr", "nstr"), 1);
+        assert_eq!(edit_distance("nostr", "nostx"), 1);
+        assert_eq!(edit_distance("nostr", "nsotr"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("josé", "jose"), 1);
+    }
+
+    #[test]
+    fn test_field_score() {
+        assert_eq!(field_score("jose", "José"), 1000);
+        assert_eq!(field_score("jose", "José Luis"), 800);
+        assert_eq!(field_score("luis", "José Luis"), 700);
+        assert_eq!(field_score("uis", "José Luis"), 600);
+        assert_eq!(field_score("nsotr", "all about nostr"), 400);
+        assert_eq!(field_score("nostrrr", "all about nostr"), 250);
+        assert_eq!(field_score("xyz", "all about nostr"), 0);
+        assert_eq!(field_score("ab", "xy"), 0);
+    }
+}
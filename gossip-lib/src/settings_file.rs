@@ -0,0 +1,314 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::people::PersonListMetadata;
+use crate::relay::Relay;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{PublicKey, Unixtime};
+use paste::paste;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Version of the settings file format
+pub const SETTINGS_FILE_VERSION: u32 = 1;
+
+/// A person list as stored in a settings file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PersonListEntry {
+    dtag: String,
+    title: String,
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default)]
+    private: bool,
+
+    /// Hex public keys, and whether each is a public entry on the list
+    people: Vec<(String, bool)>,
+}
+
+/// The contents of a settings file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    exported_at: i64,
+    #[serde(default)]
+    settings_version: u32,
+    settings: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    relays: Vec<Relay>,
+    #[serde(default)]
+    person_lists: Vec<PersonListEntry>,
+}
+
+// Every setting except the public key, which belongs to the identity rather than
+// to the configuration
+macro_rules! exported_settings {
+    ($($field:ident),* $(,)?) => {
+        paste! {
+            fn export_values() -> Result<BTreeMap<String, serde_json::Value>, Error> {
+                let mut map = BTreeMap::new();
+                $(
+                    map.insert(
+                        stringify!($field).to_owned(),
+                        serde_json::to_value(GLOBALS.storage.[<read_setting_ $field>]())?,
+                    );
+                )*
+                Ok(map)
+            }
+
+            fn import_value<'a>(
+                name: &str,
+                value: serde_json::Value,
+                txn: &mut RwTxn<'a>,
+            ) -> Result<bool, Error> {
+                match name {
+                    $(
+                        stringify!($field) => {
+                            let v = serde_json::from_value(value)?;
+                            GLOBALS.storage.[<write_setting_ $field>](&v, Some(txn))?;
+                            Ok(true)
+                        }
+                    )*
+                    _ => Ok(false),
+                }
+            }
+        }
+    };
+}
+
+exported_settings!(
+    log_n,
+    login_at_startup,
+    settings_sync,
+    settings_sync_excluded,
+    offline,
+    use_proxy,
+    proxy_host,
+    proxy_port,
+    proxy_direct_relays,
+    load_avatars,
+    load_media,
+    check_nip05,
+    automatically_fetch_metadata,
+    relay_connection_requires_approval,
+    relay_auth_requires_approval,
+    num_relays_per_person,
+    max_relays,
+    feed_chunk,
+    replies_chunk,
+    person_feed_chunk,
+    overlap,
+    reposts,
+    show_long_form,
+    show_git_events,
+    show_torrents,
+    show_live_activities,
+    show_calendar_events,
+    show_mentions,
+    direct_messages,
+    future_allowance_secs,
+    hide_mutes_entirely,
+    mute_list_sync,
+    reactions,
+    enable_zap_receipts,
+    show_user_status,
+    verify_timestamps,
+    show_media,
+    approve_content_warning,
+    show_deleted_events,
+    pow,
+    set_client_tag,
+    set_user_agent,
+    relay_list_auto_advertise,
+    web_link_base,
+    media_server,
+    follow_import_directories,
+    delegatee_tag,
+    max_fps,
+    memory_budget_mb,
+    recompute_feed_periodically,
+    feed_recompute_interval_ms,
+    theme_variant,
+    dark_mode,
+    follow_os_dark_mode,
+    override_dpi,
+    highlight_unread_events,
+    posting_area_at_top,
+    status_bar,
+    image_resize_algorithm,
+    inertial_scrolling,
+    mouse_acceleration,
+    language,
+    relative_timestamps,
+    timestamps_24_hour,
+    timezone_offset_minutes,
+    sound_on_mention,
+    sound_on_dm,
+    sound_on_zap,
+    sound_file_mention,
+    sound_file_dm,
+    sound_file_zap,
+    sound_quiet_start_hour,
+    sound_quiet_end_hour,
+    relay_list_becomes_stale_hours,
+    metadata_becomes_stale_hours,
+    nip05_becomes_stale_if_valid_hours,
+    nip05_becomes_stale_if_invalid_minutes,
+    avatar_becomes_stale_hours,
+    media_becomes_stale_hours,
+    max_websocket_message_size_kb,
+    max_websocket_frame_size_kb,
+    websocket_accept_unmasked_frames,
+    websocket_connect_timeout_sec,
+    websocket_ping_frequency_sec,
+    fetcher_metadata_looptime_ms,
+    fetcher_looptime_ms,
+    fetcher_connect_timeout_sec,
+    fetcher_timeout_sec,
+    fetcher_max_requests_per_host,
+    fetcher_host_exclusion_on_low_error_secs,
+    fetcher_host_exclusion_on_med_error_secs,
+    fetcher_host_exclusion_on_high_error_secs,
+    nip11_lines_to_output_on_error,
+    fetcher_nip98_hosts,
+    log_to_file,
+    log_filter,
+    log_file_max_size_kb,
+    crash_reports,
+    metrics_endpoint,
+    metrics_port,
+    syndication_endpoint,
+    syndication_port,
+    prune_period_days,
+    cache_prune_period_days,
+    prune_keep_followed,
+    prune_automatically,
+    backup_enabled,
+    backup_interval_hours,
+    backup_keep,
+    backup_directory,
+    backup_include_key,
+    avoid_spam_on_unsafe_relays,
+    min_pow_unknown_authors,
+    exclude_labels,
+    include_labels,
+    mute_when_reporting,
+    hide_bridged_posts,
+    follow_back_enabled,
+    follow_back_automatically,
+    follow_back_max_wot_distance,
+    follow_back_require_nip05,
+    follow_back_min_account_age_days,
+    read_later_prefetch_media,
+);
+
+/// Export all settings, relays and person lists to a JSON file
+pub fn export_settings<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let relays = GLOBALS.storage.filter_relays(|_| true)?;
+
+    let mut person_lists: Vec<PersonListEntry> = Vec::new();
+    for (list, metadata) in GLOBALS.storage.get_all_person_list_metadata()? {
+        let people = GLOBALS
+            .storage
+            .get_people_in_list(list)?
+            .into_iter()
+            .map(|(pk, public)| (pk.as_hex_string(), public))
+            .collect();
+        person_lists.push(PersonListEntry {
+            dtag: metadata.dtag,
+            title: metadata.title,
+            favorite: metadata.favorite,
+            private: metadata.private,
+            people,
+        });
+    }
+
+    let file = SettingsFile {
+        version: SETTINGS_FILE_VERSION,
+        exported_at: Unixtime::now().unwrap().0,
+        settings_version: Storage::SETTINGS_VERSION,
+        settings: export_values()?,
+        relays,
+        person_lists,
+    };
+
+    std::fs::write(path, serde_json::to_string_pretty(&file)?)?;
+
+    Ok(())
+}
+
+/// Import settings, relays and person lists from a JSON file written by
+/// [export_settings]. Relays and list members are merged with what we already have.
+pub fn import_settings<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: SettingsFile = serde_json::from_str(&contents)?;
+
+    if file.version > SETTINGS_FILE_VERSION {
+        return Err(ErrorKind::General(format!(
+            "Settings file version {} is newer than this gossip supports ({})",
+            file.version, SETTINGS_FILE_VERSION
+        ))
+        .into());
+    }
+    if file.settings_version > Storage::SETTINGS_VERSION {
+        return Err(ErrorKind::General(format!(
+            "Settings in this file are version {}, newer than this gossip supports ({})",
+            file.settings_version,
+            Storage::SETTINGS_VERSION
+        ))
+        .into());
+    }
+
+    let mut txn = GLOBALS.storage.get_write_txn()?;
+
+    let mut settings: usize = 0;
+    for (name, value) in file.settings {
+        match import_value(&name, value, &mut txn) {
+            Ok(true) => settings += 1,
+            Ok(false) => tracing::warn!("Skipping unknown setting {}", name),
+            Err(e) => tracing::warn!("Could not import setting {}: {}", name, e),
+        }
+    }
+
+    for relay in &file.relays {
+        GLOBALS.storage.write_relay(relay, Some(&mut txn))?;
+    }
+
+    for entry in &file.person_lists {
+        let list = match GLOBALS.storage.find_person_list_by_dtag(&entry.dtag)? {
+            Some((list, _)) => list,
+            None => {
+                let metadata = PersonListMetadata {
+                    dtag: entry.dtag.clone(),
+                    title: entry.title.clone(),
+                    favorite: entry.favorite,
+                    private: entry.private,
+                    ..Default::default()
+                };
+                GLOBALS
+                    .storage
+                    .allocate_person_list(&metadata, Some(&mut txn))?
+            }
+        };
+        for (hex, public) in &entry.people {
+            match PublicKey::try_from_hex_string(hex, true) {
+                Ok(pk) => GLOBALS
+                    .storage
+                    .add_person_to_list(&pk, list, *public, Some(&mut txn))?,
+                Err(_) => tracing::warn!("Skipping invalid public key {} in list", hex),
+            }
+        }
+    }
+
+    txn.commit()?;
+
+    tracing::info!(
+        "Imported {} settings, {} relays and {} person lists",
+        settings,
+        file.relays.len(),
+        file.person_lists.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::json_key;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{ContentEncryptionAlgorithm, Event, EventKind, Unixtime};
+use paste::paste;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The 'd' tag of our NIP-78 application data event
+pub const SETTINGS_SYNC_D: &str = "gossip/settings";
+
+/// A synced setting value, and when it was last changed
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct SyncedSetting {
+    value: serde_json::Value,
+
+    /// Unix time of the change, or 0 if not changed since syncing began
+    changed_at: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct SyncedSettings {
+    #[serde(default)]
+    settings_version: u32,
+    settings: BTreeMap<String, SyncedSetting>,
+}
+
+// Settings specific to a machine (keys, DPI, network tuning, caches) are not synced
+macro_rules! synced_settings {
+    ($($field:ident),* $(,)?) => {
+        paste! {
+            /// The names of the settings that can roam between installs
+            pub fn synced_setting_names() -> Vec<&'static str> {
+                vec![$(stringify!($field)),*]
+            }
+
+            fn local_values() -> Result<BTreeMap<String, serde_json::Value>, Error> {
+                let mut map = BTreeMap::new();
+                $(
+                    map.insert(
+                        stringify!($field).to_owned(),
+                        serde_json::to_value(GLOBALS.storage.[<read_setting_ $field>]())?,
+                    );
+                )*
+                Ok(map)
+            }
+
+            fn apply_value<'a>(
+                name: &str,
+                value: serde_json::Value,
+                txn: &mut RwTxn<'a>,
+            ) -> Result<(), Error> {
+                match name {
+                    $(
+                        stringify!($field) => {
+                            let v = serde_json::from_value(value)?;
+                            GLOBALS.storage.[<write_setting_ $field>](&v, Some(txn))?;
+                        }
+                    )*
+                    _ => (),
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+synced_settings!(
+    feed_chunk,
+    replies_chunk,
+    person_feed_chunk,
+    overlap,
+    reposts,
+    show_long_form,
+    show_git_events,
+    show_torrents,
+    show_live_activities,
+    show_calendar_events,
+    show_mentions,
+    direct_messages,
+    future_allowance_secs,
+    hide_mutes_entirely,
+    mute_list_sync,
+    reactions,
+    enable_zap_receipts,
+    show_user_status,
+    verify_timestamps,
+    show_media,
+    approve_content_warning,
+    show_deleted_events,
+    avoid_spam_on_unsafe_relays,
+    min_pow_unknown_authors,
+    exclude_labels,
+    include_labels,
+    mute_when_reporting,
+    hide_bridged_posts,
+    follow_back_enabled,
+    follow_back_automatically,
+    follow_back_max_wot_distance,
+    follow_back_require_nip05,
+    follow_back_min_account_age_days,
+    read_later_prefetch_media,
+    pow,
+    set_client_tag,
+    set_user_agent,
+    relay_list_auto_advertise,
+    web_link_base,
+    media_server,
+    follow_import_directories,
+    recompute_feed_periodically,
+    feed_recompute_interval_ms,
+    theme_variant,
+    dark_mode,
+    follow_os_dark_mode,
+    highlight_unread_events,
+    posting_area_at_top,
+    status_bar,
+    image_resize_algorithm,
+    inertial_scrolling,
+    mouse_acceleration,
+    language,
+    relative_timestamps,
+    timestamps_24_hour,
+    sound_on_mention,
+    sound_on_dm,
+    sound_on_zap,
+    sound_quiet_start_hour,
+    sound_quiet_end_hour,
+);
+
+fn read_state() -> Result<BTreeMap<String, SyncedSetting>, Error> {
+    Ok(GLOBALS
+        .storage
+        .read_json(json_key::SETTINGS_SYNC_STATE)?
+        .unwrap_or_default())
+}
+
+// Bring the sync state up to date with local values. A setting seen for the first
+// time gets changed_at 0, so that roaming values from other installs win over it.
+fn refresh_state() -> Result<BTreeMap<String, SyncedSetting>, Error> {
+    let mut state = read_state()?;
+    let now = Unixtime::now().unwrap().0;
+    for (name, value) in local_values()? {
+        let changed_at = match state.get(&name) {
+            None => 0,
+            Some(entry) if entry.value != value => now,
+            Some(_) => continue,
+        };
+        state.insert(name, SyncedSetting { value, changed_at });
+    }
+    Ok(state)
+}
+
+/// Build the content of our settings event (kind 30078): our settings, encrypted
+/// to ourselves
+pub(crate) fn build_settings_content() -> Result<Option<String>, Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+
+    // Take in what our other installs published first, so we don't overwrite
+    // their newer changes
+    if let Some(event) = GLOBALS.storage.get_replaceable_event(
+        EventKind::AppSpecificData,
+        public_key,
+        SETTINGS_SYNC_D,
+    )? {
+        apply_settings_event(&event)?;
+    }
+
+    let state = refresh_state()?;
+    GLOBALS
+        .storage
+        .write_json(json_key::SETTINGS_SYNC_STATE, &state, None)?;
+
+    let excluded = GLOBALS.storage.read_setting_settings_sync_excluded();
+    let synced = SyncedSettings {
+        settings_version: Storage::SETTINGS_VERSION,
+        settings: state
+            .into_iter()
+            .filter(|(name, _)| !excluded.contains(name))
+            .collect(),
+    };
+
+    let content = GLOBALS.identity.encrypt(
+        &public_key,
+        &serde_json::to_string(&synced)?,
+        ContentEncryptionAlgorithm::Nip44v2,
+    )?;
+
+    Ok(Some(content))
+}
+
+/// Is this our own settings sync event?
+pub(crate) fn is_settings_event(event: &Event) -> bool {
+    event.kind == EventKind::AppSpecificData
+        && Some(event.pubkey) == GLOBALS.identity.public_key()
+        && event
+            .tags
+            .iter()
+            .any(|t| t.tagname() == "d" && t.value() == SETTINGS_SYNC_D)
+}
+
+/// Apply settings from one of our settings events, taking each setting that
+/// was changed more recently there than here
+pub(crate) fn apply_settings_event(event: &Event) -> Result<(), Error> {
+    if !GLOBALS.storage.read_setting_settings_sync() || !is_settings_event(event) {
+        return Ok(());
+    }
+    if !GLOBALS.identity.is_unlocked() {
+        tracing::debug!("Cannot apply synced settings while the private key is locked");
+        return Ok(());
+    }
+
+    let plaintext = GLOBALS
+        .identity
+        .decrypt_nip44(&event.pubkey, &event.content)?;
+    let remote: SyncedSettings = serde_json::from_str(&plaintext)?;
+    if remote.settings_version > Storage::SETTINGS_VERSION {
+        tracing::info!("Not applying synced settings from a newer gossip install");
+        return Ok(());
+    }
+
+    let excluded = GLOBALS.storage.read_setting_settings_sync_excluded();
+    let mut state = refresh_state()?;
+    let mut applied: usize = 0;
+
+    let mut txn = GLOBALS.storage.get_write_txn()?;
+    for (name, entry) in remote.settings {
+        if excluded.contains(&name) {
+            continue;
+        }
+        let local_changed_at = state.get(&name).map(|e| e.changed_at).unwrap_or(0);
+        if entry.changed_at <= local_changed_at {
+            continue;
+        }
+        if let Err(e) = apply_value(&name, entry.value.clone(), &mut txn) {
+            tracing::warn!("Could not apply synced setting {}: {}", name, e);
+            continue;
+        }
+        state.insert(name, entry);
+        applied += 1;
+    }
+    GLOBALS
+        .storage
+        .write_json(json_key::SETTINGS_SYNC_STATE, &state, Some(&mut txn))?;
+    txn.commit()?;
+
+    if applied > 0 {
+        GLOBALS.status_queue.write().write(format!(
+            "Applied {} settings from another gossip install",
+            applied
+        ));
+    }
+
+    Ok(())
+}
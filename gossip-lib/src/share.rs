@@ -0,0 +1,91 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relay::Relay;
+use gossip_relay_picker::Direction;
+use nostr_types::{Event, EventAddr, EventPointer, Profile, PublicKey, UncheckedUrl};
+
+/// How many relay hints to embed in a shared link
+const MAX_HINTS: usize = 3;
+
+/// Relays to hint for an event: those we most recently saw it on
+pub fn event_relay_hints(event: &Event) -> Result<Vec<UncheckedUrl>, Error> {
+    let mut seen_on = GLOBALS.storage.get_event_seen_on_relay(event.id)?;
+    seen_on.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut hints: Vec<UncheckedUrl> = seen_on
+        .iter()
+        .take(MAX_HINTS)
+        .map(|(url, _)| url.to_unchecked_url())
+        .collect();
+
+    // Our own events may not have been seen back yet
+    if hints.is_empty() && Some(event.pubkey) == GLOBALS.identity.public_key() {
+        hints = our_outbox_relays()?;
+    }
+
+    Ok(hints)
+}
+
+/// Relays to hint for a person: where they write
+pub fn person_relay_hints(pubkey: PublicKey) -> Result<Vec<UncheckedUrl>, Error> {
+    if Some(pubkey) == GLOBALS.identity.public_key() {
+        return our_outbox_relays();
+    }
+    Ok(GLOBALS
+        .storage
+        .get_best_relays(pubkey, Direction::Write)?
+        .iter()
+        .take(MAX_HINTS)
+        .map(|(url, _)| url.to_unchecked_url())
+        .collect())
+}
+
+fn our_outbox_relays() -> Result<Vec<UncheckedUrl>, Error> {
+    Ok(GLOBALS
+        .storage
+        .filter_relays(|r| r.has_usage_bits(Relay::OUTBOX) && r.rank != 0)?
+        .iter()
+        .take(MAX_HINTS)
+        .map(|r| r.url.to_unchecked_url())
+        .collect())
+}
+
+/// A bech32 reference to an event with relay hints: an naddr for replaceable
+/// events (so it follows the latest version), otherwise an nevent
+pub fn event_bech32(event: &Event) -> Result<String, Error> {
+    let relays = event_relay_hints(event)?;
+    if event.kind.is_replaceable() {
+        Ok(EventAddr {
+            d: event.parameter().unwrap_or_default(),
+            relays,
+            kind: event.kind,
+            author: event.pubkey,
+        }
+        .as_bech32_string())
+    } else {
+        Ok(EventPointer {
+            id: event.id,
+            relays,
+            author: Some(event.pubkey),
+            kind: Some(event.kind),
+        }
+        .as_bech32_string())
+    }
+}
+
+/// An nprofile for a person with relay hints
+pub fn profile_bech32(pubkey: PublicKey) -> Result<String, Error> {
+    Ok(Profile {
+        pubkey,
+        relays: person_relay_hints(pubkey)?,
+    }
+    .as_bech32_string())
+}
+
+/// A web link for a bech32 reference, using the `web_link_base` setting
+pub fn web_link(bech32: &str) -> String {
+    let mut base = GLOBALS.storage.read_setting_web_link_base();
+    if !base.ends_with('/') {
+        base.push('/');
+    }
+    format!("{}{}", base, bech32)
+}
@@ -1,8 +1,47 @@
+use nostr_types::Unixtime;
+use std::collections::VecDeque;
+use std::panic::Location;
+
+/// How many status messages are kept in the history
+const MAX_HISTORY: usize = 500;
+
+/// How serious a status message is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatusSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl StatusSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusSeverity::Info => "info",
+            StatusSeverity::Warning => "warning",
+            StatusSeverity::Error => "error",
+        }
+    }
+}
+
+/// A status message as kept in the history
+#[derive(Clone, Debug)]
+pub struct StatusEntry {
+    pub time: Unixtime,
+    pub severity: StatusSeverity,
+
+    /// The source file that wrote the message
+    pub source: &'static str,
+
+    pub message: String,
+}
+
 /// A queue of up to three status messages for the UI, generally
-/// representing errors that occured in disconnected backend processes.
+/// representing errors that occured in disconnected backend processes,
+/// along with a longer history of past messages.
 pub struct StatusQueue {
     head: usize,
     messages: [String; 3],
+    history: VecDeque<StatusEntry>,
 }
 
 impl Default for StatusQueue {
@@ -10,11 +49,13 @@ impl Default for StatusQueue {
         StatusQueue {
             head: 0,
             messages: ["".to_owned(), "".to_owned(), "".to_owned()],
+            history: VecDeque::new(),
         }
     }
 }
 
 impl StatusQueue {
+    #[track_caller]
     pub fn new(initial: String) -> StatusQueue {
         let mut sq: StatusQueue = Default::default();
         sq.write(initial);
@@ -33,7 +74,35 @@ impl StatusQueue {
         self.messages[self.head].clone()
     }
 
+    /// Write an informational message
+    #[track_caller]
     pub fn write(&mut self, message: String) {
+        self.push(StatusSeverity::Info, Location::caller().file(), message);
+    }
+
+    /// Write a warning
+    #[track_caller]
+    pub fn write_warning(&mut self, message: String) {
+        self.push(StatusSeverity::Warning, Location::caller().file(), message);
+    }
+
+    /// Write an error
+    #[track_caller]
+    pub fn write_error(&mut self, message: String) {
+        self.push(StatusSeverity::Error, Location::caller().file(), message);
+    }
+
+    fn push(&mut self, severity: StatusSeverity, source: &'static str, message: String) {
+        self.history.push_back(StatusEntry {
+            time: Unixtime::now().unwrap(),
+            severity,
+            source,
+            message: message.clone(),
+        });
+        while self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+
         self.head = (self.head + 2) % 3; // like -1, but modular safe
         self.messages[self.head] = message;
     }
@@ -41,4 +110,25 @@ impl StatusQueue {
     pub fn dismiss(&mut self, offset: usize) {
         self.messages[(self.head + offset) % 3] = "".to_owned();
     }
+
+    /// Past messages at or above a severity, newest first, optionally only those
+    /// whose source or message contains some text
+    pub fn history(&self, min_severity: StatusSeverity, containing: &str) -> Vec<StatusEntry> {
+        let containing = containing.to_lowercase();
+        self.history
+            .iter()
+            .rev()
+            .filter(|e| e.severity >= min_severity)
+            .filter(|e| {
+                containing.is_empty()
+                    || e.source.to_lowercase().contains(&containing)
+                    || e.message.to_lowercase().contains(&containing)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
 }
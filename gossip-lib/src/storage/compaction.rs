@@ -0,0 +1,132 @@
+use crate::error::Error;
+use crate::profile::Profile;
+use crate::storage::events3::EVENTS3_DB_NAME;
+use crate::storage::{Storage, MAP_SIZE, MAX_DBS};
+use heed::types::UnalignedSlice;
+use heed::EnvOpenOptions;
+use nostr_types::Event;
+use speedy::Readable;
+use std::path::{Path, PathBuf};
+
+// The compacted copy is written into this directory within the LMDB directory,
+// and marked complete once it has been checked
+const COMPACTED_DIR: &str = "compacted";
+const COMPLETE_MARKER: &str = "complete";
+
+/// What compacting the database found and will save
+#[derive(Debug, Clone, Copy)]
+pub struct CompactionReport {
+    /// Bytes the database file takes now
+    pub old_size: u64,
+
+    /// Bytes the compacted copy takes
+    pub new_size: u64,
+
+    /// Events read back from the copy, each of which decoded
+    pub events_checked: usize,
+}
+
+impl CompactionReport {
+    /// How many bytes the swap will free
+    pub fn reclaimed(&self) -> u64 {
+        self.old_size.saturating_sub(self.new_size)
+    }
+}
+
+impl Storage {
+    /// Write a compacted copy of the database next to it and check that it
+    /// opens and that every event in it decodes. LMDB can not be swapped while
+    /// it is open, so the copy replaces the database the next time gossip
+    /// starts. Anything written between now and then is lost, so this is best
+    /// done right as gossip quits (see `GLOBALS.compact_on_quit`), or from the
+    /// command line.
+    pub fn compact(&self) -> Result<CompactionReport, Error> {
+        let lmdb_dir = Profile::current()?.lmdb_dir;
+        let dir = lmdb_dir.join(COMPACTED_DIR);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+
+        let result = self.compact_into(&lmdb_dir, &dir);
+        if result.is_err() {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        result
+    }
+
+    fn compact_into(&self, lmdb_dir: &Path, dir: &Path) -> Result<CompactionReport, Error> {
+        // Events are rarely removed, so the copy should have at least these
+        let live_events = self.get_event_len()? as usize;
+
+        let copy = dir.join("data.mdb");
+        self.copy_to_file(&copy)?;
+
+        let events_checked = check_copy(dir)?;
+        if events_checked < live_events {
+            return Err(format!(
+                "The compacted copy has {} events, but the database has {}",
+                events_checked, live_events
+            )
+            .into());
+        }
+
+        std::fs::write(dir.join(COMPLETE_MARKER), b"")?;
+
+        Ok(CompactionReport {
+            old_size: std::fs::metadata(lmdb_dir.join("data.mdb"))?.len(),
+            new_size: std::fs::metadata(&copy)?.len(),
+            events_checked,
+        })
+    }
+}
+
+// Open the copy on its own and read every event back out of it
+fn check_copy(dir: &Path) -> Result<usize, Error> {
+    let mut builder = EnvOpenOptions::new();
+    builder.max_dbs(MAX_DBS);
+    builder.map_size(MAP_SIZE);
+    let env = builder.open(dir)?;
+
+    let txn = env.read_txn()?;
+    let events = match env
+        .open_database::<UnalignedSlice<u8>, UnalignedSlice<u8>>(&txn, Some(EVENTS3_DB_NAME))?
+    {
+        Some(db) => db,
+        None => return Ok(0),
+    };
+    let mut count: usize = 0;
+    for result in events.iter(&txn)? {
+        let (_key, val) = result?;
+        let _ = Event::read_from_buffer(val)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// If a checked compacted copy is waiting, put it in place of the database.
+/// This must run before the database is opened.
+pub(super) fn swap_in_compacted(lmdb_dir: &Path) -> Result<(), Error> {
+    let dir: PathBuf = lmdb_dir.join(COMPACTED_DIR);
+    if !dir.join(COMPLETE_MARKER).exists() {
+        // Leftovers of a compaction that did not finish
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        return Ok(());
+    }
+
+    let database = lmdb_dir.join("data.mdb");
+    let old_size = std::fs::metadata(&database).map(|m| m.len()).unwrap_or(0);
+    let new_size = std::fs::metadata(dir.join("data.mdb"))?.len();
+    std::fs::rename(dir.join("data.mdb"), &database)?;
+    std::fs::remove_dir_all(&dir)?;
+
+    tracing::info!(
+        "Swapped in the compacted database, reclaiming {} bytes",
+        old_size.saturating_sub(new_size)
+    );
+
+    Ok(())
+}
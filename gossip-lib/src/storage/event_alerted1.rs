@@ -0,0 +1,117 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::UnalignedSlice;
+use heed::RwTxn;
+use nostr_types::{Id, Unixtime};
+use std::sync::Mutex;
+
+// Id -> Unixtime (when it matched an alert rule)
+//   key: id.as_slice()
+//   val: unixtime.0.to_be_bytes()
+
+static EVENT_ALERTED1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut EVENT_ALERTED1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_event_alerted1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = EVENT_ALERTED1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = EVENT_ALERTED1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = EVENT_ALERTED1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<UnalignedSlice<u8>, UnalignedSlice<u8>>()
+                    // no .flags needed
+                    .name("event_alerted")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                EVENT_ALERTED1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn get_event_alerted1_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_alerted1()?.len(&txn)?)
+    }
+
+    pub(crate) fn mark_event_alerted1<'a>(
+        &'a self,
+        id: Id,
+        at: Unixtime,
+        max: usize,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = at.0.to_be_bytes();
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let db = self.db_event_alerted1()?;
+            if db.get(txn, id.as_slice())?.is_some() {
+                return Ok(());
+            }
+            db.put(txn, id.as_slice(), &bytes)?;
+
+            let len = db.len(txn)? as usize;
+            if len > max {
+                let mut oldest: Vec<(i64, Vec<u8>)> = Vec::with_capacity(len);
+                for result in db.iter(txn)? {
+                    let (key, val) = result?;
+                    oldest.push((i64::from_be_bytes(val[..8].try_into()?), key.to_owned()));
+                }
+                oldest.sort();
+                oldest.truncate(len - max);
+
+                // actual deletion done in second pass
+                // (deleting during interation does not work in LMDB)
+                for (_, key) in oldest.iter() {
+                    db.delete(txn, key)?;
+                }
+            }
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn read_event_alerted1(&self, id: Id) -> Result<Option<Unixtime>, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(match self.db_event_alerted1()?.get(&txn, id.as_slice())? {
+            Some(val) => Some(Unixtime(i64::from_be_bytes(val[..8].try_into()?))),
+            None => None,
+        })
+    }
+
+    pub(crate) fn read_alerted_events1(&self) -> Result<Vec<Id>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(i64, Id)> = Vec::new();
+        for result in self.db_event_alerted1()?.iter(&txn)? {
+            let (key, val) = result?;
+            let at = i64::from_be_bytes(val[..8].try_into()?);
+            output.push((at, Id(key[..32].try_into()?)));
+        }
+        output.sort_by_key(|(at, _)| *at);
+        Ok(output.into_iter().map(|(_, id)| id).collect())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::UnalignedSlice;
+use heed::RwTxn;
+use nostr_types::{Id, Unixtime};
+use std::sync::Mutex;
+
+// Id -> Unixtime (until when it stays dismissed, i64::MAX for ever)
+//   key: id.as_slice()
+//   val: unixtime.0.to_be_bytes()
+
+static EVENT_DISMISSED1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut EVENT_DISMISSED1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_event_dismissed1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = EVENT_DISMISSED1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = EVENT_DISMISSED1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = EVENT_DISMISSED1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<UnalignedSlice<u8>, UnalignedSlice<u8>>()
+                    // no .flags needed
+                    .name("event_dismissed")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                EVENT_DISMISSED1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn get_event_dismissed1_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_dismissed1()?.len(&txn)?)
+    }
+
+    pub(crate) fn dismiss_event1<'a>(
+        &'a self,
+        id: Id,
+        until: Option<Unixtime>,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = until.map(|u| u.0).unwrap_or(i64::MAX).to_be_bytes();
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_event_dismissed1()?
+                .put(txn, id.as_slice(), &bytes)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn undismiss_event1<'a>(
+        &'a self,
+        id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            let _ = self.db_event_dismissed1()?.delete(txn, id.as_slice());
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn read_event_dismissed1(&self, id: Id) -> Result<Option<Unixtime>, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(
+            match self.db_event_dismissed1()?.get(&txn, id.as_slice())? {
+                Some(val) => Some(Unixtime(i64::from_be_bytes(val[..8].try_into()?))),
+                None => None,
+            },
+        )
+    }
+
+    pub(crate) fn read_dismissed_events1(&self, now: Unixtime) -> Result<Vec<Id>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<Id> = Vec::new();
+        for result in self.db_event_dismissed1()?.iter(&txn)? {
+            let (key, val) = result?;
+            let until = i64::from_be_bytes(val[..8].try_into()?);
+            if until > now.0 {
+                output.push(Id(key[..32].try_into()?));
+            }
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn remove_expired_dismissals1<'a>(
+        &'a self,
+        now: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<usize, Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<usize, Error> {
+            let mut expired: Vec<Vec<u8>> = Vec::new();
+            for result in self.db_event_dismissed1()?.iter(txn)? {
+                let (key, val) = result?;
+                let until = i64::from_be_bytes(val[..8].try_into()?);
+                if until <= now.0 {
+                    expired.push(key.to_owned());
+                }
+            }
+
+            // actual deletion done in second pass
+            // (deleting during interation does not work in LMDB)
+            for key in expired.iter() {
+                self.db_event_dismissed1()?.delete(txn, key)?;
+            }
+            Ok(expired.len())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn),
+            None => {
+                let mut txn = self.env.write_txn()?;
+                let count = f(&mut txn)?;
+                txn.commit()?;
+                Ok(count)
+            }
+        }
+    }
+}
@@ -97,4 +97,28 @@ impl Storage {
         }
         Ok(output)
     }
+
+    pub(crate) fn get_events_seen_on_relay1(
+        &self,
+        url: &RelayUrl,
+        since: Unixtime,
+    ) -> Result<Vec<(Id, Unixtime)>, Error> {
+        let mut url_bytes: Vec<u8> = url.as_str().as_bytes().to_owned();
+        url_bytes.truncate(MAX_LMDB_KEY - 32);
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Id, Unixtime)> = Vec::new();
+        // Keys start with the id, so we have to look at every record
+        for result in self.db_event_seen_on_relay1()?.iter(&txn)? {
+            let (key, val) = result?;
+            if key.len() < 32 || key[32..] != url_bytes[..] {
+                continue;
+            }
+            let time = Unixtime(i64::from_be_bytes(val[..8].try_into()?));
+            if time >= since {
+                let id = Id(key[..32].try_into()?);
+                output.push((id, time));
+            }
+        }
+        Ok(output)
+    }
 }
@@ -0,0 +1,237 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::UnalignedSlice;
+use heed::{RoTxn, RwTxn};
+use nostr_types::{Event, EventAddr, EventKind, Id, PublicKey, Unixtime};
+use sha2::Digest;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+// EventAddr:Unixtime:Id -> Id
+//   key: kind(u32 BE) | author.as_slice() | sha256(d) | created_at(u64 BE, sign flipped) | id.as_slice()
+//   val: id.as_slice() | Id(val[0..32].try_into()?)
+
+// NOTE: Every version of parameterized replaceable events that we have seen is kept
+// in the events table. This lists them by address, oldest first, so the last one
+// of an address is the latest and the others are superseded. The d tag is hashed
+// to keep keys within LMDB limits. created_at has its sign bit flipped so that
+// versions sort by time even from before 1970.
+
+static EVENT_VERSIONS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut EVENT_VERSIONS1_DB: Option<RawDatabase> = None;
+
+fn addr_key(kind: EventKind, author: PublicKey, d: &str) -> Vec<u8> {
+    let kind: u32 = kind.into();
+    let mut key: Vec<u8> = kind.to_be_bytes().to_vec();
+    key.extend(author.as_bytes());
+    key.extend(sha2::Sha256::digest(d.as_bytes()));
+    key
+}
+
+fn version_key(event: &Event) -> Vec<u8> {
+    let d = event.parameter().unwrap_or_default();
+    let mut key = addr_key(event.kind, event.pubkey, &d);
+    key.extend(encode_created_at(event.created_at));
+    key.extend(event.id.as_slice());
+    key
+}
+
+fn encode_created_at(created_at: Unixtime) -> [u8; 8] {
+    ((created_at.0 as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+fn decode_created_at(bytes: &[u8]) -> Result<Unixtime, Error> {
+    let bytes: [u8; 8] = bytes.try_into()?;
+    Ok(Unixtime((u64::from_be_bytes(bytes) ^ (1 << 63)) as i64))
+}
+
+impl Storage {
+    pub(super) fn db_event_versions1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = EVENT_VERSIONS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = EVENT_VERSIONS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = EVENT_VERSIONS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<UnalignedSlice<u8>, UnalignedSlice<u8>>()
+                    // no .flags needed
+                    .name("event_versions1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                EVENT_VERSIONS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_event_version1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = version_key(event);
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.db_event_versions1()?
+                .put(txn, &key, event.id.as_slice())?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// All versions, oldest first
+    pub(crate) fn get_event_versions1(&self, addr: &EventAddr) -> Result<Vec<Event>, Error> {
+        let start_key = addr_key(addr.kind, addr.author, &addr.d);
+        let mut ids: Vec<Id> = Vec::new();
+        {
+            let txn = self.env.read_txn()?;
+            for result in self.db_event_versions1()?.prefix_iter(&txn, &start_key)? {
+                let (_key, val) = result?;
+                ids.push(Id(val[0..32].try_into()?));
+            }
+        }
+
+        let mut output: Vec<Event> = Vec::new();
+        for id in ids {
+            if let Some(event) = self.read_event(id)? {
+                output.push(event);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Whether a newer version of this parameterized replaceable event has been seen
+    pub(crate) fn is_superseded_version1(
+        &self,
+        txn: &RoTxn<'_>,
+        event: &Event,
+    ) -> Result<bool, Error> {
+        if !event.kind.is_parameterized_replaceable() {
+            return Ok(false);
+        }
+        let d = event.parameter().unwrap_or_default();
+        let start_key = addr_key(event.kind, event.pubkey, &d);
+        let mut latest: Option<Id> = None;
+        for result in self.db_event_versions1()?.prefix_iter(txn, &start_key)? {
+            let (_key, val) = result?;
+            latest = Some(Id(val[0..32].try_into()?));
+        }
+        Ok(matches!(latest, Some(id) if id != event.id))
+    }
+
+    pub(crate) fn delete_event_version1<'a>(
+        &'a self,
+        event: &Event,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        self.db_event_versions1()?
+            .delete(txn, &version_key(event))?;
+        Ok(())
+    }
+
+    pub(crate) fn delete_event_versions1<'a>(
+        &'a self,
+        addr: &EventAddr,
+        until: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let start_key = addr_key(addr.kind, addr.author, &addr.d);
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // save the actual keys to delete
+            let mut deletions: Vec<(Vec<u8>, Id)> = Vec::new();
+            for result in self.db_event_versions1()?.prefix_iter(txn, &start_key)? {
+                let (key, val) = result?;
+                if decode_created_at(&key[68..76])? <= until {
+                    deletions.push((key.to_owned(), Id(val[0..32].try_into()?)));
+                }
+            }
+
+            // actual deletion done in second pass
+            // (deleting during interation does not work in LMDB)
+            for (key, id) in deletions.drain(..) {
+                self.db_event_versions1()?.delete(txn, &key)?;
+                self.delete_event(id, Some(&mut *txn))?;
+            }
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_event_versions_of1<'a>(
+        &'a self,
+        ids: &HashSet<Id>,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<usize, Error> {
+        // save the actual keys to delete
+        let mut deletions: Vec<Vec<u8>> = Vec::new();
+        for result in self.db_event_versions1()?.iter(txn)? {
+            let (key, _val) = result?;
+            let id = Id(key[76..108].try_into()?);
+            if ids.contains(&id) {
+                deletions.push(key.to_owned());
+            }
+        }
+
+        // actual deletion done in second pass
+        // (deleting during interation does not work in LMDB)
+        for deletion in deletions.iter() {
+            self.db_event_versions1()?.delete(txn, deletion)?;
+        }
+        Ok(deletions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_created_at_order() {
+        let times = [i64::MIN, -86400, -1, 0, 1, 1700000000, i64::MAX];
+        for pair in times.windows(2) {
+            assert!(
+                encode_created_at(Unixtime(pair[0])) < encode_created_at(Unixtime(pair[1])),
+                "{} should sort before {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        for t in times {
+            let encoded = encode_created_at(Unixtime(t));
+            assert_eq!(decode_created_at(&encoded).unwrap(), Unixtime(t));
+        }
+    }
+}
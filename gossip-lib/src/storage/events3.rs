@@ -10,6 +10,8 @@ use std::sync::Mutex;
 //   key: id.as_slice() | Id(val[0..32].try_into()?)
 //   val: event.write_to_vec() | Event::read_from_buffer(val)
 
+pub(super) const EVENTS3_DB_NAME: &str = "events3";
+
 static EVENTS3_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
 static mut EVENTS3_DB: Option<RawDatabase> = None;
 
@@ -35,7 +37,7 @@ impl Storage {
                     .database_options()
                     .types::<UnalignedSlice<u8>, UnalignedSlice<u8>>()
                     // no .flags needed
-                    .name("events3")
+                    .name(EVENTS3_DB_NAME)
                     .create(&mut txn)?;
                 txn.commit()?;
                 EVENTS3_DB = Some(db);
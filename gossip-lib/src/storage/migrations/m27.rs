@@ -0,0 +1,44 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::Event;
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m27_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_event_versions1()?;
+        Ok(())
+    }
+
+    pub(super) fn m27_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: recording versions of parameterized replaceable events...");
+
+        // Migrate
+        self.m27_record_event_versions(txn)?;
+
+        Ok(())
+    }
+
+    fn m27_record_event_versions<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let loop_txn = self.env.read_txn()?;
+        let mut count: usize = 0;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            let event = Event::read_from_buffer(val)?;
+            if event.kind.is_parameterized_replaceable() {
+                self.write_event_version1(&event, Some(txn))?;
+                count += 1;
+            }
+        }
+
+        tracing::info!("Recorded {} events", count);
+
+        Ok(())
+    }
+}
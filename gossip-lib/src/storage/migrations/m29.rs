@@ -0,0 +1,46 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{Id, Unixtime};
+
+impl Storage {
+    pub(super) fn m29_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_event_alerted1()?;
+        Ok(())
+    }
+
+    pub(super) fn m29_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Moving alerted events into their own table...");
+
+        // Migrate
+        self.m29_migrate_alerted_events(txn)?;
+
+        Ok(())
+    }
+
+    fn m29_migrate_alerted_events<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        const ALERTED_EVENTS: &[u8] = b"alerted_events";
+
+        let alerted: Vec<Id> = match self.general.get(txn, ALERTED_EVENTS)? {
+            Some(bytes) => serde_json::from_slice(bytes)?,
+            None => return Ok(()),
+        };
+
+        // We don't know when they matched, only in which order, so keep that
+        let now = Unixtime::now().unwrap();
+        let count = alerted.len();
+        for (i, id) in alerted.into_iter().enumerate() {
+            let at = Unixtime(now.0 - (count - i) as i64);
+            self.mark_event_alerted1(id, at, usize::MAX, Some(txn))?;
+        }
+
+        self.general.delete(txn, ALERTED_EVENTS)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m30_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_relationships_by_id1()?;
+        Ok(())
+    }
+
+    pub(super) fn m30_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: relating reposts and quotes to what they repost or quote...");
+
+        // Migrate
+        self.m30_rebuild_relationships(txn)?;
+
+        Ok(())
+    }
+
+    fn m30_rebuild_relationships<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // Events we already have get their repost and quote relationships when
+        // relationships are rebuilt
+        self.set_flag_rebuild_relationships_needed(true, Some(txn))?;
+        Ok(())
+    }
+}
@@ -25,7 +25,7 @@ impl Storage {
                 None => {
                     if 4 >= Self::MAX_MIGRATION_LEVEL {
                         // At migraiton level < 4 we know this is safe to do:
-                        crate::globals::GLOBALS.status_queue.write().write(
+                        crate::globals::GLOBALS.status_queue.write().write_warning(
                             "Settings missing or corrupted. We had to reset to defaults. Sorry about that."
                                 .to_owned(),
                         );
@@ -0,0 +1,148 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::Storage;
+use heed::RwTxn;
+use speedy::{Readable, Writable};
+
+// Settings are stored individually and fall back to their defaults when missing or
+// when they cannot be decoded. So renaming a setting, or changing its type, must come
+// with a settings migration or users silently lose their value.
+//
+// To change a setting:
+//   1. bump SETTINGS_VERSION
+//   2. add a `sN_migrate` function using rename_setting() or convert_setting()
+//   3. add it to migrate_settings_inner()
+
+impl Storage {
+    pub(crate) const SETTINGS_VERSION: u32 = 1;
+
+    pub(in crate::storage) fn migrate_settings(&self) -> Result<(), Error> {
+        let mut version = self.read_settings_version()?.unwrap_or(0);
+
+        if version > Self::SETTINGS_VERSION {
+            return Err(ErrorKind::General(format!(
+                "Settings version {} unknown: This client is older than your settings.",
+                version
+            ))
+            .into());
+        }
+
+        while version < Self::SETTINGS_VERSION {
+            version += 1;
+            let mut txn = self.env.write_txn()?;
+            self.migrate_settings_inner(version, &mut txn)?;
+            self.write_settings_version(version, Some(&mut txn))?;
+            txn.commit()?;
+        }
+
+        Ok(())
+    }
+
+    fn migrate_settings_inner<'a>(
+        &'a self,
+        version: u32,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        let prefix = format!("Settings Migration {}", version);
+        match version {
+            1 => self.s1_migrate(&prefix, txn)?,
+            _ => panic!("Unreachable settings version"),
+        };
+
+        tracing::info!("done.");
+
+        Ok(())
+    }
+
+    fn s1_migrate<'a>(&'a self, prefix: &str, _txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        // Settings before versioning are already in the current format
+        tracing::info!("{prefix}: versioning settings...");
+        Ok(())
+    }
+
+    /// Move a setting to a new key, keeping its value. Does nothing if the old key
+    /// is not set, and never overwrites a value already under the new key.
+    #[allow(dead_code)]
+    fn rename_setting<'a>(
+        &'a self,
+        old: &[u8],
+        new: &[u8],
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        let bytes = match self.general.get(txn, old)? {
+            Some(bytes) => bytes.to_owned(),
+            None => return Ok(()),
+        };
+        if self.general.get(txn, new)?.is_none() {
+            self.general.put(txn, new, &bytes)?;
+        }
+        self.general.delete(txn, old)?;
+        Ok(())
+    }
+
+    /// Change the type of a setting, converting its stored value. A value that cannot
+    /// be decoded as the old type is left alone (with a warning) rather than reset.
+    #[allow(dead_code)]
+    fn convert_setting<'a, Old, New, F>(
+        &'a self,
+        key: &[u8],
+        convert: F,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error>
+    where
+        Old: for<'b> Readable<'b, speedy::LittleEndian>,
+        New: Writable<speedy::LittleEndian>,
+        F: Fn(Old) -> New,
+    {
+        let bytes = match self.general.get(txn, key)? {
+            Some(bytes) => match convert_bytes(bytes, convert) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!(
+                        "Could not convert setting {}: {}",
+                        String::from_utf8_lossy(key),
+                        e
+                    );
+                    return Ok(());
+                }
+            },
+            None => return Ok(()),
+        };
+        self.general.put(txn, key, &bytes)?;
+        Ok(())
+    }
+}
+
+// Decode a stored setting value as `Old` and encode it again as `New`
+fn convert_bytes<Old, New, F>(bytes: &[u8], convert: F) -> Result<Vec<u8>, speedy::Error>
+where
+    Old: for<'b> Readable<'b, speedy::LittleEndian>,
+    New: Writable<speedy::LittleEndian>,
+    F: Fn(Old) -> New,
+{
+    let old = Old::read_from_buffer(bytes)?;
+    convert(old).write_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_bytes() {
+        let old = 7usize.write_to_vec().unwrap();
+        let new = convert_bytes::<usize, u32, _>(&old, |v| v as u32).unwrap();
+        assert_eq!(u32::read_from_buffer(&new).unwrap(), 7);
+
+        let old = 3600u64.write_to_vec().unwrap();
+        let new = convert_bytes::<u64, Option<u64>, _>(&old, |v| Some(v / 60)).unwrap();
+        assert_eq!(Option::<u64>::read_from_buffer(&new).unwrap(), Some(60));
+
+        let old = "dark".to_owned().write_to_vec().unwrap();
+        let new = convert_bytes::<String, bool, _>(&old, |v| v == "dark").unwrap();
+        assert!(bool::read_from_buffer(&new).unwrap());
+
+        // too short to be the old type
+        assert!(convert_bytes::<u64, u32, _>(&[1, 2], |v| v as u32).is_err());
+        assert!(convert_bytes::<String, bool, _>(&[], |v| v.is_empty()).is_err());
+    }
+}
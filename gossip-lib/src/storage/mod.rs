@@ -1,5 +1,17 @@
 const MAX_LMDB_KEY: usize = 511;
 
+// How many named databases the environment can hold
+const MAX_DBS: u32 = 32;
+
+// This has to be big enough for all the data.
+// Note that it is the size of the map in VIRTUAL address space,
+//   and that it doesn't all have to be paged in at the same time.
+// Some filesystem that doesn't handle sparse files may allocate all
+//   of this, so we don't go too crazy big.
+// NOTE: this cannot be a setting because settings are only available
+//       after the database has been launched.
+const MAP_SIZE: usize = 1048576 * 1024 * 24; // 24 GB
+
 macro_rules! key {
     ($slice:expr) => {
         if $slice.len() > 511 {
@@ -16,10 +28,13 @@ mod migrations;
 pub mod types;
 
 // database implementations
+mod event_alerted1;
+mod event_dismissed1;
 mod event_ek_c_index1;
 mod event_ek_pk_index1;
 mod event_seen_on_relay1;
 mod event_tag_index1;
+mod event_versions1;
 mod event_viewed1;
 mod events1;
 mod events2;
@@ -40,33 +55,86 @@ mod relationships_by_addr1;
 mod relationships_by_id1;
 mod relays1;
 mod relays2;
+mod relays3;
 mod reprel1;
 mod unindexed_giftwraps1;
 mod versioned;
 
+mod compaction;
+pub use compaction::CompactionReport;
+
+mod snapshot;
+pub use snapshot::Snapshot;
+
+mod stats;
+pub use stats::{KindStats, StorageStats, TableStats};
+
 use crate::dm_channel::{DmChannel, DmChannelData};
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
-use crate::nip46::{Nip46Server, Nip46UnconnectedServer};
+use crate::nip46::{Nip46Bunker, Nip46Server, Nip46UnconnectedServer};
 use crate::people::{Person, PersonList, PersonListMetadata};
 use crate::person_relay::PersonRelay;
 use crate::profile::Profile;
 use crate::relationship::{RelationshipByAddr, RelationshipById};
 use crate::relay::Relay;
+use crate::search::{SearchQuery, SearchScope};
+use dashmap::DashMap;
 use gossip_relay_picker::Direction;
 use heed::types::UnalignedSlice;
-use heed::{Database, Env, EnvFlags, EnvOpenOptions, RwTxn};
+use heed::{Database, Env, EnvFlags, EnvOpenOptions, RoTxn, RwTxn};
 use nostr_types::{
     EncryptedPrivateKey, Event, EventAddr, EventKind, EventReference, Id, MilliSatoshi, PublicKey,
-    RelayUrl, Unixtime,
+    PublicKeyHex, RelayUrl, Unixtime,
 };
 use paste::paste;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use speedy::{Readable, Writable};
 use std::collections::{HashMap, HashSet};
 use std::ops::Bound;
 
 use self::event_tag_index1::INDEXED_TAGS;
 
+/// Keys of the JSON documents kept in the general database, see
+/// [Storage::read_json]
+pub(crate) mod json_key {
+    /// When each synced setting last changed (see settings_sync.rs)
+    pub(crate) const SETTINGS_SYNC_STATE: &[u8] = b"settings_sync_state";
+
+    /// The relays people are followed only on (see relay_scope.rs)
+    pub(crate) const RELAY_SCOPES: &[u8] = b"relay_scopes";
+
+    /// The follow-back queue (see follow_back.rs)
+    pub(crate) const FOLLOW_BACK_STATE: &[u8] = b"follow_back_state";
+
+    /// The user's other identities (see identities.rs)
+    pub(crate) const OTHER_IDENTITIES: &[u8] = b"other_identities";
+
+    /// The read later queue (see read_later.rs)
+    pub(crate) const READ_LATER: &[u8] = b"read_later";
+
+    /// The alert rules (see alerts.rs)
+    pub(crate) const ALERT_RULES: &[u8] = b"alert_rules";
+
+    /// The wallet connection URI (NIP-47), encrypted to ourself (see nip47.rs)
+    pub(crate) const WALLET_CONNECTION: &[u8] = b"wallet_connection";
+
+    /// The delegations we issued (see delegation.rs)
+    pub(crate) const ISSUED_DELEGATIONS: &[u8] = b"issued_delegations";
+
+    /// The documents that portable backups carry
+    pub(crate) const BACKED_UP: [&[u8]; 7] = [
+        ALERT_RULES,
+        RELAY_SCOPES,
+        FOLLOW_BACK_STATE,
+        OTHER_IDENTITIES,
+        READ_LATER,
+        ISSUED_DELEGATIONS,
+        WALLET_CONNECTION,
+    ];
+}
+
 // Macro to define read-and-write into "general" database, largely for settings
 // The type must implemented Speedy Readable and Writable
 macro_rules! def_setting {
@@ -183,6 +251,9 @@ pub struct Storage {
 
     // General database (settings, local_settings)
     general: RawDatabase,
+
+    // Replies, reposts and quotes of events, as counted from their relationships
+    engagement_counts: DashMap<Id, (usize, usize, usize)>,
 }
 
 impl Storage {
@@ -192,18 +263,11 @@ impl Storage {
             builder.flags(EnvFlags::NO_TLS);
         }
         // builder.max_readers(126); // this is the default
-        builder.max_dbs(32);
-
-        // This has to be big enough for all the data.
-        // Note that it is the size of the map in VIRTUAL address space,
-        //   and that it doesn't all have to be paged in at the same time.
-        // Some filesystem that doesn't handle sparse files may allocate all
-        //   of this, so we don't go too crazy big.
-        // NOTE: this cannot be a setting because settings are only available
-        //       after the database has been launched.
-        builder.map_size(1048576 * 1024 * 24); // 24 GB
+        builder.max_dbs(MAX_DBS);
+        builder.map_size(MAP_SIZE);
 
         let dir = Profile::current()?.lmdb_dir;
+        compaction::swap_in_compacted(&dir)?;
         let env = match builder.open(&dir) {
             Ok(env) => env,
             Err(e) => {
@@ -221,7 +285,11 @@ impl Storage {
 
         txn.commit()?;
 
-        Ok(Storage { env, general })
+        Ok(Storage {
+            env,
+            general,
+            engagement_counts: DashMap::new(),
+        })
     }
 
     /// Run this after GLOBALS lazy static initialisation, so functions within storage can
@@ -233,11 +301,14 @@ impl Storage {
         //
         // old-version databases will be handled by their migration code and only
         // triggered into existence if their migration is necessary.
+        let _ = self.db_event_alerted()?;
+        let _ = self.db_event_dismissed()?;
         let _ = self.db_event_ek_c_index()?;
         let _ = self.db_event_ek_pk_index()?;
         let _ = self.db_event_tag_index()?;
         let _ = self.db_events()?;
         let _ = self.db_event_seen_on_relay()?;
+        let _ = self.db_event_versions()?;
         let _ = self.db_event_viewed()?;
         let _ = self.db_hashtags()?;
         let _ = self.db_nip46servers()?;
@@ -255,6 +326,7 @@ impl Storage {
             Some(level) => self.migrate(level)?,
             None => self.init_from_empty()?,
         }
+        self.migrate_settings()?;
 
         Ok(())
     }
@@ -272,6 +344,15 @@ impl Storage {
         Ok(())
     }
 
+    /// Write a compacted copy of the database to a new file. This is safe to
+    /// do while gossip is running.
+    pub fn copy_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), Error> {
+        let _ = self
+            .env
+            .copy_to_file(path, heed::CompactionOption::Enabled)?;
+        Ok(())
+    }
+
     // Database getters ---------------------------------
 
     #[inline]
@@ -299,11 +380,26 @@ impl Storage {
         self.db_event_seen_on_relay1()
     }
 
+    #[inline]
+    pub(crate) fn db_event_versions(&self) -> Result<RawDatabase, Error> {
+        self.db_event_versions1()
+    }
+
     #[inline]
     pub(crate) fn db_event_viewed(&self) -> Result<RawDatabase, Error> {
         self.db_event_viewed1()
     }
 
+    #[inline]
+    pub(crate) fn db_event_dismissed(&self) -> Result<RawDatabase, Error> {
+        self.db_event_dismissed1()
+    }
+
+    #[inline]
+    pub(crate) fn db_event_alerted(&self) -> Result<RawDatabase, Error> {
+        self.db_event_alerted1()
+    }
+
     #[inline]
     pub(crate) fn db_hashtags(&self) -> Result<RawDatabase, Error> {
         self.db_hashtags1()
@@ -336,7 +432,7 @@ impl Storage {
 
     #[inline]
     pub(crate) fn db_relays(&self) -> Result<RawDatabase, Error> {
-        self.db_relays2()
+        self.db_relays3()
     }
 
     #[inline]
@@ -374,6 +470,18 @@ impl Storage {
         self.get_event_viewed1_len()
     }
 
+    /// The number of records in the event_dismissed table
+    #[inline]
+    pub fn get_event_dismissed_len(&self) -> Result<u64, Error> {
+        self.get_event_dismissed1_len()
+    }
+
+    /// The number of records in the event_alerted table
+    #[inline]
+    pub fn get_event_alerted_len(&self) -> Result<u64, Error> {
+        self.get_event_alerted1_len()
+    }
+
     /// The number of records in the hashtags table
     pub fn get_hashtags_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
@@ -389,7 +497,7 @@ impl Storage {
     /// The number of records in the relays table
     #[inline]
     pub fn get_relays_len(&self) -> Result<u64, Error> {
-        self.get_relays2_len()
+        self.get_relays3_len()
     }
 
     /// The number of records in the event table
@@ -451,8 +559,47 @@ impl Storage {
     // Prune -------------------------------------------------------
 
     /// Remove all events (and related data) with a created_at before `from`
-    /// and all related indexes.
+    /// and all related indexes, except those the retention policy keeps
+    /// (see [prunable_events](Self::prunable_events)). Versions of replaceable
+    /// events that have been replaced are events too and go the same way.
     pub fn prune(&self, from: Unixtime) -> Result<usize, Error> {
+        let ids = self.prunable_events(from)?;
+        self.delete_events_and_indexes(&ids)
+    }
+
+    // The authors whose events pruning keeps: us, and with the
+    // `prune_keep_followed` setting the people we follow
+    fn retained_authors(&self) -> HashSet<PublicKey> {
+        let mut authors: HashSet<PublicKey> = if self.read_setting_prune_keep_followed() {
+            GLOBALS.people.get_subscribed_pubkeys().drain(..).collect()
+        } else {
+            HashSet::new()
+        };
+        if let Some(us) = self.read_setting_public_key() {
+            authors.insert(us);
+        }
+        authors
+    }
+
+    /// The Ids of events with a created_at before `from` that the retention
+    /// policy lets go. Our own events are always kept, and so are events we
+    /// bookmarked or queued to read later. Events of people we follow are kept
+    /// too with the `prune_keep_followed` setting.
+    pub fn prunable_events(&self, from: Unixtime) -> Result<HashSet<Id>, Error> {
+        let us = self.read_setting_public_key();
+        let retained = self.retained_authors();
+
+        let mut kept: HashSet<Id> = crate::read_later::items().iter().map(|i| i.id).collect();
+        if let Some(us) = us {
+            if let Some(bookmarks) = self.get_replaceable_event(EventKind::BookmarkList, us, "")? {
+                for tag in &bookmarks.tags {
+                    if let Ok((id, _, _)) = tag.parse_event() {
+                        kept.insert(id);
+                    }
+                }
+            }
+        }
+
         // Extract the Ids to delete.
         let txn = self.env.read_txn()?;
         let mut ids: HashSet<Id> = HashSet::new();
@@ -461,22 +608,28 @@ impl Storage {
 
             if let Some(created_at) = Event::get_created_at_from_speedy_bytes(val) {
                 if created_at < from {
-                    if let Some(id) = Event::get_id_from_speedy_bytes(val) {
-                        ids.insert(id);
-                        // Too bad but we can't delete it now, other threads
-                        // might try to access it still. We have to delete it from
-                        // all the other maps first.
+                    let event = Event::read_from_buffer(val)?;
+                    if retained.contains(&event.pubkey) || kept.contains(&event.id) {
+                        continue;
                     }
+                    ids.insert(event.id);
+                    // Too bad but we can't delete it now, other threads
+                    // might try to access it still. We have to delete it from
+                    // all the other maps first.
                 }
             }
         }
-        drop(txn);
 
+        Ok(ids)
+    }
+
+    /// Remove the given events and all related indexes
+    pub fn delete_events_and_indexes(&self, ids: &HashSet<Id>) -> Result<usize, Error> {
         let mut txn = self.env.write_txn()?;
 
         // Delete from event_seen_on_relay
         let mut deletions: Vec<Vec<u8>> = Vec::new();
-        for id in &ids {
+        for id in ids {
             let start_key: &[u8] = id.as_slice();
             for result in self
                 .db_event_seen_on_relay()?
@@ -495,11 +648,27 @@ impl Storage {
         }
 
         // Delete from event_viewed
-        for id in &ids {
+        for id in ids {
             let _ = self.db_event_viewed()?.delete(&mut txn, id.as_slice());
         }
         tracing::info!("PRUNE: deleted {} records from event_viewed", ids.len());
 
+        // Delete from event_dismissed
+        for id in ids {
+            let _ = self.db_event_dismissed()?.delete(&mut txn, id.as_slice());
+        }
+        tracing::info!("PRUNE: deleted {} records from event_dismissed", ids.len());
+
+        // Delete from event_alerted
+        for id in ids {
+            let _ = self.db_event_alerted()?.delete(&mut txn, id.as_slice());
+        }
+        tracing::info!("PRUNE: deleted {} records from event_alerted", ids.len());
+
+        // Delete from event_versions
+        let versions = self.delete_event_versions_of1(ids, &mut txn)?;
+        tracing::info!("PRUNE: deleted {} records from event_versions", versions);
+
         // Delete from hashtags
         // (unfortunately since Ids are the values, we have to scan the whole thing)
         let mut deletions: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
@@ -537,16 +706,102 @@ impl Storage {
         }
 
         // delete from events
-        for id in &ids {
+        for id in ids {
             let _ = self.db_events()?.delete(&mut txn, id.as_slice());
         }
         tracing::info!("PRUNE: deleted {} records from events", ids.len());
 
         txn.commit()?;
 
+        // Counts may include what was deleted
+        self.engagement_counts.clear();
+
         Ok(ids.len())
     }
 
+    /// Remove all events that have expired (NIP-40) as of `now`
+    pub fn prune_expired(&self, now: Unixtime) -> Result<usize, Error> {
+        let txn = self.env.read_txn()?;
+        let mut ids: Vec<Id> = Vec::new();
+        for result in self.db_events()?.iter(&txn)? {
+            let (_key, val) = result?;
+            let event = Event::read_from_buffer(val)?;
+            if crate::nip40::is_expired(&event, now) {
+                ids.push(event.id);
+            }
+        }
+        drop(txn);
+
+        let mut txn = self.env.write_txn()?;
+        for id in &ids {
+            self.delete_event(*id, Some(&mut txn))?;
+        }
+        txn.commit()?;
+
+        tracing::info!("PRUNE: deleted {} expired events", ids.len());
+
+        Ok(ids.len())
+    }
+
+    // Maintenance -------------------------------------------------------
+
+    /// Call `f` with every stored event. Returns how many there were.
+    pub fn for_each_event<F>(&self, mut f: F) -> Result<usize, Error>
+    where
+        F: FnMut(Event) -> Result<(), Error>,
+    {
+        let txn = self.env.read_txn()?;
+        let mut count: usize = 0;
+        for result in self.db_events()?.iter(&txn)? {
+            let (_key, val) = result?;
+            f(Event::read_from_buffer(val)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Check every stored event: that it decodes, that it is stored under its
+    /// own id, and that its signature is valid. Returns how many records were
+    /// checked and a description of each problem found.
+    pub fn verify_events(&self) -> Result<(usize, Vec<String>), Error> {
+        let txn = self.env.read_txn()?;
+        let mut count: usize = 0;
+        let mut problems: Vec<String> = Vec::new();
+        for result in self.db_events()?.iter(&txn)? {
+            let (key, val) = result?;
+            count += 1;
+            let key_id = match <[u8; 32]>::try_from(key) {
+                Ok(bytes) => Id(bytes),
+                Err(_) => {
+                    problems.push(format!("Record with a bad key of {} bytes", key.len()));
+                    continue;
+                }
+            };
+            let event = match Event::read_from_buffer(val) {
+                Ok(event) => event,
+                Err(e) => {
+                    problems.push(format!(
+                        "{}: does not decode: {}",
+                        key_id.as_hex_string(),
+                        e
+                    ));
+                    continue;
+                }
+            };
+            if event.id != key_id {
+                problems.push(format!(
+                    "{}: stored under the id of {}",
+                    event.id.as_hex_string(),
+                    key_id.as_hex_string()
+                ));
+            }
+            if let Err(e) = event.verify(None) {
+                problems.push(format!("{}: invalid: {}", event.id.as_hex_string(), e));
+            }
+        }
+        Ok((count, problems))
+    }
+
     // General key-value functions --------------------------------------------------
 
     pub(crate) fn write_migration_level<'a>(
@@ -583,6 +838,40 @@ impl Storage {
             .map(|bytes| u32::from_be_bytes(bytes[..4].try_into().unwrap())))
     }
 
+    pub(crate) fn write_settings_version<'a>(
+        &'a self,
+        settings_version: u32,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = settings_version.to_be_bytes();
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            Ok(self.general.put(txn, b"settings_version", &bytes)?)
+        };
+
+        match rw_txn {
+            Some(txn) => {
+                f(txn)?;
+            }
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn read_settings_version(&self) -> Result<Option<u32>, Error> {
+        let txn = self.env.read_txn()?;
+
+        Ok(self
+            .general
+            .get(&txn, b"settings_version")?
+            .map(|bytes| u32::from_be_bytes(bytes[..4].try_into().unwrap())))
+    }
+
     /// Write the user's encrypted private key
     pub fn write_encrypted_private_key<'a>(
         &'a self,
@@ -621,6 +910,66 @@ impl Storage {
         }
     }
 
+    /// Write a value into the general database as JSON, under one of the
+    /// [json_key]s
+    pub(crate) fn write_json<'a, T: Serialize + ?Sized>(
+        &'a self,
+        key: &[u8],
+        value: &T,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let json = serde_json::to_vec(value)?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, key, &json)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Read a value written by [Storage::write_json]
+    pub(crate) fn read_json<T: DeserializeOwned>(&self, key: &[u8]) -> Result<Option<T>, Error> {
+        let txn = self.env.read_txn()?;
+
+        match self.general.get(&txn, key)? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(serde_json::from_slice(bytes)?)),
+        }
+    }
+
+    /// Remove a value written by [Storage::write_json]
+    pub(crate) fn delete_json<'a>(
+        &'a self,
+        key: &[u8],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.delete(txn, key)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
     /// Write NIP-46 unconnected server
     #[allow(dead_code)]
     pub fn write_nip46_unconnected_server<'a>(
@@ -683,6 +1032,127 @@ impl Storage {
         Ok(())
     }
 
+    /// Write the NIP-46 remote signer (bunker) we sign with
+    pub fn write_nip46_bunker<'a>(
+        &'a self,
+        bunker: &Nip46Bunker,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = bunker.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, b"nip46_bunker", &bytes)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Read the NIP-46 remote signer (bunker) we sign with
+    pub fn read_nip46_bunker(&self) -> Result<Option<Nip46Bunker>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, b"nip46_bunker")? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(Nip46Bunker::read_from_buffer(bytes)?)),
+        }
+    }
+
+    /// Delete the NIP-46 remote signer (bunker) we sign with
+    pub fn delete_nip46_bunker<'a>(&'a self, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.delete(txn, b"nip46_bunker")?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Write our pending OpenTimestamps proofs (NIP-03), by the event they are for
+    pub fn write_pending_timestamps<'a>(
+        &'a self,
+        pending: &[(Id, Vec<u8>)],
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = pending.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, b"pending_timestamps", &bytes)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Read our pending OpenTimestamps proofs (NIP-03)
+    pub fn read_pending_timestamps(&self) -> Result<Vec<(Id, Vec<u8>)>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, b"pending_timestamps")? {
+            None => Ok(vec![]),
+            Some(bytes) => Ok(Vec::<(Id, Vec<u8>)>::read_from_buffer(bytes)?),
+        }
+    }
+
+    /// Write when the database was last pruned
+    pub fn write_last_prune<'a>(
+        &'a self,
+        when: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = when.0.write_to_vec()?;
+
+        let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            self.general.put(txn, b"last_prune", &bytes)?;
+            Ok(())
+        };
+
+        match rw_txn {
+            Some(txn) => f(txn)?,
+            None => {
+                let mut txn = self.env.write_txn()?;
+                f(&mut txn)?;
+                txn.commit()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Read when the database was last pruned, if ever
+    pub fn read_last_prune(&self) -> Result<Option<Unixtime>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.general.get(&txn, b"last_prune")? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(Unixtime(i64::read_from_buffer(bytes)?))),
+        }
+    }
+
     // Flags ------------------------------------------------------------
 
     def_flag!(following_only, b"following_only", false);
@@ -700,7 +1170,23 @@ impl Storage {
     def_setting!(public_key, b"public_key", Option::<PublicKey>, None);
     def_setting!(log_n, b"log_n", u8, 18);
     def_setting!(login_at_startup, b"login_at_startup", bool, true);
+    def_setting!(settings_sync, b"settings_sync", bool, false);
+    def_setting!(
+        settings_sync_excluded,
+        b"settings_sync_excluded",
+        Vec<String>,
+        Vec::new()
+    );
     def_setting!(offline, b"offline", bool, false);
+    def_setting!(use_proxy, b"use_proxy", bool, false);
+    def_setting!(proxy_host, b"proxy_host", String, "127.0.0.1".to_owned());
+    def_setting!(proxy_port, b"proxy_port", u16, 9050);
+    def_setting!(
+        proxy_direct_relays,
+        b"proxy_direct_relays",
+        String,
+        "".to_owned()
+    );
     def_setting!(load_avatars, b"load_avatars", bool, true);
     def_setting!(load_media, b"load_media", bool, true);
     def_setting!(check_nip05, b"check_nip05", bool, true);
@@ -735,6 +1221,10 @@ impl Storage {
     def_setting!(overlap, b"overlap", u64, 300);
     def_setting!(reposts, b"reposts", bool, true);
     def_setting!(show_long_form, b"show_long_form", bool, false);
+    def_setting!(show_git_events, b"show_git_events", bool, false);
+    def_setting!(show_torrents, b"show_torrents", bool, true);
+    def_setting!(show_live_activities, b"show_live_activities", bool, true);
+    def_setting!(show_calendar_events, b"show_calendar_events", bool, true);
     def_setting!(show_mentions, b"show_mentions", bool, true);
     def_setting!(direct_messages, b"direct_messages", bool, true);
     def_setting!(
@@ -744,8 +1234,11 @@ impl Storage {
         60 * 15
     );
     def_setting!(hide_mutes_entirely, b"hide_mutes_entirely", bool, true);
+    def_setting!(mute_list_sync, b"mute_list_sync", bool, false);
     def_setting!(reactions, b"reactions", bool, true);
     def_setting!(enable_zap_receipts, b"enable_zap_receipts", bool, true);
+    def_setting!(show_user_status, b"show_user_status", bool, true);
+    def_setting!(verify_timestamps, b"verify_timestamps", bool, false);
     def_setting!(show_media, b"show_media", bool, true);
     def_setting!(
         approve_content_warning,
@@ -757,8 +1250,28 @@ impl Storage {
     def_setting!(pow, b"pow", u8, 0);
     def_setting!(set_client_tag, b"set_client_tag", bool, false);
     def_setting!(set_user_agent, b"set_user_agent", bool, false);
+    def_setting!(
+        relay_list_auto_advertise,
+        b"relay_list_auto_advertise",
+        bool,
+        true
+    );
+    def_setting!(
+        web_link_base,
+        b"web_link_base",
+        String,
+        "https://njump.me/".to_owned()
+    );
+    def_setting!(media_server, b"media_server", String, "".to_owned());
+    def_setting!(
+        follow_import_directories,
+        b"follow_import_directories",
+        String,
+        "".to_owned()
+    );
     def_setting!(delegatee_tag, b"delegatee_tag", String, String::new());
     def_setting!(max_fps, b"max_fps", u32, 12);
+    def_setting!(memory_budget_mb, b"memory_budget_mb", u32, 0);
     def_setting!(
         recompute_feed_periodically,
         b"recompute_feed_periodically",
@@ -796,6 +1309,28 @@ impl Storage {
     );
     def_setting!(inertial_scrolling, b"inertial_scrolling", bool, true);
     def_setting!(mouse_acceleration, b"mouse_acceleration", f32, 1.0);
+    def_setting!(language, b"language", String, String::new());
+    def_setting!(relative_timestamps, b"relative_timestamps", bool, true);
+    def_setting!(timestamps_24_hour, b"timestamps_24_hour", bool, true);
+    def_setting!(
+        timezone_offset_minutes,
+        b"timezone_offset_minutes",
+        Option::<i16>,
+        None
+    );
+    def_setting!(sound_on_mention, b"sound_on_mention", bool, false);
+    def_setting!(sound_on_dm, b"sound_on_dm", bool, false);
+    def_setting!(sound_on_zap, b"sound_on_zap", bool, false);
+    def_setting!(
+        sound_file_mention,
+        b"sound_file_mention",
+        String,
+        "".to_owned()
+    );
+    def_setting!(sound_file_dm, b"sound_file_dm", String, "".to_owned());
+    def_setting!(sound_file_zap, b"sound_file_zap", String, "".to_owned());
+    def_setting!(sound_quiet_start_hour, b"sound_quiet_start_hour", u8, 0);
+    def_setting!(sound_quiet_end_hour, b"sound_quiet_end_hour", u8, 0);
     def_setting!(
         relay_list_becomes_stale_hours,
         b"relay_list_becomes_stale_hours",
@@ -906,14 +1441,71 @@ impl Storage {
         usize,
         10
     );
+    def_setting!(
+        fetcher_nip98_hosts,
+        b"fetcher_nip98_hosts",
+        String,
+        "".to_owned()
+    );
+    def_setting!(log_to_file, b"log_to_file", bool, false);
+    def_setting!(log_filter, b"log_filter", String, "info".to_owned());
+    def_setting!(log_file_max_size_kb, b"log_file_max_size_kb", u64, 10240);
+    def_setting!(crash_reports, b"crash_reports", bool, false);
+    def_setting!(metrics_endpoint, b"metrics_endpoint", bool, false);
+    def_setting!(metrics_port, b"metrics_port", u16, 9736);
+    def_setting!(syndication_endpoint, b"syndication_endpoint", bool, false);
+    def_setting!(syndication_port, b"syndication_port", u16, 9737);
     def_setting!(prune_period_days, b"prune_period_days", u64, 90);
     def_setting!(cache_prune_period_days, b"cache_prune_period_days", u64, 90);
+    def_setting!(prune_keep_followed, b"prune_keep_followed", bool, true);
+    def_setting!(prune_automatically, b"prune_automatically", bool, false);
+    def_setting!(backup_enabled, b"backup_enabled", bool, false);
+    def_setting!(backup_interval_hours, b"backup_interval_hours", u64, 24);
+    def_setting!(backup_keep, b"backup_keep", u32, 7);
+    def_setting!(backup_directory, b"backup_directory", String, "".to_owned());
+    def_setting!(backup_include_key, b"backup_include_key", bool, false);
     def_setting!(
         avoid_spam_on_unsafe_relays,
         b"avoid_spam_on_unsafe_relays",
         bool,
         false
     );
+    def_setting!(min_pow_unknown_authors, b"min_pow_unknown_authors", u8, 0);
+    def_setting!(exclude_labels, b"exclude_labels", String, "".to_owned());
+    def_setting!(include_labels, b"include_labels", String, "".to_owned());
+    def_setting!(mute_when_reporting, b"mute_when_reporting", bool, false);
+    def_setting!(hide_bridged_posts, b"hide_bridged_posts", bool, false);
+    def_setting!(follow_back_enabled, b"follow_back_enabled", bool, false);
+    def_setting!(
+        follow_back_automatically,
+        b"follow_back_automatically",
+        bool,
+        false
+    );
+    def_setting!(
+        follow_back_max_wot_distance,
+        b"follow_back_max_wot_distance",
+        u8,
+        2
+    );
+    def_setting!(
+        follow_back_require_nip05,
+        b"follow_back_require_nip05",
+        bool,
+        false
+    );
+    def_setting!(
+        follow_back_min_account_age_days,
+        b"follow_back_min_account_age_days",
+        u64,
+        30
+    );
+    def_setting!(
+        read_later_prefetch_media,
+        b"read_later_prefetch_media",
+        bool,
+        false
+    );
 
     // -------------------------------------------------------------------
 
@@ -1008,6 +1600,17 @@ impl Storage {
         self.get_event_seen_on_relay1(id)
     }
 
+    /// Get the events first or last seen on a relay since a time, and when.
+    /// This looks at every record, so it is slow.
+    #[inline]
+    pub fn get_events_seen_on_relay(
+        &self,
+        url: &RelayUrl,
+        since: Unixtime,
+    ) -> Result<Vec<(Id, Unixtime)>, Error> {
+        self.get_events_seen_on_relay1(url, since)
+    }
+
     /// Mark event viewed
     #[inline]
     pub fn mark_event_viewed<'a>(
@@ -1024,6 +1627,73 @@ impl Storage {
         self.is_event_viewed1(id)
     }
 
+    /// Dismiss an event, until a time or for ever
+    #[inline]
+    pub fn dismiss_event<'a>(
+        &'a self,
+        id: Id,
+        until: Option<Unixtime>,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.dismiss_event1(id, until, rw_txn)
+    }
+
+    /// Stop dismissing an event
+    #[inline]
+    pub fn undismiss_event<'a>(
+        &'a self,
+        id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.undismiss_event1(id, rw_txn)
+    }
+
+    /// Until when an event was dismissed, if it was (i64::MAX for ever). This
+    /// may be in the past.
+    #[inline]
+    pub fn read_event_dismissed_until(&self, id: Id) -> Result<Option<Unixtime>, Error> {
+        self.read_event_dismissed1(id)
+    }
+
+    /// The events that are dismissed now
+    #[inline]
+    pub fn read_dismissed_events(&self) -> Result<Vec<Id>, Error> {
+        self.read_dismissed_events1(Unixtime::now().unwrap())
+    }
+
+    /// Record that an event matched an alert rule, keeping only the `max` most
+    /// recent
+    #[inline]
+    pub(crate) fn mark_event_alerted<'a>(
+        &'a self,
+        id: Id,
+        max: usize,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.mark_event_alerted1(id, Unixtime::now().unwrap(), max, rw_txn)
+    }
+
+    /// When an event matched an alert rule, if it did
+    #[inline]
+    pub fn read_event_alerted(&self, id: Id) -> Result<Option<Unixtime>, Error> {
+        self.read_event_alerted1(id)
+    }
+
+    /// The events that matched alert rules, most recent match last
+    #[inline]
+    pub fn read_alerted_events(&self) -> Result<Vec<Id>, Error> {
+        self.read_alerted_events1()
+    }
+
+    /// Forget the dismissals that have expired, returning how many there were
+    #[inline]
+    pub fn remove_expired_dismissals<'a>(
+        &'a self,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<usize, Error> {
+        self.remove_expired_dismissals1(Unixtime::now().unwrap(), rw_txn)
+    }
+
     /// Associate a hashtag to an event
     #[inline]
     pub fn add_hashtag<'a>(
@@ -1052,7 +1722,7 @@ impl Storage {
         relay: &Relay,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.write_relay2(relay, rw_txn)
+        self.write_relay3(relay, rw_txn)
     }
 
     /// Delete a relay record
@@ -1063,7 +1733,7 @@ impl Storage {
         url: &RelayUrl,
         rw_txn: Option<&mut RwTxn<'a>>,
     ) -> Result<(), Error> {
-        self.delete_relay2(url, rw_txn)
+        self.delete_relay3(url, rw_txn)
     }
 
     /// Write a new relay record only if it is missing
@@ -1090,7 +1760,7 @@ impl Storage {
     where
         M: FnMut(&mut Relay),
     {
-        self.modify_relay2(url, modify, rw_txn)
+        self.modify_relay3(url, modify, rw_txn)
     }
 
     //// Modify all relay records
@@ -1103,13 +1773,13 @@ impl Storage {
     where
         M: FnMut(&mut Relay),
     {
-        self.modify_all_relays2(modify, rw_txn)
+        self.modify_all_relays3(modify, rw_txn)
     }
 
     /// Read a relay record
     #[inline]
     pub fn read_relay(&self, url: &RelayUrl) -> Result<Option<Relay>, Error> {
-        self.read_relay2(url)
+        self.read_relay3(url)
     }
 
     /// Read or create relay
@@ -1134,7 +1804,7 @@ impl Storage {
     where
         F: Fn(&Relay) -> bool,
     {
-        self.filter_relays2(f)
+        self.filter_relays3(f)
     }
 
     /// Process a relay list event
@@ -1327,6 +1997,17 @@ impl Storage {
     /// Delete the event
     pub fn delete_event<'a>(&'a self, id: Id, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
         let f = |txn: &mut RwTxn<'a>| -> Result<(), Error> {
+            // Delete from event_versions
+            let event = match self.db_events()?.get(txn, id.as_slice())? {
+                Some(bytes) => Some(Event::read_from_buffer(bytes)?),
+                None => None,
+            };
+            if let Some(event) = event {
+                if event.kind.is_parameterized_replaceable() {
+                    self.delete_event_version1(&event, txn)?;
+                }
+            }
+
             // Delete from the events table
             self.delete_event3(id, Some(txn))?;
 
@@ -1352,6 +2033,12 @@ impl Storage {
             // Delete from event_viewed
             self.db_event_viewed()?.delete(txn, id.as_slice())?;
 
+            // Delete from event_dismissed
+            self.db_event_dismissed()?.delete(txn, id.as_slice())?;
+
+            // Delete from event_alerted
+            self.db_event_alerted()?.delete(txn, id.as_slice())?;
+
             // DO NOT delete from relationships. The related event still applies in case
             // this event comes back, ESPECIALLY deletion relationships!
 
@@ -1394,19 +2081,28 @@ impl Storage {
             return Err(ErrorKind::General("Event is not replaceable.".to_owned()).into());
         }
 
-        let existing = self.find_events(
-            &[event.kind],
-            &[event.pubkey],
-            None,
-            |e| {
-                if event.kind.is_parameterized_replaceable() {
-                    e.parameter() == event.parameter()
-                } else {
-                    true
+        // Keep every version of parameterized replaceable events. The ones that
+        // have been replaced stay as history, and are left out of what we find.
+        if event.kind.is_parameterized_replaceable() {
+            let f = |txn: &mut RwTxn<'a>| -> Result<bool, Error> {
+                let had_it = self.db_events()?.get(txn, event.id.as_slice())?.is_some();
+                self.write_event_version1(event, Some(&mut *txn))?;
+                self.write_event(event, Some(&mut *txn))?;
+                Ok(!had_it && !self.is_superseded_version1(txn, event)?)
+            };
+
+            return match rw_txn {
+                Some(txn) => f(txn),
+                None => {
+                    let mut txn = self.env.write_txn()?;
+                    let latest = f(&mut txn)?;
+                    txn.commit()?;
+                    Ok(latest)
                 }
-            },
-            false,
-        )?;
+            };
+        }
+
+        let existing = self.find_events(&[event.kind], &[event.pubkey], None, |_| true, false)?;
 
         let mut found_newer = false;
         for old in existing {
@@ -1443,6 +2139,19 @@ impl Storage {
             return Err(ErrorKind::General("Event kind is not replaceable".to_owned()).into());
         }
 
+        // The latest version we have seen is normally the one we have
+        if kind.is_parameterized_replaceable() {
+            let addr = EventAddr {
+                d: parameter.to_owned(),
+                relays: vec![],
+                kind,
+                author: pubkey,
+            };
+            if let Some(latest) = self.get_event_versions1(&addr)?.pop() {
+                return Ok(Some(latest));
+            }
+        }
+
         Ok(self
             .find_events(
                 &[kind],
@@ -1461,10 +2170,37 @@ impl Storage {
             .cloned())
     }
 
+    /// Get every version of a parameterized replaceable event that we have seen,
+    /// latest first. Only the latest is found with other events, this is their history.
+    pub fn get_replaceable_event_versions(&self, addr: &EventAddr) -> Result<Vec<Event>, Error> {
+        if !addr.kind.is_parameterized_replaceable() {
+            return Err(ErrorKind::General(
+                "Event kind is not parameterized replaceable".to_owned(),
+            )
+            .into());
+        }
+
+        let mut versions = self.get_event_versions1(addr)?;
+        versions.reverse();
+        Ok(versions)
+    }
+
+    /// Forget versions of a parameterized replaceable event up to a time (e.g. when
+    /// they are deleted)
+    pub fn delete_replaceable_event_versions<'a>(
+        &'a self,
+        addr: &EventAddr,
+        until: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_event_versions1(addr, until, rw_txn)
+    }
+
     /// Find events of given kinds and pubkeys.
     /// You must supply kinds. You can skip the pubkeys and then only kinds will matter.
     fn find_ek_pk_events(
         &self,
+        txn: &RoTxn<'_>,
         kinds: &[EventKind],
         pubkeys: &[PublicKey],
     ) -> Result<HashSet<Id>, Error> {
@@ -1476,13 +2212,12 @@ impl Storage {
         }
 
         let mut ids: HashSet<Id> = HashSet::new();
-        let txn = self.env.read_txn()?;
 
         for kind in kinds {
             let ek: u32 = (*kind).into();
             if pubkeys.is_empty() {
                 let start_key = ek.to_be_bytes().as_slice().to_owned();
-                let iter = self.db_event_ek_pk_index()?.prefix_iter(&txn, &start_key)?;
+                let iter = self.db_event_ek_pk_index()?.prefix_iter(txn, &start_key)?;
                 for result in iter {
                     let (_key, val) = result?;
                     // Take the event
@@ -1493,7 +2228,7 @@ impl Storage {
                 for pubkey in pubkeys {
                     let mut start_key = ek.to_be_bytes().as_slice().to_owned();
                     start_key.extend(pubkey.as_bytes());
-                    let iter = self.db_event_ek_pk_index()?.prefix_iter(&txn, &start_key)?;
+                    let iter = self.db_event_ek_pk_index()?.prefix_iter(txn, &start_key)?;
                     for result in iter {
                         let (_key, val) = result?;
                         // Take the event
@@ -1508,7 +2243,12 @@ impl Storage {
     }
 
     /// Find events of given kinds and after the given time.
-    fn find_ek_c_events(&self, kinds: &[EventKind], since: Unixtime) -> Result<HashSet<Id>, Error> {
+    fn find_ek_c_events(
+        &self,
+        txn: &RoTxn<'_>,
+        kinds: &[EventKind],
+        since: Unixtime,
+    ) -> Result<HashSet<Id>, Error> {
         if kinds.is_empty() {
             return Err(ErrorKind::General(
                 "find_ek_c_events() requires some event kinds to be specified.".to_string(),
@@ -1518,7 +2258,6 @@ impl Storage {
 
         let now = Unixtime::now().unwrap();
         let mut ids: HashSet<Id> = HashSet::new();
-        let txn = self.env.read_txn()?;
 
         for kind in kinds {
             let ek: u32 = (*kind).into();
@@ -1527,7 +2266,7 @@ impl Storage {
             start_key.extend((i64::MAX - now.0).to_be_bytes().as_slice()); // work back from now
             end_key.extend((i64::MAX - since.0).to_be_bytes().as_slice()); // until since
             let range = (Bound::Included(&*start_key), Bound::Excluded(&*end_key));
-            let iter = self.db_event_ek_c_index()?.range(&txn, &range)?;
+            let iter = self.db_event_ek_c_index()?.range(txn, &range)?;
             for result in iter {
                 let (_key, val) = result?;
                 // Take the event
@@ -1559,16 +2298,33 @@ impl Storage {
     where
         F: Fn(&Event) -> bool,
     {
-        let ids = self.find_event_ids(kinds, pubkeys, since)?;
+        let txn = self.env.read_txn()?;
+        self.find_events_in_txn(&txn, kinds, pubkeys, since, f, sort)
+    }
+
+    /// Find events of interest, like [find_events](Self::find_events), within an
+    /// existing transaction
+    pub(crate) fn find_events_in_txn<F>(
+        &self,
+        txn: &RoTxn<'_>,
+        kinds: &[EventKind],
+        pubkeys: &[PublicKey],
+        since: Option<Unixtime>,
+        f: F,
+        sort: bool,
+    ) -> Result<Vec<Event>, Error>
+    where
+        F: Fn(&Event) -> bool,
+    {
+        let ids = self.find_event_ids_in_txn(txn, kinds, pubkeys, since)?;
 
         // Now that we have that Ids, fetch the events
-        let txn = self.env.read_txn()?;
         let mut events: Vec<Event> = Vec::new();
         for id in ids {
             // this is like self.read_event(), but we supply our existing transaction
-            if let Some(bytes) = self.db_events()?.get(&txn, id.as_slice())? {
+            if let Some(bytes) = self.db_events()?.get(txn, id.as_slice())? {
                 let event = Event::read_from_buffer(bytes)?;
-                if f(&event) {
+                if f(&event) && !self.is_superseded_version1(txn, &event)? {
                     events.push(event);
                 }
             }
@@ -1596,6 +2352,17 @@ impl Storage {
         kinds: &[EventKind],
         pubkeys: &[PublicKey],
         since: Option<Unixtime>,
+    ) -> Result<HashSet<Id>, Error> {
+        let txn = self.env.read_txn()?;
+        self.find_event_ids_in_txn(&txn, kinds, pubkeys, since)
+    }
+
+    fn find_event_ids_in_txn(
+        &self,
+        txn: &RoTxn<'_>,
+        kinds: &[EventKind],
+        pubkeys: &[PublicKey],
+        since: Option<Unixtime>,
     ) -> Result<HashSet<Id>, Error> {
         if kinds.is_empty() {
             return Err(ErrorKind::General(
@@ -1606,12 +2373,12 @@ impl Storage {
 
         // Get the Ids
         let ids = match (pubkeys.is_empty(), since) {
-            (true, None) => self.find_ek_pk_events(kinds, pubkeys)?,
-            (true, Some(when)) => self.find_ek_c_events(kinds, when)?,
-            (false, None) => self.find_ek_pk_events(kinds, pubkeys)?,
+            (true, None) => self.find_ek_pk_events(txn, kinds, pubkeys)?,
+            (true, Some(when)) => self.find_ek_c_events(txn, kinds, when)?,
+            (false, None) => self.find_ek_pk_events(txn, kinds, pubkeys)?,
             (false, Some(when)) => {
-                let group1 = self.find_ek_pk_events(kinds, pubkeys)?;
-                let group2 = self.find_ek_c_events(kinds, when)?;
+                let group1 = self.find_ek_pk_events(txn, kinds, pubkeys)?;
+                let group2 = self.find_ek_c_events(txn, kinds, when)?;
                 group1.intersection(&group2).copied().collect()
             }
         };
@@ -1622,13 +2389,79 @@ impl Storage {
     /// Search all events for the text, case insensitive. Both content and tags
     /// are searched.
     pub fn search_events(&self, text: &str) -> Result<Vec<Event>, Error> {
-        let event_kinds = crate::feed::feed_displayable_event_kinds(true);
+        let query = SearchQuery {
+            text: text.to_owned(),
+            ..Default::default()
+        };
+        self.search_events_by_query(&query)
+    }
+
+    /// Search events matching a `SearchQuery`. A thread scope walks the replies index,
+    /// and when the query names authors (or a person scope) or a since time, the event
+    /// indexes are used. Otherwise all events are scanned.
+    pub fn search_events_by_query(&self, query: &SearchQuery) -> Result<Vec<Event>, Error> {
+        let event_kinds = if query.kinds.is_empty() {
+            crate::feed::feed_displayable_event_kinds(true)
+        } else {
+            query.kinds.clone()
+        };
+
+        let re = if query.text.is_empty() {
+            None
+        } else {
+            let needle = regex::escape(query.text.to_lowercase().as_str());
+            Some(
+                regex::RegexBuilder::new(needle.as_str())
+                    .unicode(true)
+                    .case_insensitive(true)
+                    .build()?,
+            )
+        };
+
+        match &query.scope {
+            SearchScope::Thread(root) => {
+                let mut events: Vec<Event> = Vec::new();
+                let mut pending: Vec<Id> = vec![*root];
+                let mut seen: HashSet<Id> = HashSet::new();
+                while let Some(id) = pending.pop() {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    if let Some(event) = self.read_event(id)? {
+                        pending.extend(self.get_replies(&event)?);
+                        if event_kinds.contains(&event.kind) && query.matches(&event, re.as_ref()) {
+                            events.push(event);
+                        }
+                    }
+                }
+                events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+                return Ok(events);
+            }
+            SearchScope::Person(pubkey) => {
+                // Intersect with any from: authors
+                if !query.authors.is_empty() && !query.authors.contains(pubkey) {
+                    return Ok(vec![]);
+                }
+                return self.find_events(
+                    &event_kinds,
+                    &[*pubkey],
+                    query.since,
+                    |event| query.matches(event, re.as_ref()),
+                    true,
+                );
+            }
+            SearchScope::Everywhere => (),
+        }
 
-        let needle = regex::escape(text.to_lowercase().as_str());
-        let re = regex::RegexBuilder::new(needle.as_str())
-            .unicode(true)
-            .case_insensitive(true)
-            .build()?;
+        if !query.authors.is_empty() || query.since.is_some() {
+            return self.find_events(
+                &event_kinds,
+                &query.authors,
+                query.since,
+                |event| query.matches(event, re.as_ref()),
+                true,
+            );
+        }
 
         let txn = self.env.read_txn()?;
         let iter = self.db_events()?.iter(&txn)?;
@@ -1645,19 +2478,43 @@ impl Storage {
                 continue;
             }
 
+            let re = match &re {
+                Some(re) => re,
+                None => {
+                    let event = Event::read_from_buffer(val)?;
+                    if query.matches_operators(&event) {
+                        events.push(event);
+                    }
+                    continue;
+                }
+            };
+
             if let Some(content) = Event::get_content_from_speedy_bytes(val) {
                 if re.is_match(content.as_ref()) {
                     let event = Event::read_from_buffer(val)?;
-                    events.push(event);
+                    if query.matches_operators(&event) {
+                        events.push(event);
+                    }
                     continue;
                 }
             }
 
-            if Event::tag_search_in_speedy_bytes(val, &re)? {
+            if Event::tag_search_in_speedy_bytes(val, re)? {
                 let event = Event::read_from_buffer(val)?;
-                events.push(event);
+                if query.matches_operators(&event) {
+                    events.push(event);
+                }
+            }
+        }
+
+        // Versions that have been replaced are not results
+        let mut current: Vec<Event> = Vec::with_capacity(events.len());
+        for event in events.drain(..) {
+            if !self.is_superseded_version1(&txn, &event)? {
+                current.push(event);
             }
         }
+        events = current;
 
         events.sort_by(|a, b| {
             // ORDER created_at desc
@@ -1744,6 +2601,53 @@ impl Storage {
         self.write_event3_tag_index1(event, rw_txn)
     }
 
+    /// Find events of these kinds that tag any of these people, and pass the
+    /// filter. If authors is empty, they won't matter. If since is None, it
+    /// won't matter.
+    ///
+    /// Only 'p' tags of the gossip user are indexed. The tag index is used
+    /// when asking about just the user; otherwise every event of these kinds
+    /// (by these authors) is gone through.
+    pub fn find_events_tagging<F>(
+        &self,
+        kinds: &[EventKind],
+        authors: &[PublicKey],
+        since: Option<Unixtime>,
+        tagged: &[PublicKey],
+        f: F,
+        sort: bool,
+    ) -> Result<Vec<Event>, Error>
+    where
+        F: Fn(&Event) -> bool,
+    {
+        if tagged.is_empty() {
+            return Ok(vec![]);
+        }
+
+        if tagged.len() == 1 && Some(tagged[0]) == self.read_setting_public_key() {
+            let pkh: PublicKeyHex = tagged[0].into();
+            return self.find_tagged_events(
+                "p",
+                Some(pkh.as_str()),
+                |e| {
+                    kinds.contains(&e.kind)
+                        && (authors.is_empty() || authors.contains(&e.pubkey))
+                        && since.map(|since| e.created_at >= since).unwrap_or(true)
+                        && f(e)
+                },
+                sort,
+            );
+        }
+
+        self.find_events(
+            kinds,
+            authors,
+            since,
+            |e| tagged.iter().any(|pk| e.is_tagged(pk)) && f(e),
+            sort,
+        )
+    }
+
     /// Find events having a given tag, and passing the filter.
     /// Only some tags are indxed: "a", "d", "delegation", and "p" for the gossip user only
     pub fn find_tagged_events<F>(
@@ -1785,7 +2689,7 @@ impl Storage {
             // this is like self.read_event(), but we supply our existing transaction
             if let Some(bytes) = self.db_events()?.get(&txn, id.as_slice())? {
                 let event = Event::read_from_buffer(bytes)?;
-                if f(&event) {
+                if f(&event) && !self.is_superseded_version1(&txn, &event)? {
                     events.push(event);
                 }
             }
@@ -1916,9 +2820,11 @@ impl Storage {
         // Get the event (once self-reactions get deleted we can remove this)
         let maybe_target_event = self.read_event(id)?;
 
-        // Collect up to one reaction per pubkey
-        let mut phase1: HashMap<PublicKey, char> = HashMap::new();
-        for (_, rel) in self.find_relationships_by_id(id)? {
+        // Collect up to one reaction per pubkey. If someone reacted more than once,
+        // their latest reaction replaces the earlier ones, so a "-" (a downvote)
+        // takes back an earlier like.
+        let mut phase1: HashMap<PublicKey, (Id, char)> = HashMap::new();
+        for (reacting_id, rel) in self.find_relationships_by_id(id)? {
             if let RelationshipById::Reaction { by, reaction } = rel {
                 if let Some(target_event) = &maybe_target_event {
                     if target_event.pubkey == by {
@@ -1931,16 +2837,30 @@ impl Storage {
                 } else {
                     '+'
                 };
-                phase1.insert(by, symbol);
-                if Some(by) == GLOBALS.identity.public_key() {
-                    self_already_reacted = true;
+                if let Some((prior_id, _)) = phase1.get(&by) {
+                    let prior_time = self.read_event(*prior_id)?.map(|e| e.created_at);
+                    let this_time = self.read_event(reacting_id)?.map(|e| e.created_at);
+                    if this_time <= prior_time {
+                        continue;
+                    }
                 }
+                phase1.insert(by, (reacting_id, symbol));
             }
         }
 
-        // Collate by char
+        if let Some(pubkey) = GLOBALS.identity.public_key() {
+            if let Some((_, symbol)) = phase1.get(&pubkey) {
+                self_already_reacted = *symbol != '-';
+            }
+        }
+
+        // Collate by char. Custom emoji (":shortcode:") are counted by
+        // nip30::emoji_reactions instead.
         let mut output: HashMap<char, usize> = HashMap::new();
-        for (_, symbol) in phase1 {
+        for (_, (_, symbol)) in phase1 {
+            if symbol == ':' {
+                continue;
+            }
             output
                 .entry(symbol)
                 .and_modify(|count| *count += 1)
@@ -1963,6 +2883,55 @@ impl Storage {
         Ok(total)
     }
 
+    /// Returns the number of replies, reposts and quotes of an event. These are
+    /// kept once counted, until [Storage::forget_engagement_counts].
+    pub fn get_engagement_counts(&self, event: &Event) -> Result<(usize, usize, usize), Error> {
+        // Replies to replaceable events are by address, and so are not forgotten
+        // by id. Those are counted each time.
+        let cacheable = !event.kind.is_replaceable();
+        if cacheable {
+            if let Some(counts) = self.engagement_counts.get(&event.id) {
+                return Ok(*counts);
+            }
+        }
+
+        let mut replies: usize = 0;
+        let mut reposts: usize = 0;
+        let mut quotes: usize = 0;
+        for (_, rel) in self.find_relationships_by_id(event.id)? {
+            match rel {
+                RelationshipById::Reply => replies += 1,
+                RelationshipById::Repost => reposts += 1,
+                RelationshipById::Quote => quotes += 1,
+                _ => (),
+            }
+        }
+        if event.kind.is_replaceable() {
+            replies += self
+                .get_replaceable_replies(&EventAddr {
+                    d: event.parameter().unwrap_or_default(),
+                    relays: vec![],
+                    kind: event.kind,
+                    author: event.pubkey,
+                })?
+                .len();
+        }
+
+        if cacheable {
+            self.engagement_counts
+                .insert(event.id, (replies, reposts, quotes));
+        }
+        Ok((replies, reposts, quotes))
+    }
+
+    /// Forget the counted replies, reposts and quotes of these events, as they
+    /// have new relationships
+    pub(crate) fn forget_engagement_counts(&self, ids: &[Id]) {
+        for id in ids {
+            self.engagement_counts.remove(id);
+        }
+    }
+
     /// Get whether an event was deleted, and if so the optional reason
     pub fn get_deletions(&self, maybe_deleted_event: &Event) -> Result<Vec<String>, Error> {
         let mut reasons: Vec<String> = Vec::new();
@@ -1981,10 +2950,11 @@ impl Storage {
             }
         }
 
-        // Deletes via 'a tags (entire paramterized groups)
-        if let Some(parameter) = maybe_deleted_event.parameter() {
+        // Deletes via 'a tags, which delete every version of a replaceable
+        // (possibly parameterized) event up to the time of the deletion
+        if maybe_deleted_event.kind.is_replaceable() {
             let addr = EventAddr {
-                d: parameter,
+                d: maybe_deleted_event.parameter().unwrap_or_default(),
                 relays: vec![],
                 kind: maybe_deleted_event.kind,
                 author: maybe_deleted_event.pubkey,
@@ -1995,8 +2965,8 @@ impl Storage {
                     if maybe_deleted_event.delete_author_allowed(by) {
                         // We must have the deletion event to check it
                         if let Some(deleting_event) = self.read_event(deleting_id)? {
-                            // Delete must come after event in question
-                            if deleting_event.created_at > maybe_deleted_event.created_at {
+                            // Delete must not come before the version in question
+                            if deleting_event.created_at >= maybe_deleted_event.created_at {
                                 reasons.push(reason);
                             }
                         }
@@ -2209,6 +3179,7 @@ impl Storage {
                     }
                     dmcdata.message_count += 1;
                     dmcdata.unread_message_count += unread;
+                    dmcdata.legacy_message_count += 1;
                 } else {
                     map.insert(
                         dmchannel.clone(),
@@ -2221,6 +3192,7 @@ impl Storage {
                                 .ok(),
                             message_count: 1,
                             unread_message_count: unread,
+                            legacy_message_count: 1,
                         },
                     );
                 }
@@ -2248,6 +3220,7 @@ impl Storage {
                                 latest_message_content: Some(rumor_event.content.clone()),
                                 message_count: 1,
                                 unread_message_count: unread,
+                                legacy_message_count: 0,
                             },
                         );
                     }
@@ -16,6 +16,7 @@ use std::sync::Mutex;
 //     id2 reacts to id1
 //     id2 deletes id1
 //     id2 is a zap receipt on id1
+//     id2 reposts or quotes id1
 
 static RELATIONSHIPS_BY_ID1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
 static mut RELATIONSHIPS_BY_ID1_DB: Option<RawDatabase> = None;
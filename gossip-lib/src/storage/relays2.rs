@@ -44,6 +44,7 @@ impl Storage {
         }
     }
 
+    #[allow(dead_code)]
     pub(crate) fn get_relays2_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
         Ok(self.db_relays2()?.len(&txn)?)
@@ -81,6 +82,7 @@ impl Storage {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub(crate) fn delete_relay2<'a>(
         &'a self,
         url: &RelayUrl,
@@ -115,6 +117,7 @@ impl Storage {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub(crate) fn modify_relay2<'a, M>(
         &'a self,
         url: &RelayUrl,
@@ -153,6 +156,7 @@ impl Storage {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub(crate) fn modify_all_relays2<'a, M>(
         &'a self,
         mut modify: M,
@@ -189,6 +193,7 @@ impl Storage {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub(crate) fn read_relay2(&self, url: &RelayUrl) -> Result<Option<Relay2>, Error> {
         // Note that we use serde instead of speedy because the complexity of the
         // serde_json::Value type makes it difficult. Any other serde serialization
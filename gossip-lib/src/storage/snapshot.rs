@@ -0,0 +1,54 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::RoTxn;
+use nostr_types::{Event, EventKind, Id, PublicKey, Unixtime};
+use speedy::Readable;
+
+/// A consistent view of storage, held in one read transaction.
+///
+/// Computing a feed makes many reads. Done one transaction each, every read
+/// pays for opening a transaction and they may see different states of the
+/// database. A snapshot opens it once, and the events it finds all come from
+/// the same state. Writers are not held up by it, but it should not be kept
+/// for long, as pages it still sees cannot be reused until it is dropped.
+pub struct Snapshot<'a> {
+    storage: &'a Storage,
+    txn: RoTxn<'a>,
+    events: RawDatabase,
+}
+
+impl Storage {
+    /// Take a snapshot of storage for a batch of reads
+    pub fn snapshot(&self) -> Result<Snapshot<'_>, Error> {
+        Ok(Snapshot {
+            storage: self,
+            txn: self.env.read_txn()?,
+            events: self.db_events()?,
+        })
+    }
+}
+
+impl Snapshot<'_> {
+    /// Read an event
+    pub fn read_event(&self, id: Id) -> Result<Option<Event>, Error> {
+        match self.events.get(&self.txn, id.as_slice())? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(Event::read_from_buffer(bytes)?)),
+        }
+    }
+
+    /// Find events of interest, like [Storage::find_events], newest first
+    pub fn find_events<F>(
+        &self,
+        kinds: &[EventKind],
+        pubkeys: &[PublicKey],
+        since: Option<Unixtime>,
+        f: F,
+    ) -> Result<Vec<Event>, Error>
+    where
+        F: Fn(&Event) -> bool,
+    {
+        self.storage
+            .find_events_in_txn(&self.txn, kinds, pubkeys, since, f, true)
+    }
+}
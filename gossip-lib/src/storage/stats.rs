@@ -0,0 +1,121 @@
+use crate::error::Error;
+use crate::profile::Profile;
+use crate::storage::{RawDatabase, Storage};
+use nostr_types::{Event, EventKind, Unixtime};
+use speedy::Readable;
+use std::collections::HashMap;
+
+/// How much is stored in one table. Bytes count keys and values only, not
+/// the LMDB pages they sit in.
+#[derive(Debug, Clone)]
+pub struct TableStats {
+    pub name: &'static str,
+    pub records: u64,
+    pub bytes: u64,
+}
+
+/// How much is stored of one event kind
+#[derive(Debug, Clone)]
+pub struct KindStats {
+    pub kind: EventKind,
+    pub events: u64,
+    pub bytes: u64,
+}
+
+/// What the database holds, to decide what to prune
+#[derive(Debug, Clone)]
+pub struct StorageStats {
+    /// Bytes the database file takes on disk
+    pub file_size: u64,
+
+    pub tables: Vec<TableStats>,
+
+    /// Largest first
+    pub kinds: Vec<KindStats>,
+
+    /// The created_at of the oldest event
+    pub oldest_event: Option<Unixtime>,
+
+    /// The created_at of the newest event
+    pub newest_event: Option<Unixtime>,
+}
+
+impl Storage {
+    /// Count records and bytes per table and per event kind. This reads the
+    /// whole database, so it is slow on a large one.
+    pub fn get_stats(&self) -> Result<StorageStats, Error> {
+        let tables: Vec<(&'static str, RawDatabase)> = vec![
+            ("general", self.general),
+            ("events", self.db_events()?),
+            ("event_ek_pk_index", self.db_event_ek_pk_index()?),
+            ("event_ek_c_index", self.db_event_ek_c_index()?),
+            ("event_tag_index", self.db_event_tag_index()?),
+            ("event_seen_on_relay", self.db_event_seen_on_relay()?),
+            ("event_versions", self.db_event_versions()?),
+            ("event_viewed", self.db_event_viewed()?),
+            ("event_dismissed", self.db_event_dismissed()?),
+            ("event_alerted", self.db_event_alerted()?),
+            ("relationships_by_id", self.db_relationships_by_id()?),
+            ("relationships_by_addr", self.db_relationships_by_addr()?),
+            ("unindexed_giftwraps", self.db_unindexed_giftwraps()?),
+            ("hashtags", self.db_hashtags()?),
+            ("people", self.db_people()?),
+            ("person_relays", self.db_person_relays()?),
+            ("person_lists", self.db_person_lists()?),
+            ("person_lists_metadata", self.db_person_lists_metadata()?),
+            ("relays", self.db_relays()?),
+            ("nip46servers", self.db_nip46servers()?),
+        ];
+
+        let txn = self.env.read_txn()?;
+
+        let mut table_stats: Vec<TableStats> = Vec::with_capacity(tables.len());
+        for (name, db) in tables {
+            let mut records: u64 = 0;
+            let mut bytes: u64 = 0;
+            for result in db.iter(&txn)? {
+                let (key, val) = result?;
+                records += 1;
+                bytes += (key.len() + val.len()) as u64;
+            }
+            table_stats.push(TableStats {
+                name,
+                records,
+                bytes,
+            });
+        }
+
+        let mut kinds: HashMap<u32, KindStats> = HashMap::new();
+        let mut oldest_event: Option<Unixtime> = None;
+        let mut newest_event: Option<Unixtime> = None;
+        for result in self.db_events()?.iter(&txn)? {
+            let (key, val) = result?;
+            let event = Event::read_from_buffer(val)?;
+            let entry = kinds.entry(event.kind.into()).or_insert(KindStats {
+                kind: event.kind,
+                events: 0,
+                bytes: 0,
+            });
+            entry.events += 1;
+            entry.bytes += (key.len() + val.len()) as u64;
+            if oldest_event.map(|t| event.created_at < t).unwrap_or(true) {
+                oldest_event = Some(event.created_at);
+            }
+            if newest_event.map(|t| event.created_at > t).unwrap_or(true) {
+                newest_event = Some(event.created_at);
+            }
+        }
+        let mut kinds: Vec<KindStats> = kinds.into_values().collect();
+        kinds.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+        let file_size = std::fs::metadata(Profile::current()?.lmdb_dir.join("data.mdb"))?.len();
+
+        Ok(StorageStats {
+            file_size,
+            tables: table_stats,
+            kinds,
+            oldest_event,
+            newest_event,
+        })
+    }
+}
@@ -42,4 +42,17 @@ pub enum RelationshipById1 {
 
     // NIP-90 Data Vending Machines
     JobResult,
+
+    // NIP-18 Reposts
+    // (appended, as values already stored refer to variants by position)
+    Repost,
+
+    // NIP-18 Quote reposts
+    Quote,
+
+    // NIP-90 Data Vending Machines, our request about an event
+    JobRequest,
+
+    // NIP-90 Data Vending Machines
+    JobFeedback,
 }
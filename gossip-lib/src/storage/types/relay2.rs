@@ -59,6 +59,7 @@ impl Relay2 {
     pub const OUTBOX: u64 = 1 << 4; // 16          this is 'write' of kind 10002
     pub const DISCOVER: u64 = 1 << 5; // 32
     pub const SPAMSAFE: u64 = 1 << 6; // 64
+    pub const SEARCH: u64 = 1 << 7; // 128         full text search (NIP-50)
 
     pub fn new(url: RelayUrl) -> Self {
         Self {
@@ -100,7 +101,7 @@ impl Relay2 {
         if self.has_usage_bits(Self::OUTBOX) {
             output |= 1 << 3;
         }
-        // DISCOVER and SPAMSAFE shouldn't affect sort
+        // DISCOVER, SPAMSAFE and SEARCH shouldn't affect sort
         output
     }
 
@@ -0,0 +1,391 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, NostrUrl, PublicKey, Unixtime};
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How many entries a feed carries
+const MAX_ENTRIES: usize = 50;
+
+/// How far back feeds of many authors look
+const LOOKBACK_SECS: i64 = 60 * 60 * 24 * 30;
+
+/// Which events a feed carries
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Notes of the people we follow
+    Following,
+
+    /// Notes of a person
+    Person(PublicKey),
+
+    /// Notes with a hashtag
+    Hashtag(String),
+
+    /// Long-form articles of the people we follow
+    LongForm,
+
+    /// Long-form articles of a person
+    PersonLongForm(PublicKey),
+}
+
+/// The syndication format of a feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Atom,
+    Rss,
+}
+
+/// Start serving feeds as RSS and Atom on localhost, if enabled
+pub(crate) fn start() {
+    if !GLOBALS.storage.read_setting_syndication_endpoint() {
+        return;
+    }
+
+    let port = GLOBALS.storage.read_setting_syndication_port();
+    tokio::task::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Could not start feeds endpoint on port {}: {}", port, e);
+                return;
+            }
+        };
+        tracing::info!("Serving RSS and Atom feeds at http://127.0.0.1:{}/", port);
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Feeds endpoint: {}", e);
+                        continue;
+                    }
+                },
+                _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {
+                    if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+                        tracing::info!("Feeds endpoint shutting down.");
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            tokio::task::spawn(async move {
+                if let Err(e) = serve(stream).await {
+                    tracing::debug!("Feeds endpoint: {}", e);
+                }
+            });
+        }
+    });
+}
+
+async fn serve(mut stream: TcpStream) -> Result<(), Error> {
+    // We only need the request line
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let response = match parse_path(path) {
+        Some((source, format)) => {
+            // Reading storage blocks
+            let body = tokio::task::spawn_blocking(move || render(&source, format)).await??;
+            let content_type = match format {
+                Format::Atom => "application/atom+xml; charset=utf-8",
+                Format::Rss => "application/rss+xml; charset=utf-8",
+            };
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                content_type,
+                body.len(),
+                body
+            )
+        }
+        None if path == "/" => {
+            let body = index();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        None => {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned()
+        }
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn index() -> String {
+    "Feeds served by gossip. Use .atom or .rss:\n\n\
+     /following.atom - notes of people you follow\n\
+     /person/<npub or hex>.atom - notes of a person\n\
+     /hashtag/<hashtag>.atom - notes with a hashtag\n\
+     /longform.atom - long-form articles of people you follow\n\
+     /longform/<npub or hex>.atom - long-form articles of a person\n"
+        .to_owned()
+}
+
+/// Parse a feed path such as `/person/npub1....atom` or `/hashtag/nostr.rss`
+pub fn parse_path(path: &str) -> Option<(Source, Format)> {
+    let path = path.split('?').next()?.trim_start_matches('/');
+    let (path, format) = if let Some(p) = path.strip_suffix(".atom") {
+        (p, Format::Atom)
+    } else if let Some(p) = path.strip_suffix(".rss") {
+        (p, Format::Rss)
+    } else {
+        return None;
+    };
+
+    let source = match path.split_once('/') {
+        None if path == "following" => Source::Following,
+        None if path == "longform" => Source::LongForm,
+        Some(("person", who)) => Source::Person(parse_pubkey(who)?),
+        Some(("longform", who)) => Source::PersonLongForm(parse_pubkey(who)?),
+        Some(("hashtag", tag)) if !tag.is_empty() => {
+            Source::Hashtag(tag.trim_start_matches('#').to_owned())
+        }
+        _ => return None,
+    };
+
+    Some((source, format))
+}
+
+fn parse_pubkey(s: &str) -> Option<PublicKey> {
+    PublicKey::try_from_bech32_string(s, true)
+        .or_else(|_| PublicKey::try_from_hex_string(s, true))
+        .ok()
+}
+
+/// The events a feed carries, latest first
+pub fn events(source: &Source) -> Result<Vec<Event>, Error> {
+    let now = Unixtime::now().unwrap();
+    let since = Unixtime(now.0 - LOOKBACK_SECS);
+    let keep = |e: &Event| {
+        e.created_at <= now && !crate::nip40::is_expired(e, now) && e.replies_to().is_none()
+    };
+
+    let mut events = match source {
+        Source::Following | Source::LongForm => {
+            let pubkeys: Vec<PublicKey> = GLOBALS
+                .storage
+                .get_people_in_list(PersonList::Followed)?
+                .drain(..)
+                .map(|(pk, _)| pk)
+                .collect();
+            if pubkeys.is_empty() {
+                return Ok(vec![]);
+            }
+            let kind = if *source == Source::LongForm {
+                EventKind::LongFormContent
+            } else {
+                EventKind::TextNote
+            };
+            GLOBALS
+                .storage
+                .find_events(&[kind], &pubkeys, Some(since), keep, true)?
+        }
+        Source::Person(pubkey) => {
+            GLOBALS
+                .storage
+                .find_events(&[EventKind::TextNote], &[*pubkey], None, keep, true)?
+        }
+        Source::PersonLongForm(pubkey) => GLOBALS.storage.find_events(
+            &[EventKind::LongFormContent],
+            &[*pubkey],
+            None,
+            keep,
+            true,
+        )?,
+        Source::Hashtag(hashtag) => {
+            let mut ids = GLOBALS.storage.get_event_ids_with_hashtag(hashtag)?;
+            let lower = hashtag.to_lowercase();
+            if lower != *hashtag {
+                ids.extend(GLOBALS.storage.get_event_ids_with_hashtag(&lower)?);
+            }
+            ids.sort();
+            ids.dedup();
+            let mut events: Vec<Event> = Vec::new();
+            for id in ids {
+                if let Some(event) = GLOBALS.storage.read_event(id)? {
+                    if event.kind == EventKind::TextNote && keep(&event) {
+                        events.push(event);
+                    }
+                }
+            }
+            events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+            events
+        }
+    };
+
+    events.truncate(MAX_ENTRIES);
+    Ok(events)
+}
+
+/// Render a feed
+pub fn render(source: &Source, format: Format) -> Result<String, Error> {
+    let events = events(source)?;
+    let title = match source {
+        Source::Following => "Notes of people I follow".to_owned(),
+        Source::Person(pk) => format!(
+            "Notes of {}",
+            crate::names::best_name_from_pubkey_lookup(pk)
+        ),
+        Source::Hashtag(tag) => format!("#{}", tag),
+        Source::LongForm => "Articles of people I follow".to_owned(),
+        Source::PersonLongForm(pk) => format!(
+            "Articles of {}",
+            crate::names::best_name_from_pubkey_lookup(pk)
+        ),
+    };
+    let updated = events
+        .first()
+        .map(|e| e.created_at)
+        .unwrap_or(Unixtime::now().unwrap());
+
+    let mut out = String::new();
+    match format {
+        Format::Atom => {
+            let _ = writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+            let _ = writeln!(out, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+            let _ = writeln!(out, "  <title>{}</title>", escape(&title));
+            let _ = writeln!(out, "  <id>urn:gossip:feed:{}</id>", escape(&title));
+            let _ = writeln!(out, "  <updated>{}</updated>", rfc3339(updated));
+            let _ = writeln!(out, "  <generator>gossip</generator>");
+            for event in &events {
+                let link = link(event);
+                let _ = writeln!(out, "  <entry>");
+                let _ = writeln!(out, "    <title>{}</title>", escape(&entry_title(event)));
+                let _ = writeln!(out, "    <id>urn:nostr:{}</id>", event.id.as_hex_string());
+                let _ = writeln!(out, r#"    <link href="{}"/>"#, escape(&link));
+                let _ = writeln!(out, "    <updated>{}</updated>", rfc3339(event.created_at));
+                let _ = writeln!(
+                    out,
+                    "    <author><name>{}</name></author>",
+                    escape(&crate::names::best_name_from_pubkey_lookup(&event.pubkey))
+                );
+                let _ = writeln!(
+                    out,
+                    r#"    <content type="text">{}</content>"#,
+                    escape(&event.content)
+                );
+                let _ = writeln!(out, "  </entry>");
+            }
+            let _ = writeln!(out, "</feed>");
+        }
+        Format::Rss => {
+            let _ = writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+            let _ = writeln!(out, r#"<rss version="2.0">"#);
+            let _ = writeln!(out, "<channel>");
+            let _ = writeln!(out, "  <title>{}</title>", escape(&title));
+            let _ = writeln!(out, "  <description>{}</description>", escape(&title));
+            let _ = writeln!(out, "  <lastBuildDate>{}</lastBuildDate>", rfc2822(updated));
+            let _ = writeln!(out, "  <generator>gossip</generator>");
+            for event in &events {
+                let link = link(event);
+                let _ = writeln!(out, "  <item>");
+                let _ = writeln!(out, "    <title>{}</title>", escape(&entry_title(event)));
+                let _ = writeln!(
+                    out,
+                    r#"    <guid isPermaLink="false">{}</guid>"#,
+                    event.id.as_hex_string()
+                );
+                let _ = writeln!(out, "    <link>{}</link>", escape(&link));
+                let _ = writeln!(out, "    <pubDate>{}</pubDate>", rfc2822(event.created_at));
+                let _ = writeln!(
+                    out,
+                    "    <description>{}</description>",
+                    escape(&event.content)
+                );
+                let _ = writeln!(out, "  </item>");
+            }
+            let _ = writeln!(out, "</channel>");
+            let _ = writeln!(out, "</rss>");
+        }
+    }
+
+    Ok(out)
+}
+
+fn link(event: &Event) -> String {
+    match crate::share::event_bech32(event) {
+        Ok(bech32) => crate::share::web_link(&bech32),
+        Err(_) => format!("{}", NostrUrl::from(event.id)),
+    }
+}
+
+// Articles have titles. Notes use their first line.
+fn entry_title(event: &Event) -> String {
+    if let Some(tag) = event.tags.iter().find(|t| t.tagname() == "title") {
+        if !tag.value().is_empty() {
+            return tag.value().to_owned();
+        }
+    }
+    let line = event.content.lines().next().unwrap_or("").trim();
+    if line.chars().count() > 80 {
+        format!("{}…", line.chars().take(80).collect::<String>())
+    } else {
+        line.to_owned()
+    }
+}
+
+/// Escape text for XML (and so for HTML too), dropping the control characters
+/// XML 1.0 does not allow
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if (c as u32) < 0x20 && c != '\n' && c != '\r' && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn utc(time: Unixtime) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(time.0).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+pub(crate) fn rfc3339(time: Unixtime) -> String {
+    utc(time).format(&Rfc3339).unwrap_or_default()
+}
+
+fn rfc2822(time: Unixtime) -> String {
+    utc(time).format(&Rfc2822).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+        assert_eq!(escape("line\nbreak\u{0}\u{1b}tab\t"), "line\nbreaktab\t");
+    }
+
+    #[test]
+    fn test_dates() {
+        let time = Unixtime(1_700_000_000);
+        assert_eq!(rfc3339(time), "2023-11-14T22:13:20Z");
+        assert_eq!(rfc2822(time), "Tue, 14 Nov 2023 22:13:20 +0000");
+        assert_eq!(rfc3339(Unixtime(0)), "1970-01-01T00:00:00Z");
+    }
+}
@@ -51,6 +51,37 @@ pub async fn add_event_to_tags(
     }
 }
 
+/// Add a 'q' tag for a quoted event (NIP-18), if it is not already there
+pub async fn add_quote_to_tags(
+    existing_tags: &mut Vec<Tag>,
+    added: Id,
+    relay_url: Option<UncheckedUrl>,
+) -> usize {
+    let idhex = added.as_hex_string();
+    match existing_tags
+        .iter()
+        .position(|existing_tag| existing_tag.tagname() == "q" && existing_tag.value() == idhex)
+    {
+        Some(idx) => idx,
+        None => {
+            let optrelay = match relay_url {
+                Some(url) => Some(url),
+                None => Relay::recommended_relay_for_reply(added)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|rr| rr.to_unchecked_url()),
+            };
+            let newtag = match optrelay {
+                Some(url) => Tag::new(&["q", &idhex, url.as_str()]),
+                None => Tag::new(&["q", &idhex]),
+            };
+            existing_tags.push(newtag);
+            existing_tags.len() - 1
+        }
+    }
+}
+
 // FIXME pass in and set marker
 pub async fn add_addr_to_tags(
     existing_tags: &mut Vec<Tag>,
@@ -0,0 +1,196 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::syndication::escape;
+use linkify::{LinkFinder, LinkKind};
+use nostr_types::{Event, Id};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// The format of an exported thread
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreadFormat {
+    Markdown,
+    Html,
+}
+
+impl ThreadFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThreadFormat::Markdown => "md",
+            ThreadFormat::Html => "html",
+        }
+    }
+}
+
+const IMAGE_EXTENSIONS: [&str; 6] = [".jpg", ".jpeg", ".png", ".gif", ".webp", ".svg"];
+
+/// The thread containing this event, from the highest parent we have, in reply
+/// order. Each event comes with its depth. Deleted events are left out (and so
+/// are their replies).
+pub fn thread_events(id: Id) -> Result<Vec<(usize, Event)>, Error> {
+    let root_id = GLOBALS
+        .storage
+        .get_highest_local_parent_event_id(id)?
+        .ok_or_else(|| ErrorKind::General("We do not have that event".to_owned()))?;
+
+    let mut output: Vec<(usize, Event)> = Vec::new();
+    let mut seen: HashSet<Id> = HashSet::new();
+    let mut stack: Vec<(usize, Id)> = vec![(0, root_id)];
+    while let Some((depth, id)) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let event = match GLOBALS.storage.read_event(id)? {
+            Some(event) => event,
+            None => continue,
+        };
+        if !GLOBALS.storage.get_deletions(&event)?.is_empty() {
+            continue;
+        }
+
+        let mut replies: Vec<Event> = Vec::new();
+        for reply_id in GLOBALS.storage.get_replies(&event)? {
+            if let Some(reply) = GLOBALS.storage.read_event(reply_id)? {
+                replies.push(reply);
+            }
+        }
+        // Pushed latest first, so they pop oldest first
+        replies.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        for reply in replies {
+            stack.push((depth + 1, reply.id));
+        }
+
+        output.push((depth, event));
+    }
+
+    Ok(output)
+}
+
+/// Render the thread containing this event as a standalone document
+pub fn export_thread(id: Id, format: ThreadFormat) -> Result<String, Error> {
+    let events = thread_events(id)?;
+    let title = events
+        .first()
+        .map(|(_, e)| {
+            format!(
+                "Thread by {}",
+                crate::names::best_name_from_pubkey_lookup(&e.pubkey)
+            )
+        })
+        .unwrap_or_default();
+
+    let mut out = String::new();
+    match format {
+        ThreadFormat::Markdown => {
+            let _ = writeln!(out, "# {}\n", title);
+            for (depth, event) in &events {
+                let quote = "> ".repeat(*depth);
+                let _ = writeln!(
+                    out,
+                    "{}**{}** · {}\n{}",
+                    quote,
+                    crate::names::best_name_from_pubkey_lookup(&event.pubkey),
+                    crate::syndication::rfc3339(event.created_at),
+                    quote
+                );
+                for line in markdown_content(&event.content).lines() {
+                    let _ = writeln!(out, "{}{}", quote, line);
+                }
+                let _ = writeln!(out);
+            }
+        }
+        ThreadFormat::Html => {
+            let _ = writeln!(out, "<!DOCTYPE html>");
+            let _ = writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">");
+            let _ = writeln!(out, "<title>{}</title>", escape(&title));
+            let _ = writeln!(
+                out,
+                "<style>body {{ font-family: sans-serif; max-width: 50em; margin: auto; }} \
+                 .note {{ border-left: 2px solid #ccc; padding: 0.5em 1em; margin: 0.5em 0; }} \
+                 .meta {{ color: #666; font-size: small; }} \
+                 .content {{ white-space: pre-wrap; }} img {{ max-width: 100%; }}</style>"
+            );
+            let _ = writeln!(out, "</head>\n<body>");
+            let _ = writeln!(out, "<h1>{}</h1>", escape(&title));
+            for (depth, event) in &events {
+                let _ = writeln!(
+                    out,
+                    "<div class=\"note\" style=\"margin-left: {}em\">",
+                    depth * 2
+                );
+                let _ = writeln!(
+                    out,
+                    "<div class=\"meta\"><b>{}</b> · {}</div>",
+                    escape(&crate::names::best_name_from_pubkey_lookup(&event.pubkey)),
+                    crate::syndication::rfc3339(event.created_at)
+                );
+                let _ = writeln!(
+                    out,
+                    "<div class=\"content\">{}</div>",
+                    html_content(&event.content)
+                );
+                let _ = writeln!(out, "</div>");
+            }
+            let _ = writeln!(out, "</body>\n</html>");
+        }
+    }
+
+    Ok(out)
+}
+
+/// Export the thread containing this event to a file in the downloads directory
+/// (or the profile directory if there is none). Returns the path written.
+pub fn export_thread_to_file(id: Id, format: ThreadFormat) -> Result<PathBuf, Error> {
+    let document = export_thread(id, format)?;
+    let mut path = match dirs::download_dir() {
+        Some(dir) => dir,
+        None => crate::profile::Profile::current()?.profile_dir,
+    };
+    path.push(format!(
+        "thread-{}.{}",
+        &id.as_hex_string()[..12],
+        format.extension()
+    ));
+    std::fs::write(&path, document)?;
+    Ok(path)
+}
+
+fn is_image(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or("").to_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+fn markdown_content(content: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for link in LinkFinder::new().kinds(&[LinkKind::Url]).links(content) {
+        out.push_str(&content[last..link.start()]);
+        let url = link.as_str();
+        if is_image(url) {
+            let _ = write!(out, "![]({})", url);
+        } else {
+            let _ = write!(out, "<{}>", url);
+        }
+        last = link.end();
+    }
+    out.push_str(&content[last..]);
+    out
+}
+
+fn html_content(content: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for link in LinkFinder::new().kinds(&[LinkKind::Url]).links(content) {
+        out.push_str(&escape(&content[last..link.start()]));
+        let url = escape(link.as_str());
+        if is_image(link.as_str()) {
+            let _ = write!(out, "<a href=\"{}\"><img src=\"{}\"></a>", url, url);
+        } else {
+            let _ = write!(out, "<a href=\"{}\">{}</a>", url, url);
+        }
+        last = link.end();
+    }
+    out.push_str(&escape(&content[last..]));
+    out
+}